@@ -5,6 +5,7 @@ use std::sync::Arc;
 
 use arrow::{
     array::{new_null_array, ArrayRef, StringArray},
+    compute::cast,
     datatypes::SchemaRef,
     error::ArrowError,
     record_batch::RecordBatch,
@@ -24,7 +25,9 @@ where
 }
 
 /// Merge the record bacthes into one record batch
-/// and padd null values to columns that are not available in certain bacthes
+/// and padd null values to columns that are not available in certain bacthes.
+/// Columns of a different type than in `output_schema`, such as integer columns
+/// since promoted to float columns, are cast to the output type
 pub fn merge_record_batches(
     output_schema: SchemaRef,
     batches: Vec<Arc<RecordBatch>>,
@@ -48,20 +51,22 @@ pub fn merge_record_batches(
                         .find(|(_, batch_field)| output_field.name() == batch_field.name())
                         .map(|(idx, _)| idx);
 
-                    if let Some(batch_field_index) = batch_field_index {
-                        // The column available, use it
-                        Arc::clone(batch.column(batch_field_index))
-                    } else {
+                    match batch_field_index {
+                        // The column available with the output type, use it
+                        Some(idx) if batch.column(idx).data_type() == output_field.data_type() => {
+                            Ok(Arc::clone(batch.column(idx)))
+                        }
+                        // The column available with another type, cast it
+                        Some(idx) => cast(batch.column(idx), output_field.data_type()),
                         // the column not avaialble, add it with all null values
-                        new_null_array(output_field.data_type(), batch.num_rows())
+                        None => Ok(new_null_array(output_field.data_type(), batch.num_rows())),
                     }
                 })
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>, _>>()?;
 
             RecordBatch::try_new(Arc::clone(&output_schema), batch_output_columns)
-                .expect("A record batch should have been created")
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, _>>()?;
 
     // Combine batches
     if batches.is_empty() {
@@ -16,6 +16,10 @@ pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PK
 pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 /// The default request timeout
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// The default interval between HTTP/2 keepalive pings
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+/// The default time to wait for the acknowledgement of a keepalive ping
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Errors returned by the ConnectionBuilder
 #[derive(Debug, Error)]
@@ -74,6 +78,9 @@ pub struct Builder {
     headers: Vec<(HeaderName, HeaderValue)>,
     connect_timeout: Duration,
     timeout: Duration,
+    keep_alive_interval: Duration,
+    keep_alive_timeout: Duration,
+    keep_alive_while_idle: bool,
 }
 
 impl std::default::Default for Builder {
@@ -82,6 +89,9 @@ impl std::default::Default for Builder {
             user_agent: USER_AGENT.into(),
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             timeout: DEFAULT_TIMEOUT,
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            keep_alive_while_idle: false,
             headers: Default::default(),
         }
     }
@@ -119,7 +129,10 @@ impl Builder {
         let endpoint = Endpoint::from(dst.try_into()?)
             .user_agent(&self.user_agent)?
             .connect_timeout(self.connect_timeout)
-            .timeout(self.timeout);
+            .timeout(self.timeout)
+            .http2_keep_alive_interval(self.keep_alive_interval)
+            .keep_alive_timeout(self.keep_alive_timeout)
+            .keep_alive_while_idle(self.keep_alive_while_idle);
         Ok(endpoint)
     }
 
@@ -170,11 +183,48 @@ impl Builder {
     pub fn timeout(self, timeout: Duration) -> Self {
         Self { timeout, ..self }
     }
+
+    /// Sets the interval at which HTTP/2 keepalive pings are sent to the
+    /// server while requests are in flight, see also
+    /// [`keep_alive_while_idle`].
+    ///
+    /// Together with [`keep_alive_timeout`] this detects a server that
+    /// stopped responding without closing the connection.
+    ///
+    /// [`keep_alive_timeout`]: Self::keep_alive_timeout
+    /// [`keep_alive_while_idle`]: Self::keep_alive_while_idle
+    pub fn keep_alive_interval(self, interval: Duration) -> Self {
+        Self {
+            keep_alive_interval: interval,
+            ..self
+        }
+    }
+
+    /// Sets the maximum duration of time the client will wait for the
+    /// acknowledgement of a keepalive ping before closing the connection.
+    pub fn keep_alive_timeout(self, timeout: Duration) -> Self {
+        Self {
+            keep_alive_timeout: timeout,
+            ..self
+        }
+    }
+
+    /// Sets whether HTTP/2 keepalive pings are also sent while no request is
+    /// in flight, which keeps idle connections open but may get the client
+    /// disconnected by servers limiting pings. Disabled by default.
+    pub fn keep_alive_while_idle(self, enabled: bool) -> Self {
+        Self {
+            keep_alive_while_idle: enabled,
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use http::uri::PathAndQuery;
+    use std::time::Instant;
 
     #[test]
     fn test_builder_cloneable() {
@@ -182,4 +232,42 @@ mod tests {
         fn assert_clone<T: Clone>(_t: T) {}
         assert_clone(Builder::default())
     }
+
+    #[tokio::test]
+    async fn test_timeout_unresponsive_server() {
+        // accept connections but never respond to any request
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let timeout = Duration::from_millis(100);
+        let connection = Builder::default()
+            .timeout(timeout)
+            .keep_alive_interval(Duration::from_millis(100))
+            .keep_alive_timeout(Duration::from_millis(100))
+            .build(format!("http://{}", addr))
+            .await
+            .unwrap();
+
+        let mut client = tonic::client::Grpc::new(connection);
+        client.ready().await.unwrap();
+
+        let start = Instant::now();
+        let request = client.unary(
+            tonic::Request::new(()),
+            PathAndQuery::from_static("/test.Service/Method"),
+            tonic::codec::ProstCodec::<(), ()>::default(),
+        );
+        let result = tokio::time::timeout(Duration::from_secs(10), request)
+            .await
+            .expect("request was not aborted");
+
+        assert!(result.is_err());
+        assert!(start.elapsed() < 10 * timeout, "took {:?}", start.elapsed());
+    }
 }
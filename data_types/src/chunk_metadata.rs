@@ -1,5 +1,13 @@
 //! Module contains a representation of chunk metadata
-use std::{convert::TryFrom, num::NonZeroU32, str::FromStr, sync::Arc};
+use std::{
+    convert::TryFrom,
+    num::NonZeroU64,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use bytes::Bytes;
 use snafu::{ResultExt, Snafu};
@@ -230,6 +238,16 @@ impl ChunkId {
         Self(Uuid::new_v4())
     }
 
+    /// Create new ID that sorts after every ID previously created by this function within this process.
+    ///
+    /// Use this instead of [`new`](Self::new) when the ID is used as a tiebreaker between chunks of the same
+    /// [`ChunkOrder`], e.g. for in-memory chunks that are deduplicated against each other. These IDs never collide
+    /// with random IDs since they do not carry the random UUID version bits.
+    pub fn new_monotonic() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(Uuid::from_u128(NEXT.fetch_add(1, Ordering::Relaxed) as u128))
+    }
+
     /// **TESTING ONLY:** Create new ID from integer.
     ///
     /// Since this can easily lead to ID collissions (which in turn can lead to panics), this must only be used for
@@ -310,18 +328,38 @@ impl FromStr for ChunkId {
 /// 1. **upsert order:** chunks with higher order overwrite data in chunks with lower order
 /// 2. **locking order:** chunks must be locked in consistent (ascending) order
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ChunkOrder(NonZeroU32);
+pub struct ChunkOrder(NonZeroU64);
 
 impl ChunkOrder {
     // TODO: remove `unsafe` once https://github.com/rust-lang/rust/issues/51999 is fixed
-    pub const MIN: Self = Self(unsafe { NonZeroU32::new_unchecked(1) });
-    pub const MAX: Self = Self(unsafe { NonZeroU32::new_unchecked(u32::MAX) });
+    pub const MIN: Self = Self(unsafe { NonZeroU64::new_unchecked(1) });
+    pub const MAX: Self = Self(unsafe { NonZeroU64::new_unchecked(u64::MAX) });
+
+    /// The lowest order of a chunk buffered in an ingester.
+    ///
+    /// Orders below this are left to persisted chunks, so that buffered data
+    /// always wins over persisted data when deduplicating.
+    pub const MIN_BUFFERED: Self = Self(unsafe { NonZeroU64::new_unchecked(1 << 63) });
+
+    /// Order of a chunk buffered in an ingester, derived from the minimum
+    /// sequence number of its data.
+    ///
+    /// Buffered chunks always sort after persisted chunks, and later
+    /// buffered chunks of a partition after earlier ones. Every sequence
+    /// number gets an order of its own. Orders derived from sequence numbers
+    /// are only comparable between the chunks of a single partition, whose
+    /// data is all read from one sequencer - chunks of other partitions or
+    /// sequencers may share them.
+    pub fn new_buffered(min_sequence_number: i64) -> Self {
+        let offset = min_sequence_number.max(0) as u64;
+        Self::new(Self::MIN_BUFFERED.get() + offset).expect("order is never 0")
+    }
 
-    pub fn new(order: u32) -> Option<Self> {
-        NonZeroU32::new(order).map(Self)
+    pub fn new(order: u64) -> Option<Self> {
+        NonZeroU64::new(order).map(Self)
     }
 
-    pub fn get(&self) -> u32 {
+    pub fn get(&self) -> u64 {
         self.0.get()
     }
 
@@ -331,7 +369,7 @@ impl ChunkOrder {
     /// Panics if `self` is already [max](Self::MAX).
     pub fn next(&self) -> Self {
         Self(
-            NonZeroU32::new(self.0.get().checked_add(1).expect("chunk order overflow"))
+            NonZeroU64::new(self.0.get().checked_add(1).expect("chunk order overflow"))
                 .expect("did not overflow, so cannot be zero"),
         )
     }
@@ -360,6 +398,14 @@ mod tests {
         assert_ne!(ChunkId::new_test(1), ChunkId::new_test(2));
     }
 
+    #[test]
+    fn test_chunk_id_new_monotonic() {
+        // `ChunkId::new_monotonic()` creates increasing IDs
+        let a = ChunkId::new_monotonic();
+        let b = ChunkId::new_monotonic();
+        assert!(a < b);
+    }
+
     #[test]
     fn test_chunk_id_debug_and_display() {
         // Random chunk IDs use UUID-format
@@ -373,4 +419,20 @@ mod tests {
         assert_eq!(format!("{:?}", id_test), "ChunkId(42)");
         assert_eq!(format!("{}", id_test), "ChunkId(42)");
     }
+
+    #[test]
+    fn test_chunk_order_buffered() {
+        // buffered chunks are ordered by their min sequence number
+        assert!(ChunkOrder::new_buffered(0) < ChunkOrder::new_buffered(1));
+        assert!(ChunkOrder::new_buffered(10) < ChunkOrder::new_buffered(20));
+
+        // including sequence numbers beyond the range of a u32
+        let large = i64::from(u32::MAX);
+        assert!(ChunkOrder::new_buffered(large) < ChunkOrder::new_buffered(large + 1));
+        assert!(ChunkOrder::new_buffered(i64::MAX - 1) < ChunkOrder::new_buffered(i64::MAX));
+
+        // buffered chunks always sort after persisted chunks
+        assert_eq!(ChunkOrder::new_buffered(-1), ChunkOrder::MIN_BUFFERED);
+        assert_eq!(ChunkOrder::new_buffered(i64::MAX), ChunkOrder::MAX);
+    }
 }
@@ -119,6 +119,19 @@ pub struct LifecycleRules {
     /// Use up to this amount of space in bytes for caching Parquet files. None
     /// will disable Parquet file caching.
     pub parquet_cache_limit: Option<NonZeroU64>,
+
+    /// Build bloom filters over the tag values of persisted chunks, used to
+    /// prune chunks for `tag = 'value'` predicates.
+    pub persist_tag_bloom_filters: bool,
+
+    /// Read columns of persisted chunks that fail to decode as nulls instead
+    /// of failing the query, so that a chunk with a corrupt column stays
+    /// queryable.
+    pub null_corrupt_parquet_columns: bool,
+
+    /// Exclude data older than this many seconds from queries, even if it
+    /// has not yet been deleted. None keeps data forever.
+    pub retention_period_seconds: Option<NonZeroU64>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -172,6 +185,12 @@ impl LifecycleRules {
     pub fn late_arrive_window(&self) -> Duration {
         Duration::from_secs(self.late_arrive_window_seconds.get() as u64)
     }
+
+    /// The age after which data is excluded from queries, if any
+    pub fn retention_period(&self) -> Option<Duration> {
+        self.retention_period_seconds
+            .map(|seconds| Duration::from_secs(seconds.get()))
+    }
 }
 
 impl Default for LifecycleRules {
@@ -195,6 +214,9 @@ impl Default for LifecycleRules {
                 .unwrap(),
             mub_row_threshold: NonZeroUsize::new(DEFAULT_MUB_ROW_THRESHOLD).unwrap(),
             parquet_cache_limit: None,
+            persist_tag_bloom_filters: false,
+            null_corrupt_parquet_columns: false,
+            retention_period_seconds: None,
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::timestamp::TimestampRange;
-use std::{fmt::Write, num::FpCategory};
+use std::{collections::BTreeMap, fmt::Write, num::FpCategory, sync::Arc};
 
 /// Represents a parsed delete predicate for evaluation by the InfluxDB IOx
 /// query engine.
@@ -36,6 +36,49 @@ impl DeletePredicate {
     pub fn size(&self) -> usize {
         std::mem::size_of::<Self>() + self.exprs.iter().map(|expr| expr.size()).sum::<usize>()
     }
+
+    /// Merge predicates with the same set of expressions and overlapping or
+    /// adjacent time ranges into a single predicate, returning predicates that
+    /// delete exactly the same rows as `preds`.
+    ///
+    /// Predicates that are not merged are returned as-is. The returned
+    /// predicates are sorted.
+    pub fn coalesce(preds: &[Arc<Self>]) -> Vec<Arc<Self>> {
+        // Group the predicates by their (order-insensitive) expression set
+        let mut groups: BTreeMap<Vec<&DeleteExpr>, Vec<&Arc<Self>>> = BTreeMap::new();
+        for pred in preds {
+            let mut exprs: Vec<_> = pred.exprs.iter().collect();
+            exprs.sort();
+            exprs.dedup();
+            groups.entry(exprs).or_default().push(pred);
+        }
+
+        let mut coalesced = Vec::with_capacity(preds.len());
+        for (exprs, mut group) in groups {
+            group.sort_by_key(|pred| pred.range);
+
+            let mut group = group.into_iter();
+            let mut current = group.next().map(Arc::clone);
+            for pred in group {
+                let prev = current.take().expect("current predicate is always set");
+                if pred.range.start() > prev.range.end() {
+                    coalesced.push(prev);
+                    current = Some(Arc::clone(pred));
+                    continue;
+                }
+
+                let end = prev.range.end().max(pred.range.end());
+                current = Some(Arc::new(Self {
+                    range: TimestampRange::new(prev.range.start(), end),
+                    exprs: exprs.iter().map(|&expr| expr.clone()).collect(),
+                }));
+            }
+            coalesced.extend(current);
+        }
+
+        coalesced.sort();
+        coalesced
+    }
 }
 
 /// Single expression to be used as parts of a predicate.
@@ -166,6 +209,68 @@ mod tests {
 
     use super::*;
 
+    fn delete(start: i64, end: i64, exprs: &[(&str, i64)]) -> Arc<DeletePredicate> {
+        Arc::new(DeletePredicate {
+            range: TimestampRange::new(start, end),
+            exprs: exprs
+                .iter()
+                .map(|(column, v)| DeleteExpr::new(column.to_string(), Op::Eq, Scalar::I64(*v)))
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_coalesce_contiguous() {
+        let preds = vec![
+            delete(20, 30, &[("a", 1)]),
+            delete(0, 10, &[("a", 1)]),
+            delete(10, 20, &[("a", 1)]),
+        ];
+        assert_eq!(
+            DeletePredicate::coalesce(&preds),
+            vec![delete(0, 30, &[("a", 1)])]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_overlapping_and_expr_order() {
+        let preds = vec![
+            delete(0, 15, &[("a", 1), ("b", 2)]),
+            delete(10, 20, &[("b", 2), ("a", 1)]),
+            delete(5, 8, &[("a", 1), ("b", 2)]),
+        ];
+        assert_eq!(
+            DeletePredicate::coalesce(&preds),
+            vec![delete(0, 20, &[("a", 1), ("b", 2)])]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_keeps_disjoint() {
+        let preds = vec![
+            // different expressions
+            delete(0, 10, &[("a", 1)]),
+            delete(10, 20, &[("a", 2)]),
+            // gap between the ranges
+            delete(21, 30, &[("a", 1)]),
+        ];
+
+        let got = DeletePredicate::coalesce(&preds);
+        assert_eq!(
+            got,
+            vec![
+                delete(0, 10, &[("a", 1)]),
+                delete(10, 20, &[("a", 2)]),
+                delete(21, 30, &[("a", 1)]),
+            ]
+        );
+
+        // Predicates that are not merged are returned as-is
+        assert!(got.iter().all(|p| preds.iter().any(|q| Arc::ptr_eq(p, q))));
+
+        assert!(DeletePredicate::coalesce(&[]).is_empty());
+    }
+
     #[test]
     fn test_expr_to_sql_no_expressions() {
         let pred = DeletePredicate {
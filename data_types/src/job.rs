@@ -50,6 +50,14 @@ pub enum Job {
 
     /// Rebuild preserved catalog
     RebuildPreservedCatalog { db_name: Arc<str> },
+
+    /// Downsample a table into another table
+    Downsample {
+        db_name: Arc<str>,
+        source_table: Arc<str>,
+        target_db_name: Arc<str>,
+        target_table: Arc<str>,
+    },
 }
 
 impl Job {
@@ -66,6 +74,7 @@ impl Job {
             Self::WipePreservedCatalog { db_name, .. } => Some(db_name),
             Self::LoadReadBufferChunk { chunk } => Some(&chunk.db_name),
             Self::RebuildPreservedCatalog { db_name } => Some(db_name),
+            Self::Downsample { db_name, .. } => Some(db_name),
         }
     }
 
@@ -82,6 +91,7 @@ impl Job {
             Self::WipePreservedCatalog { .. } => None,
             Self::LoadReadBufferChunk { chunk } => Some(&chunk.partition_key),
             Self::RebuildPreservedCatalog { .. } => None,
+            Self::Downsample { .. } => None,
         }
     }
 
@@ -98,6 +108,7 @@ impl Job {
             Self::WipePreservedCatalog { .. } => None,
             Self::LoadReadBufferChunk { chunk } => Some(&chunk.table_name),
             Self::RebuildPreservedCatalog { .. } => None,
+            Self::Downsample { source_table, .. } => Some(source_table),
         }
     }
 
@@ -114,6 +125,7 @@ impl Job {
             Self::WipePreservedCatalog { .. } => None,
             Self::LoadReadBufferChunk { chunk } => Some(vec![chunk.chunk_id]),
             Self::RebuildPreservedCatalog { .. } => None,
+            Self::Downsample { .. } => None,
         }
     }
 
@@ -134,6 +146,7 @@ impl Job {
             Self::WipePreservedCatalog { .. } => "Wipe preserved catalog",
             Self::LoadReadBufferChunk { .. } => "Loading chunk to read buffer",
             Self::RebuildPreservedCatalog { .. } => "Rebuild preserved catalog",
+            Self::Downsample { .. } => "Downsampling a table into another table",
         }
     }
 }
@@ -157,6 +170,16 @@ impl std::fmt::Display for Job {
             Job::RebuildPreservedCatalog { db_name } => {
                 write!(f, "Job::RebuildPreservedCatalog({})", db_name)
             }
+            Job::Downsample {
+                db_name,
+                source_table,
+                target_db_name,
+                target_table,
+            } => write!(
+                f,
+                "Job::Downsample({}:{} -> {}:{})",
+                db_name, source_table, target_db_name, target_table
+            ),
         }
     }
 }
@@ -5,6 +5,7 @@ use crate::timestamp::TimestampMinMax;
 use observability_deps::tracing::warn;
 use std::{
     borrow::{Borrow, Cow},
+    collections::BTreeMap,
     iter::FromIterator,
     mem,
     num::NonZeroU64,
@@ -34,6 +35,22 @@ impl std::fmt::Display for PartitionAddr {
     }
 }
 
+/// Describes the persistence state of a partition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionMeta {
+    /// The key of the partition
+    pub partition_key: Arc<str>,
+
+    /// For each sequencer, the sequence number up to which (inclusive) all
+    /// writes to the partition have been persisted.
+    ///
+    /// Sequencers without any persisted writes to the partition are omitted.
+    pub persisted_sequence_numbers: BTreeMap<u32, u64>,
+
+    /// The number of chunks in the partition
+    pub chunk_count: usize,
+}
+
 /// Describes the aggregated (across all chunks) summary
 /// statistics for each column in a partition
 #[derive(Debug, PartialEq)]
@@ -71,6 +71,12 @@ impl TimestampRange {
     pub fn start(&self) -> i64 {
         self.start
     }
+
+    /// Restrict this range to exclude values before `cutoff`, resulting in an
+    /// empty range if the whole range is before `cutoff`.
+    pub fn exclude_before(self, cutoff: i64) -> Self {
+        Self::new(self.start.max(cutoff).min(self.end), self.end)
+    }
 }
 
 /// Specifies a min/max timestamp value.
@@ -163,6 +169,18 @@ mod tests {
         assert!(!range.contains_opt(None));
     }
 
+    #[test]
+    fn test_timestamp_range_exclude_before() {
+        let range = TimestampRange::new(100, 200);
+        assert_eq!(range.exclude_before(50), range);
+        assert_eq!(range.exclude_before(150), TimestampRange::new(150, 200));
+
+        let empty = range.exclude_before(300);
+        assert_eq!(empty, TimestampRange::new(200, 200));
+        assert!(!empty.contains(199));
+        assert!(!empty.contains(200));
+    }
+
     #[test]
     fn test_timestamp_range_overlaps() {
         let range = TimestampRange::new(100, 200);
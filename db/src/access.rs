@@ -4,7 +4,10 @@
 use super::{catalog::Catalog, chunk::DbChunk, query_log::QueryLog};
 use crate::system_tables;
 use async_trait::async_trait;
-use data_types::{chunk_metadata::ChunkSummary, partition_metadata::PartitionAddr};
+use data_types::{
+    chunk_metadata::ChunkSummary,
+    partition_metadata::{PartitionAddr, PartitionMeta},
+};
 use datafusion::{
     catalog::{catalog::CatalogProvider, schema::SchemaProvider},
     datasource::TableProvider,
@@ -13,16 +16,20 @@ use hashbrown::HashMap;
 use job_registry::JobRegistry;
 use metric::{Attributes, DurationCounter, Metric, U64Counter};
 use observability_deps::tracing::debug;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use predicate::{predicate::Predicate, rpc_predicate::QueryDatabaseMeta};
 use query::{
-    provider::{ChunkPruner, ProviderBuilder},
+    exec::{ExecutorConfig, IOxExecutionContext},
+    provider::{
+        retention_cutoff, ChunkPruner, ChunkReadMetrics, MeasuredChunk, MeasuredPruner,
+        ProviderBuilder,
+    },
     pruning::{prune_chunks, PruningObserver},
-    QueryChunkMeta, QueryCompletedToken, QueryDatabase, DEFAULT_SCHEMA,
+    QueryChunk, QueryChunkMeta, QueryCompletedToken, QueryDatabase, SortKeyMetrics, DEFAULT_SCHEMA,
 };
 use schema::Schema;
-use std::time::Instant;
-use std::{any::Any, sync::Arc};
+use std::time::{Duration, Instant};
+use std::{any::Any, num::NonZeroUsize, sync::Arc};
 use system_tables::{SystemSchemaProvider, SYSTEM_SCHEMA};
 use time::TimeProvider;
 
@@ -153,20 +160,48 @@ pub(crate) struct QueryCatalogAccess {
 
     /// Provides access to "normal" user tables
     user_tables: Arc<DbSchemaProvider>,
+
+    /// Excludes expired data from queries
+    retention: Arc<Retention>,
 }
 
 impl QueryCatalogAccess {
+    /// Creates access to `catalog` for queries run with the executor
+    /// configuration `exec_config`
     pub fn new(
         db_name: impl Into<String>,
         catalog: Arc<Catalog>,
         jobs: Arc<JobRegistry>,
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: &metric::Registry,
+        exec_config: &ExecutorConfig,
+        retention_period: Option<Duration>,
     ) -> Self {
-        let db_name = Arc::from(db_name.into());
+        let db_name: Arc<str> = Arc::from(db_name.into());
+        let sort_key_metrics = SortKeyMetrics::new(
+            metric_registry,
+            Attributes::from([("db_name", db_name.to_string().into())]),
+        );
+        let chunk_read_metrics = exec_config.chunk_read_metrics.then(|| {
+            ChunkReadMetrics::new(
+                metric_registry,
+                Attributes::from([("db_name", db_name.to_string().into())]),
+            )
+        });
         let access_metrics = AccessMetrics::new(metric_registry, Arc::clone(&db_name));
-        let chunk_access = Arc::new(ChunkAccess::new(Arc::clone(&catalog), access_metrics));
-        let query_log = Arc::new(QueryLog::new(QUERY_LOG_SIZE, time_provider));
+        let chunk_access = Arc::new(ChunkAccess::new(
+            Arc::clone(&catalog),
+            access_metrics,
+            exec_config.disable_pruning,
+        ));
+        let retention = Arc::new(Retention {
+            period: RwLock::new(retention_period),
+            time_provider: Arc::clone(&time_provider),
+        });
+        let query_log = Arc::new(
+            QueryLog::new(QUERY_LOG_SIZE, time_provider)
+                .with_slow_query_threshold(exec_config.slow_query_threshold),
+        );
 
         let system_tables = Arc::new(SystemSchemaProvider::new(
             db_name.as_ref(),
@@ -177,6 +212,10 @@ impl QueryCatalogAccess {
         let user_tables = Arc::new(DbSchemaProvider::new(
             Arc::clone(&catalog),
             Arc::clone(&chunk_access),
+            exec_config.scan_parallelism,
+            sort_key_metrics,
+            chunk_read_metrics,
+            Arc::clone(&retention),
         ));
         Self {
             catalog,
@@ -184,8 +223,35 @@ impl QueryCatalogAccess {
             query_log,
             system_tables,
             user_tables,
+            retention,
         }
     }
+
+    /// Exclude data older than `retention_period` from subsequent queries
+    pub fn set_retention_period(&self, retention_period: Option<Duration>) {
+        *self.retention.period.write() = retention_period;
+    }
+}
+
+/// The retention period of the database, after which data is excluded from
+/// queries even if it has not been deleted yet
+#[derive(Debug)]
+struct Retention {
+    period: RwLock<Option<Duration>>,
+    time_provider: Arc<dyn TimeProvider>,
+}
+
+impl Retention {
+    /// The current time in nanoseconds since the epoch
+    fn now(&self) -> i64 {
+        self.time_provider.now().timestamp_nanos()
+    }
+
+    /// The timestamp before which data has expired, if any
+    fn cutoff(&self) -> Option<i64> {
+        let period = (*self.period.read())?;
+        Some(retention_cutoff(period, self.now()))
+    }
 }
 
 /// Encapsulates everything needed to find candidate chunks for
@@ -197,13 +263,18 @@ struct ChunkAccess {
 
     /// Metrics about query processing
     access_metrics: AccessMetrics,
+
+    /// Keep all chunks rather than pruning them, see
+    /// [`ExecutorConfig::disable_pruning`](query::exec::ExecutorConfig::disable_pruning)
+    disable_pruning: bool,
 }
 
 impl ChunkAccess {
-    fn new(catalog: Arc<Catalog>, access_metrics: AccessMetrics) -> Self {
+    fn new(catalog: Arc<Catalog>, access_metrics: AccessMetrics, disable_pruning: bool) -> Self {
         Self {
             catalog,
             access_metrics,
+            disable_pruning,
         }
     }
 
@@ -221,11 +292,14 @@ impl ChunkAccess {
             };
 
             let schema = Arc::clone(&table.schema().read());
-            let chunks = table.filtered_chunks(
-                predicate.partition_key.as_deref(),
-                predicate.range,
-                DbChunk::snapshot,
-            );
+            // Selecting the chunks by time range is pruning too
+            let range = if self.disable_pruning {
+                None
+            } else {
+                predicate.range
+            };
+            let chunks =
+                table.filtered_chunks(predicate.partition_key.as_deref(), range, DbChunk::snapshot);
 
             (chunks, schema)
         };
@@ -247,6 +321,11 @@ impl ChunkPruner<DbChunk> for ChunkAccess {
         chunks: Vec<Arc<DbChunk>>,
         predicate: &Predicate,
     ) -> Vec<Arc<DbChunk>> {
+        if self.disable_pruning {
+            debug!(num_chunks=chunks.len(), %predicate, "Pruning disabled, keeping all chunks");
+            return chunks;
+        }
+
         let start = Instant::now();
 
         debug!(num_chunks=chunks.len(), %predicate, "Attempting to prune chunks");
@@ -278,6 +357,10 @@ impl QueryDatabase for QueryCatalogAccess {
         self.catalog.partition_addrs()
     }
 
+    fn partition_metadata(&self, table_name: &str) -> Vec<PartitionMeta> {
+        self.catalog.partition_metadata(table_name)
+    }
+
     /// Return a covering set of chunks for a particular table and predicate
     fn chunks(&self, table_name: &str, predicate: &Predicate) -> Vec<Arc<Self::Chunk>> {
         self.chunk_access.candidate_chunks(table_name, predicate)
@@ -289,13 +372,21 @@ impl QueryDatabase for QueryCatalogAccess {
 
     fn record_query(
         &self,
+        ctx: &IOxExecutionContext,
         query_type: impl Into<String>,
         query_text: impl Into<String>,
     ) -> QueryCompletedToken<'_> {
         // When the query token is dropped the query entry's completion time
         // will be set.
+        let progress = ctx.progress_reporter().cloned();
         let entry = self.query_log.push(query_type, query_text);
-        QueryCompletedToken::new(move || self.query_log.set_completed(entry))
+        QueryCompletedToken::new(move || {
+            let chunks = progress.map(|progress| {
+                progress.complete();
+                progress.chunks_planned()
+            });
+            self.query_log.set_completed(entry, chunks)
+        })
     }
 }
 
@@ -310,6 +401,10 @@ impl QueryDatabaseMeta for QueryCatalogAccess {
             .ok()
             .map(|table| Arc::clone(&table.schema().read()))
     }
+
+    fn retention_cutoff(&self) -> Option<i64> {
+        self.retention.cutoff()
+    }
 }
 
 // Datafusion catalog provider interface
@@ -342,14 +437,71 @@ struct DbSchemaProvider {
 
     /// Handles finding / pruning chunks based on predicates
     chunk_access: Arc<ChunkAccess>,
+
+    /// The maximum number of chunks read concurrently by a scan
+    scan_parallelism: Option<NonZeroUsize>,
+
+    /// Records how the sort keys of scans are computed
+    sort_key_metrics: SortKeyMetrics,
+
+    /// Records the reads of the scanned chunks, if enabled
+    chunk_read_metrics: Option<ChunkReadMetrics>,
+
+    /// Excludes expired data from scans
+    retention: Arc<Retention>,
 }
 
 impl DbSchemaProvider {
-    fn new(catalog: Arc<Catalog>, chunk_access: Arc<ChunkAccess>) -> Self {
+    fn new(
+        catalog: Arc<Catalog>,
+        chunk_access: Arc<ChunkAccess>,
+        scan_parallelism: Option<NonZeroUsize>,
+        sort_key_metrics: SortKeyMetrics,
+        chunk_read_metrics: Option<ChunkReadMetrics>,
+        retention: Arc<Retention>,
+    ) -> Self {
         Self {
             catalog,
             chunk_access,
+            scan_parallelism,
+            sort_key_metrics,
+            chunk_read_metrics,
+            retention,
+        }
+    }
+
+    /// Create a provider builder for `table_name` with the settings of this
+    /// schema
+    fn provider_builder<C: QueryChunk>(
+        &self,
+        table_name: &str,
+        schema: Arc<Schema>,
+        chunk_pruner: Arc<dyn ChunkPruner<C>>,
+    ) -> ProviderBuilder<C> {
+        let mut builder = ProviderBuilder::new(table_name, schema)
+            .with_sort_key_metrics(self.sort_key_metrics.clone())
+            .add_pruner(chunk_pruner);
+        if let Some(scan_parallelism) = self.scan_parallelism {
+            builder = builder.with_scan_parallelism(scan_parallelism);
         }
+        if let Some(period) = *self.retention.period.read() {
+            builder = builder.add_retention(period, self.retention.now());
+        }
+        builder
+    }
+}
+
+/// Build the provider of `builder` for `chunks`
+fn build_provider<C: QueryChunk + 'static>(
+    mut builder: ProviderBuilder<C>,
+    chunks: impl IntoIterator<Item = Arc<C>>,
+) -> Arc<dyn TableProvider> {
+    for chunk in chunks {
+        builder = builder.add_chunk(chunk);
+    }
+    match builder.build() {
+        Ok(provider) => Arc::new(provider),
+        Err(e) => panic!("unexpected error: {:?}", e),
     }
 }
 
@@ -370,22 +522,30 @@ impl SchemaProvider for DbSchemaProvider {
             schema
         };
 
-        let mut builder = ProviderBuilder::new(table_name, schema);
-        builder =
-            builder.add_pruner(Arc::clone(&self.chunk_access) as Arc<dyn ChunkPruner<DbChunk>>);
+        let chunk_pruner = Arc::clone(&self.chunk_access) as Arc<dyn ChunkPruner<DbChunk>>;
 
         // TODO: Better chunk pruning (#3570)
-        for chunk in self
+        let chunks = self
             .chunk_access
-            .candidate_chunks(table_name, &Default::default())
-        {
-            builder = builder.add_chunk(chunk);
-        }
-
-        match builder.build() {
-            Ok(provider) => Some(Arc::new(provider)),
-            Err(e) => panic!("unexpected error: {:?}", e),
-        }
+            .candidate_chunks(table_name, &Default::default());
+
+        let provider = match &self.chunk_read_metrics {
+            Some(metrics) => {
+                let chunk_pruner = Arc::new(MeasuredPruner::new(chunk_pruner));
+                let chunks = chunks
+                    .into_iter()
+                    .map(|chunk| Arc::new(MeasuredChunk::new(chunk, metrics)));
+                build_provider(
+                    self.provider_builder(table_name, schema, chunk_pruner),
+                    chunks,
+                )
+            }
+            None => build_provider(
+                self.provider_builder(table_name, schema, chunk_pruner),
+                chunks,
+            ),
+        };
+        Some(provider)
     }
 
     fn table_exist(&self, name: &str) -> bool {
@@ -396,9 +556,12 @@ impl SchemaProvider for DbSchemaProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_helpers::write_lp;
-    use crate::utils::make_db;
+    use crate::test_helpers::{run_query, write_lp};
+    use crate::utils::{make_db, TestDb};
+    use arrow_util::assert_batches_eq;
+    use data_types::database_rules::LifecycleRules;
     use predicate::predicate::PredicateBuilder;
+    use std::num::{NonZeroU32, NonZeroU64};
 
     #[tokio::test]
     async fn test_filtered_chunks() {
@@ -436,4 +599,76 @@ mod tests {
         let predicate = PredicateBuilder::new().timestamp_range(2, 5).build();
         assert_eq!(db.catalog_access.chunks("cpu", &predicate).len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_chunk_read_metrics() {
+        for chunk_read_metrics in [false, true] {
+            let test_db = TestDb::builder()
+                .chunk_read_metrics(chunk_read_metrics)
+                .build()
+                .await;
+            let db = test_db.db;
+
+            write_lp(&db, "cpu foo=1 1");
+            write_lp(&db, "cpu foo=2 2");
+
+            let batches = run_query(Arc::clone(&db), "select * from cpu").await;
+            let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+            assert_eq!(num_rows, 2);
+
+            let rows = test_db
+                .metric_registry
+                .get_instrument::<Metric<U64Counter>>("query_chunk_read_filter_rows")
+                .map(|metric| {
+                    metric
+                        .get_observer(&Attributes::from([
+                            ("db_name", "placeholder".into()),
+                            ("table", "cpu".into()),
+                            ("chunk_type", "MUB".into()),
+                        ]))
+                        .unwrap()
+                        .fetch()
+                });
+            let expected = chunk_read_metrics.then(|| 2);
+            assert_eq!(rows, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        // Data older than one second before "now" has expired
+        let time_provider = Arc::new(time::MockProvider::new(time::Time::from_timestamp_nanos(
+            1_500_000_000,
+        )));
+        let db = TestDb::builder()
+            .lifecycle_rules(LifecycleRules {
+                late_arrive_window_seconds: NonZeroU32::new(1).unwrap(),
+                retention_period_seconds: NonZeroU64::new(1),
+                ..Default::default()
+            })
+            .time_provider(time_provider)
+            .build()
+            .await
+            .db;
+
+        write_lp(&db, "cpu foo=1 100");
+        write_lp(&db, "cpu foo=2 1000000000");
+
+        let batches = run_query(Arc::clone(&db), "select foo from cpu").await;
+        assert_batches_eq!(
+            &["+-----+", "| foo |", "+-----+", "| 2   |", "+-----+"],
+            &batches
+        );
+
+        // Removing the retention period makes the data visible again
+        let mut rules = db.rules().as_ref().clone();
+        rules.lifecycle_rules.retention_period_seconds = None;
+        db.update_rules(Arc::new(rules));
+
+        let batches = run_query(Arc::clone(&db), "select foo from cpu order by foo").await;
+        assert_batches_eq!(
+            &["+-----+", "| foo |", "+-----+", "| 1   |", "| 2   |", "+-----+"],
+            &batches
+        );
+    }
 }
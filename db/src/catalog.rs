@@ -7,7 +7,9 @@ use hashbrown::{HashMap, HashSet};
 
 use data_types::chunk_metadata::ChunkSummary;
 use data_types::chunk_metadata::DetailedChunkSummary;
-use data_types::partition_metadata::{PartitionAddr, PartitionSummary, TableSummary};
+use data_types::partition_metadata::{
+    PartitionAddr, PartitionMeta, PartitionSummary, TableSummary,
+};
 use snafu::{OptionExt, Snafu};
 use tracker::{
     MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
@@ -217,6 +219,20 @@ impl Catalog {
             .collect()
     }
 
+    /// Returns the [`PartitionMeta`] of each partition of `table_name`
+    pub fn partition_metadata(&self, table_name: &str) -> Vec<PartitionMeta> {
+        self.tables
+            .read()
+            .get(table_name)
+            .map(|table| {
+                table
+                    .partitions()
+                    .map(|partition| partition.read().meta())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Returns a list of persistence window summaries for each partition
     pub fn persistence_summaries(&self) -> Vec<(PartitionAddr, WriteSummary)> {
         let mut summaries = Vec::new();
@@ -5,7 +5,7 @@ use crate::catalog::metrics::PartitionMetrics;
 use data_types::{
     chunk_metadata::{ChunkAddr, ChunkId, ChunkLifecycleAction, ChunkOrder, ChunkSummary},
     delete_predicate::DeletePredicate,
-    partition_metadata::{PartitionAddr, PartitionSummary},
+    partition_metadata::{PartitionAddr, PartitionMeta, PartitionSummary},
 };
 use hashbrown::HashMap;
 use observability_deps::tracing::info;
@@ -115,6 +115,11 @@ impl ChunkCollection {
     fn is_empty(&self) -> bool {
         self.chunk_orders.is_empty()
     }
+
+    /// Returns the number of chunks in the collection
+    fn len(&self) -> usize {
+        self.chunk_orders.len()
+    }
 }
 
 /// IOx Catalog Partition
@@ -422,6 +427,32 @@ impl Partition {
         }
     }
 
+    /// Return the persistence state of this partition.
+    ///
+    /// A write is persisted once the persistence windows no longer track it
+    /// as unpersisted, so partitions without persistence windows report no
+    /// persisted writes.
+    pub fn meta(&self) -> PartitionMeta {
+        let persisted_sequence_numbers = self
+            .sequencer_numbers()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(sequencer_id, unpersisted)| {
+                let persisted = match unpersisted.min() {
+                    Some(min) => min.checked_sub(1)?,
+                    None => unpersisted.max(),
+                };
+                Some((sequencer_id, persisted))
+            })
+            .collect();
+
+        PartitionMeta {
+            partition_key: Arc::clone(&self.addr.partition_key),
+            persisted_sequence_numbers,
+            chunk_count: self.chunks.len(),
+        }
+    }
+
     /// Return chunk summaries for all chunks in this partition
     pub fn chunk_summaries(&self) -> impl Iterator<Item = ChunkSummary> + '_ {
         self.chunks.values().map(|x| x.read().summary())
@@ -625,7 +656,7 @@ mod tests {
         assert!(!partition.contiguous_chunks(&ids, &order_range).unwrap());
     }
 
-    fn make_partitition_for_chunks_with_ids_orders(id_orders: &[(u128, u32)]) -> Partition {
+    fn make_partitition_for_chunks_with_ids_orders(id_orders: &[(u128, u64)]) -> Partition {
         let addr = PartitionAddr {
             db_name: "d".into(),
             table_name: "t".into(),
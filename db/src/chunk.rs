@@ -16,7 +16,10 @@ use observability_deps::tracing::debug;
 use parquet_file::chunk::ParquetChunk;
 use partition_metadata::TableSummary;
 use predicate::predicate::{Predicate, PredicateMatch};
-use query::{exec::stringset::StringSet, QueryChunk, QueryChunkMeta};
+use query::{
+    exec::stringset::StringSet, QueryChunk, QueryChunkMeta, SequenceNumberRange,
+    UnsupportedPredicate,
+};
 use read_buffer::RBChunk;
 use schema::InfluxColumnType;
 use schema::{selection::Selection, sort::SortKey, Schema};
@@ -56,11 +59,8 @@ pub enum Error {
     #[snafu(display("Predicate conversion error: {}", source))]
     PredicateConversion { source: super::pred::Error },
 
-    #[snafu(display(
-        "Internal error: mutable buffer does not support predicate pushdown, but got: {:?}",
-        predicate
-    ))]
-    InternalPredicateNotSupported { predicate: Predicate },
+    #[snafu(display("{}", source))]
+    UnsupportedPredicate { source: UnsupportedPredicate },
 
     #[snafu(display("internal error creating plan: {}", source))]
     InternalPlanCreation {
@@ -227,6 +227,36 @@ impl DbChunk {
         self.time_of_last_write
     }
 
+    /// Convert `predicate` into a Read Buffer predicate `chunk` can evaluate.
+    /// Fails with an [`UnsupportedPredicate`] naming the first clause the
+    /// Read Buffer can't evaluate, or the whole predicate if it doesn't apply
+    /// to the schema of `chunk`.
+    fn to_rub_predicate(chunk: &RBChunk, predicate: &Predicate) -> Result<read_buffer::Predicate> {
+        let rb_predicate = to_read_buffer_predicate(predicate).map_err(|e| {
+            let clause = predicate
+                .exprs
+                .iter()
+                .find(|expr| read_buffer::BinaryExpr::try_from(*expr).is_err())
+                .map(|expr| expr.to_string())
+                .unwrap_or_else(|| predicate.to_string());
+            Error::UnsupportedPredicate {
+                source: UnsupportedPredicate {
+                    clause,
+                    reason: e.to_string(),
+                },
+            }
+        })?;
+
+        chunk
+            .validate_predicate(rb_predicate)
+            .map_err(|e| Error::UnsupportedPredicate {
+                source: UnsupportedPredicate {
+                    clause: predicate.to_string(),
+                    reason: e.to_string(),
+                },
+            })
+    }
+
     /// NOTE: valid Read Buffer predicates are not guaranteed to be applicable
     /// to an arbitrary Read Buffer chunk, because the applicability of a
     /// predicate depends on the schema of the chunk. Callers should validate
@@ -339,7 +369,9 @@ impl QueryChunk for DbChunk {
                 }
             }
             State::ParquetFile { chunk, .. } => {
-                if predicate.has_exprs() || chunk.has_timerange(predicate.range.as_ref()) {
+                if !chunk.may_match_tag_values(predicate) {
+                    PredicateMatch::Zero
+                } else if predicate.has_exprs() || chunk.has_timerange(predicate.range.as_ref()) {
                     PredicateMatch::Unknown
                 } else {
                     PredicateMatch::Zero
@@ -382,13 +414,10 @@ impl QueryChunk for DbChunk {
                 Ok(Box::pin(MemoryStream::new(vec![batch])))
             }
             State::ReadBuffer { chunk, .. } => {
-                // Only apply pushdownable predicates
-                let rb_predicate = chunk
-                    // A predicate unsupported by the Read Buffer or against
-                    // this chunk's schema is replaced with a default empty
-                    // predicate.
-                    .validate_predicate(to_read_buffer_predicate(predicate).unwrap_or_default())
-                    .unwrap_or_default();
+                // A predicate unsupported by the Read Buffer or against this
+                // chunk's schema fails the read, so the caller can read the
+                // chunk without it and filter the data itself
+                let rb_predicate = Self::to_rub_predicate(chunk, predicate)?;
                 debug!(?rb_predicate, "RUB predicate");
 
                 // combine all delete expressions to RUB's negated ones
@@ -554,6 +583,21 @@ impl QueryChunk for DbChunk {
     fn order(&self) -> ChunkOrder {
         self.order
     }
+
+    /// Only parquet files written by the ingester record the sequence
+    /// numbers of their writes
+    fn sequence_numbers(&self) -> Option<SequenceNumberRange> {
+        match &self.state {
+            State::ParquetFile { chunk } => {
+                let (sequencer_id, range) = chunk.sequence_numbers()?;
+                Some(SequenceNumberRange::new(
+                    sequencer_id.get() as u32,
+                    range.start().get()..=range.end().get(),
+                ))
+            }
+            State::MutableBuffer { .. } | State::ReadBuffer { .. } => None,
+        }
+    }
 }
 
 impl QueryChunkMeta for DbChunk {
@@ -572,6 +616,22 @@ impl QueryChunkMeta for DbChunk {
 
         pred
     }
+
+    fn size_bytes(&self) -> usize {
+        let data = match &self.state {
+            State::MutableBuffer { chunk } => chunk.size(),
+            State::ReadBuffer { chunk } => chunk.size(),
+            State::ParquetFile { chunk } => chunk.size(),
+        };
+        let delete_predicates: usize = self
+            .meta
+            .delete_predicates
+            .iter()
+            .map(|pred| pred.size())
+            .sum();
+
+        std::mem::size_of::<Self>() + data + self.meta.table_summary.size() + delete_predicates
+    }
 }
 
 #[cfg(test)]
@@ -580,10 +640,15 @@ mod tests {
     use crate::{
         catalog::chunk::{CatalogChunk, ChunkStage},
         test_helpers::write_lp,
-        utils::make_db_time,
+        utils::{make_db_time, TestDb},
     };
-    use data_types::chunk_metadata::ChunkStorage;
-    use std::time::Duration;
+    use data_types::{chunk_metadata::ChunkStorage, database_rules::LifecycleRules};
+    use datafusion::{
+        logical_plan::{col, lit, Expr},
+        scalar::ScalarValue,
+    };
+    use predicate::predicate::PredicateBuilder;
+    use std::{num::NonZeroU32, time::Duration};
 
     async fn test_chunk_access(chunk: &CatalogChunk, time: Arc<time::MockProvider>) {
         let m1 = chunk.access_recorder().get_metrics();
@@ -673,6 +738,46 @@ mod tests {
         test_chunk_access(&chunk, time).await
     }
 
+    #[tokio::test]
+    async fn rub_read_filter_unsupported_predicate() {
+        let (db, _time) = make_db_time().await;
+
+        write_lp(&db, "cpu,tag=1 bar=1 1");
+        db.compact_partition("cpu", "1970-01-01T00").await.unwrap();
+
+        let chunks = db.catalog.chunks();
+        let chunk = chunks.into_iter().next().unwrap();
+        let chunk = DbChunk::snapshot(&chunk.read());
+
+        // Not supported by the Read Buffer
+        let expr = col("tag").eq(Expr::Literal(ScalarValue::Utf8(None)));
+        let predicate = PredicateBuilder::default().add_expr(expr.clone()).build();
+        let err = chunk.read_filter(&predicate, Selection::All).unwrap_err();
+        assert_eq!(
+            UnsupportedPredicate::find(&err),
+            Some(&UnsupportedPredicate {
+                clause: expr.to_string(),
+                reason: "Error translating predicate: NULL literal not supported".to_string(),
+            })
+        );
+
+        // Not applicable to the chunk's schema
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("missing").eq(lit("x")))
+            .build();
+        let err = chunk.read_filter(&predicate, Selection::All).unwrap_err();
+        assert_eq!(
+            UnsupportedPredicate::find(&err).unwrap().clause,
+            predicate.to_string()
+        );
+
+        // Supported predicates are evaluated by the chunk
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("tag").eq(lit("1")))
+            .build();
+        chunk.read_filter(&predicate, Selection::All).unwrap();
+    }
+
     #[tokio::test]
     async fn parquet_records_access() {
         let (db, time) = make_db_time().await;
@@ -729,4 +834,53 @@ mod tests {
         assert_eq!(w0, first_write);
         assert_eq!(w1, last_write);
     }
+
+    #[tokio::test]
+    async fn parquet_tag_bloom_filters_prune() {
+        let db = TestDb::builder()
+            .lifecycle_rules(LifecycleRules {
+                late_arrive_window_seconds: NonZeroU32::try_from(1).unwrap(),
+                persist_tag_bloom_filters: true,
+                ..Default::default()
+            })
+            .build()
+            .await
+            .db;
+
+        write_lp(&db, "cpu,host=a bar=1 1\ncpu,host=b bar=2 2");
+
+        let id = db
+            .persist_partition("cpu", "1970-01-01T00", true)
+            .await
+            .unwrap()
+            .unwrap()
+            .id();
+
+        db.unload_read_buffer("cpu", "1970-01-01T00", id).unwrap();
+
+        let chunks = db.catalog.chunks();
+        assert_eq!(chunks.len(), 1);
+        let chunk = chunks.into_iter().next().unwrap();
+        let chunk = chunk.read();
+        assert_eq!(chunk.storage().1, ChunkStorage::ObjectStoreOnly);
+        let snapshot = DbChunk::snapshot(&chunk);
+
+        // no row has host=z, so the bloom filter excludes the chunk
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("host").eq(lit("z")))
+            .build();
+        assert!(matches!(
+            snapshot.apply_predicate_to_metadata(&predicate).unwrap(),
+            PredicateMatch::Zero
+        ));
+
+        // a row has host=a, so the chunk must be kept
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("host").eq(lit("a")))
+            .build();
+        assert!(matches!(
+            snapshot.apply_predicate_to_metadata(&predicate).unwrap(),
+            PredicateMatch::Unknown
+        ));
+    }
 }
@@ -0,0 +1,532 @@
+//! Downsample a table by running a windowed aggregation and writing the
+//! results back to a (possibly different) database as a new table
+use std::{collections::HashMap, convert::TryFrom, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use data_types::timestamp::MIN_NANO_TIME;
+use dml::{DmlMeta, DmlWrite};
+use mutable_batch::{writer::Writer, MutableBatch};
+use observability_deps::tracing::debug;
+use predicate::{
+    predicate::PredicateBuilder,
+    rpc_predicate::{InfluxRpcPredicate, FIELD_COLUMN_NAME, MEASUREMENT_COLUMN_NAME},
+};
+use query::{
+    exec::{
+        seriesset::series::{Data, Either, Series},
+        ExecutionContextProvider,
+    },
+    frontend::influxrpc::InfluxRpcPlanner,
+    group_by::{Aggregate, WindowDuration},
+};
+use schema::TIME_COLUMN_NAME;
+use snafu::{OptionExt, ResultExt, Snafu};
+use time::Time;
+
+use crate::{Db, DmlError};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Invalid downsample window {:?}: must be a positive number of nanoseconds fitting an i64",
+        window
+    ))]
+    InvalidWindow { window: Duration },
+
+    #[snafu(display("Error planning downsample of {}: {}", table_name, source))]
+    Planning {
+        table_name: String,
+        source: query::frontend::influxrpc::Error,
+    },
+
+    #[snafu(display("Error running downsample of {}: {}", table_name, source))]
+    Executing {
+        table_name: String,
+        source: datafusion::error::DataFusionError,
+    },
+
+    #[snafu(display("Error building downsampled rows for {}: {}", table_name, source))]
+    BuildingRows {
+        table_name: String,
+        source: mutable_batch::writer::Error,
+    },
+
+    #[snafu(display("Downsampled series of {} has no field", table_name))]
+    MissingField { table_name: String },
+
+    #[snafu(display("Error writing downsampled rows to {}: {}", table_name, source))]
+    Storing {
+        table_name: String,
+        source: DmlError,
+    },
+
+    #[snafu(display("Error loading downsample checkpoint {}: {}", name, source))]
+    LoadingCheckpoint {
+        name: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Invalid downsample checkpoint {}: {:?}", name, content))]
+    InvalidCheckpoint { name: String, content: String },
+
+    #[snafu(display("Error storing downsample checkpoint {}: {}", name, source))]
+    StoringCheckpoint {
+        name: String,
+        source: object_store::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What to downsample and where to write the results
+#[derive(Debug, Clone)]
+pub struct DownsampleConfig {
+    /// Table whose data is aggregated
+    pub source_table: String,
+
+    /// Table the aggregated points are written to
+    pub target_table: String,
+
+    /// Aggregate applied to every field within a window
+    pub aggregate: Aggregate,
+
+    /// Width of the windows, must be a non-zero number of nanoseconds
+    pub window: Duration,
+}
+
+/// Incrementally downsamples a table.
+///
+/// Every call to [`run`](Self::run) aggregates the windows that were
+/// completed since the previous call and writes one point per window,
+/// timestamped with the end of the window, to the target table. Windows
+/// that have already been processed are never aggregated again, so
+/// running the downsampler repeatedly does not duplicate points.
+///
+/// The end of the last processed window is stored as a checkpoint in the
+/// object store of the source database after every run, so that
+/// [`load`](Self::load) can resume after a restart. Should the server stop
+/// between writing the points and storing the checkpoint, the next run
+/// writes the same points again, which replace the earlier ones as they
+/// have the same series and timestamps.
+///
+/// Data arriving for a window after it has been processed is not picked up.
+#[derive(Debug)]
+pub struct Downsampler {
+    config: DownsampleConfig,
+
+    /// Exclusive end of the last processed window, if any
+    last_processed: Option<i64>,
+}
+
+impl Downsampler {
+    pub fn new(config: DownsampleConfig) -> Self {
+        Self {
+            config,
+            last_processed: None,
+        }
+    }
+
+    /// Resume downsampling after the window ending at `last_processed`
+    pub fn new_from_last_processed(config: DownsampleConfig, last_processed: i64) -> Self {
+        Self {
+            config,
+            last_processed: Some(last_processed),
+        }
+    }
+
+    /// Resume downsampling after the last window processed for `target`,
+    /// as recorded in the object store of `source`
+    pub async fn load(config: DownsampleConfig, source: &Db, target: &Db) -> Result<Self> {
+        let name = checkpoint_name(&config, target);
+        let checkpoint = source
+            .iox_object_store()
+            .get_downsample_checkpoint(&name)
+            .await
+            .context(LoadingCheckpointSnafu { name: &name })?;
+
+        let last_processed = match checkpoint {
+            Some(bytes) => {
+                let content = String::from_utf8_lossy(&bytes);
+                let last_processed = content.parse().ok().context(InvalidCheckpointSnafu {
+                    name: &name,
+                    content: content.as_ref(),
+                })?;
+                Some(last_processed)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            last_processed,
+        })
+    }
+
+    /// Exclusive end of the last processed window, if any
+    pub fn last_processed(&self) -> Option<i64> {
+        self.last_processed
+    }
+
+    /// Aggregates all windows of `source` that ended at or before `now`
+    /// and were not processed yet, and writes them to `target`.
+    ///
+    /// Returns the number of points written.
+    pub async fn run(&mut self, source: &Arc<Db>, target: &Db, now: Time) -> Result<usize> {
+        let window = i64::try_from(self.config.window.as_nanos())
+            .ok()
+            .filter(|window| *window > 0)
+            .context(InvalidWindowSnafu {
+                window: self.config.window,
+            })?;
+
+        let now = now.timestamp_nanos();
+        let end = now - now.rem_euclid(window);
+        let start = self.last_processed.unwrap_or(MIN_NANO_TIME);
+        if start >= end {
+            return Ok(0);
+        }
+
+        let table_name = &self.config.source_table;
+        let predicate = InfluxRpcPredicate::new_table(
+            table_name,
+            PredicateBuilder::default()
+                .timestamp_range(start, end)
+                .build(),
+        );
+
+        let plans = InfluxRpcPlanner::new()
+            .read_window_aggregate(
+                source.as_ref(),
+                predicate,
+                self.config.aggregate,
+                WindowDuration::from_nanoseconds(window),
+                WindowDuration::empty(),
+            )
+            .context(PlanningSnafu { table_name })?;
+
+        let ctx = source.new_query_context(None);
+        let results = ctx
+            .to_series_and_groups(plans)
+            .await
+            .context(ExecutingSnafu { table_name })?;
+
+        let mut batch = MutableBatch::new();
+        let mut points = 0;
+        for series in results.into_iter().filter_map(|r| match r {
+            Either::Series(series) => Some(series),
+            Either::Group(_) => None,
+        }) {
+            points += write_series(&mut batch, &series, &self.config.target_table)?;
+        }
+
+        if points > 0 {
+            let tables = HashMap::from([(self.config.target_table.clone(), batch)]);
+            let write = DmlWrite::new(target.name().as_ref(), tables, DmlMeta::default());
+            target.store_write(&write).context(StoringSnafu {
+                table_name: &self.config.target_table,
+            })?;
+        }
+
+        debug!(
+            source_table=%self.config.source_table,
+            target_table=%self.config.target_table,
+            start,
+            end,
+            points,
+            "Downsampled table"
+        );
+
+        let name = checkpoint_name(&self.config, target);
+        source
+            .iox_object_store()
+            .put_downsample_checkpoint(&name, Bytes::from(end.to_string()))
+            .await
+            .context(StoringCheckpointSnafu { name })?;
+
+        self.last_processed = Some(end);
+        Ok(points)
+    }
+}
+
+/// Name of the checkpoint of the downsampling of `config` into `target`
+fn checkpoint_name(config: &DownsampleConfig, target: &Db) -> String {
+    format!("{}.{}", target.name(), config.target_table)
+}
+
+/// Appends the points of `series` to `batch` as rows with the tags of the
+/// series and a single field, returning the number of rows written
+fn write_series(batch: &mut MutableBatch, series: &Series, table_name: &str) -> Result<usize> {
+    let field_name = series
+        .tags
+        .iter()
+        .find(|tag| tag.key.as_ref() == FIELD_COLUMN_NAME)
+        .map(|tag| Arc::clone(&tag.value))
+        .context(MissingFieldSnafu { table_name })?;
+
+    let timestamps = match &series.data {
+        Data::FloatPoints { timestamps, .. }
+        | Data::IntegerPoints { timestamps, .. }
+        | Data::UnsignedPoints { timestamps, .. }
+        | Data::BooleanPoints { timestamps, .. }
+        | Data::StringPoints { timestamps, .. } => timestamps,
+    };
+    let rows = timestamps.len();
+    if rows == 0 {
+        return Ok(0);
+    }
+
+    write_rows(batch, series, &field_name, timestamps).context(BuildingRowsSnafu { table_name })?;
+
+    Ok(rows)
+}
+
+fn write_rows(
+    batch: &mut MutableBatch,
+    series: &Series,
+    field_name: &str,
+    timestamps: &[i64],
+) -> mutable_batch::writer::Result<()> {
+    let rows = timestamps.len();
+    let mut writer = Writer::new(batch, rows);
+
+    for tag in &series.tags {
+        let key = tag.key.as_ref();
+        if key == FIELD_COLUMN_NAME || key == MEASUREMENT_COLUMN_NAME {
+            continue;
+        }
+        writer.write_tag(key, None, std::iter::repeat(tag.value.as_ref()).take(rows))?;
+    }
+
+    match &series.data {
+        Data::FloatPoints { values, .. } => {
+            writer.write_f64(field_name, None, values.iter().cloned())?
+        }
+        Data::IntegerPoints { values, .. } => {
+            writer.write_i64(field_name, None, values.iter().cloned())?
+        }
+        Data::UnsignedPoints { values, .. } => {
+            writer.write_u64(field_name, None, values.iter().cloned())?
+        }
+        Data::BooleanPoints { values, .. } => {
+            writer.write_bool(field_name, None, values.iter().cloned())?
+        }
+        Data::StringPoints { values, .. } => {
+            writer.write_string(field_name, None, values.iter().map(|v| v.as_str()))?
+        }
+    }
+
+    writer.write_time(TIME_COLUMN_NAME, timestamps.iter().cloned())?;
+    writer.commit();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{run_query, write_lp},
+        utils::{make_db, TestDb},
+    };
+    use arrow_util::assert_batches_sorted_eq;
+    use tracker::TaskResult;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    fn config() -> DownsampleConfig {
+        DownsampleConfig {
+            source_table: "h2o".to_string(),
+            target_table: "h2o_1m".to_string(),
+            aggregate: Aggregate::Mean,
+            window: Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn downsample_is_incremental_and_idempotent() {
+        let db = make_db().await.db;
+        let lp = vec![
+            format!("h2o,state=MA,city=Boston temp=70.0 {}", 10 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=72.0 {}", 20 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=74.0 {}", 70 * SECOND),
+            format!("h2o,state=CA,city=LA temp=90.0 {}", 30 * SECOND),
+            format!("h2o,state=CA,city=LA temp=92.0 {}", 150 * SECOND),
+        ];
+        write_lp(&db, &lp.join("\n"));
+
+        let mut downsampler = Downsampler::new(config());
+
+        // only the windows ending at or before 2m are complete
+        let now = Time::from_timestamp_nanos(130 * SECOND);
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 3);
+        assert_eq!(downsampler.last_processed(), Some(120 * SECOND));
+
+        let expected = vec![
+            "+--------+-------+------+----------------------+",
+            "| city   | state | temp | time                 |",
+            "+--------+-------+------+----------------------+",
+            "| Boston | MA    | 71   | 1970-01-01T00:01:00Z |",
+            "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+            "| LA     | CA    | 90   | 1970-01-01T00:01:00Z |",
+            "+--------+-------+------+----------------------+",
+        ];
+        let query = "select city, state, temp, time from h2o_1m";
+        let batches = run_query(Arc::clone(&db), query).await;
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        // running again without any newly completed window writes nothing
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 0);
+
+        let batches = run_query(Arc::clone(&db), query).await;
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        // the next window is picked up once it is complete
+        let now = Time::from_timestamp_nanos(200 * SECOND);
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 1);
+        assert_eq!(downsampler.last_processed(), Some(180 * SECOND));
+
+        let expected = vec![
+            "+--------+-------+------+----------------------+",
+            "| city   | state | temp | time                 |",
+            "+--------+-------+------+----------------------+",
+            "| Boston | MA    | 71   | 1970-01-01T00:01:00Z |",
+            "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+            "| LA     | CA    | 90   | 1970-01-01T00:01:00Z |",
+            "| LA     | CA    | 92   | 1970-01-01T00:03:00Z |",
+            "+--------+-------+------+----------------------+",
+        ];
+        let batches = run_query(Arc::clone(&db), query).await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn downsample_resumes_from_last_processed() {
+        let db = make_db().await.db;
+        let lp = vec![
+            format!("h2o,state=MA,city=Boston temp=70.0 {}", 10 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=74.0 {}", 70 * SECOND),
+        ];
+        write_lp(&db, &lp.join("\n"));
+
+        // the first window was processed by an earlier run
+        let mut downsampler = Downsampler::new_from_last_processed(config(), 60 * SECOND);
+
+        let now = Time::from_timestamp_nanos(120 * SECOND);
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 1);
+
+        let expected = vec![
+            "+--------+-------+------+----------------------+",
+            "| city   | state | temp | time                 |",
+            "+--------+-------+------+----------------------+",
+            "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+            "+--------+-------+------+----------------------+",
+        ];
+        let batches = run_query(db, "select city, state, temp, time from h2o_1m").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn downsample_resumes_from_checkpoint() {
+        let db = make_db().await.db;
+        let lp = vec![
+            format!("h2o,state=MA,city=Boston temp=70.0 {}", 10 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=74.0 {}", 70 * SECOND),
+        ];
+        write_lp(&db, &lp.join("\n"));
+
+        // nothing was processed yet
+        let downsampler = Downsampler::load(config(), &db, &db).await.unwrap();
+        assert_eq!(downsampler.last_processed(), None);
+
+        let mut downsampler = Downsampler::new(config());
+        let now = Time::from_timestamp_nanos(90 * SECOND);
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 1);
+
+        // a downsampler loaded after a restart continues after the first window
+        let mut downsampler = Downsampler::load(config(), &db, &db).await.unwrap();
+        assert_eq!(downsampler.last_processed(), Some(60 * SECOND));
+
+        let now = Time::from_timestamp_nanos(120 * SECOND);
+        let points = downsampler.run(&db, &db, now).await.unwrap();
+        assert_eq!(points, 1);
+
+        let expected = vec![
+            "+--------+-------+------+----------------------+",
+            "| city   | state | temp | time                 |",
+            "+--------+-------+------+----------------------+",
+            "| Boston | MA    | 70   | 1970-01-01T00:01:00Z |",
+            "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+            "+--------+-------+------+----------------------+",
+        ];
+        let batches = run_query(db, "select city, state, temp, time from h2o_1m").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn downsample_rejects_empty_window() {
+        let db = make_db().await.db;
+        write_lp(&db, "h2o,state=MA,city=Boston temp=70.0 10");
+
+        let config = DownsampleConfig {
+            window: Duration::from_secs(0),
+            ..config()
+        };
+        let mut downsampler = Downsampler::new(config);
+
+        let now = Time::from_timestamp_nanos(120 * SECOND);
+        let err = downsampler.run(&db, &db, now).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidWindow { .. }), "{}", err);
+        assert_eq!(downsampler.last_processed(), None);
+    }
+
+    #[tokio::test]
+    async fn downsample_job() {
+        let time = Arc::new(time::MockProvider::new(Time::from_timestamp_nanos(
+            130 * SECOND,
+        )));
+        let db = TestDb::builder()
+            .time_provider(Arc::<time::MockProvider>::clone(&time))
+            .build()
+            .await
+            .db;
+        let lp = vec![
+            format!("h2o,state=MA,city=Boston temp=70.0 {}", 10 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=74.0 {}", 70 * SECOND),
+            format!("h2o,state=MA,city=Boston temp=76.0 {}", 150 * SECOND),
+        ];
+        write_lp(&db, &lp.join("\n"));
+
+        let tracker = db.downsample(config(), Arc::clone(&db));
+        tracker.join().await;
+        assert!(matches!(
+            tracker.get_status().result(),
+            Some(TaskResult::Success)
+        ));
+
+        // a second job only picks up the windows completed since the first
+        time.set(Time::from_timestamp_nanos(200 * SECOND));
+        let tracker = db.downsample(config(), Arc::clone(&db));
+        tracker.join().await;
+        assert!(matches!(
+            tracker.get_status().result(),
+            Some(TaskResult::Success)
+        ));
+
+        let expected = vec![
+            "+--------+-------+------+----------------------+",
+            "| city   | state | temp | time                 |",
+            "+--------+-------+------+----------------------+",
+            "| Boston | MA    | 70   | 1970-01-01T00:01:00Z |",
+            "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+            "| Boston | MA    | 76   | 1970-01-01T00:03:00Z |",
+            "+--------+-------+------+----------------------+",
+        ];
+        let batches = run_query(db, "select city, state, temp, time from h2o_1m").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+}
@@ -23,7 +23,7 @@ use data_types::{
     database_rules::DatabaseRules,
     delete_predicate::DeletePredicate,
     job::Job,
-    partition_metadata::{PartitionAddr, PartitionSummary, TableSummary},
+    partition_metadata::{PartitionAddr, PartitionMeta, PartitionSummary, TableSummary},
     server_id::ServerId,
 };
 use datafusion::catalog::{catalog::CatalogProvider, schema::SchemaProvider};
@@ -44,7 +44,10 @@ use parquet_catalog::{
 use persistence_windows::{checkpoint::ReplayPlan, persistence_windows::PersistenceWindows};
 use predicate::{predicate::Predicate, rpc_predicate::QueryDatabaseMeta};
 use query::{
-    exec::{ExecutionContextProvider, Executor, ExecutorType, IOxExecutionContext},
+    exec::{
+        progress::QueryProgressReporter, ExecutionContextProvider, Executor, ExecutorType,
+        IOxExecutionContext,
+    },
     QueryCompletedToken, QueryDatabase,
 };
 use rand_distr::{Distribution, Poisson};
@@ -62,11 +65,12 @@ use std::{
 };
 use time::{Time, TimeProvider};
 use trace::ctx::SpanContext;
-use tracker::TaskTracker;
+use tracker::{TaskTracker, TrackedFutureExt};
 
 pub mod access;
 pub mod catalog;
 mod chunk;
+pub mod downsample;
 mod lifecycle;
 pub mod load;
 pub mod pred;
@@ -289,6 +293,7 @@ impl Db {
         } = database_to_commit;
 
         let name = Arc::from(rules.name.as_str());
+        let retention_period = rules.lifecycle_rules.retention_period();
 
         let rules = RwLock::new(rules);
         let server_id = server_id;
@@ -302,6 +307,8 @@ impl Db {
             Arc::clone(&jobs),
             Arc::clone(&time_provider),
             metric_registry.as_ref(),
+            exec.config(),
+            retention_period,
         );
         let catalog_access = Arc::new(catalog_access);
 
@@ -349,6 +356,8 @@ impl Db {
         let late_arrive_window_updated = {
             let mut rules = self.rules.write();
             info!(db_name=%rules.name,  "updating rules for database");
+            self.catalog_access
+                .set_retention_period(new_rules.lifecycle_rules.retention_period());
             let late_arrive_window_updated = rules.lifecycle_rules.late_arrive_window_seconds
                 != new_rules.lifecycle_rules.late_arrive_window_seconds;
 
@@ -754,6 +763,33 @@ impl Db {
         Ok(tracker)
     }
 
+    /// Downsample a table of this database into a table of `target` as a
+    /// background job, resuming after the windows processed by earlier runs.
+    ///
+    /// See [`Downsampler`](downsample::Downsampler) for details.
+    pub fn downsample(
+        self: &Arc<Self>,
+        config: downsample::DownsampleConfig,
+        target: Arc<Self>,
+    ) -> TaskTracker<Job> {
+        let (tracker, registration) = self.jobs.register(Job::Downsample {
+            db_name: self.name(),
+            source_table: Arc::from(config.source_table.as_str()),
+            target_db_name: target.name(),
+            target_table: Arc::from(config.target_table.as_str()),
+        });
+
+        let db = Arc::clone(self);
+        let fut = async move {
+            let mut downsampler = downsample::Downsampler::load(config, &db, &target).await?;
+            let now = db.time_provider.now();
+            downsampler.run(&db, &target, now).await
+        };
+
+        let _ = tokio::spawn(fut.track(registration));
+        tracker
+    }
+
     /// Persist given partition.
     ///
     /// If `force` is `true` will persist all unpersisted data regardless of arrival time
@@ -1207,6 +1243,10 @@ impl QueryDatabase for Db {
         self.catalog_access.partition_addrs()
     }
 
+    fn partition_metadata(&self, table_name: &str) -> Vec<PartitionMeta> {
+        self.catalog_access.partition_metadata(table_name)
+    }
+
     fn chunks(&self, table_name: &str, predicate: &Predicate) -> Vec<Arc<Self::Chunk>> {
         self.catalog_access.chunks(table_name, predicate)
     }
@@ -1217,10 +1257,12 @@ impl QueryDatabase for Db {
 
     fn record_query(
         &self,
+        ctx: &IOxExecutionContext,
         query_type: impl Into<String>,
         query_text: impl Into<String>,
     ) -> QueryCompletedToken<'_> {
-        self.catalog_access.record_query(query_type, query_text)
+        self.catalog_access
+            .record_query(ctx, query_type, query_text)
     }
 }
 
@@ -1232,15 +1274,28 @@ impl QueryDatabaseMeta for Db {
     fn table_schema(&self, table_name: &str) -> Option<Arc<Schema>> {
         self.catalog_access.table_schema(table_name)
     }
+
+    fn retention_cutoff(&self) -> Option<i64> {
+        self.catalog_access.retention_cutoff()
+    }
 }
 
 impl ExecutionContextProvider for Db {
     fn new_query_context(self: &Arc<Self>, span_ctx: Option<SpanContext>) -> IOxExecutionContext {
-        self.exec
+        let mut config = self
+            .exec
             .new_execution_config(ExecutorType::Query)
             .with_default_catalog(Arc::<Self>::clone(self))
             .with_span_context(span_ctx)
-            .build()
+            .with_cancellation_token(self.exec.query_cancellation_token(&self.name));
+
+        // The slow query log reports the number of chunks a query read
+        if self.exec.slow_query_threshold().is_some() {
+            let (reporter, _receiver) = QueryProgressReporter::new();
+            config = config.with_progress_reporter(reporter);
+        }
+
+        config.build()
     }
 }
 
@@ -1420,6 +1475,7 @@ mod tests {
         chunk_metadata::{ChunkAddr, ChunkStorage},
         database_rules::{LifecycleRules, PartitionTemplate, TemplatePart},
         partition_metadata::{ColumnSummary, InfluxDbType, StatValues, Statistics, TableSummary},
+        sequence::Sequence,
         write_summary::TimestampSummary,
     };
     use futures::{stream, StreamExt, TryStreamExt};
@@ -1435,6 +1491,7 @@ mod tests {
     use query::{QueryChunk, QueryDatabase};
     use schema::{selection::Selection, Schema};
     use std::{
+        collections::BTreeMap,
         convert::TryFrom,
         iter::Iterator,
         num::{NonZeroU32, NonZeroU64, NonZeroUsize},
@@ -2390,6 +2447,57 @@ mod tests {
         assert_eq!(open_max.timestamp_nanos(), 20);
     }
 
+    #[tokio::test]
+    async fn partition_metadata() {
+        let (db, time) = make_db_time().await;
+
+        let write = |lp: &str, sequence_number| {
+            let meta = DmlMeta::sequenced(
+                Sequence::new(0, sequence_number),
+                Time::from_timestamp_nanos(0),
+                None,
+                0,
+            );
+            let write = DmlWrite::new("placeholder", lines_to_batches(lp, 0).unwrap(), meta);
+            db.store_write(&write).unwrap();
+        };
+
+        // Only buffered
+        write("cpu bar=1 10", 0);
+        // Persisted
+        write("cpu bar=2 3600000000000", 1);
+        write("cpu bar=3 3600000000010", 2);
+
+        let partition_key = "1970-01-01T01";
+        db.rollover_partition("cpu", partition_key).await.unwrap();
+        db.compact_partition("cpu", partition_key).await.unwrap();
+        time.inc(Duration::from_secs(1));
+        db.persist_partition("cpu", partition_key, true)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut metadata = db.partition_metadata("cpu");
+        metadata.sort_by(|a, b| a.partition_key.cmp(&b.partition_key));
+        assert_eq!(
+            metadata,
+            vec![
+                PartitionMeta {
+                    partition_key: Arc::from("1970-01-01T00"),
+                    persisted_sequence_numbers: Default::default(),
+                    chunk_count: 1,
+                },
+                PartitionMeta {
+                    partition_key: Arc::from(partition_key),
+                    persisted_sequence_numbers: BTreeMap::from([(0, 2)]),
+                    chunk_count: 1,
+                },
+            ]
+        );
+
+        assert!(db.partition_metadata("mem").is_empty());
+    }
+
     #[tokio::test]
     async fn test_chunk_timestamps() {
         let (db, time) = make_db_time().await;
@@ -124,10 +124,11 @@ pub(crate) fn compact_chunks(
             }
         }
 
+        // Merge the predicates received while compacting to reduce the cost of
+        // evaluating them against the new chunk.
         let delete_predicates = {
-            let mut tmp: Vec<_> = delete_predicates_after.into_iter().collect();
-            tmp.sort();
-            tmp
+            let tmp: Vec<_> = delete_predicates_after.into_iter().collect();
+            DeletePredicate::coalesce(&tmp)
         };
 
         let rb_chunk = match maybe_rb_chunk {
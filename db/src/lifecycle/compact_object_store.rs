@@ -376,7 +376,8 @@ async fn persist_stream_to_chunk<'a>(
     iox_metadata: IoxMetadataOld,
 ) -> Result<Option<Arc<ParquetChunk>>> {
     // Create a storage to save data of this chunk
-    let storage = Storage::new(Arc::clone(&db.iox_object_store));
+    let storage = Storage::new(Arc::clone(&db.iox_object_store))
+        .with_tag_bloom_filters(db.rules.read().lifecycle_rules.persist_tag_bloom_filters);
 
     // Write the chunk stream data into a parquet file in the storage
     let chunk_addr = ChunkAddr::new(partition_addr, iox_metadata.chunk_id);
@@ -404,7 +405,8 @@ async fn persist_stream_to_chunk<'a>(
             Arc::clone(&partition_addr.partition_key),
             metrics,
         )
-        .context(ParquetChunkSnafu)?,
+        .context(ParquetChunkSnafu)?
+        .with_null_corrupt_columns(db.rules.read().lifecycle_rules.null_corrupt_parquet_columns),
     );
 
     Ok(Some(parquet_chunk))
@@ -460,10 +462,11 @@ async fn update_in_memory_catalog(
         }
     }
 
+    // Merge the predicates received while compacting to reduce the cost of
+    // evaluating them against the new chunk.
     let delete_predicates = {
-        let mut tmp: Vec<_> = delete_predicates_after.into_iter().collect();
-        tmp.sort();
-        tmp
+        let tmp: Vec<_> = delete_predicates_after.into_iter().collect();
+        DeletePredicate::coalesce(&tmp)
     };
 
     // Only create a new chunk if compaction returns rows
@@ -928,6 +931,7 @@ mod tests {
             Arc::clone(&db.time_provider),
             false,
             false,
+            false,
         )
         .await
         .unwrap();
@@ -557,6 +557,7 @@ mod tests {
             Arc::clone(&db.time_provider),
             false,
             false,
+            false,
         )
         .await
         .unwrap();
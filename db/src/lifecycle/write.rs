@@ -75,7 +75,8 @@ pub(super) fn write_chunk_to_object_store(
     let partition = partition.into_data().partition;
 
     // Create a storage to save data of this chunk
-    let storage = Storage::new(Arc::clone(&db.iox_object_store));
+    let storage = Storage::new(Arc::clone(&db.iox_object_store))
+        .with_tag_bloom_filters(db.rules.read().lifecycle_rules.persist_tag_bloom_filters);
 
     let catalog_transactions_until_checkpoint = db
         .rules
@@ -152,7 +153,10 @@ pub(super) fn write_chunk_to_object_store(
                     Arc::clone(&partition_key),
                     metrics,
                 )
-                .context(ParquetChunkSnafu)?,
+                .context(ParquetChunkSnafu)?
+                .with_null_corrupt_columns(
+                    db.rules.read().lifecycle_rules.null_corrupt_parquet_columns,
+                ),
             );
 
             // Collect any pending delete predicate from any partitions and include them in
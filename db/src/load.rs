@@ -48,6 +48,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 ///
 /// **For now, if the catalog is broken, it will be wiped!**
 /// <https://github.com/influxdata/influxdb_iox/issues/1522>
+///
+/// If `null_corrupt_columns` is set, the loaded chunks read columns that
+/// fail to decode as nulls, see [`ParquetChunk::with_null_corrupt_columns`].
 pub async fn load_or_create_preserved_catalog(
     db_name: &str,
     iox_object_store: Arc<IoxObjectStore>,
@@ -55,6 +58,7 @@ pub async fn load_or_create_preserved_catalog(
     time_provider: Arc<dyn TimeProvider>,
     wipe_on_error: bool,
     skip_replay: bool,
+    null_corrupt_columns: bool,
 ) -> Result<(PreservedCatalog, Catalog, Option<ReplayPlan>)> {
     // first try to load existing catalogs
     match PreservedCatalog::load(
@@ -68,7 +72,8 @@ pub async fn load_or_create_preserved_catalog(
             Arc::clone(&metric_registry),
             Arc::clone(&time_provider),
             skip_replay,
-        ),
+        )
+        .with_null_corrupt_columns(null_corrupt_columns),
     )
     .await
     {
@@ -161,6 +166,7 @@ struct Loader {
     catalog: Catalog,
     planner: Option<ReplayPlanner>,
     metric_registry: Arc<metric::Registry>,
+    null_corrupt_columns: bool,
 }
 
 impl Loader {
@@ -176,8 +182,15 @@ impl Loader {
             catalog,
             planner: (!skip_replay).then(ReplayPlanner::new),
             metric_registry: Arc::new(Default::default()),
+            null_corrupt_columns: false,
         }
     }
+
+    /// Read columns of the loaded chunks that fail to decode as nulls
+    fn with_null_corrupt_columns(mut self, null_corrupt_columns: bool) -> Self {
+        self.null_corrupt_columns = null_corrupt_columns;
+        self
+    }
 }
 
 impl CatalogState for Loader {
@@ -223,7 +236,8 @@ impl CatalogState for Loader {
             Arc::clone(&iox_md.partition_key),
             metrics,
         )
-        .context(ChunkCreationFailedSnafu { path: &info.path })?;
+        .context(ChunkCreationFailedSnafu { path: &info.path })?
+        .with_null_corrupt_columns(self.null_corrupt_columns);
         let parquet_chunk = Arc::new(parquet_chunk);
 
         let (partition, table_schema) = {
@@ -322,6 +336,7 @@ mod tests {
         interface::CheckpointData,
         test_helpers::{assert_catalog_state_implementation, new_empty},
     };
+    use parquet_file::test_utils::generator::ChunkGenerator;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -348,6 +363,7 @@ mod tests {
             time_provider,
             true,
             false,
+            false,
         )
         .await
         .unwrap();
@@ -367,4 +383,38 @@ mod tests {
         );
         assert_catalog_state_implementation(loader, checkpoint_data_from_loader).await;
     }
+
+    #[tokio::test]
+    async fn test_null_corrupt_columns() {
+        let mut generator = ChunkGenerator::new().await;
+        let (chunk, iox_md) = generator.generate().await.unwrap();
+
+        for null_corrupt_columns in [false, true] {
+            let mut loader = Loader::new(
+                "db1",
+                Default::default(),
+                Arc::new(time::SystemProvider::new()),
+                false,
+            )
+            .with_null_corrupt_columns(null_corrupt_columns);
+            loader
+                .add(
+                    Arc::clone(generator.store()),
+                    CatalogParquetInfo::from_chunk(&chunk),
+                )
+                .unwrap();
+
+            let (loaded, _) = loader
+                .catalog
+                .chunk(&iox_md.table_name, &iox_md.partition_key, iox_md.chunk_id)
+                .unwrap();
+            let loaded = loaded.read();
+            match loaded.stage() {
+                ChunkStage::Persisted { parquet, .. } => {
+                    assert_eq!(parquet.null_corrupt_columns(), null_corrupt_columns)
+                }
+                stage => panic!("unexpected chunk stage: {:?}", stage),
+            }
+        }
+    }
 }
@@ -6,6 +6,7 @@ use std::{
     time::Duration,
 };
 
+use observability_deps::tracing::warn;
 use parking_lot::Mutex;
 use time::{Time, TimeProvider};
 
@@ -50,10 +51,11 @@ impl QueryLogEntry {
         }
     }
 
-    pub fn set_completed(&self, now: Time) {
+    pub fn set_completed(&self, now: Time) -> Duration {
         let dur = now - self.issue_time;
         self.query_completed_duration
             .store(dur.as_nanos() as i64, atomic::Ordering::Relaxed);
+        dur
     }
 }
 
@@ -64,6 +66,7 @@ pub struct QueryLog {
     log: Mutex<VecDeque<Arc<QueryLogEntry>>>,
     max_size: usize,
     time_provider: Arc<dyn TimeProvider>,
+    slow_query_threshold: Option<Duration>,
 }
 
 impl QueryLog {
@@ -74,6 +77,15 @@ impl QueryLog {
             log: Mutex::new(VecDeque::with_capacity(max_size)),
             max_size,
             time_provider,
+            slow_query_threshold: None,
+        }
+    }
+
+    /// Log queries taking longer than `threshold` to complete at warn level
+    pub fn with_slow_query_threshold(self, threshold: Option<Duration>) -> Self {
+        Self {
+            slow_query_threshold: threshold,
+            ..self
         }
     }
 
@@ -108,14 +120,28 @@ impl QueryLog {
         log.clone()
     }
 
-    /// Marks the provided query entry as completed using the current time.
-    pub fn set_completed(&self, entry: Arc<QueryLogEntry>) {
-        entry.set_completed(self.time_provider.now())
+    /// Marks the provided query entry as completed using the current time,
+    /// logging it if it exceeded the slow query threshold.
+    ///
+    /// `chunks` is the number of chunks the query read, if known.
+    pub fn set_completed(&self, entry: Arc<QueryLogEntry>, chunks: Option<u64>) {
+        let duration = entry.set_completed(self.time_provider.now());
+
+        if matches!(self.slow_query_threshold, Some(threshold) if duration > threshold) {
+            warn!(
+                query_type=%entry.query_type,
+                query_text=%entry.query_text,
+                chunks=?chunks,
+                duration_ms=duration.as_millis() as u64,
+                "slow query"
+            );
+        }
     }
 }
 
 #[cfg(test)]
 mod test_super {
+    use test_helpers::tracing::TracingCapture;
     use time::MockProvider;
 
     use super::*;
@@ -147,4 +173,46 @@ mod test_super {
             Some(Duration::from_millis(200))
         );
     }
+
+    #[test]
+    fn test_slow_query_logged() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100)));
+        let query_log = QueryLog::new(10, Arc::clone(&time_provider) as Arc<dyn TimeProvider>)
+            .with_slow_query_threshold(Some(Duration::from_secs(1)));
+        let capture = TracingCapture::new();
+
+        // a query completing within the threshold is not logged
+        let entry = query_log.push("sql", "SELECT 1");
+        time_provider.inc(Duration::from_millis(500));
+        query_log.set_completed(entry, Some(1));
+        assert!(!capture.to_string().contains("slow query"));
+
+        // an artificially slow query is
+        let entry = query_log.push("read_filter", "{\"range\":{}}");
+        time_provider.inc(Duration::from_millis(2500));
+        query_log.set_completed(entry, Some(3));
+
+        let logs = capture.to_string();
+        let line = logs
+            .lines()
+            .find(|l| l.contains("slow query"))
+            .unwrap_or_else(|| panic!("no slow query log in:\n{}", logs));
+        assert!(line.contains("query_type = read_filter"), "{}", line);
+        assert!(line.contains("query_text = {\"range\":{}}"), "{}", line);
+        assert!(line.contains("chunks = Some(3)"), "{}", line);
+        assert!(line.contains("duration_ms = 2500"), "{}", line);
+        assert_eq!(logs.matches("slow query").count(), 1, "{}", logs);
+    }
+
+    #[test]
+    fn test_slow_query_threshold_disabled() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100)));
+        let query_log = QueryLog::new(10, Arc::clone(&time_provider) as Arc<dyn TimeProvider>);
+        let capture = TracingCapture::new();
+
+        let entry = query_log.push("sql", "SELECT 1");
+        time_provider.inc(Duration::from_secs(3600));
+        query_log.set_completed(entry, None);
+        assert!(!capture.to_string().contains("slow query"));
+    }
 }
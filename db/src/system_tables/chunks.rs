@@ -1,6 +1,6 @@
 use crate::{catalog::Catalog, system_tables::IoxSystemTable};
 use arrow::{
-    array::{StringArray, TimestampNanosecondArray, UInt32Array, UInt64Array},
+    array::{StringArray, TimestampNanosecondArray, UInt64Array},
     datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
     error::Result,
     record_batch::RecordBatch,
@@ -50,7 +50,7 @@ fn chunk_summaries_schema() -> SchemaRef {
         Field::new("time_of_last_access", ts.clone(), true),
         Field::new("time_of_first_write", ts.clone(), false),
         Field::new("time_of_last_write", ts, false),
-        Field::new("order", DataType::UInt32, false),
+        Field::new("order", DataType::UInt64, false),
     ]))
 }
 
@@ -114,7 +114,7 @@ fn from_chunk_summaries(schema: SchemaRef, chunks: Vec<ChunkSummary>) -> Result<
     let order = chunks
         .iter()
         .map(|c| Some(c.order.get()))
-        .collect::<UInt32Array>();
+        .collect::<UInt64Array>();
 
     RecordBatch::try_new(
         schema,
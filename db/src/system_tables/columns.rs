@@ -1,5 +1,4 @@
 use crate::{catalog::Catalog, system_tables::IoxSystemTable};
-use arrow::array::UInt32Array;
 use arrow::{
     array::{ArrayRef, StringArray, StringBuilder, UInt64Array},
     datatypes::{DataType, Field, Schema, SchemaRef},
@@ -131,7 +130,7 @@ fn chunk_columns_schema() -> SchemaRef {
         Field::new("min_value", DataType::Utf8, true),
         Field::new("max_value", DataType::Utf8, true),
         Field::new("memory_bytes", DataType::UInt64, true),
-        Field::new("chunk_order", DataType::UInt32, false),
+        Field::new("chunk_order", DataType::UInt64, false),
     ]))
 }
 
@@ -215,7 +214,7 @@ fn assemble_chunk_columns(
     let order = rows
         .iter()
         .map(|each| Some(each.chunk_summary.inner.order.get()))
-        .collect::<UInt32Array>();
+        .collect::<UInt64Array>();
 
     // handle memory bytes specially to avoid having to search for
     // each column in ColumnSummary
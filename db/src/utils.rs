@@ -40,6 +40,8 @@ pub struct TestDbBuilder {
     lifecycle_rules: LifecycleRules,
     partition_template: PartitionTemplate,
     time_provider: Arc<dyn TimeProvider>,
+    disable_pruning: bool,
+    chunk_read_metrics: bool,
 }
 
 impl Default for TestDbBuilder {
@@ -61,6 +63,8 @@ impl Default for TestDbBuilder {
                 parts: vec![TemplatePart::TimeFormat("%Y-%m-%dT%H".to_string())],
             },
             time_provider: Arc::new(time::SystemProvider::new()),
+            disable_pruning: false,
+            chunk_read_metrics: false,
         }
     }
 }
@@ -91,6 +95,11 @@ impl TestDbBuilder {
         let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
             num_threads: 1,
             target_query_partitions: 4,
+            scan_parallelism: None,
+            slow_query_threshold: None,
+            target_batch_size: None,
+            disable_pruning: self.disable_pruning,
+            chunk_read_metrics: self.chunk_read_metrics,
         }));
 
         let metric_registry = Arc::new(metric::Registry::new());
@@ -102,6 +111,7 @@ impl TestDbBuilder {
             Arc::clone(&time_provider),
             false,
             false,
+            false,
         )
         .await
         .unwrap();
@@ -168,6 +178,16 @@ impl TestDbBuilder {
         self.time_provider = time_provider;
         self
     }
+
+    pub fn disable_pruning(mut self, disable_pruning: bool) -> Self {
+        self.disable_pruning = disable_pruning;
+        self
+    }
+
+    pub fn chunk_read_metrics(mut self, chunk_read_metrics: bool) -> Self {
+        self.chunk_read_metrics = chunk_read_metrics;
+        self
+    }
 }
 
 /// Used for testing: create a Database with a local store
@@ -39,6 +39,12 @@ impl From<LifecycleRules> for management::LifecycleRules {
                 .parquet_cache_limit
                 .map(|v| v.get())
                 .unwrap_or_default(),
+            persist_tag_bloom_filters: config.persist_tag_bloom_filters,
+            null_corrupt_parquet_columns: config.null_corrupt_parquet_columns,
+            retention_period_seconds: config
+                .retention_period_seconds
+                .map(|v| v.get())
+                .unwrap_or_default(),
         }
     }
 }
@@ -103,6 +109,9 @@ impl TryFrom<management::LifecycleRules> for LifecycleRules {
             mub_row_threshold: NonZeroUsize::new(proto.mub_row_threshold as usize)
                 .unwrap_or_else(|| NonZeroUsize::new(DEFAULT_MUB_ROW_THRESHOLD).unwrap()),
             parquet_cache_limit: NonZeroU64::new(proto.parquet_cache_limit),
+            persist_tag_bloom_filters: proto.persist_tag_bloom_filters,
+            null_corrupt_parquet_columns: proto.null_corrupt_parquet_columns,
+            retention_period_seconds: NonZeroU64::new(proto.retention_period_seconds),
         })
     }
 }
@@ -148,6 +157,9 @@ mod tests {
             persist_age_threshold_seconds: 60,
             mub_row_threshold: 3454,
             parquet_cache_limit: 10,
+            persist_tag_bloom_filters: true,
+            null_corrupt_parquet_columns: true,
+            retention_period_seconds: 3600,
         };
 
         let config: LifecycleRules = protobuf.clone().try_into().unwrap();
@@ -194,6 +206,32 @@ mod tests {
             protobuf.parquet_cache_limit
         );
         assert_eq!(back.parquet_cache_limit, protobuf.parquet_cache_limit);
+        assert_eq!(
+            config.persist_tag_bloom_filters,
+            protobuf.persist_tag_bloom_filters
+        );
+        assert_eq!(
+            back.persist_tag_bloom_filters,
+            protobuf.persist_tag_bloom_filters
+        );
+        assert_eq!(
+            config.null_corrupt_parquet_columns,
+            protobuf.null_corrupt_parquet_columns
+        );
+        assert_eq!(
+            back.null_corrupt_parquet_columns,
+            protobuf.null_corrupt_parquet_columns
+        );
+        assert_eq!(
+            config.retention_period(),
+            Some(std::time::Duration::from_secs(
+                protobuf.retention_period_seconds
+            ))
+        );
+        assert_eq!(
+            back.retention_period_seconds,
+            protobuf.retention_period_seconds
+        );
 
         protobuf.late_arrive_window_seconds = 20;
         protobuf.persist_age_threshold_seconds = 4;
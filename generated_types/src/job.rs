@@ -24,6 +24,7 @@ impl management::operation_metadata::Job {
                 db_name,
                 ..
             }) => db_name,
+            Self::Downsample(management::Downsample { db_name, .. }) => db_name,
         }
     }
 }
@@ -94,6 +95,17 @@ impl From<Job> for management::operation_metadata::Job {
                     db_name: db_name.to_string(),
                 })
             }
+            Job::Downsample {
+                db_name,
+                source_table,
+                target_db_name,
+                target_table,
+            } => Self::Downsample(management::Downsample {
+                db_name: db_name.to_string(),
+                source_table: source_table.to_string(),
+                target_db_name: target_db_name.to_string(),
+                target_table: target_table.to_string(),
+            }),
         }
     }
 }
@@ -8,3 +8,14 @@ pub mod run_config;
 pub mod server_id;
 pub mod socket_addr;
 pub mod write_buffer;
+
+/// The value rendered in place of a secret when displaying a config.
+pub const REDACTED: &str = "<redacted>";
+
+/// Render `secret` as [`REDACTED`] if it is set, or null otherwise.
+pub(crate) fn redact(secret: &Option<String>) -> serde_json::Value {
+    match secret {
+        Some(_) => REDACTED.into(),
+        None => serde_json::Value::Null,
+    }
+}
@@ -4,9 +4,12 @@ use std::{convert::TryFrom, fs, num::NonZeroUsize, path::PathBuf, time::Duration
 use futures::TryStreamExt;
 use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi, ThrottleConfig};
 use observability_deps::tracing::{info, warn};
+use serde_json::json;
 use snafu::{ResultExt, Snafu};
 use uuid::Uuid;
 
+use super::redact;
+
 #[derive(Debug, Snafu)]
 pub enum ParseError {
     #[snafu(display("Unable to create database directory {:?}: {}", path, source))]
@@ -180,6 +183,26 @@ Possible values (case insensitive):
     pub object_store_connection_limit: NonZeroUsize,
 }
 
+impl ObjectStoreConfig {
+    /// Render this config as JSON, with credentials redacted.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "object_store": self.object_store.map(|t| format!("{:?}", t)),
+            "bucket": self.bucket,
+            "database_directory": self.database_directory,
+            "aws_access_key_id": redact(&self.aws_access_key_id),
+            "aws_secret_access_key": redact(&self.aws_secret_access_key),
+            "aws_default_region": self.aws_default_region,
+            "aws_endpoint": self.aws_endpoint,
+            "aws_session_token": redact(&self.aws_session_token),
+            "google_service_account": self.google_service_account,
+            "azure_storage_account": self.azure_storage_account,
+            "azure_storage_access_key": redact(&self.azure_storage_access_key),
+            "object_store_connection_limit": self.object_store_connection_limit,
+        })
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, clap::ArgEnum)]
 pub enum ObjectStoreType {
     Memory,
@@ -1,9 +1,13 @@
+use std::time::Duration;
+
+use serde_json::json;
 use trace_exporters::TracingConfig;
 use trogging::cli::LoggingConfig;
 
 use crate::{
     clap_blocks::{
-        object_store::ObjectStoreConfig, server_id::ServerIdConfig, socket_addr::SocketAddr,
+        boolean_flag::BooleanFlag, object_store::ObjectStoreConfig, server_id::ServerIdConfig,
+        socket_addr::SocketAddr,
     },
     influxdb_ioxd::serving_readiness::ServingReadinessState,
 };
@@ -62,7 +66,47 @@ pub struct RunConfig {
     )]
     pub max_http_request_size: usize,
 
+    /// Maximum time allowed to receive a HTTP request body, after which the
+    /// request is rejected with a "408 Request Timeout".
+    #[clap(
+        long = "--http-request-timeout",
+        env = "INFLUXDB_IOX_HTTP_REQUEST_TIMEOUT",
+        default_value = "30s",
+        parse(try_from_str = humantime::parse_duration)
+    )]
+    pub http_request_timeout: Duration,
+
+    /// Expose the CPU and heap profiling endpoints under "/debug/pprof", and
+    /// the CPU profile of the given number of seconds under
+    /// "/debug/profile?seconds=<n>".
+    ///
+    /// Profiling must also be compiled in using the "pprof" and/or "heappy"
+    /// features.
+    #[clap(
+        long = "--debug-profiling",
+        env = "INFLUXDB_IOX_DEBUG_PROFILING",
+        default_value = "no"
+    )]
+    pub debug_profiling: BooleanFlag,
+
     /// object store config
     #[clap(flatten)]
     pub(crate) object_store_config: ObjectStoreConfig,
 }
+
+impl RunConfig {
+    /// Render this config as JSON, with credentials redacted.
+    ///
+    /// The logging and tracing options are not included.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "http_bind_address": self.http_bind_address.to_string(),
+            "grpc_bind_address": self.grpc_bind_address.to_string(),
+            "initial_serving_state": format!("{:?}", self.initial_serving_state),
+            "max_http_request_size": self.max_http_request_size,
+            "http_request_timeout": humantime::format_duration(self.http_request_timeout).to_string(),
+            "debug_profiling": bool::from(self.debug_profiling),
+            "object_store": self.object_store_config.to_json(),
+        })
+    }
+}
@@ -3,6 +3,7 @@
 use crate::clap_blocks::catalog_dsn::CatalogDsnConfig;
 use thiserror::Error;
 
+mod namespace;
 mod topic;
 
 #[allow(clippy::enum_variant_names)]
@@ -14,11 +15,17 @@ pub enum Error {
     #[error("Error in topic subcommand: {0}")]
     Topic(#[from] topic::Error),
 
+    #[error("Error in namespace subcommand: {0}")]
+    Namespace(#[from] namespace::Error),
+
     #[error("Client error: {0}")]
     ClientError(#[from] influxdb_iox_client::error::Error),
 
     #[error("Catalog error: {0}")]
     Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] crate::clap_blocks::catalog_dsn::Error),
 }
 
 /// Various commands for catalog manipulation
@@ -43,6 +50,9 @@ enum Command {
 
     /// Manage kafka topic
     Topic(topic::Config),
+
+    /// Manage namespaces
+    Namespace(namespace::Config),
 }
 
 pub async fn command(config: Config) -> Result<(), Error> {
@@ -55,6 +65,9 @@ pub async fn command(config: Config) -> Result<(), Error> {
         Command::Topic(config) => {
             topic::command(config).await?;
         }
+        Command::Namespace(config) => {
+            namespace::command(config).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,91 @@
+//! This module implements the `catalog namespace` CLI subcommand
+
+use iox_catalog::interface::INFINITE_RETENTION;
+use thiserror::Error;
+
+use crate::clap_blocks::catalog_dsn::CatalogDsnConfig;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error updating catalog: {0}")]
+    UpdateCatalogError(#[from] iox_catalog::interface::Error),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsnError(#[from] crate::clap_blocks::catalog_dsn::Error),
+
+    #[error("Invalid retention: {0}")]
+    InvalidRetentionError(#[from] humantime::DurationError),
+}
+
+/// Manage IOx namespaces
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Create a namespace
+#[derive(Debug, clap::Parser)]
+struct Create {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    /// The name of the namespace
+    name: String,
+
+    /// How long data of the namespace is retained, such as "30d" or "inf".
+    ///
+    /// If not set, the `--catalog-default-retention` applies.
+    #[clap(long = "--retention")]
+    retention: Option<String>,
+
+    /// The kafka topic the writes of the namespace are sent to
+    #[clap(long = "--kafka-topic", default_value = "iox-shared")]
+    kafka_topic: String,
+
+    /// The query pool serving queries of the namespace
+    #[clap(long = "--query-pool", default_value = "iox-shared")]
+    query_pool: String,
+}
+
+/// All possible subcommands for namespace
+#[derive(Debug, clap::Parser)]
+enum Command {
+    Create(Create),
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    match config.command {
+        Command::Create(create) => {
+            // Rejected here, as the ingester fails to buffer writes of a namespace with an
+            // unparsable retention
+            if let Some(retention) = create.retention.as_deref() {
+                if retention != INFINITE_RETENTION {
+                    humantime::parse_duration(retention)?;
+                }
+            }
+
+            let catalog = create.catalog_dsn.get_catalog("cli").await?;
+            let topic = catalog
+                .kafka_topics()
+                .create_or_get(&create.kafka_topic)
+                .await?;
+            let query_pool = catalog
+                .query_pools()
+                .create_or_get(&create.query_pool)
+                .await?;
+            let namespace = catalog
+                .namespaces()
+                .create(
+                    &create.name,
+                    create.retention.as_deref(),
+                    topic.id,
+                    query_pool.id,
+                )
+                .await?;
+            println!("{}", namespace.id);
+            Ok(())
+        }
+    }
+}
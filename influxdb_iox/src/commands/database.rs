@@ -114,6 +114,21 @@ struct Create {
     /// value of zero disables Parquet file caching.
     #[clap(long, default_value = "0")]
     parquet_cache_limit: u64,
+
+    /// Build bloom filters over the tag values of persisted chunks, used to
+    /// prune chunks for `tag = 'value'` predicates.
+    #[clap(long)]
+    persist_tag_bloom_filters: bool,
+
+    /// Read columns of persisted chunks that fail to decode as nulls instead
+    /// of failing the query.
+    #[clap(long)]
+    null_corrupt_parquet_columns: bool,
+
+    /// Exclude data older than this many seconds from queries. A value of
+    /// zero keeps data forever.
+    #[clap(long, default_value = "0")]
+    retention_period_seconds: u64,
 }
 
 /// Get list of databases
@@ -249,6 +264,9 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
                     persist_age_threshold_seconds: command.persist_age_threshold_seconds,
                     mub_row_threshold: command.mub_row_threshold,
                     parquet_cache_limit: command.parquet_cache_limit,
+                    persist_tag_bloom_filters: command.persist_tag_bloom_filters,
+                    null_corrupt_parquet_columns: command.null_corrupt_parquet_columns,
+                    retention_period_seconds: command.retention_period_seconds,
                 }),
 
                 // Default to hourly partitions
@@ -2,11 +2,15 @@ use snafu::{ResultExt, Snafu};
 
 mod dump_catalog;
 mod print_cpu;
+mod replay_write_buffer;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Error in dump-catalog subcommand: {}", source))]
     DumpCatalogError { source: dump_catalog::Error },
+
+    #[snafu(display("Error in replay-write-buffer subcommand: {}", source))]
+    ReplayWriteBufferError { source: replay_write_buffer::Error },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -25,6 +29,10 @@ enum Command {
 
     /// Prints what CPU features are used by the compiler by default.
     PrintCpu,
+
+    /// Print the operations of a sequence number range of a write buffer
+    /// partition, without persisting them.
+    ReplayWriteBuffer(Box<replay_write_buffer::Config>),
 }
 
 pub async fn command(config: Config) -> Result<()> {
@@ -36,5 +44,10 @@ pub async fn command(config: Config) -> Result<()> {
             print_cpu::main();
             Ok(())
         }
+        Command::ReplayWriteBuffer(replay_write_buffer) => {
+            replay_write_buffer::command(*replay_write_buffer)
+                .await
+                .context(ReplayWriteBufferSnafu)
+        }
     }
 }
@@ -0,0 +1,441 @@
+use crate::clap_blocks::write_buffer::WriteBufferConfig;
+use arrow::util::pretty::pretty_format_batches;
+use data_types::write_buffer::WriteBufferConnection;
+use dml::DmlOperation;
+use futures::StreamExt;
+use schema::selection::Selection;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::{fs::File, io::Write, ops::Range, path::PathBuf, sync::Arc, time::Duration};
+use time::SystemProvider;
+use write_buffer::{
+    config::WriteBufferConfigFactory,
+    core::{WriteBufferError, WriteBufferReading},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Empty sequence number range: {} is not before {}", start, end))]
+    EmptyRange { start: u64, end: u64 },
+
+    #[snafu(display("Cannot connect to write buffer: {}", source))]
+    Connect { source: WriteBufferError },
+
+    #[snafu(display(
+        "Cannot seek partition {} to sequence number {}: {}",
+        partition,
+        sequence_number,
+        source
+    ))]
+    Seek {
+        partition: u32,
+        sequence_number: u64,
+        source: WriteBufferError,
+    },
+
+    #[snafu(display("Write buffer has no partition {}", partition))]
+    UnknownPartition { partition: u32 },
+
+    #[snafu(display("Cannot read from write buffer: {}", source))]
+    Read { source: WriteBufferError },
+
+    #[snafu(display("Operation read from write buffer has no sequence number"))]
+    Unsequenced,
+
+    #[snafu(display("Cannot convert table {} to arrow: {}", table, source))]
+    ConvertTable {
+        table: String,
+        source: mutable_batch::Error,
+    },
+
+    #[snafu(display("Cannot format table {}: {}", table, source))]
+    FormatTable {
+        table: String,
+        source: arrow::error::ArrowError,
+    },
+
+    #[snafu(display("Cannot create output file {:?}: {}", path, source))]
+    CreateOutput {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Cannot write output: {}", source))]
+    WriteOutput { source: std::io::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Replay a range of a write buffer partition, printing the decoded
+/// operations.
+///
+/// The operations are only printed, nothing is persisted.
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    // write buffer config
+    #[clap(flatten)]
+    write_buffer_config: WriteBufferConfig,
+
+    /// The write buffer partition to replay.
+    #[clap(long = "--partition", default_value = "0")]
+    partition: u32,
+
+    /// The sequence number of the first operation to replay.
+    #[clap(long = "--start")]
+    start: u64,
+
+    /// The sequence number to stop replaying at (exclusive).
+    ///
+    /// Replaying also stops at the end of the partition, it never waits for
+    /// new operations.
+    #[clap(long = "--end")]
+    end: u64,
+
+    /// Stop replaying once no operation was read for this long.
+    ///
+    /// Records that do not decode to an operation, like Kafka control
+    /// records, are skipped by the consumer without returning anything, so
+    /// the end of the partition may not be recognized from the operations
+    /// read alone.
+    #[clap(
+        long = "--idle-timeout",
+        default_value = "5s",
+        parse(try_from_str = humantime::parse_duration)
+    )]
+    idle_timeout: Duration,
+
+    /// Write the operations to this file instead of stdout.
+    #[clap(long = "--output")]
+    output: Option<PathBuf>,
+}
+
+pub async fn command(config: Config) -> Result<()> {
+    let factory = WriteBufferConfigFactory::new(
+        Arc::new(SystemProvider::default()),
+        Arc::new(metric::Registry::default()),
+    );
+    // Never create the write buffer, there is nothing to replay from a new one
+    let connection = WriteBufferConnection {
+        type_: config.write_buffer_config.type_.clone(),
+        connection: config.write_buffer_config.connection_string.clone(),
+        connection_config: Default::default(),
+        creation_config: None,
+    };
+    let mut write_buffer = factory
+        .new_config_read(&config.write_buffer_config.topic, None, &connection)
+        .await
+        .context(ConnectSnafu)?;
+
+    let range = config.start..config.end;
+    match config.output {
+        Some(path) => {
+            let mut file = File::create(&path).context(CreateOutputSnafu { path })?;
+            replay(
+                write_buffer.as_mut(),
+                config.partition,
+                range,
+                config.idle_timeout,
+                &mut file,
+            )
+            .await?;
+        }
+        None => {
+            let mut stdout = std::io::stdout();
+            replay(
+                write_buffer.as_mut(),
+                config.partition,
+                range,
+                config.idle_timeout,
+                &mut stdout,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the operations of `partition` with a sequence number in `range` to
+/// `out`, returning the number of operations written.
+///
+/// Replaying stops at the end of `range` or of the partition, whichever comes
+/// first, or once no operation was read for `idle_timeout`.
+async fn replay<W: Write + Send>(
+    write_buffer: &mut dyn WriteBufferReading,
+    partition: u32,
+    range: Range<u64>,
+    idle_timeout: Duration,
+    out: &mut W,
+) -> Result<usize> {
+    ensure!(
+        range.start < range.end,
+        EmptyRangeSnafu {
+            start: range.start,
+            end: range.end
+        }
+    );
+
+    write_buffer
+        .seek(partition, range.start)
+        .await
+        .context(SeekSnafu {
+            partition,
+            sequence_number: range.start,
+        })?;
+
+    let mut streams = write_buffer.streams();
+    let mut stream = streams
+        .remove(&partition)
+        .context(UnknownPartitionSnafu { partition })?;
+
+    // Stop at the end of the partition rather than waiting for new operations
+    let high_watermark = (stream.fetch_high_watermark)().await.context(ReadSnafu)?;
+    let end = range.end.min(high_watermark);
+    if range.start >= end {
+        return Ok(0);
+    }
+
+    let mut replayed = 0;
+    loop {
+        // The records up to the end may not decode to operations, in which
+        // case the stream stays pending although the end was reached
+        let op = match tokio::time::timeout(idle_timeout, stream.stream.next()).await {
+            Ok(Some(op)) => op.context(ReadSnafu)?,
+            Ok(None) | Err(_) => break,
+        };
+        let sequence_number = op.meta().sequence().context(UnsequencedSnafu)?.number;
+        // The range may end in a hole of the sequence
+        if sequence_number >= end {
+            break;
+        }
+
+        write_operation(out, sequence_number, &op)?;
+        replayed += 1;
+
+        if sequence_number + 1 >= end {
+            break;
+        }
+    }
+
+    Ok(replayed)
+}
+
+/// Write a human readable representation of `op` to `out`.
+fn write_operation<W: Write>(out: &mut W, sequence_number: u64, op: &DmlOperation) -> Result<()> {
+    let producer_time = op
+        .meta()
+        .producer_ts()
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| "-".to_string());
+    writeln!(
+        out,
+        "sequence_number: {}, producer_time: {}, namespace: {}",
+        sequence_number,
+        producer_time,
+        op.namespace()
+    )
+    .context(WriteOutputSnafu)?;
+
+    match op {
+        DmlOperation::Write(write) => {
+            let mut tables: Vec<_> = write.tables().collect();
+            tables.sort_unstable_by_key(|(name, _)| *name);
+
+            for (table, batch) in tables {
+                let record_batch = batch
+                    .to_arrow(Selection::All)
+                    .context(ConvertTableSnafu { table })?;
+                let formatted =
+                    pretty_format_batches(&[record_batch]).context(FormatTableSnafu { table })?;
+                writeln!(
+                    out,
+                    "write to table {} ({} rows):\n{}",
+                    table,
+                    batch.rows(),
+                    formatted
+                )
+                .context(WriteOutputSnafu)?;
+            }
+        }
+        DmlOperation::Delete(delete) => {
+            writeln!(
+                out,
+                "delete from table {}: {:?}",
+                delete.table_name().unwrap_or("<all>"),
+                delete.predicate()
+            )
+            .context(WriteOutputSnafu)?;
+        }
+    }
+
+    writeln!(out).context(WriteOutputSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use data_types::{sequence::Sequence, write_buffer::WriteBufferCreationConfig};
+    use std::{collections::BTreeMap, num::NonZeroU32};
+    use time::Time;
+    use write_buffer::{
+        core::{WriteBufferWriting, WriteStream},
+        file::{FileBufferConsumer, FileBufferProducer},
+        mock::{MockBufferForReading, MockBufferSharedState},
+    };
+
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn test_replay_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let creation_config = WriteBufferCreationConfig {
+            n_sequencers: NonZeroU32::new(1).unwrap(),
+            ..Default::default()
+        };
+
+        let producer = FileBufferProducer::new(
+            dir.path(),
+            "my-topic",
+            Some(&creation_config),
+            Arc::new(SystemProvider::default()),
+        )
+        .await
+        .unwrap();
+        for lp in [
+            "cpu,host=a usage=1 10",
+            "cpu,host=b usage=2 20\nmem,host=b free=3i 20",
+            "cpu,host=c usage=4 30",
+        ] {
+            producer.store_lp(0, lp, 0).await.unwrap();
+        }
+
+        let mut consumer = FileBufferConsumer::new(dir.path(), "my-topic", None, None)
+            .await
+            .unwrap();
+
+        let mut out = vec![];
+        let replayed = replay(&mut consumer, 0, 1..3, IDLE_TIMEOUT, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 2);
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains("sequence_number: 0,"), "{}", out);
+        assert!(out.contains("sequence_number: 1,"), "{}", out);
+        assert!(out.contains("sequence_number: 2,"), "{}", out);
+        assert!(out.contains("write to table mem (1 rows)"), "{}", out);
+        assert!(
+            out.find("sequence_number: 1,").unwrap() < out.find("sequence_number: 2,").unwrap()
+        );
+
+        // A range beyond the end of the partition does not wait for new
+        // operations
+        let mut out = vec![];
+        let replayed = replay(&mut consumer, 0, 2..100, IDLE_TIMEOUT, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 1);
+
+        let mut out = vec![];
+        let replayed = replay(&mut consumer, 0, 3..100, IDLE_TIMEOUT, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 0);
+        assert!(out.is_empty());
+
+        let err = replay(&mut consumer, 0, 2..2, IDLE_TIMEOUT, &mut Vec::<u8>::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::EmptyRange { .. }), "{}", err);
+    }
+
+    /// Reads a mocked write buffer, without returning the operations with the
+    /// sequence numbers in `skipped`, like records that do not decode to an
+    /// operation
+    #[derive(Debug)]
+    struct SkippingReader {
+        inner: MockBufferForReading,
+        skipped: Range<u64>,
+    }
+
+    #[async_trait]
+    impl WriteBufferReading for SkippingReader {
+        fn streams(&mut self) -> BTreeMap<u32, WriteStream<'_>> {
+            let skipped = self.skipped.clone();
+            self.inner
+                .streams()
+                .into_iter()
+                .map(|(partition, stream)| {
+                    let skipped = skipped.clone();
+                    let filtered = stream
+                        .stream
+                        .filter(move |op| {
+                            let skip = match op {
+                                Ok(op) => skipped.contains(&op.meta().sequence().unwrap().number),
+                                Err(_) => false,
+                            };
+                            futures::future::ready(!skip)
+                        })
+                        .boxed();
+                    let stream = WriteStream {
+                        stream: filtered,
+                        fetch_high_watermark: stream.fetch_high_watermark,
+                    };
+                    (partition, stream)
+                })
+                .collect()
+        }
+
+        async fn seek(
+            &mut self,
+            partition: u32,
+            sequence_number: u64,
+        ) -> Result<(), WriteBufferError> {
+            self.inner.seek(partition, sequence_number).await
+        }
+
+        async fn sequence_number_at_timestamp(
+            &self,
+            partition: u32,
+            timestamp: Time,
+        ) -> Result<u64, WriteBufferError> {
+            self.inner
+                .sequence_number_at_timestamp(partition, timestamp)
+                .await
+        }
+
+        fn type_name(&self) -> &'static str {
+            "skipping"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_after_trailing_records_without_operations() {
+        let state = MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::new(1).unwrap());
+        for (sequence_number, lp) in [
+            (0, "cpu,host=a usage=1 10"),
+            (1, "cpu,host=b usage=2 20"),
+            (2, "cpu,host=c usage=3 30"),
+            (3, "cpu,host=d usage=4 40"),
+        ] {
+            state.push_lp(Sequence::new(0, sequence_number), lp);
+        }
+        let mut reader = SkippingReader {
+            inner: MockBufferForReading::new(state, None).unwrap(),
+            skipped: 2..4,
+        };
+
+        // The records before the end of the partition return no operations, so
+        // replaying stops once it is idle
+        let mut out = vec![];
+        let replayed = replay(&mut reader, 0, 0..100, Duration::from_millis(100), &mut out)
+            .await
+            .unwrap();
+        assert_eq!(replayed, 2);
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("sequence_number: 0,"), "{}", out);
+        assert!(out.contains("sequence_number: 1,"), "{}", out);
+        assert!(!out.contains("sequence_number: 2,"), "{}", out);
+    }
+}
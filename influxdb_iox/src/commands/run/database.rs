@@ -1,6 +1,6 @@
 //! Implementation of command line option for running server
 
-use std::sync::Arc;
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 
 use crate::{
     clap_blocks::{boolean_flag::BooleanFlag, run_config::RunConfig},
@@ -61,6 +61,70 @@ pub struct Config {
     #[clap(long = "--num-worker-threads", env = "INFLUXDB_IOX_NUM_WORKER_THREADS")]
     pub num_worker_threads: Option<usize>,
 
+    /// The maximum number of chunks of a table read concurrently by a query.
+    ///
+    /// Lower values reduce the memory used by queries over tables with many
+    /// chunks, higher values may speed them up on fast storage.
+    ///
+    /// If not specified, all chunks of a table are read concurrently
+    #[clap(long = "--scan-parallelism", env = "INFLUXDB_IOX_SCAN_PARALLELISM")]
+    pub scan_parallelism: Option<NonZeroUsize>,
+
+    /// Log queries taking longer than this to complete, e.g. "2s".
+    ///
+    /// Slow queries are logged at warn level with their type, text and
+    /// duration. If not specified, no slow queries are logged
+    #[clap(
+        long = "--slow-query-threshold",
+        env = "INFLUXDB_IOX_SLOW_QUERY_THRESHOLD",
+        parse(try_from_str = humantime::parse_duration)
+    )]
+    pub slow_query_threshold: Option<Duration>,
+
+    /// The number of rows of the record batches streamed as query output.
+    ///
+    /// Query output is re-chunked into batches of this many rows, only the
+    /// last batch of a result may be smaller. If not specified, batches are
+    /// streamed with the size produced by the query plan
+    #[clap(long = "--query-batch-size", env = "INFLUXDB_IOX_QUERY_BATCH_SIZE")]
+    pub query_batch_size: Option<NonZeroUsize>,
+
+    /// The maximum number of chunks an InfluxRPC query may scan after
+    /// pruning.
+    ///
+    /// Queries that would scan more chunks fail instead, protecting the
+    /// server from queries of partitions with many small chunks that have not
+    /// been compacted yet. Requests may lower the limit using the
+    /// "iox-max-query-chunks" gRPC header. If not specified, queries may scan
+    /// any number of chunks
+    #[clap(long = "--max-query-chunks", env = "INFLUXDB_IOX_MAX_QUERY_CHUNKS")]
+    pub max_query_chunks: Option<usize>,
+
+    /// The maximum number of series an InfluxRPC read_filter, read_group or
+    /// read_window_aggregate query may produce.
+    ///
+    /// Queries producing more series fail instead. Requests may lower the
+    /// limit using the "iox-max-query-series" gRPC header. If not specified,
+    /// queries may produce any number of series
+    #[clap(long = "--max-query-series", env = "INFLUXDB_IOX_MAX_QUERY_SERIES")]
+    pub max_query_series: Option<usize>,
+
+    /// Keep all chunks of the queried tables rather than pruning those whose
+    /// metadata shows they cannot match the predicate.
+    ///
+    /// The predicate is still applied to the data, so results must be
+    /// identical with and without pruning. Only intended for debugging
+    /// pruning problems, as queries scan much more data
+    #[clap(long = "--disable-pruning", env = "INFLUXDB_IOX_DISABLE_PRUNING")]
+    pub disable_pruning: bool,
+
+    /// Record the latency, rows and bytes of the chunk reads of queries.
+    ///
+    /// The metrics are labelled with the database, table and chunk type.
+    /// Adds some overhead to every chunk read
+    #[clap(long = "--chunk-read-metrics", env = "INFLUXDB_IOX_CHUNK_READ_METRICS")]
+    pub chunk_read_metrics: bool,
+
     // TODO(marco): Remove once the database-run-mode (aka the `server` crate) cannot handle routing anymore and we're
     //              fully migrated to the new router code.
     /// When IOx nodes need to talk to remote peers they consult an internal remote address
@@ -95,11 +159,15 @@ pub async fn command(config: Config) -> Result<()> {
 
     let application = make_application(&config, common_state.trace_collector()).await?;
     let app_server = make_server(Arc::clone(&application), &config);
-    let server_type = Arc::new(DatabaseServerType::new(
-        Arc::clone(&application),
-        Arc::clone(&app_server),
-        &common_state,
-    ));
+    let server_type = Arc::new(
+        DatabaseServerType::new(
+            Arc::clone(&application),
+            Arc::clone(&app_server),
+            &common_state,
+        )
+        .with_max_query_chunks(config.max_query_chunks)
+        .with_max_query_series(config.max_query_series),
+    );
 
     Ok(influxdb_ioxd::main(common_state, server_type).await?)
 }
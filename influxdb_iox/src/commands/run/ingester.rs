@@ -2,7 +2,8 @@
 
 use crate::{
     clap_blocks::{
-        catalog_dsn::CatalogDsnConfig, run_config::RunConfig, write_buffer::WriteBufferConfig,
+        catalog_dsn::CatalogDsnConfig, redact, run_config::RunConfig,
+        write_buffer::WriteBufferConfig,
     },
     influxdb_ioxd::{
         self,
@@ -14,14 +15,20 @@ use crate::{
 };
 use data_types::write_buffer::WriteBufferConnection;
 use ingester::{
+    compact::CompactionInputSelection,
+    data::RejectOutOfOrder,
     handler::IngestHandlerImpl,
     server::{grpc::GrpcDelegate, http::HttpDelegate, IngesterServer},
+    wal::Wal,
 };
 use iox_catalog::interface::KafkaPartition;
 use object_store::ObjectStore;
 use observability_deps::tracing::*;
+use query::exec::Executor;
+use serde_json::json;
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
+use std::path::PathBuf;
 use std::sync::Arc;
 use thiserror::Error;
 use time::TimeProvider;
@@ -38,20 +45,34 @@ pub enum Error {
     #[error("Catalog error: {0}")]
     Catalog(#[from] iox_catalog::interface::Error),
 
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] crate::clap_blocks::catalog_dsn::Error),
+
     #[error("Kafka topic {0} not found in the catalog")]
     KafkaTopicNotFound(String),
 
     #[error("Cannot parse object store config: {0}")]
     ObjectStoreParsing(#[from] crate::clap_blocks::object_store::ParseError),
 
-    #[error("kafka_partition_range_start must be <= kafka_partition_range_end")]
-    KafkaRange,
+    #[error(
+        "write buffer partition range start ({start}) must be <= write buffer partition range end ({end})"
+    )]
+    KafkaRange { start: i32, end: i32 },
+
+    #[error("write buffer partition range start and end must be set together")]
+    KafkaRangePartial,
+
+    #[error("write buffer partition range must be set for write buffer type {0}")]
+    KafkaRangeRequired(String),
 
     #[error("sequencer record not found for partition {0}")]
     SequencerNotFound(KafkaPartition),
 
     #[error("error initializing write buffer {0}")]
     WriteBuffer(#[from] write_buffer::core::WriteBufferError),
+
+    #[error("error opening write-ahead log: {0}")]
+    Wal(#[from] ingester::wal::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -81,21 +102,148 @@ pub struct Config {
     pub(crate) write_buffer_config: WriteBufferConfig,
 
     /// Write buffer partition number to start (inclusive) range with
+    ///
+    /// If neither the start nor the end of the range is set, the partitions
+    /// of a file write buffer are discovered from its directory.
     #[clap(
         long = "--write-buffer-partition-range-start",
         env = "INFLUXDB_IOX_WRITE_BUFFER_PARTITION_RANGE_START"
     )]
-    pub write_buffer_partition_range_start: i32,
+    pub write_buffer_partition_range_start: Option<i32>,
 
     /// Write buffer partition number to end (inclusive) range with
     #[clap(
         long = "--write-buffer-partition-range-end",
         env = "INFLUXDB_IOX_WRITE_BUFFER_PARTITION_RANGE_END"
     )]
-    pub write_buffer_partition_range_end: i32,
+    pub write_buffer_partition_range_end: Option<i32>,
+
+    /// Drop the buffered rows matching a delete as soon as the delete arrives,
+    /// if the delete's time range covers all buffered data of a partition
+    #[clap(long = "--eager-deletes", env = "INFLUXDB_IOX_INGESTER_EAGER_DELETES")]
+    pub eager_deletes: bool,
+
+    /// Reject writes with a point older than the latest buffered point of
+    /// its series, for the given namespaces (`<namespace>`) or tables
+    /// (`<namespace>/<table>`). Writes to other tables may be out of order.
+    #[clap(
+        long = "--reject-out-of-order",
+        env = "INFLUXDB_IOX_INGESTER_REJECT_OUT_OF_ORDER",
+        use_delimiter = true
+    )]
+    pub reject_out_of_order: Vec<String>,
+
+    /// Directory of a local write-ahead log of the buffered writes and
+    /// deletes. On restart, the data buffered before is recovered from it
+    /// instead of being read from the write buffer again.
+    #[clap(long = "--wal-dir", env = "INFLUXDB_IOX_INGESTER_WAL_DIR")]
+    pub wal_dir: Option<PathBuf>,
+
+    /// Maximum number of partitions buffered per namespace and write buffer
+    /// partition. Before buffering a write that would exceed it, the
+    /// partitions with the oldest buffered data are persisted and dropped.
+    /// Unlimited if not set.
+    #[clap(
+        long = "--max-partitions-per-namespace",
+        env = "INFLUXDB_IOX_INGESTER_MAX_PARTITIONS_PER_NAMESPACE"
+    )]
+    pub max_partitions_per_namespace: Option<usize>,
+
+    /// Target size, in bytes, of the buffered data of a partition compacted
+    /// into each persisted file. A partition with more buffered data is
+    /// persisted as several files, each covering a contiguous range of its
+    /// writes. Unbounded if not set.
+    #[clap(
+        long = "--persist-target-bytes",
+        env = "INFLUXDB_IOX_INGESTER_PERSIST_TARGET_BYTES"
+    )]
+    pub persist_target_bytes: Option<u64>,
+
+    /// Number of threads compacting the data of the partitions being
+    /// persisted.
+    ///
+    /// If not specified, defaults to the number of cores on the system
+    #[clap(
+        long = "--num-persist-threads",
+        env = "INFLUXDB_IOX_INGESTER_NUM_PERSIST_THREADS"
+    )]
+    pub num_persist_threads: Option<usize>,
+
+    /// Serve the admin Flight actions (seek_to_timestamp, snapshot and
+    /// persist), which change the state of the ingester.
+    ///
+    /// Requests for the actions must carry this token in an
+    /// "authorization: Token <token>" gRPC metadata entry. If not set, the
+    /// actions are not served.
+    #[clap(long = "--admin-token", env = "INFLUXDB_IOX_INGESTER_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+}
+
+impl Config {
+    /// Render the resolved config as JSON, with secrets redacted.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "run_config": self.run_config.to_json(),
+            "catalog_dsn": self.catalog_dsn.to_json(),
+            "write_buffer_config": self.write_buffer_config.to_json(),
+            "write_buffer_partition_range_start": self.write_buffer_partition_range_start,
+            "write_buffer_partition_range_end": self.write_buffer_partition_range_end,
+            "eager_deletes": self.eager_deletes,
+            "reject_out_of_order": self.reject_out_of_order,
+            "wal_dir": self.wal_dir,
+            "max_partitions_per_namespace": self.max_partitions_per_namespace,
+            "persist_target_bytes": self.persist_target_bytes,
+            "num_persist_threads": self.num_persist_threads,
+            "admin_token": redact(&self.admin_token),
+        })
+    }
+}
+
+/// Return the namespaces and tables configured to reject out-of-order writes
+fn reject_out_of_order(config: &Config) -> RejectOutOfOrder {
+    config
+        .reject_out_of_order
+        .iter()
+        .fold(RejectOutOfOrder::default(), |reject, target| {
+            match target.split_once('/') {
+                Some((namespace, table)) => reject.with_table(namespace, table),
+                None => reject.with_namespace(target.as_str()),
+            }
+        })
+}
+
+/// Return the Kafka partitions in the configured write buffer partition range,
+/// or the discovered partitions of a file write buffer if no range is set.
+async fn kafka_partitions(config: &Config) -> Result<Vec<KafkaPartition>> {
+    match (
+        config.write_buffer_partition_range_start,
+        config.write_buffer_partition_range_end,
+    ) {
+        (Some(start), Some(end)) => {
+            if start > end {
+                return Err(Error::KafkaRange { start, end });
+            }
+            Ok((start..end).map(KafkaPartition::new).collect())
+        }
+        (None, None) => match config.write_buffer_config.discover_partitions().await? {
+            Some(ids) => Ok(ids
+                .into_iter()
+                .map(|id| KafkaPartition::new(id as i32))
+                .collect()),
+            None => Err(Error::KafkaRangeRequired(
+                config.write_buffer_config.type_.clone(),
+            )),
+        },
+        _ => Err(Error::KafkaRangePartial),
+    }
 }
 
 pub async fn command(config: Config) -> Result<()> {
+    // Validate the partition range before connecting to anything
+    let kafka_partitions = kafka_partitions(&config).await?;
+    let debug_config = config.to_json();
+    let reject_out_of_order = reject_out_of_order(&config);
+
     let common_state = CommonServerState::from_config(config.run_config.clone())?;
 
     let catalog = config.catalog_dsn.get_catalog("ingester").await?;
@@ -106,15 +254,6 @@ pub async fn command(config: Config) -> Result<()> {
         .await?
         .ok_or(Error::KafkaTopicNotFound(config.write_buffer_config.topic))?;
 
-    if config.write_buffer_partition_range_start > config.write_buffer_partition_range_end {
-        return Err(Error::KafkaRange);
-    }
-
-    let kafka_partitions: Vec<_> = (config.write_buffer_partition_range_start
-        ..config.write_buffer_partition_range_end)
-        .map(KafkaPartition::new)
-        .collect();
-
     let object_store = Arc::new(
         ObjectStore::try_from(&config.run_config.object_store_config)
             .map_err(Error::ObjectStoreParsing)?,
@@ -140,7 +279,7 @@ pub async fn command(config: Config) -> Result<()> {
         type_: config.write_buffer_config.type_,
         connection: config.write_buffer_config.connection_string,
         connection_config: Default::default(),
-        creation_config: None,
+        creation_config: config.write_buffer_config.creation_config(),
     };
     let write_buffer = write_buffer_factory
         .new_config_read(
@@ -150,21 +289,88 @@ pub async fn command(config: Config) -> Result<()> {
         )
         .await?;
 
+    let wal = match &config.wal_dir {
+        Some(dir) => Some(Wal::new(dir).await?),
+        None => None,
+    };
+
+    let persist_selection =
+        config
+            .persist_target_bytes
+            .map(|target_total_bytes| CompactionInputSelection {
+                target_total_bytes,
+                max_files: usize::MAX,
+            });
+
+    let num_persist_threads = config.num_persist_threads.unwrap_or_else(num_cpus::get);
+    let exec = Arc::new(Executor::new(num_persist_threads));
+
     let ingest_handler = Arc::new(IngestHandlerImpl::new(
         kafka_topic,
         sequencers,
         catalog,
         object_store,
+        exec,
         write_buffer,
         &metric_registry,
+        config.eager_deletes,
+        reject_out_of_order,
+        wal,
+        config.max_partitions_per_namespace,
+        persist_selection,
     ));
     let http = HttpDelegate::new(Arc::clone(&ingest_handler));
-    let grpc = GrpcDelegate::new(ingest_handler);
+    let mut grpc = GrpcDelegate::new(ingest_handler);
+    if let Some(token) = &config.admin_token {
+        grpc = grpc.with_admin_token(token);
+    }
 
     let ingester = IngesterServer::new(http, grpc);
-    let server_type = Arc::new(IngesterServerType::new(ingester, &common_state));
+    let server_type =
+        Arc::new(IngesterServerType::new(ingester, &common_state).with_debug_config(debug_config));
 
     info!("starting ingester");
 
     Ok(influxdb_ioxd::main(common_state, server_type).await?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn config(start: &str, end: &str) -> Config {
+        Config::try_parse_from([
+            "ingester",
+            "--catalog-dsn",
+            "mem",
+            "--write-buffer",
+            "file",
+            "--write-buffer-addr",
+            "/tmp/write-buffer",
+            "--write-buffer-partition-range-start",
+            start,
+            "--write-buffer-partition-range-end",
+            end,
+        ])
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_kafka_partitions() {
+        let partitions = kafka_partitions(&config("1", "3")).await.unwrap();
+        assert_eq!(
+            partitions,
+            vec![KafkaPartition::new(1), KafkaPartition::new(2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inverted_kafka_partition_range() {
+        let err = kafka_partitions(&config("3", "1")).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "write buffer partition range start (3) must be <= write buffer partition range end (1)"
+        );
+    }
+}
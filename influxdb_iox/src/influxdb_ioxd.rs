@@ -227,6 +227,7 @@ where
         Arc::clone(&server_type),
         frontend_shutdown.clone(),
         trace_header_parser,
+        common_state.run_config().debug_profiling.into(),
     )
     .fuse();
     info!("HTTP server listening");
@@ -275,14 +276,18 @@ where
     // process, or by a background task exiting - most likely with an error
     //
     // Graceful shutdown should then proceed in the following order
-    // 1. Stop accepting new HTTP and gRPC requests and drain existing connections
-    // 2. Trigger shutdown of internal background workers loops
+    // 1. Drain in-flight requests (if supported by the server type)
+    // 2. Stop accepting new HTTP and gRPC requests and drain existing connections
+    // 3. Trigger shutdown of internal background workers loops
     //
     // This is important to ensure background tasks, such as polling the tracker
     // registry, don't exit before HTTP and gRPC requests dependent on them
     while !grpc_server.is_terminated() && !http_server.is_terminated() {
         futures::select! {
-            _ = signal => info!("Shutdown requested"),
+            _ = signal => {
+                info!("Shutdown requested");
+                server_type.drain().await;
+            },
             _ = server_handle => {
                 error!("server worker shutdown prematurely");
                 res = res.and(Err(Error::LostServer));
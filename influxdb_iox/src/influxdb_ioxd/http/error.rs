@@ -1,5 +1,8 @@
-use hyper::{Body, Response, StatusCode};
+use std::time::Duration;
+
+use hyper::{header::RETRY_AFTER, Body, Response, StatusCode};
 use observability_deps::tracing::warn;
+use router2::server::http::retry_after_header_value;
 
 /// Constants used in API error codes.
 ///
@@ -101,6 +104,9 @@ pub struct HttpApiError {
 
     /// Human-readable message.
     msg: String,
+
+    /// Duration after which the client may retry the request.
+    retry_after: Option<Duration>,
 }
 
 impl HttpApiError {
@@ -109,9 +115,17 @@ impl HttpApiError {
         Self {
             code: code.into(),
             msg: msg.into(),
+            retry_after: None,
         }
     }
 
+    /// Advise the client to retry the request after `d`, using a
+    /// `Retry-After` header.
+    pub fn with_retry_after(mut self, d: Duration) -> Self {
+        self.retry_after = Some(d);
+        self
+    }
+
     /// Generate response body for this error.
     fn body(&self) -> Body {
         let json = serde_json::json!({
@@ -125,10 +139,11 @@ impl HttpApiError {
 
     /// Generate response for this error.
     pub fn response(&self) -> Response<Body> {
-        Response::builder()
-            .status(self.code.status_code())
-            .body(self.body())
-            .unwrap()
+        let mut response = Response::builder().status(self.code.status_code());
+        if let Some(d) = self.retry_after {
+            response = response.header(RETRY_AFTER, retry_after_header_value(d));
+        }
+        response.body(self.body()).unwrap()
     }
 
     /// Check if the error is an internal server error.
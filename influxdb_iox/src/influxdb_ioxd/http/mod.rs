@@ -1,13 +1,13 @@
 use std::{convert::Infallible, num::NonZeroI32, sync::Arc};
 
 use hyper::{
-    http::HeaderValue,
+    http::{header::CONTENT_TYPE, HeaderValue},
     server::conn::{AddrIncoming, AddrStream},
     Body, Method, Request, Response,
 };
 use observability_deps::tracing::{debug, error};
 use serde::Deserialize;
-use snafu::Snafu;
+use snafu::{OptionExt, ResultExt, Snafu};
 use tokio_util::sync::CancellationToken;
 use tower::Layer;
 use trace_http::{ctx::TraceHeaderParser, tower::TraceLayer};
@@ -17,6 +17,10 @@ use crate::influxdb_ioxd::{
     server_type::ServerType,
 };
 
+/// The HTTP header carrying the ID used to correlate the logs of a single
+/// request, echoed back to the client in the response.
+pub use router2::server::http::REQUEST_ID_HEADER;
+
 #[cfg(feature = "heappy")]
 mod heappy;
 
@@ -66,6 +70,15 @@ pub enum ApplicationError {
     #[snafu(display("pprof support is not compiled"))]
     PProfIsNotCompiled,
 
+    #[snafu(display("profiling endpoints are disabled"))]
+    ProfilingDisabled,
+
+    #[snafu(display("config endpoint is not served by this server type"))]
+    DebugConfigUnavailable,
+
+    #[snafu(display("Error serializing config: {}", source))]
+    DebugConfigSerialization { source: serde_json::Error },
+
     #[snafu(display("Route error from run mode: {}", e))]
     RunModeRouteError { e: Box<dyn HttpApiErrorSource> },
 }
@@ -80,6 +93,9 @@ impl HttpApiErrorSource for ApplicationError {
             e @ Self::EmptyFlamegraph => e.empty_value(),
             e @ Self::HeappyIsNotCompiled => e.internal_error(),
             e @ Self::PProfIsNotCompiled => e.internal_error(),
+            e @ Self::ProfilingDisabled => e.not_found(),
+            e @ Self::DebugConfigUnavailable => e.not_found(),
+            e @ Self::DebugConfigSerialization { .. } => e.internal_error(),
             #[cfg(feature = "heappy")]
             e @ Self::HeappyError { .. } => e.internal_error(),
             Self::RunModeRouteError { e } => e.to_http_api_error(),
@@ -87,11 +103,17 @@ impl HttpApiErrorSource for ApplicationError {
     }
 }
 
+/// Serve HTTP requests for `server_type` on `addr` until `shutdown` is
+/// cancelled.
+///
+/// The "/debug/pprof" and "/debug/profile" profiling endpoints are only
+/// served if `profiling` is true.
 pub async fn serve<M>(
     addr: AddrIncoming,
     server_type: Arc<M>,
     shutdown: CancellationToken,
     trace_header_parser: TraceHeaderParser,
+    profiling: bool,
 ) -> Result<(), hyper::Error>
 where
     M: ServerType,
@@ -105,7 +127,7 @@ where
         .serve(hyper::service::make_service_fn(|_conn: &AddrStream| {
             let server_type = Arc::clone(&server_type);
             let service = hyper::service::service_fn(move |request: Request<_>| {
-                route_request(Arc::clone(&server_type), request)
+                route_request(Arc::clone(&server_type), request, profiling)
             });
 
             let service = trace_layer.layer(service);
@@ -118,12 +140,25 @@ where
 async fn route_request<M>(
     server_type: Arc<M>,
     mut req: Request<Body>,
+    profiling: bool,
 ) -> Result<Response<Body>, Infallible>
 where
     M: ServerType,
 {
     // we don't need the authorization header and we don't want to accidentally log it.
     req.headers_mut().remove("authorization");
+
+    // Ensure every request carries a request ID for the server type to log,
+    // generating one if the client did not provide it.
+    let request_id = req
+        .headers_mut()
+        .entry(REQUEST_ID_HEADER)
+        .or_insert_with(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("uuid is a valid header value")
+        })
+        .clone();
+
     debug!(request = ?req,"Processing request");
 
     let method = req.method().clone();
@@ -133,8 +168,13 @@ where
     let response = match (method.clone(), uri.path()) {
         (Method::GET, "/health") => health(),
         (Method::GET, "/metrics") => handle_metrics(server_type.as_ref()),
+        (Method::GET, "/debug/config") => handle_debug_config(server_type.as_ref()),
+        (
+            Method::GET,
+            "/debug/pprof" | "/debug/pprof/profile" | "/debug/pprof/allocs" | "/debug/profile",
+        ) if !profiling => ProfilingDisabledSnafu.fail(),
         (Method::GET, "/debug/pprof") => pprof_home(req).await,
-        (Method::GET, "/debug/pprof/profile") => pprof_profile(req).await,
+        (Method::GET, "/debug/pprof/profile" | "/debug/profile") => pprof_profile(req).await,
         (Method::GET, "/debug/pprof/allocs") => pprof_heappy_profile(req).await,
         _ => server_type
             .route_http_request(req)
@@ -143,21 +183,40 @@ where
     };
 
     // TODO: Move logging to TraceLayer
-    match response {
+    let mut response = match response {
         Ok(response) => {
             debug!(?response, "Successfully processed request");
-            Ok(response)
+            response
         }
         Err(error) => {
             let error: HttpApiError = error.to_http_api_error();
             if error.is_internal() {
-                error!(%error, %method, %uri, ?content_length, "Error while handling request");
+                error!(
+                    %error,
+                    %method,
+                    %uri,
+                    ?content_length,
+                    ?request_id,
+                    "Error while handling request"
+                );
             } else {
-                debug!(%error, %method, %uri, ?content_length, "Error while handling request");
+                debug!(
+                    %error,
+                    %method,
+                    %uri,
+                    ?content_length,
+                    ?request_id,
+                    "Error while handling request"
+                );
             }
-            Ok(error.response())
+            error.response()
         }
-    }
+    };
+
+    // Echo the request ID so clients can quote it when reporting a problem.
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+
+    Ok(response)
 }
 
 fn health() -> Result<Response<Body>, ApplicationError> {
@@ -176,6 +235,21 @@ where
     Ok(Response::new(Body::from(body)))
 }
 
+fn handle_debug_config<M>(server_type: &M) -> Result<Response<Body>, ApplicationError>
+where
+    M: ServerType,
+{
+    let config = server_type
+        .debug_config()
+        .context(DebugConfigUnavailableSnafu)?;
+    let body = serde_json::to_vec_pretty(&config).context(DebugConfigSerializationSnafu)?;
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("builder should be successful"))
+}
+
 async fn pprof_home(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
     let default_host = HeaderValue::from_static("localhost");
     let host = req
@@ -248,7 +322,6 @@ impl PProfAllocsArgs {
 #[cfg(feature = "pprof")]
 async fn pprof_profile(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
     use ::pprof::protos::Message;
-    use snafu::ResultExt;
 
     let query_string = req.uri().query().unwrap_or_default();
     let query: PProfArgs = serde_urlencoded::from_str(query_string)
@@ -296,8 +369,6 @@ async fn pprof_profile(_req: Request<Body>) -> Result<Response<Body>, Applicatio
 // If heappy support is enabled, call it
 #[cfg(feature = "heappy")]
 async fn pprof_heappy_profile(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
-    use snafu::ResultExt;
-
     let query_string = req.uri().query().unwrap_or_default();
     let query: PProfAllocsArgs = serde_urlencoded::from_str(query_string)
         .context(InvalidQueryStringSnafu { query_string })?;
@@ -99,6 +99,11 @@ where
     M: ServerType,
 {
     pub fn new(server_type: Arc<M>) -> Self {
+        Self::new_with_profiling(server_type, false)
+    }
+
+    /// Start a [`TestServer`], optionally serving the profiling endpoints.
+    pub fn new_with_profiling(server_type: Arc<M>, profiling: bool) -> Self {
         // NB: specify port 0 to let the OS pick the port.
         let bind_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
         let addr = AddrIncoming::bind(&bind_addr).expect("failed to bind server");
@@ -108,12 +113,13 @@ where
             .with_jaeger_trace_context_header_name("uber-trace-id");
 
         let server_type_captured = Arc::clone(&server_type);
-        let join_handle = tokio::task::spawn(async {
+        let join_handle = tokio::task::spawn(async move {
             serve(
                 addr,
                 server_type_captured,
                 CancellationToken::new(),
                 trace_header_parser,
+                profiling,
             )
             .await
             .unwrap();
@@ -162,6 +168,51 @@ where
     check_response("health", response, StatusCode::OK, Some("OK")).await;
 }
 
+/// Assert that the profiling endpoints are only served when enabled.
+pub async fn assert_profiling_disabled<T>(test_server: TestServer<T>)
+where
+    T: ServerType,
+{
+    let client = Client::new();
+    for path in [
+        "/debug/pprof",
+        "/debug/pprof/profile",
+        "/debug/pprof/allocs",
+        "/debug/profile",
+    ] {
+        let response = client
+            .get(&format!("{}{}", test_server.url(), path))
+            .send()
+            .await;
+
+        check_response(
+            path,
+            response,
+            StatusCode::NOT_FOUND,
+            Some("profiling endpoints are disabled"),
+        )
+        .await;
+    }
+}
+
+/// Assert that the CPU profiling endpoint returns a profile.
+#[cfg(feature = "pprof")]
+pub async fn assert_cpu_profile<T>(test_server: TestServer<T>)
+where
+    T: ServerType,
+{
+    let client = Client::new();
+    let response = client
+        .get(&format!("{}/debug/profile?seconds=1", test_server.url()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.bytes().await.unwrap();
+    assert!(!body.is_empty(), "empty profile");
+}
+
 /// Assert that metrics exposure is working.
 pub async fn assert_metrics<T>(test_server: TestServer<T>)
 where
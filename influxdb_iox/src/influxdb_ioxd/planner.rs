@@ -1,5 +1,5 @@
 //! Query planner wrapper for use in IOx services
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use datafusion::physical_plan::ExecutionPlan;
 use query::{
@@ -22,6 +22,33 @@ use predicate::rpc_predicate::InfluxRpcPredicate;
 pub struct Planner {
     /// Executors (whose threadpool to use)
     ctx: IOxExecutionContext,
+
+    /// Options of the InfluxRPC planners
+    influxrpc_options: InfluxRpcOptions,
+}
+
+/// Options applied to the [`InfluxRpcPlanner`]s of a [`Planner`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InfluxRpcOptions {
+    /// The server-wide maximum number of chunks a query may scan, see
+    /// [`InfluxRpcPlanner::with_max_chunks`]
+    pub max_chunks: Option<usize>,
+
+    /// The maximum number of chunks a single request may scan, see
+    /// [`InfluxRpcPlanner::with_request_max_chunks`]
+    pub request_max_chunks: Option<usize>,
+
+    /// The server-wide maximum number of series a query may produce, see
+    /// [`InfluxRpcPlanner::with_max_series`]
+    pub max_series: Option<usize>,
+
+    /// The maximum number of series a single request may produce, see
+    /// [`InfluxRpcPlanner::with_request_max_series`]
+    pub request_max_series: Option<usize>,
+
+    /// Keep all chunks rather than pruning them, see
+    /// [`InfluxRpcPlanner::with_disable_pruning`]
+    pub disable_pruning: bool,
 }
 
 impl Planner {
@@ -29,7 +56,42 @@ impl Planner {
     pub fn new(ctx: &IOxExecutionContext) -> Self {
         Self {
             ctx: ctx.child_ctx("Planner"),
+            influxrpc_options: Default::default(),
+        }
+    }
+
+    /// Plan InfluxRPC queries with `options`
+    pub fn with_influxrpc_options(self, influxrpc_options: InfluxRpcOptions) -> Self {
+        Self {
+            influxrpc_options,
+            ..self
+        }
+    }
+
+    /// Create an [`InfluxRpcPlanner`] with the options of this planner
+    fn influxrpc_planner(&self) -> InfluxRpcPlanner {
+        let InfluxRpcOptions {
+            max_chunks,
+            request_max_chunks,
+            max_series,
+            request_max_series,
+            disable_pruning,
+        } = self.influxrpc_options;
+
+        let mut planner = InfluxRpcPlanner::new().with_disable_pruning(disable_pruning);
+        if let Some(max_chunks) = max_chunks {
+            planner = planner.with_max_chunks(max_chunks);
+        }
+        if let Some(request_max_chunks) = request_max_chunks {
+            planner = planner.with_request_max_chunks(request_max_chunks);
+        }
+        if let Some(max_series) = max_series {
+            planner = planner.with_max_series(max_series);
         }
+        if let Some(request_max_series) = request_max_series {
+            planner = planner.with_request_max_series(request_max_series);
+        }
+        planner
     }
 
     /// Plan a SQL query against the data in `database`, and return a
@@ -54,7 +116,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -75,7 +137,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -98,7 +160,7 @@ impl Planner {
         D: QueryDatabase + 'static,
     {
         let tag_name = tag_name.into();
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -109,6 +171,29 @@ impl Planner {
             .await
     }
 
+    /// Creates a plan per table as described on
+    /// [`InfluxRpcPlanner::tag_values_by_table`], on a separate threadpool
+    pub async fn tag_values_by_table<D>(
+        &self,
+        database: Arc<D>,
+        tag_name: impl Into<String> + Send,
+        predicate: InfluxRpcPredicate,
+    ) -> Result<BTreeMap<String, StringSetPlan>>
+    where
+        D: QueryDatabase + 'static,
+    {
+        let tag_name = tag_name.into();
+        let planner = self.influxrpc_planner();
+
+        self.ctx
+            .run(async move {
+                planner
+                    .tag_values_by_table(database.as_ref(), &tag_name, predicate)
+                    .map_err(|e| Error::Plan(format!("tag_values_by_table error: {}", e)))
+            })
+            .await
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRpcPlanner::field_columns`], on a separate threadpool
     pub async fn field_columns<D>(
@@ -119,7 +204,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -140,7 +225,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -163,7 +248,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
@@ -174,6 +259,29 @@ impl Planner {
             .await
     }
 
+    /// Creates a plan as described on
+    /// [`InfluxRpcPlanner::read_group_aggregates`], on a separate threadpool
+    pub async fn read_group_aggregates<D>(
+        &self,
+        database: Arc<D>,
+        predicate: InfluxRpcPredicate,
+        aggs: Vec<Aggregate>,
+        group_columns: Vec<String>,
+    ) -> Result<SeriesSetPlans>
+    where
+        D: QueryDatabase + 'static,
+    {
+        let planner = self.influxrpc_planner();
+
+        self.ctx
+            .run(async move {
+                planner
+                    .read_group_aggregates(database.as_ref(), predicate, &aggs, &group_columns)
+                    .map_err(|e| Error::Plan(format!("read_group_aggregates error: {}", e)))
+            })
+            .await
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRpcPlanner::read_window_aggregate`], on a separate threadpool
     pub async fn read_window_aggregate<D>(
@@ -187,7 +295,7 @@ impl Planner {
     where
         D: QueryDatabase + 'static,
     {
-        let planner = InfluxRpcPlanner::new();
+        let planner = self.influxrpc_planner();
 
         self.ctx
             .run(async move {
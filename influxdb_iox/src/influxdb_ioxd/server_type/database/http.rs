@@ -252,9 +252,9 @@ async fn query(
 
     let db = server.db(&db_name)?;
 
-    let _query_completed_token = db.record_query("sql", &q);
-
     let ctx = db.new_query_context(req.extensions().get().cloned());
+    let _query_completed_token = db.record_query(&ctx, "sql", &q);
+
     let physical_plan = Planner::new(&ctx).sql(&q).await.context(PlanningSnafu)?;
 
     // TODO: stream read results out rather than rendering the
@@ -314,6 +314,7 @@ mod tests {
         Arc::new(ApplicationState::new(
             Arc::new(ObjectStore::new_in_memory()),
             None,
+            Default::default(),
             Some(Arc::new(RingBufferTraceCollector::new(5))),
         ))
     }
@@ -28,6 +28,8 @@ pub struct DatabaseServerType {
     pub lp_metrics: Arc<LineProtocolMetrics>,
     pub max_request_size: usize,
     pub serving_readiness: ServingReadiness,
+    pub max_query_chunks: Option<usize>,
+    pub max_query_series: Option<usize>,
     shutdown: CancellationToken,
 }
 
@@ -47,9 +49,29 @@ impl DatabaseServerType {
             lp_metrics,
             max_request_size: common_state.run_config().max_http_request_size,
             serving_readiness: common_state.serving_readiness().clone(),
+            max_query_chunks: None,
+            max_query_series: None,
             shutdown: CancellationToken::new(),
         }
     }
+
+    /// Fail InfluxRPC queries that would scan more than `max_query_chunks`
+    /// chunks, if set
+    pub fn with_max_query_chunks(self, max_query_chunks: Option<usize>) -> Self {
+        Self {
+            max_query_chunks,
+            ..self
+        }
+    }
+
+    /// Fail InfluxRPC queries that produce more than `max_query_series`
+    /// series, if set
+    pub fn with_max_query_series(self, max_query_series: Option<usize>) -> Self {
+        Self {
+            max_query_series,
+            ..self
+        }
+    }
 }
 
 #[async_trait]
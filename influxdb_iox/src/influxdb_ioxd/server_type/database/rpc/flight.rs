@@ -14,18 +14,25 @@ use arrow_flight::{
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
     HandshakeRequest, HandshakeResponse, PutResult, SchemaAsIpc, SchemaResult, Ticket,
 };
-use datafusion::physical_plan::ExecutionPlan;
+use bytes::Bytes;
+use datafusion::{catalog::catalog::CatalogProvider, physical_plan::ExecutionPlan};
 use futures::{SinkExt, Stream, StreamExt};
+use iox_object_store::IoxObjectStore;
 use pin_project::{pin_project, pinned_drop};
 use query::QueryDatabase;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use tokio::task::JoinHandle;
 use tonic::{Request, Response, Streaming};
+use uuid::Uuid;
 
 use data_types::{DatabaseName, DatabaseNameError};
 use observability_deps::tracing::{info, warn};
-use query::exec::{ExecutionContextProvider, IOxExecutionContext};
+use predicate::{predicate::Predicate, rpc_predicate::InfluxRpcPredicate};
+use query::{
+    exec::{ExecutionContextProvider, ExecutorType, IOxExecutionContext},
+    plan::seriesset::SeriesSetPlans,
+};
 use server::Server;
 
 use super::error::default_server_error_handler;
@@ -71,6 +78,28 @@ pub enum Error {
     Planning {
         source: crate::influxdb_ioxd::planner::Error,
     },
+
+    #[snafu(display("Unknown action type: {}", action_type))]
+    UnknownAction { action_type: String },
+
+    #[snafu(display("Invalid export request: {}", source))]
+    InvalidExportRequest { source: serde_json::Error },
+
+    #[snafu(display("No data to export for table {}", table_name))]
+    NoDataToExport { table_name: String },
+
+    #[snafu(display("Error writing Arrow IPC data: {}", source))]
+    WritingIpc { source: ArrowError },
+
+    #[snafu(display(
+        "Error storing export of database {} in object storage: {}",
+        database_name,
+        source
+    ))]
+    StoringExport {
+        database_name: String,
+        source: object_store::Error,
+    },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -86,11 +115,16 @@ impl From<Error> for tonic::Status {
             | Error::InvalidTicket { .. }
             | Error::InvalidQuery { .. }
             // TODO(edd): this should be `debug`. Keeping at info whilst IOx still in early development
-            | Error::InvalidDatabaseName { .. } => info!(?err, msg),
+            | Error::InvalidDatabaseName { .. }
+            | Error::UnknownAction { .. }
+            | Error::InvalidExportRequest { .. }
+            | Error::NoDataToExport { .. } => info!(?err, msg),
             Error::Query { .. } => info!(?err, msg),
             Error::DictionaryError { .. }
             | Error::InvalidRecordBatch { .. }
-            | Error::Planning { .. } => warn!(?err, msg),
+            | Error::Planning { .. }
+            | Error::WritingIpc { .. }
+            | Error::StoringExport { .. } => warn!(?err, msg),
         }
         err.to_status()
     }
@@ -110,6 +144,11 @@ impl Error {
             Self::InvalidRecordBatch { .. } => Status::internal(self.to_string()),
             Self::Planning { .. } => Status::invalid_argument(self.to_string()),
             Self::DictionaryError { .. } => Status::internal(self.to_string()),
+            Self::UnknownAction { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidExportRequest { .. } => Status::invalid_argument(self.to_string()),
+            Self::NoDataToExport { .. } => Status::not_found(self.to_string()),
+            Self::WritingIpc { .. } => Status::internal(self.to_string()),
+            Self::StoringExport { .. } => Status::internal(self.to_string()),
         }
     }
 }
@@ -122,6 +161,29 @@ type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send
 struct ReadInfo {
     database_name: String,
     sql_query: String,
+    /// If not empty, the tables of `database_name` and of these databases
+    /// are queried together, each database as a schema named after it
+    #[serde(default)]
+    other_database_names: Vec<String>,
+}
+
+/// Type of the [`Action`] that exports the contents of a table to object
+/// storage as Arrow IPC streams
+const EXPORT_TABLE_ACTION: &str = "export_table";
+
+#[derive(Deserialize, Debug)]
+/// Body of the `export_table` [`Action`]
+struct ExportInfo {
+    database_name: String,
+    table_name: String,
+}
+
+#[derive(Serialize, Debug)]
+/// Body of the result of the `export_table` [`Action`]
+struct ExportResult {
+    /// Locations of the parts of the export in object storage, each an
+    /// Arrow IPC stream; concatenated in order they hold the whole table
+    locations: Vec<String>,
 }
 
 /// Concrete implementation of the gRPC Arrow Flight Service API
@@ -172,9 +234,26 @@ impl Flight for FlightService {
             .db(&database)
             .map_err(default_server_error_handler)?;
 
-        let _query_completed_token = db.record_query("sql", &read_info.sql_query);
+        let ctx = if read_info.other_database_names.is_empty() {
+            db.new_query_context(span_ctx)
+        } else {
+            let mut namespaces = vec![(
+                read_info.database_name.clone(),
+                Arc::clone(&db) as Arc<dyn CatalogProvider>,
+            )];
+            for name in &read_info.other_database_names {
+                let database = DatabaseName::new(name).context(InvalidDatabaseNameSnafu)?;
+                let other_db = self
+                    .server
+                    .db(&database)
+                    .map_err(default_server_error_handler)?;
+                namespaces.push((name.clone(), other_db as Arc<dyn CatalogProvider>));
+            }
+            db.executor()
+                .new_multi_namespace_context(ExecutorType::Query, namespaces)
+        };
 
-        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "sql", &read_info.sql_query);
 
         let physical_plan = Planner::new(&ctx)
             .sql(&read_info.sql_query)
@@ -222,16 +301,68 @@ impl Flight for FlightService {
 
     async fn do_action(
         &self,
-        _request: Request<Action>,
+        request: Request<Action>,
     ) -> Result<Response<Self::DoActionStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let span_ctx = request.extensions().get().cloned();
+        let action = request.into_inner();
+
+        if action.r#type != EXPORT_TABLE_ACTION {
+            return Err(Error::UnknownAction {
+                action_type: action.r#type,
+            }
+            .into());
+        }
+
+        let export_info: ExportInfo =
+            serde_json::from_slice(&action.body).context(InvalidExportRequestSnafu)?;
+
+        let database =
+            DatabaseName::new(&export_info.database_name).context(InvalidDatabaseNameSnafu)?;
+
+        let db = self
+            .server
+            .db(&database)
+            .map_err(default_server_error_handler)?;
+
+        let ctx = db.new_query_context(span_ctx);
+
+        let _query_completed_token = db.record_query(&ctx, "export", &export_info.table_name);
+
+        let predicate =
+            InfluxRpcPredicate::new_table(&export_info.table_name, Predicate::default());
+        let plans = Planner::new(&ctx)
+            .read_filter(Arc::clone(&db), predicate)
+            .await
+            .context(PlanningSnafu)?;
+
+        let iox_object_store = db.iox_object_store();
+        let locations = export_ipc_parts(
+            &ctx,
+            plans,
+            &iox_object_store,
+            &export_info.database_name,
+            &export_info.table_name,
+        )
+        .await?;
+
+        let body = serde_json::to_vec(&ExportResult { locations })
+            .expect("export result can be serialized");
+        let output = futures::stream::iter(std::iter::once(Ok(arrow_flight::Result { body })));
+
+        Ok(Response::new(Box::pin(output) as Self::DoActionStream))
     }
 
     async fn list_actions(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<Self::ListActionsStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let export_table = ActionType {
+            r#type: EXPORT_TABLE_ACTION.to_string(),
+            description: "Export a table to object storage as Arrow IPC streams".to_string(),
+        };
+        let output = futures::stream::iter(std::iter::once(Ok(export_table)));
+
+        Ok(Response::new(Box::pin(output) as Self::ListActionsStream))
     }
 
     async fn do_exchange(
@@ -368,6 +499,122 @@ impl Stream for GetStream {
     }
 }
 
+/// Approximate in-memory size of the record batches buffered for a part of
+/// an export before it is written to object storage
+const EXPORT_PART_BYTES: usize = 64 * 1024 * 1024;
+
+/// Runs the `read_filter` `plans` and writes their output to object storage
+/// as a sequence of Arrow IPC streams, hydrating dictionaries the same way
+/// as [`GetStream`]. Only one part of at most about [`EXPORT_PART_BYTES`] is
+/// buffered at a time. Returns the locations of the parts, in order.
+async fn export_ipc_parts(
+    ctx: &IOxExecutionContext,
+    plans: SeriesSetPlans,
+    iox_object_store: &IoxObjectStore,
+    database_name: &str,
+    table_name: &str,
+) -> Result<Vec<String>, Error> {
+    let export_id = Uuid::new_v4();
+    let mut locations = vec![];
+    let mut part: Option<ExportPart> = None;
+
+    for plan in plans.plans {
+        let physical_plan = ctx
+            .prepare_plan(&plan.plan)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(QuerySnafu { database_name })?;
+
+        let schema = Arc::new(optimize_schema(&physical_plan.schema()));
+        let mut stream_record_batches = ctx
+            .execute_stream(physical_plan)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(QuerySnafu { database_name })?;
+
+        while let Some(batch) = stream_record_batches.next().await {
+            let batch = batch
+                .map_err(|e| Box::new(e) as _)
+                .context(QuerySnafu { database_name })?;
+            let batch = optimize_record_batch(&batch, Arc::clone(&schema))?;
+
+            let current = match part.as_mut() {
+                Some(current) => current,
+                None => part.insert(ExportPart::try_new(&schema)?),
+            };
+            current.write(&batch)?;
+
+            if current.buffered_bytes >= EXPORT_PART_BYTES {
+                let full = part.take().expect("part was just written");
+                let file_name = format!("{}-{:05}.arrow", export_id, locations.len());
+                locations.push(
+                    full.store(iox_object_store, &file_name, database_name)
+                        .await?,
+                );
+            }
+        }
+    }
+
+    if let Some(last) = part {
+        let file_name = format!("{}-{:05}.arrow", export_id, locations.len());
+        locations.push(
+            last.store(iox_object_store, &file_name, database_name)
+                .await?,
+        );
+    }
+
+    if locations.is_empty() {
+        return Err(Error::NoDataToExport {
+            table_name: table_name.to_string(),
+        });
+    }
+
+    Ok(locations)
+}
+
+/// A part of an export that is being buffered as an Arrow IPC stream
+struct ExportPart {
+    writer: arrow::ipc::writer::StreamWriter<Vec<u8>>,
+    buffered_bytes: usize,
+}
+
+impl ExportPart {
+    fn try_new(schema: &Schema) -> Result<Self, Error> {
+        let writer = arrow::ipc::writer::StreamWriter::try_new(Vec::new(), schema)
+            .context(WritingIpcSnafu)?;
+        Ok(Self {
+            writer,
+            buffered_bytes: 0,
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        self.writer.write(batch).context(WritingIpcSnafu)?;
+        self.buffered_bytes += batch
+            .columns()
+            .iter()
+            .map(|array| array.get_array_memory_size())
+            .sum::<usize>();
+        Ok(())
+    }
+
+    /// Finishes the IPC stream and stores it as the export file `file_name`
+    async fn store(
+        mut self,
+        iox_object_store: &IoxObjectStore,
+        file_name: &str,
+        database_name: &str,
+    ) -> Result<String, Error> {
+        self.writer.finish().context(WritingIpcSnafu)?;
+        let bytes = self.writer.into_inner().context(WritingIpcSnafu)?;
+
+        iox_object_store
+            .put_export_file(file_name, Bytes::from(bytes))
+            .await
+            .context(StoringExportSnafu { database_name })
+    }
+}
+
 /// Some batches are small slices of the underlying arrays.
 /// At this stage we only know the number of rows in the record batch
 /// and the sizes in bytes of the backing buffers of the column arrays.
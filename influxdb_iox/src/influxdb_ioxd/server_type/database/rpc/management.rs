@@ -1,11 +1,12 @@
 use data_types::{chunk_metadata::ChunkId, DatabaseName};
+use db::downsample::DownsampleConfig;
 use generated_types::{
     google::{FieldViolation, FieldViolationExt},
     influxdata::iox::management::v1::{Error as ProtobufError, *},
 };
-use query::QueryDatabase;
+use query::{group_by::Aggregate, QueryDatabase};
 use server::{rules::ProvidedDatabaseRules, ApplicationState, Server};
-use std::{convert::TryFrom, sync::Arc};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
@@ -590,6 +591,67 @@ impl management_service_server::ManagementService for ManagementService {
             operation,
         }))
     }
+
+    // Downsample a table into a table of the same or another database
+    async fn downsample_table(
+        &self,
+        request: Request<DownsampleTableRequest>,
+    ) -> Result<Response<DownsampleTableResponse>, Status> {
+        let DownsampleTableRequest {
+            db_name,
+            source_table,
+            target_db_name,
+            target_table,
+            aggregate,
+            window_nanoseconds,
+        } = request.into_inner();
+
+        // Validate that the database names are legit
+        let db_name = DatabaseName::new(db_name).scope("db_name")?;
+        let target_db_name = DatabaseName::new(target_db_name).scope("target_db_name")?;
+
+        let aggregate = match DownsampleAggregate::from_i32(aggregate) {
+            Some(DownsampleAggregate::Sum) => Aggregate::Sum,
+            Some(DownsampleAggregate::Count) => Aggregate::Count,
+            Some(DownsampleAggregate::Min) => Aggregate::Min,
+            Some(DownsampleAggregate::Max) => Aggregate::Max,
+            Some(DownsampleAggregate::First) => Aggregate::First,
+            Some(DownsampleAggregate::Last) => Aggregate::Last,
+            Some(DownsampleAggregate::Mean) => Aggregate::Mean,
+            Some(DownsampleAggregate::Unspecified) | None => {
+                return Err(FieldViolation::required("aggregate").into())
+            }
+        };
+        if window_nanoseconds == 0 || i64::try_from(window_nanoseconds).is_err() {
+            return Err(FieldViolation {
+                field: "window_nanoseconds".to_string(),
+                description: "Window must be a positive number of nanoseconds fitting an i64"
+                    .to_string(),
+            }
+            .into());
+        }
+
+        let db = self
+            .server
+            .db(&db_name)
+            .map_err(default_server_error_handler)?;
+        let target = self
+            .server
+            .db(&target_db_name)
+            .map_err(default_server_error_handler)?;
+
+        let config = DownsampleConfig {
+            source_table,
+            target_table,
+            aggregate,
+            window: Duration::from_nanos(window_nanoseconds),
+        };
+        let tracker = db.downsample(config, target);
+
+        let operation = Some(super::operations::encode_tracker(tracker)?);
+
+        Ok(Response::new(DownsampleTableResponse { operation }))
+    }
 }
 
 /// Returns [`DatabaseRules`] formated according to the `omit_defaults` flag. If `omit_defaults` is
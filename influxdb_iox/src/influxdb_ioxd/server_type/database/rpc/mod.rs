@@ -22,7 +22,12 @@ pub async fn server_grpc(
 
     add_gated_service!(
         builder,
-        storage::make_server(Arc::clone(&server_type.server),)
+        storage::make_server(
+            Arc::clone(&server_type.server),
+            server_type.max_query_chunks,
+            server_type.max_query_series,
+            server_type.application.executor().disable_pruning(),
+        )
     );
     add_gated_service!(
         builder,
@@ -18,12 +18,39 @@ use generated_types::storage_server::{Storage, StorageServer};
 use server::DatabaseStore;
 use std::sync::Arc;
 
+/// gRPC request header lowering the maximum number of chunks the request
+/// may scan below the server maximum
+pub const MAX_QUERY_CHUNKS_HEADER: &str = "iox-max-query-chunks";
+
+/// gRPC request header lowering the maximum number of series the request
+/// may produce below the server maximum
+pub const MAX_QUERY_SERIES_HEADER: &str = "iox-max-query-series";
+
 /// Concrete implementation of the gRPC InfluxDB Storage Service API
 #[derive(Debug)]
 struct StorageService<T: DatabaseStore> {
     pub db_store: Arc<T>,
+
+    /// The maximum number of chunks a query may scan, if limited
+    pub max_query_chunks: Option<usize>,
+
+    /// The maximum number of series a query may produce, if limited
+    pub max_query_series: Option<usize>,
+
+    /// Keep all chunks of queries rather than pruning them
+    pub disable_pruning: bool,
 }
 
-pub fn make_server<T: DatabaseStore + 'static>(db_store: Arc<T>) -> StorageServer<impl Storage> {
-    StorageServer::new(StorageService { db_store })
+pub fn make_server<T: DatabaseStore + 'static>(
+    db_store: Arc<T>,
+    max_query_chunks: Option<usize>,
+    max_query_series: Option<usize>,
+    disable_pruning: bool,
+) -> StorageServer<impl Storage> {
+    StorageServer::new(StorageService {
+        db_store,
+        max_query_chunks,
+        max_query_series,
+        disable_pruning,
+    })
 }
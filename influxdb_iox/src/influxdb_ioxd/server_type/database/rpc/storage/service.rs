@@ -28,14 +28,14 @@ use observability_deps::tracing::{error, info, trace};
 use query::{
     exec::{
         fieldlist::FieldList, seriesset::converter::Error as SeriesSetError,
-        ExecutionContextProvider,
+        ExecutionContextProvider, IOxExecutionContext,
     },
     QueryDatabase,
 };
 use server::DatabaseStore;
 
 use crate::influxdb_ioxd::{
-    planner::Planner,
+    planner::{InfluxRpcOptions, Planner},
     server_type::database::rpc::storage::{
         data::{
             fieldlist_to_measurement_fields_response, series_or_groups_to_read_response,
@@ -43,10 +43,9 @@ use crate::influxdb_ioxd::{
         },
         expr::{self, GroupByAndAggregate, InfluxRpcPredicateBuilder, Loggable, SpecialTagKeys},
         input::GrpcInputs,
-        StorageService,
+        StorageService, MAX_QUERY_CHUNKS_HEADER, MAX_QUERY_SERIES_HEADER,
     },
 };
-use trace::ctx::SpanContext;
 
 use super::{TAG_KEY_FIELD, TAG_KEY_MEASUREMENT};
 
@@ -172,6 +171,20 @@ pub enum Error {
 
     #[snafu(display("Operation not yet implemented:  {}", operation))]
     NotYetImplemented { operation: String },
+
+    #[snafu(display(
+        "Invalid value of the {} header, expected a number of chunks: {:?}",
+        MAX_QUERY_CHUNKS_HEADER,
+        value
+    ))]
+    InvalidMaxQueryChunks { value: String },
+
+    #[snafu(display(
+        "Invalid value of the {} header, expected a number of series: {:?}",
+        MAX_QUERY_SERIES_HEADER,
+        value
+    ))]
+    InvalidMaxQuerySeries { value: String },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -218,10 +231,59 @@ impl Error {
             Self::MeasurementLiteralOrRegex { .. } => Status::invalid_argument(self.to_string()),
             Self::MissingTagKeyPredicate {} => Status::invalid_argument(self.to_string()),
             Self::InvalidTagKeyRegex { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidMaxQueryChunks { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidMaxQuerySeries { .. } => Status::invalid_argument(self.to_string()),
         }
     }
 }
 
+impl<T> StorageService<T>
+where
+    T: DatabaseStore,
+{
+    /// Returns the options of the InfluxRPC planners of `req`, which may
+    /// lower the maximum number of chunks scanned and series produced using
+    /// the [`MAX_QUERY_CHUNKS_HEADER`] and [`MAX_QUERY_SERIES_HEADER`]
+    /// headers
+    fn influxrpc_options<R>(&self, req: &tonic::Request<R>) -> Result<InfluxRpcOptions> {
+        let request_max_chunks = req
+            .metadata()
+            .get(MAX_QUERY_CHUNKS_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .context(InvalidMaxQueryChunksSnafu {
+                        value: String::from_utf8_lossy(value.as_bytes()),
+                    })
+            })
+            .transpose()?;
+
+        let request_max_series = req
+            .metadata()
+            .get(MAX_QUERY_SERIES_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .context(InvalidMaxQuerySeriesSnafu {
+                        value: String::from_utf8_lossy(value.as_bytes()),
+                    })
+            })
+            .transpose()?;
+
+        Ok(InfluxRpcOptions {
+            max_chunks: self.max_query_chunks,
+            request_max_chunks,
+            max_series: self.max_query_series,
+            request_max_series,
+            disable_pruning: self.disable_pruning,
+        })
+    }
+}
+
 /// Implements the protobuf defined Storage service for a DatabaseStore
 #[tonic::async_trait]
 impl<T> Storage for StorageService<T>
@@ -235,6 +297,7 @@ where
         req: tonic::Request<ReadFilterRequest>,
     ) -> Result<tonic::Response<Self::ReadFilterStream>, Status> {
         let span_ctx = req.extensions().get().cloned();
+        let influxrpc_options = self.influxrpc_options(&req)?;
 
         let req = req.into_inner();
         let db_name = get_database_name(&req)?;
@@ -244,9 +307,10 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("read_filter", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "read_filter", defer_json(&req));
 
-        let results = read_filter_impl(Arc::clone(&db), db_name, req, span_ctx)
+        let results = read_filter_impl(Arc::clone(&db), db_name, req, influxrpc_options, &ctx)
             .await?
             .into_iter()
             .map(Ok)
@@ -262,6 +326,7 @@ where
         req: tonic::Request<ReadGroupRequest>,
     ) -> Result<tonic::Response<Self::ReadGroupStream>, Status> {
         let span_ctx = req.extensions().get().cloned();
+        let influxrpc_options = self.influxrpc_options(&req)?;
         let req = req.into_inner();
 
         let db_name = get_database_name(&req)?;
@@ -269,7 +334,8 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("read_group", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "read_group", defer_json(&req));
 
         let ReadGroupRequest {
             read_source: _read_source,
@@ -300,7 +366,8 @@ where
             range,
             predicate,
             gby_agg,
-            span_ctx,
+            influxrpc_options,
+            &ctx,
         )
         .await
         .map_err(|e| e.to_status())?
@@ -319,6 +386,7 @@ where
         req: tonic::Request<ReadWindowAggregateRequest>,
     ) -> Result<tonic::Response<Self::ReadGroupStream>, Status> {
         let span_ctx = req.extensions().get().cloned();
+        let influxrpc_options = self.influxrpc_options(&req)?;
         let req = req.into_inner();
 
         let db_name = get_database_name(&req)?;
@@ -326,7 +394,9 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("read_window_aggregate", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token =
+            db.record_query(&ctx, "read_window_aggregate", defer_json(&req));
 
         let ReadWindowAggregateRequest {
             read_source: _read_source,
@@ -354,7 +424,8 @@ where
             range,
             predicate,
             gby_agg,
-            span_ctx,
+            influxrpc_options,
+            &ctx,
         )
         .await
         .map_err(|e| e.to_status())?
@@ -381,7 +452,8 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("tag_keys", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "tag_keys", defer_json(&req));
 
         let TagKeysRequest {
             tags_source: _tag_source,
@@ -399,7 +471,7 @@ where
             measurement,
             range,
             predicate,
-            span_ctx,
+            &ctx,
         )
         .await
         .map_err(|e| e.to_status());
@@ -418,6 +490,7 @@ where
         req: tonic::Request<TagValuesRequest>,
     ) -> Result<tonic::Response<Self::TagValuesStream>, Status> {
         let span_ctx = req.extensions().get().cloned();
+        let influxrpc_options = self.influxrpc_options(&req)?;
         let (tx, rx) = mpsc::channel(4);
 
         let req = req.into_inner();
@@ -427,7 +500,8 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("tag_values", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "tag_values", defer_json(&req));
 
         let TagValuesRequest {
             tags_source: _tag_source,
@@ -450,13 +524,20 @@ where
                 .to_status());
             }
 
-            measurement_name_impl(Arc::clone(&db), db_name, range, predicate, span_ctx).await
+            measurement_name_impl(Arc::clone(&db), db_name, range, predicate, &ctx).await
         } else if tag_key.is_field() {
             info!(%db_name, ?range, predicate=%predicate.loggable(), "tag_values with tag_key=[xff] (field name)");
 
-            let fieldlist =
-                field_names_impl(Arc::clone(&db), db_name, None, range, predicate, span_ctx)
-                    .await?;
+            let fieldlist = field_names_impl(
+                Arc::clone(&db),
+                db_name,
+                None,
+                range,
+                predicate,
+                influxrpc_options,
+                &ctx,
+            )
+            .await?;
 
             // Pick out the field names into a Vec<Vec<u8>>for return
             let values = fieldlist
@@ -478,7 +559,7 @@ where
                 measurement,
                 range,
                 predicate,
-                span_ctx,
+                &ctx,
             )
             .await
         };
@@ -508,24 +589,22 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
+        let ctx = db.new_query_context(span_ctx);
         let _query_completed_token = db.record_query(
+            &ctx,
             "tag_values_grouped_by_measurement_and_tag_key",
             defer_json(&req),
         );
 
         info!(%db_name, ?req.measurement_patterns, ?req.tag_key_predicate, predicate=%req.condition.loggable(), "tag_values_grouped_by_measurement_and_tag_key");
 
-        let results = tag_values_grouped_by_measurement_and_tag_key_impl(
-            Arc::clone(&db),
-            db_name,
-            req,
-            span_ctx,
-        )
-        .await
-        .map_err(|e| e.to_status())?
-        .into_iter()
-        .map(Ok)
-        .collect::<Vec<_>>();
+        let results =
+            tag_values_grouped_by_measurement_and_tag_key_impl(Arc::clone(&db), db_name, req, &ctx)
+                .await
+                .map_err(|e| e.to_status())?
+                .into_iter()
+                .map(Ok)
+                .collect::<Vec<_>>();
 
         Ok(tonic::Response::new(futures::stream::iter(results)))
     }
@@ -591,7 +670,8 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("measurement_names", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "measurement_names", defer_json(&req));
 
         let MeasurementNamesRequest {
             source: _source,
@@ -601,7 +681,7 @@ where
 
         info!(%db_name, ?range, predicate=%predicate.loggable(), "measurement_names");
 
-        let response = measurement_name_impl(Arc::clone(&db), db_name, range, predicate, span_ctx)
+        let response = measurement_name_impl(Arc::clone(&db), db_name, range, predicate, &ctx)
             .await
             .map_err(|e| e.to_status());
 
@@ -628,7 +708,9 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("measurement_tag_keys", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token =
+            db.record_query(&ctx, "measurement_tag_keys", defer_json(&req));
 
         let MeasurementTagKeysRequest {
             source: _source,
@@ -647,7 +729,7 @@ where
             measurement,
             range,
             predicate,
-            span_ctx,
+            &ctx,
         )
         .await
         .map_err(|e| e.to_status());
@@ -675,7 +757,9 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("measurement_tag_values", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token =
+            db.record_query(&ctx, "measurement_tag_values", defer_json(&req));
 
         let MeasurementTagValuesRequest {
             source: _source,
@@ -696,7 +780,7 @@ where
             measurement,
             range,
             predicate,
-            span_ctx,
+            &ctx,
         )
         .await
         .map_err(|e| e.to_status());
@@ -715,6 +799,7 @@ where
         req: tonic::Request<MeasurementFieldsRequest>,
     ) -> Result<tonic::Response<Self::MeasurementFieldsStream>, Status> {
         let span_ctx = req.extensions().get().cloned();
+        let influxrpc_options = self.influxrpc_options(&req)?;
         let (tx, rx) = mpsc::channel(4);
 
         let req = req.into_inner();
@@ -724,7 +809,8 @@ where
             .db_store
             .db(&db_name)
             .context(DatabaseNotFoundSnafu { db_name: &db_name })?;
-        let _query_completed_token = db.record_query("measurement_fields", defer_json(&req));
+        let ctx = db.new_query_context(span_ctx);
+        let _query_completed_token = db.record_query(&ctx, "measurement_fields", defer_json(&req));
 
         let MeasurementFieldsRequest {
             source: _source,
@@ -743,7 +829,8 @@ where
             measurement,
             range,
             predicate,
-            span_ctx,
+            influxrpc_options,
+            &ctx,
         )
         .await
         .map(|fieldlist| {
@@ -790,7 +877,7 @@ async fn measurement_name_impl<D>(
     db_name: DatabaseName<'static>,
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<StringValuesResponse>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -806,9 +893,7 @@ where
         })?
         .build();
 
-    let ctx = db.new_query_context(span_ctx);
-
-    let plan = Planner::new(&ctx)
+    let plan = Planner::new(ctx)
         .table_names(db, predicate)
         .await
         .map_err(|e| Box::new(e) as _)
@@ -838,7 +923,7 @@ async fn tag_keys_impl<D>(
     measurement: Option<String>,
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<StringValuesResponse>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -855,9 +940,7 @@ where
         })?
         .build();
 
-    let ctx = db.new_query_context(span_ctx);
-
-    let tag_key_plan = Planner::new(&ctx)
+    let tag_key_plan = Planner::new(ctx)
         .tag_keys(db, predicate)
         .await
         .map_err(|e| Box::new(e) as _)
@@ -885,7 +968,7 @@ async fn tag_values_impl<D>(
     measurement: Option<String>,
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<StringValuesResponse>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -904,9 +987,7 @@ where
     let db_name = db_name.as_str();
     let tag_name = &tag_name;
 
-    let ctx = db.new_query_context(span_ctx);
-
-    let tag_value_plan = Planner::new(&ctx)
+    let tag_value_plan = Planner::new(ctx)
         .tag_values(db, tag_name, predicate)
         .await
         .map_err(|e| Box::new(e) as _)
@@ -934,7 +1015,7 @@ async fn tag_values_grouped_by_measurement_and_tag_key_impl<D>(
     db: Arc<D>,
     db_name: DatabaseName<'static>,
     req: TagValuesGroupedByMeasurementAndTagKeyRequest,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<Vec<TagValuesResponse>, Error>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -955,7 +1036,7 @@ where
         Arc::clone(&db),
         db_name.clone(),
         req.measurement_patterns,
-        span_ctx.clone(),
+        ctx,
     )
     .await?;
 
@@ -966,7 +1047,7 @@ where
             db_name.clone(),
             name.clone(),
             tag_key_pred.clone(),
-            span_ctx.clone(),
+            ctx,
         )
         .await?;
 
@@ -979,7 +1060,7 @@ where
                 Some(name.clone()),
                 None,
                 req.condition.clone(),
-                span_ctx.clone(),
+                ctx,
             )
             .await?
             .values
@@ -1008,15 +1089,14 @@ async fn read_filter_impl<D>(
     db: Arc<D>,
     db_name: DatabaseName<'static>,
     req: ReadFilterRequest,
-    span_ctx: Option<SpanContext>,
+    influxrpc_options: InfluxRpcOptions,
+    ctx: &IOxExecutionContext,
 ) -> Result<Vec<ReadResponse>, Error>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
 {
     let db_name = db_name.as_str();
 
-    let ctx = db.new_query_context(span_ctx);
-
     let rpc_predicate_string = format!("{:?}", req.predicate);
 
     let predicate = InfluxRpcPredicateBuilder::default()
@@ -1032,7 +1112,8 @@ where
     // if big queries are causing a significant latency in TTFB.
 
     // Build the plans
-    let series_plan = Planner::new(&ctx)
+    let series_plan = Planner::new(ctx)
+        .with_influxrpc_options(influxrpc_options)
         .read_filter(db, predicate)
         .await
         .map_err(|e| Box::new(e) as _)
@@ -1059,13 +1140,13 @@ async fn query_group_impl<D>(
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
     gby_agg: GroupByAndAggregate,
-    span_ctx: Option<SpanContext>,
+    influxrpc_options: InfluxRpcOptions,
+    ctx: &IOxExecutionContext,
 ) -> Result<Vec<ReadResponse>, Error>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
 {
     let db_name = db_name.as_str();
-    let ctx = db.new_query_context(span_ctx);
 
     let rpc_predicate_string = format!("{:?}", rpc_predicate);
 
@@ -1077,7 +1158,7 @@ where
         })?
         .build();
 
-    let planner = Planner::new(&ctx);
+    let planner = Planner::new(ctx).with_influxrpc_options(influxrpc_options);
     let grouped_series_set_plan = match gby_agg {
         GroupByAndAggregate::Columns { agg, group_columns } => {
             planner.read_group(db, predicate, agg, group_columns).await
@@ -1119,7 +1200,8 @@ async fn field_names_impl<D>(
     measurement: Option<String>,
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
-    span_ctx: Option<SpanContext>,
+    influxrpc_options: InfluxRpcOptions,
+    ctx: &IOxExecutionContext,
 ) -> Result<FieldList>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -1136,9 +1218,9 @@ where
         .build();
 
     let db_name = db_name.as_str();
-    let ctx = db.new_query_context(span_ctx);
 
-    let field_list_plan = Planner::new(&ctx)
+    let field_list_plan = Planner::new(ctx)
+        .with_influxrpc_options(influxrpc_options)
         .field_columns(db, predicate)
         .await
         .map_err(|e| Box::new(e) as _)
@@ -1160,7 +1242,7 @@ async fn materialise_measurement_names<D>(
     db: Arc<D>,
     db_name: DatabaseName<'static>,
     measurement_exprs: Vec<LiteralOrRegex>,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<BTreeSet<String>, Error>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -1174,14 +1256,7 @@ where
 
     // Materialise all measurements
     if measurement_exprs.is_empty() {
-        let resp = measurement_name_impl(
-            Arc::clone(&db),
-            db_name.clone(),
-            None,
-            None,
-            span_ctx.clone(),
-        )
-        .await?;
+        let resp = measurement_name_impl(Arc::clone(&db), db_name.clone(), None, None, ctx).await?;
         for name in resp.values {
             names
                 .insert(String::from_utf8(name).expect("table/measurement name to be valid UTF-8"));
@@ -1220,7 +1295,7 @@ where
                         Some(Predicate {
                             root: Some(regex_node),
                         }),
-                        span_ctx.clone(),
+                        ctx,
                     )
                     .await?;
                     for name in resp.values {
@@ -1246,7 +1321,7 @@ async fn materialise_tag_keys<D>(
     db_name: DatabaseName<'static>,
     measurement_name: String,
     tag_key_predicate: tag_key_predicate::Value,
-    span_ctx: Option<SpanContext>,
+    ctx: &IOxExecutionContext,
 ) -> Result<BTreeSet<String>, Error>
 where
     D: QueryDatabase + ExecutionContextProvider + 'static,
@@ -1271,7 +1346,7 @@ where
         Some(measurement_name),
         None,
         None,
-        span_ctx.clone(),
+        ctx,
     )
     .await?
     .values
@@ -2817,6 +2892,87 @@ mod tests {
         assert_contains!(response_string, "Sugar we are going down");
     }
 
+    #[test]
+    fn test_max_query_chunks_header() {
+        let service = StorageService {
+            db_store: Arc::new(TestDatabaseStore::new()),
+            max_query_chunks: Some(10),
+            max_query_series: None,
+            disable_pruning: false,
+        };
+
+        let mut request = tonic::Request::new(());
+        assert_eq!(
+            service.influxrpc_options(&request).unwrap(),
+            InfluxRpcOptions {
+                max_chunks: Some(10),
+                request_max_chunks: None,
+                max_series: None,
+                request_max_series: None,
+                disable_pruning: false,
+            }
+        );
+
+        request
+            .metadata_mut()
+            .insert(MAX_QUERY_CHUNKS_HEADER, "3".parse().unwrap());
+        assert_eq!(
+            service.influxrpc_options(&request).unwrap(),
+            InfluxRpcOptions {
+                max_chunks: Some(10),
+                request_max_chunks: Some(3),
+                max_series: None,
+                request_max_series: None,
+                disable_pruning: false,
+            }
+        );
+
+        request
+            .metadata_mut()
+            .insert(MAX_QUERY_CHUNKS_HEADER, "lots".parse().unwrap());
+        let err = service.influxrpc_options(&request).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidMaxQueryChunks { value } if value == "lots"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_max_query_series_header() {
+        let service = StorageService {
+            db_store: Arc::new(TestDatabaseStore::new()),
+            max_query_chunks: None,
+            max_query_series: Some(1000),
+            disable_pruning: false,
+        };
+
+        let mut request = tonic::Request::new(());
+        request
+            .metadata_mut()
+            .insert(MAX_QUERY_SERIES_HEADER, "10".parse().unwrap());
+        assert_eq!(
+            service.influxrpc_options(&request).unwrap(),
+            InfluxRpcOptions {
+                max_chunks: None,
+                request_max_chunks: None,
+                max_series: Some(1000),
+                request_max_series: Some(10),
+                disable_pruning: false,
+            }
+        );
+
+        request
+            .metadata_mut()
+            .insert(MAX_QUERY_SERIES_HEADER, "-1".parse().unwrap());
+        let err = service.influxrpc_options(&request).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidMaxQuerySeries { value } if value == "-1"),
+            "{}",
+            err
+        );
+    }
+
     fn make_timestamp_range(start: i64, end: i64) -> TimestampRange {
         TimestampRange { start, end }
     }
@@ -2933,6 +3089,9 @@ mod tests {
                 .add_service(
                     crate::influxdb_ioxd::server_type::database::rpc::storage::make_server(
                         Arc::clone(&test_storage),
+                        None,
+                        None,
+                        false,
                     ),
                 );
 
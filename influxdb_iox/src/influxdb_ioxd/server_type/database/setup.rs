@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use object_store::ObjectStore;
 use observability_deps::tracing::warn;
-use server::{ApplicationState, Server, ServerConfig};
+use server::{ApplicationState, QuerySettings, Server, ServerConfig};
 use snafu::{ResultExt, Snafu};
 use trace::TraceCollector;
 
@@ -41,6 +41,13 @@ pub async fn make_application(
     Ok(Arc::new(ApplicationState::new(
         object_storage,
         config.num_worker_threads,
+        QuerySettings {
+            scan_parallelism: config.scan_parallelism,
+            slow_query_threshold: config.slow_query_threshold,
+            target_batch_size: config.query_batch_size,
+            disable_pruning: config.disable_pruning,
+            chunk_read_metrics: config.chunk_read_metrics,
+        },
         trace_collector,
     )))
 }
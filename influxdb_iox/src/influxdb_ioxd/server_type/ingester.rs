@@ -12,7 +12,7 @@ use trace::TraceCollector;
 
 use crate::influxdb_ioxd::{
     http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
-    rpc::{serve_builder, setup_builder, RpcBuilderInput},
+    rpc::{add_service, serve_builder, setup_builder, RpcBuilderInput},
     server_type::{common_state::CommonServerState, RpcError, ServerType},
 };
 use ingester::handler::IngestHandler;
@@ -22,6 +22,7 @@ pub struct IngesterServerType<I: IngestHandler> {
     server: IngesterServer<I>,
     shutdown: CancellationToken,
     trace_collector: Option<Arc<dyn TraceCollector>>,
+    debug_config: Option<serde_json::Value>,
 }
 
 impl<I: IngestHandler> IngesterServerType<I> {
@@ -30,8 +31,15 @@ impl<I: IngestHandler> IngesterServerType<I> {
             server,
             shutdown: CancellationToken::new(),
             trace_collector: common_state.trace_collector(),
+            debug_config: None,
         }
     }
+
+    /// Serve `config`, which must not contain any secrets, at `/debug/config`.
+    pub fn with_debug_config(mut self, config: serde_json::Value) -> Self {
+        self.debug_config = Some(config);
+        self
+    }
 }
 
 #[async_trait]
@@ -48,6 +56,11 @@ impl<I: IngestHandler + Sync + Send + Debug + 'static> ServerType for IngesterSe
         self.trace_collector.as_ref().map(Arc::clone)
     }
 
+    /// Returns the redacted ingester config, if provided.
+    fn debug_config(&self) -> Option<serde_json::Value> {
+        self.debug_config.clone()
+    }
+
     /// Just return "not found".
     async fn route_http_request(
         &self,
@@ -56,9 +69,10 @@ impl<I: IngestHandler + Sync + Send + Debug + 'static> ServerType for IngesterSe
         Err(IoxHttpError::NotFound)
     }
 
-    /// Provide a placeholder gRPC service.
+    /// Serve the ingester's Arrow Flight gRPC service.
     async fn server_grpc(self: Arc<Self>, builder_input: RpcBuilderInput) -> Result<(), RpcError> {
         let builder = setup_builder!(builder_input, self);
+        add_service!(builder, self.server.grpc().flight_service());
         serve_builder!(builder);
 
         Ok(())
@@ -48,6 +48,13 @@ pub trait ServerType: std::fmt::Debug + Send + Sync + 'static {
     /// Trace collector associated with the server, if any.
     fn trace_collector(&self) -> Option<Arc<dyn TraceCollector>>;
 
+    /// The resolved server config, with secrets redacted, served at `/debug/config`.
+    ///
+    /// The default implementation returns `None`, in which case the endpoint is not served.
+    fn debug_config(&self) -> Option<serde_json::Value> {
+        None
+    }
+
     /// Route given HTTP request.
     ///
     /// Note that this is only called if none of the shared, common routes (e.g. `/health`) match.
@@ -59,6 +66,12 @@ pub trait ServerType: std::fmt::Debug + Send + Sync + 'static {
     /// Construct and serve gRPC subsystem.
     async fn server_grpc(self: Arc<Self>, builder_input: RpcBuilderInput) -> Result<(), RpcError>;
 
+    /// Drain in-flight requests ahead of shutdown.
+    ///
+    /// Called once when shutdown is requested, before the HTTP and gRPC frontends stop accepting
+    /// connections. The default implementation does nothing.
+    async fn drain(&self) {}
+
     /// Join shutdown worker.
     ///
     /// This MUST NOT exit before `shutdown` is called, otherwise the server is deemed to be dead and the process will exit.
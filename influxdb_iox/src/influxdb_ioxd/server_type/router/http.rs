@@ -106,7 +106,8 @@ mod tests {
                 assert_write_to_invalid_database,
             },
             test_utils::{
-                assert_health, assert_metrics, assert_tracing, check_response, TestServer,
+                assert_health, assert_metrics, assert_profiling_disabled, assert_tracing,
+                check_response, TestServer,
             },
         },
         server_type::common_state::CommonServerState,
@@ -124,6 +125,23 @@ mod tests {
         assert_metrics(test_server().await).await;
     }
 
+    #[tokio::test]
+    async fn test_profiling_disabled() {
+        assert_profiling_disabled(test_server().await).await;
+    }
+
+    #[cfg(feature = "pprof")]
+    #[tokio::test]
+    async fn test_cpu_profile() {
+        use crate::influxdb_ioxd::http::test_utils::assert_cpu_profile;
+
+        let test_server = test_server().await;
+        let server_type = Arc::clone(test_server.server_type());
+        drop(test_server);
+
+        assert_cpu_profile(TestServer::new_with_profiling(server_type, true)).await;
+    }
+
     #[tokio::test]
     async fn test_tracing() {
         assert_tracing(test_server().await).await;
@@ -1,14 +1,17 @@
 use std::{
     fmt::{Debug, Display},
     sync::Arc,
+    time::Duration,
 };
 
 use async_trait::async_trait;
 use hyper::{Body, Request, Response};
 use metric::Registry;
+use observability_deps::tracing::*;
 use router2::{dml_handlers::DmlHandler, server::RouterServer};
 use tokio_util::sync::CancellationToken;
 use trace::TraceCollector;
+use write_buffer::core::WriteBufferWriting;
 
 use crate::influxdb_ioxd::{
     http::error::{HttpApiError, HttpApiErrorSource},
@@ -21,6 +24,8 @@ pub struct RouterServerType<D> {
     server: RouterServer<D>,
     shutdown: CancellationToken,
     trace_collector: Option<Arc<dyn TraceCollector>>,
+    drain: Option<(Duration, Arc<dyn WriteBufferWriting>)>,
+    debug_config: Option<serde_json::Value>,
 }
 
 impl<D> RouterServerType<D> {
@@ -29,8 +34,32 @@ impl<D> RouterServerType<D> {
             server,
             shutdown: CancellationToken::new(),
             trace_collector: common_state.trace_collector(),
+            drain: None,
+            debug_config: None,
         }
     }
+
+    /// On shutdown, wait up to `timeout` for in-flight writes to complete
+    /// before flushing `write_buffer`.
+    pub fn with_graceful_drain(
+        mut self,
+        timeout: Duration,
+        write_buffer: Arc<dyn WriteBufferWriting>,
+    ) -> Self {
+        self.drain = Some((timeout, write_buffer));
+        self
+    }
+
+    /// Serve `config`, which must not contain any secrets, at `/debug/config`.
+    pub fn with_debug_config(mut self, config: serde_json::Value) -> Self {
+        self.debug_config = Some(config);
+        self
+    }
+
+    /// Get a reference to the router server.
+    pub fn server(&self) -> &RouterServer<D> {
+        &self.server
+    }
 }
 
 #[async_trait]
@@ -50,6 +79,16 @@ where
         self.trace_collector.as_ref().map(Arc::clone)
     }
 
+    /// Returns the redacted router config, if provided, with the fields that
+    /// were reloaded since set to their current values.
+    fn debug_config(&self) -> Option<serde_json::Value> {
+        let mut config = self.debug_config.clone()?;
+        if let serde_json::Value::Object(reloaded) = self.server.http().reloadable_config() {
+            apply_reloaded_config(&mut config, &reloaded);
+        }
+        Some(config)
+    }
+
     /// Dispatches `req` to the router [`HttpDelegate`] delegate.
     ///
     /// [`HttpDelegate`]: router2::server::http::HttpDelegate
@@ -75,6 +114,26 @@ where
         Ok(())
     }
 
+    /// Reject new writes and wait for in-flight writes to be committed to the
+    /// write buffer, before flushing the write buffer producer.
+    async fn drain(&self) {
+        let (timeout, write_buffer) = match &self.drain {
+            Some(v) => v,
+            None => return,
+        };
+
+        info!(?timeout, "draining in-flight writes");
+        if !self.server.drain(*timeout).await {
+            warn!(
+                ?timeout,
+                "in-flight writes did not complete before drain deadline"
+            );
+        }
+
+        write_buffer.flush().await;
+        info!("write buffer flushed");
+    }
+
     async fn join(self: Arc<Self>) {
         self.shutdown.cancelled().await;
     }
@@ -100,6 +159,57 @@ impl std::error::Error for IoxHttpErrorAdaptor {}
 
 impl HttpApiErrorSource for IoxHttpErrorAdaptor {
     fn to_http_api_error(&self) -> HttpApiError {
-        HttpApiError::new(self.0.as_status_code(), self.to_string())
+        let err = HttpApiError::new(self.0.as_status_code(), self.to_string());
+        match self.0.retry_after() {
+            Some(d) => err.with_retry_after(d),
+            None => err,
+        }
+    }
+}
+
+/// Set the fields of `config`, at any depth, that are named like a field of
+/// `reloaded` to its value.
+fn apply_reloaded_config(
+    config: &mut serde_json::Value,
+    reloaded: &serde_json::Map<String, serde_json::Value>,
+) {
+    if let serde_json::Value::Object(fields) = config {
+        for (key, value) in fields.iter_mut() {
+            match reloaded.get(key) {
+                Some(reloaded_value) => *value = reloaded_value.clone(),
+                None => apply_reloaded_config(value, reloaded),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_reloaded_config() {
+        let mut config = json!({
+            "run_config": {"max_http_request_size": 1024, "debug_profiling": false},
+            "max_tag_bytes": 42,
+            "max_points_per_request": null,
+        });
+        let reloaded = json!({
+            "max_http_request_size": 2048,
+            "max_tag_bytes": 42,
+            "max_points_per_request": 100,
+            "log_filter": "debug",
+        });
+
+        apply_reloaded_config(&mut config, reloaded.as_object().unwrap());
+        assert_eq!(
+            config,
+            json!({
+                "run_config": {"max_http_request_size": 2048, "debug_profiling": false},
+                "max_tag_bytes": 42,
+                "max_points_per_request": 100,
+            })
+        );
     }
 }
@@ -42,6 +42,7 @@ pub struct TestServerType {
     trace_collector: Option<Arc<dyn TraceCollector>>,
     shutdown: CancellationToken,
     test_action: TestAction,
+    debug_config: Option<serde_json::Value>,
 }
 
 impl TestServerType {
@@ -55,8 +56,15 @@ impl TestServerType {
             trace_collector,
             shutdown: CancellationToken::new(),
             test_action,
+            debug_config: None,
         }
     }
+
+    /// Serve `config` at `/debug/config`.
+    pub fn with_debug_config(mut self, config: serde_json::Value) -> Self {
+        self.debug_config = Some(config);
+        self
+    }
 }
 
 #[async_trait]
@@ -71,6 +79,10 @@ impl ServerType for TestServerType {
         self.trace_collector.clone()
     }
 
+    fn debug_config(&self) -> Option<serde_json::Value> {
+        self.debug_config.clone()
+    }
+
     async fn route_http_request(
         &self,
         req: Request<Body>,
@@ -50,3 +50,92 @@ pub async fn test_no_rows() {
     let batch = query_results.next().await.unwrap();
     assert!(batch.is_none());
 }
+
+#[tokio::test]
+pub async fn test_export_table() {
+    let server_fixture = ServerFixture::create_shared(ServerType::Database).await;
+
+    let mut write_client = server_fixture.write_client();
+    let mut management_client = server_fixture.management_client();
+
+    let scenario = Scenario::new();
+    scenario.create_database(&mut management_client).await;
+    scenario.load_data(&mut write_client).await;
+
+    let mut client = server_fixture.flight_client();
+
+    let locations = client
+        .export_table(scenario.database_name(), "cpu_load_short")
+        .await
+        .unwrap();
+    assert!(!locations.is_empty());
+
+    // the server uses a file object store rooted at the fixture directory
+    let mut exported = vec![];
+    for location in &locations {
+        let file = std::fs::File::open(server_fixture.dir().join(location)).unwrap();
+        let reader = arrow::ipc::reader::StreamReader::try_new(file).unwrap();
+        for batch in reader {
+            exported.push(batch.unwrap());
+        }
+    }
+
+    // the export should contain the same rows as a streaming query
+    let schema = exported[0].schema();
+    let columns: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    let sql_query = format!("select {} from cpu_load_short", columns.join(", "));
+
+    let batches = client
+        .perform_query(scenario.database_name(), sql_query)
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let expected = arrow_util::display::pretty_format_batches(&batches).unwrap();
+    let expected: Vec<_> = expected.lines().collect();
+    assert_batches_sorted_eq!(expected, &exported);
+}
+
+#[tokio::test]
+pub async fn test_multi_database_query() {
+    let server_fixture = ServerFixture::create_shared(ServerType::Database).await;
+
+    let mut write_client = server_fixture.write_client();
+
+    let db_names = [rand_name(), rand_name()];
+    for (db_name, user) in db_names.iter().zip([1, 2]) {
+        create_readable_database(db_name, server_fixture.grpc_channel()).await;
+        write_client
+            .write_lp(db_name, format!("cpu,region=west user={} 100", user), 0)
+            .await
+            .unwrap();
+    }
+
+    let mut client = server_fixture.flight_client();
+
+    // The tables of each database are qualified with its name
+    let sql_query = format!(
+        "select user from \"{}\".cpu union all select user from \"{}\".cpu",
+        db_names[0], db_names[1]
+    );
+    let batches = client
+        .perform_multi_database_query(&db_names[0], vec![db_names[1].clone()], sql_query)
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let expected = [
+        "+------+", "| user |", "+------+", "| 1    |", "| 2    |", "+------+",
+    ];
+    assert_batches_sorted_eq!(expected, &batches);
+
+    // Unknown databases are rejected
+    client
+        .perform_multi_database_query(&db_names[0], vec![rand_name()], "select 1")
+        .await
+        .unwrap_err();
+}
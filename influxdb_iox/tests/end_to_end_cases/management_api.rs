@@ -8,6 +8,7 @@ use crate::{
         },
     },
 };
+use arrow_util::assert_batches_sorted_eq;
 use bytes::Bytes;
 use data_types::chunk_metadata::ChunkId;
 use generated_types::google::protobuf::{Duration, Empty};
@@ -1853,3 +1854,90 @@ async fn test_compact_os_partition() {
     assert_ne!(new_chunk_id, chunk_ids[0]);
     assert_ne!(new_chunk_id, chunk_ids[1]);
 }
+
+#[tokio::test]
+async fn test_downsample_table() {
+    let fixture = ServerFixture::create_shared(ServerType::Database).await;
+    let mut write_client = fixture.write_client();
+    let mut management_client = fixture.management_client();
+    let mut operations_client = fixture.operations_client();
+    let mut flight_client = fixture.flight_client();
+
+    let db_name = rand_name();
+    create_readable_database(&db_name, fixture.grpc_channel()).await;
+
+    let lp_lines = vec![
+        "h2o,state=MA,city=Boston temp=70.0 10000000000",
+        "h2o,state=MA,city=Boston temp=72.0 20000000000",
+        "h2o,state=MA,city=Boston temp=74.0 70000000000",
+        "h2o,state=CA,city=LA temp=90.0 30000000000",
+    ];
+    write_client
+        .write_lp(&db_name, lp_lines.join("\n"), 0)
+        .await
+        .expect("write succeded");
+
+    let expected = [
+        "+--------+-------+------+----------------------+",
+        "| city   | state | temp | time                 |",
+        "+--------+-------+------+----------------------+",
+        "| Boston | MA    | 71   | 1970-01-01T00:01:00Z |",
+        "| Boston | MA    | 74   | 1970-01-01T00:02:00Z |",
+        "| LA     | CA    | 90   | 1970-01-01T00:01:00Z |",
+        "+--------+-------+------+----------------------+",
+    ];
+
+    // A second run finds no newly completed window and writes nothing
+    for _ in 0..2 {
+        let iox_operation = management_client
+            .downsample_table(
+                &db_name,
+                "h2o",
+                &db_name,
+                "h2o_1m",
+                DownsampleAggregate::Mean,
+                std::time::Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+
+        match &iox_operation.metadata.job {
+            Some(Job::Downsample(job)) => {
+                assert_eq!(&job.db_name, &db_name);
+                assert_eq!(job.source_table.as_str(), "h2o");
+                assert_eq!(&job.target_db_name, &db_name);
+                assert_eq!(job.target_table.as_str(), "h2o_1m");
+            }
+            job => panic!("unexpected job returned {:#?}", job),
+        }
+
+        let operation_id = iox_operation.operation.id();
+        operations_client
+            .wait_operation(operation_id, Some(std::time::Duration::from_secs(1)))
+            .await
+            .expect("failed to wait operation");
+
+        let batches = flight_client
+            .perform_query(&db_name, "select city, state, temp, time from h2o_1m")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    // The window must be positive
+    let err = management_client
+        .downsample_table(
+            &db_name,
+            "h2o",
+            &db_name,
+            "h2o_1m",
+            DownsampleAggregate::Mean,
+            std::time::Duration::from_secs(0),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)), "{:?}", err);
+}
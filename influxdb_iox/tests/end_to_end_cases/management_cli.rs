@@ -984,7 +984,7 @@ async fn test_list_partition_chunks() {
         .stdout(
             predicate::str::contains(r#""partitionKey": "cpu""#)
                 .and(predicate::str::contains(r#""tableName": "cpu""#))
-                .and(predicate::str::contains(r#""order": 1"#))
+                .and(predicate::str::contains(r#""order": "1""#))
                 .and(predicate::str::contains(
                     r#""storage": "CHUNK_STORAGE_OPEN_MUTABLE_BUFFER""#,
                 ))
@@ -1,20 +1,20 @@
-use std::{convert::TryFrom, sync::Arc};
+use std::{collections::BTreeMap, convert::TryFrom, sync::Arc};
 
 use futures_util::stream;
-use futures_util::stream::StreamExt;
-use serde::Serialize;
+use futures_util::stream::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tonic::Streaming;
 
 use arrow::{
     array::Array,
-    datatypes::Schema,
+    datatypes::{DataType, Field, IntervalUnit, Schema, UnionMode},
     ipc::{self, reader},
     record_batch::RecordBatch,
 };
 use arrow_flight::{
-    flight_service_client::FlightServiceClient, utils::flight_data_to_arrow_batch, FlightData,
-    HandshakeRequest, Ticket,
+    flight_service_client::FlightServiceClient, utils::flight_data_to_arrow_batch, Action,
+    FlightData, HandshakeRequest, Ticket,
 };
 
 use crate::connection::Connection;
@@ -55,6 +55,38 @@ pub enum Error {
     /// Arrow Flight handshake failed.
     #[error("Handshake failed")]
     HandshakeFailed,
+
+    /// The server did not return a result for an action.
+    #[error("no result returned for action")]
+    NoActionResult,
+
+    /// The `app_metadata` of a message, naming the table of the message,
+    /// is not valid UTF-8.
+    #[error("invalid table name in app_metadata: {0}")]
+    InvalidTableName(#[from] std::string::FromUtf8Error),
+
+    /// A record batch returned by the server does not match the schema
+    /// sent in the first message of the response.
+    #[error(
+        "record batch with {actual} field nodes does not match the schema of the query with {expected}"
+    )]
+    SchemaMismatch {
+        /// The number of field nodes of the schema
+        expected: usize,
+        /// The number of field nodes of the record batch
+        actual: usize,
+    },
+
+    /// The buffers of a record batch returned by the server do not match the
+    /// type of a column in the schema sent in the first message of the
+    /// response.
+    #[error("record batch buffers do not match the type {data_type:?} of column {column}")]
+    BufferMismatch {
+        /// The name of the column
+        column: String,
+        /// The type of the column in the schema
+        data_type: DataType,
+    },
 }
 
 /// An IOx Arrow Flight gRPC API client.
@@ -86,6 +118,9 @@ pub enum Error {
 #[derive(Debug)]
 pub struct Client {
     inner: FlightServiceClient<Connection>,
+
+    /// The protocol version negotiated by the last successful handshake
+    protocol_version: Option<u64>,
 }
 
 impl Client {
@@ -93,9 +128,16 @@ impl Client {
     pub fn new(channel: Connection) -> Self {
         Self {
             inner: FlightServiceClient::new(channel),
+            protocol_version: None,
         }
     }
 
+    /// Returns the protocol version negotiated by the last successful
+    /// [`handshake`](Self::handshake), if any.
+    pub fn protocol_version(&self) -> Option<u64> {
+        self.protocol_version
+    }
+
     /// Query the given database with the given SQL query, and return a
     /// [`PerformQuery`] instance that streams Arrow `RecordBatch` results.
     pub async fn perform_query(
@@ -103,7 +145,62 @@ impl Client {
         database_name: impl Into<String> + Send,
         sql_query: impl Into<String> + Send,
     ) -> Result<PerformQuery, Error> {
-        PerformQuery::new(self, database_name.into(), sql_query.into()).await
+        PerformQuery::new(self, database_name.into(), vec![], sql_query.into()).await
+    }
+
+    /// Query the given databases together with the given SQL query, and
+    /// return a [`PerformQuery`] instance that streams Arrow `RecordBatch`
+    /// results. The tables of each database are in a schema named after it,
+    /// e.g. `SELECT * FROM db1.t UNION ALL SELECT * FROM db2.t`.
+    pub async fn perform_multi_database_query(
+        &mut self,
+        database_name: impl Into<String> + Send,
+        other_database_names: Vec<String>,
+        sql_query: impl Into<String> + Send,
+    ) -> Result<PerformQuery, Error> {
+        PerformQuery::new(
+            self,
+            database_name.into(),
+            other_database_names,
+            sql_query.into(),
+        )
+        .await
+    }
+
+    /// Send `ticket` as is, for example a ticket of an ingester querying
+    /// several tables, and return a [`PerformQuery`] instance that streams
+    /// the Arrow `RecordBatch` results of every table of the response.
+    ///
+    /// Unlike [`perform_query`](Self::perform_query), the response may be
+    /// empty, and it may hold the data of several tables, see
+    /// [`PerformQuery::next_table_batch`].
+    pub async fn perform_ticket_query(
+        &mut self,
+        ticket: impl Into<Vec<u8>> + Send,
+    ) -> Result<PerformQuery, Error> {
+        PerformQuery::new_ticket(
+            self,
+            Ticket {
+                ticket: ticket.into(),
+            },
+        )
+        .await
+    }
+
+    /// Like [`perform_query`](Self::perform_query), but first performs a
+    /// [`handshake`](Self::handshake) if this client has not completed one.
+    ///
+    /// The negotiated protocol version is cached, so later queries made with
+    /// this client skip the handshake round-trip.
+    pub async fn handshake_and_query(
+        &mut self,
+        database_name: impl Into<String> + Send,
+        sql_query: impl Into<String> + Send,
+    ) -> Result<PerformQuery, Error> {
+        if self.protocol_version.is_none() {
+            self.handshake().await?;
+        }
+        self.perform_query(database_name, sql_query).await
     }
 
     /// Perform a handshake with the server, as defined by the Arrow Flight API.
@@ -117,17 +214,38 @@ impl Client {
             .handshake(stream::iter(vec![request.clone()]))
             .await?
             .into_inner();
-        if request.payload.eq(&response
-            .next()
-            .await
-            .ok_or(Error::HandshakeFailed)??
-            .payload)
-        {
+        let response = response.next().await.ok_or(Error::HandshakeFailed)??;
+        if request.payload.eq(&response.payload) {
+            self.protocol_version = Some(response.protocol_version);
             Result::Ok(())
         } else {
             Result::Err(Error::HandshakeFailed)
         }
     }
+
+    /// Export the contents of a table to object storage as one or more Arrow
+    /// IPC streams, returning the locations of the written objects in order.
+    pub async fn export_table(
+        &mut self,
+        database_name: impl Into<String> + Send,
+        table_name: impl Into<String> + Send,
+    ) -> Result<Vec<String>, Error> {
+        let export = ExportInfo {
+            database_name: database_name.into(),
+            table_name: table_name.into(),
+        };
+
+        let action = Action {
+            r#type: "export_table".to_string(),
+            body: serde_json::to_vec(&export)?,
+        };
+        let mut response = self.inner.do_action(action).await?.into_inner();
+
+        let result = response.next().await.ok_or(Error::NoActionResult)??;
+        let result: ExportResult = serde_json::from_slice(&result.body)?;
+
+        Ok(result.locations)
+    }
 }
 
 // TODO: this should be shared
@@ -135,27 +253,66 @@ impl Client {
 struct ReadInfo {
     database_name: String,
     sql_query: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other_database_names: Vec<String>,
+}
+
+// TODO: this should be shared
+#[derive(Serialize, Debug)]
+struct ExportInfo {
+    database_name: String,
+    table_name: String,
+}
+
+// TODO: this should be shared
+#[derive(Deserialize, Debug)]
+struct ExportResult {
+    locations: Vec<String>,
 }
 
 /// A struct that manages the stream of Arrow `RecordBatch` results from an
 /// Arrow Flight query. Created by calling the `perform_query` method on a
 /// Flight [`Client`].
+///
+/// A response may interleave the results of several tables, each message
+/// carrying the name of its table in its `app_metadata`. The results are
+/// demultiplexed by table, each table starting with a schema message of its
+/// own. The results of an SQL query are those of a single table with an
+/// empty name.
 #[derive(Debug)]
 pub struct PerformQuery {
+    tables: BTreeMap<String, TableDecoder>,
+    response: Streaming<FlightData>,
+}
+
+/// The schema and dictionaries of the results of a table of a response
+#[derive(Debug)]
+struct TableDecoder {
     schema: Arc<Schema>,
     dictionaries_by_field: Vec<Option<Arc<dyn Array>>>,
-    response: Streaming<FlightData>,
+}
+
+impl TableDecoder {
+    fn new(schema: Arc<Schema>) -> Self {
+        let dictionaries_by_field = vec![None; schema.fields().len()];
+        Self {
+            schema,
+            dictionaries_by_field,
+        }
+    }
 }
 
 impl PerformQuery {
     pub(crate) async fn new(
         flight: &mut Client,
         database_name: String,
+        other_database_names: Vec<String>,
         sql_query: String,
     ) -> Result<Self, Error> {
         let query = ReadInfo {
             database_name,
             sql_query,
+            other_database_names,
         };
 
         let t = Ticket {
@@ -164,13 +321,20 @@ impl PerformQuery {
         let mut response = flight.inner.do_get(t).await?.into_inner();
 
         let flight_data_schema = response.next().await.ok_or(Error::NoSchema)??;
+        let table_name = String::from_utf8(flight_data_schema.app_metadata.clone())?;
         let schema = Arc::new(Schema::try_from(&flight_data_schema)?);
 
-        let dictionaries_by_field = vec![None; schema.fields().len()];
+        Ok(Self {
+            tables: BTreeMap::from([(table_name, TableDecoder::new(schema))]),
+            response,
+        })
+    }
+
+    pub(crate) async fn new_ticket(flight: &mut Client, ticket: Ticket) -> Result<Self, Error> {
+        let response = flight.inner.do_get(ticket).await?.into_inner();
 
         Ok(Self {
-            schema,
-            dictionaries_by_field,
+            tables: BTreeMap::new(),
             response,
         })
     }
@@ -178,53 +342,553 @@ impl PerformQuery {
     /// Returns the next `RecordBatch` available for this query, or `None` if
     /// there are no further results available.
     pub async fn next(&mut self) -> Result<Option<RecordBatch>, Error> {
-        let Self {
-            schema,
-            dictionaries_by_field,
-            response,
-        } = self;
+        Ok(self.next_table_batch().await?.map(|(_, batch)| batch))
+    }
+
+    /// Returns the next `RecordBatch` available for this query with the name
+    /// of its table, or `None` if there are no further results available.
+    pub async fn next_table_batch(&mut self) -> Result<Option<(String, RecordBatch)>, Error> {
+        let Self { tables, response } = self;
 
-        let mut data = match response.next().await {
+        next_record_batch(tables, response).await
+    }
+
+    /// Collect and return all `RecordBatch`es into a `Vec`
+    pub async fn collect(&mut self) -> Result<Vec<RecordBatch>, Error> {
+        let mut batches = Vec::new();
+        while let Some(data) = self.next().await? {
+            batches.push(data);
+        }
+
+        Ok(batches)
+    }
+
+    /// Collect and return all `RecordBatch`es, keyed by the name of their
+    /// table. Tables without batches are omitted.
+    pub async fn collect_tables(&mut self) -> Result<BTreeMap<String, Vec<RecordBatch>>, Error> {
+        let mut tables: BTreeMap<String, Vec<RecordBatch>> = BTreeMap::new();
+        while let Some((table_name, batch)) = self.next_table_batch().await? {
+            tables.entry(table_name).or_default().push(batch);
+        }
+
+        Ok(tables)
+    }
+}
+
+/// Decodes the next `RecordBatch` of `response`, a stream of `FlightData`,
+/// returning it with the name of its table.
+///
+/// Schema messages set the schema of their table in `tables`, replacing any
+/// previous one, and dictionaries are read into the dictionaries of their
+/// table. Returns [`Error::NoSchema`] for a message of a table without a
+/// schema, and [`Error::SchemaMismatch`] or [`Error::BufferMismatch`] if the
+/// record batch does not match the schema of its table, rather than decoding
+/// it into invalid arrays.
+async fn next_record_batch<S>(
+    tables: &mut BTreeMap<String, TableDecoder>,
+    response: &mut S,
+) -> Result<Option<(String, RecordBatch)>, Error>
+where
+    S: Stream<Item = Result<FlightData, tonic::Status>> + Unpin + Send,
+{
+    loop {
+        let data = match response.next().await {
             Some(d) => d?,
             None => return Ok(None),
         };
 
-        let mut message = ipc::root_as_message(&data.data_header[..])
+        let table_name = String::from_utf8(data.app_metadata.clone())?;
+        let message = ipc::root_as_message(&data.data_header[..])
             .map_err(|e| Error::InvalidFlatbuffer(e.to_string()))?;
 
-        while message.header_type() == ipc::MessageHeader::DictionaryBatch {
-            reader::read_dictionary(
-                &data.data_body,
-                message
-                    .header_as_dictionary_batch()
-                    .ok_or(Error::CouldNotGetDictionaryBatch)?,
-                schema,
-                dictionaries_by_field,
-            )?;
-
-            data = match response.next().await {
-                Some(d) => d?,
-                None => return Ok(None),
+        match message.header_type() {
+            ipc::MessageHeader::Schema => {
+                let schema = Arc::new(Schema::try_from(&data)?);
+                tables.insert(table_name, TableDecoder::new(schema));
+            }
+            ipc::MessageHeader::DictionaryBatch => {
+                let table = tables.get_mut(&table_name).ok_or(Error::NoSchema)?;
+                reader::read_dictionary(
+                    &data.data_body,
+                    message
+                        .header_as_dictionary_batch()
+                        .ok_or(Error::CouldNotGetDictionaryBatch)?,
+                    &table.schema,
+                    &mut table.dictionaries_by_field,
+                )?;
+            }
+            _ => {
+                let table = tables.get(&table_name).ok_or(Error::NoSchema)?;
+                if let Some(batch) = message.header_as_record_batch() {
+                    validate_record_batch(table.schema.fields(), &batch)?;
+                }
+
+                let batch = flight_data_to_arrow_batch(
+                    &data,
+                    Arc::clone(&table.schema),
+                    &table.dictionaries_by_field,
+                )?;
+                return Ok(Some((table_name, batch)));
+            }
+        }
+    }
+}
+
+/// Checks the field nodes and buffers of a record batch message against the
+/// columns of its schema: their number, and that the buffers of every column
+/// are large enough for its type and length, so that decoding the message
+/// cannot read past its body.
+fn validate_record_batch(fields: &[Field], batch: &ipc::RecordBatch<'_>) -> Result<(), Error> {
+    let nodes = batch.nodes().unwrap_or_default();
+    let expected = fields
+        .iter()
+        .map(|field| num_field_nodes(field.data_type()))
+        .sum();
+    if nodes.len() != expected {
+        return Err(Error::SchemaMismatch {
+            expected,
+            actual: nodes.len(),
+        });
+    }
+
+    let mut nodes = nodes.iter();
+    let mut buffers = batch.buffers().unwrap_or_default().iter();
+    for field in fields {
+        validate_column(field, &mut nodes, &mut buffers)?;
+    }
+    // buffers left over belong to no column
+    match (buffers.next(), fields.last()) {
+        (Some(_), Some(field)) => Err(buffer_mismatch(field)),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the buffers of the column `field`, and of its children, against
+/// its type and the length of its field node
+fn validate_column<'a>(
+    field: &Field,
+    nodes: &mut impl Iterator<Item = &'a ipc::FieldNode>,
+    buffers: &mut impl Iterator<Item = &'a ipc::Buffer>,
+) -> Result<(), Error> {
+    // the number of field nodes was checked upfront
+    let node = nodes.next().expect("field node of column");
+    let len = usize::try_from(node.length()).map_err(|_| buffer_mismatch(field))?;
+    let mut next_buffer = |min_len: usize| match buffers.next() {
+        Some(buffer) if usize::try_from(buffer.length()).map_or(false, |l| l >= min_len) => Ok(()),
+        _ => Err(buffer_mismatch(field)),
+    };
+    let bitmap_len = (len + 7) / 8;
+    let offsets_len = |width: usize| match len {
+        0 => 0,
+        len => len.saturating_add(1).saturating_mul(width),
+    };
+
+    // unions and null columns have no validity bitmap
+    match field.data_type() {
+        DataType::Null | DataType::Union(..) => {}
+        _ if node.null_count() > 0 => next_buffer(bitmap_len)?,
+        _ => next_buffer(0)?,
+    }
+
+    match field.data_type() {
+        DataType::Null => {}
+        DataType::Boolean => next_buffer(bitmap_len)?,
+        DataType::Utf8 | DataType::Binary => {
+            next_buffer(offsets_len(4))?;
+            next_buffer(0)?;
+        }
+        DataType::LargeUtf8 | DataType::LargeBinary => {
+            next_buffer(offsets_len(8))?;
+            next_buffer(0)?;
+        }
+        DataType::List(child) | DataType::Map(child, _) => {
+            next_buffer(offsets_len(4))?;
+            validate_column(child, nodes, buffers)?;
+        }
+        DataType::LargeList(child) => {
+            next_buffer(offsets_len(8))?;
+            validate_column(child, nodes, buffers)?;
+        }
+        DataType::FixedSizeList(child, _) => validate_column(child, nodes, buffers)?,
+        DataType::Struct(children) => {
+            for child in children {
+                validate_column(child, nodes, buffers)?;
+            }
+        }
+        DataType::Union(children, mode) => {
+            next_buffer(len)?;
+            if matches!(mode, UnionMode::Dense) {
+                next_buffer(len.saturating_mul(4))?;
+            }
+            for child in children {
+                validate_column(child, nodes, buffers)?;
+            }
+        }
+        DataType::Dictionary(key_type, _) => {
+            next_buffer(len.saturating_mul(fixed_width(key_type).unwrap_or_default()))?
+        }
+        data_type => next_buffer(len.saturating_mul(fixed_width(data_type).unwrap_or_default()))?,
+    }
+
+    Ok(())
+}
+
+fn buffer_mismatch(field: &Field) -> Error {
+    Error::BufferMismatch {
+        column: field.name().clone(),
+        data_type: field.data_type().clone(),
+    }
+}
+
+/// Returns the width in bytes of the values of a fixed-width `data_type`, if
+/// known
+fn fixed_width(data_type: &DataType) -> Option<usize> {
+    match data_type {
+        DataType::Int8 | DataType::UInt8 => Some(1),
+        DataType::Int16 | DataType::UInt16 | DataType::Float16 => Some(2),
+        DataType::Int32
+        | DataType::UInt32
+        | DataType::Float32
+        | DataType::Date32
+        | DataType::Time32(_)
+        | DataType::Interval(IntervalUnit::YearMonth) => Some(4),
+        DataType::Int64
+        | DataType::UInt64
+        | DataType::Float64
+        | DataType::Date64
+        | DataType::Time64(_)
+        | DataType::Timestamp(_, _)
+        | DataType::Duration(_)
+        | DataType::Interval(IntervalUnit::DayTime) => Some(8),
+        DataType::Decimal(_, _) => Some(16),
+        DataType::FixedSizeBinary(width) => usize::try_from(*width).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the number of IPC field nodes of a column of `data_type`, one for
+/// the column itself and one for every nested child
+fn num_field_nodes(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _)
+        | DataType::Map(field, _) => 1 + num_field_nodes(field.data_type()),
+        DataType::Struct(fields) | DataType::Union(fields, _) => {
+            1 + fields
+                .iter()
+                .map(|field| num_field_nodes(field.data_type()))
+                .sum::<usize>()
+        }
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use arrow::{
+        array::{ArrayRef, Int32Array, Int64Array},
+        ipc::writer::IpcWriteOptions,
+    };
+    use arrow_flight::{
+        flight_service_server::{FlightService, FlightServiceServer},
+        utils::{flight_data_from_arrow_batch, flight_data_from_arrow_schema},
+        ActionType, Criteria, Empty, FlightDescriptor, FlightInfo, HandshakeResponse, PutResult,
+        SchemaResult,
+    };
+    use tonic::{Request, Response, Status};
+
+    use super::*;
+    use crate::connection::Builder;
+
+    type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + Sync + 'static>>;
+
+    /// A Flight service answering every query with `batch`, counting the
+    /// handshakes it performs
+    #[derive(Debug)]
+    struct TestFlightService {
+        batch: RecordBatch,
+        handshakes: Arc<AtomicUsize>,
+    }
+
+    #[tonic::async_trait]
+    impl FlightService for TestFlightService {
+        type HandshakeStream = TonicStream<HandshakeResponse>;
+        type ListFlightsStream = TonicStream<FlightInfo>;
+        type DoGetStream = TonicStream<FlightData>;
+        type DoPutStream = TonicStream<PutResult>;
+        type DoActionStream = TonicStream<arrow_flight::Result>;
+        type ListActionsStream = TonicStream<ActionType>;
+        type DoExchangeStream = TonicStream<FlightData>;
+
+        async fn handshake(
+            &self,
+            request: Request<Streaming<HandshakeRequest>>,
+        ) -> Result<Response<Self::HandshakeStream>, Status> {
+            self.handshakes.fetch_add(1, Ordering::SeqCst);
+
+            let request = request
+                .into_inner()
+                .next()
+                .await
+                .ok_or_else(|| Status::invalid_argument("no handshake request"))??;
+            let response = HandshakeResponse {
+                protocol_version: request.protocol_version,
+                payload: request.payload,
             };
+            Ok(Response::new(Box::pin(stream::iter(vec![Ok(response)]))))
+        }
 
-            message = ipc::root_as_message(&data.data_header[..])
-                .map_err(|e| Error::InvalidFlatbuffer(e.to_string()))?;
+        async fn do_get(
+            &self,
+            _request: Request<Ticket>,
+        ) -> Result<Response<Self::DoGetStream>, Status> {
+            let options = IpcWriteOptions::default();
+            let schema = flight_data_from_arrow_schema(&self.batch.schema(), &options);
+            let (_, batch) = flight_data_from_arrow_batch(&self.batch, &options);
+            Ok(Response::new(Box::pin(stream::iter(vec![
+                Ok(schema),
+                Ok(batch),
+            ]))))
         }
 
-        Ok(Some(flight_data_to_arrow_batch(
-            &data,
-            Arc::clone(schema),
-            dictionaries_by_field,
-        )?))
+        async fn list_flights(
+            &self,
+            _request: Request<Criteria>,
+        ) -> Result<Response<Self::ListFlightsStream>, Status> {
+            Err(Status::unimplemented("list_flights"))
+        }
+
+        async fn get_flight_info(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<FlightInfo>, Status> {
+            Err(Status::unimplemented("get_flight_info"))
+        }
+
+        async fn get_schema(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<SchemaResult>, Status> {
+            Err(Status::unimplemented("get_schema"))
+        }
+
+        async fn do_put(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoPutStream>, Status> {
+            Err(Status::unimplemented("do_put"))
+        }
+
+        async fn do_action(
+            &self,
+            _request: Request<Action>,
+        ) -> Result<Response<Self::DoActionStream>, Status> {
+            Err(Status::unimplemented("do_action"))
+        }
+
+        async fn list_actions(
+            &self,
+            _request: Request<Empty>,
+        ) -> Result<Response<Self::ListActionsStream>, Status> {
+            Err(Status::unimplemented("list_actions"))
+        }
+
+        async fn do_exchange(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoExchangeStream>, Status> {
+            Err(Status::unimplemented("do_exchange"))
+        }
     }
 
-    /// Collect and return all `RecordBatch`es into a `Vec`
-    pub async fn collect(&mut self) -> Result<Vec<RecordBatch>, Error> {
-        let mut batches = Vec::new();
-        while let Some(data) = self.next().await? {
-            batches.push(data);
+    fn to_flight_data(batch: &RecordBatch) -> FlightData {
+        let (dictionaries, data) = flight_data_from_arrow_batch(batch, &IpcWriteOptions::default());
+        assert!(dictionaries.is_empty());
+        data
+    }
+
+    #[tokio::test]
+    async fn test_next_record_batch_schema_mismatch() {
+        let column = || Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef;
+        let batch = RecordBatch::try_from_iter(vec![("a", column())]).unwrap();
+        let extra_column =
+            RecordBatch::try_from_iter(vec![("a", column()), ("b", column())]).unwrap();
+
+        let mut tables = BTreeMap::from([(String::new(), TableDecoder::new(batch.schema()))]);
+        let mut response = stream::iter(vec![
+            Ok(to_flight_data(&batch)),
+            Ok(to_flight_data(&extra_column)),
+        ]);
+
+        let (_, decoded) = next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded, batch);
+
+        let err = next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::SchemaMismatch {
+                    expected: 1,
+                    actual: 2
+                }
+            ),
+            "{}",
+            err
+        );
+
+        assert!(next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_next_record_batch_buffer_mismatch() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+        )])
+        .unwrap();
+        // same number of field nodes and buffers, but half the width
+        let narrow_column = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+        )])
+        .unwrap();
+
+        let mut tables = BTreeMap::from([(String::new(), TableDecoder::new(batch.schema()))]);
+        let mut response = stream::iter(vec![
+            Ok(to_flight_data(&batch)),
+            Ok(to_flight_data(&narrow_column)),
+        ]);
+
+        let (_, decoded) = next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded, batch);
+
+        let err = next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::BufferMismatch {
+                    column,
+                    data_type: DataType::Int64,
+                } if column == "a"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_record_batch_demultiplexes_tables() {
+        let column = |values: Vec<i64>| Arc::new(Int64Array::from(values)) as ArrayRef;
+        let cpu = RecordBatch::try_from_iter(vec![("usage", column(vec![1, 2]))]).unwrap();
+        let cpu2 = RecordBatch::try_from_iter(vec![("usage", column(vec![3]))]).unwrap();
+        let mem =
+            RecordBatch::try_from_iter(vec![("free", column(vec![4])), ("used", column(vec![5]))])
+                .unwrap();
+
+        let options = IpcWriteOptions::default();
+        let tagged = |table_name: &str, mut data: FlightData| {
+            data.app_metadata = table_name.as_bytes().to_vec();
+            Ok(data)
+        };
+        let mut response = stream::iter(vec![
+            tagged(
+                "cpu",
+                flight_data_from_arrow_schema(&cpu.schema(), &options),
+            ),
+            tagged("cpu", to_flight_data(&cpu)),
+            tagged(
+                "mem",
+                flight_data_from_arrow_schema(&mem.schema(), &options),
+            ),
+            tagged("mem", to_flight_data(&mem)),
+            tagged("cpu", to_flight_data(&cpu2)),
+        ]);
+
+        let mut tables = BTreeMap::new();
+        let mut decoded = vec![];
+        while let Some(batch) = next_record_batch(&mut tables, &mut response).await.unwrap() {
+            decoded.push(batch);
         }
+        assert_eq!(
+            decoded,
+            vec![
+                ("cpu".to_string(), cpu),
+                ("mem".to_string(), mem),
+                ("cpu".to_string(), cpu2),
+            ]
+        );
 
-        Ok(batches)
+        // A message of a table whose schema was never sent cannot be decoded
+        let mut response = stream::iter(vec![tagged("disk", to_flight_data(&cpu2))]);
+        let err = next_record_batch(&mut tables, &mut response)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NoSchema), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_query_caches_handshake() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+        let handshakes = Arc::new(AtomicUsize::new(0));
+        let service = TestFlightService {
+            batch: batch.clone(),
+            handshakes: Arc::clone(&handshakes),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = stream::unfold(listener, |listener| async move {
+            let socket = listener.accept().await.map(|(socket, _)| socket);
+            Some((socket, listener))
+        });
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(FlightServiceServer::new(service))
+                .serve_with_incoming(incoming),
+        );
+
+        let connection = Builder::default()
+            .build(format!("http://{}", addr))
+            .await
+            .unwrap();
+        let mut client = Client::new(connection);
+        assert_eq!(client.protocol_version(), None);
+
+        for _ in 0..2 {
+            let batches = client
+                .handshake_and_query("db", "select * from t")
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            assert_eq!(batches, vec![batch.clone()]);
+        }
+
+        assert_eq!(client.protocol_version(), Some(0));
+        assert_eq!(handshakes.load(Ordering::SeqCst), 1);
     }
 }
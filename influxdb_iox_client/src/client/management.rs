@@ -5,7 +5,7 @@ use crate::{
     google::{longrunning::IoxOperation, OptionalField},
 };
 use bytes::Bytes;
-use std::convert::TryInto;
+use std::{convert::TryInto, time::Duration};
 use uuid::Uuid;
 
 /// Re-export generated_types
@@ -553,4 +553,34 @@ impl Client {
             .unwrap_field("operation")?
             .try_into()?)
     }
+
+    /// Downsample a table into a table of the same or another database,
+    /// aggregating the windows completed since the previous run
+    pub async fn downsample_table(
+        &mut self,
+        db_name: impl Into<String> + Send,
+        source_table: impl Into<String> + Send,
+        target_db_name: impl Into<String> + Send,
+        target_table: impl Into<String> + Send,
+        aggregate: DownsampleAggregate,
+        window: Duration,
+    ) -> Result<IoxOperation, Error> {
+        let response = self
+            .inner
+            .downsample_table(DownsampleTableRequest {
+                db_name: db_name.into(),
+                source_table: source_table.into(),
+                target_db_name: target_db_name.into(),
+                target_table: target_table.into(),
+                aggregate: aggregate.into(),
+                window_nanoseconds: window.as_nanos().try_into().unwrap_or(u64::MAX),
+            })
+            .await?;
+
+        Ok(response
+            .into_inner()
+            .operation
+            .unwrap_field("operation")?
+            .try_into()?)
+    }
 }
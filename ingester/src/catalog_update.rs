@@ -0,0 +1,315 @@
+//! Update the catalog after persisting data to object storage
+
+use iox_catalog::interface::{Catalog, ParquetFile, Timestamp};
+use observability_deps::tracing::warn;
+use parquet_file::metadata::IoxMetadata;
+use snafu::{ResultExt, Snafu};
+use std::{future::Future, time::Duration};
+use uuid::Uuid;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display(
+        "Error adding parquet file {} to the catalog: {}",
+        object_store_id,
+        source
+    ))]
+    AddParquetFile {
+        object_store_id: Uuid,
+        source: iox_catalog::interface::Error,
+    },
+}
+
+/// A specialized `Error` for Ingester's catalog update errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// How often and how long to back off when a catalog update fails with a transient error
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one
+    pub max_attempts: usize,
+    /// Wait before the first retry, doubled for every following retry
+    pub init_backoff: Duration,
+    /// Upper bound of the wait between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            init_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Record the parquet file of `file_size_bytes` described by `metadata` in the catalog,
+/// retrying transient errors
+///
+/// An attempt failing with a transient error may still have recorded the file, e.g. if the
+/// connection dropped before its response arrived, so a retry finding the file already
+/// recorded with the same `metadata` succeeds with the existing record.
+pub async fn add_parquet_file(
+    catalog: &dyn Catalog,
+    metadata: &IoxMetadata,
+    min_time: Timestamp,
+    max_time: Timestamp,
+    file_size_bytes: i64,
+    retry: RetryConfig,
+) -> Result<ParquetFile> {
+    let result = retry_catalog_op(retry, "add parquet file", || {
+        catalog.parquet_files().create(
+            metadata.sequencer_id,
+            metadata.table_id,
+            metadata.partition_id,
+            metadata.object_store_id,
+            metadata.min_sequence_number,
+            metadata.max_sequence_number,
+            min_time,
+            max_time,
+            file_size_bytes,
+        )
+    })
+    .await;
+
+    let result = match result {
+        Err(e @ iox_catalog::interface::Error::FileExists { .. }) => {
+            let existing = retry_catalog_op(retry, "get parquet file", || {
+                catalog
+                    .parquet_files()
+                    .get_by_object_store_id(metadata.object_store_id)
+            })
+            .await;
+            match existing {
+                Ok(Some(file)) if recorded_from(&file, metadata) => Ok(file),
+                Ok(_) => Err(e),
+                Err(e) => Err(e),
+            }
+        }
+        result => result,
+    };
+
+    result.context(AddParquetFileSnafu {
+        object_store_id: metadata.object_store_id,
+    })
+}
+
+/// Return whether `file` is the catalog record of the parquet file described by `metadata`
+fn recorded_from(file: &ParquetFile, metadata: &IoxMetadata) -> bool {
+    file.object_store_id == metadata.object_store_id
+        && file.sequencer_id == metadata.sequencer_id
+        && file.table_id == metadata.table_id
+        && file.partition_id == metadata.partition_id
+        && file.min_sequence_number == metadata.min_sequence_number
+        && file.max_sequence_number == metadata.max_sequence_number
+}
+
+/// Run `op` until it succeeds, fails with an error that is not
+/// [retryable](iox_catalog::interface::Error::is_retryable) or has been attempted
+/// `retry.max_attempts` times
+async fn retry_catalog_op<T, F, Fut>(
+    retry: RetryConfig,
+    op_name: &str,
+    mut op: F,
+) -> Result<T, iox_catalog::interface::Error>
+where
+    F: FnMut() -> Fut + Send,
+    Fut: Future<Output = Result<T, iox_catalog::interface::Error>> + Send,
+{
+    let mut backoff = retry.init_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match op().await {
+            Err(e) if e.is_retryable() && attempt < retry.max_attempts => {
+                warn!(
+                    %e,
+                    op_name,
+                    attempt,
+                    ?backoff,
+                    "Transient catalog error, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(retry.max_backoff);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iox_catalog::{
+        interface::{NamespaceId, PartitionId, SequenceNumber, SequencerId, TableId},
+        mem::MemCatalog,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use time::Time;
+
+    fn retry_config() -> RetryConfig {
+        RetryConfig {
+            max_attempts: 5,
+            init_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    fn metadata() -> IoxMetadata {
+        IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: Time::from_timestamp(0, 0),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "mydata".into(),
+            sequencer_id: SequencerId::new(2),
+            table_id: TableId::new(3),
+            table_name: "temperature".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "somehour".into(),
+            time_of_first_write: Time::from_timestamp(0, 0),
+            time_of_last_write: Time::from_timestamp(0, 0),
+            min_sequence_number: SequenceNumber::new(5),
+            max_sequence_number: SequenceNumber::new(6),
+        }
+    }
+
+    fn retryable_error() -> iox_catalog::interface::Error {
+        iox_catalog::interface::Error::SqlxError {
+            source: sqlx::Error::PoolTimedOut,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_success() {
+        let catalog = MemCatalog::new();
+        let metadata = metadata();
+        let attempts = AtomicUsize::new(0);
+        let (catalog, metadata, attempts) = (&catalog, &metadata, &attempts);
+
+        let parquet_file = retry_catalog_op(retry_config(), "test", move || async move {
+            // fail twice with a connection error, then hit the catalog
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                return Err(retryable_error());
+            }
+            catalog
+                .parquet_files()
+                .create(
+                    metadata.sequencer_id,
+                    metadata.table_id,
+                    metadata.partition_id,
+                    metadata.object_store_id,
+                    metadata.min_sequence_number,
+                    metadata.max_sequence_number,
+                    Timestamp::new(1),
+                    Timestamp::new(2),
+                    1234,
+                )
+                .await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(parquet_file.object_store_id, metadata.object_store_id);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_errors() {
+        let catalog = MemCatalog::new();
+        let metadata = metadata();
+
+        add_parquet_file(
+            &catalog,
+            &metadata,
+            Timestamp::new(1),
+            Timestamp::new(2),
+            1234,
+            retry_config(),
+        )
+        .await
+        .unwrap();
+
+        // recording the same file again violates a unique constraint
+        let attempts = AtomicUsize::new(0);
+        let err = retry_catalog_op(retry_config(), "test", || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            catalog.parquet_files().create(
+                metadata.sequencer_id,
+                metadata.table_id,
+                metadata.partition_id,
+                metadata.object_store_id,
+                metadata.min_sequence_number,
+                metadata.max_sequence_number,
+                Timestamp::new(1),
+                Timestamp::new(2),
+                1234,
+            )
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            err,
+            iox_catalog::interface::Error::FileExists { object_store_id } if object_store_id == metadata.object_store_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn adding_an_already_recorded_file_succeeds() {
+        let catalog = MemCatalog::new();
+        let metadata = metadata();
+        let add = |metadata: IoxMetadata| {
+            let catalog = &catalog;
+            async move {
+                add_parquet_file(
+                    catalog,
+                    &metadata,
+                    Timestamp::new(1),
+                    Timestamp::new(2),
+                    1234,
+                    retry_config(),
+                )
+                .await
+            }
+        };
+
+        // e.g. the first attempt recorded the file but its response was lost
+        let recorded = add(metadata.clone()).await.unwrap();
+        let retried = add(metadata.clone()).await.unwrap();
+        assert_eq!(retried, recorded);
+
+        // a different file with the same object store id is still an error
+        let other = IoxMetadata {
+            partition_id: PartitionId::new(5),
+            ..metadata
+        };
+        let err = add(other).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::AddParquetFile {
+                source: iox_catalog::interface::Error::FileExists { .. },
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let attempts = AtomicUsize::new(0);
+        let attempts = &attempts;
+
+        let err = retry_catalog_op(retry_config(), "test", move || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(retryable_error())
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 5);
+        assert!(err.is_retryable());
+    }
+}
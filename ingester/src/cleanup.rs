@@ -0,0 +1,268 @@
+//! Delete the parquet files that are no longer needed from object storage
+
+use crate::persist::SNAPSHOT_PREFIX;
+use iox_catalog::{
+    interface::{Catalog, SequencerId, Timestamp},
+    is_deletable,
+};
+use object_store::{
+    path::{ObjectStorePath, Path},
+    ObjectStore, ObjectStoreApi,
+};
+use observability_deps::tracing::info;
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Error listing the objects of object store: {}", source))]
+    ListingObjects { source: object_store::Error },
+
+    #[snafu(display("Error deleting object {} from object store: {}", path, source))]
+    DeletingObject {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display(
+        "Error looking up parquet file {} in the catalog: {}",
+        object_store_id,
+        source
+    ))]
+    LookingUpParquetFile {
+        object_store_id: Uuid,
+        source: iox_catalog::interface::Error,
+    },
+}
+
+/// A specialized `Error` for Ingester's cleanup errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Delete the parquet files persisted by the given sequencers from object storage that were
+/// uploaded before `cutoff` and are no longer needed, as their catalog record is missing or
+/// was marked for deletion before `cutoff`: see [`is_deletable`]. Return the number of files
+/// deleted.
+///
+/// Files uploaded after `cutoff` are kept even without a catalog record, as they may be
+/// about to be recorded.
+pub async fn delete_unneeded_parquet_files(
+    catalog: &dyn Catalog,
+    object_store: &ObjectStore,
+    sequencers: &BTreeSet<SequencerId>,
+    cutoff: Timestamp,
+) -> Result<usize> {
+    // The files are stored under <namespace>/<table>/<sequencer>/<partition>/<id>.parquet
+    let mut partitions = vec![];
+    for namespace in list_dirs(object_store, &object_store.new_path()).await? {
+        if last_part(&namespace) == SNAPSHOT_PREFIX {
+            continue;
+        }
+        for table in list_dirs(object_store, &namespace).await? {
+            for sequencer in list_dirs(object_store, &table).await? {
+                let owned = last_part(&sequencer)
+                    .parse()
+                    .map_or(false, |id| sequencers.contains(&SequencerId::new(id)));
+                if owned {
+                    partitions.extend(list_dirs(object_store, &sequencer).await?);
+                }
+            }
+        }
+    }
+
+    let mut deleted = 0;
+    for partition in partitions {
+        let objects = object_store
+            .list_with_delimiter(&partition)
+            .await
+            .context(ListingObjectsSnafu)?
+            .objects;
+        for object in objects {
+            let object_store_id = match last_part(&object.location)
+                .strip_suffix(".parquet")
+                .and_then(|id| Uuid::parse_str(id).ok())
+            {
+                Some(id) => id,
+                None => continue,
+            };
+            if Timestamp::new(object.last_modified.timestamp_nanos()) >= cutoff {
+                continue;
+            }
+            let deletable = is_deletable(catalog, object_store_id, cutoff)
+                .await
+                .context(LookingUpParquetFileSnafu { object_store_id })?;
+            if deletable {
+                info!(%object_store_id, "Deleting parquet file that is no longer needed");
+                object_store
+                    .delete(&object.location)
+                    .await
+                    .context(DeletingObjectSnafu {
+                        path: object.location.to_raw(),
+                    })?;
+                deleted += 1;
+            }
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Return the directories directly under `prefix`
+async fn list_dirs(object_store: &ObjectStore, prefix: &Path) -> Result<Vec<Path>> {
+    Ok(object_store
+        .list_with_delimiter(prefix)
+        .await
+        .context(ListingObjectsSnafu)?
+        .common_prefixes)
+}
+
+/// Return the last part of `path`, its file name or its innermost directory
+fn last_part(path: &Path) -> String {
+    path.to_raw()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+    use iox_catalog::{
+        interface::{KafkaPartition, SequenceNumber},
+        mem::MemCatalog,
+    };
+    use std::time::Duration;
+    use time::{SystemProvider, TimeProvider};
+
+    #[tokio::test]
+    async fn deletes_marked_and_unknown_files_of_owned_sequencers() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let query_pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("cpu", namespace.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(1))
+            .await
+            .unwrap();
+        let other_sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(2))
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("1970-01-01", sequencer.id, table.id)
+            .await
+            .unwrap();
+
+        let object_store = ObjectStore::new_in_memory();
+        let put = |sequencer_id: SequencerId, object_store_id: Uuid| {
+            let mut path = object_store.new_path();
+            path.push_all_dirs(&[
+                namespace.id.to_string().as_str(),
+                table.id.to_string().as_str(),
+                sequencer_id.to_string().as_str(),
+                partition.id.to_string().as_str(),
+            ]);
+            path.set_file_name(format!("{}.parquet", object_store_id));
+            let object_store = &object_store;
+            async move {
+                object_store
+                    .put(&path, Bytes::from_static(b"parquet"))
+                    .await
+                    .unwrap();
+            }
+        };
+        let record = |object_store_id: Uuid| {
+            catalog.parquet_files().create(
+                sequencer.id,
+                table.id,
+                partition.id,
+                object_store_id,
+                SequenceNumber::new(1),
+                SequenceNumber::new(1),
+                Timestamp::new(1),
+                Timestamp::new(1),
+                1,
+            )
+        };
+
+        // A file marked for deletion, a file in use and a file without catalog record, plus a
+        // file of another sequencer and a snapshot file
+        let marked = Uuid::new_v4();
+        put(sequencer.id, marked).await;
+        record(marked).await.unwrap();
+        catalog
+            .parquet_files()
+            .mark_for_deletion(marked, Timestamp::new(1))
+            .await
+            .unwrap();
+        let in_use = Uuid::new_v4();
+        put(sequencer.id, in_use).await;
+        record(in_use).await.unwrap();
+        let unknown = Uuid::new_v4();
+        put(sequencer.id, unknown).await;
+        put(other_sequencer.id, Uuid::new_v4()).await;
+        let mut snapshot = object_store.new_path();
+        snapshot.push_all_dirs(&[SNAPSHOT_PREFIX, "1"]);
+        snapshot.set_file_name(format!("{}.parquet", Uuid::new_v4()));
+        object_store
+            .put(&snapshot, Bytes::from_static(b"parquet"))
+            .await
+            .unwrap();
+
+        let list = || async {
+            let mut paths: Vec<_> = object_store
+                .list(None)
+                .await
+                .unwrap()
+                .try_concat()
+                .await
+                .unwrap()
+                .iter()
+                .map(|p| p.to_raw())
+                .collect();
+            paths.sort();
+            paths
+        };
+        let before = list().await;
+        let sequencers = BTreeSet::from([sequencer.id]);
+
+        // The objects of the in-memory store are as new as the listing, so nothing was
+        // uploaded before a cutoff in the past
+        let now = SystemProvider::new().now();
+        let past = Timestamp::new((now - Duration::from_secs(60)).timestamp_nanos());
+        let deleted = delete_unneeded_parquet_files(&catalog, &object_store, &sequencers, past)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 0);
+        assert_eq!(list().await, before);
+
+        let future = Timestamp::new((now + Duration::from_secs(60)).timestamp_nanos());
+        let deleted = delete_unneeded_parquet_files(&catalog, &object_store, &sequencers, future)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+        let after = list().await;
+        assert_eq!(after.len(), before.len() - 2);
+        for id in [marked, unknown] {
+            assert!(!after.iter().any(|p| p.contains(&id.to_string())));
+        }
+        assert!(after.iter().any(|p| p.contains(&in_use.to_string())));
+    }
+}
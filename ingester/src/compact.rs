@@ -3,14 +3,16 @@
 use crate::data::{PersistingBatch, QueryableBatch};
 use arrow::{array::TimestampNanosecondArray, record_batch::RecordBatch};
 use datafusion::{error::DataFusionError, physical_plan::SendableRecordBatchStream};
-use iox_catalog::interface::NamespaceId;
+use iox_catalog::interface::{NamespaceId, SequenceNumber};
+use observability_deps::tracing::warn;
 use parquet_file::metadata::IoxMetadata;
 use query::{
+    compute_sort_key_for_chunks, compute_sort_key_for_chunks_with_metrics,
     exec::{Executor, ExecutorType},
     frontend::reorg::ReorgPlanner,
-    QueryChunkMeta,
+    QueryChunkMeta, SortKeyMetrics,
 };
-use schema::TIME_COLUMN_NAME;
+use schema::{sort::SortKey, Schema, TIME_COLUMN_NAME};
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::sync::Arc;
 use time::{Time, TimeProvider};
@@ -53,6 +55,62 @@ pub enum Error {
 /// A specialized `Error` for Ingester's Compact errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Bounds the input of a compaction, so that a partition with a lot of
+/// buffered data is persisted over several bounded compactions, one file each,
+/// rather than one huge one. The inputs are the snapshots of the partition: see
+/// [`IngesterData::persist_partition`](crate::data::IngesterData::persist_partition).
+///
+/// Inputs are only ever compacted together with their neighbours in sequence
+/// number order: the output of a compaction covers the sequence numbers from
+/// the min to the max of its inputs, so compacting two inputs but not one
+/// between them would produce an output overlapping the skipped input, and
+/// deduplication could no longer tell which write was the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionInputSelection {
+    /// Maximum total size, in bytes, of the inputs compacted together. Inputs
+    /// larger than this are never compacted with others.
+    pub target_total_bytes: u64,
+    /// Maximum number of inputs compacted together
+    pub max_files: usize,
+}
+
+impl CompactionInputSelection {
+    /// Split `inputs` into runs of inputs contiguous in the order of their
+    /// `min_sequence_number`, each within `target_total_bytes` and
+    /// `max_files`. Every input is in exactly one run: an input larger than
+    /// the target is a run of its own.
+    ///
+    /// `size` returns the size in bytes of an input.
+    pub fn runs<T>(
+        &self,
+        mut inputs: Vec<T>,
+        size: impl Fn(&T) -> u64,
+        min_sequence_number: impl Fn(&T) -> SequenceNumber,
+    ) -> Vec<Vec<T>> {
+        inputs.sort_by_key(&min_sequence_number);
+
+        let mut runs = vec![];
+        let mut run: Vec<T> = vec![];
+        let mut run_bytes = 0_u64;
+        for input in inputs {
+            let input_bytes = size(&input);
+            if !run.is_empty()
+                && (run.len() >= self.max_files
+                    || run_bytes.saturating_add(input_bytes) > self.target_total_bytes)
+            {
+                runs.push(std::mem::take(&mut run));
+                run_bytes = 0;
+            }
+            run_bytes = run_bytes.saturating_add(input_bytes);
+            run.push(input);
+        }
+        if !run.is_empty() {
+            runs.push(run);
+        }
+        runs
+    }
+}
+
 /// Return min and max for column `time` of the given set of record batches
 pub fn compute_timenanosecond_min_max(batches: &[RecordBatch]) -> Result<(i64, i64)> {
     let mut min_time = i64::MAX;
@@ -155,6 +213,91 @@ pub async fn compact_persisting_batch(
     Ok(Some((output_batches, meta)))
 }
 
+/// Compact a given persisting batch without collecting the compacted data in memory.
+/// Return the stream of compacted data with its metadata
+///
+/// The metadata must be known before the data is written, so its time range is computed
+/// from the uncompacted snapshots and may be wider than that of the compacted data if rows
+/// were removed by deletes.
+///
+/// If the partition already has a `sort_key`, such as the one of a previously persisted
+/// file, it is reused as long as it is still valid for the schema of the batch.
+#[allow(clippy::too_many_arguments)]
+pub async fn compact_persisting_batch_streaming(
+    time_provider: Arc<dyn TimeProvider>,
+    executor: &Executor,
+    namespace_id: i32,
+    namespace_name: &str,
+    table_name: &str,
+    partition_key: &str,
+    batch: Arc<PersistingBatch>,
+    sort_key: Option<&SortKey<'_>>,
+) -> Result<Option<(SendableRecordBatchStream, IoxMetadata)>> {
+    // Nothing to compact
+    if batch.data.data.is_empty() {
+        return Ok(None);
+    }
+
+    // Compute min and max of the `time` column
+    let mut min_time = i64::MAX;
+    let mut max_time = i64::MIN;
+    for snapshot in &batch.data.data {
+        let (min, max) = compute_timenanosecond_min_max_for_one_record_bacth(&snapshot.data)?;
+        min_time = min_time.min(min);
+        max_time = max_time.max(max);
+    }
+
+    // Compute min and max sequence numbers
+    let (min_seq, max_seq) = batch.data.min_max_sequence_numbers();
+
+    let meta = IoxMetadata {
+        object_store_id: batch.object_store_id,
+        creation_timestamp: time_provider.now(),
+        sequencer_id: batch.sequencer_id,
+        namespace_id: NamespaceId::new(namespace_id),
+        namespace_name: Arc::from(namespace_name),
+        table_id: batch.table_id,
+        table_name: Arc::from(table_name),
+        partition_id: batch.partition_id,
+        partition_key: Arc::from(partition_key),
+        time_of_first_write: Time::from_timestamp_nanos(min_time),
+        time_of_last_write: Time::from_timestamp_nanos(max_time),
+        min_sequence_number: min_seq,
+        max_sequence_number: max_seq,
+    };
+
+    // Compact
+    let stream = compact_streaming(executor, Arc::clone(&batch.data), sort_key).await?;
+
+    Ok(Some((stream, meta)))
+}
+
+/// Returns `stored`, the existing sort key of a partition, if it only references columns
+/// of `schema`, extended with any primary key columns of `schema` it does not cover yet
+/// (e.g. tags added since the key was computed). Otherwise, e.g. when a column was
+/// dropped since the key was computed, returns a new sort key computed for `chunks`,
+/// recording in `sort_key_metrics` if provided whether it was computed from their statistics.
+pub(crate) fn sort_key_for_compaction<'a, C>(
+    schema: &'a Schema,
+    chunks: &'a [C],
+    stored: Option<&SortKey<'a>>,
+    sort_key_metrics: Option<&SortKeyMetrics>,
+) -> SortKey<'a>
+where
+    C: QueryChunkMeta,
+{
+    if let Some(stored) = stored {
+        match stored.validate_against(schema) {
+            Ok(()) => return stored.with_missing_primary_key(schema),
+            Err(e) => warn!(%e, sort_key=%stored, "stored sort key is invalid, recomputing"),
+        }
+    }
+    match sort_key_metrics {
+        Some(metrics) => compute_sort_key_for_chunks_with_metrics(schema, chunks, metrics),
+        None => compute_sort_key_for_chunks(schema, chunks),
+    }
+}
+
 /// Compact a given Queryable Batch
 pub async fn compact(
     executor: &Executor,
@@ -181,6 +324,43 @@ pub async fn compact(
     Ok(output_stream)
 }
 
+/// Compact a given Queryable Batch by sorting each of its snapshots separately and
+/// k-way merging the sorted snapshots on the primary key. Unlike [`compact`], the
+/// snapshots are never concatenated, so only one snapshot at a time is sorted in memory.
+///
+/// The data is sorted on `sort_key` if provided and valid for the schema of the data,
+/// otherwise on a sort key computed from the snapshots.
+pub async fn compact_streaming(
+    executor: &Executor,
+    data: Arc<QueryableBatch>,
+    sort_key: Option<&SortKey<'_>>,
+) -> Result<SendableRecordBatchStream> {
+    // One chunk per snapshot, each carrying all tombstones of the batch
+    let chunks = data.split_snapshots();
+
+    // Build logical plan for compaction
+    let ctx = executor.new_context(ExecutorType::Reorg);
+    let schema = data.schema();
+    let sort_key = sort_key_for_compaction(&schema, &chunks, sort_key, None);
+    let (_, logical_plan) = ReorgPlanner::new()
+        .compact_plan(Arc::clone(&schema), chunks.iter().cloned(), sort_key)
+        .context(LogicalPlanSnafu {})?;
+
+    // Build physical plan
+    let physical_plan = ctx
+        .prepare_plan(&logical_plan)
+        .await
+        .context(PhysicalPlanSnafu {})?;
+
+    // Execute the plan and return the compacted stream
+    let output_stream = ctx
+        .execute_stream(physical_plan)
+        .await
+        .context(ExecutePlanSnafu {})?;
+
+    Ok(output_stream)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -615,6 +795,103 @@ mod tests {
         assert_batches_eq!(&expected, &output_batches);
     }
 
+    #[tokio::test]
+    async fn test_compact_streaming_many_batches_same_as_one_shot() {
+        // create many-batches input data
+        let batches = create_batches_with_influxtype_different_columns().await;
+        let tombstones = vec![create_tombstone(1, 1, 1, 100, 0, 200000, "tag1=CT")];
+
+        // build queryable batch from the input batches
+        let compact_batch = make_queryable_batch_with_deletes("test_table", 1, batches, tombstones);
+        assert!(compact_batch.data.len() > 1);
+
+        // compact in one shot and streaming
+        let exc = Executor::new(1);
+        let stream = compact(&exc, Arc::clone(&compact_batch)).await.unwrap();
+        let one_shot_batches = datafusion::physical_plan::common::collect(stream)
+            .await
+            .unwrap();
+        let stream = compact_streaming(&exc, compact_batch, None).await.unwrap();
+        let streaming_batches = datafusion::physical_plan::common::collect(stream)
+            .await
+            .unwrap();
+
+        // verify both compactions produce the same data
+        let one_shot = arrow::util::pretty::pretty_format_batches(&one_shot_batches)
+            .unwrap()
+            .to_string();
+        let streaming = arrow::util::pretty::pretty_format_batches(&streaming_batches)
+            .unwrap()
+            .to_string();
+        assert_eq!(one_shot, streaming);
+
+        // and that the deleted and duplicated rows are gone
+        let expected = vec![
+            "+-----------+------------+------+------+--------------------------------+",
+            "| field_int | field_int2 | tag1 | tag2 | time                           |",
+            "+-----------+------------+------+------+--------------------------------+",
+            "| 10        |            | AL   |      | 1970-01-01T00:00:00.000000050Z |",
+            "| 100       | 100        | AL   | MA   | 1970-01-01T00:00:00.000000050Z |",
+            "| 30        |            | MT   |      | 1970-01-01T00:00:00.000000005Z |",
+            "| 1000      |            | MT   |      | 1970-01-01T00:00:00.000001Z    |",
+            "| 1000      |            | MT   |      | 1970-01-01T00:00:00.000002Z    |",
+            "| 20        |            | MT   |      | 1970-01-01T00:00:00.000007Z    |",
+            "| 5         | 5          | MT   | AL   | 1970-01-01T00:00:00.000005Z    |",
+            "| 10        | 10         | MT   | AL   | 1970-01-01T00:00:00.000007Z    |",
+            "| 1000      | 1000       | MT   | CT   | 1970-01-01T00:00:00.000001Z    |",
+            "+-----------+------------+------+------+--------------------------------+",
+        ];
+        assert_batches_eq!(&expected, &streaming_batches);
+    }
+
+    #[tokio::test]
+    async fn test_sort_key_for_compaction() {
+        let batches = create_batches_with_influxtype_different_columns().await;
+        let compact_batch = make_queryable_batch("test_table", 1, batches);
+        let chunks = compact_batch.split_snapshots();
+        let schema = compact_batch.schema();
+        let computed = compute_sort_key_for_chunks(&schema, &chunks);
+
+        // No stored sort key
+        assert_eq!(
+            sort_key_for_compaction(&schema, &chunks, None, None),
+            computed
+        );
+
+        // A valid stored sort key is reused
+        let mut stored = SortKey::with_capacity(3);
+        stored.with_col("tag2");
+        stored.with_col("tag1");
+        stored.with_col(TIME_COLUMN_NAME);
+        assert_eq!(
+            sort_key_for_compaction(&schema, &chunks, Some(&stored), None),
+            stored
+        );
+
+        // Tags missing from a stored sort key are added before the time column
+        let mut stored = SortKey::with_capacity(2);
+        stored.with_col("tag2");
+        stored.with_col(TIME_COLUMN_NAME);
+        let mut expected = SortKey::with_capacity(3);
+        expected.with_col("tag2");
+        expected.with_col("tag1");
+        expected.with_col(TIME_COLUMN_NAME);
+        assert_eq!(
+            sort_key_for_compaction(&schema, &chunks, Some(&stored), None).to_string(),
+            expected.to_string()
+        );
+
+        // A stored sort key referencing a column that does not exist is recomputed
+        let mut stored = SortKey::with_capacity(3);
+        stored.with_col("dropped");
+        stored.with_col("tag1");
+        stored.with_col(TIME_COLUMN_NAME);
+        assert_eq!(
+            sort_key_for_compaction(&schema, &chunks, Some(&stored), None),
+            computed
+        );
+    }
+
     #[tokio::test]
     #[should_panic(expected = "Schemas compatible")]
     async fn test_compact_many_batches_same_columns_different_types() {
@@ -627,4 +904,86 @@ mod tests {
         // the schema merge will thorw a panic
         compact_batch.schema();
     }
+
+    #[test]
+    fn test_compaction_input_selection() {
+        const KB: u64 = 1024;
+        const MB: u64 = 1024 * KB;
+
+        let selection = CompactionInputSelection {
+            target_total_bytes: MB,
+            max_files: 10,
+        };
+
+        // (file name, min sequence number, size), mixing small and large files
+        let files = vec![
+            ("large_1", 1, 100 * MB),
+            ("small_1", 2, 300 * KB),
+            ("small_2", 3, 100 * KB),
+            ("large_2", 4, 2 * MB),
+            ("small_3", 5, 200 * KB),
+            ("small_4", 6, 400 * KB),
+            ("small_5", 7, 500 * KB),
+        ];
+        let size = |(_, _, size): &(&str, i64, u64)| *size;
+        let seq = |(_, seq, _): &(&str, i64, u64)| SequenceNumber::new(*seq);
+        let names = |files: &[(&'static str, i64, u64)]| -> Vec<&'static str> {
+            files.iter().map(|(name, _, _)| *name).collect()
+        };
+
+        // Every file is in exactly one run of files contiguous in sequence
+        // order, bounded by the 1MB target: 200 + 400 = 600KB, adding the
+        // 500KB file would exceed the target
+        let runs = selection.runs(files.clone(), size, seq);
+        let runs: Vec<_> = runs.iter().map(|run| names(run)).collect();
+        assert_eq!(
+            runs,
+            vec![
+                vec!["large_1"],
+                vec!["small_1", "small_2"],
+                vec!["large_2"],
+                vec!["small_3", "small_4"],
+                vec!["small_5"],
+            ]
+        );
+
+        // The runs are in sequence order, whatever the order of the input
+        let mut reversed = files.clone();
+        reversed.reverse();
+        let runs = selection.runs(reversed, size, seq);
+        assert_eq!(names(&runs[1]), vec!["small_1", "small_2"]);
+
+        // The file count is bounded too
+        let selection = CompactionInputSelection {
+            target_total_bytes: MB,
+            max_files: 2,
+        };
+        let runs = selection.runs(files.clone(), size, seq);
+        let runs: Vec<_> = runs.iter().map(|run| names(run)).collect();
+        assert_eq!(
+            runs,
+            vec![
+                vec!["large_1"],
+                vec!["small_1", "small_2"],
+                vec!["large_2"],
+                vec!["small_3", "small_4"],
+                vec!["small_5"],
+            ]
+        );
+        let selection = CompactionInputSelection {
+            target_total_bytes: 10 * MB,
+            max_files: 2,
+        };
+        let runs = selection.runs(files, size, seq);
+        let runs: Vec<_> = runs.iter().map(|run| names(run)).collect();
+        assert_eq!(
+            runs,
+            vec![
+                vec!["large_1"],
+                vec!["small_1", "small_2"],
+                vec!["large_2", "small_3"],
+                vec!["small_4", "small_5"],
+            ]
+        );
+    }
 }
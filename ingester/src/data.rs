@@ -1,23 +1,62 @@
 //! Data for the lifecycle of the Ingester
 
-use arrow::record_batch::RecordBatch;
-use data_types::delete_predicate::DeletePredicate;
+use crate::catalog_update::{add_parquet_file, RetryConfig};
+use crate::compact::{
+    compact_persisting_batch_streaming, compute_timenanosecond_min_max_for_one_record_bacth,
+    sort_key_for_compaction, CompactionInputSelection,
+};
+use crate::persist::{persist_snapshot, persist_stream, read_persisted, PersistMetrics};
+use crate::query::deduplicate;
+use crate::wal::Wal;
+use arrow::{
+    array::{as_boolean_array, as_primitive_array, as_string_array, ArrayRef, BooleanArray},
+    compute::{cast, filter_record_batch},
+    datatypes::{DataType, Float64Type, Int64Type, TimestampNanosecondType},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use arrow_util::util::merge_record_batches;
+use data_types::{
+    chunk_metadata::ChunkId,
+    delete_predicate::{DeleteExpr, DeletePredicate, Op, Scalar},
+    partition_metadata::Statistics,
+    timestamp::{TimestampRange, MAX_NANO_TIME, MIN_NANO_TIME},
+};
 
 use chrono::{format::StrftimeItems, TimeZone, Utc};
-use dml::DmlOperation;
+use dml::{DmlOperation, DmlWrite};
 use iox_catalog::interface::{
-    Catalog, KafkaPartition, NamespaceId, PartitionId, SequenceNumber, SequencerId, TableId,
-    Timestamp, Tombstone,
+    Catalog, KafkaPartition, KafkaTopicId, Namespace, NamespaceId, ParquetFile, Partition,
+    PartitionId, SequenceNumber, SequencerId, Table, TableId, Timestamp, Tombstone,
 };
-use mutable_batch::column::ColumnData;
+use metric::{Attributes, Metric, U64Gauge};
+use mutable_batch::column::{Column, ColumnData};
 use mutable_batch::MutableBatch;
-use object_store::ObjectStore;
-use parking_lot::RwLock;
+use object_store::{path::ObjectStorePath, ObjectStore};
+use observability_deps::tracing::{info, warn};
+use parking_lot::{Mutex, RwLock};
+use parquet_file::metadata::IoxMetadata;
+use query::{
+    chunks_have_stats, compute_sort_key_for_chunks, exec::Executor, provider::retention_cutoff,
+    QueryChunkMeta,
+};
+use schema::merge::merge_record_batch_schemas;
 use schema::selection::Selection;
-use schema::TIME_COLUMN_NAME;
+use schema::sort::SortKey;
+use schema::{InfluxColumnType, InfluxFieldType, TIME_COLUMN_NAME};
+use serde::Serialize;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::convert::TryFrom;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use time::{SystemProvider, Time, TimeProvider};
 use uuid::Uuid;
 
 #[derive(Debug, Snafu)]
@@ -63,6 +102,64 @@ pub enum Error {
 
     #[snafu(display("Snapshot error: {}", source))]
     Snapshot { source: mutable_batch::Error },
+
+    #[snafu(display("Error computing the time range of a snapshot: {}", source))]
+    SnapshotTimeRange { source: crate::compact::Error },
+
+    #[snafu(display("Error deleting rows from a snapshot: {}", source))]
+    DeleteRows { source: ArrowError },
+
+    #[snafu(display("Error merging the buffered data of table {}: {}", table_name, source))]
+    MergeBatches {
+        table_name: String,
+        source: ArrowError,
+    },
+
+    #[snafu(display(
+        "Error deduplicating the buffered data of table {}: {}",
+        table_name,
+        source
+    ))]
+    Deduplicate {
+        table_name: String,
+        source: crate::query::Error,
+    },
+
+    #[snafu(display("Error writing a snapshot of the buffered data: {}", source))]
+    SnapshotPersist { source: crate::persist::Error },
+
+    #[snafu(display("Error compacting the data of a persisting batch: {}", source))]
+    PersistCompact { source: crate::compact::Error },
+
+    #[snafu(display("Error persisting a parquet file: {}", source))]
+    Persist { source: crate::persist::Error },
+
+    #[snafu(display("Error recording a persisted parquet file in the catalog: {}", source))]
+    PersistCatalogUpdate {
+        source: crate::catalog_update::Error,
+    },
+
+    #[snafu(display("Error accessing the write-ahead log: {}", source))]
+    Wal { source: crate::wal::Error },
+
+    #[snafu(display(
+        "Rejected {} out-of-order rows of a write to table {}: series '{}' at {} is older than its latest point at {}",
+        rejected,
+        table_name,
+        series,
+        timestamp,
+        latest
+    ))]
+    OutOfOrderWrite {
+        table_name: String,
+        series: String,
+        timestamp: i64,
+        latest: i64,
+        rejected: usize,
+    },
+
+    #[snafu(display("Error filtering out-of-order rows: {}", source))]
+    FilterOutOfOrder { source: mutable_batch::Error },
 }
 
 /// A specialized `Error` for Ingester Data errors
@@ -78,6 +175,23 @@ pub struct IngesterData {
     // The content of each SequenceData will get changed when more namespaces and tables
     // get ingested.
     pub(crate) sequencers: BTreeMap<SequencerId, SequencerData>,
+    /// Drop rows matching a delete from the buffered data as soon as the delete
+    /// arrives, rather than only at query and persist time
+    pub(crate) eager_deletes: bool,
+    /// Executor compacting the data being persisted
+    pub(crate) exec: Arc<Executor>,
+    /// Metrics recorded for every parquet file persisted from this ingester
+    pub(crate) persist_metrics: PersistMetrics,
+    /// The namespaces and tables that reject writes with out-of-order timestamps
+    pub(crate) reject_out_of_order: RejectOutOfOrder,
+    /// Local log of the buffered operations, to recover the buffered data after a crash
+    /// without re-reading the write buffer
+    pub(crate) wal: Option<Arc<Wal>>,
+    /// Limit on the number of partitions buffered per namespace
+    pub(crate) partition_limit: PartitionLimit,
+    /// Bounds the data of a partition compacted into each persisted file. Without it, all
+    /// the buffered data of a partition is compacted into one file
+    pub(crate) persist_selection: Option<CompactionInputSelection>,
 }
 
 impl IngesterData {
@@ -85,6 +199,10 @@ impl IngesterData {
     /// be written into the catalog before getting stored in the buffer.
     /// Any writes that create new IOx partitions will have those records
     /// created in the catalog before putting into the buffer.
+    ///
+    /// If the ingester has a write-ahead log, the operation is appended to it first. If a
+    /// write would buffer more partitions for its namespace than the [`PartitionLimit`], the
+    /// partitions with the oldest buffered data are persisted and dropped before it is buffered.
     pub async fn buffer_operation(
         &self,
         sequencer_id: SequencerId,
@@ -94,17 +212,764 @@ impl IngesterData {
             .sequencers
             .get(&sequencer_id)
             .context(SequencerNotFoundSnafu { sequencer_id })?;
-        sequencer_data
-            .buffer_operation(dml_operation, sequencer_id, self.catalog.as_ref())
+        if let Some(wal) = &self.wal {
+            wal.append(sequencer_id, &dml_operation)
+                .await
+                .context(WalSnafu)?;
+        }
+
+        if let (Some(max), DmlOperation::Write(write)) =
+            (self.partition_limit.max_per_namespace, &dml_operation)
+        {
+            self.make_room_for_partitions(sequencer_id, sequencer_data, write, max)
+                .await?;
+        }
+
+        let namespace = dml_operation.namespace().to_string();
+        let result = sequencer_data
+            .buffer_operation(
+                dml_operation,
+                sequencer_id,
+                self.catalog.as_ref(),
+                self.eager_deletes,
+                &self.reject_out_of_order,
+            )
+            .await;
+
+        if let Some(namespace_data) = sequencer_data.namespace(&namespace) {
+            self.partition_limit
+                .record(&namespace, sequencer_id, &namespace_data);
+        }
+        result
+    }
+
+    /// Persist and drop the partitions of the namespace of `write` with the oldest buffered
+    /// data, so that buffering `write` keeps the number of partitions buffered for the
+    /// namespace by the sequencer within `max`. Partitions that fail to persist stay buffered,
+    /// and `write` is then buffered beyond the limit rather than dropped.
+    async fn make_room_for_partitions(
+        &self,
+        sequencer_id: SequencerId,
+        sequencer_data: &SequencerData,
+        write: &DmlWrite,
+        max: usize,
+    ) -> Result<()> {
+        let namespace = write.namespace();
+        let namespace_data = match sequencer_data.namespace(namespace) {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+
+        let mut written = BTreeSet::new();
+        for (table_name, batch) in write.tables() {
+            written.insert((table_name.to_string(), partition_key(batch)?));
+        }
+        let new_partitions = written
+            .iter()
+            .filter(|(table_name, partition_key)| {
+                namespace_data
+                    .table_data(table_name)
+                    .and_then(|t| t.partition_data(partition_key))
+                    .is_none()
+            })
+            .count();
+        let excess = (namespace_data.partition_count() + new_partitions).saturating_sub(max);
+
+        let mut evicted: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (table_name, partition_key) in namespace_data
+            .oldest_partitions()
+            .into_iter()
+            .filter(|partition| !written.contains(partition))
+            .take(excess)
+        {
+            evicted.entry(table_name).or_default().push(partition_key);
+        }
+        for (table_name, partition_keys) in evicted {
+            let result = self
+                .persist_partitions(sequencer_id, namespace, &table_name, &partition_keys)
+                .await;
+            if let Err(e) = result {
+                warn!(
+                    %e,
+                    %sequencer_id,
+                    %namespace,
+                    %table_name,
+                    "Failed to persist partitions to make room for new partitions"
+                );
+            }
+            // Partitions that failed to persist still have data and are kept
+            if let Some(table_data) = namespace_data.table_data(&table_name) {
+                for partition_key in &partition_keys {
+                    table_data.remove_partition_if_empty(partition_key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Persist the data buffered by `sequencer_id` for the partitions `partition_keys` of
+    /// `table_name` in `namespace` in one operation, as one parquet file per partition: data
+    /// of different partitions is never written to the same file. Return the catalog records
+    /// of the files written, skipping partitions without buffered data.
+    ///
+    /// With a [`persist_selection`](Self::persist_selection), a partition may be persisted
+    /// as several files. Every partition is attempted even if persisting an earlier one
+    /// fails; the partitions that failed stay buffered and the first error is returned.
+    pub async fn persist_partitions(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_keys: &[String],
+    ) -> Result<Vec<ParquetFile>> {
+        let mut files = Vec::with_capacity(partition_keys.len());
+        let mut first_error = None;
+        for partition_key in partition_keys {
+            let result = loop {
+                match self
+                    .persist_partition(sequencer_id, namespace, table_name, partition_key)
+                    .await
+                {
+                    Ok(Some(file)) => files.push(file),
+                    Ok(None) => break Ok(()),
+                    Err(e) => break Err(e),
+                }
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) => {
+                    warn!(%e, %sequencer_id, %namespace, %table_name, %partition_key, "Failed to persist partition");
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(files),
+        }
+    }
+
+    /// Buffer the operations logged in the write-ahead log, if any, as after a restart of the
+    /// ingester, returning the highest sequence number recovered for each sequencer.
+    ///
+    /// Consumption of the write buffer should continue after these sequence numbers. Operations
+    /// that fail to be buffered are skipped, as they were when first consumed. Operations below
+    /// the `min_unpersisted_sequence_number` of their sequencer in the catalog are persisted
+    /// already, and skipped too, in case the log wasn't trimmed after persisting them. So are
+    /// the writes to partitions persisted up to a later sequence number than that.
+    pub async fn recover_from_wal(&self) -> Result<BTreeMap<SequencerId, SequenceNumber>> {
+        let mut recovered = BTreeMap::new();
+        let wal = match &self.wal {
+            Some(wal) => wal,
+            None => return Ok(recovered),
+        };
+
+        let min_unpersisted: BTreeMap<_, _> = self
+            .catalog
+            .sequencers()
+            .list()
+            .await
+            .context(CatalogSnafu)?
+            .into_iter()
+            .map(|s| (s.id, s.min_unpersisted_sequence_number))
+            .collect();
+
+        for (sequencer_id, sequencer_data) in &self.sequencers {
+            let min_unpersisted = min_unpersisted
+                .get(sequencer_id)
+                .copied()
+                .unwrap_or_default();
+            let operations = wal.read(*sequencer_id).await.context(WalSnafu)?;
+            let mut num_operations = 0;
+            for operation in operations {
+                let sequence_number = operation
+                    .meta()
+                    .sequence()
+                    .map(|s| SequenceNumber::new(s.number as i64));
+                if let Some(sequence_number) = sequence_number {
+                    recovered.insert(*sequencer_id, sequence_number);
+                    if sequence_number.get() < min_unpersisted {
+                        continue;
+                    }
+                }
+                num_operations += 1;
+
+                // Not appended to the log again, it is there already
+                let result = sequencer_data
+                    .buffer_operation(
+                        operation,
+                        *sequencer_id,
+                        self.catalog.as_ref(),
+                        self.eager_deletes,
+                        &self.reject_out_of_order,
+                    )
+                    .await;
+                if let Err(e) = result {
+                    warn!(
+                        %e,
+                        %sequencer_id,
+                        "Failed to buffer an operation from the write-ahead log"
+                    );
+                }
+            }
+            self.partition_limit
+                .record_sequencer(*sequencer_id, sequencer_data);
+            info!(
+                %sequencer_id,
+                num_operations,
+                "Recovered buffered data from the write-ahead log"
+            );
+        }
+
+        Ok(recovered)
+    }
+
+    /// Remove the segments holding only operations with a sequence number lower than
+    /// `min_unpersisted` from the write-ahead log of the sequencer, if any. Call once the data
+    /// they wrote is persisted.
+    ///
+    /// Persisting a partition trims the log to the data still unpersisted, so that it doesn't
+    /// grow forever.
+    pub async fn trim_wal(
+        &self,
+        sequencer_id: SequencerId,
+        min_unpersisted: SequenceNumber,
+    ) -> Result<()> {
+        match &self.wal {
+            Some(wal) => wal
+                .trim(sequencer_id, min_unpersisted)
+                .await
+                .context(WalSnafu),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the sequence number below which the data of the sequencer is persisted in the
+    /// catalog, and trim the write-ahead log, if any, to the operations after it. Failing to is
+    /// only logged, as the data is persisted: the operations recovered from the log are
+    /// filtered by the catalog, and the log trimmed on the next persist.
+    async fn advance_min_unpersisted(
+        &self,
+        sequencer_id: SequencerId,
+        sequencer_data: &SequencerData,
+    ) {
+        let min_unpersisted = match sequencer_data.min_unpersisted_sequence_number() {
+            Some(n) => n,
+            None => return,
+        };
+
+        let result = self
+            .catalog
+            .sequencers()
+            .update_min_unpersisted_sequence_number(sequencer_id, min_unpersisted)
+            .await;
+        if let Err(e) = result {
+            warn!(
+                %e,
+                %sequencer_id,
+                "Failed to record the min unpersisted sequence number in the catalog"
+            );
+        }
+
+        if let Err(e) = self.trim_wal(sequencer_id, min_unpersisted).await {
+            warn!(
+                %e,
+                %sequencer_id,
+                "Failed to trim the write-ahead log after persisting"
+            );
+        }
+    }
+
+    /// Load the namespaces and tables of `kafka_topic_id` and the partitions of this ingester's
+    /// sequencers, with the sequence number they are persisted up to, from the catalog, to
+    /// create buffers from when they are first written to.
+    ///
+    /// Buffering an operation for a namespace, table or partition that has no buffer yet looks
+    /// it up in the catalog first, one at a time. Warming up on startup replaces those lookups
+    /// with one batch load of each (of partitions and their parquet files, one per sequencer),
+    /// so replaying the write buffer isn't slowed down by them. Only the catalog records are
+    /// kept, each until a buffer is created from it: nothing is buffered for partitions that
+    /// are not written to.
+    pub async fn warm_up(&self, kafka_topic_id: KafkaTopicId) -> Result<()> {
+        let namespaces = self
+            .catalog
+            .namespaces()
+            .list_by_kafka_topic(kafka_topic_id)
+            .await
+            .context(CatalogSnafu)?;
+        let tables = self
+            .catalog
+            .tables()
+            .list_by_kafka_topic(kafka_topic_id)
+            .await
+            .context(CatalogSnafu)?;
+        let min_unpersisted: BTreeMap<_, _> = self
+            .catalog
+            .sequencers()
+            .list()
+            .await
+            .context(CatalogSnafu)?
+            .into_iter()
+            .map(|s| (s.id, s.min_unpersisted_sequence_number))
+            .collect();
+
+        for (sequencer_id, sequencer_data) in &self.sequencers {
+            let partitions = self
+                .catalog
+                .partitions()
+                .list_by_sequencer(*sequencer_id)
+                .await
+                .context(CatalogSnafu)?;
+            // Files persisted up to a lower sequence number don't matter: replaying starts
+            // after them
+            let min_unpersisted = min_unpersisted
+                .get(sequencer_id)
+                .copied()
+                .unwrap_or_default();
+            let files = self
+                .catalog
+                .parquet_files()
+                .list_by_sequencer_greater_than(
+                    *sequencer_id,
+                    SequenceNumber::new(min_unpersisted - 1),
+                )
+                .await
+                .context(CatalogSnafu)?;
+            sequencer_data.warm_up(&namespaces, &tables, partitions, files);
+        }
+
+        Ok(())
+    }
+
+    /// Reload the retention of the namespaces of `kafka_topic_id` from the catalog, so that
+    /// queries of the buffered data apply changes to it without a restart.
+    pub async fn refresh_retention(&self, kafka_topic_id: KafkaTopicId) -> Result<()> {
+        let namespaces = self
+            .catalog
+            .namespaces()
+            .list_by_kafka_topic(kafka_topic_id)
+            .await
+            .context(CatalogSnafu)?;
+        for sequencer_data in self.sequencers.values() {
+            sequencer_data.refresh_retention(&namespaces);
+        }
+        Ok(())
+    }
+
+    /// Drop the catalog records loaded by [`Self::warm_up`] for `sequencer_id` that no buffer
+    /// was created from. Called once replaying the write buffer caught up with it, after which
+    /// buffers are created rarely enough to look up their records in the catalog.
+    pub fn finish_warm_up(&self, sequencer_id: SequencerId) {
+        if let Some(sequencer_data) = self.sequencers.get(&sequencer_id) {
+            sequencer_data.finish_warm_up();
+        }
+    }
+
+    /// Return the data buffered for `table_name` in `namespace` by all sequencers, restricted
+    /// to rows with a timestamp in `range` and with buffered deletes applied, as one
+    /// deduplicated batch per partition, ordered by partition key. Rows written more than once
+    /// are merged as described in [`deduplicate`]. Rows outside the retention period of the
+    /// namespace are excluded.
+    pub fn query_table(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<RecordBatch>> {
+        let now = SystemProvider::new().now();
+
+        // The batches of each partition of all sequencers, in the order they were written
+        let mut partitions: BTreeMap<String, Vec<Arc<RecordBatch>>> = BTreeMap::new();
+        for sequencer in self.sequencers.values() {
+            let namespace_data = match sequencer.namespace(namespace) {
+                Some(n) => n,
+                None => continue,
+            };
+            let range = namespace_data.unexpired_range(range, now);
+            if let Some(table) = namespace_data.table_data(table_name) {
+                for (partition_key, partition) in table.partitions() {
+                    partitions
+                        .entry(partition_key)
+                        .or_default()
+                        .extend(partition.query_data(table_name, range)?);
+                }
+            }
+        }
+
+        let mut batches = Vec::with_capacity(partitions.len());
+        for partition_batches in partitions.into_values() {
+            // Batches may have different columns
+            let schema = merge_record_batch_schemas(&partition_batches);
+            let batch = merge_record_batches(schema.as_arrow(), partition_batches)
+                .context(MergeBatchesSnafu { table_name })?;
+            if let Some(batch) = batch {
+                batches.push(deduplicate(&batch).context(DeduplicateSnafu { table_name })?);
+            }
+        }
+        Ok(batches)
+    }
+
+    /// Return the number of rows buffered for each table of `namespace` by all sequencers,
+    /// restricted to rows with a timestamp in `range` and with buffered deletes applied.
+    /// Rows outside the retention period of the namespace are not counted. Tables without
+    /// such rows are omitted.
+    ///
+    /// The rows are counted without copying them, see [`PartitionData::count_rows`].
+    pub fn count_rows(
+        &self,
+        namespace: &str,
+        range: TimestampRange,
+    ) -> Result<BTreeMap<String, u64>> {
+        let now = SystemProvider::new().now();
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for sequencer in self.sequencers.values() {
+            let namespace_data = match sequencer.namespace(namespace) {
+                Some(n) => n,
+                None => continue,
+            };
+            let range = namespace_data.unexpired_range(range, now);
+            for (table_name, table_data) in namespace_data.tables() {
+                let rows = table_data.count_rows(&table_name, range)?;
+                if rows > 0 {
+                    *counts.entry(table_name).or_default() += rows;
+                }
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Persist the data buffered by `sequencer_id` for the partition `partition_key` of
+    /// `table_name` in `namespace` as one parquet file: the data and its tombstones are moved
+    /// to a persisting batch, compacted, written to object storage and recorded in the
+    /// catalog, after which the batch is dropped. Return the catalog record of the file, or
+    /// `None` if the partition has no buffered data left.
+    ///
+    /// With a [`persist_selection`](Self::persist_selection), only the first run of the
+    /// snapshots of the partition in sequence order is persisted, and the rest is left for
+    /// the next call: see [`CompactionInputSelection::runs()`].
+    ///
+    /// The compacted data is streamed to the parquet encoder, so it is never held in memory
+    /// at once. The data is sorted on the sort key of the last file persisted from the
+    /// partition, if still valid, so the files of a partition are sorted alike. If persisting
+    /// fails, the data is moved back to the buffer to be persisted again later.
+    pub async fn persist_partition(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Option<ParquetFile>> {
+        let sequencer_data = self
+            .sequencers
+            .get(&sequencer_id)
+            .context(SequencerNotFoundSnafu { sequencer_id })?;
+        let namespace_data = match sequencer_data.namespace(namespace) {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+        let table_data = match namespace_data.table_data(table_name) {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let partition_data = match table_data.partition_data(partition_key) {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        loop {
+            let batch = match partition_data.start_persisting(
+                sequencer_id,
+                table_data.table_id,
+                table_name,
+                partition_key,
+                self.persist_selection.as_ref(),
+            )? {
+                Some(b) => b,
+                None => return Ok(None),
+            };
+            let stored_sort_key = partition_data.persisted_sort_key();
+
+            let result = self
+                .persist_batch(
+                    namespace_data.namespace_id,
+                    namespace,
+                    table_name,
+                    partition_key,
+                    Arc::clone(&batch),
+                    stored_sort_key.as_deref(),
+                )
+                .await;
+            match result {
+                Ok((file, sort_key)) => {
+                    partition_data.finish_persisting(&batch, sort_key)?;
+                    let (_, max_sequence_number) = batch.data.min_max_sequence_numbers();
+                    table_data.forget_persisted(batch.partition_id, max_sequence_number);
+                    self.advance_min_unpersisted(sequencer_id, sequencer_data)
+                        .await;
+                    // Nothing is left of a batch whose rows were all deleted: carry on with
+                    // the rest of the partition
+                    if file.is_some() {
+                        return Ok(file);
+                    }
+                }
+                Err(e) => {
+                    partition_data.abort_persisting(&batch)?;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Compact and persist `batch`, sorted on `stored_sort_key` if still valid, and record
+    /// the file in the catalog. Return the catalog record of the file, if any data was left
+    /// after compaction, and the columns of the sort key the data was sorted on.
+    async fn persist_batch(
+        &self,
+        namespace_id: NamespaceId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+        batch: Arc<PersistingBatch>,
+        stored_sort_key: Option<&[String]>,
+    ) -> Result<(Option<ParquetFile>, Vec<String>)> {
+        let stored_sort_key = stored_sort_key.map(|columns| {
+            let mut key = SortKey::with_capacity(columns.len());
+            for column in columns {
+                key.with_col(column);
+            }
+            key
+        });
+        let schema = batch.data.schema();
+        let chunks = batch.data.split_snapshots();
+        let sort_key = sort_key_for_compaction(
+            &schema,
+            &chunks,
+            stored_sort_key.as_ref(),
+            Some(self.persist_metrics.sort_key()),
+        );
+        let sort_key_columns = sort_key.iter().map(|(col, _)| col.to_string()).collect();
+
+        let compacted = compact_persisting_batch_streaming(
+            Arc::new(SystemProvider::new()),
+            &self.exec,
+            namespace_id.get(),
+            namespace,
+            table_name,
+            partition_key,
+            batch,
+            Some(&sort_key),
+        )
+        .await
+        .context(PersistCompactSnafu)?;
+        let (stream, metadata) = match compacted {
+            Some(v) => v,
+            None => return Ok((None, sort_key_columns)),
+        };
+
+        let file_size_bytes =
+            match persist_stream(&metadata, stream, &self.object_store, &self.persist_metrics)
+                .await
+                .context(PersistSnafu)?
+            {
+                Some(size) => size,
+                None => return Ok((None, sort_key_columns)),
+            };
+
+        let file = add_parquet_file(
+            self.catalog.as_ref(),
+            &metadata,
+            Timestamp::new(metadata.time_of_first_write.timestamp_nanos()),
+            Timestamp::new(metadata.time_of_last_write.timestamp_nanos()),
+            file_size_bytes as i64,
+            RetryConfig::default(),
+        )
+        .await
+        .context(PersistCatalogUpdateSnafu)?;
+
+        Ok((Some(file), sort_key_columns))
+    }
+
+    /// Compact the parquet files persisted by `sequencer_id` for the partition
+    /// `partition_key` of `table_name` in `namespace`: every run of files selected by the
+    /// [`persist_selection`](Self::persist_selection), or all the files without one, is read
+    /// back and persisted again as a single file, after which the files of the run are
+    /// marked for deletion in the catalog. Return the catalog records of the new files.
+    ///
+    /// The marked files are left in object storage for the queries already reading them,
+    /// until the garbage collector deletes them: see [`crate::cleanup`].
+    pub async fn compact_partition_files(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Vec<ParquetFile>> {
+        let sequencer_data = self
+            .sequencers
+            .get(&sequencer_id)
+            .context(SequencerNotFoundSnafu { sequencer_id })?;
+        let partition = sequencer_data
+            .namespace(namespace)
+            .and_then(|n| n.table_data(table_name).map(|t| (n, t)))
+            .and_then(|(n, t)| t.partition_data(partition_key).map(|p| (n, t, p)));
+        let (namespace_data, table_data, partition_data) = match partition {
+            Some(v) => v,
+            None => return Ok(vec![]),
+        };
+
+        let files: Vec<_> = self
+            .catalog
+            .parquet_files()
+            .list_by_partition(partition_data.id)
             .await
+            .context(CatalogSnafu)?
+            .into_iter()
+            .filter(|f| f.sequencer_id == sequencer_id && f.to_delete.is_none())
+            .collect();
+        let runs = match &self.persist_selection {
+            Some(selection) => selection.runs(
+                files,
+                |f| f.file_size_bytes as u64,
+                |f| f.min_sequence_number,
+            ),
+            None => vec![files],
+        };
+
+        let mut compacted = vec![];
+        for run in runs.into_iter().filter(|run| run.len() > 1) {
+            let mut snapshots = Vec::with_capacity(run.len());
+            for file in &run {
+                let data = read_persisted(namespace_data.namespace_id, file, &self.object_store)
+                    .await
+                    .context(PersistSnafu)?;
+                snapshots.push(SnapshotBatch {
+                    min_sequencer_number: file.min_sequence_number,
+                    max_sequencer_number: file.max_sequence_number,
+                    data: Arc::new(data),
+                });
+            }
+            let batch = Arc::new(PersistingBatch {
+                sequencer_id,
+                table_id: table_data.table_id,
+                partition_id: partition_data.id,
+                object_store_id: Uuid::new_v4(),
+                data: Arc::new(QueryableBatch::new(table_name, snapshots, vec![])),
+            });
+
+            let (file, _) = self
+                .persist_batch(
+                    namespace_data.namespace_id,
+                    namespace,
+                    table_name,
+                    partition_key,
+                    batch,
+                    partition_data.persisted_sort_key().as_deref(),
+                )
+                .await?;
+
+            // The files of the run are superseded by the new file, or hold no data at all
+            let marked_at = Timestamp::new(SystemProvider::new().now().timestamp_nanos());
+            for input in &run {
+                self.catalog
+                    .parquet_files()
+                    .mark_for_deletion(input.object_store_id, marked_at)
+                    .await
+                    .context(CatalogSnafu)?;
+            }
+            compacted.extend(file);
+        }
+
+        Ok(compacted)
+    }
+
+    /// Write a copy of all buffered data, with buffered deletes applied, to one parquet file
+    /// per partition under `snapshot_id` in the [snapshot prefix] of the object store, and
+    /// return the paths of the files written.
+    ///
+    /// Unlike persistence, the files are not recorded in the catalog, the data stays
+    /// buffered and the minimum unpersisted sequence number of the sequencers is left as
+    /// is: ingest carries on as if the snapshot was never taken.
+    ///
+    /// [snapshot prefix]: crate::persist::SNAPSHOT_PREFIX
+    pub async fn snapshot_to_object_store(
+        &self,
+        snapshot_id: Uuid,
+        now: Time,
+    ) -> Result<Vec<String>> {
+        let mut paths = vec![];
+        for (sequencer_id, sequencer) in &self.sequencers {
+            for (namespace_name, namespace_data) in sequencer.namespaces() {
+                for (table_name, table_data) in namespace_data.tables() {
+                    for (partition_key, partition_data) in table_data.partitions() {
+                        let (batch, min_sequence_number, max_sequence_number) =
+                            match partition_data.buffered_batch(&table_name)? {
+                                Some(v) => v,
+                                None => continue,
+                            };
+                        let (min_time, max_time) =
+                            compute_timenanosecond_min_max_for_one_record_bacth(&batch)
+                                .context(SnapshotTimeRangeSnafu)?;
+
+                        let metadata = IoxMetadata {
+                            object_store_id: Uuid::new_v4(),
+                            creation_timestamp: now,
+                            namespace_id: namespace_data.namespace_id,
+                            namespace_name: Arc::from(namespace_name.as_str()),
+                            sequencer_id: *sequencer_id,
+                            table_id: table_data.table_id,
+                            table_name: Arc::from(table_name.as_str()),
+                            partition_id: partition_data.id,
+                            partition_key: Arc::from(partition_key.as_str()),
+                            time_of_first_write: Time::from_timestamp_nanos(min_time),
+                            time_of_last_write: Time::from_timestamp_nanos(max_time),
+                            min_sequence_number,
+                            max_sequence_number,
+                        };
+                        let path = persist_snapshot(
+                            snapshot_id,
+                            &metadata,
+                            vec![batch],
+                            &self.object_store,
+                        )
+                        .await
+                        .context(SnapshotPersistSnafu)?;
+                        paths.extend(path.map(|p| p.to_raw()));
+                    }
+                }
+            }
+        }
+
+        Ok(paths)
     }
 }
 
+/// Catalog records loaded by [`IngesterData::warm_up`] for a sequencer, used instead of looking
+/// them up in the catalog when a buffer is first created for them. Each record is removed once
+/// its buffer exists, and all are removed by [`IngesterData::finish_warm_up`].
+#[derive(Debug, Default)]
+struct WarmedUp {
+    /// Namespaces by name
+    namespaces: Mutex<BTreeMap<String, Namespace>>,
+    /// Tables by namespace ID and table name
+    tables: Mutex<BTreeMap<(NamespaceId, String), Table>>,
+    /// Partitions by table ID and partition key
+    partitions: Mutex<BTreeMap<(TableId, String), Partition>>,
+    /// Greatest sequence number persisted for the partitions by partition ID, if it is not
+    /// below the minimum unpersisted sequence number of the sequencer
+    max_persisted: Mutex<BTreeMap<PartitionId, SequenceNumber>>,
+}
+
 /// Data of a Shard
 #[derive(Default)]
 pub struct SequencerData {
     // New namespaces can come in at any time so we need to be able to add new ones
     namespaces: RwLock<BTreeMap<String, Arc<NamespaceData>>>,
+    /// Catalog records to create buffers from, shared with the namespaces and tables
+    warmed_up: Arc<WarmedUp>,
+    /// Sequence number of the last operation buffered, or skipped as it failed to buffer
+    last_buffered_sequence_number: Mutex<Option<SequenceNumber>>,
 }
 
 impl SequencerData {
@@ -117,6 +982,36 @@ impl SequencerData {
         dml_operation: DmlOperation,
         sequencer_id: SequencerId,
         catalog: &dyn Catalog,
+        eager_deletes: bool,
+        reject_out_of_order: &RejectOutOfOrder,
+    ) -> Result<()> {
+        let sequence_number = dml_operation
+            .meta()
+            .sequence()
+            .map(|s| SequenceNumber::new(s.number as i64));
+        let result = self
+            .buffer_namespace_operation(
+                dml_operation,
+                sequencer_id,
+                catalog,
+                eager_deletes,
+                reject_out_of_order,
+            )
+            .await;
+
+        if let Some(sequence_number) = sequence_number {
+            *self.last_buffered_sequence_number.lock() = Some(sequence_number);
+        }
+        result
+    }
+
+    async fn buffer_namespace_operation(
+        &self,
+        dml_operation: DmlOperation,
+        sequencer_id: SequencerId,
+        catalog: &dyn Catalog,
+        eager_deletes: bool,
+        reject_out_of_order: &RejectOutOfOrder,
     ) -> Result<()> {
         let namespace_data = match self.namespace(dml_operation.namespace()) {
             Some(d) => d,
@@ -127,36 +1022,132 @@ impl SequencerData {
         };
 
         namespace_data
-            .buffer_operation(dml_operation, sequencer_id, catalog)
+            .buffer_operation(
+                dml_operation,
+                sequencer_id,
+                catalog,
+                eager_deletes,
+                reject_out_of_order,
+            )
             .await
     }
 
+    /// The lowest sequence number of the data buffered or persisting in any partition or, if
+    /// there is none, the one after the last operation buffered. The data of all operations
+    /// before it is persisted.
+    fn min_unpersisted_sequence_number(&self) -> Option<SequenceNumber> {
+        // Read first, so that an operation buffered meanwhile is found in its partition
+        let last_buffered = *self.last_buffered_sequence_number.lock();
+
+        self.namespaces()
+            .into_iter()
+            .flat_map(|(_, namespace_data)| namespace_data.tables())
+            .flat_map(|(_, table_data)| table_data.partitions())
+            .filter_map(|(_, partition_data)| partition_data.min_unpersisted_sequence_number())
+            .min()
+            .or_else(|| last_buffered.map(|n| SequenceNumber::new(n.get() + 1)))
+    }
+
     /// Gets the namespace data out of the map
     pub fn namespace(&self, namespace: &str) -> Option<Arc<NamespaceData>> {
         let n = self.namespaces.read();
         n.get(namespace).cloned()
     }
 
-    /// Retrieves the namespace from the catalog and initializes an empty buffer, or
-    /// retrieves the buffer if some other caller gets it first
+    /// Gets the buffered data of all namespaces, keyed by namespace name
+    pub fn namespaces(&self) -> Vec<(String, Arc<NamespaceData>)> {
+        let n = self.namespaces.read();
+        n.iter()
+            .map(|(name, namespace)| (name.clone(), Arc::clone(namespace)))
+            .collect()
+    }
+
+    /// Keep the catalog records of `namespaces`, `tables` and those of `partitions` that belong
+    /// to `tables`, as well as the greatest sequence number of the parquet `files` of each
+    /// partition, to create buffers from without looking them up in the catalog
+    fn warm_up(
+        &self,
+        namespaces: &[Namespace],
+        tables: &[Table],
+        partitions: Vec<Partition>,
+        files: Vec<ParquetFile>,
+    ) {
+        let table_ids: BTreeSet<_> = tables.iter().map(|t| t.id).collect();
+
+        self.warmed_up
+            .namespaces
+            .lock()
+            .extend(namespaces.iter().map(|n| (n.name.clone(), n.clone())));
+        self.warmed_up.tables.lock().extend(
+            tables
+                .iter()
+                .map(|t| ((t.namespace_id, t.name.clone()), t.clone())),
+        );
+        // partitions of tables in namespaces of other kafka topics are not ours
+        self.warmed_up.partitions.lock().extend(
+            partitions
+                .into_iter()
+                .filter(|p| table_ids.contains(&p.table_id))
+                .map(|p| ((p.table_id, p.partition_key.clone()), p)),
+        );
+        let mut max_persisted = self.warmed_up.max_persisted.lock();
+        for file in files {
+            let max = max_persisted
+                .entry(file.partition_id)
+                .or_insert(file.max_sequence_number);
+            *max = (*max).max(file.max_sequence_number);
+        }
+    }
+
+    /// Drop the warmed up catalog records that no buffer was created from
+    fn finish_warm_up(&self) {
+        self.warmed_up.namespaces.lock().clear();
+        self.warmed_up.tables.lock().clear();
+        self.warmed_up.partitions.lock().clear();
+        self.warmed_up.max_persisted.lock().clear();
+    }
+
+    /// Retrieves the namespace from the warmed up records or the catalog and initializes an
+    /// empty buffer, or retrieves the buffer if some other caller gets it first
     async fn insert_namespace(
         &self,
         namespace: &str,
         catalog: &dyn Catalog,
     ) -> Result<Arc<NamespaceData>> {
-        let namespace = catalog
-            .namespaces()
-            .get_by_name(namespace)
-            .await
-            .context(CatalogSnafu)?
-            .context(NamespaceNotFoundSnafu { namespace })?;
+        let warmed_up = self.warmed_up.namespaces.lock().remove(namespace);
+        let namespace = match warmed_up {
+            Some(namespace) => namespace,
+            None => catalog
+                .namespaces()
+                .get_by_name(namespace)
+                .await
+                .context(CatalogSnafu)?
+                .context(NamespaceNotFoundSnafu { namespace })?,
+        };
+
+        Ok(self.get_or_insert_namespace(&namespace))
+    }
+
+    /// Initializes an empty buffer for the namespace or returns the existing one
+    fn get_or_insert_namespace(&self, namespace: &Namespace) -> Arc<NamespaceData> {
+        let retention = namespace.retention_or_infinite();
         let mut n = self.namespaces.write();
-        let data = Arc::clone(
-            n.entry(namespace.name)
-                .or_insert_with(|| Arc::new(NamespaceData::new(namespace.id))),
-        );
+        Arc::clone(n.entry(namespace.name.clone()).or_insert_with(|| {
+            Arc::new(NamespaceData {
+                warmed_up: Arc::clone(&self.warmed_up),
+                ..NamespaceData::new(namespace.id).with_retention(retention)
+            })
+        }))
+    }
 
-        Ok(data)
+    /// Set the retention of the buffered namespaces to that of the matching `namespaces`
+    fn refresh_retention(&self, namespaces: &[Namespace]) {
+        let buffered = self.namespaces.read();
+        for namespace in namespaces {
+            if let Some(namespace_data) = buffered.get(&namespace.name) {
+                namespace_data.set_retention(namespace.retention_or_infinite());
+            }
+        }
     }
 }
 
@@ -164,6 +1155,12 @@ impl SequencerData {
 pub struct NamespaceData {
     namespace_id: NamespaceId,
     tables: RwLock<BTreeMap<String, Arc<TableData>>>,
+    /// Number of partitions buffered by all tables, shared with the tables
+    partition_count: Arc<AtomicUsize>,
+    /// Rows older than this are excluded from queries, even if still buffered
+    retention: RwLock<Option<Duration>>,
+    /// Catalog records to create buffers from, shared with the sequencer
+    warmed_up: Arc<WarmedUp>,
 }
 
 impl NamespaceData {
@@ -172,16 +1169,46 @@ impl NamespaceData {
         Self {
             namespace_id,
             tables: Default::default(),
+            partition_count: Default::default(),
+            retention: Default::default(),
+            warmed_up: Default::default(),
+        }
+    }
+
+    /// Exclude rows older than `retention` from queries of the namespace
+    pub fn with_retention(self, retention: Option<Duration>) -> Self {
+        self.set_retention(retention);
+        self
+    }
+
+    /// Exclude rows older than `retention` from queries of the namespace from now on
+    pub fn set_retention(&self, retention: Option<Duration>) {
+        *self.retention.write() = retention;
+    }
+
+    /// Restrict `range` to the rows of the namespace that have not expired at `now`
+    pub fn unexpired_range(&self, range: TimestampRange, now: Time) -> TimestampRange {
+        match *self.retention.read() {
+            Some(retention) => {
+                range.exclude_before(retention_cutoff(retention, now.timestamp_nanos()))
+            }
+            None => range,
         }
     }
 
     /// Buffer the operation in the cache, adding any new partitions or delete tombstones to the caatalog
+    ///
+    /// Out-of-order rows of tables rejecting them are dropped, and the first
+    /// [`Error::OutOfOrderWrite`] returned once all other rows of the write are buffered.
     pub async fn buffer_operation(
         &self,
         dml_operation: DmlOperation,
         sequencer_id: SequencerId,
         catalog: &dyn Catalog,
+        eager_deletes: bool,
+        reject_out_of_order: &RejectOutOfOrder,
     ) -> Result<()> {
+        let namespace = dml_operation.namespace().to_string();
         let sequence_number = dml_operation
             .meta()
             .sequence()
@@ -192,17 +1219,35 @@ impl NamespaceData {
 
         match dml_operation {
             DmlOperation::Write(write) => {
+                // The other tables are still buffered if rows of one are out of order
+                let mut out_of_order = None;
                 for (t, b) in write.into_tables() {
                     let table_data = match self.table_data(&t) {
                         Some(t) => t,
                         None => self.insert_table(&t, catalog).await?,
                     };
-                    table_data
-                        .buffer_table_write(sequence_number, b, sequencer_id, catalog)
-                        .await?;
+                    let result = table_data
+                        .buffer_table_write(
+                            &t,
+                            sequence_number,
+                            b,
+                            sequencer_id,
+                            catalog,
+                            reject_out_of_order.rejects(&namespace, &t),
+                        )
+                        .await;
+                    match result {
+                        Err(e @ Error::OutOfOrderWrite { .. }) => {
+                            out_of_order.get_or_insert(e);
+                        }
+                        result => result?,
+                    }
                 }
 
-                Ok(())
+                match out_of_order {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
             }
             DmlOperation::Delete(delete) => {
                 let table_name = delete.table_name().context(TableNotPresentSnafu)?;
@@ -212,7 +1257,13 @@ impl NamespaceData {
                 };
 
                 table_data
-                    .buffer_delete(delete.predicate(), sequencer_id, sequence_number, catalog)
+                    .buffer_delete(
+                        delete.predicate(),
+                        sequencer_id,
+                        sequence_number,
+                        catalog,
+                        eager_deletes,
+                    )
                     .await
             }
         }
@@ -224,24 +1275,73 @@ impl NamespaceData {
         t.get(table_name).cloned()
     }
 
-    /// Inserts the table or returns it if it happens to be inserted by some other thread
-    async fn insert_table(
-        &self,
-        table_name: &str,
+    /// Gets the buffered data of all tables, keyed by table name
+    pub fn tables(&self) -> Vec<(String, Arc<TableData>)> {
+        let t = self.tables.read();
+        t.iter()
+            .map(|(name, table)| (name.clone(), Arc::clone(table)))
+            .collect()
+    }
+
+    /// The number of partitions buffered by all tables of the namespace
+    pub fn partition_count(&self) -> usize {
+        self.partition_count.load(Ordering::Relaxed)
+    }
+
+    /// The table name and partition key of all partitions buffered by the namespace, those
+    /// with the oldest buffered data first and those without buffered data before them
+    fn oldest_partitions(&self) -> Vec<(String, String)> {
+        let mut partitions: Vec<_> =
+            self.tables()
+                .into_iter()
+                .flat_map(|(table_name, table_data)| {
+                    table_data.partitions().into_iter().map(
+                        move |(partition_key, partition_data)| {
+                            let oldest = partition_data.min_buffered_sequence_number();
+                            (oldest, table_name.clone(), partition_key)
+                        },
+                    )
+                })
+                .collect();
+        partitions.sort();
+        partitions
+            .into_iter()
+            .map(|(_, table_name, partition_key)| (table_name, partition_key))
+            .collect()
+    }
+
+    /// Inserts the table or returns it if it happens to be inserted by some other thread
+    async fn insert_table(
+        &self,
+        table_name: &str,
         catalog: &dyn Catalog,
     ) -> Result<Arc<TableData>> {
-        let table = catalog
-            .tables()
-            .create_or_get(table_name, self.namespace_id)
-            .await
-            .context(CatalogSnafu)?;
-        let mut t = self.tables.write();
-        let data = Arc::clone(
-            t.entry(table.name)
-                .or_insert_with(|| Arc::new(TableData::new(table.id))),
-        );
+        let warmed_up = self
+            .warmed_up
+            .tables
+            .lock()
+            .remove(&(self.namespace_id, table_name.to_string()));
+        let table = match warmed_up {
+            Some(table) => table,
+            None => catalog
+                .tables()
+                .create_or_get(table_name, self.namespace_id)
+                .await
+                .context(CatalogSnafu)?,
+        };
+
+        Ok(self.get_or_insert_table(&table))
+    }
 
-        Ok(data)
+    /// Initializes an empty buffer for the table or returns the existing one
+    fn get_or_insert_table(&self, table: &Table) -> Arc<TableData> {
+        let mut t = self.tables.write();
+        Arc::clone(t.entry(table.name.clone()).or_insert_with(|| {
+            Arc::new(TableData {
+                warmed_up: Arc::clone(&self.warmed_up),
+                ..TableData::new(table.id, Arc::clone(&self.partition_count))
+            })
+        }))
     }
 }
 
@@ -250,39 +1350,37 @@ pub struct TableData {
     table_id: TableId,
     // Map pf partition key to its data
     partition_data: RwLock<BTreeMap<String, Arc<PartitionData>>>,
+    // Map of series key to the latest timestamp buffered for it, only kept for tables
+    // rejecting out-of-order writes
+    series_latest_timestamp: Mutex<BTreeMap<String, LatestPoint>>,
+    // Number of partitions buffered by all tables of the namespace
+    namespace_partition_count: Arc<AtomicUsize>,
+    // Catalog records to create buffers from, shared with the sequencer
+    warmed_up: Arc<WarmedUp>,
 }
 
 impl TableData {
-    /// Initialize new table buffer
-    pub fn new(table_id: TableId) -> Self {
+    /// Initialize new table buffer, counting its partitions in `namespace_partition_count`
+    pub fn new(table_id: TableId, namespace_partition_count: Arc<AtomicUsize>) -> Self {
         Self {
             table_id,
             partition_data: Default::default(),
+            series_latest_timestamp: Default::default(),
+            namespace_partition_count,
+            warmed_up: Default::default(),
         }
     }
 
     async fn buffer_table_write(
         &self,
+        table_name: &str,
         sequence_number: SequenceNumber,
         batch: MutableBatch,
         sequencer_id: SequencerId,
         catalog: &dyn Catalog,
+        reject_out_of_order: bool,
     ) -> Result<()> {
-        let (_, col) = batch
-            .columns()
-            .find(|(name, _)| *name == TIME_COLUMN_NAME)
-            .unwrap();
-        let timestamp = match col.data() {
-            ColumnData::I64(_, s) => s.min.unwrap(),
-            _ => return Err(Error::TimeColumnNotPresent),
-        };
-
-        let partition_key = format!(
-            "{}",
-            Utc.timestamp_nanos(timestamp)
-                .format_with_items(StrftimeItems::new("%Y-%m-%d"))
-        );
-
+        let partition_key = partition_key(&batch)?;
         let partition_data = match self.partition_data(&partition_key) {
             Some(p) => p,
             None => {
@@ -291,9 +1389,43 @@ impl TableData {
             }
         };
 
-        partition_data.buffer_write(sequence_number, batch);
+        // Replayed after a restart, the write is persisted in the partition already
+        if partition_data.is_persisted(sequence_number) {
+            return Ok(());
+        }
+
+        if !reject_out_of_order {
+            partition_data.buffer_write(sequence_number, batch);
+            return Ok(());
+        }
 
-        Ok(())
+        // The latest points are checked and updated, and the rows in order buffered, under
+        // one lock so that they always describe the buffered data
+        let mut latest_points = self.series_latest_timestamp.lock();
+        let (batch, out_of_order) = retain_in_order(
+            table_name,
+            batch,
+            partition_data.id,
+            sequence_number,
+            &mut latest_points,
+        )?;
+        if batch.rows() > 0 {
+            partition_data.buffer_write(sequence_number, batch);
+        }
+
+        match out_of_order {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the latest points of the series buffered in `partition_id` up to
+    /// `sequence_number`, once that data is persisted, so that only buffered data is checked
+    /// for out-of-order writes and the map doesn't grow with every series ever written
+    fn forget_persisted(&self, partition_id: PartitionId, sequence_number: SequenceNumber) {
+        self.series_latest_timestamp.lock().retain(|_, latest| {
+            latest.partition_id != partition_id || latest.sequence_number > sequence_number
+        });
     }
 
     async fn buffer_delete(
@@ -302,6 +1434,7 @@ impl TableData {
         sequencer_id: SequencerId,
         sequence_number: SequenceNumber,
         catalog: &dyn Catalog,
+        eager_deletes: bool,
     ) -> Result<()> {
         let min_time = Timestamp::new(predicate.range.start());
         let max_time = Timestamp::new(predicate.range.end());
@@ -320,8 +1453,20 @@ impl TableData {
             .context(CatalogSnafu)?;
 
         let partitions = self.partition_data.read();
-        for data in partitions.values() {
+        for (partition_key, data) in partitions.iter() {
+            // The tombstone is needed for the persisting and persisted data whether or not
+            // the buffered data is deleted eagerly, and keeps queries correct if that fails
             data.buffer_tombstone(tombstone.clone());
+            if eager_deletes {
+                if let Err(e) = data.apply_delete(predicate) {
+                    warn!(
+                        %e,
+                        table_id = self.table_id.get(),
+                        %partition_key,
+                        "failed to apply delete to buffered data, leaving it to the tombstone"
+                    );
+                }
+            }
         }
 
         Ok(())
@@ -333,22 +1478,326 @@ impl TableData {
         p.get(partition_key).cloned()
     }
 
+    /// Gets the buffered data of all partitions, keyed by partition key
+    pub fn partitions(&self) -> Vec<(String, Arc<PartitionData>)> {
+        let p = self.partition_data.read();
+        p.iter()
+            .map(|(key, partition)| (key.clone(), Arc::clone(partition)))
+            .collect()
+    }
+
+    /// Return the number of rows buffered in all partitions of this table with a timestamp in
+    /// `range`, with buffered deletes applied
+    pub fn count_rows(&self, table_name: &str, range: TimestampRange) -> Result<u64> {
+        let partitions: Vec<_> = self.partition_data.read().values().cloned().collect();
+
+        let mut rows = 0;
+        for partition in partitions {
+            rows += partition.count_rows(table_name, range)?;
+        }
+        Ok(rows)
+    }
+
     async fn insert_partition(
         &self,
         partition_key: &str,
         sequencer_id: SequencerId,
         catalog: &dyn Catalog,
     ) -> Result<Arc<PartitionData>> {
-        let partition = catalog
-            .partitions()
-            .create_or_get(partition_key, sequencer_id, self.table_id)
-            .await
-            .context(CatalogSnafu)?;
+        let warmed_up = self
+            .warmed_up
+            .partitions
+            .lock()
+            .remove(&(self.table_id, partition_key.to_string()));
+        let (partition, max_persisted) = match warmed_up {
+            Some(partition) => {
+                let max_persisted = self.warmed_up.max_persisted.lock().remove(&partition.id);
+                (partition, max_persisted)
+            }
+            None => {
+                let partition = catalog
+                    .partitions()
+                    .create_or_get(partition_key, sequencer_id, self.table_id)
+                    .await
+                    .context(CatalogSnafu)?;
+                let max_persisted = catalog
+                    .parquet_files()
+                    .max_persisted_sequence_number(partition.id, sequencer_id)
+                    .await
+                    .context(CatalogSnafu)?;
+                (partition, max_persisted)
+            }
+        };
+
+        Ok(self.get_or_insert_partition(partition, max_persisted))
+    }
+
+    /// Initializes an empty buffer for the partition or returns the existing one
+    fn get_or_insert_partition(
+        &self,
+        partition: Partition,
+        max_persisted: Option<SequenceNumber>,
+    ) -> Arc<PartitionData> {
+        let mut p = self.partition_data.write();
+        Arc::clone(p.entry(partition.partition_key).or_insert_with(|| {
+            self.namespace_partition_count
+                .fetch_add(1, Ordering::Relaxed);
+            Arc::new(PartitionData::new(partition.id).with_max_persisted(max_persisted))
+        }))
+    }
+
+    /// Drops the buffer of the partition if it has no buffered or persisting data, returning
+    /// whether it was dropped. The columns of the sort key of its last persisted file are
+    /// forgotten with it.
+    ///
+    /// Only call while buffering operations of the sequencer, which is the only time data
+    /// is added to the buffer, so no data can be buffered in the partition once dropped.
+    fn remove_partition_if_empty(&self, partition_key: &str) -> bool {
         let mut p = self.partition_data.write();
-        let data = Arc::new(PartitionData::new(partition.id));
-        p.insert(partition.partition_key, Arc::clone(&data));
+        match p.get(partition_key) {
+            Some(partition) if partition.is_empty() => {
+                p.remove(partition_key);
+                self.namespace_partition_count
+                    .fetch_sub(1, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The key of the partition `batch` is buffered in: the day of its earliest timestamp
+fn partition_key(batch: &MutableBatch) -> Result<String> {
+    let timestamp = match batch.column(TIME_COLUMN_NAME).map(|c| c.data()) {
+        Ok(ColumnData::I64(_, s)) => s.min.context(TimeColumnNotPresentSnafu)?,
+        _ => return Err(Error::TimeColumnNotPresent),
+    };
+
+    Ok(format!(
+        "{}",
+        Utc.timestamp_nanos(timestamp)
+            .format_with_items(StrftimeItems::new("%Y-%m-%d"))
+    ))
+}
+
+/// Extend `batch` with `other`, first converting the integer fields of either that are float
+/// fields of the other to floats: an integer column promoted to a float column in the catalog
+/// is buffered as both until the writes with integer values are persisted.
+fn extend_promoting_integers(
+    batch: &mut MutableBatch,
+    other: &MutableBatch,
+) -> Result<(), mutable_batch::Error> {
+    let integer = InfluxColumnType::Field(InfluxFieldType::Integer);
+    let float = InfluxColumnType::Field(InfluxFieldType::Float);
+
+    let mut promoted: Option<MutableBatch> = None;
+    for (name, column) in other.columns() {
+        let existing = match batch.column(name) {
+            Ok(existing) => existing.influx_type(),
+            Err(_) => continue,
+        };
+        if existing == integer && column.influx_type() == float {
+            batch.coerce_integer_to_float(name)?;
+        } else if existing == float && column.influx_type() == integer {
+            promoted
+                .get_or_insert_with(|| other.clone())
+                .coerce_integer_to_float(name)?;
+        }
+    }
+
+    batch.extend_from(promoted.as_ref().unwrap_or(other))
+}
+
+/// The latest point buffered for a series, and where it was buffered
+#[derive(Debug, Clone, Copy)]
+struct LatestPoint {
+    timestamp: i64,
+    partition_id: PartitionId,
+    sequence_number: SequenceNumber,
+}
+
+/// Split off the rows of `batch` older than the latest point in `latest_points` for their
+/// series, or than a previous row of the same series in `batch`, and record the timestamps of
+/// the other rows as buffered in `partition_id` at `sequence_number`.
+///
+/// Return the rows in order, and the [`Error::OutOfOrderWrite`] describing the first
+/// rejected row if any were rejected.
+fn retain_in_order(
+    table_name: &str,
+    batch: MutableBatch,
+    partition_id: PartitionId,
+    sequence_number: SequenceNumber,
+    latest_points: &mut BTreeMap<String, LatestPoint>,
+) -> Result<(MutableBatch, Option<Error>)> {
+    let timestamps = match batch.column(TIME_COLUMN_NAME).map(|c| c.data()) {
+        Ok(ColumnData::I64(values, _)) => values,
+        _ => return Err(Error::TimeColumnNotPresent),
+    };
+
+    let mut tags: Vec<_> = batch
+        .columns()
+        .filter(|(_, col)| col.influx_type() == InfluxColumnType::Tag)
+        .collect();
+    tags.sort_by_key(|(name, _)| *name);
+
+    // The series key is only built once per distinct combination of tag value IDs
+    let mut series_keys: HashMap<Vec<i32>, String> = HashMap::new();
+    let mut ids = Vec::with_capacity(tags.len());
+    let mut kept: Vec<Range<usize>> = vec![];
+    let mut rejected = 0;
+    let mut first_rejected = None;
+    for (row, &timestamp) in timestamps.iter().enumerate() {
+        ids.clear();
+        ids.extend(tags.iter().map(|(_, col)| match col.data() {
+            ColumnData::Tag(values, _, _) if col.valid_mask().get(row) => values[row],
+            _ => -1,
+        }));
+        if !series_keys.contains_key(ids.as_slice()) {
+            series_keys.insert(ids.clone(), series_key(&tags, row));
+        }
+        let series = &series_keys[ids.as_slice()];
+
+        let point = LatestPoint {
+            timestamp,
+            partition_id,
+            sequence_number,
+        };
+        match latest_points.get_mut(series) {
+            Some(latest) if timestamp < latest.timestamp => {
+                rejected += 1;
+                first_rejected.get_or_insert_with(|| (series.clone(), timestamp, latest.timestamp));
+                continue;
+            }
+            Some(latest) => *latest = point,
+            None => {
+                latest_points.insert(series.clone(), point);
+            }
+        }
+
+        match kept.last_mut() {
+            Some(range) if range.end == row => range.end += 1,
+            _ => kept.push(row..row + 1),
+        }
+    }
+
+    let (series, timestamp, latest) = match first_rejected {
+        Some(first) => first,
+        None => return Ok((batch, None)),
+    };
+    let error = OutOfOrderWriteSnafu {
+        table_name,
+        series,
+        timestamp,
+        latest,
+        rejected,
+    }
+    .build();
+
+    let mut in_order = MutableBatch::new();
+    in_order
+        .extend_from_ranges(&batch, &kept)
+        .context(FilterOutOfOrderSnafu)?;
+    Ok((in_order, Some(error)))
+}
+
+/// The series key of `row`: the `name=value` pairs of the tags not null in that row, in the
+/// order of `tags`
+fn series_key(tags: &[(&String, &Column)], row: usize) -> String {
+    let pairs: Vec<_> = tags
+        .iter()
+        .filter(|(_, col)| col.valid_mask().get(row))
+        .filter_map(|(name, col)| match col.data() {
+            ColumnData::Tag(ids, dictionary, _) => dictionary
+                .lookup_id(ids[row])
+                .map(|value| format!("{}={}", name, value)),
+            _ => None,
+        })
+        .collect();
+    pairs.join(",")
+}
+
+/// The namespaces and tables whose series must be written in time order. The points of a
+/// write older than the latest point buffered for their series are rejected with
+/// [`Error::OutOfOrderWrite`], while its other points are buffered.
+///
+/// Only points buffered and not yet persisted are checked against, so the check is bounded
+/// by the buffered data and replaying the unpersisted writes on restart rebuilds it.
+///
+/// Writes to all other tables may be out of order, which is the default.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RejectOutOfOrder {
+    /// Namespaces rejecting out-of-order writes to all of their tables
+    namespaces: BTreeSet<String>,
+    /// Tables rejecting out-of-order writes, by namespace and table name
+    tables: BTreeSet<(String, String)>,
+}
+
+impl RejectOutOfOrder {
+    /// Reject out-of-order writes to all tables of `namespace`
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespaces.insert(namespace.into());
+        self
+    }
+
+    /// Reject out-of-order writes to `table` of `namespace`
+    pub fn with_table(mut self, namespace: impl Into<String>, table: impl Into<String>) -> Self {
+        self.tables.insert((namespace.into(), table.into()));
+        self
+    }
+
+    /// Return whether out-of-order writes to `table` of `namespace` are rejected
+    pub fn rejects(&self, namespace: &str, table: &str) -> bool {
+        self.namespaces.contains(namespace)
+            || self
+                .tables
+                .contains(&(namespace.to_string(), table.to_string()))
+    }
+}
+
+/// Limits the number of partitions buffered per namespace of a sequencer, so that writes to
+/// unbounded partition keys can't exhaust the memory of the ingester, and reports the number
+/// of partitions buffered.
+///
+/// Before buffering a write that would buffer partitions beyond the limit, the partitions of
+/// the namespace with the oldest buffered data are persisted and dropped to make room, see
+/// [`IngesterData::buffer_operation`]. Writes are never rejected: if persisting fails, the
+/// partitions are kept and the limit exceeded until a later write makes room.
+#[derive(Debug)]
+pub struct PartitionLimit {
+    /// The maximum number of partitions buffered per namespace of a sequencer, unlimited if
+    /// not set
+    max_per_namespace: Option<usize>,
+    /// Number of partitions buffered, by namespace and sequencer
+    buffered_partitions: Metric<U64Gauge>,
+}
+
+impl PartitionLimit {
+    /// Register the metric of the buffered partitions with the given registry
+    pub fn new(registry: &metric::Registry, max_per_namespace: Option<usize>) -> Self {
+        Self {
+            max_per_namespace,
+            buffered_partitions: registry.register_metric(
+                "ingester_buffered_partitions",
+                "number of partitions buffered per namespace and sequencer",
+            ),
+        }
+    }
+
+    /// Report the number of partitions buffered for the namespace
+    fn record(&self, namespace: &str, sequencer_id: SequencerId, namespace_data: &NamespaceData) {
+        self.buffered_partitions
+            .recorder(Attributes::from([
+                ("namespace", namespace.to_string().into()),
+                ("sequencer_id", sequencer_id.to_string().into()),
+            ]))
+            .set(namespace_data.partition_count() as u64);
+    }
 
-        Ok(data)
+    /// Report the number of partitions buffered for every namespace of the sequencer
+    fn record_sequencer(&self, sequencer_id: SequencerId, sequencer_data: &SequencerData) {
+        for (namespace, namespace_data) in sequencer_data.namespaces() {
+            self.record(&namespace, sequencer_id, &namespace_data);
+        }
     }
 }
 
@@ -356,6 +1805,9 @@ impl TableData {
 pub struct PartitionData {
     id: PartitionId,
     inner: RwLock<DataBuffer>,
+    /// Greatest sequence number of the data of the partition persisted when the buffer was
+    /// created, as recorded in the catalog
+    max_persisted: Option<SequenceNumber>,
 }
 
 impl PartitionData {
@@ -364,9 +1816,25 @@ impl PartitionData {
         Self {
             id,
             inner: Default::default(),
+            max_persisted: None,
+        }
+    }
+
+    /// Skip the writes up to `max_persisted`, persisted in the partition already
+    pub fn with_max_persisted(self, max_persisted: Option<SequenceNumber>) -> Self {
+        Self {
+            max_persisted,
+            ..self
         }
     }
 
+    /// Return whether the write with `sequence_number` is persisted in the partition already,
+    /// as replayed after a restart
+    fn is_persisted(&self, sequence_number: SequenceNumber) -> bool {
+        self.max_persisted
+            .map_or(false, |max_persisted| sequence_number <= max_persisted)
+    }
+
     /// Snapshot whatever is in the buffer and return a new vec of the
     /// arc cloned snapshots
     pub fn snapshot(&self) -> Result<Vec<Arc<SnapshotBatch>>> {
@@ -375,6 +1843,192 @@ impl PartitionData {
         Ok(data.snapshots.to_vec())
     }
 
+    /// Return the schema, number of chunks and sort key the buffered data
+    /// would be compacted with right now, or `None` if nothing is buffered.
+    /// The buffer is left as is: its writes count as the one chunk they would
+    /// be snapshotted into.
+    pub fn info(&self, table_name: &str) -> Result<Option<PartitionInfo>> {
+        let data = {
+            let data = self.inner.read();
+            let buffered = data.buffer_snapshot().context(SnapshotSnafu)?;
+            data.snapshots
+                .iter()
+                .map(|s| s.as_ref().clone())
+                .chain(buffered)
+                .collect::<Vec<_>>()
+        };
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        let batch = QueryableBatch::new(table_name, data, vec![]);
+        let chunks = batch.split_snapshots();
+        let schema = batch.schema();
+        let sort_key = compute_sort_key_for_chunks(&schema, &chunks);
+
+        Ok(Some(PartitionInfo {
+            columns: schema
+                .iter()
+                .map(|(influx_type, field)| PartitionColumnInfo {
+                    name: field.name().to_string(),
+                    influx_type: influx_type.map(|t| t.to_string()),
+                })
+                .collect(),
+            sort_key: sort_key.iter().map(|(col, _)| col.to_string()).collect(),
+            chunks: chunks.len(),
+            chunks_have_stats: chunks_have_stats(&chunks),
+        }))
+    }
+
+    /// Return the rows of the persisting, snapshot and buffered data with a timestamp in
+    /// `range`, without the rows deleted by buffered tombstones. Batches left without rows
+    /// are skipped. The buffer is left as is.
+    pub fn query_data(
+        &self,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<Arc<RecordBatch>>> {
+        Ok(self
+            .query_snapshots(table_name, range)?
+            .into_iter()
+            .map(|(batch, _)| batch)
+            .collect())
+    }
+
+    /// Return a copy of all buffered rows, without the
+    /// rows deleted by buffered tombstones, merged into a single batch with the min and max
+    /// sequence numbers of their writes. Return `None` if no rows are buffered.
+    fn buffered_batch(
+        &self,
+        table_name: &str,
+    ) -> Result<Option<(RecordBatch, SequenceNumber, SequenceNumber)>> {
+        let range = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        let snapshots = self.query_snapshots(table_name, range)?;
+
+        let min_sequence_number = snapshots.iter().map(|(_, (min, _))| *min).min();
+        let max_sequence_number = snapshots.iter().map(|(_, (_, max))| *max).max();
+        let (min_sequence_number, max_sequence_number) =
+            match (min_sequence_number, max_sequence_number) {
+                (Some(min), Some(max)) => (min, max),
+                _ => return Ok(None),
+            };
+
+        // Snapshots may have different columns
+        let batches: Vec<_> = snapshots.into_iter().map(|(batch, _)| batch).collect();
+        let schema = merge_record_batch_schemas(&batches);
+        let batch = merge_record_batches(schema.as_arrow(), batches)
+            .context(MergeBatchesSnafu { table_name })?;
+
+        Ok(batch.map(|b| (b, min_sequence_number, max_sequence_number)))
+    }
+
+    /// Like [`Self::query_data`], also returning the min and max sequence numbers of the
+    /// snapshot each batch was read from
+    fn query_snapshots(
+        &self,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<(Arc<RecordBatch>, (SequenceNumber, SequenceNumber))>> {
+        let data = self.inner.read();
+        let buffered = data.buffer_snapshot().context(SnapshotSnafu)?;
+
+        // Parses the delete predicates of the buffered tombstones
+        let deletes = QueryableBatch::new(table_name, vec![], data.deletes.clone());
+
+        let persisting = data.persisting.iter().flat_map(|persisting| {
+            persisting
+                .data
+                .data
+                .iter()
+                .map(move |snapshot| (snapshot, persisting.data.delete_predicates.as_slice()))
+        });
+        let snapshots = data
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.as_ref())
+            .chain(&buffered)
+            .map(|snapshot| (snapshot, &[][..]));
+
+        let mut batches = vec![];
+        for (snapshot, persisting_deletes) in persisting.chain(snapshots) {
+            let predicates = persisting_deletes.iter().chain(&deletes.delete_predicates);
+            let batch = query_rows(&snapshot.data, range, predicates).context(DeleteRowsSnafu)?;
+            if batch.num_rows() > 0 {
+                let sequence_numbers =
+                    (snapshot.min_sequencer_number, snapshot.max_sequencer_number);
+                batches.push((Arc::new(batch), sequence_numbers));
+            }
+        }
+        Ok(batches)
+    }
+
+    /// Return the number of rows of the persisting, snapshot and buffered data with a
+    /// timestamp in `range`, not deleted by buffered tombstones. Only the time column and
+    /// the columns of the tombstones are read, and the buffer is left as is.
+    ///
+    /// Rows are not deduplicated: a point written more than once is counted once per write.
+    pub fn count_rows(&self, table_name: &str, range: TimestampRange) -> Result<u64> {
+        let data = self.inner.read();
+
+        // Parses the delete predicates of the buffered tombstones
+        let deletes = QueryableBatch::new(table_name, vec![], data.deletes.clone());
+
+        let mut rows = 0;
+        for persisting in &data.persisting {
+            for snapshot in &persisting.data.data {
+                let predicates = persisting
+                    .data
+                    .delete_predicates
+                    .iter()
+                    .chain(&deletes.delete_predicates);
+                rows += count_rows(&snapshot.data, range, predicates).context(DeleteRowsSnafu)?;
+            }
+        }
+        for snapshot in &data.snapshots {
+            rows += count_rows(&snapshot.data, range, &deletes.delete_predicates)
+                .context(DeleteRowsSnafu)?;
+        }
+
+        let mut columns: Vec<_> = deletes
+            .delete_predicates
+            .iter()
+            .flat_map(|d| d.exprs.iter().map(|e| e.column()))
+            .chain([TIME_COLUMN_NAME])
+            .collect();
+        columns.sort_unstable();
+        columns.dedup();
+        for buffered in &data.buffer {
+            let stats = match buffered.data.column(TIME_COLUMN_NAME).map(|c| c.data()) {
+                Ok(ColumnData::I64(_, stats)) => stats,
+                _ => return Err(Error::TimeColumnNotPresent),
+            };
+            let in_range = match (stats.min, stats.max) {
+                (Some(min), Some(max)) => range.contains(min) && range.contains(max),
+                _ => false,
+            };
+            // The statistics suffice if all rows are in range and none may be deleted
+            if in_range && deletes.delete_predicates.is_empty() {
+                rows += buffered.data.rows();
+                continue;
+            }
+
+            // Deletes on columns the batch doesn't have match no rows
+            let columns: Vec<_> = columns
+                .iter()
+                .copied()
+                .filter(|c| buffered.data.column(c).is_ok())
+                .collect();
+            let batch = buffered
+                .data
+                .to_arrow(Selection::Some(&columns))
+                .context(SnapshotSnafu)?;
+            rows +=
+                count_rows(&batch, range, &deletes.delete_predicates).context(DeleteRowsSnafu)?;
+        }
+
+        Ok(rows as u64)
+    }
+
     fn buffer_write(&self, sequencer_number: SequenceNumber, mb: MutableBatch) {
         let mut data = self.inner.write();
         data.buffer.push(BufferBatch {
@@ -387,6 +2041,157 @@ impl PartitionData {
         let mut data = self.inner.write();
         data.deletes.push(tombstone);
     }
+
+    /// Drop the buffered rows matching the given delete predicate if its
+    /// time range covers all buffered data. Return whether the rows were dropped
+    pub fn apply_delete(&self, predicate: &DeletePredicate) -> Result<bool> {
+        let mut data = self.inner.write();
+        data.apply_delete(predicate)
+    }
+
+    /// Move the buffered data and tombstones to a new [`PersistingBatch`] of the table
+    /// `table_id` and return it, or `None` if no data is buffered. Fails if another batch of
+    /// the partition is being persisted.
+    ///
+    /// With a `selection`, only the snapshots of its first run are moved, and the tombstones
+    /// are copied as they still apply to the snapshots left buffered.
+    fn start_persisting(
+        &self,
+        sequencer_id: SequencerId,
+        table_id: TableId,
+        table_name: &str,
+        partition_key: &str,
+        selection: Option<&CompactionInputSelection>,
+    ) -> Result<Option<Arc<PersistingBatch>>> {
+        let mut data = self.inner.write();
+        if data.persisting.is_some() {
+            return Err(Error::PersistingNotEmpty);
+        }
+        data.snapshot().context(SnapshotSnafu)?;
+        if data.snapshots.is_empty() {
+            return Ok(None);
+        }
+
+        let mut snapshots = std::mem::take(&mut data.snapshots);
+        if let Some(selection) = selection {
+            let mut runs = selection
+                .runs(
+                    snapshots,
+                    |s| s.size_bytes() as u64,
+                    |s| s.min_sequencer_number,
+                )
+                .into_iter();
+            snapshots = runs.next().unwrap_or_default();
+            data.snapshots = runs.flatten().collect();
+        }
+        let snapshots = snapshots
+            .into_iter()
+            .map(|s| Arc::try_unwrap(s).unwrap_or_else(|s| s.as_ref().clone()))
+            .collect();
+        let deletes = if data.snapshots.is_empty() {
+            std::mem::take(&mut data.deletes)
+        } else {
+            data.deletes.clone()
+        };
+        let batch = Arc::new(PersistingBatch {
+            sequencer_id,
+            table_id,
+            partition_id: self.id,
+            object_store_id: Uuid::new_v4(),
+            data: Arc::new(
+                QueryableBatch::new(table_name, snapshots, deletes)
+                    .with_origin(sequencer_id, partition_key),
+            ),
+        });
+        data.add_persisting_batch(Arc::clone(&batch))?;
+
+        Ok(Some(batch))
+    }
+
+    /// Drop the persisted `batch`, remembering the columns of the sort key it was persisted
+    /// with for the next file of the partition
+    fn finish_persisting(&self, batch: &Arc<PersistingBatch>, sort_key: Vec<String>) -> Result<()> {
+        let mut data = self.inner.write();
+        data.remove_persisting_batch(batch)?;
+        data.sort_key = Some(sort_key);
+        Ok(())
+    }
+
+    /// Move the data and tombstones of `batch`, which failed to persist, back in front of
+    /// those buffered since it was created
+    fn abort_persisting(&self, batch: &Arc<PersistingBatch>) -> Result<()> {
+        let mut data = self.inner.write();
+        data.remove_persisting_batch(batch)?;
+
+        let snapshots = batch.data.data.iter().cloned().map(Arc::new);
+        data.snapshots = snapshots
+            .chain(std::mem::take(&mut data.snapshots))
+            .collect();
+        // Tombstones still buffered were copied to the batch when it was created
+        let buffered_deletes = std::mem::take(&mut data.deletes);
+        let deletes = batch
+            .data
+            .deletes
+            .iter()
+            .filter(|d| !buffered_deletes.iter().any(|b| b.id == d.id))
+            .cloned();
+        data.deletes = deletes.chain(buffered_deletes).collect();
+        Ok(())
+    }
+
+    /// Return the columns of the sort key of the last file persisted from this partition
+    fn persisted_sort_key(&self) -> Option<Vec<String>> {
+        self.inner.read().sort_key.clone()
+    }
+
+    /// Return the lowest sequence number of the data buffered and not persisting, if any
+    fn min_buffered_sequence_number(&self) -> Option<SequenceNumber> {
+        let data = self.inner.read();
+        let buffered = data.buffer.first().map(|b| b.sequencer_number);
+        let snapshots = data.snapshots.iter().map(|s| s.min_sequencer_number);
+        snapshots.chain(buffered).min()
+    }
+
+    /// Return the lowest sequence number of the data buffered or persisting, if any
+    fn min_unpersisted_sequence_number(&self) -> Option<SequenceNumber> {
+        let data = self.inner.read();
+        let buffered = data.buffer.first().map(|b| b.sequencer_number);
+        let snapshots = data.snapshots.iter().map(|s| s.min_sequencer_number);
+        let persisting = data
+            .persisting
+            .as_ref()
+            .map(|p| p.data.min_max_sequence_numbers().0);
+        snapshots.chain(buffered).chain(persisting).min()
+    }
+
+    /// Return whether the partition has no buffered, snapshot or persisting data
+    fn is_empty(&self) -> bool {
+        let data = self.inner.read();
+        data.buffer.is_empty() && data.snapshots.is_empty() && data.persisting.is_none()
+    }
+}
+
+/// Debugging information about the data buffered for an IOx partition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PartitionInfo {
+    /// Columns of the merged schema of the buffered data
+    pub columns: Vec<PartitionColumnInfo>,
+    /// Sort key the buffered data would be compacted on
+    pub sort_key: Vec<String>,
+    /// Number of buffered chunks
+    pub chunks: usize,
+    /// Whether all buffered chunks have statistics. If not, the sort key is
+    /// the primary key rather than one computed from the column cardinalities
+    pub chunks_have_stats: bool,
+}
+
+/// A column of [`PartitionInfo`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PartitionColumnInfo {
+    /// Column name
+    pub name: String,
+    /// IOx type of the column, if any
+    pub influx_type: Option<String>,
 }
 
 /// Data of an IOx partition split into batches
@@ -424,13 +2229,17 @@ pub struct DataBuffer {
 
     /// Data in `buffer` will be moved to a `snapshot` when one of these happens:
     ///  . A background persist is called
-    ///  . A read request from Querier
-    /// The `buffer` will be empty when this happens.
+    ///  . A delete is applied to the buffered data
+    /// The `buffer` will be empty when this happens. Read requests from Queriers
+    /// read the `buffer` as is.
     pub snapshots: Vec<Arc<SnapshotBatch>>,
     /// When a persist is called, data in `buffer` will be moved to a `snapshot`
     /// and then all `snapshots` will be moved to a `persisting`.
     /// Both `buffer` and 'snaphots` will be empty when this happens.
     pub persisting: Option<Arc<PersistingBatch>>,
+    /// Columns of the sort key of the last file persisted from this partition, reused for
+    /// the next file so all files of the partition are sorted alike
+    pub sort_key: Option<Vec<String>>,
     // Extra Notes:
     //  . In MVP, we will only persist a set of sanpshots at a time.
     //    In later version, multiple perssiting operations may be happenning concurrently but
@@ -448,37 +2257,94 @@ pub struct DataBuffer {
 impl DataBuffer {
     /// Move `BufferBatch`es to a `SnapshotBatch`.
     pub fn snapshot(&mut self) -> Result<(), mutable_batch::Error> {
-        if !self.buffer.is_empty() {
-            let min_sequencer_number = self
-                .buffer
-                .first()
-                .expect("Buffer isn't empty in this block")
-                .sequencer_number;
-            let max_sequencer_number = self
-                .buffer
-                .last()
-                .expect("Buffer isn't empty in this block")
-                .sequencer_number;
-            assert!(min_sequencer_number <= max_sequencer_number);
+        if let Some(snapshot) = self.buffer_snapshot()? {
+            self.snapshots.push(Arc::new(snapshot));
+            self.buffer.clear();
+        }
 
-            let mut batches = self.buffer.iter();
-            let first_batch = batches.next().expect("Buffer isn't empty in this block");
-            let mut mutable_batch = first_batch.data.clone();
+        Ok(())
+    }
 
-            for batch in batches {
-                mutable_batch.extend_from(&batch.data)?;
-            }
+    /// Combine the `BufferBatch`es into a `SnapshotBatch` without moving them, or return
+    /// `None` if the buffer is empty
+    fn buffer_snapshot(&self) -> Result<Option<SnapshotBatch>, mutable_batch::Error> {
+        let mut batches = self.buffer.iter();
+        let first_batch = match batches.next() {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+        let min_sequencer_number = first_batch.sequencer_number;
+        let max_sequencer_number = self
+            .buffer
+            .last()
+            .expect("Buffer isn't empty in this block")
+            .sequencer_number;
+        assert!(min_sequencer_number <= max_sequencer_number);
 
-            self.snapshots.push(Arc::new(SnapshotBatch {
-                min_sequencer_number,
-                max_sequencer_number,
-                data: Arc::new(mutable_batch.to_arrow(Selection::All)?),
-            }));
+        let mut mutable_batch = first_batch.data.clone();
+        for batch in batches {
+            extend_promoting_integers(&mut mutable_batch, &batch.data)?;
+        }
 
-            self.buffer.clear();
+        Ok(Some(SnapshotBatch {
+            min_sequencer_number,
+            max_sequencer_number,
+            data: Arc::new(mutable_batch.to_arrow(Selection::All)?),
+        }))
+    }
+
+    /// Drop the rows matching the given delete predicate from `buffer` and `snapshots`
+    /// if the predicate's time range covers all of their data. Return false and leave
+    /// the data as is otherwise, or if no rows match.
+    /// Note: if rows are dropped, `buffer` is moved to a `snapshot` so its rows can be
+    /// filtered. `persisting` is never touched.
+    pub fn apply_delete(&mut self, predicate: &DeletePredicate) -> Result<bool> {
+        let covers =
+            |min: i64, max: i64| predicate.range.contains(min) && predicate.range.contains(max);
+
+        for snapshot in &self.snapshots {
+            let (min, max) = compute_timenanosecond_min_max_for_one_record_bacth(&snapshot.data)
+                .context(SnapshotTimeRangeSnafu)?;
+            if !covers(min, max) {
+                return Ok(false);
+            }
+        }
+        for batch in &self.buffer {
+            let time = batch.data.column(TIME_COLUMN_NAME).context(SnapshotSnafu)?;
+            if let Statistics::I64(stats) = time.stats() {
+                if let (Some(min), Some(max)) = (stats.min, stats.max) {
+                    if !covers(min, max) {
+                        return Ok(false);
+                    }
+                }
+            }
         }
 
-        Ok(())
+        // Filter a snapshot of the buffer without moving it, so nothing changes if no
+        // rows match
+        let buffer_snapshot = self.buffer_snapshot().context(SnapshotSnafu)?;
+        let mut deleted = false;
+        let mut snapshots = Vec::with_capacity(self.snapshots.len() + 1);
+        for snapshot in self.snapshots.iter().map(|s| &**s).chain(&buffer_snapshot) {
+            let data = delete_rows(&snapshot.data, &predicate.exprs).context(DeleteRowsSnafu)?;
+            deleted |= data.num_rows() < snapshot.data.num_rows();
+            // Snapshots whose rows are all deleted are dropped
+            if data.num_rows() > 0 {
+                snapshots.push(Arc::new(SnapshotBatch {
+                    min_sequencer_number: snapshot.min_sequencer_number,
+                    max_sequencer_number: snapshot.max_sequencer_number,
+                    data: Arc::new(data),
+                }));
+            }
+        }
+        if !deleted {
+            return Ok(false);
+        }
+
+        self.snapshots = snapshots;
+        self.buffer.clear();
+
+        Ok(true)
     }
 
     /// Add a persiting batch into the buffer persisting list
@@ -511,6 +2377,119 @@ impl DataBuffer {
     }
 }
 
+/// Return the given batch without the rows matching all of the given delete expressions
+fn delete_rows(batch: &RecordBatch, exprs: &[DeleteExpr]) -> Result<RecordBatch, ArrowError> {
+    let deleted = deleted_rows(batch, exprs)?;
+    let keep = BooleanArray::from(deleted.into_iter().map(|d| !d).collect::<Vec<_>>());
+    filter_record_batch(batch, &keep)
+}
+
+/// Return the rows of the given batch with a timestamp in `range` that are not deleted by any
+/// of the given delete predicates
+fn query_rows<'a>(
+    batch: &RecordBatch,
+    range: TimestampRange,
+    deletes: impl IntoIterator<Item = &'a Arc<DeletePredicate>>,
+) -> Result<RecordBatch, ArrowError> {
+    let keep = kept_rows(batch, range, deletes)?;
+    filter_record_batch(batch, &BooleanArray::from(keep))
+}
+
+/// Return the number of rows of the given batch with a timestamp in `range` that are not
+/// deleted by any of the given delete predicates
+fn count_rows<'a>(
+    batch: &RecordBatch,
+    range: TimestampRange,
+    deletes: impl IntoIterator<Item = &'a Arc<DeletePredicate>>,
+) -> Result<usize, ArrowError> {
+    let keep = kept_rows(batch, range, deletes)?;
+    Ok(keep.into_iter().filter(|keep| *keep).count())
+}
+
+/// Return for each row of the given batch whether its timestamp is in `range` and it is not
+/// deleted by any of the given delete predicates
+fn kept_rows<'a>(
+    batch: &RecordBatch,
+    range: TimestampRange,
+    deletes: impl IntoIterator<Item = &'a Arc<DeletePredicate>>,
+) -> Result<Vec<bool>, ArrowError> {
+    let times = batch.column(batch.schema().index_of(TIME_COLUMN_NAME)?);
+    let times: Vec<_> = as_primitive_array::<TimestampNanosecondType>(times)
+        .iter()
+        .collect();
+
+    let mut keep: Vec<_> = times.iter().map(|t| range.contains_opt(*t)).collect();
+    for delete in deletes {
+        let deleted = deleted_rows(batch, &delete.exprs)?;
+        for ((keep, deleted), t) in keep.iter_mut().zip(deleted).zip(&times) {
+            *keep &= !(deleted && delete.range.contains_opt(*t));
+        }
+    }
+
+    Ok(keep)
+}
+
+/// Return for each row of the given batch whether it matches all of the given delete
+/// expressions
+fn deleted_rows(batch: &RecordBatch, exprs: &[DeleteExpr]) -> Result<Vec<bool>, ArrowError> {
+    let mut deleted = vec![true; batch.num_rows()];
+    for expr in exprs {
+        // A missing column is all NULL, which no expression matches
+        let matches = match batch.schema().index_of(expr.column()) {
+            Ok(index) => delete_expr_matches(batch.column(index), expr)?,
+            Err(_) => vec![false; batch.num_rows()],
+        };
+        for (deleted, matches) in deleted.iter_mut().zip(matches) {
+            *deleted &= matches;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Evaluate the given delete expression on each value of the given column
+fn delete_expr_matches(column: &ArrayRef, expr: &DeleteExpr) -> Result<Vec<bool>, ArrowError> {
+    let equal: Vec<Option<bool>> = match expr.scalar() {
+        Scalar::Bool(v) => {
+            let column = cast(column, &DataType::Boolean)?;
+            as_boolean_array(&column)
+                .iter()
+                .map(|x| x.map(|x| x == *v))
+                .collect()
+        }
+        Scalar::I64(v) => {
+            let column = cast(column, &DataType::Int64)?;
+            as_primitive_array::<Int64Type>(&column)
+                .iter()
+                .map(|x| x.map(|x| x == *v))
+                .collect()
+        }
+        Scalar::F64(v) => {
+            let column = cast(column, &DataType::Float64)?;
+            as_primitive_array::<Float64Type>(&column)
+                .iter()
+                .map(|x| x.map(|x| x == v.0))
+                .collect()
+        }
+        Scalar::String(v) => {
+            let column = cast(column, &DataType::Utf8)?;
+            as_string_array(&column)
+                .iter()
+                .map(|x| x.map(|x| x == v))
+                .collect()
+        }
+    };
+
+    // NULL never matches
+    Ok(equal
+        .into_iter()
+        .map(|equal| match (equal, expr.op()) {
+            (Some(equal), Op::Eq) => equal,
+            (Some(equal), Op::Ne) => !equal,
+            (None, _) => false,
+        })
+        .collect())
+}
+
 /// BufferBatch is a MutauableBatch with its ingesting order, sequencer_number, that
 /// helps the ingester keep the batches of data in thier ingesting order
 pub struct BufferBatch {
@@ -521,7 +2500,7 @@ pub struct BufferBatch {
 }
 
 /// SnapshotBatch contains data of many contiguous BufferBatches
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SnapshotBatch {
     /// Min sequencer number of its combined BufferBatches
     pub min_sequencer_number: SequenceNumber,
@@ -531,6 +2510,19 @@ pub struct SnapshotBatch {
     pub data: Arc<RecordBatch>,
 }
 
+impl SnapshotBatch {
+    /// Return the size of this snapshot in memory, in bytes
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .data
+                .columns()
+                .iter()
+                .map(|array| array.get_array_memory_size())
+                .sum::<usize>()
+    }
+}
+
 /// PersistingBatch contains all needed info and data for creating
 /// a parquet file for given set of SnapshotBatches
 #[derive(Debug, PartialEq)]
@@ -566,13 +2558,36 @@ pub struct QueryableBatch {
 
     /// This is needed to return a reference for a trait function
     pub table_name: String,
+
+    /// Unique ID of this chunk, allocated when the batch is created as the
+    /// same sequence numbers may be buffered for other partitions and
+    /// sequencers. Allocated with [`ChunkId::new_monotonic`] so that chunks
+    /// with the same order deduplicate deterministically
+    pub chunk_id: ChunkId,
+
+    /// The sequencer that assigned the sequence numbers of the data, if known
+    pub sequencer_id: Option<SequencerId>,
+
+    /// Key of the partition of the data, if known
+    pub partition_key: Option<Arc<str>>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
+    use crate::test_util::create_tombstone;
+    use arrow_util::assert_batches_eq;
+    use data_types::non_empty::NonEmptyString;
+    use data_types::sequence::Sequence;
+    use dml::{DmlDelete, DmlMeta, DmlWrite};
+    use futures::TryStreamExt;
+    use iox_catalog::interface::{KafkaTopic, Sequencer};
+    use iox_catalog::mem::MemCatalog;
+    use metric::{U64Counter, U64Histogram};
+    use mutable_batch_lp::{lines_to_batches, test_helpers::lp_to_mutable_batch};
+    use object_store::ObjectStoreApi;
     use test_helpers::assert_error;
+    use time::Time;
 
     #[test]
     fn snapshot_empty_buffer_adds_no_snapshots() {
@@ -682,6 +2697,209 @@ mod tests {
         assert_eq!(&*snapshot.data, &combined_record_batch);
     }
 
+    #[test]
+    fn snapshot_promotes_integer_fields_to_float() {
+        let mut data_buffer = DataBuffer::default();
+        data_buffer.buffer.push(make_buffer_batch(1, "foo v=1i 1"));
+        data_buffer.buffer.push(make_buffer_batch(2, "foo v=1.5 2"));
+        data_buffer.snapshot().unwrap();
+        // Routers with a stale schema still write integers to the promoted column
+        data_buffer.buffer.push(make_buffer_batch(3, "foo v=2.5 3"));
+        data_buffer.buffer.push(make_buffer_batch(4, "foo v=3i 4"));
+        data_buffer.snapshot().unwrap();
+        data_buffer.buffer.push(make_buffer_batch(5, "foo v=4i 5"));
+        data_buffer.snapshot().unwrap();
+
+        let types: Vec<_> = data_buffer
+            .snapshots
+            .iter()
+            .map(|s| {
+                s.data
+                    .schema()
+                    .field_with_name("v")
+                    .unwrap()
+                    .data_type()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![DataType::Float64, DataType::Float64, DataType::Int64]
+        );
+
+        // Snapshots of both types are merged to floats
+        let batches: Vec<_> = data_buffer
+            .snapshots
+            .iter()
+            .map(|s| Arc::clone(&s.data))
+            .collect();
+        let schema = merge_record_batch_schemas(&batches);
+        let merged = merge_record_batches(schema.as_arrow(), batches)
+            .unwrap()
+            .unwrap();
+        let expected = vec![
+            "+--------------------------------+-----+",
+            "| time                           | v   |",
+            "+--------------------------------+-----+",
+            "| 1970-01-01T00:00:00.000000001Z | 1   |",
+            "| 1970-01-01T00:00:00.000000002Z | 1.5 |",
+            "| 1970-01-01T00:00:00.000000003Z | 2.5 |",
+            "| 1970-01-01T00:00:00.000000004Z | 3   |",
+            "| 1970-01-01T00:00:00.000000005Z | 4   |",
+            "+--------------------------------+-----+",
+        ];
+        assert_batches_eq!(expected, &[merged]);
+    }
+
+    fn buffered_rows(data_buffer: &DataBuffer) -> usize {
+        data_buffer
+            .snapshots
+            .iter()
+            .map(|s| s.data.num_rows())
+            .sum::<usize>()
+            + data_buffer
+                .buffer
+                .iter()
+                .map(|b| b.data.rows())
+                .sum::<usize>()
+    }
+
+    fn make_buffer_batch(sequencer_number: i64, lp: &str) -> BufferBatch {
+        let (_, data) = lp_to_mutable_batch(lp);
+        BufferBatch {
+            sequencer_number: SequenceNumber::new(sequencer_number),
+            data,
+        }
+    }
+
+    #[test]
+    fn apply_delete_covering_buffer_drops_matching_rows() {
+        let mut data_buffer = DataBuffer::default();
+        data_buffer.buffer.push(make_buffer_batch(
+            1,
+            "foo,t1=asdf iv=1i 10\nfoo,t1=aoeu iv=2i 20",
+        ));
+        data_buffer.snapshot().unwrap();
+        data_buffer
+            .buffer
+            .push(make_buffer_batch(2, "foo,t1=asdf iv=3i 30\nfoo iv=4i 40"));
+        assert_eq!(buffered_rows(&data_buffer), 4);
+
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(0, 100),
+            exprs: vec![DeleteExpr::new(
+                "t1".to_string(),
+                Op::Eq,
+                Scalar::String("asdf".to_string()),
+            )],
+        };
+        assert!(data_buffer.apply_delete(&predicate).unwrap());
+
+        assert!(data_buffer.buffer.is_empty());
+        assert_eq!(data_buffer.snapshots.len(), 2);
+        assert_eq!(buffered_rows(&data_buffer), 2);
+    }
+
+    #[test]
+    fn apply_delete_dropping_all_rows_drops_snapshots() {
+        let mut data_buffer = DataBuffer::default();
+        data_buffer.buffer.push(make_buffer_batch(
+            1,
+            "foo,t1=asdf iv=1i 10\nfoo,t1=aoeu iv=2i 20",
+        ));
+
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(0, 100),
+            exprs: vec![],
+        };
+        assert!(data_buffer.apply_delete(&predicate).unwrap());
+
+        assert!(data_buffer.snapshots.is_empty());
+        assert_eq!(buffered_rows(&data_buffer), 0);
+    }
+
+    #[test]
+    fn apply_delete_not_covering_buffer_leaves_data_as_is() {
+        let mut data_buffer = DataBuffer::default();
+        data_buffer.buffer.push(make_buffer_batch(
+            1,
+            "foo,t1=asdf iv=1i 10\nfoo,t1=asdf iv=2i 200",
+        ));
+
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(0, 100),
+            exprs: vec![DeleteExpr::new(
+                "t1".to_string(),
+                Op::Eq,
+                Scalar::String("asdf".to_string()),
+            )],
+        };
+        assert!(!data_buffer.apply_delete(&predicate).unwrap());
+
+        assert_eq!(buffered_rows(&data_buffer), 2);
+    }
+
+    #[test]
+    fn partition_info_of_empty_partition_is_none() {
+        let partition = PartitionData::new(PartitionId::new(1));
+
+        assert_eq!(partition.info("foo").unwrap(), None);
+    }
+
+    #[test]
+    fn partition_info_describes_snapshots_and_buffer() {
+        let partition = PartitionData::new(PartitionId::new(1));
+        let (_, mutable_batch) = lp_to_mutable_batch(r#"foo,t2=a,t1=b iv=1i 1"#);
+        partition.buffer_write(SequenceNumber::new(1), mutable_batch);
+        partition.snapshot().unwrap();
+        let (_, mutable_batch) = lp_to_mutable_batch(r#"foo,t1=c,t3=d fv=1.0 2"#);
+        partition.buffer_write(SequenceNumber::new(2), mutable_batch);
+
+        let info = partition.info("foo").unwrap().unwrap();
+
+        // Without statistics, the sort key is the primary key
+        assert_eq!(info.sort_key, vec!["t1", "t2", "t3", "time"]);
+        assert_eq!(info.chunks, 2);
+        assert!(!info.chunks_have_stats);
+        let columns: Vec<_> = info.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, vec!["fv", "iv", "t1", "t2", "t3", "time"]);
+        assert_eq!(
+            info.columns[2].influx_type.as_deref(),
+            Some("iox::column_type::tag")
+        );
+
+        // The buffered write was not moved to a snapshot
+        let data = partition.inner.read();
+        assert_eq!(data.buffer.len(), 1);
+        assert_eq!(data.snapshots.len(), 1);
+    }
+
+    #[test]
+    fn partition_query_data_applies_range_and_tombstones() {
+        let partition = PartitionData::new(PartitionId::new(1));
+        let (_, mutable_batch) = lp_to_mutable_batch("foo,t1=a iv=1i 10\nfoo,t1=b iv=2i 20");
+        partition.buffer_write(SequenceNumber::new(1), mutable_batch);
+        partition.snapshot().unwrap();
+        let (_, mutable_batch) = lp_to_mutable_batch("foo,t1=a iv=3i 30\nfoo,t1=b iv=4i 400");
+        partition.buffer_write(SequenceNumber::new(2), mutable_batch);
+        partition.buffer_tombstone(create_tombstone(1, 1, 1, 3, 0, 25, "t1=a"));
+
+        let batches = partition
+            .query_data("foo", TimestampRange::new(0, 100))
+            .unwrap();
+
+        let expected = vec![
+            "+----+----+--------------------------------+",
+            "| iv | t1 | time                           |",
+            "+----+----+--------------------------------+",
+            "| 2  | b  | 1970-01-01T00:00:00.000000020Z |",
+            "| 3  | a  | 1970-01-01T00:00:00.000000030Z |",
+            "+----+----+--------------------------------+",
+        ];
+        let batches: Vec<_> = batches.iter().map(|b| b.as_ref().clone()).collect();
+        assert_batches_eq!(expected, &batches);
+    }
+
     #[test]
     fn snapshot_buffer_error_leaves_data_buffer_as_is() {
         let mut data_buffer = DataBuffer::default();
@@ -714,4 +2932,1273 @@ mod tests {
         assert_eq!(data_buffer.buffer.len(), 2);
         assert!(data_buffer.snapshots.is_empty());
     }
+
+    /// A catalog with a kafka topic, a query pool, a sequencer and the given namespaces
+    struct TestCatalog {
+        catalog: Arc<dyn Catalog>,
+        kafka_topic: KafkaTopic,
+        sequencer: Sequencer,
+        namespaces: Vec<Namespace>,
+    }
+
+    impl TestCatalog {
+        async fn new(namespaces: &[&str]) -> Self {
+            let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new());
+            let kafka_topic = catalog
+                .kafka_topics()
+                .create_or_get("whatevs")
+                .await
+                .unwrap();
+            let query_pool = catalog
+                .query_pools()
+                .create_or_get("whatevs")
+                .await
+                .unwrap();
+            let sequencer = catalog
+                .sequencers()
+                .create_or_get(&kafka_topic, KafkaPartition::new(0))
+                .await
+                .unwrap();
+
+            let mut created = Vec::with_capacity(namespaces.len());
+            for namespace in namespaces {
+                created.push(
+                    catalog
+                        .namespaces()
+                        .create(namespace, Some("inf"), kafka_topic.id, query_pool.id)
+                        .await
+                        .unwrap(),
+                );
+            }
+
+            Self {
+                catalog,
+                kafka_topic,
+                sequencer,
+                namespaces: created,
+            }
+        }
+
+        /// Data of an ingester buffering the writes of the sequencer, with the defaults of
+        /// all other options
+        fn ingester_data(&self) -> IngesterData {
+            IngesterData {
+                object_store: Arc::new(ObjectStore::new_in_memory()),
+                catalog: Arc::clone(&self.catalog),
+                sequencers: BTreeMap::from([(self.sequencer.id, SequencerData::default())]),
+                eager_deletes: false,
+                exec: Arc::new(Executor::new(1)),
+                persist_metrics: PersistMetrics::new(&metric::Registry::new()),
+                reject_out_of_order: RejectOutOfOrder::default(),
+                wal: None,
+                partition_limit: PartitionLimit::new(&metric::Registry::new(), None),
+                persist_selection: None,
+            }
+        }
+    }
+
+    /// A write of `lp` to `namespace` with the given sequence number of sequencer 0
+    fn sequenced_write(namespace: &str, sequence_number: u64, lp: &str) -> DmlOperation {
+        DmlOperation::Write(DmlWrite::new(
+            namespace,
+            lines_to_batches(lp, 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, sequence_number),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        ))
+    }
+
+    #[tokio::test]
+    async fn warm_up_avoids_catalog_lookups_when_buffering() {
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new());
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(0))
+            .await
+            .unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("mem", namespace.id)
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("1970-01-01", sequencer.id, table.id)
+            .await
+            .unwrap();
+        let unused_table = catalog
+            .tables()
+            .create_or_get("cpu", namespace.id)
+            .await
+            .unwrap();
+        catalog
+            .partitions()
+            .create_or_get("1970-01-01", sequencer.id, unused_table.id)
+            .await
+            .unwrap();
+
+        let data = IngesterData {
+            object_store: Arc::new(ObjectStore::new_in_memory()),
+            catalog: Arc::clone(&catalog),
+            sequencers: BTreeMap::from([(sequencer.id, SequencerData::default())]),
+            eager_deletes: false,
+            exec: Arc::new(Executor::new(1)),
+            persist_metrics: PersistMetrics::new(&metric::Registry::new()),
+            reject_out_of_order: RejectOutOfOrder::default(),
+            wal: None,
+            partition_limit: PartitionLimit::new(&metric::Registry::new(), None),
+            persist_selection: None,
+        };
+        data.warm_up(kafka_topic.id).await.unwrap();
+
+        // Nothing is buffered until it is written to
+        let sequencer_data = &data.sequencers[&sequencer.id];
+        assert!(sequencer_data.namespace("foo").is_none());
+
+        // Buffering with a catalog that knows nothing only works if no lookups are needed
+        let empty_catalog = MemCatalog::new();
+        let write = DmlWrite::new(
+            "foo",
+            lines_to_batches("mem foo=1 10", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 1),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        );
+        sequencer_data
+            .buffer_operation(
+                DmlOperation::Write(write),
+                sequencer.id,
+                &empty_catalog,
+                false,
+                &RejectOutOfOrder::default(),
+            )
+            .await
+            .unwrap();
+        assert!(empty_catalog
+            .tables()
+            .list_by_namespace_id(namespace.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(empty_catalog
+            .partitions()
+            .list_by_sequencer(sequencer.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let partition_data = sequencer_data
+            .namespace("foo")
+            .unwrap()
+            .table_data("mem")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap();
+        assert_eq!(partition_data.id, partition.id);
+        assert_eq!(partition_data.snapshot().unwrap().len(), 1);
+
+        // The records of the table that was not written to are kept until replay caught up
+        assert_eq!(sequencer_data.warmed_up.tables.lock().len(), 1);
+        assert_eq!(sequencer_data.warmed_up.partitions.lock().len(), 1);
+        data.finish_warm_up(sequencer.id);
+        assert!(sequencer_data.warmed_up.namespaces.lock().is_empty());
+        assert!(sequencer_data.warmed_up.tables.lock().is_empty());
+        assert!(sequencer_data.warmed_up.partitions.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reject_out_of_order_writes() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(0))
+            .await
+            .unwrap();
+        catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+
+        let sequencer_data = SequencerData::default();
+        let reject_out_of_order = RejectOutOfOrder::default().with_table("foo", "cpu");
+        let mut sequence_number = 0;
+        let mut write = |lp: &str| {
+            sequence_number += 1;
+            let write = DmlWrite::new(
+                "foo",
+                lines_to_batches(lp, 0).unwrap(),
+                DmlMeta::sequenced(
+                    Sequence::new(0, sequence_number),
+                    Time::from_timestamp_millis(42),
+                    None,
+                    50,
+                ),
+            );
+            sequencer_data.buffer_operation(
+                DmlOperation::Write(write),
+                sequencer.id,
+                &catalog,
+                false,
+                &reject_out_of_order,
+            )
+        };
+
+        // In order per series
+        write("cpu,host=a v=1 20\ncpu,host=b v=1 10").await.unwrap();
+        write("cpu,host=a v=2 30").await.unwrap();
+        // Same timestamp as the latest point of the series
+        write("cpu,host=b v=2 10").await.unwrap();
+
+        assert_error!(
+            write("cpu,host=a v=3 25").await,
+            Error::OutOfOrderWrite { ref series, timestamp: 25, latest: 30, rejected: 1, .. } if series == "host=a"
+        );
+        // Out of order within the write, only the older point is rejected
+        assert_error!(
+            write("cpu,host=c v=1 40\ncpu,host=c v=2 35").await,
+            Error::OutOfOrderWrite { ref series, timestamp: 35, latest: 40, rejected: 1, .. } if series == "host=c"
+        );
+        // The rows of other series and tables in the write are buffered
+        assert_error!(
+            write("cpu,host=a v=4 10\ncpu,host=d v=1 5\ncpu,host=a v=5 15\nmem,host=a v=1 1").await,
+            Error::OutOfOrderWrite { ref series, timestamp: 10, latest: 30, rejected: 2, .. } if series == "host=a"
+        );
+
+        // Other tables accept out-of-order writes
+        write("mem,host=a v=1 20").await.unwrap();
+        write("mem,host=a v=2 10").await.unwrap();
+
+        let table_rows = |table_name: &str| {
+            sequencer_data
+                .namespace("foo")
+                .unwrap()
+                .table_data(table_name)
+                .unwrap()
+                .partition_data("1970-01-01")
+                .unwrap()
+                .snapshot()
+                .unwrap()
+                .iter()
+                .map(|s| s.data.num_rows())
+                .sum::<usize>()
+        };
+        // Only the rejected rows were not buffered
+        assert_eq!(table_rows("cpu"), 6);
+        assert_eq!(table_rows("mem"), 3);
+    }
+
+    #[tokio::test]
+    async fn persisted_points_are_not_checked_for_out_of_order_writes() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(0))
+            .await
+            .unwrap();
+        catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+
+        let sequencer_data = SequencerData::default();
+        let reject_out_of_order = RejectOutOfOrder::default().with_table("foo", "cpu");
+        let mut sequence_number = 0;
+        let mut write = |lp: &str| {
+            sequence_number += 1;
+            let write = DmlWrite::new(
+                "foo",
+                lines_to_batches(lp, 0).unwrap(),
+                DmlMeta::sequenced(
+                    Sequence::new(0, sequence_number),
+                    Time::from_timestamp_millis(42),
+                    None,
+                    50,
+                ),
+            );
+            sequencer_data.buffer_operation(
+                DmlOperation::Write(write),
+                sequencer.id,
+                &catalog,
+                false,
+                &reject_out_of_order,
+            )
+        };
+
+        write("cpu,host=a v=1 20\ncpu,host=b v=1 20").await.unwrap();
+        write("cpu,host=b v=2 30").await.unwrap();
+
+        let table_data = sequencer_data
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap();
+        let partition_id = table_data.partition_data("1970-01-01").unwrap().id;
+        // The second write is still buffered
+        table_data.forget_persisted(partition_id, SequenceNumber::new(1));
+        assert_eq!(table_data.series_latest_timestamp.lock().len(), 1);
+
+        write("cpu,host=a v=2 10").await.unwrap();
+        assert_error!(
+            write("cpu,host=b v=3 25").await,
+            Error::OutOfOrderWrite { ref series, timestamp: 25, latest: 30, .. } if series == "host=b"
+        );
+
+        table_data.forget_persisted(partition_id, SequenceNumber::new(4));
+        assert!(table_data.series_latest_timestamp.lock().is_empty());
+        write("cpu,host=b v=3 25").await.unwrap();
+    }
+
+    #[test]
+    fn apply_delete_matching_no_rows_leaves_buffer_as_is() {
+        let mut data_buffer = DataBuffer::default();
+        data_buffer
+            .buffer
+            .push(make_buffer_batch(1, "foo,t1=aoeu iv=1i 10"));
+
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(0, 100),
+            exprs: vec![DeleteExpr::new(
+                "t1".to_string(),
+                Op::Eq,
+                Scalar::String("asdf".to_string()),
+            )],
+        };
+        assert!(!data_buffer.apply_delete(&predicate).unwrap());
+
+        // The buffer was not snapshotted
+        assert_eq!(data_buffer.buffer.len(), 1);
+        assert!(data_buffer.snapshots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn eager_deletes_drop_buffered_rows_and_buffer_tombstones() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let mut data = test.ingester_data();
+        data.eager_deletes = true;
+
+        let lp = "cpu,host=a usage=1 10\ncpu,host=b usage=2 20\ncpu,host=a usage=3 30";
+        data.buffer_operation(test.sequencer.id, sequenced_write("foo", 1, lp))
+            .await
+            .unwrap();
+
+        let delete = DmlOperation::Delete(DmlDelete::new(
+            "foo",
+            DeletePredicate {
+                range: TimestampRange::new(0, 100),
+                exprs: vec![DeleteExpr::new(
+                    "host".to_string(),
+                    Op::Eq,
+                    Scalar::String("a".to_string()),
+                )],
+            },
+            NonEmptyString::new("cpu"),
+            DmlMeta::sequenced(
+                Sequence::new(0, 2),
+                Time::from_timestamp_millis(43),
+                None,
+                10,
+            ),
+        ));
+        data.buffer_operation(test.sequencer.id, delete)
+            .await
+            .unwrap();
+
+        let cpu = data.sequencers[&test.sequencer.id]
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap();
+        let buffer = cpu.inner.read();
+        assert_eq!(buffered_rows(&buffer), 1);
+        // The tombstone is still buffered for the persisting and persisted data
+        assert_eq!(buffer.deletes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn count_rows_per_table_in_time_range() {
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new());
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, KafkaPartition::new(0))
+            .await
+            .unwrap();
+        catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+
+        let data = IngesterData {
+            object_store: Arc::new(ObjectStore::new_in_memory()),
+            catalog: Arc::clone(&catalog),
+            sequencers: BTreeMap::from([(sequencer.id, SequencerData::default())]),
+            eager_deletes: false,
+            exec: Arc::new(Executor::new(1)),
+            persist_metrics: PersistMetrics::new(&metric::Registry::new()),
+            reject_out_of_order: RejectOutOfOrder::default(),
+            wal: None,
+            partition_limit: PartitionLimit::new(&metric::Registry::new(), None),
+            persist_selection: None,
+        };
+        let write = |sequence_number: u64, lp: &str| {
+            DmlOperation::Write(DmlWrite::new(
+                "foo",
+                lines_to_batches(lp, 0).unwrap(),
+                DmlMeta::sequenced(
+                    Sequence::new(0, sequence_number),
+                    Time::from_timestamp_millis(42),
+                    None,
+                    50,
+                ),
+            ))
+        };
+
+        let lp = "\
+            cpu,host=a usage=1 10\n\
+            cpu,host=b usage=2 20\n\
+            cpu,host=a usage=3 70\n\
+            mem,host=a free=1i 30\n\
+            mem,host=a free=2i 80\n\
+            disk,host=a used=1i 90\n\
+        ";
+        data.buffer_operation(sequencer.id, write(1, lp))
+            .await
+            .unwrap();
+
+        let counts = data.count_rows("foo", TimestampRange::new(15, 75)).unwrap();
+        assert_eq!(
+            counts,
+            BTreeMap::from([("cpu".to_string(), 2), ("mem".to_string(), 1)])
+        );
+
+        // Snapshotted and buffered rows are counted, without the deleted ones
+        let cpu = data.sequencers[&sequencer.id]
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap();
+        cpu.snapshot().unwrap();
+        let lp = "cpu,host=b usage=4 40\ncpu,host=a usage=5 60";
+        data.buffer_operation(sequencer.id, write(2, lp))
+            .await
+            .unwrap();
+        cpu.buffer_tombstone(create_tombstone(1, 1, 1, 3, 50, 65, "host=a"));
+
+        let counts = data.count_rows("foo", TimestampRange::new(15, 75)).unwrap();
+        assert_eq!(
+            counts,
+            BTreeMap::from([("cpu".to_string(), 3), ("mem".to_string(), 1)])
+        );
+        // Counting left the buffer as is
+        let buffer = cpu.inner.read();
+        assert_eq!(buffer.buffer.len(), 1);
+        assert_eq!(buffer.snapshots.len(), 1);
+        drop(buffer);
+
+        assert!(data
+            .count_rows("bar", TimestampRange::new(15, 75))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn query_table_deduplicates_each_partition() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let data = test.ingester_data();
+        const DAY: i64 = 24 * 60 * 60 * 1_000_000_000;
+
+        let writes = [
+            "cpu,host=a usage=1,idle=9 10\ncpu,host=b usage=2 10".to_string(),
+            "cpu,host=a usage=3 10".to_string(),
+            format!("cpu,host=a usage=4 {}", DAY + 10),
+        ];
+        for (sequence_number, lp) in writes.iter().enumerate() {
+            data.buffer_operation(
+                test.sequencer.id,
+                sequenced_write("foo", sequence_number as u64 + 1, lp),
+            )
+            .await
+            .unwrap();
+        }
+        // Deduplicates across snapshots and the buffer
+        data.sequencers[&test.sequencer.id]
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap()
+            .snapshot()
+            .unwrap();
+        let lp = "cpu,host=b usage=5 10";
+        data.buffer_operation(test.sequencer.id, sequenced_write("foo", 4, lp))
+            .await
+            .unwrap();
+
+        let batches = data
+            .query_table(
+                "foo",
+                "cpu",
+                TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME),
+            )
+            .unwrap();
+        assert_eq!(batches.len(), 2);
+        let expected = vec![
+            "+------+------+--------------------------------+-------+",
+            "| host | idle | time                           | usage |",
+            "+------+------+--------------------------------+-------+",
+            "| a    | 9    | 1970-01-01T00:00:00.000000010Z | 3     |",
+            "| b    |      | 1970-01-01T00:00:00.000000010Z | 5     |",
+            "+------+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &batches[..1]);
+        let expected = vec![
+            "+------+--------------------------------+-------+",
+            "| host | time                           | usage |",
+            "+------+--------------------------------+-------+",
+            "| a    | 1970-01-02T00:00:00.000000010Z | 4     |",
+            "+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &batches[1..]);
+    }
+
+    #[tokio::test]
+    async fn queries_exclude_rows_outside_namespace_retention() {
+        let test = TestCatalog::new(&[]).await;
+        let query_pool = test
+            .catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let namespace = test
+            .catalog
+            .namespaces()
+            .create("foo", Some("1h"), test.kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let data = test.ingester_data();
+
+        let now = SystemProvider::new().now().timestamp_nanos();
+        let lp = format!("cpu,host=a usage=1 10\ncpu,host=b usage=2 {}", now);
+        data.buffer_operation(test.sequencer.id, sequenced_write("foo", 1, &lp))
+            .await
+            .unwrap();
+
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("cpu".to_string(), 1)])
+        );
+        let batches = data.query_table("foo", "cpu", all_time).unwrap();
+        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 1);
+
+        // The expired row is still buffered, only queries exclude it
+        let sequencer_data = &data.sequencers[&test.sequencer.id];
+        let namespace_data = sequencer_data.namespace("foo").unwrap();
+        assert_eq!(
+            namespace_data
+                .table_data("cpu")
+                .unwrap()
+                .count_rows("cpu", all_time)
+                .unwrap(),
+            2
+        );
+
+        // Changes to the retention apply once refreshed, and an invalid retention retains
+        // data forever
+        sequencer_data.refresh_retention(&[Namespace {
+            retention_duration: Some("bananas".to_string()),
+            ..namespace
+        }]);
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("cpu".to_string(), 2)])
+        );
+    }
+
+    #[tokio::test]
+    async fn snapshot_to_object_store_leaves_ingest_unaffected() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let catalog = &test.catalog;
+        let sequencer = &test.sequencer;
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let data = IngesterData {
+            object_store: Arc::clone(&object_store),
+            ..test.ingester_data()
+        };
+
+        for (sequence_number, lp) in [(1, "cpu,host=a usage=1 10"), (2, "cpu usage=2 20")] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+        }
+
+        let snapshot_id = Uuid::new_v4();
+        let paths = data
+            .snapshot_to_object_store(snapshot_id, Time::from_timestamp_millis(42))
+            .await
+            .unwrap();
+
+        // One file for the single partition, under the snapshot prefix
+        assert_eq!(paths.len(), 1);
+        let prefix = format!("{}/{}/", crate::persist::SNAPSHOT_PREFIX, snapshot_id);
+        assert!(paths[0].starts_with(&prefix), "{}", paths[0]);
+        let stored: Vec<_> = object_store
+            .list(None)
+            .await
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.to_raw())
+            .collect();
+        assert_eq!(stored, paths);
+
+        // Nothing is recorded as persisted
+        assert!(catalog
+            .parquet_files()
+            .list_by_sequencer_greater_than(sequencer.id, SequenceNumber::new(0))
+            .await
+            .unwrap()
+            .is_empty());
+        let sequencer_after = catalog
+            .sequencers()
+            .get_by_topic_id_and_partition(test.kafka_topic.id, KafkaPartition::new(0))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            sequencer_after.min_unpersisted_sequence_number,
+            sequencer.min_unpersisted_sequence_number
+        );
+
+        // And the data is still buffered
+        let counts = data
+            .count_rows("foo", TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME))
+            .unwrap();
+        assert_eq!(counts, BTreeMap::from([("cpu".to_string(), 2)]));
+
+        // Neither the snapshot nor the count moved the buffered writes to a snapshot
+        let partition_data = data.sequencers[&sequencer.id]
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap();
+        let buffer = partition_data.inner.read();
+        assert_eq!(buffer.buffer.len(), 2);
+        assert!(buffer.snapshots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn persist_partition_records_persist_metrics() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let registry = metric::Registry::new();
+        let data = IngesterData {
+            persist_metrics: PersistMetrics::new(&registry),
+            ..test.ingester_data()
+        };
+
+        let lp = "cpu,host=a usage=1 10\ncpu,host=b usage=2 20";
+        data.buffer_operation(test.sequencer.id, sequenced_write("foo", 1, lp))
+            .await
+            .unwrap();
+        let file = data
+            .persist_partition(test.sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let attributes = Attributes::from(&[("namespace", "foo")]);
+        let input_rows = registry
+            .get_instrument::<Metric<U64Counter>>("ingester_persist_input_rows")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(input_rows, 2);
+        let file_size = registry
+            .get_instrument::<Metric<U64Histogram>>("ingester_persist_file_size_bytes")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(file_size.sample_count(), 1);
+        assert_eq!(file_size.total, file.file_size_bytes as u64);
+
+        // The sort key is computed for the first file only, the next one reuses it
+        data.buffer_operation(test.sequencer.id, sequenced_write("foo", 2, lp))
+            .await
+            .unwrap();
+        data.persist_partition(test.sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        let sort_keys: u64 = ["present", "missing"]
+            .into_iter()
+            .map(|stats| {
+                registry
+                    .get_instrument::<Metric<U64Counter>>("query_sort_key_computed")
+                    .unwrap()
+                    .get_observer(&Attributes::from(&[
+                        ("component", "ingester"),
+                        ("stats", stats),
+                    ]))
+                    .unwrap()
+                    .fetch()
+            })
+            .sum();
+        assert_eq!(sort_keys, 1);
+    }
+
+    #[tokio::test]
+    async fn persist_partition_writes_a_file_and_drops_the_buffered_data() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let catalog = &test.catalog;
+        let sequencer = &test.sequencer;
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let data = IngesterData {
+            object_store: Arc::clone(&object_store),
+            ..test.ingester_data()
+        };
+
+        let lp = "cpu,host=a usage=1 10\ncpu,host=b usage=2 20";
+        data.buffer_operation(sequencer.id, sequenced_write("foo", 1, lp))
+            .await
+            .unwrap();
+
+        let file = data
+            .persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.min_sequence_number, SequenceNumber::new(1));
+        assert_eq!(file.max_sequence_number, SequenceNumber::new(1));
+        assert_eq!(file.min_time, Timestamp::new(10));
+        assert_eq!(file.max_time, Timestamp::new(20));
+
+        // The file is in object storage and recorded in the catalog
+        let stored = object_store
+            .list(None)
+            .await
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0]
+            .to_raw()
+            .ends_with(&format!("{}.parquet", file.object_store_id)));
+        let files = catalog
+            .parquet_files()
+            .list_by_partition(file.partition_id)
+            .await
+            .unwrap();
+        assert_eq!(files, vec![file]);
+
+        // The persisted data is no longer buffered, and nothing is left to persist
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        assert!(data.count_rows("foo", all_time).unwrap().is_empty());
+        assert!(data
+            .persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .is_none());
+
+        // The next file reuses the sort key of the first one, with the new tag added
+        let partition_data = data.sequencers[&sequencer.id]
+            .namespace("foo")
+            .unwrap()
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .unwrap();
+        assert_eq!(
+            partition_data.persisted_sort_key().unwrap(),
+            vec!["host", "time"]
+        );
+        let lp = "cpu,host=a,region=west usage=3 30";
+        data.buffer_operation(sequencer.id, sequenced_write("foo", 2, lp))
+            .await
+            .unwrap();
+        data.persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            partition_data.persisted_sort_key().unwrap(),
+            vec!["host", "region", "time"]
+        );
+        assert!(partition_data.inner.read().persisting.is_none());
+    }
+
+    #[tokio::test]
+    async fn compact_partition_files_replaces_the_files_of_a_partition() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let catalog = &test.catalog;
+        let sequencer = &test.sequencer;
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let data = IngesterData {
+            object_store: Arc::clone(&object_store),
+            ..test.ingester_data()
+        };
+
+        // Two files, the second one overwriting a row of the first one
+        for (sequence_number, lp) in [
+            (1, "cpu,host=a usage=1 10"),
+            (2, "cpu,host=a usage=2 10\ncpu,host=b usage=3 20"),
+        ] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+            data.persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        let compacted = data
+            .compact_partition_files(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap();
+        assert_eq!(compacted.len(), 1);
+        let file = &compacted[0];
+        assert_eq!(file.min_sequence_number, SequenceNumber::new(1));
+        assert_eq!(file.max_sequence_number, SequenceNumber::new(2));
+
+        // The inputs are marked for deletion, leaving the new file as the only one in use
+        let files = catalog
+            .parquet_files()
+            .list_by_partition(file.partition_id)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 3);
+        for f in &files {
+            assert_eq!(
+                f.to_delete.is_none(),
+                f.object_store_id == file.object_store_id
+            );
+        }
+
+        // The new file holds the deduplicated rows, the latest write winning
+        let batch = read_persisted(test.namespaces[0].id, file, &object_store)
+            .await
+            .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let usage = batch.column(batch.schema().index_of("usage").unwrap());
+        let usage = as_primitive_array::<Float64Type>(usage);
+        let mut values: Vec<_> = usage.iter().map(Option::unwrap).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(values, vec![2.0, 3.0]);
+
+        // Nothing is left to compact
+        assert!(data
+            .compact_partition_files(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn persist_partitions_writes_one_file_per_partition() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let sequencer = &test.sequencer;
+        let data = test.ingester_data();
+
+        // Two partitions of the same table, one of them written twice
+        for (sequence_number, lp) in [
+            (1, "cpu,host=a usage=1 10"),
+            (2, "cpu,host=a usage=2 86400000000010"),
+            (3, "cpu,host=b usage=3 20"),
+        ] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+        }
+
+        let partition_keys = vec![
+            "1970-01-01".to_string(),
+            "1970-01-02".to_string(),
+            "1970-01-03".to_string(),
+        ];
+        let files = data
+            .persist_partitions(sequencer.id, "foo", "cpu", &partition_keys)
+            .await
+            .unwrap();
+
+        // One file per partition with data, covering the writes of that partition only
+        let ranges: Vec<_> = files
+            .iter()
+            .map(|f| (f.min_sequence_number.get(), f.max_sequence_number.get()))
+            .collect();
+        assert_eq!(ranges, vec![(1, 3), (2, 2)]);
+        assert_ne!(files[0].partition_id, files[1].partition_id);
+        for file in &files {
+            let in_catalog = test
+                .catalog
+                .parquet_files()
+                .list_by_partition(file.partition_id)
+                .await
+                .unwrap();
+            assert_eq!(in_catalog, vec![file.clone()]);
+        }
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        assert!(data.count_rows("foo", all_time).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn persist_partition_with_selection_writes_bounded_files() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let sequencer = &test.sequencer;
+        let data = IngesterData {
+            persist_selection: Some(CompactionInputSelection {
+                target_total_bytes: u64::MAX,
+                max_files: 2,
+            }),
+            ..test.ingester_data()
+        };
+
+        // Three snapshots of one partition
+        for (sequence_number, lp) in [
+            (1, "cpu,host=a usage=1 10"),
+            (2, "cpu,host=b usage=2 20"),
+            (3, "cpu,host=a usage=3 30"),
+        ] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+            data.sequencers[&sequencer.id]
+                .namespace("foo")
+                .unwrap()
+                .table_data("cpu")
+                .unwrap()
+                .partition_data("1970-01-01")
+                .unwrap()
+                .snapshot()
+                .unwrap();
+        }
+
+        // The oldest two snapshots are persisted first, the last one is left buffered
+        let file = data
+            .persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.min_sequence_number, SequenceNumber::new(1));
+        assert_eq!(file.max_sequence_number, SequenceNumber::new(2));
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        let expected_counts = BTreeMap::from([("cpu".to_string(), 1)]);
+        assert_eq!(data.count_rows("foo", all_time).unwrap(), expected_counts);
+
+        let file = data
+            .persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.min_sequence_number, SequenceNumber::new(3));
+        assert_eq!(file.max_sequence_number, SequenceNumber::new(3));
+        assert!(data.count_rows("foo", all_time).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recover_buffered_data_from_wal() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let sequencer = &test.sequencer;
+
+        let wal_dir = tempfile::tempdir().unwrap();
+        let new_data = |wal: Wal| IngesterData {
+            wal: Some(Arc::new(wal)),
+            ..test.ingester_data()
+        };
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        for (sequence_number, lp) in [
+            (1, "cpu,host=a usage=1 10\ncpu,host=b usage=2 10"),
+            (2, "cpu,host=a usage=3 20\nmem free=1i 20"),
+        ] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+        }
+        let expected_counts = BTreeMap::from([("cpu".to_string(), 3), ("mem".to_string(), 1)]);
+        assert_eq!(data.count_rows("foo", all_time).unwrap(), expected_counts);
+
+        // Crash, losing everything buffered, and restart on the same log
+        drop(data);
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        assert!(data.count_rows("foo", all_time).unwrap().is_empty());
+
+        let recovered = data.recover_from_wal().await.unwrap();
+        assert_eq!(
+            recovered,
+            BTreeMap::from([(sequencer.id, SequenceNumber::new(2))])
+        );
+        assert_eq!(data.count_rows("foo", all_time).unwrap(), expected_counts);
+
+        // Once the first write is persisted, it is no longer recovered
+        data.trim_wal(sequencer.id, SequenceNumber::new(2))
+            .await
+            .unwrap();
+        drop(data);
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        let recovered = data.recover_from_wal().await.unwrap();
+        assert_eq!(
+            recovered,
+            BTreeMap::from([(sequencer.id, SequenceNumber::new(2))])
+        );
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("cpu".to_string(), 1), ("mem".to_string(), 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn persisted_operations_are_not_recovered_from_wal() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let sequencer = &test.sequencer;
+
+        let wal_dir = tempfile::tempdir().unwrap();
+        let new_data = |wal: Wal| IngesterData {
+            wal: Some(Arc::new(wal)),
+            ..test.ingester_data()
+        };
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        for (sequence_number, lp) in [(1, "cpu usage=1 10"), (2, "mem free=1i 20")] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+        }
+        data.persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Crash and restart on the same log: only the unpersisted write is recovered
+        drop(data);
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        let recovered = data.recover_from_wal().await.unwrap();
+        assert_eq!(
+            recovered,
+            BTreeMap::from([(sequencer.id, SequenceNumber::new(2))])
+        );
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("mem".to_string(), 1)])
+        );
+
+        // Once everything is persisted, nothing is buffered again. The log segment written
+        // before the restart is kept, but the catalog records that its operations are persisted.
+        data.persist_partition(sequencer.id, "foo", "mem", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+        let sequencers = test.catalog.sequencers().list().await.unwrap();
+        assert_eq!(sequencers[0].min_unpersisted_sequence_number, 3);
+        drop(data);
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        assert_eq!(
+            data.recover_from_wal().await.unwrap(),
+            BTreeMap::from([(sequencer.id, SequenceNumber::new(2))])
+        );
+        assert!(data.count_rows("foo", all_time).unwrap().is_empty());
+
+        // Each write was persisted once
+        let files = test
+            .catalog
+            .parquet_files()
+            .list_by_sequencer_greater_than(sequencer.id, SequenceNumber::new(0))
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn writes_persisted_in_their_partition_are_not_recovered_from_wal() {
+        let test = TestCatalog::new(&["foo"]).await;
+        let sequencer = &test.sequencer;
+
+        let wal_dir = tempfile::tempdir().unwrap();
+        let new_data = |wal: Wal| IngesterData {
+            wal: Some(Arc::new(wal)),
+            ..test.ingester_data()
+        };
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        for (sequence_number, lp) in [
+            (1, "cpu usage=1 10"),
+            (2, "mem free=1i 20"),
+            (3, "cpu usage=2 30"),
+        ] {
+            data.buffer_operation(sequencer.id, sequenced_write("foo", sequence_number, lp))
+                .await
+                .unwrap();
+        }
+        data.persist_partition(sequencer.id, "foo", "cpu", "1970-01-01")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Crash and restart on the same log: the operations from the unpersisted write on are
+        // recovered, but the later write to cpu is persisted already and not buffered again
+        drop(data);
+        let data = new_data(Wal::new(wal_dir.path()).await.unwrap());
+        data.recover_from_wal().await.unwrap();
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("mem".to_string(), 1)])
+        );
+
+        // Writes after the persisted ones are buffered
+        data.buffer_operation(sequencer.id, sequenced_write("foo", 4, "cpu usage=3 40"))
+            .await
+            .unwrap();
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("cpu".to_string(), 1), ("mem".to_string(), 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn partition_limit_persists_oldest_partitions() {
+        let test = TestCatalog::new(&["foo", "bar"]).await;
+
+        let registry = metric::Registry::new();
+        let data = IngesterData {
+            persist_metrics: PersistMetrics::new(&registry),
+            partition_limit: PartitionLimit::new(&registry, Some(3)),
+            ..test.ingester_data()
+        };
+
+        let mut sequence_number = 0;
+        let mut write = |namespace: &str, lp: &str| {
+            sequence_number += 1;
+            data.buffer_operation(
+                test.sequencer.id,
+                sequenced_write(namespace, sequence_number, lp),
+            )
+        };
+        const DAY: i64 = 24 * 60 * 60 * 1_000_000_000;
+
+        // Partitions of all tables of the namespace count towards the limit
+        write("foo", "cpu v=1 0\nmem v=1 0").await.unwrap();
+        write("foo", &format!("cpu v=1 {}", DAY)).await.unwrap();
+        // The partition with the oldest data is persisted to make room
+        write("foo", &format!("cpu v=1 {}", 2 * DAY)).await.unwrap();
+        write("foo", "disk v=1 0").await.unwrap();
+
+        // Buffered partitions still accept writes, and other namespaces have their own limit
+        write("foo", &format!("cpu v=2 {}", DAY + 1)).await.unwrap();
+        write("bar", &format!("cpu v=1 {}", 2 * DAY)).await.unwrap();
+
+        let all_time = TimestampRange::new(MIN_NANO_TIME, MAX_NANO_TIME);
+        assert_eq!(
+            data.count_rows("foo", all_time).unwrap(),
+            BTreeMap::from([("cpu".to_string(), 3), ("disk".to_string(), 1)])
+        );
+
+        // The persisted partitions are no longer buffered
+        let namespace_data = data.sequencers[&test.sequencer.id]
+            .namespace("foo")
+            .unwrap();
+        assert_eq!(namespace_data.partition_count(), 3);
+        assert!(namespace_data
+            .table_data("cpu")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .is_none());
+        assert!(namespace_data
+            .table_data("mem")
+            .unwrap()
+            .partition_data("1970-01-01")
+            .is_none());
+        let files = test
+            .catalog
+            .parquet_files()
+            .list_by_sequencer_greater_than(test.sequencer.id, SequenceNumber::new(0))
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files
+            .iter()
+            .all(|f| f.min_sequence_number == SequenceNumber::new(1)));
+
+        let buffered_partitions = |namespace: &'static str| {
+            registry
+                .get_instrument::<Metric<U64Gauge>>("ingester_buffered_partitions")
+                .unwrap()
+                .get_observer(&Attributes::from(&[
+                    ("namespace", namespace),
+                    ("sequencer_id", "1"),
+                ]))
+                .unwrap()
+                .fetch()
+        };
+        assert_eq!(buffered_partitions("foo"), 3);
+        assert_eq!(buffered_partitions("bar"), 1);
+    }
 }
@@ -1,22 +1,44 @@
 //! Ingest handler
 
-use iox_catalog::interface::{Catalog, KafkaPartition, KafkaTopic, Sequencer, SequencerId};
+use iox_catalog::interface::{
+    Catalog, KafkaPartition, KafkaTopic, KafkaTopicId, ParquetFile, Sequencer, SequencerId,
+    Timestamp,
+};
 use object_store::ObjectStore;
+use query::exec::Executor;
 
-use crate::data::{IngesterData, SequencerData};
+use crate::{
+    cleanup::delete_unneeded_parquet_files,
+    compact::CompactionInputSelection,
+    data::{IngesterData, PartitionInfo, PartitionLimit, RejectOutOfOrder, SequencerData},
+    persist::PersistMetrics,
+    wal::Wal,
+};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use data_types::timestamp::TimestampRange;
 use db::write_buffer::metrics::{SequencerMetrics, WriteBufferIngestMetrics};
 use dml::DmlOperation;
-use futures::{stream::BoxStream, StreamExt};
-use observability_deps::tracing::{debug, warn};
-use snafu::Snafu;
-use std::collections::BTreeMap;
+use futures::{future::join_all, stream::BoxStream, StreamExt};
+use metric::{Attributes, DurationHistogram};
+use observability_deps::tracing::{debug, error, info, warn};
+use snafu::{ResultExt, Snafu};
+use std::collections::{BTreeMap, BTreeSet};
 use std::{
     fmt::Formatter,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
-use tokio::task::JoinHandle;
+use time::{SystemProvider, Time, TimeProvider};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
 use trace::span::SpanRecorder;
+use uuid::Uuid;
 use write_buffer::core::{FetchHighWatermark, WriteBufferError, WriteBufferReading};
 
 #[derive(Debug, Snafu)]
@@ -31,13 +53,93 @@ pub enum Error {
         kafka_topic: String,
         kafka_partition: KafkaPartition,
     },
+
+    #[snafu(display(
+        "Unable to seek kafka partition {} to timestamp {}: {}",
+        kafka_partition,
+        timestamp,
+        source
+    ))]
+    SeekToTimestamp {
+        kafka_partition: KafkaPartition,
+        timestamp: Time,
+        source: WriteBufferError,
+    },
+
+    #[snafu(display("The write buffer consumer is not running"))]
+    ConsumerNotRunning,
 }
 
 /// A specialized `Error` for Catalog errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// The [`IngestHandler`] handles all ingest from kafka, persistence and queries
-pub trait IngestHandler {}
+#[async_trait]
+pub trait IngestHandler {
+    /// Return debugging information about the data buffered for the given
+    /// partition, or `None` if there is no such partition or it has no data
+    fn partition_info(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Option<PartitionInfo>, crate::data::Error>;
+
+    /// Return the data buffered for the given table by all sequencers, restricted to rows with
+    /// a timestamp in `range`, as one deduplicated batch per partition. No batches are
+    /// returned if no rows are buffered.
+    fn query_table(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<RecordBatch>, crate::data::Error>;
+
+    /// Return the number of rows buffered for each table of the namespace by all sequencers,
+    /// restricted to rows with a timestamp in `range`. Tables without such rows are omitted.
+    fn count_rows(
+        &self,
+        namespace: &str,
+        range: TimestampRange,
+    ) -> Result<BTreeMap<String, u64>, crate::data::Error>;
+
+    /// Write a copy of all buffered data to parquet files under `snapshot_id` in the snapshot
+    /// prefix of the object store, without persisting it, returning the paths of the files
+    async fn snapshot(&self, snapshot_id: Uuid) -> Result<Vec<String>, crate::data::Error>;
+
+    /// Move the consumer of every kafka partition of this ingester to the first entry produced at
+    /// or after `timestamp`, returning the sequence number consumption resumes from per partition
+    async fn seek_to_timestamp(&self, timestamp: Time) -> Result<BTreeMap<KafkaPartition, u64>>;
+
+    /// Persist the data buffered for the given partition to a parquet file, returning the
+    /// catalog record of the file, or `None` if the partition has no buffered data
+    async fn persist_partition(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Option<ParquetFile>, crate::data::Error>;
+
+    /// Compact the parquet files persisted for the given partition, returning the catalog
+    /// records of the new files. The compacted files are marked for deletion and removed from
+    /// object storage by the garbage collector later on.
+    async fn compact_partition(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Vec<ParquetFile>, crate::data::Error>;
+}
+
+/// Request to the write buffer consumer to seek all of its kafka partitions to a timestamp
+#[derive(Debug)]
+struct SeekRequest {
+    timestamp: Time,
+    response: oneshot::Sender<Result<BTreeMap<KafkaPartition, u64>>>,
+}
 
 /// Implementation of the `IngestHandler` trait to ingest from kafka and manage persistence and answer queries
 pub struct IngestHandlerImpl {
@@ -48,8 +150,9 @@ pub struct IngestHandlerImpl {
     #[allow(dead_code)]
     join_handles: Vec<JoinHandle<()>>,
     /// The cache and buffered data for the ingester
-    #[allow(dead_code)]
     data: Arc<IngesterData>,
+    /// Sends seek requests to the write buffer consumer
+    seek_tx: mpsc::Sender<SeekRequest>,
 }
 
 impl std::fmt::Debug for IngestHandlerImpl {
@@ -60,13 +163,23 @@ impl std::fmt::Debug for IngestHandlerImpl {
 
 impl IngestHandlerImpl {
     /// Initialize the Ingester
+    ///
+    /// With a write-ahead log, the data buffered before a restart is recovered from it and
+    /// consumption of the write buffer continues after the recovered operations.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         topic: KafkaTopic,
-        mut sequencer_states: BTreeMap<KafkaPartition, Sequencer>,
+        sequencer_states: BTreeMap<KafkaPartition, Sequencer>,
         catalog: Arc<dyn Catalog>,
         object_store: Arc<ObjectStore>,
+        exec: Arc<Executor>,
         write_buffer: Box<dyn WriteBufferReading>,
         registry: &metric::Registry,
+        eager_deletes: bool,
+        reject_out_of_order: RejectOutOfOrder,
+        wal: Option<Wal>,
+        max_partitions_per_namespace: Option<usize>,
+        persist_selection: Option<CompactionInputSelection>,
     ) -> Self {
         // build the initial ingester data state
         let mut sequencers = BTreeMap::new();
@@ -77,50 +190,156 @@ impl IngestHandlerImpl {
             object_store,
             catalog,
             sequencers,
+            eager_deletes,
+            exec,
+            persist_metrics: PersistMetrics::new(registry),
+            reject_out_of_order,
+            wal: wal.map(Arc::new),
+            partition_limit: PartitionLimit::new(registry, max_partitions_per_namespace),
+            persist_selection,
         });
 
-        let ingester_data = Arc::clone(&data);
-        let kafka_topic_name = topic.name.clone();
         let ingest_metrics = WriteBufferIngestMetrics::new(registry, &topic.name);
+        let warm_up_duration = registry
+            .register_metric::<DurationHistogram>(
+                "ingester_warm_up_duration",
+                "wall-clock time taken to load the catalog state of the ingester on startup",
+            )
+            .recorder(Attributes::from([(
+                "kafka_topic",
+                topic.name.clone().into(),
+            )]));
 
-        let write_buffer: &'static mut _ = Box::leak(write_buffer);
-        let join_handles: Vec<_> = write_buffer
-            .streams()
-            .into_iter()
-            .filter_map(|(kafka_partition_id, stream)| {
-                // streams may return a stream for every partition in the kafka topic. We only want
-                // to process streams for those specified by the call to new.
-                let kafka_partition = KafkaPartition::new(kafka_partition_id as i32);
-                sequencer_states.remove(&kafka_partition).map(|sequencer| {
-                    let metrics = ingest_metrics.new_sequencer_metrics(kafka_partition_id);
-                    let ingester_data = Arc::clone(&ingester_data);
-                    let kafka_topic_name = kafka_topic_name.clone();
-
-                    tokio::task::spawn(async move {
-                        stream_in_sequenced_entries(
-                            ingester_data,
-                            sequencer.id,
-                            kafka_topic_name,
-                            kafka_partition,
-                            stream.stream,
-                            stream.fetch_high_watermark,
-                            metrics,
-                        )
-                        .await;
-                    })
-                })
-            })
-            .collect();
+        // Every kafka partition is buffered by a task of its own, fed with the operations read
+        // from the write buffer by the consumer task
+        let seek_generation = Arc::new(AtomicU64::new(0));
+        let mut join_handles = Vec::with_capacity(sequencer_states.len() + 3);
+        let mut sequencers = BTreeMap::new();
+        let mut senders = BTreeMap::new();
+        for (kafka_partition, sequencer) in sequencer_states {
+            let (tx, rx) = mpsc::channel(READ_AHEAD_OPERATIONS);
+            join_handles.push(tokio::task::spawn(stream_in_sequenced_entries(
+                Arc::clone(&data),
+                sequencer.id,
+                topic.name.clone(),
+                kafka_partition,
+                rx,
+                ingest_metrics.new_sequencer_metrics(kafka_partition.get() as u32),
+                Arc::clone(&seek_generation),
+            )));
+            sequencers.insert(kafka_partition, sequencer.id);
+            senders.insert(kafka_partition, tx);
+        }
+
+        let (seek_tx, seek_rx) = mpsc::channel(1);
+        join_handles.push(tokio::task::spawn(consume_write_buffer(
+            write_buffer,
+            Arc::clone(&data),
+            topic.id,
+            topic.name.clone(),
+            warm_up_duration,
+            sequencers,
+            senders,
+            seek_rx,
+            seek_generation,
+        )));
+        join_handles.push(tokio::task::spawn(refresh_retention(
+            Arc::clone(&data),
+            topic.id,
+        )));
+        join_handles.push(tokio::task::spawn(collect_garbage(Arc::clone(&data))));
 
         Self {
             data,
             kafka_topic: topic,
             join_handles,
+            seek_tx,
         }
     }
 }
 
-impl IngestHandler for IngestHandlerImpl {}
+#[async_trait]
+impl IngestHandler for IngestHandlerImpl {
+    fn partition_info(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Option<PartitionInfo>, crate::data::Error> {
+        let partition = self
+            .data
+            .sequencers
+            .get(&sequencer_id)
+            .and_then(|s| s.namespace(namespace))
+            .and_then(|n| n.table_data(table_name))
+            .and_then(|t| t.partition_data(partition_key));
+
+        match partition {
+            Some(p) => p.info(table_name),
+            None => Ok(None),
+        }
+    }
+
+    fn query_table(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<RecordBatch>, crate::data::Error> {
+        self.data.query_table(namespace, table_name, range)
+    }
+
+    fn count_rows(
+        &self,
+        namespace: &str,
+        range: TimestampRange,
+    ) -> Result<BTreeMap<String, u64>, crate::data::Error> {
+        self.data.count_rows(namespace, range)
+    }
+
+    async fn snapshot(&self, snapshot_id: Uuid) -> Result<Vec<String>, crate::data::Error> {
+        let now = SystemProvider::new().now();
+        self.data.snapshot_to_object_store(snapshot_id, now).await
+    }
+
+    async fn seek_to_timestamp(&self, timestamp: Time) -> Result<BTreeMap<KafkaPartition, u64>> {
+        let (response, rx) = oneshot::channel();
+        self.seek_tx
+            .send(SeekRequest {
+                timestamp,
+                response,
+            })
+            .await
+            .map_err(|_| Error::ConsumerNotRunning)?;
+
+        rx.await.map_err(|_| Error::ConsumerNotRunning)?
+    }
+
+    async fn persist_partition(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Option<ParquetFile>, crate::data::Error> {
+        self.data
+            .persist_partition(sequencer_id, namespace, table_name, partition_key)
+            .await
+    }
+
+    async fn compact_partition(
+        &self,
+        sequencer_id: SequencerId,
+        namespace: &str,
+        table_name: &str,
+        partition_key: &str,
+    ) -> Result<Vec<ParquetFile>, crate::data::Error> {
+        self.data
+            .compact_partition_files(sequencer_id, namespace, table_name, partition_key)
+            .await
+    }
+}
 
 impl Drop for IngestHandlerImpl {
     fn drop(&mut self) {
@@ -130,19 +349,266 @@ impl Drop for IngestHandlerImpl {
     }
 }
 
-/// This is used to take entries from a `Stream` and put them in the
-/// mutable buffer, such as streaming entries from a write buffer.
+/// Number of operations read from the write buffer stream of a kafka partition ahead of
+/// the task buffering them
+const READ_AHEAD_OPERATIONS: usize = 10;
+
+/// How often the retention of the buffered namespaces is reloaded from the catalog
+const RETENTION_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the parquet files no longer needed are deleted from object storage
+const GARBAGE_COLLECTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How long parquet files marked for deletion are kept for the queries still reading them,
+/// and files without catalog record for the persist that is about to record them
+const GARBAGE_COLLECTION_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Initial and maximum delays between attempts to buffer an operation that fails to be
+/// appended to the write-ahead log
+const WAL_RETRY_INIT_BACKOFF: Duration = Duration::from_millis(100);
+const WAL_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// An operation read from the write buffer, or the error reading it
+#[derive(Debug)]
+struct ReadOperation {
+    result: Result<DmlOperation, WriteBufferError>,
+    /// High watermark of the kafka partition at the time
+    watermark: u64,
+    /// Number of seeks done before the operation was read, so that the operations read ahead
+    /// of a seek are discarded instead of buffered
+    seek_generation: u64,
+}
+
+/// Read the write buffer streams of the given kafka partitions and send their operations to
+/// the tasks buffering them in the ingester data until the handler is dropped, after warming
+/// up the ingester data from the catalog.
 ///
-/// Note all errors reading / parsing / writing entries from the write
-/// buffer are ignored.
-async fn stream_in_sequenced_entries<'a>(
+/// The streams borrow the write buffer, so a [`SeekRequest`] stops reading, seeks every
+/// kafka partition and then restarts the streams from their new positions. The operations
+/// still in the channels were read from the old positions: a successful seek bumps
+/// `seek_generation` so that the buffering tasks discard them.
+#[allow(clippy::too_many_arguments)]
+async fn consume_write_buffer(
+    mut write_buffer: Box<dyn WriteBufferReading>,
     ingester_data: Arc<IngesterData>,
-    sequencer_id: SequencerId,
+    kafka_topic_id: KafkaTopicId,
     kafka_topic: String,
+    warm_up_duration: DurationHistogram,
+    sequencers: BTreeMap<KafkaPartition, SequencerId>,
+    senders: BTreeMap<KafkaPartition, mpsc::Sender<ReadOperation>>,
+    mut seek_rx: mpsc::Receiver<SeekRequest>,
+    seek_generation: Arc<AtomicU64>,
+) {
+    // Failing to warm up only makes consumption slower, as everything missing is looked up
+    // in the catalog when first written to
+    let start = Instant::now();
+    match ingester_data.warm_up(kafka_topic_id).await {
+        Ok(()) => info!(%kafka_topic, "Warmed up ingester data from the catalog"),
+        Err(e) => warn!(%e, %kafka_topic, "Failed to warm up ingester data from the catalog"),
+    }
+    warm_up_duration.record(start.elapsed());
+
+    recover_from_wal(
+        write_buffer.as_mut(),
+        &ingester_data,
+        &kafka_topic,
+        &sequencers,
+    )
+    .await;
+
+    loop {
+        let request = {
+            let generation = seek_generation.load(Ordering::SeqCst);
+            let read: Vec<_> = write_buffer
+                .streams()
+                .into_iter()
+                .filter_map(|(kafka_partition_id, stream)| {
+                    // streams may return a stream for every partition in the kafka topic. We only want
+                    // to process streams for those specified by the call to new.
+                    let kafka_partition = KafkaPartition::new(kafka_partition_id as i32);
+                    senders.get(&kafka_partition).map(|tx| {
+                        read_sequenced_entries(
+                            &kafka_topic,
+                            kafka_partition,
+                            stream.stream,
+                            stream.fetch_high_watermark,
+                            tx,
+                            generation,
+                        )
+                    })
+                })
+                .collect();
+            let read = join_all(read);
+            tokio::pin!(read);
+
+            tokio::select! {
+                _ = &mut read => return,
+                request = seek_rx.recv() => match request {
+                    Some(request) => request,
+                    // the handler is gone, which also aborts this task
+                    None => return,
+                },
+            }
+        };
+
+        let result = seek_to_timestamp(
+            write_buffer.as_mut(),
+            &kafka_topic,
+            sequencers.keys().copied(),
+            request.timestamp,
+        )
+        .await;
+        if result.is_ok() {
+            seek_generation.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // the requester may have gone away in the meantime, which is fine
+        let _ = request.response.send(result);
+    }
+}
+
+/// Reload the retention of the namespaces of `kafka_topic_id` from the catalog every
+/// [`RETENTION_REFRESH_INTERVAL`] until the handler is dropped.
+async fn refresh_retention(ingester_data: Arc<IngesterData>, kafka_topic_id: KafkaTopicId) {
+    let mut interval = tokio::time::interval(RETENTION_REFRESH_INTERVAL);
+    // The first tick completes immediately, but the retention was just loaded with the
+    // namespaces
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = ingester_data.refresh_retention(kafka_topic_id).await {
+            warn!(
+                %e,
+                %kafka_topic_id,
+                "Failed to refresh the retention of namespaces from the catalog"
+            );
+        }
+    }
+}
+
+/// Delete the parquet files of the sequencers of `ingester_data` that are no longer needed
+/// from object storage every [`GARBAGE_COLLECTION_INTERVAL`] until the handler is dropped.
+async fn collect_garbage(ingester_data: Arc<IngesterData>) {
+    let sequencers: BTreeSet<_> = ingester_data.sequencers.keys().copied().collect();
+    let time_provider = SystemProvider::new();
+    let mut interval = tokio::time::interval(GARBAGE_COLLECTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = time_provider.now() - GARBAGE_COLLECTION_GRACE_PERIOD;
+        let result = delete_unneeded_parquet_files(
+            ingester_data.catalog.as_ref(),
+            &ingester_data.object_store,
+            &sequencers,
+            Timestamp::new(cutoff.timestamp_nanos()),
+        )
+        .await;
+        match result {
+            Ok(deleted) => debug!(deleted, "Deleted parquet files no longer needed"),
+            Err(e) => warn!(%e, "Failed to delete parquet files no longer needed"),
+        }
+    }
+}
+
+/// Seek the given kafka partitions of `write_buffer` to `timestamp`, returning the sequence
+/// number each of them was moved to.
+///
+/// The sequence numbers of all kafka partitions are looked up before any of them is moved, so
+/// that failing to look one up leaves every kafka partition where it was.
+async fn seek_to_timestamp(
+    write_buffer: &mut dyn WriteBufferReading,
+    kafka_topic: &str,
+    kafka_partitions: impl Iterator<Item = KafkaPartition>,
+    timestamp: Time,
+) -> Result<BTreeMap<KafkaPartition, u64>> {
+    let mut sequence_numbers = BTreeMap::new();
+    for kafka_partition in kafka_partitions {
+        let sequence_number = write_buffer
+            .sequence_number_at_timestamp(kafka_partition.get() as u32, timestamp)
+            .await
+            .context(SeekToTimestampSnafu {
+                kafka_partition,
+                timestamp,
+            })?;
+        sequence_numbers.insert(kafka_partition, sequence_number);
+    }
+
+    for (&kafka_partition, &sequence_number) in &sequence_numbers {
+        // the kafka partition is known to the write buffer by now, so seeking it only records
+        // the new position
+        write_buffer
+            .seek(kafka_partition.get() as u32, sequence_number)
+            .await
+            .context(SeekToTimestampSnafu {
+                kafka_partition,
+                timestamp,
+            })?;
+        info!(
+            %kafka_topic,
+            %kafka_partition,
+            sequence_number,
+            %timestamp,
+            "Seeked write buffer to timestamp",
+        );
+    }
+
+    Ok(sequence_numbers)
+}
+
+/// Buffer the operations of the write-ahead log of the ingester data, if any, and seek the
+/// write buffer past them, so that they aren't read from the write buffer again.
+///
+/// Failing to recover leaves the write buffer where it is, so the operations are read from it
+/// instead.
+async fn recover_from_wal(
+    write_buffer: &mut dyn WriteBufferReading,
+    ingester_data: &IngesterData,
+    kafka_topic: &str,
+    sequencers: &BTreeMap<KafkaPartition, SequencerId>,
+) {
+    let recovered = match ingester_data.recover_from_wal().await {
+        Ok(recovered) => recovered,
+        Err(e) => {
+            warn!(%e, %kafka_topic, "Failed to recover buffered data from the write-ahead log");
+            return;
+        }
+    };
+
+    for (kafka_partition, sequencer_id) in sequencers {
+        let sequence_number = match recovered.get(sequencer_id) {
+            Some(sequence_number) => sequence_number.get() as u64 + 1,
+            None => continue,
+        };
+        match write_buffer
+            .seek(kafka_partition.get() as u32, sequence_number)
+            .await
+        {
+            Ok(()) => info!(
+                %kafka_topic,
+                %kafka_partition,
+                sequence_number,
+                "Seeked write buffer past the operations recovered from the write-ahead log",
+            ),
+            Err(e) => warn!(
+                %e,
+                %kafka_topic,
+                %kafka_partition,
+                sequence_number,
+                "Failed to seek write buffer past the operations recovered from the log",
+            ),
+        }
+    }
+}
+
+/// Read the operations of a write buffer stream and send them, with the high watermark of
+/// their kafka partition and the current seek generation, to the task buffering that kafka
+/// partition until the stream or the task ends.
+async fn read_sequenced_entries<'a>(
+    kafka_topic: &str,
     kafka_partition: KafkaPartition,
     mut stream: BoxStream<'a, Result<DmlOperation, WriteBufferError>>,
     f_mark: FetchHighWatermark<'a>,
-    mut metrics: SequencerMetrics,
+    tx: &mpsc::Sender<ReadOperation>,
+    seek_generation: u64,
 ) {
     let mut watermark_last_updated: Option<Instant> = None;
     let mut watermark = 0_u64;
@@ -173,10 +639,43 @@ async fn stream_in_sequenced_entries<'a>(
             watermark_last_updated = Some(now);
         }
 
-        let ingest_recorder = metrics.recorder(watermark);
+        let operation = ReadOperation {
+            result: db_write_result,
+            watermark,
+            seek_generation,
+        };
+        if tx.send(operation).await.is_err() {
+            // the buffering task is gone, which only happens when the handler is dropped
+            return;
+        }
+    }
+}
+
+/// This is used to take entries read from a write buffer and put them in the
+/// mutable buffer.
+///
+/// Note all errors reading / parsing / writing entries from the write
+/// buffer are ignored, except failures to append them to the write-ahead
+/// log, which are retried. Operations read before the last seek are
+/// dropped.
+async fn stream_in_sequenced_entries(
+    ingester_data: Arc<IngesterData>,
+    sequencer_id: SequencerId,
+    kafka_topic: String,
+    kafka_partition: KafkaPartition,
+    mut rx: mpsc::Receiver<ReadOperation>,
+    mut metrics: SequencerMetrics,
+    seek_generation: Arc<AtomicU64>,
+) {
+    let mut warming_up = true;
+    while let Some(operation) = rx.recv().await {
+        if operation.seek_generation != seek_generation.load(Ordering::SeqCst) {
+            continue;
+        }
+        let ingest_recorder = metrics.recorder(operation.watermark);
 
         // get entry from sequencer
-        let dml_operation = match db_write_result {
+        let dml_operation = match operation.result {
             Ok(db_write) => db_write,
             // skip over invalid data in the write buffer so recovery can succeed
             Err(e) => {
@@ -191,6 +690,10 @@ async fn stream_in_sequenced_entries<'a>(
         };
 
         let ingest_recorder = ingest_recorder.operation(&dml_operation);
+        let caught_up = dml_operation
+            .meta()
+            .sequence()
+            .map_or(false, |s| s.number + 1 >= operation.watermark);
 
         // store entry
         let mut span_recorder = SpanRecorder::new(
@@ -200,15 +703,30 @@ async fn stream_in_sequenced_entries<'a>(
                 .map(|parent| parent.child("IOx write buffer")),
         );
 
-        let result = ingester_data
-            .buffer_operation(sequencer_id, dml_operation.clone())
-            .await;
+        let result = buffer_operation(
+            &ingester_data,
+            sequencer_id,
+            &kafka_topic,
+            dml_operation.clone(),
+        )
+        .await;
 
         match result {
             Ok(_) => {
                 ingest_recorder.success();
                 span_recorder.ok("stored write");
             }
+            Err(e @ crate::data::Error::OutOfOrderWrite { .. }) => {
+                // the rows in order are buffered and the others skipped like invalid data, but
+                // they point to a bug of the client
+                warn!(
+                    %e,
+                    %kafka_topic,
+                    %sequencer_id,
+                    "Rejected out-of-order rows of write from write buffer"
+                );
+                span_recorder.error("out-of-order rows in write");
+            }
             Err(e) => {
                 // skip over invalid data in the write buffer so recovery can succeed
                 debug!(
@@ -220,6 +738,44 @@ async fn stream_in_sequenced_entries<'a>(
                 span_recorder.error("cannot store write");
             }
         }
+
+        // Once replay caught up with the write buffer, the catalog records warmed up for
+        // everything that wasn't written to are unlikely to be needed
+        if warming_up && caught_up {
+            ingester_data.finish_warm_up(sequencer_id);
+            warming_up = false;
+        }
+    }
+}
+
+/// Buffer the operation, retrying with backoff for as long as it fails to be appended to the
+/// write-ahead log: skipping it would lose the write, and consuming the operations after it
+/// would lose its place in the log.
+async fn buffer_operation(
+    ingester_data: &IngesterData,
+    sequencer_id: SequencerId,
+    kafka_topic: &str,
+    dml_operation: DmlOperation,
+) -> Result<(), crate::data::Error> {
+    let mut backoff = WAL_RETRY_INIT_BACKOFF;
+    loop {
+        match ingester_data
+            .buffer_operation(sequencer_id, dml_operation.clone())
+            .await
+        {
+            Err(e @ crate::data::Error::Wal { .. }) => {
+                error!(
+                    %e,
+                    %kafka_topic,
+                    %sequencer_id,
+                    ?backoff,
+                    "Failed to append to the write-ahead log, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(WAL_RETRY_MAX_BACKOFF);
+            }
+            result => return result,
+        }
     }
 }
 
@@ -231,7 +787,7 @@ mod tests {
     use iox_catalog::interface::NamespaceSchema;
     use iox_catalog::mem::MemCatalog;
     use iox_catalog::validate_or_insert_schema;
-    use metric::{Attributes, Metric, U64Counter, U64Gauge};
+    use metric::{Metric, U64Counter, U64Gauge};
     use mutable_batch_lp::lines_to_batches;
     use std::num::NonZeroU32;
     use time::Time;
@@ -253,7 +809,7 @@ mod tests {
         let kafka_partition = KafkaPartition::new(0);
         let namespace = catalog
             .namespaces()
-            .create("foo", "inf", kafka_topic.id, query_pool.id)
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
             .await
             .unwrap();
         let sequencer = catalog
@@ -299,8 +855,14 @@ mod tests {
             sequencer_states,
             Arc::new(catalog),
             object_store,
+            Arc::new(Executor::new(1)),
             reading,
             &metrics,
+            false,
+            RejectOutOfOrder::default(),
+            None,
+            None,
+            None,
         );
 
         // give the writes some time to go through the buffer. Exit once we've verified there's
@@ -425,5 +987,425 @@ mod tests {
             .unwrap()
             .fetch();
         assert_eq!(observation, ingest_ts2.timestamp_nanos() as u64);
+
+        let warm_up_duration = metrics
+            .get_instrument::<Metric<DurationHistogram>>("ingester_warm_up_duration")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("kafka_topic", "whatevs")]))
+            .unwrap()
+            .fetch();
+        assert_eq!(warm_up_duration.sample_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn seek_to_timestamp_resumes_from_expected_entry() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let kafka_partition = KafkaPartition::new(0);
+        let namespace = catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, kafka_partition)
+            .await
+            .unwrap();
+        let mut sequencer_states = BTreeMap::new();
+        sequencer_states.insert(kafka_partition, sequencer);
+
+        let schema = NamespaceSchema::new(namespace.id, kafka_topic.id, query_pool.id);
+
+        let write_buffer_state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::try_from(1).unwrap());
+        let w1 = DmlWrite::new(
+            "foo",
+            lines_to_batches("mem foo=1 10", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 0),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        );
+        let schema = validate_or_insert_schema(w1.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+        write_buffer_state.push_write(w1);
+        let w2 = DmlWrite::new(
+            "foo",
+            lines_to_batches("cpu bar=2 20\ncpu bar=3 30", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 7),
+                Time::from_timestamp_millis(1337),
+                None,
+                150,
+            ),
+        );
+        let _schema = validate_or_insert_schema(w2.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+        write_buffer_state.push_write(w2);
+        let reading = Box::new(MockBufferForReading::new(write_buffer_state, None).unwrap());
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let metrics: Arc<metric::Registry> = Default::default();
+
+        let ingester = IngestHandlerImpl::new(
+            kafka_topic,
+            sequencer_states,
+            Arc::new(catalog),
+            object_store,
+            Arc::new(Executor::new(1)),
+            reading,
+            &metrics,
+            false,
+            RejectOutOfOrder::default(),
+            None,
+            None,
+            None,
+        );
+
+        let buffered_rows = |table_name: &str| -> usize {
+            ingester
+                .data
+                .sequencers
+                .get(&sequencer.id)
+                .and_then(|s| s.namespace(&namespace.name))
+                .and_then(|n| n.table_data(table_name))
+                .and_then(|t| t.partition_data("1970-01-01"))
+                .map(|p| {
+                    p.snapshot()
+                        .unwrap()
+                        .iter()
+                        .map(|s| s.data.num_rows())
+                        .sum()
+                })
+                .unwrap_or_default()
+        };
+
+        // wait for both writes to be buffered
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while buffered_rows("mem") < 1 || buffered_rows("cpu") < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timeout");
+
+        // seeking in between the two writes resumes from the second one
+        let sequence_numbers = ingester
+            .seek_to_timestamp(Time::from_timestamp_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(sequence_numbers, BTreeMap::from([(kafka_partition, 7)]));
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while buffered_rows("cpu") < 4 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timeout");
+        assert_eq!(buffered_rows("mem"), 1);
+    }
+
+    #[tokio::test]
+    async fn operations_read_ahead_of_a_seek_are_discarded() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let kafka_partition = KafkaPartition::new(0);
+        let namespace = catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, kafka_partition)
+            .await
+            .unwrap();
+        let mut sequencer_states = BTreeMap::new();
+        sequencer_states.insert(kafka_partition, sequencer);
+
+        let schema = NamespaceSchema::new(namespace.id, kafka_topic.id, query_pool.id);
+        let stale = DmlWrite::new(
+            "foo",
+            lines_to_batches("mem foo=1 10", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 0),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        );
+        let schema = validate_or_insert_schema(stale.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+        let fresh = DmlWrite::new(
+            "foo",
+            lines_to_batches("cpu bar=2 20", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 7),
+                Time::from_timestamp_millis(1337),
+                None,
+                150,
+            ),
+        );
+        let _schema = validate_or_insert_schema(fresh.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the write buffer of the handler stays empty, the operations are fed to a buffering
+        // task of the test instead
+        let write_buffer_state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::try_from(1).unwrap());
+        let reading = Box::new(MockBufferForReading::new(write_buffer_state, None).unwrap());
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let metrics: Arc<metric::Registry> = Default::default();
+
+        let ingester = IngestHandlerImpl::new(
+            kafka_topic,
+            sequencer_states,
+            Arc::new(catalog),
+            object_store,
+            Arc::new(Executor::new(1)),
+            reading,
+            &metrics,
+            false,
+            RejectOutOfOrder::default(),
+            None,
+            None,
+            None,
+        );
+
+        // the stale write was read before the seek, the fresh one after it
+        let (tx, rx) = mpsc::channel(READ_AHEAD_OPERATIONS);
+        tx.send(ReadOperation {
+            result: Ok(DmlOperation::Write(stale)),
+            watermark: 8,
+            seek_generation: 0,
+        })
+        .await
+        .unwrap();
+        tx.send(ReadOperation {
+            result: Ok(DmlOperation::Write(fresh)),
+            watermark: 8,
+            seek_generation: 1,
+        })
+        .await
+        .unwrap();
+        drop(tx);
+
+        stream_in_sequenced_entries(
+            Arc::clone(&ingester.data),
+            sequencer.id,
+            "whatevs".to_string(),
+            kafka_partition,
+            rx,
+            WriteBufferIngestMetrics::new(&metrics, "whatevs").new_sequencer_metrics(0),
+            Arc::new(AtomicU64::new(1)),
+        )
+        .await;
+
+        let buffered_rows = |table_name: &str| -> usize {
+            ingester
+                .data
+                .sequencers
+                .get(&sequencer.id)
+                .and_then(|s| s.namespace(&namespace.name))
+                .and_then(|n| n.table_data(table_name))
+                .and_then(|t| t.partition_data("1970-01-01"))
+                .map(|p| {
+                    p.snapshot()
+                        .unwrap()
+                        .iter()
+                        .map(|s| s.data.num_rows())
+                        .sum()
+                })
+                .unwrap_or_default()
+        };
+        assert_eq!(buffered_rows("mem"), 0);
+        assert_eq!(buffered_rows("cpu"), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_seek_moves_no_kafka_partition() {
+        let write_buffer_state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::try_from(2).unwrap());
+        write_buffer_state.push_write(DmlWrite::new(
+            "foo",
+            lines_to_batches("mem foo=1 10", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 0),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        ));
+        let mut reading = MockBufferForReading::new(write_buffer_state, None).unwrap();
+
+        // kafka partition 5 is unknown to the write buffer, so looking it up fails after
+        // kafka partition 0 was looked up
+        seek_to_timestamp(
+            &mut reading,
+            "whatevs",
+            [KafkaPartition::new(0), KafkaPartition::new(5)].into_iter(),
+            Time::from_timestamp_millis(100),
+        )
+        .await
+        .unwrap_err();
+
+        // kafka partition 0 still starts from the write before the timestamp
+        let mut streams = reading.streams();
+        let op = streams
+            .get_mut(&0)
+            .unwrap()
+            .stream
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(op.meta().sequence().unwrap().number, 0);
+    }
+
+    #[tokio::test]
+    async fn recover_from_wal_skips_recovered_writes() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog
+            .kafka_topics()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let query_pool = catalog
+            .query_pools()
+            .create_or_get("whatevs")
+            .await
+            .unwrap();
+        let kafka_partition = KafkaPartition::new(0);
+        let namespace = catalog
+            .namespaces()
+            .create("foo", Some("inf"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka_topic, kafka_partition)
+            .await
+            .unwrap();
+        let mut sequencer_states = BTreeMap::new();
+        sequencer_states.insert(kafka_partition, sequencer);
+
+        let schema = NamespaceSchema::new(namespace.id, kafka_topic.id, query_pool.id);
+
+        let write_buffer_state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::try_from(1).unwrap());
+        let w1 = DmlWrite::new(
+            "foo",
+            lines_to_batches("mem foo=1 10", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 0),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        );
+        let schema = validate_or_insert_schema(w1.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+        write_buffer_state.push_write(w1.clone());
+        let w2 = DmlWrite::new(
+            "foo",
+            lines_to_batches("cpu bar=2 20\ncpu bar=3 30", 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, 7),
+                Time::from_timestamp_millis(1337),
+                None,
+                150,
+            ),
+        );
+        let _schema = validate_or_insert_schema(w2.tables(), &schema, &catalog)
+            .await
+            .unwrap()
+            .unwrap();
+        write_buffer_state.push_write(w2);
+        let reading = Box::new(MockBufferForReading::new(write_buffer_state, None).unwrap());
+        let object_store = Arc::new(ObjectStore::new_in_memory());
+        let metrics: Arc<metric::Registry> = Default::default();
+
+        // The first write was buffered before a crash
+        let wal_dir = tempfile::tempdir().unwrap();
+        let wal = Wal::new(wal_dir.path()).await.unwrap();
+        wal.append(sequencer.id, &DmlOperation::Write(w1))
+            .await
+            .unwrap();
+
+        let ingester = IngestHandlerImpl::new(
+            kafka_topic,
+            sequencer_states,
+            Arc::new(catalog),
+            object_store,
+            Arc::new(Executor::new(1)),
+            reading,
+            &metrics,
+            false,
+            RejectOutOfOrder::default(),
+            Some(wal),
+            None,
+            None,
+        );
+
+        let buffered_rows = |table_name: &str| -> usize {
+            ingester
+                .data
+                .sequencers
+                .get(&sequencer.id)
+                .and_then(|s| s.namespace(&namespace.name))
+                .and_then(|n| n.table_data(table_name))
+                .and_then(|t| t.partition_data("1970-01-01"))
+                .map(|p| {
+                    p.snapshot()
+                        .unwrap()
+                        .iter()
+                        .map(|s| s.data.num_rows())
+                        .sum()
+                })
+                .unwrap_or_default()
+        };
+
+        // wait for the second write to be read from the write buffer
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while buffered_rows("cpu") < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timeout");
+
+        // the first write was recovered from the log and not read again
+        assert_eq!(buffered_rows("mem"), 1);
     }
 }
@@ -13,6 +13,8 @@
 )]
 #![allow(dead_code)]
 
+pub mod catalog_update;
+pub mod cleanup;
 pub mod compact;
 pub mod data;
 pub mod handler;
@@ -20,3 +22,4 @@ pub mod persist;
 pub mod query;
 pub mod server;
 pub mod test_util;
+pub mod wal;
@@ -1,13 +1,35 @@
 //! Persist compacted data to parquet files in object storage
 
-use arrow::record_batch::RecordBatch;
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
 use bytes::Bytes;
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use iox_catalog::interface::{NamespaceId, ParquetFile, PartitionId, SequencerId, TableId};
+use metric::{
+    Attributes, DurationHistogram, Metric, U64Counter, U64Histogram, U64HistogramOptions,
+};
 use object_store::{
     path::{ObjectStorePath, Path},
     ObjectStore, ObjectStoreApi,
 };
+use parquet::{
+    arrow::{ArrowReader, ParquetFileArrowReader},
+    file::serialized_reader::{SerializedFileReader, SliceableCursor},
+};
 use parquet_file::metadata::IoxMetadata;
+use query::SortKeyMetrics;
 use snafu::{ResultExt, Snafu};
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -17,35 +39,187 @@ pub enum Error {
         source: parquet_file::storage::Error,
     },
 
+    #[snafu(display("Error reading the encoded parquet file: {}", source))]
+    ReadingEncodedFile { source: std::io::Error },
+
     #[snafu(display("Error writing to object store: {}", source))]
     WritingToObjectStore { source: object_store::Error },
+
+    #[snafu(display("Error reading from object store: {}", source))]
+    ReadingFromObjectStore { source: object_store::Error },
+
+    #[snafu(display("Error decoding a persisted parquet file: {}", source))]
+    DecodingParquet {
+        source: parquet::errors::ParquetError,
+    },
+
+    #[snafu(display("Error decoding the data of a persisted parquet file: {}", source))]
+    DecodingRecordBatches { source: arrow::error::ArrowError },
 }
 
 /// A specialized `Error` for Ingester's persistence errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// The object store directory holding the parquet files of snapshots of the buffered data,
+/// see [`persist_snapshot`]
+pub const SNAPSHOT_PREFIX: &str = "snapshots";
+
+/// Number of rows decoded at once when reading back a persisted parquet file
+const READ_BATCH_SIZE: usize = 8 * 1024;
+
+/// Metrics recorded for every persisted parquet file, labelled by namespace
+#[derive(Debug)]
+pub struct PersistMetrics {
+    /// Wall-clock time taken to encode and upload a file
+    duration: Metric<DurationHistogram>,
+    /// Number of rows persisted
+    input_rows: Metric<U64Counter>,
+    /// Size of the persisted parquet files
+    file_size_bytes: Metric<U64Histogram>,
+    /// In-memory size of the persisted data relative to the parquet file size
+    compression_percent: Metric<U64Histogram>,
+    /// Sort keys computed to persist or compact data, as no valid sort key was stored for
+    /// the partition
+    sort_key: SortKeyMetrics,
+}
+
+impl PersistMetrics {
+    /// Register the persist metrics with the given registry
+    pub fn new(registry: &metric::Registry) -> Self {
+        Self {
+            duration: registry.register_metric(
+                "ingester_persist_duration",
+                "wall-clock time taken to persist a parquet file",
+            ),
+            input_rows: registry
+                .register_metric("ingester_persist_input_rows", "total rows persisted"),
+            file_size_bytes: registry.register_metric_with_options(
+                "ingester_persist_file_size_bytes",
+                "distribution of persisted parquet file sizes",
+                || {
+                    U64HistogramOptions::new([
+                        1024,
+                        16 * 1024,
+                        128 * 1024,
+                        1024 * 1024,
+                        8 * 1024 * 1024,
+                        64 * 1024 * 1024,
+                        512 * 1024 * 1024,
+                        u64::MAX,
+                    ])
+                },
+            ),
+            compression_percent: registry.register_metric_with_options(
+                "ingester_persist_compression_percent",
+                "in-memory size of the persisted data relative to the parquet file size, in percent",
+                || U64HistogramOptions::new([100, 200, 400, 800, 1600, 3200, 6400, u64::MAX]),
+            ),
+            sort_key: SortKeyMetrics::new(registry, &[("component", "ingester")]),
+        }
+    }
+
+    /// Return the metrics of the sort keys computed to persist or compact data
+    pub(crate) fn sort_key(&self) -> &SortKeyMetrics {
+        &self.sort_key
+    }
+
+    fn record(
+        &self,
+        namespace: &str,
+        start: Instant,
+        input_rows: u64,
+        input_bytes: u64,
+        file_size_bytes: u64,
+    ) {
+        let attributes = Attributes::from([("namespace", namespace.to_string().into())]);
+
+        self.duration
+            .recorder(attributes.clone())
+            .record(Instant::now().duration_since(start));
+        self.input_rows.recorder(attributes.clone()).inc(input_rows);
+        self.file_size_bytes
+            .recorder(attributes.clone())
+            .record(file_size_bytes);
+        if file_size_bytes > 0 {
+            self.compression_percent
+                .recorder(attributes)
+                .record(input_bytes * 100 / file_size_bytes);
+        }
+    }
+}
+
+/// Running totals of the rows and in-memory bytes of the record batches being persisted
+#[derive(Debug, Default)]
+struct PersistInput {
+    rows: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl PersistInput {
+    fn add(&self, batch: &RecordBatch) {
+        let bytes: usize = batch
+            .columns()
+            .iter()
+            .map(|array| array.get_array_memory_size())
+            .sum();
+
+        self.rows
+            .fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn rows(&self) -> u64 {
+        self.rows.load(Ordering::Relaxed)
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Adds every record batch of the wrapped stream to a [`PersistInput`] as it passes through
+struct CountingStream {
+    inner: SendableRecordBatchStream,
+    input: Arc<PersistInput>,
+}
+
+impl Stream for CountingStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(batch))) = &poll {
+            self.input.add(batch);
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for CountingStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
 /// Write the given data to the given location in the given object storage
 pub async fn persist(
     metadata: &IoxMetadata,
     record_batches: Vec<RecordBatch>,
     object_store: &ObjectStore,
+    metrics: &PersistMetrics,
 ) -> Result<()> {
-    if record_batches.is_empty() {
-        return Ok(());
+    let start = Instant::now();
+    let input = PersistInput::default();
+    for batch in &record_batches {
+        input.add(batch);
     }
-    let schema = record_batches
-        .first()
-        .expect("record_batches.is_empty was just checked")
-        .schema();
-
-    let data = parquet_file::storage::Storage::parquet_bytes(record_batches, schema, metadata)
-        .await
-        .context(ConvertingToBytesSnafu)?;
 
+    let data = parquet_bytes(metadata, record_batches).await?;
     if data.is_empty() {
         return Ok(());
     }
 
+    let file_size_bytes = data.len() as u64;
     let bytes = Bytes::from(data);
 
     let path = parquet_file_object_store_path(metadata, object_store);
@@ -55,21 +229,192 @@ pub async fn persist(
         .await
         .context(WritingToObjectStoreSnafu)?;
 
+    metrics.record(
+        &metadata.namespace_name,
+        start,
+        input.rows(),
+        input.bytes(),
+        file_size_bytes,
+    );
+
     Ok(())
 }
 
-fn parquet_file_object_store_path(metadata: &IoxMetadata, object_store: &ObjectStore) -> Path {
-    let mut path = object_store.new_path();
+/// Write the given stream of data to the given location in the given object storage,
+/// encoding each record batch to a temporary file as it arrives rather than collecting the
+/// stream or the encoded file in memory. Return the size of the file written, or `None` if
+/// the stream had no data.
+///
+/// Object store uploads take the whole object, so the encoded file is only read into memory
+/// once it is complete, to be uploaded.
+pub async fn persist_stream(
+    metadata: &IoxMetadata,
+    stream: SendableRecordBatchStream,
+    object_store: &ObjectStore,
+    metrics: &PersistMetrics,
+) -> Result<Option<u64>> {
+    let start = Instant::now();
+    let input = Arc::new(PersistInput::default());
+    let stream = Box::pin(CountingStream {
+        inner: stream,
+        input: Arc::clone(&input),
+    });
+
+    let (file, file_size_bytes) =
+        match parquet_file::storage::Storage::parquet_stream_to_file(stream, metadata)
+            .await
+            .context(ConvertingToBytesSnafu)?
+        {
+            Some(v) => v,
+            // The stream had no data
+            None => return Ok(None),
+        };
+
+    let mut data = Vec::with_capacity(file_size_bytes as usize);
+    tokio::fs::File::from_std(file)
+        .read_to_end(&mut data)
+        .await
+        .context(ReadingEncodedFileSnafu)?;
+    let bytes = Bytes::from(data);
+
+    let path = parquet_file_object_store_path(metadata, object_store);
 
+    object_store
+        .put(&path, bytes)
+        .await
+        .context(WritingToObjectStoreSnafu)?;
+
+    metrics.record(
+        &metadata.namespace_name,
+        start,
+        input.rows(),
+        input.bytes(),
+        file_size_bytes,
+    );
+
+    Ok(Some(file_size_bytes))
+}
+
+/// Write the given data as a parquet file under `snapshot_id` in the [`SNAPSHOT_PREFIX`]
+/// directory of the given object storage, rather than at the location of persisted files,
+/// returning the path of the file. Return `None` if there is no data to write.
+///
+/// Persist metrics are not recorded, as the data is not persisted.
+pub async fn persist_snapshot(
+    snapshot_id: Uuid,
+    metadata: &IoxMetadata,
+    record_batches: Vec<RecordBatch>,
+    object_store: &ObjectStore,
+) -> Result<Option<Path>> {
+    let data = parquet_bytes(metadata, record_batches).await?;
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    let mut path = object_store.new_path();
     path.push_all_dirs(&[
+        SNAPSHOT_PREFIX,
+        snapshot_id.to_string().as_str(),
         metadata.namespace_id.to_string().as_str(),
         metadata.table_id.to_string().as_str(),
         metadata.sequencer_id.to_string().as_str(),
         metadata.partition_id.to_string().as_str(),
     ]);
-
     path.set_file_name(format!("{}.parquet", metadata.object_store_id));
 
+    object_store
+        .put(&path, Bytes::from(data))
+        .await
+        .context(WritingToObjectStoreSnafu)?;
+
+    Ok(Some(path))
+}
+
+/// Read back the parquet file of `file`, persisted for the namespace `namespace_id`, from the
+/// given object storage as a single record batch
+pub async fn read_persisted(
+    namespace_id: NamespaceId,
+    file: &ParquetFile,
+    object_store: &ObjectStore,
+) -> Result<RecordBatch> {
+    let path = object_store_path(
+        object_store,
+        namespace_id,
+        file.table_id,
+        file.sequencer_id,
+        file.partition_id,
+        file.object_store_id,
+    );
+    let data = object_store
+        .get(&path)
+        .await
+        .context(ReadingFromObjectStoreSnafu)?
+        .bytes()
+        .await
+        .context(ReadingFromObjectStoreSnafu)?;
+
+    // Decoding is CPU-bound, so it does not run on the async executor
+    tokio::task::spawn_blocking(move || {
+        let reader =
+            SerializedFileReader::new(SliceableCursor::new(data)).context(DecodingParquetSnafu)?;
+        let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(reader));
+        let schema = Arc::new(arrow_reader.get_schema().context(DecodingParquetSnafu)?);
+        let batches = arrow_reader
+            .get_record_reader(READ_BATCH_SIZE)
+            .context(DecodingParquetSnafu)?
+            .collect::<ArrowResult<Vec<_>>>()
+            .context(DecodingRecordBatchesSnafu)?;
+        RecordBatch::concat(&schema, &batches).context(DecodingRecordBatchesSnafu)
+    })
+    .await
+    .expect("decoding a parquet file panicked")
+}
+
+/// Encode the given data as parquet, returning no bytes if there is no data
+async fn parquet_bytes(
+    metadata: &IoxMetadata,
+    record_batches: Vec<RecordBatch>,
+) -> Result<Vec<u8>> {
+    let schema = match record_batches.first() {
+        Some(batch) => batch.schema(),
+        None => return Ok(vec![]),
+    };
+
+    parquet_file::storage::Storage::parquet_bytes(record_batches, schema, metadata)
+        .await
+        .context(ConvertingToBytesSnafu)
+}
+
+fn parquet_file_object_store_path(metadata: &IoxMetadata, object_store: &ObjectStore) -> Path {
+    object_store_path(
+        object_store,
+        metadata.namespace_id,
+        metadata.table_id,
+        metadata.sequencer_id,
+        metadata.partition_id,
+        metadata.object_store_id,
+    )
+}
+
+fn object_store_path(
+    object_store: &ObjectStore,
+    namespace_id: NamespaceId,
+    table_id: TableId,
+    sequencer_id: SequencerId,
+    partition_id: PartitionId,
+    object_store_id: Uuid,
+) -> Path {
+    let mut path = object_store.new_path();
+
+    path.push_all_dirs(&[
+        namespace_id.to_string().as_str(),
+        table_id.to_string().as_str(),
+        sequencer_id.to_string().as_str(),
+        partition_id.to_string().as_str(),
+    ]);
+
+    path.set_file_name(format!("{}.parquet", object_store_id));
+
     path
 }
 
@@ -78,7 +423,11 @@ mod tests {
     use super::*;
     use futures::{stream, StreamExt, TryStreamExt};
     use iox_catalog::interface::{NamespaceId, PartitionId, SequenceNumber, SequencerId, TableId};
-    use query::test::{raw_data, TestChunk};
+    use query::{
+        test::{raw_data, TestChunk},
+        QueryChunk,
+    };
+    use schema::selection::Selection;
     use std::sync::Arc;
     use time::Time;
     use uuid::Uuid;
@@ -87,6 +436,10 @@ mod tests {
         Time::from_timestamp(0, 0)
     }
 
+    fn persist_metrics() -> PersistMetrics {
+        PersistMetrics::new(&metric::Registry::new())
+    }
+
     fn object_store() -> Arc<ObjectStore> {
         Arc::new(ObjectStore::new_in_memory())
     }
@@ -120,7 +473,9 @@ mod tests {
         };
         let object_store = object_store();
 
-        persist(&metadata, vec![], &object_store).await.unwrap();
+        persist(&metadata, vec![], &object_store, &persist_metrics())
+            .await
+            .unwrap();
 
         assert!(list_all(&object_store).await.unwrap().is_empty());
     }
@@ -156,12 +511,152 @@ mod tests {
 
         let object_store = object_store();
 
-        persist(&metadata, batches, &object_store).await.unwrap();
+        persist(&metadata, batches, &object_store, &persist_metrics())
+            .await
+            .unwrap();
 
         let obj_store_paths = list_all(&object_store).await.unwrap();
         assert_eq!(obj_store_paths.len(), 1);
     }
 
+    #[tokio::test]
+    async fn persist_records_metrics() {
+        let metadata = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: now(),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "mydata".into(),
+            sequencer_id: SequencerId::new(2),
+            table_id: TableId::new(3),
+            table_name: "temperature".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "somehour".into(),
+            time_of_first_write: now(),
+            time_of_last_write: now(),
+            min_sequence_number: SequenceNumber::new(5),
+            max_sequence_number: SequenceNumber::new(6),
+        };
+
+        let chunk1 = Arc::new(
+            TestChunk::new("t")
+                .with_id(1)
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_three_rows_of_data(),
+        );
+        let batches = raw_data(&[chunk1]).await;
+
+        let object_store = object_store();
+        let registry = metric::Registry::new();
+        let metrics = PersistMetrics::new(&registry);
+
+        persist(&metadata, batches, &object_store, &metrics)
+            .await
+            .unwrap();
+
+        let obj_store_paths = list_all(&object_store).await.unwrap();
+        assert_eq!(obj_store_paths.len(), 1);
+        let file_size_bytes = object_store
+            .get(&obj_store_paths[0])
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap()
+            .len() as u64;
+
+        let attributes = Attributes::from(&[("namespace", "mydata")]);
+
+        let duration = registry
+            .get_instrument::<Metric<DurationHistogram>>("ingester_persist_duration")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(duration.sample_count(), 1);
+        assert!(duration.total > std::time::Duration::from_nanos(0));
+
+        let input_rows = registry
+            .get_instrument::<Metric<U64Counter>>("ingester_persist_input_rows")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(input_rows, 3);
+
+        let file_size = registry
+            .get_instrument::<Metric<U64Histogram>>("ingester_persist_file_size_bytes")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(file_size.sample_count(), 1);
+        assert_eq!(file_size.total, file_size_bytes);
+
+        let compression = registry
+            .get_instrument::<Metric<U64Histogram>>("ingester_persist_compression_percent")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(compression.sample_count(), 1);
+        assert!(compression.total > 0);
+    }
+
+    #[tokio::test]
+    async fn stream_with_batches_writes_to_object_store() {
+        let metadata = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: now(),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "mydata".into(),
+            sequencer_id: SequencerId::new(2),
+            table_id: TableId::new(3),
+            table_name: "temperature".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "somehour".into(),
+            time_of_first_write: now(),
+            time_of_last_write: now(),
+            min_sequence_number: SequenceNumber::new(5),
+            max_sequence_number: SequenceNumber::new(6),
+        };
+
+        let chunk1 = Arc::new(
+            TestChunk::new("t")
+                .with_id(1)
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_three_rows_of_data(),
+        );
+        let stream = chunk1
+            .read_filter(&Default::default(), Selection::All)
+            .unwrap();
+
+        let object_store = object_store();
+
+        let file_size_bytes = persist_stream(&metadata, stream, &object_store, &persist_metrics())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let obj_store_paths = list_all(&object_store).await.unwrap();
+        assert_eq!(obj_store_paths.len(), 1);
+        let data = object_store
+            .get(&obj_store_paths[0])
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(data.len() as u64, file_size_bytes);
+        assert_eq!(
+            obj_store_paths[0].to_raw(),
+            parquet_file_object_store_path(&metadata, &object_store).to_raw()
+        );
+    }
+
     #[test]
     fn parquet_file_path_in_object_storage() {
         let object_store = object_store();
@@ -1,8 +1,12 @@
 //! Module to handle query on Ingester's data
 
-use std::sync::Arc;
+use std::{cmp::Ordering, ops::Range, sync::Arc};
 
-use arrow::record_batch::RecordBatch;
+use arrow::{
+    array::{build_compare, Array, ArrayRef, UInt32Array},
+    compute::{lexsort_to_indices, take, SortColumn},
+    record_batch::RecordBatch,
+};
 use arrow_util::util::merge_record_batches;
 use data_types::{
     chunk_metadata::{ChunkAddr, ChunkId, ChunkOrder},
@@ -14,14 +18,17 @@ use datafusion::physical_plan::{
     metrics::{ExecutionPlanMetricsSet, MemTrackingMetrics},
     SendableRecordBatchStream,
 };
-use iox_catalog::interface::{SequenceNumber, Tombstone};
+use iox_catalog::interface::{SequenceNumber, SequencerId, Tombstone};
 use predicate::{
     delete_predicate::parse_delete_predicate,
     predicate::{Predicate, PredicateMatch},
 };
-use query::{exec::stringset::StringSet, QueryChunk, QueryChunkMeta};
-use schema::{merge::merge_record_batch_schemas, selection::Selection, sort::SortKey, Schema};
-use snafu::{ResultExt, Snafu};
+use query::{exec::stringset::StringSet, QueryChunk, QueryChunkMeta, SequenceNumberRange};
+use schema::{
+    merge::merge_record_batch_schemas, selection::Selection, sort::SortKey, InfluxColumnType,
+    Schema,
+};
+use snafu::{OptionExt, ResultExt, Snafu};
 
 use crate::data::{QueryableBatch, SnapshotBatch};
 
@@ -40,6 +47,24 @@ pub enum Error {
     // PaddNulls { source: arrow::error::ArrowError },
     #[snafu(display("Internal error while concat record batches {}", source))]
     ConcatBatches { source: arrow::error::ArrowError },
+
+    #[snafu(display("Error selecting columns: {}", source))]
+    SelectColumns { source: schema::Error },
+
+    #[snafu(display("Internal error while projecting a record batch {}", source))]
+    ProjectBatch { source: arrow::error::ArrowError },
+
+    #[snafu(display("Internal error converting the schema of a record batch: {}", source))]
+    BatchSchema { source: schema::Error },
+
+    #[snafu(display("Record batch has no time column"))]
+    NoTimeColumn,
+
+    #[snafu(display("Internal error while sorting the rows of a record batch {}", source))]
+    SortRows { source: arrow::error::ArrowError },
+
+    #[snafu(display("Internal error while taking rows of a record batch {}", source))]
+    TakeRows { source: arrow::error::ArrowError },
 }
 
 /// A specialized `Error` for Ingester's Query errors
@@ -67,22 +92,55 @@ impl QueryableBatch {
             deletes,
             delete_predicates,
             table_name: table_name.to_string(),
+            chunk_id: ChunkId::new_monotonic(),
+            sequencer_id: None,
+            partition_key: None,
         }
     }
 
+    /// Set the sequencer that assigned the sequence numbers of the data and
+    /// the partition of the data, which are required to report the sequence
+    /// numbers as [`QueryChunk::sequence_numbers`]
+    pub fn with_origin(mut self, sequencer_id: SequencerId, partition_key: &str) -> Self {
+        self.sequencer_id = Some(sequencer_id);
+        self.partition_key = Some(Arc::from(partition_key));
+        self
+    }
+
+    /// Return one QueryableBatch per snapshot of this batch, each carrying all
+    /// the tombstones of this batch
+    pub fn split_snapshots(&self) -> Vec<Arc<Self>> {
+        self.data
+            .iter()
+            .map(|snapshot| {
+                Arc::new(Self {
+                    data: vec![snapshot.clone()],
+                    deletes: self.deletes.clone(),
+                    delete_predicates: self.delete_predicates.clone(),
+                    table_name: self.table_name.clone(),
+                    chunk_id: ChunkId::new_monotonic(),
+                    sequencer_id: self.sequencer_id,
+                    partition_key: self.partition_key.clone(),
+                })
+            })
+            .collect()
+    }
+
     /// return min and max of all the snapshots
     pub fn min_max_sequence_numbers(&self) -> (SequenceNumber, SequenceNumber) {
         let min = self
             .data
-            .first()
-            .expect("The Queryable Batch should not empty")
-            .min_sequencer_number;
+            .iter()
+            .map(|s| s.min_sequencer_number)
+            .min()
+            .expect("The Queryable Batch should not empty");
 
         let max = self
             .data
-            .first()
-            .expect("The Queryable Batch should not empty")
-            .max_sequencer_number;
+            .iter()
+            .map(|s| s.max_sequencer_number)
+            .max()
+            .expect("The Queryable Batch should not empty");
 
         assert!(min <= max);
 
@@ -108,16 +166,28 @@ impl QueryChunkMeta for QueryableBatch {
     fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
         self.delete_predicates.as_ref()
     }
+
+    fn size_bytes(&self) -> usize {
+        let data: usize = self.data.iter().map(|snapshot| snapshot.size_bytes()).sum();
+        let deletes: usize = self
+            .deletes
+            .iter()
+            .map(|tombstone| {
+                std::mem::size_of::<Tombstone>() + tombstone.serialized_predicate.len()
+            })
+            .sum();
+        let delete_predicates: usize = self.delete_predicates.iter().map(|pred| pred.size()).sum();
+
+        std::mem::size_of::<Self>() + self.table_name.len() + data + deletes + delete_predicates
+    }
 }
 
 impl QueryChunk for QueryableBatch {
     type Error = Error;
 
-    // This function should not be used in QueryBatch context
+    /// Returns the unique ID allocated to this chunk when it was created
     fn id(&self) -> ChunkId {
-        // always return id 0 for debugging mode
-        // todo: need to see if the same id for all chunks will cause any panics
-        ChunkId::new_test(0)
+        self.chunk_id
     }
 
     // This function should not be used in PersistingBatch context
@@ -125,6 +195,10 @@ impl QueryChunk for QueryableBatch {
         unimplemented!()
     }
 
+    fn partition_key(&self) -> Option<Arc<str>> {
+        self.partition_key.clone()
+    }
+
     /// Returns the name of the table stored in this chunk
     fn table_name(&self) -> &str {
         &self.table_name
@@ -191,8 +265,13 @@ impl QueryChunk for QueryableBatch {
     fn read_filter(
         &self,
         _predicate: &Predicate, // no needs because all data will be read for compaction
-        _selection: Selection<'_>, // no needs because all columns will be read and compact
+        selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, Self::Error> {
+        let schema = self
+            .schema()
+            .select(selection)
+            .context(SelectColumnsSnafu)?;
+
         // Get all record batches from their snapshots
         let batches: Vec<_> = self.data.iter().map(|s| Arc::clone(&s.data)).collect();
 
@@ -204,14 +283,14 @@ impl QueryChunk for QueryableBatch {
 
         let mut stream_batches = vec![];
         if let Some(batch) = batch {
+            let batch = project_record_batch(&batch, &schema).context(ProjectBatchSnafu)?;
             stream_batches.push(Arc::new(batch));
         }
 
         // Return sream of data
         let dummy_metrics = ExecutionPlanMetricsSet::new();
         let mem_metrics = MemTrackingMetrics::new(&dummy_metrics, 0);
-        let stream =
-            SizedRecordBatchStream::new(self.schema().as_arrow(), stream_batches, mem_metrics);
+        let stream = SizedRecordBatchStream::new(schema.as_arrow(), stream_batches, mem_metrics);
         Ok(Box::pin(stream))
     }
 
@@ -230,15 +309,223 @@ impl QueryChunk for QueryableBatch {
         "PersistingBatch"
     }
 
-    // This function should not be used in PersistingBatch context
+    /// Returns the order of this chunk, derived from its first sequence
+    /// number, so that data of later snapshots and of any buffered data over
+    /// persisted files wins when deduplicating (see
+    /// [`ChunkOrder::new_buffered`])
     fn order(&self) -> ChunkOrder {
-        unimplemented!()
+        let (min_seq, _) = self.min_max_sequence_numbers();
+        ChunkOrder::new_buffered(min_seq.get())
+    }
+
+    /// Returns `None` if the sequencer of the data is not known, as sequence
+    /// numbers are only comparable within a sequencer
+    fn sequence_numbers(&self) -> Option<SequenceNumberRange> {
+        let sequencer_id = self.sequencer_id?;
+        let (min_seq, max_seq) = self.min_max_sequence_numbers();
+        Some(SequenceNumberRange::new(
+            sequencer_id.get() as u32,
+            min_seq.get()..=max_seq.get(),
+        ))
+    }
+
+    /// Excludes all snapshots whose sequence numbers are all within
+    /// `exclude`
+    fn exclude_sequence_numbers(
+        self: &Arc<Self>,
+        exclude: &SequenceNumberRange,
+    ) -> Option<Arc<Self>> {
+        let sequencer_id = match self.sequencer_id {
+            Some(sequencer_id) => sequencer_id.get() as u32,
+            None => return Some(Arc::clone(self)),
+        };
+        let data: Vec<_> = self
+            .data
+            .iter()
+            .filter(|s| {
+                !(exclude.contains(sequencer_id, s.min_sequencer_number.get())
+                    && exclude.contains(sequencer_id, s.max_sequencer_number.get()))
+            })
+            .cloned()
+            .collect();
+
+        match data.len() {
+            0 => None,
+            n if n == self.data.len() => Some(Arc::clone(self)),
+            _ => Some(Arc::new(Self {
+                data,
+                deletes: self.deletes.clone(),
+                delete_predicates: self.delete_predicates.clone(),
+                table_name: self.table_name.clone(),
+                chunk_id: ChunkId::new_monotonic(),
+                sequencer_id: self.sequencer_id,
+                partition_key: self.partition_key.clone(),
+            })),
+        }
+    }
+}
+
+/// Return the columns of `batch` named by the fields of `schema`, in the order of `schema`.
+/// `schema` must be a selection of the schema of `batch`.
+pub fn project_record_batch(
+    batch: &RecordBatch,
+    schema: &Schema,
+) -> Result<RecordBatch, arrow::error::ArrowError> {
+    let columns = schema
+        .iter()
+        .map(|(_, field)| {
+            let idx = batch.schema().index_of(field.name())?;
+            Ok(Arc::clone(batch.column(idx)))
+        })
+        .collect::<Result<Vec<_>, arrow::error::ArrowError>>()?;
+
+    RecordBatch::try_new(schema.as_arrow(), columns)
+}
+
+/// Return the latest row of every series of `batch`, i.e. the row with the greatest timestamp
+/// for each distinct combination of tag values. Of rows with the same timestamp, the row
+/// appearing last in `batch` wins, as buffered data is in the order it was written.
+///
+/// The whole row is returned, so the columns of `batch` should not be projected before: a
+/// projection applied afterwards returns the selected columns of the latest rows. The rows are
+/// returned ordered by their tag values.
+pub fn latest_per_series(batch: &RecordBatch) -> Result<RecordBatch> {
+    let (_, order, groups) = group_rows(batch, false)?;
+    let rows = UInt32Array::from_iter_values(groups.iter().map(|rows| order[rows.end - 1]));
+
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|c| take(c.as_ref(), &rows, None))
+        .collect::<Result<Vec<_>, _>>()
+        .context(TakeRowsSnafu)?;
+    RecordBatch::try_new(batch.schema(), columns).context(TakeRowsSnafu)
+}
+
+/// Return the rows of `batch` with duplicate primary keys, i.e. the same tag values and
+/// timestamp, merged into one row holding the latest value of every field. As buffered data is
+/// in the order it was written, the row appearing last in `batch` wins, and a field is only null
+/// if it is null in all rows with that primary key. The rows are returned ordered by their
+/// primary key.
+pub fn deduplicate(batch: &RecordBatch) -> Result<RecordBatch> {
+    let (schema, order, groups) = group_rows(batch, true)?;
+
+    let fields: Vec<_> = schema
+        .iter()
+        .enumerate()
+        .filter(|(_, (influx_type, _))| {
+            !matches!(
+                influx_type,
+                Some(InfluxColumnType::Tag | InfluxColumnType::Timestamp)
+            )
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+
+    // The rows to take the tags and time of each merged row from, and the rows to take the
+    // values of each field from
+    let mut key_rows = vec![];
+    let mut field_rows = vec![Vec::<Option<u32>>::new(); fields.len()];
+    for rows in groups {
+        let rows = &order[rows];
+
+        let mut latest = None;
+        for (&idx, field_rows) in fields.iter().zip(&mut field_rows) {
+            let column = batch.column(idx);
+            let pos = rows.iter().rposition(|&row| column.is_valid(row as usize));
+            latest = latest.max(pos);
+            field_rows.push(pos.map(|pos| rows[pos]));
+        }
+        key_rows.push(rows[latest.unwrap_or(rows.len() - 1)]);
+    }
+
+    let key_rows = UInt32Array::from(key_rows);
+    let mut field_rows = field_rows.into_iter().map(UInt32Array::from);
+    let columns = schema
+        .iter()
+        .enumerate()
+        .map(|(idx, (influx_type, _))| match influx_type {
+            Some(InfluxColumnType::Tag | InfluxColumnType::Timestamp) => {
+                take(batch.column(idx).as_ref(), &key_rows, None)
+            }
+            _ => take(
+                batch.column(idx).as_ref(),
+                &field_rows.next().expect("rows for every field"),
+                None,
+            ),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .context(TakeRowsSnafu)?;
+    RecordBatch::try_new(batch.schema(), columns).context(TakeRowsSnafu)
+}
+
+/// Order the rows of `batch` by series, then by time, then by their position in `batch`, and
+/// return the schema of `batch`, that order and the ranges of it holding rows with the same
+/// tag values, and also the same timestamp if `by_time` is set.
+fn group_rows(batch: &RecordBatch, by_time: bool) -> Result<(Schema, Vec<u32>, Vec<Range<usize>>)> {
+    let schema = Schema::try_from(batch.schema()).context(BatchSchemaSnafu)?;
+
+    let mut tags = vec![];
+    let mut time = None;
+    for (idx, (influx_type, _)) in schema.iter().enumerate() {
+        match influx_type {
+            Some(InfluxColumnType::Tag) => tags.push(batch.column(idx)),
+            Some(InfluxColumnType::Timestamp) => time = Some(batch.column(idx)),
+            _ => {}
+        }
+    }
+    let time = time.context(NoTimeColumnSnafu)?;
+
+    let position: ArrayRef = Arc::new(UInt32Array::from_iter_values(0..batch.num_rows() as u32));
+    let sort_columns: Vec<_> = tags
+        .iter()
+        .copied()
+        .chain([time, &position])
+        .map(|values| SortColumn {
+            values: Arc::clone(values),
+            options: None,
+        })
+        .collect();
+    let order = lexsort_to_indices(&sort_columns, None).context(SortRowsSnafu)?;
+    let order = order.values().to_vec();
+
+    // The columns whose values identify the rows of a group
+    let mut keys = tags;
+    if by_time {
+        keys.push(time);
+    }
+    let comparators = keys
+        .iter()
+        .map(|column| build_compare(column.as_ref(), column.as_ref()))
+        .collect::<Result<Vec<_>, _>>()
+        .context(SortRowsSnafu)?;
+    let same_key = |a: usize, b: usize| {
+        keys.iter().zip(&comparators).all(|(column, cmp)| {
+            match (column.is_valid(a), column.is_valid(b)) {
+                (true, true) => cmp(a, b) == Ordering::Equal,
+                (a_valid, b_valid) => a_valid == b_valid,
+            }
+        })
+    };
+
+    let mut groups = vec![];
+    let mut start = 0;
+    while start < order.len() {
+        let end = start
+            + order[start..]
+                .iter()
+                .take_while(|&&row| same_key(order[start] as usize, row as usize))
+                .count();
+        groups.push(start..end);
+        start = end;
     }
+
+    Ok((schema, order, groups))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::test_util::create_tombstone;
+    use crate::test_util::{create_tombstone, make_snapshot_batch};
 
     use super::*;
 
@@ -249,6 +536,7 @@ mod tests {
         },
         datatypes::{DataType, Int32Type, TimeUnit},
     };
+    use arrow_util::assert_batches_eq;
     use data_types::{
         delete_predicate::{DeleteExpr, Op, Scalar},
         timestamp::TimestampRange,
@@ -330,6 +618,120 @@ mod tests {
         assert_eq!(expected, predicates);
     }
 
+    #[tokio::test]
+    async fn test_size_bytes_grows_with_data() {
+        let batches = create_batches();
+        let snapshot = |batch: &Arc<RecordBatch>, seq| {
+            make_snapshot_batch(
+                Arc::clone(batch),
+                SequenceNumber::new(seq),
+                SequenceNumber::new(seq),
+            )
+        };
+
+        let empty = QueryableBatch::new("test_table", vec![], vec![]);
+        let one = QueryableBatch::new("test_table", vec![snapshot(&batches[0], 1)], vec![]);
+        let two = QueryableBatch::new(
+            "test_table",
+            vec![snapshot(&batches[0], 1), snapshot(&batches[1], 2)],
+            vec![],
+        );
+
+        assert!(empty.size_bytes() < one.size_bytes());
+        assert!(one.size_bytes() < two.size_bytes());
+
+        // tombstones are accounted for as well
+        let tombstones = vec![create_tombstone(1, 1, 1, 1, 100, 200, "temp=10")];
+        let with_tombstone =
+            QueryableBatch::new("test_table", vec![snapshot(&batches[0], 1)], tombstones);
+        assert!(one.size_bytes() < with_tombstone.size_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_exclude_sequence_numbers() {
+        let batches = create_batches();
+        let snapshot = |batch: &Arc<RecordBatch>, min, max| {
+            make_snapshot_batch(
+                Arc::clone(batch),
+                SequenceNumber::new(min),
+                SequenceNumber::new(max),
+            )
+        };
+
+        let range = |sequencer_id, range| SequenceNumberRange::new(sequencer_id, range);
+
+        let snapshots = vec![snapshot(&batches[0], 1, 2), snapshot(&batches[1], 3, 5)];
+
+        // Without a sequencer, sequence numbers are unknown
+        let unknown = Arc::new(QueryableBatch::new("test_table", snapshots.clone(), vec![]));
+        assert_eq!(unknown.sequence_numbers(), None);
+        assert_eq!(unknown.partition_key(), None);
+        let same = unknown.exclude_sequence_numbers(&range(1, 1..=5)).unwrap();
+        assert!(Arc::ptr_eq(&unknown, &same));
+
+        let batch = Arc::new(
+            QueryableBatch::new("test_table", snapshots, vec![])
+                .with_origin(SequencerId::new(1), "1970-01-01"),
+        );
+        assert_eq!(batch.sequence_numbers(), Some(range(1, 1..=5)));
+        assert_eq!(batch.partition_key().as_deref(), Some("1970-01-01"));
+
+        // Nothing to exclude
+        let same = batch.exclude_sequence_numbers(&range(1, 6..=10)).unwrap();
+        assert!(Arc::ptr_eq(&batch, &same));
+
+        // Sequence numbers of another sequencer are not excluded
+        let same = batch.exclude_sequence_numbers(&range(2, 1..=5)).unwrap();
+        assert!(Arc::ptr_eq(&batch, &same));
+
+        // Snapshots only partially within the range are kept
+        let same = batch.exclude_sequence_numbers(&range(1, 2..=4)).unwrap();
+        assert!(Arc::ptr_eq(&batch, &same));
+
+        // The first snapshot is excluded
+        let excluded = batch.exclude_sequence_numbers(&range(1, 0..=2)).unwrap();
+        assert_eq!(excluded.data, vec![snapshot(&batches[1], 3, 5)]);
+        assert_eq!(excluded.sequence_numbers(), Some(range(1, 3..=5)));
+
+        // All snapshots are excluded
+        assert!(batch.exclude_sequence_numbers(&range(1, 1..=5)).is_none());
+    }
+
+    #[test]
+    fn test_buffered_chunk_order() {
+        let batches = create_batches();
+        let buffered = |min, max| {
+            QueryableBatch::new(
+                "test_table",
+                vec![make_snapshot_batch(
+                    Arc::clone(&batches[0]),
+                    SequenceNumber::new(min),
+                    SequenceNumber::new(max),
+                )],
+                vec![],
+            )
+        };
+        let buffered_early = buffered(21, 25);
+        let buffered_late = buffered(26, 30);
+
+        // later writes always sort after earlier ones
+        assert!(buffered_early.order() < buffered_late.order());
+
+        // including writes with sequence numbers beyond the range of a u32
+        let large = i64::from(u32::MAX);
+        assert!(buffered(large, large).order() < buffered(large + 1, large + 1).order());
+
+        // buffered data sorts as newest
+        assert!(ChunkOrder::MIN_BUFFERED <= buffered(0, 1).order());
+
+        // IDs are unique
+        assert_ne!(buffered_early.id(), buffered_late.id());
+
+        // including those of batches sharing sequence numbers, such as the
+        // batches of another partition
+        assert_ne!(buffered_early.id(), buffered(21, 25).id());
+    }
+
     // ----------------------------------------------------------------------------------------------
     // Data for testing
 
@@ -399,4 +801,125 @@ mod tests {
 
         vec![Arc::new(batch1), Arc::new(batch2)]
     }
+
+    #[tokio::test]
+    async fn test_read_filter_selection() {
+        let batches = create_batches();
+        let data = batches
+            .into_iter()
+            .enumerate()
+            .map(|(i, batch)| SnapshotBatch {
+                min_sequencer_number: SequenceNumber::new(i as i64),
+                max_sequencer_number: SequenceNumber::new(i as i64),
+                data: batch,
+            })
+            .collect();
+        let batch = QueryableBatch::new("test_table", data, vec![]);
+
+        let stream = batch
+            .read_filter(&Predicate::default(), Selection::Some(&["time", "int64"]))
+            .unwrap();
+        let schema = stream.schema();
+        let batches = datafusion::physical_plan::common::collect(stream)
+            .await
+            .unwrap();
+
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["time", "int64"]);
+        for batch in &batches {
+            assert_eq!(batch.schema(), schema);
+        }
+    }
+
+    #[test]
+    fn test_latest_per_series() {
+        let (_, batch) = mutable_batch_lp::test_helpers::lp_to_mutable_batch(
+            "cpu,host=a,region=west usage=1.0 30
+            cpu,host=b,region=west usage=2.0 10
+            cpu,host=a,region=west usage=3.0 20
+            cpu,host=a usage=4.0 10
+            cpu,host=b,region=west usage=5.0 40
+            cpu,host=b,region=west usage=6.0 40
+            cpu,host=b,region=west idle=1.0 50
+            cpu,host=b,region=west idle=2.0 35
+            cpu,host=a usage=7.0 5",
+        );
+        let batch = batch.to_arrow(Selection::All).unwrap();
+
+        // The whole latest row of each series is returned, even if it lacks some fields
+        let latest = latest_per_series(&batch).unwrap();
+        let expected = vec![
+            "+------+------+--------+--------------------------------+-------+",
+            "| host | idle | region | time                           | usage |",
+            "+------+------+--------+--------------------------------+-------+",
+            "| a    |      |        | 1970-01-01T00:00:00.000000010Z | 4     |",
+            "| a    |      | west   | 1970-01-01T00:00:00.000000030Z | 1     |",
+            "| b    | 1    | west   | 1970-01-01T00:00:00.000000050Z |       |",
+            "+------+------+--------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &[latest]);
+
+        // A projection afterwards returns the selected columns of these rows
+        let schema = Schema::try_from(batch.schema()).unwrap();
+        let selection = schema
+            .select_by_names(&["host", "region", "time", "usage"])
+            .unwrap();
+        let latest = latest_per_series(&batch).unwrap();
+        let latest = project_record_batch(&latest, &selection).unwrap();
+        let expected = vec![
+            "+------+--------+--------------------------------+-------+",
+            "| host | region | time                           | usage |",
+            "+------+--------+--------------------------------+-------+",
+            "| a    |        | 1970-01-01T00:00:00.000000010Z | 4     |",
+            "| a    | west   | 1970-01-01T00:00:00.000000030Z | 1     |",
+            "| b    | west   | 1970-01-01T00:00:00.000000050Z |       |",
+            "+------+--------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &[latest]);
+
+        // Of rows with the same timestamp, the last one wins
+        let (_, batch) = mutable_batch_lp::test_helpers::lp_to_mutable_batch(
+            "cpu,host=b usage=5.0 40
+            cpu,host=b usage=6.0 40
+            cpu,host=b usage=2.0 10",
+        );
+        let batch = batch.to_arrow(Selection::All).unwrap();
+        let latest = latest_per_series(&batch).unwrap();
+        let expected = vec![
+            "+------+--------------------------------+-------+",
+            "| host | time                           | usage |",
+            "+------+--------------------------------+-------+",
+            "| b    | 1970-01-01T00:00:00.000000040Z | 6     |",
+            "+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &[latest]);
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let (_, batch) = mutable_batch_lp::test_helpers::lp_to_mutable_batch(
+            "cpu,host=a usage=1.0,idle=9.0 10
+            cpu,host=b usage=2.0 10
+            cpu,host=a usage=3.0 10
+            cpu,host=a usage=4.0 20
+            cpu,host=b idle=5.0 10
+            cpu usage=6.0 10
+            cpu usage=7.0 10",
+        );
+        let batch = batch.to_arrow(Selection::All).unwrap();
+
+        // The last value of each field written for a primary key wins
+        let deduplicated = deduplicate(&batch).unwrap();
+        let expected = vec![
+            "+------+------+--------------------------------+-------+",
+            "| host | idle | time                           | usage |",
+            "+------+------+--------------------------------+-------+",
+            "|      |      | 1970-01-01T00:00:00.000000010Z | 7     |",
+            "| a    | 9    | 1970-01-01T00:00:00.000000010Z | 3     |",
+            "| a    |      | 1970-01-01T00:00:00.000000020Z | 4     |",
+            "| b    | 5    | 1970-01-01T00:00:00.000000010Z | 2     |",
+            "+------+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &[deduplicated]);
+    }
 }
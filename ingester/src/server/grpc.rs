@@ -1,20 +1,1169 @@
 //! gRPC service implementations for `ingester`.
 
-use crate::handler::IngestHandler;
-use std::sync::Arc;
+use crate::{
+    handler::IngestHandler,
+    query::{latest_per_series, project_record_batch},
+};
+use arrow::{
+    array::{ArrayRef, StringArray, UInt64Array},
+    error::ArrowError,
+    ipc::writer::IpcWriteOptions,
+    record_batch::RecordBatch,
+};
+use arrow_flight::{
+    flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
+    utils::{flight_data_from_arrow_batch, flight_data_from_arrow_schema},
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+};
+use arrow_util::util::merge_record_batches;
+use data_types::timestamp::{TimestampRange, MAX_NANO_TIME, MIN_NANO_TIME};
+use futures::{Stream, StreamExt};
+use iox_catalog::interface::SequencerId;
+use schema::{merge::merge_record_batch_schemas, selection::Selection, InfluxColumnType};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, pin::Pin, sync::Arc};
+use time::Time;
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+
+/// The name of the Flight action returning [`PartitionInfo`] for a partition.
+///
+/// [`PartitionInfo`]: crate::data::PartitionInfo
+pub const PARTITION_INFO_ACTION: &str = "partition_info";
+
+/// The name of the Flight action moving the write buffer consumer of every kafka partition to a
+/// wall-clock time. An admin action, see [`GrpcDelegate::with_admin_token`].
+pub const SEEK_TO_TIMESTAMP_ACTION: &str = "seek_to_timestamp";
+
+/// The name of the Flight action writing a copy of the buffered data to parquet files in
+/// object storage without persisting it, returning a [`SnapshotResponse`]. An admin action, see
+/// [`GrpcDelegate::with_admin_token`].
+pub const SNAPSHOT_ACTION: &str = "snapshot";
+
+/// The name of the Flight action persisting the data buffered for a partition to a parquet
+/// file, returning a [`PersistResponse`]. An admin action, see
+/// [`GrpcDelegate::with_admin_token`].
+pub const PERSIST_ACTION: &str = "persist";
+
+/// The name of the Flight action compacting the parquet files persisted for a partition,
+/// returning a [`CompactResponse`]. An admin action, see [`GrpcDelegate::with_admin_token`].
+pub const COMPACT_ACTION: &str = "compact";
+
+/// The gRPC metadata key of the token authorizing admin actions
+const AUTHORIZATION_HEADER: &str = "authorization";
 
 /// This type is responsible for managing all gRPC services exposed by
 /// `ingester`.
 #[derive(Debug, Default)]
 pub struct GrpcDelegate<I: IngestHandler> {
-    #[allow(dead_code)]
     ingest_handler: Arc<I>,
+    admin_token: Option<String>,
 }
 
 impl<I: IngestHandler> GrpcDelegate<I> {
     /// Initialise a new [`GrpcDelegate`] passing valid requests to the
     /// specified `ingest_handler`.
     pub fn new(ingest_handler: Arc<I>) -> Self {
-        Self { ingest_handler }
+        Self {
+            ingest_handler,
+            admin_token: None,
+        }
+    }
+
+    /// Serve the admin actions ([`SEEK_TO_TIMESTAMP_ACTION`], [`SNAPSHOT_ACTION`],
+    /// [`PERSIST_ACTION`] and [`COMPACT_ACTION`]), which change the state of the ingester.
+    ///
+    /// Requests must carry `token` in an `authorization: Token <token>` metadata entry, and
+    /// are rejected as unauthenticated otherwise.
+    ///
+    /// The admin actions are not served by default.
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+}
+
+impl<I: IngestHandler + Send + Sync + 'static> GrpcDelegate<I> {
+    /// Acquire an Arrow Flight gRPC service implementation.
+    pub fn flight_service(&self) -> FlightServer<impl Flight> {
+        FlightServer::new(FlightService {
+            ingest_handler: Arc::clone(&self.ingest_handler),
+            admin_token: self.admin_token.clone(),
+        })
+    }
+}
+
+type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + Sync + 'static>>;
+
+/// Body of the [`PARTITION_INFO_ACTION`], [`PERSIST_ACTION`] and [`COMPACT_ACTION`] actions,
+/// serialized as JSON
+#[derive(Debug, Deserialize)]
+struct PartitionInfoRequest {
+    sequencer_id: i16,
+    namespace: String,
+    table_name: String,
+    partition_key: String,
+}
+
+/// Body of the [`SEEK_TO_TIMESTAMP_ACTION`] action, serialized as JSON
+#[derive(Debug, Deserialize)]
+struct SeekToTimestampRequest {
+    /// RFC3339 timestamp, e.g. `2022-01-20T13:00:00Z`
+    timestamp: String,
+}
+
+/// Result of the [`SNAPSHOT_ACTION`] action, serialized as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotResponse {
+    /// Id of the snapshot, naming the object store directory holding its files
+    pub snapshot_id: String,
+    /// Object store paths of the parquet files written, one per buffered partition
+    pub files: Vec<String>,
+}
+
+/// Result of the [`PERSIST_ACTION`] action, serialized as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistResponse {
+    /// Object store id of the parquet file written, if the partition had buffered data
+    pub object_store_id: Option<String>,
+    /// Size of the parquet file written, in bytes
+    pub file_size_bytes: Option<i64>,
+}
+
+/// Result of the [`COMPACT_ACTION`] action, serialized as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactResponse {
+    /// Object store ids of the parquet files written, one per run of files compacted together
+    pub object_store_ids: Vec<String>,
+}
+
+/// Ticket of a `do_get` request, serialized as JSON: the tables of a namespace whose buffered
+/// data is returned in a single response.
+///
+/// Every `FlightData` of the response carries the [`ResponseMessage`] it is part of in its
+/// `app_metadata`. Each table starts with a single schema message, the merged schema of its
+/// batches, and the tables are streamed one after the other, each queried only once the
+/// previous one is sent. The `perform_ticket_query` method of the Flight client demultiplexes
+/// the response by `app_metadata`, and [`decode_response`] decodes the result.
+///
+/// If `row_counts` is set, the response instead holds the number of rows buffered for each
+/// table of the namespace, in a [`ResponseMessage::RowCounts`] message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryTicket {
+    /// Namespace of the tables
+    pub namespace: String,
+    /// Tables to return the buffered data of
+    #[serde(default)]
+    pub tables: Vec<TableQuery>,
+    /// Return the number of rows per table instead of the data of `tables`, which must be
+    /// empty
+    #[serde(default)]
+    pub row_counts: Option<RowCountQuery>,
+}
+
+/// The time range of the rows counted by a [`QueryTicket`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RowCountQuery {
+    /// Only count rows with a timestamp greater than or equal to this
+    pub min_time: Option<i64>,
+    /// Only count rows with a timestamp less than this
+    pub max_time: Option<i64>,
+}
+
+/// A table of a [`QueryTicket`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableQuery {
+    /// Name of the table
+    pub table_name: String,
+    /// Only return rows with a timestamp greater than or equal to this
+    pub min_time: Option<i64>,
+    /// Only return rows with a timestamp less than this
+    pub max_time: Option<i64>,
+    /// Only return these columns, plus the tag and time columns identifying the rows. All
+    /// columns are returned if not set.
+    pub columns: Option<Vec<String>>,
+    /// Only return the latest row of every series (i.e. distinct combination of tag values),
+    /// for example to display the current value of each series.
+    #[serde(default)]
+    pub latest_per_series: bool,
+}
+
+impl TableQuery {
+    /// The time range of the rows to return, open-ended if not set
+    fn time_range(&self) -> (i64, i64) {
+        (
+            self.min_time.unwrap_or(MIN_NANO_TIME),
+            self.max_time.unwrap_or(MAX_NANO_TIME),
+        )
+    }
+}
+
+/// What the messages of a `do_get` response to a [`QueryTicket`] are part of, carried in their
+/// `app_metadata` serialized as JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseMessage {
+    /// The buffered data of the named table
+    Table(String),
+    /// The number of rows buffered per table, as a batch of `table_name` and `row_count`
+    /// columns
+    RowCounts,
+}
+
+impl ResponseMessage {
+    fn to_app_metadata(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("response message serializes")
+    }
+}
+
+/// A decoded `do_get` response to a [`QueryTicket`]
+#[derive(Debug, Default)]
+pub struct QueryResponse {
+    /// The record batches of each table, keyed by table name
+    pub tables: BTreeMap<String, Vec<RecordBatch>>,
+    /// The number of rows buffered per table, keyed by table name, if the ticket requested
+    /// `row_counts`
+    pub row_counts: Option<BTreeMap<String, u64>>,
+}
+
+/// Decode the record batches of a `do_get` response to a [`QueryTicket`], keyed by the
+/// `app_metadata` of their messages as returned by the `collect_tables` method of the Flight
+/// client's `PerformQuery`.
+pub fn decode_response(
+    messages: BTreeMap<String, Vec<RecordBatch>>,
+) -> Result<QueryResponse, ArrowError> {
+    let mut response = QueryResponse::default();
+    for (app_metadata, batches) in messages {
+        let message: ResponseMessage = serde_json::from_str(&app_metadata).map_err(|e| {
+            ArrowError::ParseError(format!(
+                "invalid response message {:?}: {}",
+                app_metadata, e
+            ))
+        })?;
+        match message {
+            ResponseMessage::Table(table_name) => {
+                response.tables.insert(table_name, batches);
+            }
+            ResponseMessage::RowCounts => {
+                response.row_counts = Some(decode_row_counts(&batches)?);
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+/// Decode the number of rows of each table, keyed by table name, from the record batches of
+/// a [`ResponseMessage::RowCounts`] message.
+fn decode_row_counts(batches: &[RecordBatch]) -> Result<BTreeMap<String, u64>, ArrowError> {
+    let mut counts = BTreeMap::new();
+    for batch in batches {
+        let column = |name: &str| {
+            let idx = batch.schema().index_of(name)?;
+            Ok::<_, ArrowError>(Arc::clone(batch.column(idx)))
+        };
+        let table_names = column("table_name")?;
+        let table_names = table_names
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| ArrowError::ParseError("invalid table_name column".to_string()))?;
+        let row_counts = column("row_count")?;
+        let row_counts = row_counts
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| ArrowError::ParseError("invalid row_count column".to_string()))?;
+
+        for i in 0..batch.num_rows() {
+            counts.insert(table_names.value(i).to_string(), row_counts.value(i));
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Return the columns of `batch` in `selection`, plus its tag and time columns. Selected
+/// columns that do not exist in `batch` are ignored.
+fn select_columns(batch: &RecordBatch, selection: Selection<'_>) -> Result<RecordBatch, Status> {
+    let schema =
+        schema::Schema::try_from(batch.schema()).map_err(|e| Status::internal(e.to_string()))?;
+    let indices: Vec<_> = schema
+        .iter()
+        .enumerate()
+        .filter(|(_, (influx_type, field))| {
+            matches!(
+                influx_type,
+                Some(InfluxColumnType::Tag | InfluxColumnType::Timestamp)
+            ) || selection.contains(field.name())
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+
+    project_record_batch(batch, &schema.select_by_indices(&indices))
+        .map_err(|e| Status::internal(e.to_string()))
+}
+
+/// Encode `batches` as a single schema message, the merged schema of the batches, followed by
+/// the dictionaries and data of each batch, all carrying `message` in their `app_metadata`.
+/// Columns missing from a batch are sent as nulls.
+fn encode_batches(
+    message: &ResponseMessage,
+    batches: Vec<RecordBatch>,
+) -> Result<Vec<FlightData>, Status> {
+    if batches.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let batches: Vec<_> = batches.into_iter().map(Arc::new).collect();
+    let schema = merge_record_batch_schemas(&batches);
+    let options = IpcWriteOptions::default();
+
+    let mut messages = vec![flight_data_from_arrow_schema(
+        schema.as_arrow().as_ref(),
+        &options,
+    )];
+    for batch in batches {
+        let batch = merge_record_batches(schema.as_arrow(), vec![batch])
+            .map_err(|e| Status::internal(e.to_string()))?;
+        for batch in batch {
+            let (dictionaries, batch) = flight_data_from_arrow_batch(&batch, &options);
+            messages.extend(dictionaries);
+            messages.push(batch);
+        }
+    }
+
+    let app_metadata = message.to_app_metadata();
+    for message in &mut messages {
+        message.app_metadata = app_metadata.clone();
+    }
+    Ok(messages)
+}
+
+/// Return the batches of the buffered data of `table` in `namespace`, with the columns and
+/// rows selected by `table`
+fn query_table<I: IngestHandler>(
+    ingest_handler: &I,
+    namespace: &str,
+    table: &TableQuery,
+) -> Result<Vec<RecordBatch>, Status> {
+    let (min_time, max_time) = table.time_range();
+    let mut batches = ingest_handler
+        .query_table(
+            namespace,
+            &table.table_name,
+            TimestampRange::new(min_time, max_time),
+        )
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+    // Before the projection, so the latest rows are those of the whole series and not only of
+    // the selected fields. A series may have rows in several partitions, so their batches are
+    // merged first.
+    if table.latest_per_series {
+        let merged: Vec<_> = batches.into_iter().map(Arc::new).collect();
+        let schema = merge_record_batch_schemas(&merged);
+        batches = merge_record_batches(schema.as_arrow(), merged)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map(|batch| latest_per_series(&batch).map_err(|e| Status::internal(e.to_string())))
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+    }
+
+    if let Some(columns) = &table.columns {
+        let columns: Vec<_> = columns.iter().map(|c| c.as_str()).collect();
+        batches = batches
+            .iter()
+            .map(|batch| select_columns(batch, Selection::Some(&columns)))
+            .collect::<Result<_, _>>()?;
+    }
+
+    Ok(batches)
+}
+
+/// Concrete implementation of the gRPC Arrow Flight Service API
+#[derive(Debug)]
+struct FlightService<I: IngestHandler> {
+    ingest_handler: Arc<I>,
+    admin_token: Option<String>,
+}
+
+impl<I: IngestHandler + Send + Sync + 'static> FlightService<I> {
+    /// Reject `request` for an admin action unless it carries the admin token, see
+    /// [`GrpcDelegate::with_admin_token`]
+    fn authorize_admin<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let token = self
+            .admin_token
+            .as_ref()
+            .ok_or_else(|| Status::permission_denied("admin actions are disabled"))?;
+        let authorized = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Token "))
+            .map(|v| constant_time_eq(v.as_bytes(), token.as_bytes()))
+            .unwrap_or(false);
+        if !authorized {
+            return Err(Status::unauthenticated("invalid admin token"));
+        }
+
+        Ok(())
+    }
+
+    fn partition_info(&self, body: &[u8]) -> Result<Vec<u8>, Status> {
+        let request: PartitionInfoRequest = serde_json::from_slice(body).map_err(|e| {
+            Status::invalid_argument(format!("invalid {} request: {}", PARTITION_INFO_ACTION, e))
+        })?;
+
+        let info = self
+            .ingest_handler
+            .partition_info(
+                SequencerId::new(request.sequencer_id),
+                &request.namespace,
+                &request.table_name,
+                &request.partition_key,
+            )
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "no buffered data for partition {} of table {} in namespace {}",
+                    request.partition_key, request.table_name, request.namespace
+                ))
+            })?;
+
+        serde_json::to_vec(&info).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn seek_to_timestamp(&self, body: &[u8]) -> Result<Vec<u8>, Status> {
+        let request: SeekToTimestampRequest = serde_json::from_slice(body).map_err(|e| {
+            Status::invalid_argument(format!(
+                "invalid {} request: {}",
+                SEEK_TO_TIMESTAMP_ACTION, e
+            ))
+        })?;
+        let timestamp = Time::from_rfc3339(&request.timestamp).map_err(|e| {
+            Status::invalid_argument(format!("invalid timestamp {}: {}", request.timestamp, e))
+        })?;
+
+        // sequence number consumption resumes from, keyed by kafka partition
+        let sequence_numbers: BTreeMap<_, _> = self
+            .ingest_handler
+            .seek_to_timestamp(timestamp)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .into_iter()
+            .map(|(kafka_partition, sequence_number)| (kafka_partition.get(), sequence_number))
+            .collect();
+
+        serde_json::to_vec(&sequence_numbers).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn snapshot(&self) -> Result<Vec<u8>, Status> {
+        let snapshot_id = Uuid::new_v4();
+        let files = self
+            .ingest_handler
+            .snapshot(snapshot_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let response = SnapshotResponse {
+            snapshot_id: snapshot_id.to_string(),
+            files,
+        };
+        serde_json::to_vec(&response).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn persist(&self, body: &[u8]) -> Result<Vec<u8>, Status> {
+        let request: PartitionInfoRequest = serde_json::from_slice(body).map_err(|e| {
+            Status::invalid_argument(format!("invalid {} request: {}", PERSIST_ACTION, e))
+        })?;
+
+        let file = self
+            .ingest_handler
+            .persist_partition(
+                SequencerId::new(request.sequencer_id),
+                &request.namespace,
+                &request.table_name,
+                &request.partition_key,
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let response = PersistResponse {
+            object_store_id: file.as_ref().map(|f| f.object_store_id.to_string()),
+            file_size_bytes: file.as_ref().map(|f| f.file_size_bytes),
+        };
+        serde_json::to_vec(&response).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn compact(&self, body: &[u8]) -> Result<Vec<u8>, Status> {
+        let request: PartitionInfoRequest = serde_json::from_slice(body).map_err(|e| {
+            Status::invalid_argument(format!("invalid {} request: {}", COMPACT_ACTION, e))
+        })?;
+
+        let files = self
+            .ingest_handler
+            .compact_partition(
+                SequencerId::new(request.sequencer_id),
+                &request.namespace,
+                &request.table_name,
+                &request.partition_key,
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let response = CompactResponse {
+            object_store_ids: files
+                .iter()
+                .map(|f| f.object_store_id.to_string())
+                .collect(),
+        };
+        serde_json::to_vec(&response).map_err(|e| Status::internal(e.to_string()))
+    }
+
+    fn query(&self, ticket: &[u8]) -> Result<TonicStream<FlightData>, Status> {
+        let ticket: QueryTicket = serde_json::from_slice(ticket)
+            .map_err(|e| Status::invalid_argument(format!("invalid ticket: {}", e)))?;
+
+        if let Some(row_counts) = &ticket.row_counts {
+            if !ticket.tables.is_empty() {
+                return Err(Status::invalid_argument(
+                    "a ticket with row_counts must not list tables",
+                ));
+            }
+            let messages = self.row_counts(&ticket.namespace, row_counts)?;
+            return Ok(Box::pin(futures::stream::iter(
+                messages.into_iter().map(Ok),
+            )));
+        }
+
+        for table in &ticket.tables {
+            let (min_time, max_time) = table.time_range();
+            if min_time > max_time {
+                return Err(Status::invalid_argument(format!(
+                    "invalid time range for table {}: {} > {}",
+                    table.table_name, min_time, max_time
+                )));
+            }
+        }
+
+        let ingest_handler = Arc::clone(&self.ingest_handler);
+        let namespace = ticket.namespace;
+        let messages = futures::stream::iter(ticket.tables).flat_map(move |table| {
+            let messages =
+                query_table(ingest_handler.as_ref(), &namespace, &table).and_then(|batches| {
+                    encode_batches(&ResponseMessage::Table(table.table_name.clone()), batches)
+                });
+            match messages {
+                Ok(messages) => futures::stream::iter(messages.into_iter().map(Ok)).left_stream(),
+                Err(e) => futures::stream::iter([Err(e)]).right_stream(),
+            }
+        });
+
+        Ok(Box::pin(messages))
+    }
+
+    fn row_counts(
+        &self,
+        namespace: &str,
+        query: &RowCountQuery,
+    ) -> Result<Vec<FlightData>, Status> {
+        let min_time = query.min_time.unwrap_or(MIN_NANO_TIME);
+        let max_time = query.max_time.unwrap_or(MAX_NANO_TIME);
+        if min_time > max_time {
+            return Err(Status::invalid_argument(format!(
+                "invalid time range for row counts: {} > {}",
+                min_time, max_time
+            )));
+        }
+
+        let counts = self
+            .ingest_handler
+            .count_rows(namespace, TimestampRange::new(min_time, max_time))
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let table_names: ArrayRef = Arc::new(counts.keys().map(Some).collect::<StringArray>());
+        let row_counts: ArrayRef =
+            Arc::new(counts.values().map(|c| Some(*c)).collect::<UInt64Array>());
+        let batch = RecordBatch::try_from_iter(vec![
+            ("table_name", table_names),
+            ("row_count", row_counts),
+        ])
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        encode_batches(&ResponseMessage::RowCounts, vec![batch])
+    }
+}
+
+#[tonic::async_trait]
+impl<I: IngestHandler + Send + Sync + 'static> Flight for FlightService<I> {
+    type HandshakeStream = TonicStream<HandshakeResponse>;
+    type ListFlightsStream = TonicStream<FlightInfo>;
+    type DoGetStream = TonicStream<FlightData>;
+    type DoPutStream = TonicStream<PutResult>;
+    type DoActionStream = TonicStream<arrow_flight::Result>;
+    type ListActionsStream = TonicStream<ActionType>;
+    type DoExchangeStream = TonicStream<FlightData>;
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let output = self.query(&request.into_inner().ticket)?;
+        Ok(Response::new(output))
+    }
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_action(
+        &self,
+        request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        if matches!(
+            request.get_ref().r#type.as_str(),
+            SEEK_TO_TIMESTAMP_ACTION | SNAPSHOT_ACTION | PERSIST_ACTION | COMPACT_ACTION
+        ) {
+            self.authorize_admin(&request)?;
+        }
+
+        let action = request.into_inner();
+        let body = match action.r#type.as_str() {
+            PARTITION_INFO_ACTION => self.partition_info(&action.body)?,
+            SEEK_TO_TIMESTAMP_ACTION => self.seek_to_timestamp(&action.body).await?,
+            SNAPSHOT_ACTION => self.snapshot().await?,
+            PERSIST_ACTION => self.persist(&action.body).await?,
+            COMPACT_ACTION => self.compact(&action.body).await?,
+            other => {
+                return Err(Status::invalid_argument(format!(
+                    "unknown action: {}",
+                    other
+                )))
+            }
+        };
+
+        let output = futures::stream::iter(std::iter::once(Ok(arrow_flight::Result { body })));
+        Ok(Response::new(Box::pin(output) as Self::DoActionStream))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        let actions = [
+            ActionType {
+                r#type: PARTITION_INFO_ACTION.to_string(),
+                description: "Return the schema, sort key and number of chunks of the data \
+                    buffered for a partition"
+                    .to_string(),
+            },
+            ActionType {
+                r#type: SEEK_TO_TIMESTAMP_ACTION.to_string(),
+                description: "Resume consumption of every kafka partition from the first \
+                    entry produced at or after a timestamp"
+                    .to_string(),
+            },
+            ActionType {
+                r#type: SNAPSHOT_ACTION.to_string(),
+                description: "Write a copy of the buffered data to parquet files in object \
+                    storage, without persisting it"
+                    .to_string(),
+            },
+            ActionType {
+                r#type: PERSIST_ACTION.to_string(),
+                description: "Persist the data buffered for a partition to a parquet file"
+                    .to_string(),
+            },
+            ActionType {
+                r#type: COMPACT_ACTION.to_string(),
+                description: "Compact the parquet files persisted for a partition, marking \
+                    the compacted files for deletion"
+                    .to_string(),
+            },
+        ];
+        let output = futures::stream::iter(IntoIterator::into_iter(actions).map(Ok));
+        Ok(Response::new(Box::pin(output) as Self::ListActionsStream))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+}
+
+/// Compare `a` and `b` in time independent of the position of their first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::PartitionInfo;
+    use arrow_util::assert_batches_eq;
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use influxdb_iox_client::{connection::Builder, flight::Client};
+    use iox_catalog::interface::{KafkaPartition, ParquetFile};
+    use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
+    use schema::selection::Selection;
+    use time::Time;
+
+    /// Handler returning the same buffered data for a table regardless of the time range
+    #[derive(Debug, Default, Clone)]
+    struct TestHandler {
+        tables: BTreeMap<String, RecordBatch>,
+    }
+
+    #[async_trait]
+    impl IngestHandler for TestHandler {
+        fn partition_info(
+            &self,
+            _sequencer_id: SequencerId,
+            _namespace: &str,
+            _table_name: &str,
+            _partition_key: &str,
+        ) -> Result<Option<PartitionInfo>, crate::data::Error> {
+            Ok(None)
+        }
+
+        fn query_table(
+            &self,
+            _namespace: &str,
+            table_name: &str,
+            _range: TimestampRange,
+        ) -> Result<Vec<RecordBatch>, crate::data::Error> {
+            Ok(self.tables.get(table_name).cloned().into_iter().collect())
+        }
+
+        fn count_rows(
+            &self,
+            _namespace: &str,
+            _range: TimestampRange,
+        ) -> Result<BTreeMap<String, u64>, crate::data::Error> {
+            Ok(self
+                .tables
+                .iter()
+                .map(|(table_name, batch)| (table_name.clone(), batch.num_rows() as u64))
+                .collect())
+        }
+
+        async fn snapshot(&self, snapshot_id: Uuid) -> Result<Vec<String>, crate::data::Error> {
+            Ok(self
+                .tables
+                .keys()
+                .map(|table_name| format!("snapshots/{}/{}.parquet", snapshot_id, table_name))
+                .collect())
+        }
+
+        async fn seek_to_timestamp(
+            &self,
+            _timestamp: Time,
+        ) -> crate::handler::Result<BTreeMap<KafkaPartition, u64>> {
+            Ok(BTreeMap::new())
+        }
+
+        async fn persist_partition(
+            &self,
+            _sequencer_id: SequencerId,
+            _namespace: &str,
+            _table_name: &str,
+            _partition_key: &str,
+        ) -> Result<Option<ParquetFile>, crate::data::Error> {
+            Ok(None)
+        }
+
+        async fn compact_partition(
+            &self,
+            _sequencer_id: SequencerId,
+            _namespace: &str,
+            _table_name: &str,
+            _partition_key: &str,
+        ) -> Result<Vec<ParquetFile>, crate::data::Error> {
+            Ok(vec![])
+        }
+    }
+
+    fn lp_to_record_batch(lp: &str) -> (String, RecordBatch) {
+        let (table_name, batch) = lp_to_mutable_batch(lp);
+        (table_name, batch.to_arrow(Selection::All).unwrap())
+    }
+
+    /// Serve the Flight service of `handler` on a local port and return the response to
+    /// `ticket` as received by the Flight client
+    async fn query(handler: TestHandler, ticket: &QueryTicket) -> QueryResponse {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            let socket = listener.accept().await.map(|(socket, _)| socket);
+            Some((socket, listener))
+        });
+        let delegate = GrpcDelegate::new(Arc::new(handler));
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(delegate.flight_service())
+                .serve_with_incoming(incoming),
+        );
+
+        let connection = Builder::default()
+            .build(format!("http://{}", addr))
+            .await
+            .unwrap();
+        let messages = Client::new(connection)
+            .perform_ticket_query(serde_json::to_vec(ticket).unwrap())
+            .await
+            .unwrap()
+            .collect_tables()
+            .await
+            .unwrap();
+        decode_response(messages).unwrap()
+    }
+
+    #[tokio::test]
+    async fn do_get_multiple_tables() {
+        let handler = TestHandler {
+            tables: [
+                lp_to_record_batch("cpu,host=a usage=1.0 10\ncpu,host=b usage=2.0 20"),
+                lp_to_record_batch("mem,host=a free=5i 10"),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let ticket = QueryTicket {
+            namespace: "ns".to_string(),
+            tables: vec![
+                TableQuery {
+                    table_name: "mem".to_string(),
+                    min_time: None,
+                    max_time: None,
+                    columns: None,
+                    latest_per_series: false,
+                },
+                TableQuery {
+                    table_name: "cpu".to_string(),
+                    min_time: Some(0),
+                    max_time: Some(100),
+                    columns: None,
+                    latest_per_series: false,
+                },
+                TableQuery {
+                    table_name: "disk".to_string(),
+                    min_time: None,
+                    max_time: None,
+                    columns: None,
+                    latest_per_series: false,
+                },
+            ],
+            row_counts: None,
+        };
+        let tables = query(handler, &ticket).await.tables;
+        assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["cpu", "mem"]);
+
+        let expected = vec![
+            "+------+--------------------------------+-------+",
+            "| host | time                           | usage |",
+            "+------+--------------------------------+-------+",
+            "| a    | 1970-01-01T00:00:00.000000010Z | 1     |",
+            "| b    | 1970-01-01T00:00:00.000000020Z | 2     |",
+            "+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &tables["cpu"]);
+
+        let expected = vec![
+            "+------+------+--------------------------------+",
+            "| free | host | time                           |",
+            "+------+------+--------------------------------+",
+            "| 5    | a    | 1970-01-01T00:00:00.000000010Z |",
+            "+------+------+--------------------------------+",
+        ];
+        assert_batches_eq!(expected, &tables["mem"]);
+    }
+
+    #[tokio::test]
+    async fn do_get_selected_columns() {
+        let handler = TestHandler {
+            tables: [lp_to_record_batch(
+                "cpu,host=a,region=west usage=1.0,idle=2.0,system=3.0,user=4.0 10",
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let ticket = QueryTicket {
+            namespace: "ns".to_string(),
+            tables: vec![TableQuery {
+                table_name: "cpu".to_string(),
+                min_time: None,
+                max_time: None,
+                columns: Some(vec![
+                    "user".to_string(),
+                    "idle".to_string(),
+                    "missing".to_string(),
+                ]),
+                latest_per_series: false,
+            }],
+            row_counts: None,
+        };
+        let tables = query(handler, &ticket).await.tables;
+        let expected = vec![
+            "+------+------+--------+--------------------------------+------+",
+            "| host | idle | region | time                           | user |",
+            "+------+------+--------+--------------------------------+------+",
+            "| a    | 2    | west   | 1970-01-01T00:00:00.000000010Z | 4    |",
+            "+------+------+--------+--------------------------------+------+",
+        ];
+        assert_batches_eq!(expected, &tables["cpu"]);
+    }
+
+    #[tokio::test]
+    async fn do_get_latest_per_series() {
+        let handler = TestHandler {
+            tables: [lp_to_record_batch(
+                "cpu,host=a usage=1.0,idle=9.0 10\ncpu,host=b usage=2.0 10\n\
+                cpu,host=a usage=3.0 30\ncpu,host=a usage=4.0 20\ncpu,host=b usage=5.0 20\n\
+                cpu,host=a idle=8.0 40",
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let ticket = QueryTicket {
+            namespace: "ns".to_string(),
+            tables: vec![TableQuery {
+                table_name: "cpu".to_string(),
+                min_time: None,
+                max_time: None,
+                columns: Some(vec!["usage".to_string()]),
+                latest_per_series: true,
+            }],
+            row_counts: None,
+        };
+        let tables = query(handler, &ticket).await.tables;
+        let expected = vec![
+            "+------+--------------------------------+-------+",
+            "| host | time                           | usage |",
+            "+------+--------------------------------+-------+",
+            "| a    | 1970-01-01T00:00:00.000000040Z |       |",
+            "| b    | 1970-01-01T00:00:00.000000020Z | 5     |",
+            "+------+--------------------------------+-------+",
+        ];
+        assert_batches_eq!(expected, &tables["cpu"]);
+    }
+
+    #[tokio::test]
+    async fn do_get_row_counts() {
+        let handler = TestHandler {
+            tables: [
+                lp_to_record_batch("cpu,host=a usage=1.0 10\ncpu,host=b usage=2.0 20"),
+                lp_to_record_batch("mem,host=a free=5i 10"),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let service = FlightService {
+            ingest_handler: Arc::new(handler.clone()),
+            admin_token: None,
+        };
+
+        let mut ticket: QueryTicket = serde_json::from_str(
+            r#"{"namespace": "ns", "row_counts": {"min_time": 0, "max_time": 100}}"#,
+        )
+        .unwrap();
+        let response = query(handler.clone(), &ticket).await;
+        assert!(response.tables.is_empty());
+        assert_eq!(
+            response.row_counts,
+            Some(BTreeMap::from([
+                ("cpu".to_string(), 2),
+                ("mem".to_string(), 1)
+            ]))
+        );
+
+        // Counts cannot be combined with the data of tables
+        ticket
+            .tables
+            .push(serde_json::from_str(r#"{"table_name": "cpu"}"#).unwrap());
+        let request = Request::new(Ticket {
+            ticket: serde_json::to_vec(&ticket).unwrap(),
+        });
+        let err = service
+            .do_get(request)
+            .await
+            .err()
+            .expect("ticket rejected");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn do_action_snapshot() {
+        let handler = TestHandler {
+            tables: [lp_to_record_batch("cpu,host=a usage=1.0 10")]
+                .into_iter()
+                .collect(),
+        };
+        let service = FlightService {
+            ingest_handler: Arc::new(handler),
+            admin_token: Some("secret".to_string()),
+        };
+
+        let mut request = Request::new(Action {
+            r#type: SNAPSHOT_ACTION.to_string(),
+            body: vec![],
+        });
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_HEADER, "Token secret".parse().unwrap());
+        let results: Vec<_> = service
+            .do_action(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let response: SnapshotResponse = serde_json::from_slice(&results[0].body).unwrap();
+        assert_eq!(
+            response.files,
+            vec![format!("snapshots/{}/cpu.parquet", response.snapshot_id)]
+        );
+    }
+
+    #[tokio::test]
+    async fn do_action_requires_admin_token() {
+        let action = |token: Option<&str>| {
+            let mut request = Request::new(Action {
+                r#type: PERSIST_ACTION.to_string(),
+                body: br#"{"sequencer_id": 1, "namespace": "ns", "table_name": "cpu", "partition_key": "1970-01-01"}"#.to_vec(),
+            });
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert(AUTHORIZATION_HEADER, token.parse().unwrap());
+            }
+            request
+        };
+
+        // Admin actions are not served without a token
+        let service = FlightService {
+            ingest_handler: Arc::new(TestHandler::default()),
+            admin_token: None,
+        };
+        let err = service
+            .do_action(action(Some("Token secret")))
+            .await
+            .err()
+            .expect("action rejected");
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let service = FlightService {
+            ingest_handler: Arc::new(TestHandler::default()),
+            admin_token: Some("secret".to_string()),
+        };
+        for token in [None, Some("Token wrong"), Some("secret")] {
+            let err = service
+                .do_action(action(token))
+                .await
+                .err()
+                .expect("action rejected");
+            assert_eq!(err.code(), tonic::Code::Unauthenticated, "{:?}", token);
+        }
+        service
+            .do_action(action(Some("Token secret")))
+            .await
+            .unwrap();
+
+        // Introspection is not an admin action
+        let request = Request::new(Action {
+            r#type: PARTITION_INFO_ACTION.to_string(),
+            body: br#"{"sequencer_id": 1, "namespace": "ns", "table_name": "cpu", "partition_key": "1970-01-01"}"#.to_vec(),
+        });
+        let err = service
+            .do_action(request)
+            .await
+            .err()
+            .expect("no partition");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[test]
+    fn encode_batches_sends_schema_once() {
+        let (_, cpu1) = lp_to_record_batch("cpu,host=a usage=1.0 10");
+        let (_, cpu2) = lp_to_record_batch("cpu,host=b idle=2.0 20");
+        let message = ResponseMessage::Table("cpu".to_string());
+        let messages = encode_batches(&message, vec![cpu1, cpu2]).unwrap();
+
+        let header_types: Vec<_> = messages
+            .iter()
+            .map(|m| {
+                arrow::ipc::root_as_message(&m.data_header[..])
+                    .unwrap()
+                    .header_type()
+            })
+            .collect();
+        assert_eq!(
+            header_types,
+            vec![
+                arrow::ipc::MessageHeader::Schema,
+                arrow::ipc::MessageHeader::DictionaryBatch,
+                arrow::ipc::MessageHeader::RecordBatch,
+                arrow::ipc::MessageHeader::DictionaryBatch,
+                arrow::ipc::MessageHeader::RecordBatch,
+            ]
+        );
+        assert!(messages
+            .iter()
+            .all(|m| m.app_metadata == br#"{"table":"cpu"}"#));
+
+        // Both batches are sent with the merged schema
+        let schema = arrow::datatypes::Schema::try_from(&messages[0]).unwrap();
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["host", "idle", "time", "usage"]);
+    }
+
+    #[test]
+    fn decode_response_rejects_untyped_messages() {
+        let (_, batch) = lp_to_record_batch("cpu,host=a usage=1.0 10");
+        let err = decode_response(BTreeMap::from([("cpu".to_string(), vec![batch])]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid response message \"cpu\""), "{}", err);
+    }
+
+    #[test]
+    fn latest_per_series_defaults_to_false() {
+        let query: TableQuery = serde_json::from_str(r#"{"table_name": "cpu"}"#).unwrap();
+        assert!(!query.latest_per_series);
     }
 }
@@ -0,0 +1,672 @@
+//! Local write-ahead log of the operations buffered by the ingester
+//!
+//! Operations are appended to the log of their sequencer before they are buffered, so that data
+//! which is buffered but not yet persisted survives a crash of the ingester without being
+//! re-read from the write buffer.
+//!
+//! The log of a sequencer is a directory of segment files, each named after the sequence
+//! number of its first operation. Operations are appended to the last segment until it reaches
+//! [`SEGMENT_SIZE`] bytes, or the ingester restarts, and then to a new one. Once the data below
+//! a sequence number is persisted, the segments holding only operations before it are deleted.
+//!
+//! Each record of a segment consists of
+//!
+//! - the length of the rest of the record after the checksum, as a little-endian u32
+//! - the CRC32 checksum of the rest of the record, as a little-endian u32
+//! - the kafka partition and sequence number of the operation, as little-endian u32 and u64
+//! - the producer timestamp in nanoseconds, as a little-endian i64
+//! - the number of bytes read from the write buffer for the operation, as a little-endian u64
+//! - the length of the namespace as a little-endian u32, followed by the namespace
+//! - the operation, encoded as a write buffer payload
+//!
+//! Appends are synced to disk in batches, see [`SYNC_BATCH_OPERATIONS`] and
+//! [`SYNC_BATCH_INTERVAL`]. A crash can lose the operations appended since the last sync, or
+//! leave a partial or corrupt record in their place: a segment is read up to the first such
+//! record, and the rest of it dropped. The dropped operations are read from the write buffer
+//! again, as consumption continues after the last operation recovered.
+
+use data_types::sequence::Sequence;
+use dml::DmlOperation;
+use iox_catalog::interface::{SequenceNumber, SequencerId};
+use observability_deps::tracing::warn;
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use time::Time;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+    sync::Mutex,
+};
+use write_buffer::{
+    codec::{decode, encode_operation, ContentType, IoxHeaders},
+    core::WriteBufferError,
+};
+
+#[derive(Debug, Snafu)]
+#[allow(missing_copy_implementations, missing_docs)]
+pub enum Error {
+    #[snafu(display("Error accessing write-ahead log file {}: {}", path.display(), source))]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Error encoding an operation for the write-ahead log: {}", source))]
+    Encode { source: WriteBufferError },
+
+    #[snafu(display("Only sequenced operations can be written to the write-ahead log"))]
+    Unsequenced,
+}
+
+/// A specialized `Error` for write-ahead log errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Size of the length and checksum prefix of a record
+const PREFIX_SIZE: usize = 4 + 4;
+
+/// Size of the fixed-size fields of a record following its prefix
+const HEADER_SIZE: usize = 4 + 8 + 8 + 8 + 4;
+
+/// Number of operations appended to the log of a sequencer after which it is synced to disk
+pub const SYNC_BATCH_OPERATIONS: usize = 100;
+
+/// Time after the last sync of the log of a sequencer from which the next append syncs it to
+/// disk, whatever the number of operations appended
+pub const SYNC_BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size in bytes from which the next operation appended to the log of a sequencer starts a new
+/// segment
+pub const SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The segment of a sequencer opened for appending
+#[derive(Debug)]
+struct Segment {
+    file: File,
+    /// Sequence number of the first operation of the segment, which names it
+    first_sequence_number: u64,
+    /// Sequence number of the last operation appended to the segment
+    last_sequence_number: u64,
+    /// Size of the segment in bytes
+    size: u64,
+    /// Number of operations appended since the last sync
+    unsynced: usize,
+    /// When the file was last synced, or opened
+    last_sync: Instant,
+}
+
+/// The log of a sequencer, with the segment opened for appending, if any. Locked while
+/// appending, reading or trimming so that these never interleave.
+type Log = Arc<Mutex<Option<Segment>>>;
+
+/// Write-ahead log of the operations buffered for each sequencer, stored in a local directory
+#[derive(Debug)]
+pub struct Wal {
+    dir: PathBuf,
+    segment_size: u64,
+    /// The log of each sequencer, locked independently of the others
+    logs: parking_lot::Mutex<BTreeMap<SequencerId, Log>>,
+}
+
+impl Wal {
+    /// Create a write-ahead log stored in `dir`, creating the directory if it doesn't exist.
+    /// The operations logged in the directory before are kept.
+    pub async fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .context(IoSnafu { path: &dir })?;
+
+        Ok(Self {
+            dir,
+            segment_size: SEGMENT_SIZE,
+            logs: Default::default(),
+        })
+    }
+
+    /// Start a new segment once the current one reaches `segment_size` bytes, instead of
+    /// [`SEGMENT_SIZE`]
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size = segment_size;
+        self
+    }
+
+    /// The directory the log is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn sequencer_dir(&self, sequencer_id: SequencerId) -> PathBuf {
+        self.dir.join(sequencer_id.get().to_string())
+    }
+
+    fn segment_path(&self, sequencer_id: SequencerId, first_sequence_number: u64) -> PathBuf {
+        self.sequencer_dir(sequencer_id)
+            .join(format!("{:020}.wal", first_sequence_number))
+    }
+
+    fn log(&self, sequencer_id: SequencerId) -> Log {
+        Arc::clone(self.logs.lock().entry(sequencer_id).or_default())
+    }
+
+    /// Return the first sequence number of each segment of the sequencer, in order
+    async fn segments(&self, sequencer_id: SequencerId) -> Result<Vec<u64>> {
+        let dir = self.sequencer_dir(sequencer_id);
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).context(IoSnafu { path: dir }),
+        };
+
+        let mut segments = vec![];
+        while let Some(entry) = entries.next_entry().await.context(IoSnafu { path: &dir })? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("wal") {
+                continue;
+            }
+            if let Some(first) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse().ok())
+            {
+                segments.push(first);
+            }
+        }
+        segments.sort_unstable();
+        Ok(segments)
+    }
+
+    /// Append the sequenced operation to the log of the sequencer. The log is synced to disk
+    /// once [`SYNC_BATCH_OPERATIONS`] operations are appended or [`SYNC_BATCH_INTERVAL`] has
+    /// passed since the last sync.
+    pub async fn append(&self, sequencer_id: SequencerId, operation: &DmlOperation) -> Result<()> {
+        let record = encode_record(operation)?;
+        let sequence_number = operation
+            .meta()
+            .sequence()
+            .context(UnsequencedSnafu)?
+            .number;
+
+        let log = self.log(sequencer_id);
+        let mut log = log.lock().await;
+        if let Some(segment) = log.as_mut() {
+            if segment.size > 0 && segment.size + record.len() as u64 > self.segment_size {
+                // The full segment is only read back from now on
+                let path = self.segment_path(sequencer_id, segment.first_sequence_number);
+                segment.file.sync_data().await.context(IoSnafu { path })?;
+                *log = None;
+            }
+        }
+        if log.is_none() {
+            *log = Some(self.open_segment(sequencer_id, sequence_number).await?);
+        }
+        let segment = log.as_mut().expect("segment opened above");
+        let path = self.segment_path(sequencer_id, segment.first_sequence_number);
+
+        let sync = segment.unsynced + 1 >= SYNC_BATCH_OPERATIONS
+            || segment.last_sync.elapsed() >= SYNC_BATCH_INTERVAL;
+        let file = &mut segment.file;
+        let result = async {
+            // Hands the record to the OS even when it isn't synced, so that it is read back
+            file.write_all(&record).await?;
+            file.flush().await?;
+            if sync {
+                file.sync_data().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            // Don't leave a partial record in front of the records appended next
+            if let Err(e) = file.set_len(segment.size).await {
+                warn!(
+                    %e,
+                    path=%path.display(),
+                    "Failed to remove a partial write-ahead log record"
+                );
+            }
+            return Err(e).context(IoSnafu { path });
+        }
+
+        segment.size += record.len() as u64;
+        segment.last_sequence_number = sequence_number;
+        if sync {
+            segment.unsynced = 0;
+            segment.last_sync = Instant::now();
+        } else {
+            segment.unsynced += 1;
+        }
+        Ok(())
+    }
+
+    /// Open the segment starting at `first_sequence_number` for appending, creating it and
+    /// the directory of the sequencer if they don't exist
+    async fn open_segment(
+        &self,
+        sequencer_id: SequencerId,
+        first_sequence_number: u64,
+    ) -> Result<Segment> {
+        let dir = self.sequencer_dir(sequencer_id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .context(IoSnafu { path: &dir })?;
+
+        let path = self.segment_path(sequencer_id, first_sequence_number);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .context(IoSnafu { path: &path })?;
+        let size = file
+            .metadata()
+            .await
+            .context(IoSnafu { path: &path })?
+            .len();
+
+        Ok(Segment {
+            file,
+            first_sequence_number,
+            last_sequence_number: first_sequence_number,
+            size,
+            unsynced: 0,
+            last_sync: Instant::now(),
+        })
+    }
+
+    /// Read the operations logged for the sequencer, oldest first. A partial or corrupt record,
+    /// left by a crash, and the records after it in its segment are removed from the log.
+    /// Operations logged again after a restart are only returned once.
+    pub async fn read(&self, sequencer_id: SequencerId) -> Result<Vec<DmlOperation>> {
+        let log = self.log(sequencer_id);
+        let _log = log.lock().await;
+
+        let mut operations: Vec<DmlOperation> = vec![];
+        let mut last_sequence_number = None;
+        for first in self.segments(sequencer_id).await? {
+            let path = self.segment_path(sequencer_id, first);
+            let file = File::open(&path).await.context(IoSnafu { path: &path })?;
+            let mut reader = BufReader::new(file);
+
+            let mut record = vec![];
+            let mut offset = 0;
+            while read_record(&mut reader, &mut record)
+                .await
+                .context(IoSnafu { path: &path })?
+            {
+                let operation = match check_record(&record)
+                    .and_then(parse_record)
+                    .and_then(|r| r.decode())
+                {
+                    Ok(operation) => operation,
+                    Err(reason) => {
+                        warn!(
+                            path=%path.display(),
+                            offset,
+                            %reason,
+                            "Corrupt record in the write-ahead log"
+                        );
+                        break;
+                    }
+                };
+                offset += record.len() as u64;
+
+                let sequence_number = operation.meta().sequence().map(|s| s.number);
+                if sequence_number > last_sequence_number {
+                    last_sequence_number = sequence_number;
+                    operations.push(operation);
+                }
+            }
+
+            let len = tokio::fs::metadata(&path)
+                .await
+                .context(IoSnafu { path: &path })?
+                .len();
+            if offset < len {
+                warn!(
+                    path=%path.display(),
+                    offset,
+                    "Dropping the end of a write-ahead log segment from a partial or corrupt record"
+                );
+                let file = OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .await
+                    .context(IoSnafu { path: &path })?;
+                file.set_len(offset)
+                    .await
+                    .context(IoSnafu { path: &path })?;
+            }
+        }
+
+        Ok(operations)
+    }
+
+    /// Delete the segments of the sequencer holding only operations with a sequence number
+    /// lower than `min_unpersisted`, once the data they wrote is persisted.
+    pub async fn trim(
+        &self,
+        sequencer_id: SequencerId,
+        min_unpersisted: SequenceNumber,
+    ) -> Result<()> {
+        let min_unpersisted = min_unpersisted.get().max(0) as u64;
+        let log = self.log(sequencer_id);
+        let mut log = log.lock().await;
+
+        let segments = self.segments(sequencer_id).await?;
+        for (i, first) in segments.iter().enumerate() {
+            // The operations of a segment precede the first operation of the next one
+            let persisted = match (segments.get(i + 1), log.as_ref()) {
+                (Some(next), _) => *next <= min_unpersisted,
+                (None, Some(segment)) if segment.first_sequence_number == *first => {
+                    segment.last_sequence_number < min_unpersisted
+                }
+                // The last operation of a segment not appended to since the restart is unknown
+                (None, _) => false,
+            };
+            if !persisted {
+                break;
+            }
+
+            if matches!(log.as_ref(), Some(segment) if segment.first_sequence_number == *first) {
+                *log = None;
+            }
+            let path = self.segment_path(sequencer_id, *first);
+            tokio::fs::remove_file(&path)
+                .await
+                .context(IoSnafu { path })?;
+        }
+
+        Ok(())
+    }
+}
+/// Read the next record of `reader` into `record`, with its prefix. Return false at the end of
+/// the segment, or if it ends with a partial record.
+async fn read_record(
+    reader: &mut (impl AsyncRead + Unpin),
+    record: &mut Vec<u8>,
+) -> std::io::Result<bool> {
+    let mut prefix = [0; PREFIX_SIZE];
+    match reader.read_exact(&mut prefix).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_le_bytes(prefix[..4].try_into().unwrap()) as usize;
+    record.clear();
+    record.extend_from_slice(&prefix);
+    record.resize(PREFIX_SIZE + len, 0);
+    match reader.read_exact(&mut record[PREFIX_SIZE..]).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Return the record without its prefix if it matches its checksum
+fn check_record(record: &[u8]) -> Result<&[u8], String> {
+    let checksum = u32::from_le_bytes(record[4..PREFIX_SIZE].try_into().unwrap());
+    let record = &record[PREFIX_SIZE..];
+    if crc32fast::hash(record) != checksum {
+        return Err("checksum mismatch".to_string());
+    }
+    Ok(record)
+}
+
+fn encode_record(operation: &DmlOperation) -> Result<Vec<u8>> {
+    let meta = operation.meta();
+    let sequence = meta.sequence().context(UnsequencedSnafu)?;
+    let producer_ts = meta.producer_ts().context(UnsequencedSnafu)?;
+    let bytes_read = meta.bytes_read().unwrap_or_default();
+    let namespace = operation.namespace();
+
+    let mut payload = vec![];
+    encode_operation(namespace, operation, &mut payload).context(EncodeSnafu)?;
+
+    let len = HEADER_SIZE + namespace.len() + payload.len();
+    let mut record = Vec::with_capacity(PREFIX_SIZE + len);
+    record.extend_from_slice(&(len as u32).to_le_bytes());
+    // The checksum is filled in once the rest of the record is encoded
+    record.extend_from_slice(&[0; 4]);
+    record.extend_from_slice(&sequence.id.to_le_bytes());
+    record.extend_from_slice(&sequence.number.to_le_bytes());
+    record.extend_from_slice(&producer_ts.timestamp_nanos().to_le_bytes());
+    record.extend_from_slice(&(bytes_read as u64).to_le_bytes());
+    record.extend_from_slice(&(namespace.len() as u32).to_le_bytes());
+    record.extend_from_slice(namespace.as_bytes());
+    record.extend_from_slice(&payload);
+
+    let checksum = crc32fast::hash(&record[PREFIX_SIZE..]);
+    record[4..PREFIX_SIZE].copy_from_slice(&checksum.to_le_bytes());
+    Ok(record)
+}
+
+/// A record of the log, with the operation still encoded
+#[derive(Debug)]
+struct Record<'a> {
+    sequence: Sequence,
+    producer_ts: Time,
+    bytes_read: usize,
+    namespace: &'a str,
+    payload: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    fn decode(self) -> Result<DmlOperation, String> {
+        let headers = IoxHeaders::new(ContentType::Protobuf, None, self.namespace.to_string());
+        decode(
+            self.payload,
+            headers,
+            self.sequence,
+            self.producer_ts,
+            self.bytes_read,
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+fn parse_record(record: &[u8]) -> Result<Record<'_>, String> {
+    if record.len() < HEADER_SIZE {
+        return Err("record is shorter than its header".to_string());
+    }
+    let (header, rest) = record.split_at(HEADER_SIZE);
+
+    let id = u32::from_le_bytes(header[0..4].try_into().unwrap());
+    let number = u64::from_le_bytes(header[4..12].try_into().unwrap());
+    let producer_ts = i64::from_le_bytes(header[12..20].try_into().unwrap());
+    let bytes_read = u64::from_le_bytes(header[20..28].try_into().unwrap());
+    let namespace_len = u32::from_le_bytes(header[28..32].try_into().unwrap()) as usize;
+
+    if rest.len() < namespace_len {
+        return Err("namespace exceeds the record".to_string());
+    }
+    let (namespace, payload) = rest.split_at(namespace_len);
+    let namespace = std::str::from_utf8(namespace).map_err(|e| e.to_string())?;
+
+    Ok(Record {
+        sequence: Sequence::new(id, number),
+        producer_ts: Time::from_timestamp_nanos(producer_ts),
+        bytes_read: bytes_read as usize,
+        namespace,
+        payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::{delete_predicate::DeletePredicate, timestamp::TimestampRange};
+    use dml::{test_util::assert_op_eq, DmlDelete, DmlMeta, DmlWrite};
+    use mutable_batch_lp::lines_to_batches;
+
+    fn write(sequence_number: u64, lp: &str) -> DmlOperation {
+        DmlOperation::Write(DmlWrite::new(
+            "foo",
+            lines_to_batches(lp, 0).unwrap(),
+            DmlMeta::sequenced(
+                Sequence::new(0, sequence_number),
+                Time::from_timestamp_millis(42),
+                None,
+                50,
+            ),
+        ))
+    }
+
+    fn delete(sequence_number: u64) -> DmlOperation {
+        DmlOperation::Delete(DmlDelete::new(
+            "foo",
+            DeletePredicate {
+                range: TimestampRange::new(1, 2),
+                exprs: vec![],
+            },
+            None,
+            DmlMeta::sequenced(
+                Sequence::new(0, sequence_number),
+                Time::from_timestamp_millis(43),
+                None,
+                10,
+            ),
+        ))
+    }
+
+    fn assert_ops_eq(actual: &[DmlOperation], expected: &[DmlOperation]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, b) in actual.iter().zip(expected) {
+            assert_op_eq(a, b);
+        }
+    }
+
+    #[tokio::test]
+    async fn append_read_trim() {
+        let dir = tempfile::tempdir().unwrap();
+        let sequencer_id = SequencerId::new(1);
+        let other_sequencer_id = SequencerId::new(2);
+
+        let ops = vec![
+            write(1, "cpu,host=a usage=1 10"),
+            delete(2),
+            write(3, "cpu,host=b usage=2 20\nmem free=1i 20"),
+        ];
+        // Each operation gets a segment of its own
+        let wal = Wal::new(dir.path()).await.unwrap().with_segment_size(1);
+        for op in &ops {
+            wal.append(sequencer_id, op).await.unwrap();
+        }
+        assert!(wal.read(other_sequencer_id).await.unwrap().is_empty());
+
+        // The log survives the process
+        drop(wal);
+        let wal = Wal::new(dir.path()).await.unwrap().with_segment_size(1);
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &ops);
+
+        wal.trim(sequencer_id, SequenceNumber::new(2))
+            .await
+            .unwrap();
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &ops[1..]);
+
+        // Appending after trimming goes to the trimmed log
+        let op = write(4, "cpu,host=a usage=3 30");
+        wal.append(sequencer_id, &op).await.unwrap();
+        let expected = vec![ops[1].clone(), ops[2].clone(), op];
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &expected);
+
+        // The segment appended to is deleted too once all of it is persisted
+        wal.trim(sequencer_id, SequenceNumber::new(5))
+            .await
+            .unwrap();
+        assert!(wal.read(sequencer_id).await.unwrap().is_empty());
+        assert!(wal.segments(sequencer_id).await.unwrap().is_empty());
+
+        // Trimming a sequencer without a log does nothing
+        wal.trim(other_sequencer_id, SequenceNumber::new(2))
+            .await
+            .unwrap();
+        assert!(wal.read(other_sequencer_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn partial_record_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let sequencer_id = SequencerId::new(1);
+
+        let ops = vec![write(1, "cpu usage=1 10"), write(2, "cpu usage=2 20")];
+        let wal = Wal::new(dir.path()).await.unwrap();
+        for op in &ops {
+            wal.append(sequencer_id, op).await.unwrap();
+        }
+        drop(wal);
+
+        // Simulate a crash in the middle of appending the last record
+        let path = dir.path().join("1").join(format!("{:020}.wal", 1));
+        let len = std::fs::metadata(&path).unwrap().len();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 3).unwrap();
+
+        let wal = Wal::new(dir.path()).await.unwrap();
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &ops[..1]);
+
+        // The partial record is gone, so new records can be read again
+        let op = write(3, "cpu usage=3 30");
+        wal.append(sequencer_id, &op).await.unwrap();
+        let expected = vec![ops[0].clone(), op];
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &expected);
+    }
+
+    #[tokio::test]
+    async fn corrupt_record_and_the_records_after_it_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let sequencer_id = SequencerId::new(1);
+
+        let ops = vec![
+            write(1, "cpu usage=1 10"),
+            write(2, "cpu usage=2 20"),
+            write(3, "cpu usage=3 30"),
+        ];
+        let wal = Wal::new(dir.path()).await.unwrap();
+        for op in &ops {
+            wal.append(sequencer_id, op).await.unwrap();
+        }
+        drop(wal);
+
+        // Simulate a crash losing an unsynced record, but not the one appended after it
+        let path = dir.path().join("1").join(format!("{:020}.wal", 1));
+        let mut data = std::fs::read(&path).unwrap();
+        let first_len = PREFIX_SIZE + u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+        data[first_len + PREFIX_SIZE + HEADER_SIZE] ^= 0xff;
+        std::fs::write(&path, &data).unwrap();
+
+        let wal = Wal::new(dir.path()).await.unwrap();
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &ops[..1]);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), first_len as u64);
+
+        // Appending after a restart goes to a new segment, read after the first one
+        let op = write(2, "cpu usage=4 20");
+        wal.append(sequencer_id, &op).await.unwrap();
+        let expected = vec![ops[0].clone(), op];
+        assert_ops_eq(&wal.read(sequencer_id).await.unwrap(), &expected);
+    }
+
+    #[tokio::test]
+    async fn unsequenced_operation_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal = Wal::new(dir.path()).await.unwrap();
+
+        let op = DmlOperation::Write(DmlWrite::new(
+            "foo",
+            lines_to_batches("cpu usage=1 10", 0).unwrap(),
+            DmlMeta::unsequenced(None),
+        ));
+        assert!(matches!(
+            wal.append(SequencerId::new(1), &op).await,
+            Err(Error::Unsequenced)
+        ));
+        assert!(wal.read(SequencerId::new(1)).await.unwrap().is_empty());
+    }
+}
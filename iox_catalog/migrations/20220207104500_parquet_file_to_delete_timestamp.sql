@@ -0,0 +1,7 @@
+-- Record when a parquet file was marked for deletion rather than only whether
+-- it was, so that files superseded by compaction are kept until in-flight
+-- queries reading them complete. Files already flagged are treated as marked
+-- at the epoch.
+ALTER TABLE IF EXISTS iox_catalog.parquet_file
+    ALTER COLUMN to_delete TYPE BIGINT
+    USING CASE WHEN to_delete THEN 0 ELSE NULL END;
@@ -0,0 +1,5 @@
+-- Record the size of every parquet file, so that the storage used by a
+-- namespace can be summarised without listing object storage. The size of
+-- files recorded before is unknown and counted as zero.
+ALTER TABLE IF EXISTS iox_catalog.parquet_file
+    ADD COLUMN IF NOT EXISTS file_size_bytes BIGINT NOT NULL DEFAULT 0;
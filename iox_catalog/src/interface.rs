@@ -2,11 +2,12 @@
 
 use async_trait::async_trait;
 use influxdb_line_protocol::FieldValue;
+use observability_deps::tracing::warn;
 use schema::{InfluxColumnType, InfluxFieldType};
 use snafu::{OptionExt, Snafu};
 use std::convert::TryFrom;
 use std::fmt::Formatter;
-use std::{collections::BTreeMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug, time::Duration};
 use uuid::Uuid;
 
 #[derive(Debug, Snafu)]
@@ -38,17 +39,55 @@ pub enum Error {
     #[snafu(display("namespace {} not found", name))]
     NamespaceNotFound { name: String },
 
+    #[snafu(display("column {} not found", name))]
+    ColumnNotFound { name: String },
+
     #[snafu(display("parquet file with object_store_id {} already exists", object_store_id))]
     FileExists { object_store_id: Uuid },
 
-    #[snafu(display("parquet_file record {} not found", id))]
-    ParquetRecordNotFound { id: ParquetFileId },
+    #[snafu(display(
+        "parquet_file record with object_store_id {} not found",
+        object_store_id
+    ))]
+    ParquetObjectNotFound { object_store_id: Uuid },
 
     #[snafu(display("cannot derive valid column schema from column {}: {}", name, source))]
     InvalidColumn {
         source: Box<dyn std::error::Error + Send>,
         name: String,
     },
+
+    #[snafu(display(
+        "catalog schema version mismatch: expected {} but found {}",
+        expected,
+        found
+    ))]
+    SchemaVersionMismatch { expected: i64, found: i64 },
+
+    #[snafu(display(
+        "catalog schema has no applied migrations: expected version {}",
+        expected
+    ))]
+    SchemaNotInitialised { expected: i64 },
+
+    #[snafu(display("namespace {} has invalid retention {:?}: {}", name, retention, source))]
+    InvalidRetention {
+        name: String,
+        retention: String,
+        source: humantime::DurationError,
+    },
+}
+
+impl Error {
+    /// Returns true if the operation that failed with this error may succeed when retried, e.g.
+    /// after a serialization failure, deadlock or lost connection. Errors caused by the request
+    /// itself, like constraint violations, are never retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::SqlxError { source } => crate::postgres::is_transient(source),
+            _ => false,
+        }
+    }
 }
 
 /// A specialized `Error` for Catalog errors
@@ -333,16 +372,26 @@ pub trait QueryPoolRepo: Send + Sync {
 pub trait NamespaceRepo: Send + Sync {
     /// Creates the namespace in the catalog. If one by the same name already exists, an
     /// error is returned.
+    ///
+    /// If `retention_duration` is [`None`], the catalog's default retention is applied, if
+    /// one is configured.
     async fn create(
         &self,
         name: &str,
-        retention_duration: &str,
+        retention_duration: Option<&str>,
         kafka_topic_id: KafkaTopicId,
         query_pool_id: QueryPoolId,
     ) -> Result<Namespace>;
 
     /// Gets the namespace by its unique name.
     async fn get_by_name(&self, name: &str) -> Result<Option<Namespace>>;
+
+    /// Lists all namespaces whose writes go to the given kafka topic.
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Namespace>>;
+
+    /// Counts the tables, columns and parquet files of the given namespace. Parquet files
+    /// flagged for deletion are not counted.
+    async fn summary(&self, namespace_id: NamespaceId) -> Result<NamespaceSummary>;
 }
 
 /// Functions for working with tables in the catalog
@@ -353,6 +402,9 @@ pub trait TableRepo: Send + Sync {
 
     /// Lists all tables in the catalog for the given namespace id.
     async fn list_by_namespace_id(&self, namespace_id: NamespaceId) -> Result<Vec<Table>>;
+
+    /// Lists all tables of the namespaces whose writes go to the given kafka topic.
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Table>>;
 }
 
 /// Functions for working with columns in the catalog
@@ -370,6 +422,12 @@ pub trait ColumnRepo: Send + Sync {
 
     /// Lists all columns in the passed in namespace id.
     async fn list_by_namespace_id(&self, namespace_id: NamespaceId) -> Result<Vec<Column>>;
+
+    /// Changes the type of the `I64` column `name` of the table `table_id` to `F64` and
+    /// returns the updated column. Promoting a column that is already `F64` returns it
+    /// unchanged. Will return `Error::ColumnTypeMismatch` if the column has any other type
+    /// and `Error::ColumnNotFound` if it doesn't exist.
+    async fn promote_to_float(&self, name: &str, table_id: TableId) -> Result<Column>;
 }
 
 /// Functions for working with sequencers in the catalog
@@ -394,6 +452,14 @@ pub trait SequencerRepo: Send + Sync {
 
     /// list all sequencers for a given kafka topic
     async fn list_by_kafka_topic(&self, topic: &KafkaTopic) -> Result<Vec<Sequencer>>;
+
+    /// advance the min_unpersisted_sequence_number of the sequencer to `sequence_number`, once
+    /// the data of all operations before it is persisted. It is never moved backwards.
+    async fn update_min_unpersisted_sequence_number(
+        &self,
+        sequencer_id: SequencerId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()>;
 }
 
 /// Functions for working with IOx partitions in the catalog. Note that these are how
@@ -451,10 +517,24 @@ pub trait ParquetFileRepo: Send + Sync {
         max_sequence_number: SequenceNumber,
         min_time: Timestamp,
         max_time: Timestamp,
+        file_size_bytes: i64,
     ) -> Result<ParquetFile>;
 
-    /// Flag the parquet file for deletion
-    async fn flag_for_delete(&self, id: ParquetFileId) -> Result<()>;
+    /// Mark the parquet file with the given object store ID for deletion at
+    /// `marked_at`, such as when it is superseded by the output of a
+    /// compaction.
+    ///
+    /// The file is not removed from the catalog, so queries already reading
+    /// it can complete. Marking an already marked file keeps the original
+    /// time.
+    async fn mark_for_deletion(&self, object_store_id: Uuid, marked_at: Timestamp) -> Result<()>;
+
+    /// Get the parquet file with the given object store ID, if any
+    async fn get_by_object_store_id(&self, object_store_id: Uuid) -> Result<Option<ParquetFile>>;
+
+    /// List all parquet files marked for deletion before `older_than`, whose
+    /// objects can be hard-deleted.
+    async fn list_deletable(&self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
 
     /// Get all parquet files for a sequencer with a max_sequence_number greater than the
     /// one passed in. The ingester will use this on startup to see which files were persisted
@@ -465,6 +545,23 @@ pub trait ParquetFileRepo: Send + Sync {
         sequencer_id: SequencerId,
         sequence_number: SequenceNumber,
     ) -> Result<Vec<ParquetFile>>;
+
+    /// List all parquet files of the tables in the given namespace, excluding
+    /// files flagged for deletion.
+    async fn list_by_namespace(&self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>>;
+
+    /// List all parquet files of the given partition, excluding files flagged
+    /// for deletion.
+    async fn list_by_partition(&self, partition_id: PartitionId) -> Result<Vec<ParquetFile>>;
+
+    /// Get the greatest max_sequence_number of the parquet files persisted for the given
+    /// partition from the given sequencer, including files flagged for deletion, or `None` if
+    /// no such file exists. All data of the partition up to this sequence number is persisted.
+    async fn max_persisted_sequence_number(
+        &self,
+        partition_id: PartitionId,
+        sequencer_id: SequencerId,
+    ) -> Result<Option<SequenceNumber>>;
 }
 
 /// Data object for a kafka topic
@@ -501,6 +598,37 @@ pub struct Namespace {
     pub query_pool_id: QueryPoolId,
 }
 
+impl Namespace {
+    /// Parse the retention duration of this namespace, returning [`None`] if data should be
+    /// retained forever.
+    pub fn retention(&self) -> Result<Option<Duration>> {
+        match self.retention_duration.as_deref() {
+            None | Some(INFINITE_RETENTION) => Ok(None),
+            Some(s) => {
+                humantime::parse_duration(s)
+                    .map(Some)
+                    .map_err(|e| Error::InvalidRetention {
+                        name: self.name.clone(),
+                        retention: s.to_string(),
+                        source: e,
+                    })
+            }
+        }
+    }
+
+    /// Like [`Self::retention()`], but a retention duration that fails to parse is logged and
+    /// treated as infinite, so that it does not stop writes to and queries of the namespace.
+    pub fn retention_or_infinite(&self) -> Option<Duration> {
+        self.retention().unwrap_or_else(|e| {
+            warn!(%e, "invalid namespace retention, retaining data forever");
+            None
+        })
+    }
+}
+
+/// The retention duration of a namespace that never drops data.
+pub const INFINITE_RETENTION: &str = "inf";
+
 /// Schema collection for a namespace. This is an in-memory object useful for a schema
 /// cache.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -511,18 +639,21 @@ pub struct NamespaceSchema {
     pub kafka_topic_id: KafkaTopicId,
     /// the query pool assigned to answer queries for this namespace
     pub query_pool_id: QueryPoolId,
+    /// the retention period of the namespace, or [`None`] if data is retained forever
+    pub retention: Option<Duration>,
     /// the tables in the namespace by name
     pub tables: BTreeMap<String, TableSchema>,
 }
 
 impl NamespaceSchema {
-    /// Create a new `NamespaceSchema`
+    /// Create a new `NamespaceSchema` that retains data forever
     pub fn new(id: NamespaceId, kafka_topic_id: KafkaTopicId, query_pool_id: QueryPoolId) -> Self {
         Self {
             id,
             tables: BTreeMap::new(),
             kafka_topic_id,
             query_pool_id,
+            retention: None,
         }
     }
 }
@@ -538,12 +669,14 @@ pub async fn get_schema_by_name(name: &str, catalog: &dyn Catalog) -> Result<Nam
     // get the columns first just in case someone else is creating schema while we're doing this.
     let columns = catalog.columns().list_by_namespace_id(namespace.id).await?;
     let tables = catalog.tables().list_by_namespace_id(namespace.id).await?;
+    let retention = namespace.retention_or_infinite();
 
     let mut namespace = NamespaceSchema::new(
         namespace.id,
         namespace.kafka_topic_id,
         namespace.query_pool_id,
     );
+    namespace.retention = retention;
 
     let mut table_id_to_schema = BTreeMap::new();
     for t in tables {
@@ -578,6 +711,20 @@ pub async fn get_schema_by_name(name: &str, catalog: &dyn Catalog) -> Result<Nam
     Ok(namespace)
 }
 
+/// Aggregated counts of the contents of a namespace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct NamespaceSummary {
+    /// The number of tables in the namespace
+    pub table_count: i64,
+    /// The number of columns of all tables in the namespace
+    pub column_count: i64,
+    /// The number of parquet files of all tables in the namespace
+    pub parquet_file_count: i64,
+    /// The total size in bytes of the parquet files of all tables in the
+    /// namespace
+    pub total_bytes: i64,
+}
+
 /// Data object for a table
 #[derive(Debug, Clone, sqlx::FromRow, Eq, PartialEq)]
 pub struct Table {
@@ -860,8 +1007,10 @@ pub struct ParquetFile {
     pub min_time: Timestamp,
     /// the max timestamp of data in this file
     pub max_time: Timestamp,
-    /// flag to mark that this file should be deleted from object storage
-    pub to_delete: bool,
+    /// the size of the file in object storage, in bytes
+    pub file_size_bytes: i64,
+    /// when this file was marked to be deleted from object storage, if it was
+    pub to_delete: Option<Timestamp>,
 }
 
 #[cfg(test)]
@@ -881,6 +1030,10 @@ pub(crate) mod test_helpers {
         test_partition(Arc::clone(&catalog)).await;
         test_tombstone(Arc::clone(&catalog)).await;
         test_parquet_file(Arc::clone(&catalog)).await;
+        test_parquet_file_concurrent_create(Arc::clone(&catalog)).await;
+        test_parquet_file_size(Arc::clone(&catalog)).await;
+        test_max_persisted_sequence_number(Arc::clone(&catalog)).await;
+        test_namespace_summary(Arc::clone(&catalog)).await;
     }
 
     async fn test_setup(catalog: Arc<dyn Catalog>) {
@@ -917,14 +1070,15 @@ pub(crate) mod test_helpers {
 
         let namespace_name = "test_namespace";
         let namespace = namespace_repo
-            .create(namespace_name, "inf", kafka.id, pool.id)
+            .create(namespace_name, Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
         assert!(namespace.id > NamespaceId::new(0));
         assert_eq!(namespace.name, namespace_name);
+        assert_eq!(namespace.retention().unwrap(), None);
 
         let conflict = namespace_repo
-            .create(namespace_name, "inf", kafka.id, pool.id)
+            .create(namespace_name, Some("inf"), kafka.id, pool.id)
             .await;
         assert!(matches!(
             conflict.unwrap_err(),
@@ -937,6 +1091,76 @@ pub(crate) mod test_helpers {
             .unwrap()
             .expect("namespace should be there");
         assert_eq!(namespace, found);
+
+        let other_kafka = catalog
+            .kafka_topics()
+            .create_or_get("test_namespace_other_topic")
+            .await
+            .unwrap();
+        let other_namespace = namespace_repo
+            .create("test_namespace_other", None, other_kafka.id, pool.id)
+            .await
+            .unwrap();
+        let listed = namespace_repo.list_by_kafka_topic(kafka.id).await.unwrap();
+        assert!(listed.contains(&namespace));
+        assert!(!listed.contains(&other_namespace));
+        let listed = namespace_repo
+            .list_by_kafka_topic(other_kafka.id)
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![other_namespace]);
+    }
+
+    /// Assert namespaces created in `catalog` without an explicit retention inherit
+    /// `default_retention`, while an explicit retention overrides it.
+    ///
+    /// Namespace names are randomised so this may run against a catalog shared with
+    /// other tests.
+    pub(crate) async fn test_namespace_default_retention(
+        catalog: Arc<dyn Catalog>,
+        default_retention: &str,
+    ) {
+        catalog.setup().await.expect("catalog setup");
+
+        let namespace_repo = catalog.namespaces();
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+
+        let default_name = format!("namespace_default_retention_{}", Uuid::new_v4());
+        let namespace = namespace_repo
+            .create(&default_name, None, kafka.id, pool.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            namespace.retention_duration.as_deref(),
+            Some(default_retention)
+        );
+
+        let explicit_name = format!("namespace_explicit_retention_{}", Uuid::new_v4());
+        let namespace = namespace_repo
+            .create(&explicit_name, Some("1h"), kafka.id, pool.id)
+            .await
+            .unwrap();
+        assert_eq!(namespace.retention_duration.as_deref(), Some("1h"));
+        assert_eq!(
+            namespace.retention().unwrap(),
+            Some(Duration::from_secs(60 * 60))
+        );
+
+        let found = namespace_repo
+            .get_by_name(&default_name)
+            .await
+            .unwrap()
+            .expect("namespace should be there");
+        assert_eq!(found.retention_duration.as_deref(), Some(default_retention));
+
+        // A retention that fails to parse retains data forever
+        let invalid = Namespace {
+            retention_duration: Some("bananas".to_string()),
+            ..found
+        };
+        invalid.retention().unwrap_err();
+        assert_eq!(invalid.retention_or_infinite(), None);
     }
 
     async fn test_table(catalog: Arc<dyn Catalog>) {
@@ -944,7 +1168,7 @@ pub(crate) mod test_helpers {
         let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
         let namespace = catalog
             .namespaces()
-            .create("namespace_table_test", "inf", kafka.id, pool.id)
+            .create("namespace_table_test", Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
 
@@ -972,7 +1196,7 @@ pub(crate) mod test_helpers {
         // test we can create a table of the same name in a different namespace
         let namespace2 = catalog
             .namespaces()
-            .create("two", "inf", kafka.id, pool.id)
+            .create("two", Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
         assert_ne!(namespace, namespace2);
@@ -982,7 +1206,38 @@ pub(crate) mod test_helpers {
             .await
             .unwrap();
         assert_ne!(tt, test_table);
-        assert_eq!(test_table.namespace_id, namespace2.id)
+        assert_eq!(test_table.namespace_id, namespace2.id);
+
+        // test we can list the tables of all namespaces of a kafka topic
+        let other_kafka = catalog
+            .kafka_topics()
+            .create_or_get("test_table_other_topic")
+            .await
+            .unwrap();
+        let other_namespace = catalog
+            .namespaces()
+            .create("test_table_other", Some("inf"), other_kafka.id, pool.id)
+            .await
+            .unwrap();
+        let other_table = catalog
+            .tables()
+            .create_or_get("test_table", other_namespace.id)
+            .await
+            .unwrap();
+        let tables = catalog
+            .tables()
+            .list_by_kafka_topic(kafka.id)
+            .await
+            .unwrap();
+        assert!(tables.contains(&tt));
+        assert!(tables.contains(&test_table));
+        assert!(!tables.contains(&other_table));
+        let tables = catalog
+            .tables()
+            .list_by_kafka_topic(other_kafka.id)
+            .await
+            .unwrap();
+        assert_eq!(tables, vec![other_table]);
     }
 
     async fn test_column(catalog: Arc<dyn Catalog>) {
@@ -990,7 +1245,7 @@ pub(crate) mod test_helpers {
         let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
         let namespace = catalog
             .namespaces()
-            .create("namespace_column_test", "inf", kafka.id, pool.id)
+            .create("namespace_column_test", Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
         let table = catalog
@@ -1048,6 +1303,51 @@ pub(crate) mod test_helpers {
             .await
             .unwrap();
         assert_eq!(vec![c, ccc], columns);
+
+        // test that an integer column can be promoted to a float column
+        let int_col = catalog
+            .columns()
+            .create_or_get("int_column", table.id, ColumnType::I64)
+            .await
+            .unwrap();
+        let promoted = catalog
+            .columns()
+            .promote_to_float("int_column", table.id)
+            .await
+            .unwrap();
+        assert_eq!(promoted.id, int_col.id);
+        assert_eq!(promoted.column_type, ColumnType::F64 as i16);
+
+        // promoting it again is a no-op
+        let promoted_again = catalog
+            .columns()
+            .promote_to_float("int_column", table.id)
+            .await
+            .unwrap();
+        assert_eq!(promoted, promoted_again);
+
+        // the promoted type is persisted
+        let got = catalog
+            .columns()
+            .create_or_get("int_column", table.id, ColumnType::F64)
+            .await
+            .unwrap();
+        assert_eq!(promoted, got);
+
+        // columns of other types cannot be promoted
+        let err = catalog
+            .columns()
+            .promote_to_float("column_test", table.id)
+            .await
+            .expect_err("should error with wrong column type");
+        assert!(matches!(err, Error::ColumnTypeMismatch { .. }));
+
+        let err = catalog
+            .columns()
+            .promote_to_float("missing", table.id)
+            .await
+            .expect_err("should error with missing column");
+        assert!(matches!(err, Error::ColumnNotFound { .. }));
     }
 
     async fn test_sequencer(catalog: Arc<dyn Catalog>) {
@@ -1101,6 +1401,31 @@ pub(crate) mod test_helpers {
             .await
             .unwrap();
         assert!(sequencer.is_none());
+
+        // the min unpersisted sequence number only advances
+        let sequencer_id = created.keys().next().copied().unwrap();
+        let min_unpersisted = || async {
+            catalog
+                .sequencers()
+                .list_by_kafka_topic(&kafka)
+                .await
+                .unwrap()
+                .into_iter()
+                .find(|s| s.id == sequencer_id)
+                .unwrap()
+                .min_unpersisted_sequence_number
+        };
+        for (sequence_number, expected) in [(10, 10), (5, 10), (12, 12)] {
+            catalog
+                .sequencers()
+                .update_min_unpersisted_sequence_number(
+                    sequencer_id,
+                    SequenceNumber::new(sequence_number),
+                )
+                .await
+                .unwrap();
+            assert_eq!(min_unpersisted().await, expected);
+        }
     }
 
     async fn test_partition(catalog: Arc<dyn Catalog>) {
@@ -1108,7 +1433,7 @@ pub(crate) mod test_helpers {
         let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
         let namespace = catalog
             .namespaces()
-            .create("namespace_partition_test", "inf", kafka.id, pool.id)
+            .create("namespace_partition_test", Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
         let table = catalog
@@ -1165,7 +1490,7 @@ pub(crate) mod test_helpers {
         let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
         let namespace = catalog
             .namespaces()
-            .create("namespace_tombstone_test", "inf", kafka.id, pool.id)
+            .create("namespace_tombstone_test", Some("inf"), kafka.id, pool.id)
             .await
             .unwrap();
         let table = catalog
@@ -1237,12 +1562,20 @@ pub(crate) mod test_helpers {
         assert_eq!(vec![t2, t3], listed);
     }
 
+    /// The size of the parquet files created by the tests
+    const TEST_FILE_SIZE_BYTES: i64 = 1337;
+
     async fn test_parquet_file(catalog: Arc<dyn Catalog>) {
         let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
         let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
         let namespace = catalog
             .namespaces()
-            .create("namespace_parquet_file_test", "inf", kafka.id, pool.id)
+            .create(
+                "namespace_parquet_file_test",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
             .await
             .unwrap();
         let table = catalog
@@ -1285,6 +1618,7 @@ pub(crate) mod test_helpers {
                 SequenceNumber::new(140),
                 min_time,
                 max_time,
+                TEST_FILE_SIZE_BYTES,
             )
             .await
             .unwrap();
@@ -1300,6 +1634,7 @@ pub(crate) mod test_helpers {
                 SequenceNumber::new(140),
                 min_time,
                 max_time,
+                TEST_FILE_SIZE_BYTES,
             )
             .await
             .unwrap_err();
@@ -1315,6 +1650,7 @@ pub(crate) mod test_helpers {
                 SequenceNumber::new(200),
                 min_time,
                 max_time,
+                TEST_FILE_SIZE_BYTES,
             )
             .await
             .unwrap();
@@ -1330,13 +1666,481 @@ pub(crate) mod test_helpers {
             .unwrap();
         assert_eq!(vec![other_file], files);
 
-        // verify that to_delete is initially set to false and that it can be updated to true
-        assert!(!parquet_file.to_delete);
-        parquet_repo.flag_for_delete(parquet_file.id).await.unwrap();
+        // files of a table in another namespace are not listed for this one
+        let other_namespace = catalog
+            .namespaces()
+            .create(
+                "namespace_parquet_file_test_other",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let other_namespace_table = catalog
+            .tables()
+            .create_or_get("test_table", other_namespace.id)
+            .await
+            .unwrap();
+        let other_namespace_partition = catalog
+            .partitions()
+            .create_or_get("one", sequencer.id, other_namespace_table.id)
+            .await
+            .unwrap();
+        let other_namespace_file = parquet_repo
+            .create(
+                sequencer.id,
+                other_namespace_partition.table_id,
+                other_namespace_partition.id,
+                Uuid::new_v4(),
+                SequenceNumber::new(1),
+                SequenceNumber::new(2),
+                min_time,
+                max_time,
+                TEST_FILE_SIZE_BYTES,
+            )
+            .await
+            .unwrap();
+
+        let files = parquet_repo.list_by_namespace(namespace.id).await.unwrap();
+        assert_eq!(vec![parquet_file, other_file], files);
+        let files = parquet_repo
+            .list_by_namespace(other_namespace.id)
+            .await
+            .unwrap();
+        assert_eq!(vec![other_namespace_file], files);
+
+        let files = parquet_repo.list_by_partition(partition.id).await.unwrap();
+        assert_eq!(vec![parquet_file], files);
+        let files = parquet_repo
+            .list_by_partition(other_partition.id)
+            .await
+            .unwrap();
+        assert_eq!(vec![other_file], files);
+
+        // verify that to_delete is initially unset and that the file can be marked
+        assert!(parquet_file.to_delete.is_none());
+        assert!(parquet_repo
+            .list_deletable(Timestamp::new(i64::MAX))
+            .await
+            .unwrap()
+            .is_empty());
+        parquet_repo
+            .mark_for_deletion(parquet_file.object_store_id, Timestamp::new(100))
+            .await
+            .unwrap();
         let files = parquet_repo
             .list_by_sequencer_greater_than(sequencer.id, SequenceNumber::new(1))
             .await
             .unwrap();
-        assert!(files.first().unwrap().to_delete);
+        assert_eq!(files.first().unwrap().to_delete, Some(Timestamp::new(100)));
+
+        // marking an already marked file keeps the original time
+        parquet_repo
+            .mark_for_deletion(parquet_file.object_store_id, Timestamp::new(200))
+            .await
+            .unwrap();
+        let marked = parquet_repo
+            .get_by_object_store_id(parquet_file.object_store_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(marked.to_delete, Some(Timestamp::new(100)));
+
+        // marking an unknown file is an error
+        let err = parquet_repo
+            .mark_for_deletion(Uuid::new_v4(), Timestamp::new(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ParquetObjectNotFound { .. }));
+
+        // only files marked before the cutoff are deletable
+        let files = parquet_repo
+            .list_deletable(Timestamp::new(100))
+            .await
+            .unwrap();
+        assert!(files.is_empty());
+        let files = parquet_repo
+            .list_deletable(Timestamp::new(101))
+            .await
+            .unwrap();
+        assert_eq!(vec![marked], files);
+
+        // files marked for deletion are not listed
+        let files = parquet_repo.list_by_namespace(namespace.id).await.unwrap();
+        assert_eq!(vec![other_file], files);
+        let files = parquet_repo.list_by_partition(partition.id).await.unwrap();
+        assert!(files.is_empty());
+
+        // the garbage collector may only delete objects that are marked before
+        // the cutoff or unknown to the catalog
+        let cutoff = Timestamp::new(101);
+        assert!(
+            crate::is_deletable(&*catalog, parquet_file.object_store_id, cutoff)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !crate::is_deletable(&*catalog, parquet_file.object_store_id, Timestamp::new(100))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !crate::is_deletable(&*catalog, other_file.object_store_id, cutoff)
+                .await
+                .unwrap()
+        );
+        assert!(crate::is_deletable(&*catalog, Uuid::new_v4(), cutoff)
+            .await
+            .unwrap());
+    }
+
+    async fn test_parquet_file_concurrent_create(catalog: Arc<dyn Catalog>) {
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create(
+                "namespace_parquet_file_concurrent_test",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, KafkaPartition::new(2))
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("concurrent", sequencer.id, table.id)
+            .await
+            .unwrap();
+
+        let (sequencer_id, table_id, partition_id) =
+            (partition.sequencer_id, partition.table_id, partition.id);
+        let create = |object_store_id: Uuid, sequence_number: i64| {
+            let catalog = Arc::clone(&catalog);
+            tokio::spawn(async move {
+                catalog
+                    .parquet_files()
+                    .create(
+                        sequencer_id,
+                        table_id,
+                        partition_id,
+                        object_store_id,
+                        SequenceNumber::new(sequence_number),
+                        SequenceNumber::new(sequence_number),
+                        Timestamp::new(1),
+                        Timestamp::new(10),
+                        TEST_FILE_SIZE_BYTES,
+                    )
+                    .await
+            })
+        };
+
+        // Concurrent creates of distinct files all succeed with unique ids
+        let created = (1..=50)
+            .map(|sequence_number| create(Uuid::new_v4(), sequence_number))
+            .collect::<FuturesOrdered<_>>()
+            .map(|res| {
+                let file = res
+                    .expect("task panicked")
+                    .expect("failed to create parquet file");
+                (file.id, file)
+            })
+            .collect::<BTreeMap<_, _>>()
+            .await;
+        assert_eq!(created.len(), 50);
+
+        let listed = catalog
+            .parquet_files()
+            .list_by_partition(partition.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.id, file))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(created, listed);
+
+        // Of concurrent creates of the same file exactly one succeeds
+        let object_store_id = Uuid::new_v4();
+        let results = (1..=10)
+            .map(|_| create(object_store_id, 100))
+            .collect::<FuturesOrdered<_>>()
+            .map(|res| res.expect("task panicked"))
+            .collect::<Vec<_>>()
+            .await;
+        let (created, rejected): (Vec<_>, Vec<_>) = results.into_iter().partition(|r| r.is_ok());
+        assert_eq!(created.len(), 1);
+        assert!(rejected
+            .into_iter()
+            .all(|r| matches!(r, Err(Error::FileExists { .. }))));
+
+        let listed = catalog
+            .parquet_files()
+            .list_by_partition(partition.id)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 51);
+        assert_eq!(
+            listed
+                .iter()
+                .filter(|f| f.object_store_id == object_store_id)
+                .count(),
+            1
+        );
+    }
+
+    async fn test_parquet_file_size(catalog: Arc<dyn Catalog>) {
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create(
+                "namespace_parquet_file_size_test",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, KafkaPartition::new(6))
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("size", sequencer.id, table.id)
+            .await
+            .unwrap();
+
+        let parquet_repo = catalog.parquet_files();
+        for file_size_bytes in [0, 1, TEST_FILE_SIZE_BYTES, i64::MAX] {
+            let file = parquet_repo
+                .create(
+                    sequencer.id,
+                    partition.table_id,
+                    partition.id,
+                    Uuid::new_v4(),
+                    SequenceNumber::new(1),
+                    SequenceNumber::new(2),
+                    Timestamp::new(1),
+                    Timestamp::new(10),
+                    file_size_bytes,
+                )
+                .await
+                .unwrap();
+            assert_eq!(file.file_size_bytes, file_size_bytes);
+
+            let stored = parquet_repo
+                .get_by_object_store_id(file.object_store_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored.file_size_bytes, file_size_bytes);
+        }
+    }
+
+    async fn test_max_persisted_sequence_number(catalog: Arc<dyn Catalog>) {
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create(
+                "namespace_max_persisted_sequence_number_test",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, KafkaPartition::new(3))
+            .await
+            .unwrap();
+        let other_sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, KafkaPartition::new(4))
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("max_persisted", sequencer.id, table.id)
+            .await
+            .unwrap();
+
+        let parquet_repo = catalog.parquet_files();
+        assert_eq!(
+            parquet_repo
+                .max_persisted_sequence_number(partition.id, sequencer.id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        for (sequencer_id, min, max) in [
+            (sequencer.id, 1, 20),
+            (sequencer.id, 21, 50),
+            (sequencer.id, 5, 10),
+            (other_sequencer.id, 1, 100),
+        ] {
+            parquet_repo
+                .create(
+                    sequencer_id,
+                    partition.table_id,
+                    partition.id,
+                    Uuid::new_v4(),
+                    SequenceNumber::new(min),
+                    SequenceNumber::new(max),
+                    Timestamp::new(1),
+                    Timestamp::new(10),
+                    TEST_FILE_SIZE_BYTES,
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            parquet_repo
+                .max_persisted_sequence_number(partition.id, sequencer.id)
+                .await
+                .unwrap(),
+            Some(SequenceNumber::new(50))
+        );
+        assert_eq!(
+            parquet_repo
+                .max_persisted_sequence_number(partition.id, other_sequencer.id)
+                .await
+                .unwrap(),
+            Some(SequenceNumber::new(100))
+        );
+    }
+
+    async fn test_namespace_summary(catalog: Arc<dyn Catalog>) {
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create("namespace_summary_test", Some("inf"), kafka.id, pool.id)
+            .await
+            .unwrap();
+        let other_namespace = catalog
+            .namespaces()
+            .create(
+                "namespace_summary_test_other",
+                Some("inf"),
+                kafka.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            catalog.namespaces().summary(namespace.id).await.unwrap(),
+            NamespaceSummary::default()
+        );
+
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, KafkaPartition::new(5))
+            .await
+            .unwrap();
+        for (namespace_id, table_name, columns) in [
+            (namespace.id, "cpu", &["host", "usage", "time"][..]),
+            (namespace.id, "mem", &["host", "time"][..]),
+            (other_namespace.id, "disk", &["host", "free", "time"][..]),
+        ] {
+            let table = catalog
+                .tables()
+                .create_or_get(table_name, namespace_id)
+                .await
+                .unwrap();
+            for column in columns {
+                catalog
+                    .columns()
+                    .create_or_get(column, table.id, ColumnType::Tag)
+                    .await
+                    .unwrap();
+            }
+
+            let partition = catalog
+                .partitions()
+                .create_or_get("summary", sequencer.id, table.id)
+                .await
+                .unwrap();
+            for _ in 0..2 {
+                catalog
+                    .parquet_files()
+                    .create(
+                        sequencer.id,
+                        table.id,
+                        partition.id,
+                        Uuid::new_v4(),
+                        SequenceNumber::new(1),
+                        SequenceNumber::new(2),
+                        Timestamp::new(1),
+                        Timestamp::new(10),
+                        TEST_FILE_SIZE_BYTES,
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // files flagged for deletion are not counted
+        let file = catalog
+            .parquet_files()
+            .list_by_namespace(namespace.id)
+            .await
+            .unwrap()
+            .remove(0);
+        catalog
+            .parquet_files()
+            .mark_for_deletion(file.object_store_id, Timestamp::new(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            catalog.namespaces().summary(namespace.id).await.unwrap(),
+            NamespaceSummary {
+                table_count: 2,
+                column_count: 5,
+                parquet_file_count: 3,
+                total_bytes: 3 * TEST_FILE_SIZE_BYTES,
+            }
+        );
+        assert_eq!(
+            catalog
+                .namespaces()
+                .summary(other_namespace.id)
+                .await
+                .unwrap(),
+            NamespaceSummary {
+                table_count: 1,
+                column_count: 3,
+                parquet_file_count: 2,
+                total_bytes: 2 * TEST_FILE_SIZE_BYTES,
+            }
+        );
     }
 }
@@ -13,12 +13,13 @@
 
 use crate::interface::{
     Catalog, ColumnType, Error, KafkaPartition, KafkaTopic, NamespaceSchema, QueryPool, Result,
-    Sequencer, SequencerId, TableSchema,
+    Sequencer, SequencerId, TableSchema, Timestamp,
 };
 use futures::{stream::FuturesOrdered, StreamExt};
 
 use mutable_batch::MutableBatch;
 use std::{borrow::Cow, collections::BTreeMap};
+use uuid::Uuid;
 
 #[allow(dead_code)]
 const SHARED_KAFKA_TOPIC: &str = "iox_shared";
@@ -186,6 +187,29 @@ pub async fn create_or_get_default_records(
     Ok((kafka_topic, query_pool, sequencers))
 }
 
+/// Returns true if the garbage collector may hard-delete the object of the
+/// parquet file with `object_store_id` from object storage.
+///
+/// This is the case if the catalog has no record of the file, or its record
+/// was marked for deletion before `cutoff`. As a file is uploaded before its
+/// catalog record is created, callers must not consider objects newer than
+/// `cutoff`.
+pub async fn is_deletable(
+    catalog: &dyn Catalog,
+    object_store_id: Uuid,
+    cutoff: Timestamp,
+) -> Result<bool> {
+    let file = catalog
+        .parquet_files()
+        .get_by_object_store_id(object_store_id)
+        .await?;
+
+    Ok(match file {
+        None => true,
+        Some(file) => matches!(file.to_delete, Some(marked_at) if marked_at < cutoff),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,7 +240,7 @@ mod tests {
 
                     let namespace = repo
                         .namespaces()
-                        .create(NAMESPACE_NAME, "inf", kafka_topic.id, query_pool.id)
+                        .create(NAMESPACE_NAME, Some("inf"), kafka_topic.id, query_pool.id)
                         .await
                         .unwrap();
 
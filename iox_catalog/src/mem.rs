@@ -3,10 +3,10 @@
 
 use crate::interface::{
     Catalog, Column, ColumnId, ColumnRepo, ColumnType, Error, KafkaPartition, KafkaTopic,
-    KafkaTopicId, KafkaTopicRepo, Namespace, NamespaceId, NamespaceRepo, ParquetFile,
-    ParquetFileId, ParquetFileRepo, Partition, PartitionId, PartitionRepo, QueryPool, QueryPoolId,
-    QueryPoolRepo, Result, SequenceNumber, Sequencer, SequencerId, SequencerRepo, Table, TableId,
-    TableRepo, Timestamp, Tombstone, TombstoneId, TombstoneRepo,
+    KafkaTopicId, KafkaTopicRepo, Namespace, NamespaceId, NamespaceRepo, NamespaceSummary,
+    ParquetFile, ParquetFileId, ParquetFileRepo, Partition, PartitionId, PartitionRepo, QueryPool,
+    QueryPoolId, QueryPoolRepo, Result, SequenceNumber, Sequencer, SequencerId, SequencerRepo,
+    Table, TableId, TableRepo, Timestamp, Tombstone, TombstoneId, TombstoneRepo,
 };
 use async_trait::async_trait;
 use std::convert::TryFrom;
@@ -19,6 +19,7 @@ use uuid::Uuid;
 #[derive(Default)]
 pub struct MemCatalog {
     collections: Mutex<MemCollections>,
+    default_retention: Option<String>,
 }
 
 impl MemCatalog {
@@ -26,6 +27,13 @@ impl MemCatalog {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Set the retention duration applied to namespaces created without an explicit
+    /// retention.
+    pub fn with_default_retention(mut self, retention_duration: impl Into<String>) -> Self {
+        self.default_retention = Some(retention_duration.into());
+        self
+    }
 }
 
 impl std::fmt::Debug for MemCatalog {
@@ -149,7 +157,7 @@ impl NamespaceRepo for MemCatalog {
     async fn create(
         &self,
         name: &str,
-        retention_duration: &str,
+        retention_duration: Option<&str>,
         kafka_topic_id: KafkaTopicId,
         query_pool_id: QueryPoolId,
     ) -> Result<Namespace> {
@@ -165,7 +173,9 @@ impl NamespaceRepo for MemCatalog {
             name: name.to_string(),
             kafka_topic_id,
             query_pool_id,
-            retention_duration: Some(retention_duration.to_string()),
+            retention_duration: retention_duration
+                .or_else(|| self.default_retention.as_deref())
+                .map(ToString::to_string),
         };
         collections.namespaces.push(namespace);
         Ok(collections.namespaces.last().unwrap().clone())
@@ -179,6 +189,46 @@ impl NamespaceRepo for MemCatalog {
             .find(|n| n.name == name)
             .cloned())
     }
+
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Namespace>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        Ok(collections
+            .namespaces
+            .iter()
+            .filter(|n| n.kafka_topic_id == kafka_topic_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn summary(&self, namespace_id: NamespaceId) -> Result<NamespaceSummary> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        let tables: Vec<_> = collections
+            .tables
+            .iter()
+            .filter(|t| t.namespace_id == namespace_id)
+            .map(|t| t.id)
+            .collect();
+
+        let column_count = collections
+            .columns
+            .iter()
+            .filter(|c| tables.contains(&c.table_id))
+            .count();
+        let (parquet_file_count, total_bytes) = collections
+            .parquet_files
+            .iter()
+            .filter(|f| f.to_delete.is_none() && tables.contains(&f.table_id))
+            .fold((0, 0), |(count, bytes), f| {
+                (count + 1, bytes + f.file_size_bytes)
+            });
+
+        Ok(NamespaceSummary {
+            table_count: tables.len() as i64,
+            column_count: column_count as i64,
+            parquet_file_count,
+            total_bytes,
+        })
+    }
 }
 
 #[async_trait]
@@ -216,6 +266,23 @@ impl TableRepo for MemCatalog {
             .collect();
         Ok(tables)
     }
+
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Table>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        let namespace_ids: Vec<_> = collections
+            .namespaces
+            .iter()
+            .filter(|n| n.kafka_topic_id == kafka_topic_id)
+            .map(|n| n.id)
+            .collect();
+        let tables: Vec<_> = collections
+            .tables
+            .iter()
+            .filter(|t| namespace_ids.contains(&t.namespace_id))
+            .cloned()
+            .collect();
+        Ok(tables)
+    }
 }
 
 #[async_trait]
@@ -279,6 +346,32 @@ impl ColumnRepo for MemCatalog {
 
         Ok(columns)
     }
+
+    async fn promote_to_float(&self, name: &str, table_id: TableId) -> Result<Column> {
+        let mut collections = self.collections.lock().expect("mutex poisoned");
+
+        let column = collections
+            .columns
+            .iter_mut()
+            .find(|c| c.name == name && c.table_id == table_id)
+            .ok_or_else(|| Error::ColumnNotFound {
+                name: name.to_string(),
+            })?;
+
+        if column.column_type == ColumnType::I64 as i16 {
+            column.column_type = ColumnType::F64 as i16;
+        } else if column.column_type != ColumnType::F64 as i16 {
+            return Err(Error::ColumnTypeMismatch {
+                name: name.to_string(),
+                existing: ColumnType::try_from(column.column_type)
+                    .unwrap()
+                    .to_string(),
+                new: ColumnType::F64.to_string(),
+            });
+        }
+
+        Ok(column.clone())
+    }
 }
 
 #[async_trait]
@@ -340,6 +433,24 @@ impl SequencerRepo for MemCatalog {
             .collect();
         Ok(sequencers)
     }
+
+    async fn update_min_unpersisted_sequence_number(
+        &self,
+        sequencer_id: SequencerId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        let mut collections = self.collections.lock().expect("mutex poisoned");
+        if let Some(sequencer) = collections
+            .sequencers
+            .iter_mut()
+            .find(|s| s.id == sequencer_id)
+        {
+            sequencer.min_unpersisted_sequence_number = sequencer
+                .min_unpersisted_sequence_number
+                .max(sequence_number.get());
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -446,6 +557,7 @@ impl ParquetFileRepo for MemCatalog {
         max_sequence_number: SequenceNumber,
         min_time: Timestamp,
         max_time: Timestamp,
+        file_size_bytes: i64,
     ) -> Result<ParquetFile> {
         let mut collections = self.collections.lock().expect("mutex poisoned");
         if collections
@@ -466,23 +578,50 @@ impl ParquetFileRepo for MemCatalog {
             max_sequence_number,
             min_time,
             max_time,
-            to_delete: false,
+            file_size_bytes,
+            to_delete: None,
         };
         collections.parquet_files.push(parquet_file);
         Ok(*collections.parquet_files.last().unwrap())
     }
 
-    async fn flag_for_delete(&self, id: ParquetFileId) -> Result<()> {
+    async fn mark_for_deletion(&self, object_store_id: Uuid, marked_at: Timestamp) -> Result<()> {
         let mut collections = self.collections.lock().expect("mutex poisoned");
 
-        match collections.parquet_files.iter_mut().find(|p| p.id == id) {
-            Some(f) => f.to_delete = true,
-            None => return Err(Error::ParquetRecordNotFound { id }),
+        match collections
+            .parquet_files
+            .iter_mut()
+            .find(|p| p.object_store_id == object_store_id)
+        {
+            Some(f) => {
+                f.to_delete.get_or_insert(marked_at);
+            }
+            None => return Err(Error::ParquetObjectNotFound { object_store_id }),
         }
 
         Ok(())
     }
 
+    async fn get_by_object_store_id(&self, object_store_id: Uuid) -> Result<Option<ParquetFile>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        Ok(collections
+            .parquet_files
+            .iter()
+            .find(|f| f.object_store_id == object_store_id)
+            .cloned())
+    }
+
+    async fn list_deletable(&self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        let files: Vec<_> = collections
+            .parquet_files
+            .iter()
+            .filter(|f| matches!(f.to_delete, Some(marked_at) if marked_at < older_than))
+            .cloned()
+            .collect();
+        Ok(files)
+    }
+
     async fn list_by_sequencer_greater_than(
         &self,
         sequencer_id: SequencerId,
@@ -497,6 +636,48 @@ impl ParquetFileRepo for MemCatalog {
             .collect();
         Ok(files)
     }
+
+    async fn list_by_namespace(&self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        let table_ids: Vec<_> = collections
+            .tables
+            .iter()
+            .filter(|t| t.namespace_id == namespace_id)
+            .map(|t| t.id)
+            .collect();
+        let files: Vec<_> = collections
+            .parquet_files
+            .iter()
+            .filter(|f| f.to_delete.is_none() && table_ids.contains(&f.table_id))
+            .cloned()
+            .collect();
+        Ok(files)
+    }
+
+    async fn list_by_partition(&self, partition_id: PartitionId) -> Result<Vec<ParquetFile>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        let files: Vec<_> = collections
+            .parquet_files
+            .iter()
+            .filter(|f| f.to_delete.is_none() && f.partition_id == partition_id)
+            .cloned()
+            .collect();
+        Ok(files)
+    }
+
+    async fn max_persisted_sequence_number(
+        &self,
+        partition_id: PartitionId,
+        sequencer_id: SequencerId,
+    ) -> Result<Option<SequenceNumber>> {
+        let collections = self.collections.lock().expect("mutex poisoned");
+        Ok(collections
+            .parquet_files
+            .iter()
+            .filter(|f| f.partition_id == partition_id && f.sequencer_id == sequencer_id)
+            .map(|f| f.max_sequence_number)
+            .max())
+    }
 }
 
 #[cfg(test)]
@@ -508,4 +689,14 @@ mod tests {
     async fn test_catalog() {
         crate::interface::test_helpers::test_catalog(Arc::new(MemCatalog::new())).await;
     }
+
+    #[tokio::test]
+    async fn test_default_retention() {
+        let catalog = MemCatalog::new().with_default_retention("30days");
+        crate::interface::test_helpers::test_namespace_default_retention(
+            Arc::new(catalog),
+            "30days",
+        )
+        .await;
+    }
 }
@@ -2,7 +2,7 @@
 
 use crate::interface::{
     Catalog, Column, ColumnRepo, ColumnType, Error, KafkaPartition, KafkaTopic, KafkaTopicId,
-    KafkaTopicRepo, Namespace, NamespaceId, NamespaceRepo, ParquetFile, ParquetFileId,
+    KafkaTopicRepo, Namespace, NamespaceId, NamespaceRepo, NamespaceSummary, ParquetFile,
     ParquetFileRepo, Partition, PartitionId, PartitionRepo, QueryPool, QueryPoolId, QueryPoolRepo,
     Result, SequenceNumber, Sequencer, SequencerId, SequencerRepo, Table, TableId, TableRepo,
     Timestamp, Tombstone, TombstoneRepo,
@@ -25,6 +25,7 @@ static MIGRATOR: Migrator = sqlx::migrate!();
 #[derive(Debug)]
 pub struct PostgresCatalog {
     pool: Pool<Postgres>,
+    default_retention: Option<String>,
 }
 
 impl PostgresCatalog {
@@ -59,7 +60,63 @@ impl PostgresCatalog {
         // name for cross-correlation between Conductor logs & database connections.
         info!(application_name=%app_name, "connected to catalog store");
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            default_retention: None,
+        })
+    }
+
+    /// Set the retention duration applied to namespaces created without an explicit
+    /// retention.
+    pub fn with_default_retention(mut self, retention_duration: impl Into<String>) -> Self {
+        self.default_retention = Some(retention_duration.into());
+        self
+    }
+
+    /// The catalog schema version this binary expects, which is the version
+    /// of the most recent migration it embeds.
+    pub fn expected_schema_version() -> i64 {
+        MIGRATOR
+            .iter()
+            .map(|m| m.version)
+            .max()
+            .expect("no catalog migrations embedded")
+    }
+
+    /// Read the version of the most recent migration successfully applied to
+    /// the catalog, or [`None`] if no migrations have been applied.
+    pub async fn applied_schema_version(&self) -> Result<Option<i64>> {
+        let rec = sqlx::query_scalar::<_, Option<i64>>(
+            r#"
+SELECT MAX(version) FROM _sqlx_migrations WHERE success = TRUE;
+        "#,
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        match rec {
+            Ok(v) => Ok(v),
+            // The migrations table is created by the first migration run.
+            Err(e) if is_undefined_table(&e) => Ok(None),
+            Err(e) => Err(Error::SqlxError { source: e }),
+        }
+    }
+
+    /// Return an error if the catalog schema version does not match
+    /// [`Self::expected_schema_version()`].
+    pub async fn check_schema_version(&self) -> Result<()> {
+        let found = self.applied_schema_version().await?;
+        check_schema_version(Self::expected_schema_version(), found)
+    }
+}
+
+/// Compare the `expected` schema version against the `found` version read
+/// from the catalog.
+fn check_schema_version(expected: i64, found: Option<i64>) -> Result<()> {
+    match found {
+        Some(found) if found == expected => Ok(()),
+        Some(found) => Err(Error::SchemaVersionMismatch { expected, found }),
+        None => Err(Error::SchemaNotInitialised { expected }),
     }
 }
 
@@ -175,7 +232,7 @@ impl NamespaceRepo for PostgresCatalog {
     async fn create(
         &self,
         name: &str,
-        retention_duration: &str,
+        retention_duration: Option<&str>,
         kafka_topic_id: KafkaTopicId,
         query_pool_id: QueryPoolId,
     ) -> Result<Namespace> {
@@ -187,7 +244,7 @@ RETURNING *
         "#,
         )
         .bind(&name) // $1
-        .bind(&retention_duration) // $2
+        .bind(retention_duration.or_else(|| self.default_retention.as_deref())) // $2
         .bind(kafka_topic_id) // $3
         .bind(query_pool_id) // $4
         .fetch_one(&self.pool)
@@ -225,6 +282,44 @@ SELECT * FROM namespace WHERE name = $1;
 
         Ok(Some(namespace))
     }
+
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Namespace>> {
+        sqlx::query_as::<_, Namespace>(
+            r#"
+SELECT * FROM namespace WHERE kafka_topic_id = $1;
+        "#,
+        )
+        .bind(&kafka_topic_id) // $1
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn summary(&self, namespace_id: NamespaceId) -> Result<NamespaceSummary> {
+        sqlx::query_as::<_, NamespaceSummary>(
+            r#"
+SELECT
+    (SELECT COUNT(*) FROM table_name WHERE namespace_id = $1) AS table_count,
+    (SELECT COUNT(*) FROM column_name
+        INNER JOIN table_name ON table_name.id = column_name.table_id
+        WHERE table_name.namespace_id = $1) AS column_count,
+    files.parquet_file_count,
+    files.total_bytes
+FROM (
+    SELECT
+        COUNT(*) AS parquet_file_count,
+        COALESCE(SUM(parquet_file.file_size_bytes), 0)::BIGINT AS total_bytes
+    FROM parquet_file
+    INNER JOIN table_name ON table_name.id = parquet_file.table_id
+    WHERE table_name.namespace_id = $1 AND parquet_file.to_delete IS NULL
+) AS files;
+        "#,
+        )
+        .bind(&namespace_id) // $1
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
 }
 
 #[async_trait]
@@ -267,6 +362,22 @@ WHERE namespace_id = $1;
 
         Ok(rec)
     }
+
+    async fn list_by_kafka_topic(&self, kafka_topic_id: KafkaTopicId) -> Result<Vec<Table>> {
+        let rec = sqlx::query_as::<_, Table>(
+            r#"
+SELECT table_name.* FROM table_name
+INNER JOIN namespace ON namespace.id = table_name.namespace_id
+WHERE namespace.kafka_topic_id = $1;
+            "#,
+        )
+        .bind(&kafka_topic_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(rec)
+    }
 }
 
 #[async_trait]
@@ -326,6 +437,51 @@ WHERE table_name.namespace_id = $1;
 
         Ok(rec)
     }
+
+    async fn promote_to_float(&self, name: &str, table_id: TableId) -> Result<Column> {
+        let rec = sqlx::query_as::<_, Column>(
+            r#"
+UPDATE column_name
+SET column_type = $3
+WHERE name = $1 AND table_id = $2 AND column_type IN ( $3, $4 )
+RETURNING *;
+        "#,
+        )
+        .bind(&name) // $1
+        .bind(&table_id) // $2
+        .bind(ColumnType::F64 as i16) // $3
+        .bind(ColumnType::I64 as i16) // $4
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        if let Some(rec) = rec {
+            return Ok(rec);
+        }
+
+        // The column either doesn't exist or is of a type that can't be promoted.
+        let existing = sqlx::query_as::<_, Column>(
+            r#"
+SELECT * FROM column_name WHERE name = $1 AND table_id = $2;
+        "#,
+        )
+        .bind(&name) // $1
+        .bind(&table_id) // $2
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .ok_or_else(|| Error::ColumnNotFound {
+            name: name.to_string(),
+        })?;
+
+        Err(Error::ColumnTypeMismatch {
+            name: name.to_string(),
+            existing: ColumnType::try_from(existing.column_type)
+                .map(|t| t.to_string())
+                .unwrap_or_else(|_| existing.column_type.to_string()),
+            new: ColumnType::F64.to_string(),
+        })
+    }
 }
 
 #[async_trait]
@@ -396,6 +552,27 @@ SELECT * FROM sequencer WHERE kafka_topic_id = $1 AND kafka_partition = $2;
             .await
             .map_err(|e| Error::SqlxError { source: e })
     }
+
+    async fn update_min_unpersisted_sequence_number(
+        &self,
+        sequencer_id: SequencerId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE sequencer
+SET min_unpersisted_sequence_number = GREATEST(min_unpersisted_sequence_number, $2)
+WHERE id = $1;
+        "#,
+        )
+        .bind(&sequencer_id) // $1
+        .bind(&sequence_number) // $2
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -503,11 +680,12 @@ impl ParquetFileRepo for PostgresCatalog {
         max_sequence_number: SequenceNumber,
         min_time: Timestamp,
         max_time: Timestamp,
+        file_size_bytes: i64,
     ) -> Result<ParquetFile> {
         let rec = sqlx::query_as::<_, ParquetFile>(
             r#"
-INSERT INTO parquet_file ( sequencer_id, table_id, partition_id, object_store_id, min_sequence_number, max_sequence_number, min_time, max_time, to_delete )
-VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, false )
+INSERT INTO parquet_file ( sequencer_id, table_id, partition_id, object_store_id, min_sequence_number, max_sequence_number, min_time, max_time, file_size_bytes, to_delete )
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, NULL )
 RETURNING *
         "#,
         )
@@ -519,6 +697,7 @@ RETURNING *
             .bind(max_sequence_number) // $6
             .bind(min_time) // $7
             .bind(max_time) // $8
+            .bind(file_size_bytes) // $9
             .fetch_one(&self.pool)
             .await
             .map_err(|e| {
@@ -536,16 +715,43 @@ RETURNING *
         Ok(rec)
     }
 
-    async fn flag_for_delete(&self, id: ParquetFileId) -> Result<()> {
-        let _ = sqlx::query(r#"UPDATE parquet_file SET to_delete = true WHERE id = $1;"#)
-            .bind(&id) // $1
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::SqlxError { source: e })?;
+    async fn mark_for_deletion(&self, object_store_id: Uuid, marked_at: Timestamp) -> Result<()> {
+        let res = sqlx::query(
+            r#"UPDATE parquet_file SET to_delete = COALESCE(to_delete, $2) WHERE object_store_id = $1;"#,
+        )
+        .bind(&object_store_id) // $1
+        .bind(&marked_at) // $2
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        if res.rows_affected() == 0 {
+            return Err(Error::ParquetObjectNotFound { object_store_id });
+        }
 
         Ok(())
     }
 
+    async fn get_by_object_store_id(&self, object_store_id: Uuid) -> Result<Option<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFile>(
+            r#"SELECT * FROM parquet_file WHERE object_store_id = $1;"#,
+        )
+        .bind(&object_store_id) // $1
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_deletable(&self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFile>(
+            r#"SELECT * FROM parquet_file WHERE to_delete < $1 ORDER BY id;"#,
+        )
+        .bind(&older_than) // $1
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn list_by_sequencer_greater_than(
         &self,
         sequencer_id: SequencerId,
@@ -558,6 +764,49 @@ RETURNING *
             .await
             .map_err(|e| Error::SqlxError { source: e })
     }
+
+    async fn list_by_namespace(&self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFile>(
+            r#"
+SELECT parquet_file.*
+FROM parquet_file
+INNER JOIN table_name ON table_name.id = parquet_file.table_id
+WHERE table_name.namespace_id = $1 AND parquet_file.to_delete IS NULL
+ORDER BY parquet_file.id;
+        "#,
+        )
+        .bind(&namespace_id) // $1
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_partition(&self, partition_id: PartitionId) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFile>(
+            r#"SELECT * FROM parquet_file WHERE partition_id = $1 AND to_delete IS NULL ORDER BY id;"#,
+        )
+        .bind(&partition_id) // $1
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn max_persisted_sequence_number(
+        &self,
+        partition_id: PartitionId,
+        sequencer_id: SequencerId,
+    ) -> Result<Option<SequenceNumber>> {
+        let max = sqlx::query_scalar::<_, Option<i64>>(
+            r#"SELECT MAX(max_sequence_number) FROM parquet_file WHERE partition_id = $1 AND sequencer_id = $2;"#,
+        )
+        .bind(&partition_id) // $1
+        .bind(&sequencer_id) // $2
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(max.map(SequenceNumber::new))
+    }
 }
 
 /// The error code returned by Postgres for a unique constraint violation.
@@ -593,6 +842,40 @@ fn is_fk_violation(e: &sqlx::Error) -> bool {
     false
 }
 
+/// Error code returned by Postgres when a referenced table does not exist.
+const PG_UNDEFINED_TABLE: &str = "42P01";
+
+fn is_undefined_table(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(inner) = e {
+        if let Some(code) = inner.code() {
+            if code == PG_UNDEFINED_TABLE {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Error code returned by Postgres when a transaction could not be serialized.
+const PG_SERIALIZATION_FAILURE: &str = "40001";
+
+/// Error code returned by Postgres when a deadlock was detected.
+const PG_DEADLOCK_DETECTED: &str = "40P01";
+
+/// Returns true if `e` is caused by a serialization failure, deadlock or connection problem, so
+/// the operation may succeed when retried.
+pub(crate) fn is_transient(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(inner) => matches!(
+            inner.code().as_deref(),
+            Some(PG_SERIALIZATION_FAILURE | PG_DEADLOCK_DETECTED)
+        ),
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -658,6 +941,75 @@ mod tests {
         crate::interface::test_helpers::test_catalog(postgres).await;
     }
 
+    #[tokio::test]
+    async fn test_default_retention() {
+        maybe_skip_integration!();
+
+        let postgres = setup_db().await.with_default_retention("30days");
+
+        crate::interface::test_helpers::test_namespace_default_retention(
+            Arc::new(postgres),
+            "30days",
+        )
+        .await;
+    }
+
+    #[test]
+    fn test_check_schema_version() {
+        let expected = PostgresCatalog::expected_schema_version();
+
+        check_schema_version(expected, Some(expected)).unwrap();
+
+        let err = check_schema_version(expected, Some(expected - 1)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::SchemaVersionMismatch { expected: e, found: f } if e == expected && f == expected - 1
+        ));
+
+        let err = check_schema_version(expected, None).unwrap_err();
+        assert!(matches!(err, Error::SchemaNotInitialised { expected: e } if e == expected));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        assert!(Error::SqlxError {
+            source: sqlx::Error::Io(io_error)
+        }
+        .is_retryable());
+        assert!(Error::SqlxError {
+            source: sqlx::Error::PoolTimedOut
+        }
+        .is_retryable());
+
+        assert!(!Error::SqlxError {
+            source: sqlx::Error::RowNotFound
+        }
+        .is_retryable());
+        assert!(!Error::FileExists {
+            object_store_id: Uuid::new_v4()
+        }
+        .is_retryable());
+        assert!(!Error::NameExists {
+            name: "foo".to_string()
+        }
+        .is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_schema_version() {
+        maybe_skip_integration!();
+
+        let postgres = setup_db().await;
+        postgres.setup().await.unwrap();
+
+        assert_eq!(
+            postgres.applied_schema_version().await.unwrap(),
+            Some(PostgresCatalog::expected_schema_version())
+        );
+        postgres.check_schema_version().await.unwrap();
+    }
+
     async fn clear_schema(pool: &Pool<Postgres>) {
         sqlx::query("delete from tombstone;")
             .execute(pool)
@@ -17,7 +17,10 @@
 use bytes::Bytes;
 use data_types::server_id::ServerId;
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
-use object_store::{path::Path, GetResult, ObjectStore, ObjectStoreApi, Result};
+use object_store::{
+    path::{ObjectStorePath, Path},
+    GetResult, ObjectStore, ObjectStoreApi, Result,
+};
 use observability_deps::tracing::warn;
 use snafu::{ensure, ResultExt, Snafu};
 use std::sync::Arc;
@@ -67,7 +70,6 @@ impl IoxObjectStore {
         let path = paths::server_config_path(inner, server_id);
         let result = match inner.get(&path).await {
             Err(object_store::Error::NotFound { .. }) => {
-                use object_store::path::ObjectStorePath;
                 let mut legacy_path = inner.new_path();
                 legacy_path.push_dir(server_id.to_string());
                 legacy_path.set_file_name(paths::SERVER_CONFIG_FILE_NAME);
@@ -318,6 +320,34 @@ impl IoxObjectStore {
         self.inner.delete(&full_path).await
     }
 
+    // Export file methods =========================================================================
+
+    /// Store an exported file with the given name, returning its location in object storage
+    pub async fn put_export_file(&self, file_name: &str, bytes: Bytes) -> Result<String> {
+        let path = self.root_path.export_path(file_name);
+        self.inner.put(&path, bytes).await?;
+        Ok(path.to_raw())
+    }
+
+    // Downsample checkpoint methods ==============================================================
+
+    /// Get the downsampling checkpoint with the given name, if it exists
+    pub async fn get_downsample_checkpoint(&self, name: &str) -> Result<Option<Bytes>> {
+        let path = self.root_path.downsample_checkpoint_path(name);
+        match self.inner.get(&path).await {
+            Ok(result) => Ok(Some(result.bytes().await?.into())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store the downsampling checkpoint with the given name, replacing any
+    /// previous checkpoint of that name
+    pub async fn put_downsample_checkpoint(&self, name: &str, bytes: Bytes) -> Result<()> {
+        let path = self.root_path.downsample_checkpoint_path(name);
+        self.inner.put(&path, bytes).await
+    }
+
     // Database rule file methods =================================================================
 
     // Deliberately private; this should not leak outside this crate
@@ -803,4 +833,63 @@ mod tests {
         let alternate = IoxObjectStore::root_path_for(&object_store, uuid).to_string();
         assert_eq!(alternate, saved_root_path);
     }
+
+    #[tokio::test]
+    async fn export_file_round_trips() {
+        let object_store = make_object_store();
+        let uuid = Uuid::new_v4();
+        let iox_object_store = IoxObjectStore::create(Arc::clone(&object_store), uuid)
+            .await
+            .unwrap();
+
+        let content = Bytes::from("exported data");
+        let location = iox_object_store
+            .put_export_file("cpu.arrow", content.clone())
+            .await
+            .unwrap();
+
+        let mut expected_path = object_store.new_path();
+        expected_path.push_all_dirs(&[
+            ALL_DATABASES_DIRECTORY,
+            uuid.to_string().as_str(),
+            "exports",
+        ]);
+        expected_path.set_file_name("cpu.arrow");
+        assert_eq!(location, expected_path.to_raw());
+
+        let actual_content = object_store
+            .get(&expected_path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(content, actual_content);
+    }
+
+    #[tokio::test]
+    async fn downsample_checkpoint_round_trips() {
+        let object_store = make_object_store();
+        let iox_object_store = IoxObjectStore::create(Arc::clone(&object_store), Uuid::new_v4())
+            .await
+            .unwrap();
+
+        let checkpoint = iox_object_store
+            .get_downsample_checkpoint("db.h2o_1m")
+            .await
+            .unwrap();
+        assert!(checkpoint.is_none());
+
+        for content in ["60", "120"] {
+            iox_object_store
+                .put_downsample_checkpoint("db.h2o_1m", Bytes::from(content))
+                .await
+                .unwrap();
+            let checkpoint = iox_object_store
+                .get_downsample_checkpoint("db.h2o_1m")
+                .await
+                .unwrap();
+            assert_eq!(checkpoint, Some(Bytes::from(content)));
+        }
+    }
 }
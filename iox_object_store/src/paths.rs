@@ -75,6 +75,20 @@ impl RootPath {
     pub(crate) fn transactions_path(&self) -> TransactionsPath {
         TransactionsPath::new(self)
     }
+
+    /// Location of an exported file with the given name
+    pub(crate) fn export_path(&self, file_name: &str) -> Path {
+        let mut result = self.join("exports");
+        result.set_file_name(file_name);
+        result
+    }
+
+    /// Location of the downsampling checkpoint with the given name
+    pub(crate) fn downsample_checkpoint_path(&self, name: &str) -> Path {
+        let mut result = self.join("downsample");
+        result.set_file_name(name);
+        result
+    }
 }
 
 impl fmt::Display for RootPath {
@@ -22,14 +22,16 @@ use arrow::record_batch::RecordBatch;
 use hashbrown::HashMap;
 use snafu::{OptionExt, ResultExt, Snafu};
 
+use data_types::partition_metadata::StatValues;
 use data_types::write_summary::TimestampSummary;
 use schema::selection::Selection;
-use schema::{builder::SchemaBuilder, Schema, TIME_COLUMN_NAME};
+use schema::{builder::SchemaBuilder, InfluxColumnType, InfluxFieldType, Schema, TIME_COLUMN_NAME};
 
 use crate::column::{Column, ColumnData};
 
 pub mod column;
 pub mod payload;
+pub mod record_batch;
 pub mod writer;
 
 pub use payload::*;
@@ -52,6 +54,9 @@ pub enum Error {
     #[snafu(display("Column not found: {}", column))]
     ColumnNotFound { column: String },
 
+    #[snafu(display("Column {} is not an integer field", column))]
+    NotIntegerField { column: String },
+
     #[snafu(context(false))]
     WriterError { source: writer::Error },
 }
@@ -85,12 +90,15 @@ impl MutableBatch {
 
     /// Returns the schema for a given selection
     ///
-    /// If Selection::All the returned columns are sorted by name
+    /// If Selection::All or Selection::AllExcept the returned columns are sorted by name
     pub fn schema(&self, selection: Selection<'_>) -> Result<Schema> {
         let mut schema_builder = SchemaBuilder::new();
         let schema = match selection {
-            Selection::All => {
+            Selection::All | Selection::AllExcept(_) => {
                 for (column_name, column_idx) in self.column_names.iter() {
+                    if !selection.contains(column_name) {
+                        continue;
+                    }
                     let column = &self.columns[*column_idx];
                     schema_builder.influx_column(column_name, column.influx_type());
                 }
@@ -159,6 +167,63 @@ impl MutableBatch {
         Some(summary)
     }
 
+    /// Replaces every timestamp later than `max` with `max`, returning the
+    /// number of rows changed
+    pub fn clamp_timestamps(&mut self, max: i64) -> usize {
+        let time = match self.column_names.get(TIME_COLUMN_NAME) {
+            Some(time) => *time,
+            None => return 0,
+        };
+
+        match &mut self.columns[time].data {
+            ColumnData::I64(col_data, stats) => {
+                let mut clamped = 0;
+                for t in col_data.iter_mut().filter(|t| **t > max) {
+                    *t = max;
+                    clamped += 1;
+                }
+
+                if clamped > 0 {
+                    stats.min = stats.min.map(|min| min.min(max));
+                    stats.max = stats.max.map(|v| v.min(max));
+                    stats.distinct_count = None;
+                }
+                clamped
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Converts the integer field `column` into a float field, returning an error if no
+    /// integer field named `column` exists
+    pub fn coerce_integer_to_float(&mut self, column: &str) -> Result<()> {
+        let idx = *self
+            .column_names
+            .get(column)
+            .context(ColumnNotFoundSnafu { column })?;
+
+        let col = &mut self.columns[idx];
+        let data = match (col.influx_type, &col.data) {
+            (InfluxColumnType::Field(InfluxFieldType::Integer), ColumnData::I64(values, stats)) => {
+                ColumnData::F64(
+                    values.iter().map(|v| *v as f64).collect(),
+                    StatValues {
+                        min: stats.min.map(|v| v as f64),
+                        max: stats.max.map(|v| v as f64),
+                        total_count: stats.total_count,
+                        null_count: stats.null_count,
+                        distinct_count: stats.distinct_count,
+                    },
+                )
+            }
+            _ => return NotIntegerFieldSnafu { column }.fail(),
+        };
+
+        col.influx_type = InfluxColumnType::Field(InfluxFieldType::Float);
+        col.data = data;
+        Ok(())
+    }
+
     /// Extend this [`MutableBatch`] with the contents of `other`
     pub fn extend_from(&mut self, other: &Self) -> Result<()> {
         let mut writer = writer::Writer::new(self, other.row_count);
@@ -0,0 +1,277 @@
+//! Code to write an arrow [`RecordBatch`] to a [`MutableBatch`]
+
+use arrow::{
+    array::{
+        Array, BooleanArray, DictionaryArray, Float64Array, Int64Array, StringArray,
+        TimestampNanosecondArray, UInt64Array,
+    },
+    datatypes::{DataType, Int32Type},
+    record_batch::RecordBatch,
+};
+use arrow_util::bitset::BitSet;
+use schema::{InfluxColumnType, InfluxFieldType, Schema, TIME_COLUMN_NAME};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{writer::Writer, MutableBatch};
+
+/// Error type for [`RecordBatch`] conversion
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("error writing column {}: {}", column, source))]
+    Write {
+        source: crate::writer::Error,
+        column: String,
+    },
+
+    #[snafu(display("invalid record batch schema: {}", source))]
+    InvalidSchema { source: schema::Error },
+
+    #[snafu(display("record batch must contain time column"))]
+    MissingTime,
+
+    #[snafu(display("time column must not contain nulls"))]
+    NullTime,
+
+    #[snafu(display("cannot write column {} of type {}", column, data_type))]
+    UnsupportedType { column: String, data_type: DataType },
+}
+
+/// Result type for [`RecordBatch`] conversion
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Writes the provided [`RecordBatch`] to a [`MutableBatch`], on error any
+/// changes made to `batch` are reverted
+///
+/// The InfluxDB column type of each column is read from the IOx schema
+/// metadata of the record batch, if present. Otherwise a dictionary encoded
+/// string column is written as a tag, the column named [`TIME_COLUMN_NAME`] as
+/// the timestamp, and all other columns as fields of the corresponding type
+pub fn write_record_batch(batch: &mut MutableBatch, record_batch: &RecordBatch) -> Result<()> {
+    let to_insert = record_batch.num_rows();
+    if to_insert == 0 {
+        return Ok(());
+    }
+
+    // Validates column names are unique and any metadata matches the data type
+    let schema = Schema::try_from(record_batch.schema()).context(InvalidSchemaSnafu)?;
+    ensure!(
+        schema.find_index_of(TIME_COLUMN_NAME).is_some(),
+        MissingTimeSnafu
+    );
+
+    let mut writer = Writer::new(batch, to_insert);
+    for (idx, array) in record_batch.columns().iter().enumerate() {
+        let (influx_type, field) = schema.field(idx);
+        let column = field.name().as_str();
+        let influx_type = influx_type
+            .or_else(|| infer_column_type(column, field.data_type()))
+            .filter(|influx_type| influx_type.valid_arrow_type(field.data_type()))
+            .context(UnsupportedTypeSnafu {
+                column,
+                data_type: field.data_type().clone(),
+            })?;
+
+        let valid_mask = compute_valid_mask(array.as_ref());
+        let valid_mask = valid_mask.as_ref().map(|mask| mask.bytes());
+
+        match influx_type {
+            InfluxColumnType::Field(InfluxFieldType::Float) => {
+                let array = downcast::<Float64Array>(array.as_ref());
+                writer.write_f64(column, valid_mask, array.iter().flatten())
+            }
+            InfluxColumnType::Field(InfluxFieldType::Integer) => {
+                let array = downcast::<Int64Array>(array.as_ref());
+                writer.write_i64(column, valid_mask, array.iter().flatten())
+            }
+            InfluxColumnType::Field(InfluxFieldType::UInteger) => {
+                let array = downcast::<UInt64Array>(array.as_ref());
+                writer.write_u64(column, valid_mask, array.iter().flatten())
+            }
+            InfluxColumnType::Field(InfluxFieldType::Boolean) => {
+                let array = downcast::<BooleanArray>(array.as_ref());
+                writer.write_bool(column, valid_mask, array.iter().flatten())
+            }
+            InfluxColumnType::Field(InfluxFieldType::String) => {
+                let array = downcast::<StringArray>(array.as_ref());
+                writer.write_string(column, valid_mask, array.iter().flatten())
+            }
+            InfluxColumnType::Tag => match array.data_type() {
+                DataType::Utf8 => {
+                    let array = downcast::<StringArray>(array.as_ref());
+                    writer.write_tag(column, valid_mask, array.iter().flatten())
+                }
+                _ => {
+                    let array = downcast::<DictionaryArray<Int32Type>>(array.as_ref());
+                    let values = downcast::<StringArray>(array.values().as_ref());
+                    writer.write_tag_dict(
+                        column,
+                        valid_mask,
+                        array.keys().iter().flatten().map(|key| key as usize),
+                        (0..values.len()).map(|idx| values.value(idx)),
+                    )
+                }
+            },
+            InfluxColumnType::Timestamp => {
+                ensure!(valid_mask.is_none(), NullTimeSnafu);
+                let array = downcast::<TimestampNanosecondArray>(array.as_ref());
+                writer.write_time(column, array.values().iter().cloned())
+            }
+        }
+        .context(WriteSnafu { column })?;
+    }
+
+    writer.commit();
+    Ok(())
+}
+
+/// Infers the InfluxDB column type of a column without IOx schema metadata
+fn infer_column_type(column: &str, data_type: &DataType) -> Option<InfluxColumnType> {
+    match data_type {
+        _ if column == TIME_COLUMN_NAME => Some(InfluxColumnType::Timestamp),
+        DataType::Dictionary(_, _) => Some(InfluxColumnType::Tag),
+        data_type => InfluxFieldType::try_from(data_type.clone())
+            .ok()
+            .map(InfluxColumnType::Field),
+    }
+}
+
+/// Returns a mask with a bit set for every non-null row of `array`, or `None`
+/// if `array` contains no nulls
+fn compute_valid_mask(array: &dyn Array) -> Option<BitSet> {
+    if array.null_count() == 0 {
+        return None;
+    }
+
+    let mut mask = BitSet::with_size(array.len());
+    (0..array.len())
+        .filter(|idx| array.is_valid(*idx))
+        .for_each(|idx| mask.set(idx));
+    Some(mask)
+}
+
+/// Downcasts `array`, whose data type has already been validated
+fn downcast<T: 'static>(array: &dyn Array) -> &T {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .expect("data type validated against column type")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::ArrayRef;
+    use arrow_util::assert_batches_eq;
+    use schema::selection::Selection;
+
+    use super::*;
+
+    #[test]
+    fn test_write_record_batch() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "tag",
+                Arc::new(
+                    vec![Some("a"), None, Some("b")]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ) as ArrayRef,
+            ),
+            (
+                "f64",
+                Arc::new(Float64Array::from(vec![Some(1.5), Some(2.5), None])),
+            ),
+            (
+                "i64",
+                Arc::new(Int64Array::from(vec![None, Some(-1), Some(2)])),
+            ),
+            (
+                "u64",
+                Arc::new(UInt64Array::from(vec![Some(1), None, Some(3)])),
+            ),
+            (
+                "bool",
+                Arc::new(BooleanArray::from(vec![true, false, true])),
+            ),
+            ("str", Arc::new(StringArray::from(vec!["x", "y", "z"]))),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_vec(vec![1, 2, 3], None)),
+            ),
+        ])
+        .unwrap();
+
+        let mut mb = MutableBatch::new();
+        write_record_batch(&mut mb, &batch).unwrap();
+
+        let schema = mb.schema(Selection::All).unwrap();
+        assert_eq!(
+            schema.field(schema.find_index_of("tag").unwrap()).0,
+            Some(InfluxColumnType::Tag)
+        );
+
+        let expected = vec![
+            "+-------+-----+-----+-----+-----+--------------------------------+-----+",
+            "| bool  | f64 | i64 | str | tag | time                           | u64 |",
+            "+-------+-----+-----+-----+-----+--------------------------------+-----+",
+            "| true  | 1.5 |     | x   | a   | 1970-01-01T00:00:00.000000001Z | 1   |",
+            "| false | 2.5 | -1  | y   |     | 1970-01-01T00:00:00.000000002Z |     |",
+            "| true  |     | 2   | z   | b   | 1970-01-01T00:00:00.000000003Z | 3   |",
+            "+-------+-----+-----+-----+-----+--------------------------------+-----+",
+        ];
+        assert_batches_eq!(expected, &[mb.to_arrow(Selection::All).unwrap()]);
+    }
+
+    #[test]
+    fn test_write_record_batch_errors() {
+        // no time column
+        let batch = RecordBatch::try_from_iter(vec![(
+            "f64",
+            Arc::new(Float64Array::from(vec![1.0])) as ArrayRef,
+        )])
+        .unwrap();
+        let mut mb = MutableBatch::new();
+        let err = write_record_batch(&mut mb, &batch).unwrap_err();
+        assert!(matches!(err, Error::MissingTime));
+
+        // unsupported type
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "f32",
+                Arc::new(arrow::array::Float32Array::from(vec![1.0])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_vec(vec![1], None)),
+            ),
+        ])
+        .unwrap();
+        let err = write_record_batch(&mut mb, &batch).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedType { .. }));
+
+        // conflicting type with existing data is reverted
+        let batch = RecordBatch::try_from_iter(vec![
+            ("f64", Arc::new(Float64Array::from(vec![1.0])) as ArrayRef),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_vec(vec![1], None)),
+            ),
+        ])
+        .unwrap();
+        write_record_batch(&mut mb, &batch).unwrap();
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("f64", Arc::new(Int64Array::from(vec![1])) as ArrayRef),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_vec(vec![2], None)),
+            ),
+        ])
+        .unwrap();
+        let err = write_record_batch(&mut mb, &batch).unwrap_err();
+        assert!(matches!(err, Error::Write { .. }));
+        assert_eq!(mb.rows(), 1);
+    }
+}
@@ -23,6 +23,9 @@ pub enum Error {
 
     #[snafu(display("Key not found in dictionary: {}", key))]
     KeyNotFound { key: usize },
+
+    #[snafu(display("Column \"{}\" was written more than once", name))]
+    DuplicateColumn { name: String },
 }
 
 /// A specialized `Error` for [`Writer`] errors
@@ -72,10 +75,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_f64<I>(
         &mut self,
         name: &str,
@@ -117,10 +117,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_i64<I>(
         &mut self,
         name: &str,
@@ -162,10 +159,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_u64<I>(
         &mut self,
         name: &str,
@@ -207,10 +201,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_bool<I>(
         &mut self,
         name: &str,
@@ -254,10 +245,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_string<'s, I>(
         &mut self,
         name: &str,
@@ -300,10 +288,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_tag<'s, I>(
         &mut self,
         name: &str,
@@ -345,10 +330,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_tag_dict<'s, K, V>(
         &mut self,
         name: &str,
@@ -405,10 +387,7 @@ impl<'a> Writer<'a> {
     /// For each set bit in `valid_mask` an a value from `values` is inserted at the
     /// corresponding index in the column. Nulls are inserted for the other rows
     ///
-    /// # Panic
-    ///
-    /// - panics if this column has already been written to by this `Writer`
-    ///
+    /// Returns an error if this column has already been written to by this `Writer`
     pub fn write_time<I>(&mut self, name: &str, mut values: I) -> Result<()>
     where
         I: Iterator<Item = i64>,
@@ -628,6 +607,13 @@ impl<'a> Writer<'a> {
 
         let col = &mut self.batch.columns[column_idx];
 
+        // Every write to a column extends it past the initial rows
+        if col.valid.len() != self.initial_rows {
+            return Err(Error::DuplicateColumn {
+                name: name.to_string(),
+            });
+        }
+
         if col.influx_type != influx_type {
             return Err(Error::TypeMismatch {
                 existing: col.influx_type,
@@ -635,16 +621,6 @@ impl<'a> Writer<'a> {
             });
         }
 
-        assert_eq!(
-            col.valid.len(),
-            self.initial_rows,
-            "expected {} rows in column \"{}\" got {} when performing write of {} rows",
-            self.initial_rows,
-            name,
-            col.valid.len(),
-            self.to_insert
-        );
-
         Ok((column_idx, col))
     }
 
@@ -0,0 +1,34 @@
+use data_types::partition_metadata::{StatValues, Statistics};
+use mutable_batch::writer::Writer;
+use mutable_batch::MutableBatch;
+use schema::TIME_COLUMN_NAME;
+
+#[test]
+fn test_clamp_timestamps() {
+    let mut batch = MutableBatch::new();
+    let mut writer = Writer::new(&mut batch, 4);
+    writer
+        .write_f64("f64", None, vec![1., 2., 3., 4.].into_iter())
+        .unwrap();
+    writer
+        .write_time(TIME_COLUMN_NAME, vec![5, 20, 10, 30].into_iter())
+        .unwrap();
+    writer.commit();
+
+    assert_eq!(batch.clamp_timestamps(30), 0);
+    assert_eq!(batch.clamp_timestamps(10), 2);
+
+    let time = batch.column(TIME_COLUMN_NAME).unwrap();
+    assert_eq!(
+        time.stats(),
+        Statistics::I64(StatValues::new(Some(5), Some(10), 4, 0))
+    );
+
+    let summary = batch.timestamp_summary().unwrap();
+    assert_eq!(summary.stats.max, Some(10));
+    assert_eq!(batch.rows(), 4);
+
+    // a batch without a time column is left unchanged
+    let mut batch = MutableBatch::new();
+    assert_eq!(batch.clamp_timestamps(0), 0);
+}
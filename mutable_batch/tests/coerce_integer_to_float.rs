@@ -0,0 +1,75 @@
+use arrow_util::assert_batches_eq;
+use data_types::partition_metadata::{StatValues, Statistics};
+use mutable_batch::writer::Writer;
+use mutable_batch::{Error, MutableBatch};
+use schema::selection::Selection;
+use schema::{InfluxColumnType, InfluxFieldType, TIME_COLUMN_NAME};
+
+#[test]
+fn test_coerce_integer_to_float() {
+    let mut batch = MutableBatch::new();
+    let mut writer = Writer::new(&mut batch, 3);
+    writer
+        .write_i64("i64", Some(&[0b00000101]), vec![1, 3].into_iter())
+        .unwrap();
+    writer
+        .write_u64("u64", None, vec![1, 2, 3].into_iter())
+        .unwrap();
+    writer
+        .write_time(TIME_COLUMN_NAME, vec![1, 2, 3].into_iter())
+        .unwrap();
+    writer.commit();
+
+    // only integer fields are coerced
+    assert!(matches!(
+        batch.coerce_integer_to_float("u64"),
+        Err(Error::NotIntegerField { .. })
+    ));
+    assert!(matches!(
+        batch.coerce_integer_to_float(TIME_COLUMN_NAME),
+        Err(Error::NotIntegerField { .. })
+    ));
+    assert!(matches!(
+        batch.coerce_integer_to_float("missing"),
+        Err(Error::ColumnNotFound { .. })
+    ));
+
+    batch.coerce_integer_to_float("i64").unwrap();
+    let column = batch.column("i64").unwrap();
+    assert_eq!(
+        column.influx_type(),
+        InfluxColumnType::Field(InfluxFieldType::Float)
+    );
+    assert_eq!(
+        column.stats(),
+        Statistics::F64(StatValues::new(Some(1.), Some(3.), 3, 1))
+    );
+
+    let expected = vec![
+        "+-----+--------------------------------+-----+",
+        "| i64 | time                           | u64 |",
+        "+-----+--------------------------------+-----+",
+        "| 1   | 1970-01-01T00:00:00.000000001Z | 1   |",
+        "|     | 1970-01-01T00:00:00.000000002Z | 2   |",
+        "| 3   | 1970-01-01T00:00:00.000000003Z | 3   |",
+        "+-----+--------------------------------+-----+",
+    ];
+    assert_batches_eq!(expected, &[batch.to_arrow(Selection::All).unwrap()]);
+
+    // the coerced column accepts float values
+    let mut writer = Writer::new(&mut batch, 1);
+    writer
+        .write_f64("i64", None, vec![1.5].into_iter())
+        .unwrap();
+    writer
+        .write_time(TIME_COLUMN_NAME, vec![4].into_iter())
+        .unwrap();
+    writer.commit();
+    assert_eq!(batch.rows(), 4);
+
+    // a coerced column is no longer an integer field
+    assert!(matches!(
+        batch.coerce_integer_to_float("i64"),
+        Err(Error::NotIntegerField { .. })
+    ));
+}
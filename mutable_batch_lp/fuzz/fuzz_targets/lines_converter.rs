@@ -0,0 +1,30 @@
+//! Feeds arbitrary input through the line protocol conversion performed by
+//! the router's write path, which must reject malformed input with an error
+//! rather than panic.
+//!
+//! Run with `cargo +nightly fuzz run lines_converter` from `mutable_batch_lp`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mutable_batch_lp::LinesConverter;
+
+/// The timestamp bases of the precisions accepted by the router
+const TIMESTAMP_BASES: [i64; 4] = [1, 1_000, 1_000_000, 1_000_000_000];
+
+fuzz_target!(|data: &[u8]| {
+    let (precision, body) = match data.split_first() {
+        Some(v) => v,
+        None => return,
+    };
+
+    // The router rejects bodies that are not valid UTF-8 before parsing
+    let body = match std::str::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return,
+    };
+
+    let mut converter = LinesConverter::new(42);
+    converter.set_timestamp_base(TIMESTAMP_BASES[*precision as usize % TIMESTAMP_BASES.len()]);
+    let _ = converter.write_lp(body).and_then(|_| converter.finish());
+});
@@ -15,7 +15,7 @@ use hashbrown::HashMap;
 use influxdb_line_protocol::{parse_lines, FieldValue, ParsedLine};
 use mutable_batch::writer::Writer;
 use mutable_batch::MutableBatch;
-use snafu::{ResultExt, Snafu};
+use snafu::{OptionExt, ResultExt, Snafu};
 
 /// Error type for line protocol conversion
 #[derive(Debug, Snafu)]
@@ -33,8 +33,18 @@ pub enum Error {
         line: usize,
     },
 
+    #[snafu(display("timestamp overflows i64 nanoseconds on line {}", line))]
+    TimestampOverflow { line: usize },
+
     #[snafu(display("empty write payload"))]
     EmptyPayload,
+
+    #[snafu(display(
+        "write payload contains {} lines, more than the maximum of {}",
+        lines,
+        max
+    ))]
+    TooManyLines { lines: usize, max: usize },
 }
 
 /// Result type for line protocol conversion
@@ -56,6 +66,8 @@ pub struct LinesConverter {
     default_time: i64,
     /// The multiplier to convert input timestamps to nanoseconds
     timestamp_base: i64,
+    /// The maximum number of lines accepted, if any
+    max_lines: Option<usize>,
     /// The statistics
     stats: PayloadStatistics,
     /// The current batches
@@ -68,6 +80,7 @@ impl LinesConverter {
         Self {
             default_time,
             timestamp_base: 1,
+            max_lines: None,
             stats: Default::default(),
             batches: Default::default(),
         }
@@ -78,13 +91,31 @@ impl LinesConverter {
         self.timestamp_base = timestamp_base
     }
 
+    /// Sets the maximum number of lines accepted across all calls to
+    /// [`write_lp`](Self::write_lp), returning [`Error::TooManyLines`] once a
+    /// line beyond `max_lines` is parsed. The remaining lines of the payload
+    /// are then only counted, not converted.
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        self.max_lines = Some(max_lines)
+    }
+
     /// Write some line protocol data
     pub fn write_lp(&mut self, lines: &str) -> Result<()> {
-        for (line_idx, maybe_line) in parse_lines(lines).enumerate() {
+        let mut parsed = parse_lines(lines).enumerate();
+        while let Some((line_idx, maybe_line)) = parsed.next() {
             let mut line = maybe_line.context(LineProtocolSnafu { line: line_idx + 1 })?;
 
+            if let Some(max) = self.max_lines {
+                if self.stats.num_lines >= max {
+                    let lines = self.stats.num_lines + 1 + parsed.count();
+                    return TooManyLinesSnafu { lines, max }.fail();
+                }
+            }
+
             if let Some(t) = line.timestamp.as_mut() {
-                *t *= self.timestamp_base
+                *t = t
+                    .checked_mul(self.timestamp_base)
+                    .context(TimestampOverflowSnafu { line: line_idx + 1 })?;
             }
 
             self.stats.num_lines += 1;
@@ -228,4 +259,109 @@ mod tests {
             &[batch["mem"].to_arrow(Selection::All).unwrap()]
         );
     }
+    #[test]
+    fn test_malformed_input() {
+        // Inputs found to be problematic for the parser, none of which may
+        // cause a panic
+        let cases = [
+            // unterminated quotes
+            r#"m f="unterminated"#,
+            r#"m f="unterminated\" 1"#,
+            "m f=\"a\nm f=1",
+            // huge exponents and out of range numbers
+            "m f=1e+99999999999999999999",
+            "m f=-1.5E-99999999999999999999",
+            "m f=99999999999999999999i",
+            "m f=-1u",
+            "m f=1 99999999999999999999",
+            // embedded nulls
+            "m\0,t=\0 f=\"\0\" 1",
+            "\0",
+            "m f=1\0",
+            // trailing backslashes and dangling escapes
+            "m\\",
+            "m,t=v\\ f=1",
+            "m f\\=1",
+            // truncated lines
+            "m",
+            "m,",
+            "m,t",
+            "m,t=",
+            "m f",
+            "m f=",
+            "m f=1 ",
+            "m f=1 -",
+            // repeated columns within a line
+            "m f=1,f=2",
+            "m,t=a,t=b f=1",
+            "m,t=a t=1",
+            "m time=1",
+            "m,time=a f=1",
+            // multi-byte characters
+            "m,\u{1F600}=\u{1F600} \u{1F600}=\"\u{1F600}\" 1",
+            "m\\\u{e9} f=1",
+        ];
+
+        for input in cases {
+            for timestamp_base in [1, 1_000, 1_000_000, 1_000_000_000] {
+                let mut converter = LinesConverter::new(5);
+                converter.set_timestamp_base(timestamp_base);
+                let _ = converter.write_lp(input).and_then(|_| converter.finish());
+            }
+        }
+
+        let err = lines_to_batches("m f=1,f=2", 5).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Write {
+                    source: mutable_batch::writer::Error::DuplicateColumn { .. },
+                    line: 1
+                }
+            ),
+            "{}",
+            err
+        );
+
+        let mut converter = LinesConverter::new(5);
+        converter.set_timestamp_base(1_000_000_000);
+        let err = converter
+            .write_lp("m f=1 1\nm f=1 9223372036854775807")
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::TimestampOverflow { line: 2 }),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_max_lines() {
+        let mut converter = LinesConverter::new(5);
+        converter.set_max_lines(3);
+        converter.write_lp("m f=1 1\nm f=2 2").unwrap();
+        converter.write_lp("m f=3 3").unwrap();
+
+        // the limit applies across calls
+        let err = converter.write_lp("m f=4 4").unwrap_err();
+        assert!(
+            matches!(err, Error::TooManyLines { lines: 4, max: 3 }),
+            "{}",
+            err
+        );
+        assert_eq!(
+            err.to_string(),
+            "write payload contains 4 lines, more than the maximum of 3"
+        );
+
+        // all lines of the payload are counted
+        let mut converter = LinesConverter::new(5);
+        converter.set_max_lines(1);
+        let err = converter.write_lp("m f=1 1\nm f=2 2\nm f=3 3").unwrap_err();
+        assert!(
+            matches!(err, Error::TooManyLines { lines: 3, max: 1 }),
+            "{}",
+            err
+        );
+    }
 }
@@ -72,17 +72,31 @@ impl ChunkSnapshot {
                     .schema
                     .compute_select_indicies(columns)
                     .context(SelectColumnsSnafu)?;
-                let schema = self.schema.select_by_indices(&projection).into();
-                let columns = projection
-                    .into_iter()
-                    .map(|x| Arc::clone(self.batch.column(x)))
+                self.project(projection)
+            }
+            Selection::AllExcept(_) => {
+                let projection = self
+                    .schema
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, (_, field))| selection.contains(field.name()).then(|| idx))
                     .collect();
-
-                RecordBatch::try_new(schema, columns).expect("failed to project record batch")
+                self.project(projection)
             }
         })
     }
 
+    /// Returns a RecordBatch with the columns at the given indices
+    fn project(&self, projection: Vec<usize>) -> RecordBatch {
+        let schema = self.schema.select_by_indices(&projection).into();
+        let columns = projection
+            .into_iter()
+            .map(|x| Arc::clone(self.batch.column(x)))
+            .collect();
+
+        RecordBatch::try_new(schema, columns).expect("failed to project record batch")
+    }
+
     /// Returns a given selection of column names from a table
     pub fn column_names(&self, selection: Selection<'_>) -> Option<BTreeSet<String>> {
         let fields = self.schema.inner().fields().iter();
@@ -98,6 +112,10 @@ impl ChunkSnapshot {
                 })
                 .collect(),
             Selection::All => fields.map(|x| x.name().clone()).collect(),
+            Selection::AllExcept(cols) => fields
+                .filter(|x| !cols.contains(&x.name().as_str()))
+                .map(|x| x.name().clone())
+                .collect(),
         })
     }
 
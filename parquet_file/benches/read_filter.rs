@@ -0,0 +1,131 @@
+//! Benchmarks reading a wide parquet file with and without a column
+//! projection.
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, Float64Array, TimestampNanosecondArray},
+    record_batch::RecordBatch,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use data_types::chunk_metadata::{ChunkAddr, ChunkId, ChunkOrder};
+use datafusion_util::MemoryStream;
+use parquet_file::{
+    metadata::IoxMetadataOld,
+    storage::Storage,
+    test_utils::{create_partition_and_database_checkpoint, make_iox_object_store},
+};
+use predicate::predicate::Predicate;
+use schema::{builder::SchemaBuilder, selection::Selection, InfluxFieldType, TIME_COLUMN_NAME};
+use time::Time;
+use tokio::runtime::Runtime;
+
+/// The number of field columns in the generated file, in addition to the
+/// time column.
+const N_FIELDS: usize = 99;
+const N_ROWS: usize = 10_000;
+
+fn read_filter(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let field_names = (0..N_FIELDS)
+        .map(|i| format!("field_{:02}", i))
+        .collect::<Vec<_>>();
+
+    let mut schema_builder = SchemaBuilder::new();
+    for name in &field_names {
+        schema_builder.influx_field(name, InfluxFieldType::Float);
+    }
+    let schema = schema_builder.timestamp().build().unwrap();
+
+    let columns = field_names
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            Arc::new(Float64Array::from_iter_values(
+                (0..N_ROWS).map(|row| (row * i) as f64),
+            )) as ArrayRef
+        })
+        .chain(std::iter::once(
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                (0..N_ROWS).map(|row| row as i64),
+            )) as ArrayRef,
+        ))
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_new(schema.as_arrow(), columns).unwrap();
+
+    let (path, store, parquet_metadata) = rt.block_on(async {
+        let store = make_iox_object_store().await;
+        let table_name: Arc<str> = Arc::from("table1");
+        let partition_key: Arc<str> = Arc::from("part1");
+        let (partition_checkpoint, database_checkpoint) = create_partition_and_database_checkpoint(
+            Arc::clone(&table_name),
+            Arc::clone(&partition_key),
+        );
+        let metadata = IoxMetadataOld {
+            creation_timestamp: Time::from_timestamp_nanos(0),
+            table_name: Arc::clone(&table_name),
+            partition_key: Arc::clone(&partition_key),
+            chunk_id: ChunkId::new_test(1),
+            partition_checkpoint,
+            database_checkpoint,
+            time_of_first_write: Time::from_timestamp_nanos(0),
+            time_of_last_write: Time::from_timestamp_nanos(N_ROWS as _),
+            chunk_order: ChunkOrder::new(1).unwrap(),
+        };
+        let chunk_addr = ChunkAddr {
+            db_name: Arc::from("db1"),
+            table_name,
+            partition_key,
+            chunk_id: ChunkId::new_test(1),
+        };
+
+        let stream = Box::pin(MemoryStream::new_with_schema(
+            vec![batch],
+            schema.as_arrow(),
+        ));
+        let (path, _file_size_bytes, parquet_metadata) = Storage::new(Arc::clone(&store))
+            .write_to_object_store(chunk_addr, stream, metadata)
+            .await
+            .unwrap()
+            .unwrap();
+
+        (path, store, parquet_metadata)
+    });
+    let parquet_metadata = parquet_metadata.decode().unwrap();
+
+    let two_columns = [field_names[0].as_str(), TIME_COLUMN_NAME];
+    let selections = [
+        ("all_columns", Selection::All),
+        ("two_columns", Selection::Some(&two_columns)),
+    ];
+
+    let mut group = c.benchmark_group("parquet_read_filter");
+    for (name, selection) in selections {
+        // Report throughput in terms of the (compressed) column chunk bytes
+        // that must be read to decode the selection.
+        let bytes = parquet_metadata.column_chunk_bytes(selection);
+        group.throughput(Throughput::Bytes(bytes as u64));
+
+        group.bench_function(name, |b| {
+            b.to_async(&rt).iter(|| async {
+                let stream = Storage::read_filter(
+                    &Predicate::default(),
+                    selection,
+                    schema.as_arrow(),
+                    path.clone(),
+                    Arc::clone(&store),
+                    None,
+                )
+                .unwrap();
+                let batches = datafusion::physical_plan::common::collect(stream)
+                    .await
+                    .unwrap();
+                assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), N_ROWS);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read_filter);
+criterion_main!(benches);
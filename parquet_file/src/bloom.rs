@@ -0,0 +1,425 @@
+//! Bloom filters over tag values, used to prune chunks for equality
+//! predicates that min/max statistics cannot rule out.
+use std::{
+    collections::{BTreeMap, HashSet},
+    hash::Hasher,
+    mem,
+};
+
+use arrow::{
+    array::{Array, DictionaryArray, StringArray},
+    compute::cast,
+    datatypes::{DataType, Int32Type},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    logical_plan::{Expr, Operator},
+    scalar::ScalarValue,
+};
+use generated_types::influxdata::iox::preserved_catalog::v1 as preserved_catalog;
+use predicate::predicate::Predicate;
+use prost::Message;
+use schema::Schema;
+use siphasher::sip128::{Hasher128, SipHasher13};
+use snafu::{ResultExt, Snafu};
+
+/// Target false positive rate of the bloom filters built on persist.
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Cannot read values of tag column '{}': {}", column_name, source))]
+    ReadingTagValues {
+        column_name: String,
+        source: ArrowError,
+    },
+
+    #[snafu(display("Cannot decode tag bloom filters: {}", source))]
+    DecodingTagBloomFilters { source: prost::DecodeError },
+
+    #[snafu(display("Bloom filter for tag column '{}' is empty", column_name))]
+    EmptyBloomFilter { column_name: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The 128-bit SipHash of a value, from which its bit indexes in a filter are derived.
+type ValueHash = (u64, u64);
+
+fn hash_value(value: &str) -> ValueHash {
+    let mut hasher = SipHasher13::new();
+    hasher.write(value.as_bytes());
+    let hash = hasher.finish128();
+    (hash.h1, hash.h2)
+}
+
+/// A bloom filter over string values.
+///
+/// Values are hashed with a fixed-key SipHash so that filters stay valid
+/// across processes and versions once persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    num_hashes: u32,
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for `num_values` distinct values.
+    pub fn with_capacity(num_values: usize) -> Self {
+        let num_values = num_values.max(1) as f64;
+        let num_bits =
+            (-num_values * FALSE_POSITIVE_RATE.ln() / (2f64.ln() * 2f64.ln())).ceil() as usize;
+        let num_words = ((num_bits + 63) / 64).max(1);
+        let num_hashes = ((num_words * 64) as f64 / num_values * 2f64.ln()).round() as u32;
+
+        Self {
+            num_hashes: num_hashes.clamp(1, 16),
+            bits: vec![0; num_words],
+        }
+    }
+
+    /// Add `value` to the filter.
+    pub fn insert(&mut self, value: &str) {
+        self.insert_hash(hash_value(value))
+    }
+
+    fn insert_hash(&mut self, hash: ValueHash) {
+        for idx in self.bit_indexes(hash).collect::<Vec<_>>() {
+            self.bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+
+    /// Returns false if `value` was definitely never inserted, and true if
+    /// it may have been.
+    pub fn may_contain(&self, value: &str) -> bool {
+        self.bit_indexes(hash_value(value))
+            .all(|idx| self.bits[idx / 64] & (1 << (idx % 64)) != 0)
+    }
+
+    fn bit_indexes(&self, (h1, h2): ValueHash) -> impl Iterator<Item = usize> {
+        let num_bits = (self.bits.len() * 64) as u64;
+        (0..self.num_hashes as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    /// In-memory size in bytes, including `self`.
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.bits.capacity() * mem::size_of::<u64>()
+    }
+}
+
+/// Bloom filters over the values of each tag column of a chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagBloomFilters {
+    filters: BTreeMap<String, BloomFilter>,
+}
+
+impl TagBloomFilters {
+    /// Build filters for all tag columns in `schema` from the data in
+    /// `batches`.
+    pub fn try_new(schema: &Schema, batches: &[RecordBatch]) -> Result<Self> {
+        let mut builder = TagBloomFiltersBuilder::new(schema);
+        for batch in batches {
+            builder.push(batch)?;
+        }
+        Ok(builder.build())
+    }
+
+    /// Returns the filter for the tag column `column_name`, if any.
+    pub fn get(&self, column_name: &str) -> Option<&BloomFilter> {
+        self.filters.get(column_name)
+    }
+
+    /// Returns false if the filters show that no row can match `predicate`,
+    /// and true otherwise.
+    ///
+    /// Only `tag = 'value'` expressions, possibly combined with `AND`, are
+    /// considered.
+    pub fn may_match(&self, predicate: &Predicate) -> bool {
+        predicate.exprs.iter().all(|expr| self.may_match_expr(expr))
+    }
+
+    fn may_match_expr(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::BinaryExpr {
+                left,
+                op: Operator::And,
+                right,
+            } => self.may_match_expr(left) && self.may_match_expr(right),
+            Expr::BinaryExpr {
+                left,
+                op: Operator::Eq,
+                right,
+            } => match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(c), Expr::Literal(ScalarValue::Utf8(Some(value))))
+                | (Expr::Literal(ScalarValue::Utf8(Some(value))), Expr::Column(c)) => self
+                    .get(&c.name)
+                    .map(|filter| filter.may_contain(value))
+                    .unwrap_or(true),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    /// In-memory size in bytes, including `self`.
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .filters
+                .iter()
+                .map(|(k, v)| k.capacity() + v.size())
+                .sum::<usize>()
+    }
+
+    /// Read from protobuf message
+    pub(crate) fn from_protobuf(data: &[u8]) -> Result<Self> {
+        let proto_msg = preserved_catalog::TagBloomFilters::decode(data)
+            .context(DecodingTagBloomFiltersSnafu)?;
+
+        let filters = proto_msg
+            .filters
+            .into_iter()
+            .map(|(column_name, filter)| {
+                if filter.bits.is_empty() || filter.num_hashes == 0 {
+                    return EmptyBloomFilterSnafu { column_name }.fail();
+                }
+
+                let filter = BloomFilter {
+                    num_hashes: filter.num_hashes,
+                    bits: filter.bits,
+                };
+                Ok((column_name, filter))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { filters })
+    }
+
+    /// Convert to protobuf v3 message.
+    pub(crate) fn to_protobuf(&self) -> std::result::Result<Vec<u8>, prost::EncodeError> {
+        let proto_msg = preserved_catalog::TagBloomFilters {
+            filters: self
+                .filters
+                .iter()
+                .map(|(column_name, filter)| {
+                    (
+                        column_name.clone(),
+                        preserved_catalog::BloomFilter {
+                            num_hashes: filter.num_hashes,
+                            bits: filter.bits.clone(),
+                        },
+                    )
+                })
+                .collect(),
+        };
+
+        let mut buf = Vec::new();
+        proto_msg.encode(&mut buf)?;
+
+        Ok(buf)
+    }
+}
+
+/// Builds [`TagBloomFilters`] one record batch at a time, keeping only the
+/// hashes of the distinct tag values seen so far.
+#[derive(Debug)]
+pub struct TagBloomFiltersBuilder {
+    hashes: BTreeMap<String, HashSet<ValueHash>>,
+}
+
+impl TagBloomFiltersBuilder {
+    /// Create a builder for the tag columns in `schema`.
+    pub fn new(schema: &Schema) -> Self {
+        let hashes = schema
+            .tags_iter()
+            .map(|field| (field.name().to_string(), HashSet::new()))
+            .collect();
+        Self { hashes }
+    }
+
+    /// Add the tag values of `batch`.
+    pub fn push(&mut self, batch: &RecordBatch) -> Result<()> {
+        for (column_name, hashes) in &mut self.hashes {
+            let column = match batch.schema().index_of(column_name) {
+                Ok(idx) => batch.column(idx),
+                Err(_) => continue,
+            };
+
+            if let Some(column) = column.as_any().downcast_ref::<DictionaryArray<Int32Type>>() {
+                if let Some(values) = column.values().as_any().downcast_ref::<StringArray>() {
+                    // Hash each dictionary value used by a row once
+                    let mut used = vec![false; values.len()];
+                    for key in column.keys().iter().flatten() {
+                        used[key as usize] = true;
+                    }
+                    hashes.extend(
+                        used.iter()
+                            .enumerate()
+                            .filter(|(idx, used)| **used && values.is_valid(*idx))
+                            .map(|(idx, _)| hash_value(values.value(idx))),
+                    );
+                    continue;
+                }
+            }
+
+            if let Some(column) = column.as_any().downcast_ref::<StringArray>() {
+                hashes.extend(column.iter().flatten().map(hash_value));
+                continue;
+            }
+
+            let column =
+                cast(column, &DataType::Utf8).context(ReadingTagValuesSnafu { column_name })?;
+            let column = column
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .expect("cast to utf8");
+            hashes.extend(column.iter().flatten().map(hash_value));
+        }
+
+        Ok(())
+    }
+
+    /// Build the filters, sized for the number of distinct values of each tag column.
+    pub fn build(self) -> TagBloomFilters {
+        let filters = self
+            .hashes
+            .into_iter()
+            .map(|(column_name, hashes)| {
+                let mut filter = BloomFilter::with_capacity(hashes.len());
+                for hash in hashes {
+                    filter.insert_hash(hash);
+                }
+                (column_name, filter)
+            })
+            .collect();
+
+        TagBloomFilters { filters }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow::array::{ArrayRef, Float64Array};
+    use datafusion::logical_plan::{col, lit};
+    use predicate::predicate::PredicateBuilder;
+    use schema::builder::SchemaBuilder;
+    use std::sync::Arc;
+
+    fn make_filters() -> TagBloomFilters {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .field("usage", DataType::Float64)
+            .build()
+            .unwrap();
+
+        let host: DictionaryArray<Int32Type> =
+            vec![Some("a"), None, Some("b")].into_iter().collect();
+        let usage = Float64Array::from(vec![1.0, 2.0, 3.0]);
+        let batch = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![Arc::new(host) as ArrayRef, Arc::new(usage) as ArrayRef],
+        )
+        .unwrap();
+
+        TagBloomFilters::try_new(&schema, &[batch]).unwrap()
+    }
+
+    #[test]
+    fn test_bloom_filter_no_false_negatives() {
+        let values: Vec<_> = (0..1000).map(|i| format!("host{}", i)).collect();
+
+        let mut filter = BloomFilter::with_capacity(values.len());
+        for value in &values {
+            filter.insert(value);
+        }
+
+        assert!(values.iter().all(|v| filter.may_contain(v)));
+
+        let false_positives = (1000..11000)
+            .filter(|i| filter.may_contain(&format!("host{}", i)))
+            .count();
+        assert!(false_positives < 500, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_tag_bloom_filters_may_match() {
+        let filters = make_filters();
+
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("host").eq(lit("a")))
+            .build();
+        assert!(filters.may_match(&predicate));
+
+        let predicate = PredicateBuilder::default()
+            .add_expr(lit("b").eq(col("host")).and(col("usage").gt(lit(1.0))))
+            .build();
+        assert!(filters.may_match(&predicate));
+
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("host").eq(lit("z")))
+            .build();
+        assert!(!filters.may_match(&predicate));
+
+        // only equality can be decided using the filters
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("host").not_eq(lit("z")))
+            .build();
+        assert!(filters.may_match(&predicate));
+    }
+
+    #[test]
+    fn test_tag_bloom_filters_builder() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .tag("region")
+            .build()
+            .unwrap();
+
+        // The first row is sliced off, leaving "a" in the dictionary unused
+        let host: DictionaryArray<Int32Type> =
+            vec![Some("a"), Some("b"), None].into_iter().collect();
+        let host = Arc::new(host).slice(1, 2);
+        let region: DictionaryArray<Int32Type> =
+            vec![Some("west"), Some("east")].into_iter().collect();
+        let batch1 =
+            RecordBatch::try_new(schema.as_arrow(), vec![host, Arc::new(region) as ArrayRef])
+                .unwrap();
+
+        let host = StringArray::from(vec![Some("c"), Some("b")]);
+        let region = StringArray::from(vec![None, Some("north")]);
+        let batch2 = RecordBatch::try_from_iter(vec![
+            ("host", Arc::new(host) as ArrayRef),
+            ("region", Arc::new(region) as ArrayRef),
+        ])
+        .unwrap();
+
+        let mut builder = TagBloomFiltersBuilder::new(&schema);
+        builder.push(&batch1).unwrap();
+        builder.push(&batch2).unwrap();
+        let filters = builder.build();
+
+        let host = filters.get("host").unwrap();
+        assert!(host.may_contain("b"));
+        assert!(host.may_contain("c"));
+        assert!(!host.may_contain("a"));
+
+        let region = filters.get("region").unwrap();
+        for value in ["west", "east", "north"] {
+            assert!(region.may_contain(value));
+        }
+        assert!(!region.may_contain("south"));
+    }
+
+    #[test]
+    fn test_tag_bloom_filters_protobuf_roundtrip() {
+        let filters = make_filters();
+
+        let bytes = filters.to_protobuf().unwrap();
+        let decoded = TagBloomFilters::from_protobuf(&bytes).unwrap();
+        assert_eq!(filters, decoded);
+    }
+}
@@ -1,15 +1,17 @@
-use crate::{metadata::IoxParquetMetaData, storage::Storage};
+use crate::{bloom::TagBloomFilters, metadata::IoxParquetMetaData, storage::Storage};
 use data_types::{
     partition_metadata::{Statistics, TableSummary},
     timestamp::{TimestampMinMax, TimestampRange},
 };
 use datafusion::physical_plan::SendableRecordBatchStream;
+use iox_catalog::interface::{SequenceNumber, SequencerId};
 use iox_object_store::{IoxObjectStore, ParquetFilePath};
+use metric::U64Counter;
 use predicate::predicate::Predicate;
 use schema::selection::Selection;
 use schema::{Schema, TIME_COLUMN_NAME};
 use snafu::{ResultExt, Snafu};
-use std::{collections::BTreeSet, mem, sync::Arc};
+use std::{collections::BTreeSet, mem, ops::RangeInclusive, sync::Arc};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -48,6 +50,15 @@ pub enum Error {
         source: crate::metadata::Error,
         path: ParquetFilePath,
     },
+
+    #[snafu(
+        display("Cannot read tag bloom filters from {:?}: {}", path, source),
+        visibility(pub)
+    )]
+    TagBloomFiltersReadFailed {
+        source: crate::metadata::Error,
+        path: ParquetFilePath,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -55,7 +66,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug)]
 #[allow(missing_copy_implementations)]
 pub struct ChunkMetrics {
-    // Placeholder
+    /// Number of columns that failed to decode and were read as nulls, see
+    /// [`ParquetChunk::with_null_corrupt_columns`]
+    corrupt_columns: U64Counter,
 }
 
 impl ChunkMetrics {
@@ -64,11 +77,20 @@ impl ChunkMetrics {
     /// will therefore not be visible to other ChunkMetrics instances or metric instruments
     /// created on a metrics domain, and vice versa
     pub fn new_unregistered() -> Self {
-        Self {}
+        Self {
+            corrupt_columns: Default::default(),
+        }
     }
 
-    pub fn new(_metrics: &metric::Registry) -> Self {
-        Self {}
+    pub fn new(metrics: &metric::Registry) -> Self {
+        Self {
+            corrupt_columns: metrics
+                .register_metric::<U64Counter>(
+                    "parquet_corrupt_columns",
+                    "number of parquet columns that failed to decode and were read as nulls",
+                )
+                .recorder(&[]),
+        }
     }
 }
 
@@ -99,10 +121,19 @@ pub struct ParquetChunk {
     /// Parquet metadata that can be used checkpoint the catalog state.
     parquet_metadata: Arc<IoxParquetMetaData>,
 
+    /// Bloom filters over the tag values, if the file was written with them
+    tag_bloom_filters: Option<Arc<TagBloomFilters>>,
+
     /// Number of rows
     rows: usize,
 
-    #[allow(dead_code)]
+    /// Read columns that fail to decode as nulls instead of failing the read
+    null_corrupt_columns: bool,
+
+    /// Sequencer and sequence numbers of the writes in the file, if it was written by
+    /// the ingester
+    sequence_numbers: Option<(SequencerId, RangeInclusive<SequenceNumber>)>,
+
     metrics: ChunkMetrics,
 }
 
@@ -131,8 +162,18 @@ impl ParquetChunk {
             columns,
         };
         let rows = decoded.row_count();
-
-        Ok(Self::new_from_parts(
+        let tag_bloom_filters = decoded
+            .read_tag_bloom_filters()
+            .context(TagBloomFiltersReadFailedSnafu { path })?;
+        // Files written by the database have no sequence numbers of their own
+        let sequence_numbers = decoded.read_iox_metadata_new().ok().map(|metadata| {
+            (
+                metadata.sequencer_id,
+                metadata.min_sequence_number..=metadata.max_sequence_number,
+            )
+        });
+
+        let chunk = Self::new_from_parts(
             partition_key,
             Arc::new(table_summary),
             schema,
@@ -140,9 +181,14 @@ impl ParquetChunk {
             iox_object_store,
             file_size_bytes,
             parquet_metadata,
+            tag_bloom_filters.map(Arc::new),
             rows,
             metrics,
-        ))
+        );
+        Ok(Self {
+            sequence_numbers,
+            ..chunk
+        })
     }
 
     /// Creates a new chunk from given parts w/o parsing anything from the provided parquet
@@ -156,6 +202,7 @@ impl ParquetChunk {
         iox_object_store: Arc<IoxObjectStore>,
         file_size_bytes: usize,
         parquet_metadata: Arc<IoxParquetMetaData>,
+        tag_bloom_filters: Option<Arc<TagBloomFilters>>,
         rows: usize,
         metrics: ChunkMetrics,
     ) -> Self {
@@ -170,11 +217,30 @@ impl ParquetChunk {
             path: path.into(),
             file_size_bytes,
             parquet_metadata,
+            tag_bloom_filters,
             rows,
+            null_corrupt_columns: false,
+            sequence_numbers: None,
             metrics,
         }
     }
 
+    /// Return the columns of the parquet file that fail to decode as nulls when reading
+    /// this chunk, rather than failing the whole read, so that a file with a corrupt column
+    /// stays queryable. Corrupt columns are logged and counted in the chunk metrics.
+    ///
+    /// Columns are then decoded one at a time and in full, so reading needs more memory.
+    pub fn with_null_corrupt_columns(mut self, null_corrupt_columns: bool) -> Self {
+        self.null_corrupt_columns = null_corrupt_columns;
+        self
+    }
+
+    /// Returns true if columns that fail to decode are read as nulls, see
+    /// [`Self::with_null_corrupt_columns`]
+    pub fn null_corrupt_columns(&self) -> bool {
+        self.null_corrupt_columns
+    }
+
     /// Return the chunk's partition key
     pub fn partition_key(&self) -> &str {
         self.partition_key.as_ref()
@@ -204,6 +270,17 @@ impl ParquetChunk {
             + mem::size_of_val(&self.schema.as_ref())
             + mem::size_of_val(&self.path)
             + self.parquet_metadata.size()
+            + self
+                .tag_bloom_filters
+                .as_ref()
+                .map(|filters| filters.size())
+                .unwrap_or(0)
+    }
+
+    /// Returns the sequencer and the range of sequence numbers of the writes whose data
+    /// is in this chunk, if the file was written by the ingester
+    pub fn sequence_numbers(&self) -> Option<(SequencerId, RangeInclusive<SequenceNumber>)> {
+        self.sequence_numbers.clone()
     }
 
     /// Infallably return the full schema (for all columns) for this chunk
@@ -225,6 +302,17 @@ impl ParquetChunk {
         }
     }
 
+    /// Return false if the tag bloom filters of this chunk show that no row
+    /// can match the `tag = 'value'` expressions in `predicate`.
+    ///
+    /// Returns true if the chunk has no bloom filters.
+    pub fn may_match_tag_values(&self, predicate: &Predicate) -> bool {
+        self.tag_bloom_filters
+            .as_ref()
+            .map(|filters| filters.may_match(predicate))
+            .unwrap_or(true)
+    }
+
     // Return the columns names that belong to the given column selection
     pub fn column_names(&self, selection: Selection<'_>) -> Option<BTreeSet<String>> {
         let fields = self.schema.inner().fields().iter();
@@ -240,6 +328,10 @@ impl ParquetChunk {
                 })
                 .collect(),
             Selection::All => fields.map(|x| x.name().clone()).collect(),
+            Selection::AllExcept(cols) => fields
+                .filter(|x| !cols.contains(&x.name().as_str()))
+                .map(|x| x.name().clone())
+                .collect(),
         })
     }
 
@@ -255,6 +347,8 @@ impl ParquetChunk {
             Arc::clone(&self.schema.as_arrow()),
             self.path.clone(),
             Arc::clone(&self.iox_object_store),
+            self.null_corrupt_columns
+                .then(|| self.metrics.corrupt_columns.clone()),
         )
         .context(ReadParquetSnafu)
     }
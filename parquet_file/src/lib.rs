@@ -8,6 +8,7 @@
     clippy::clone_on_ref_ptr
 )]
 
+pub mod bloom;
 pub mod chunk;
 pub mod metadata;
 pub mod storage;
@@ -86,6 +86,7 @@
 //! [Apache Parquet]: https://parquet.apache.org/
 //! [Apache Thrift]: https://thrift.apache.org/
 //! [Thrift Compact Protocol]: https://github.com/apache/thrift/blob/master/doc/specs/thrift-compact-protocol.md
+use crate::bloom::TagBloomFilters;
 use data_types::{
     chunk_metadata::{ChunkId, ChunkOrder},
     partition_metadata::{ColumnSummary, InfluxDbType, StatValues, Statistics},
@@ -111,7 +112,7 @@ use persistence_windows::{
     min_max_sequence::OptionalMinMaxSequence,
 };
 use prost::Message;
-use schema::{InfluxColumnType, InfluxFieldType, Schema};
+use schema::{selection::Selection, InfluxColumnType, InfluxFieldType, Schema};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::{collections::BTreeMap, convert::TryInto, sync::Arc};
 use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol, TOutputProtocol};
@@ -133,6 +134,14 @@ pub const METADATA_VERSION: u32 = 10;
 /// [Protocol Buffers 3]: https://developers.google.com/protocol-buffers/docs/proto3
 pub const METADATA_KEY: &str = "IOX:metadata";
 
+/// File-level metadata key to store the bloom filters over tag values.
+///
+/// This will contain [`TagBloomFilters`] serialized as base64-encoded [Protocol Buffers 3]. Files written before these
+/// filters were introduced do not contain this key.
+///
+/// [Protocol Buffers 3]: https://developers.google.com/protocol-buffers/docs/proto3
+pub const TAG_BLOOM_FILTERS_KEY: &str = "IOX:tag_bloom_filters";
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Cannot read parquet metadata from bytes: {}", source))]
@@ -246,6 +255,11 @@ pub enum Error {
 
     #[snafu(display("Cannot parse UUID: {}", source))]
     UuidParse { source: uuid::Error },
+
+    #[snafu(display("Cannot parse tag bloom filters: {}", source))]
+    TagBloomFiltersBroken {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -536,7 +550,6 @@ impl IoxMetadata {
     }
 
     /// Read from protobuf message
-    #[allow(dead_code)]
     fn from_protobuf(data: &[u8]) -> Result<Self> {
         // extract protobuf message from bytes
         let proto_msg = proto::IoxMetadata::decode(data)
@@ -783,8 +796,34 @@ impl DecodedIoxParquetMetaData {
         self.md.file_metadata().num_rows() as usize
     }
 
+    /// Return the total compressed size, across all row groups, of the column chunks that
+    /// must be read from the parquet file to decode `selection`.
+    pub fn column_chunk_bytes(&self, selection: Selection<'_>) -> usize {
+        self.md
+            .row_groups()
+            .iter()
+            .flat_map(|row_group| row_group.columns())
+            .filter(|column| selection.contains(column.column_descr().name()))
+            .map(|column| column.compressed_size() as usize)
+            .sum()
+    }
+
     /// Read IOx metadata from file-level key-value parquet metadata.
     pub fn read_iox_metadata(&self) -> Result<IoxMetadataOld> {
+        IoxMetadataOld::from_protobuf(&self.iox_metadata_bytes()?)
+    }
+
+    /// Read the IOx metadata of a file written by the ingester from file-level key-value
+    /// parquet metadata.
+    ///
+    /// Fails for files written with [`IoxMetadataOld`], as both formats are stored under the
+    /// same key but do not decode as each other.
+    pub fn read_iox_metadata_new(&self) -> Result<IoxMetadata> {
+        IoxMetadata::from_protobuf(&self.iox_metadata_bytes()?)
+    }
+
+    /// Read the protobuf-encoded IOx metadata from file-level key-value parquet metadata
+    fn iox_metadata_bytes(&self) -> Result<Vec<u8>> {
         // find file-level key-value metadata entry
         let kv = self
             .md
@@ -798,12 +837,35 @@ impl DecodedIoxParquetMetaData {
 
         // extract protobuf message from key-value entry
         let proto_base64 = kv.value.as_ref().context(IoxMetadataMissingSnafu)?;
+        base64::decode(proto_base64)
+            .map_err(|err| Box::new(err) as _)
+            .context(IoxMetadataBrokenSnafu)
+    }
+
+    /// Read the tag bloom filters from file-level key-value parquet metadata.
+    ///
+    /// Returns `None` if the file was written without them.
+    pub fn read_tag_bloom_filters(&self) -> Result<Option<TagBloomFilters>> {
+        let proto_base64 = match self
+            .md
+            .file_metadata()
+            .key_value_metadata()
+            .as_ref()
+            .and_then(|kvs| kvs.iter().find(|kv| kv.key == TAG_BLOOM_FILTERS_KEY))
+            .and_then(|kv| kv.value.as_ref())
+        {
+            Some(proto_base64) => proto_base64,
+            None => return Ok(None),
+        };
+
         let proto_bytes = base64::decode(proto_base64)
             .map_err(|err| Box::new(err) as _)
-            .context(IoxMetadataBrokenSnafu)?;
+            .context(TagBloomFiltersBrokenSnafu)?;
 
-        // convert to Rust object
-        IoxMetadataOld::from_protobuf(proto_bytes.as_slice())
+        TagBloomFilters::from_protobuf(proto_bytes.as_slice())
+            .map(Some)
+            .map_err(|err| Box::new(err) as _)
+            .context(TagBloomFiltersBrokenSnafu)
     }
 
     /// Read IOx schema from parquet metadata.
@@ -1026,8 +1088,10 @@ mod tests {
 
     use schema::TIME_COLUMN_NAME;
 
+    use crate::storage::Storage;
     use crate::test_utils::create_partition_and_database_checkpoint;
     use crate::test_utils::generator::{ChunkGenerator, GeneratorConfig};
+    use crate::test_utils::{make_record_batch, TestSize};
 
     #[tokio::test]
     async fn test_restore_from_file() {
@@ -1200,4 +1264,44 @@ mod tests {
 
         assert_eq!(iox_metadata, iox_metadata_again);
     }
+
+    #[tokio::test]
+    async fn test_read_iox_metadata_new() {
+        let iox_metadata = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: Time::from_timestamp(3234, 0),
+            namespace_id: NamespaceId::new(2),
+            namespace_name: Arc::from("hi"),
+            sequencer_id: SequencerId::new(1),
+            table_id: TableId::new(3),
+            table_name: Arc::from("weather"),
+            partition_id: PartitionId::new(4),
+            partition_key: Arc::from("part"),
+            time_of_first_write: Time::from_timestamp(3234, 0),
+            time_of_last_write: Time::from_timestamp(3234, 3456),
+            min_sequence_number: SequenceNumber::new(5),
+            max_sequence_number: SequenceNumber::new(6),
+        };
+        let (batches, _, _, _) = make_record_batch("foo", TestSize::Minimal);
+        let schema = batches[0].schema();
+        let data = Storage::parquet_bytes(batches, schema, &iox_metadata)
+            .await
+            .unwrap();
+        let decoded = IoxParquetMetaData::from_file_bytes(data)
+            .unwrap()
+            .unwrap()
+            .decode()
+            .unwrap();
+
+        // A file written by the ingester only has the new metadata
+        assert_eq!(decoded.read_iox_metadata_new().unwrap(), iox_metadata);
+        assert!(decoded.read_iox_metadata().is_err());
+
+        // ... and a file written by the database only has the old one
+        let mut generator = ChunkGenerator::new().await;
+        let (chunk, _) = generator.generate().await.unwrap();
+        let decoded = chunk.parquet_metadata().decode().unwrap();
+        assert!(decoded.read_iox_metadata().is_ok());
+        assert!(decoded.read_iox_metadata_new().is_err());
+    }
 }
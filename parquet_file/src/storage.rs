@@ -1,7 +1,9 @@
 /// This module responsible to write given data to specify object store and
 /// read them back
 use arrow::{
-    datatypes::{Schema, SchemaRef},
+    array::{new_empty_array, new_null_array, ArrayRef},
+    compute::concat,
+    datatypes::{DataType, Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
@@ -11,11 +13,12 @@ use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion_util::AdapterStream;
 use futures::{stream, Stream, StreamExt};
 use iox_object_store::{IoxObjectStore, ParquetFilePath};
+use metric::U64Counter;
 use object_store::GetResult;
-use observability_deps::tracing::debug;
+use observability_deps::tracing::{debug, warn};
 use parking_lot::Mutex;
 use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
-use parquet::file::reader::SerializedFileReader;
+use parquet::file::reader::{FileReader, SerializedFileReader};
 use parquet::{
     self,
     arrow::ArrowWriter,
@@ -26,12 +29,20 @@ use predicate::predicate::Predicate;
 use schema::selection::Selection;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
+    convert::TryInto,
+    fs::File,
     io::{Cursor, Seek, SeekFrom, Write},
     marker::Unpin,
     sync::Arc,
 };
+use thrift::protocol::{TCompactOutputProtocol, TOutputProtocol};
 
-use crate::metadata::{IoxMetadata, IoxMetadataOld, IoxParquetMetaData, METADATA_KEY};
+use crate::{
+    bloom::TagBloomFiltersBuilder,
+    metadata::{
+        IoxMetadata, IoxMetadataOld, IoxParquetMetaData, METADATA_KEY, TAG_BLOOM_FILTERS_KEY,
+    },
+};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -48,6 +59,14 @@ pub enum Error {
         source: parquet::errors::ParquetError,
     },
 
+    #[snafu(display("Error writing Parquet to a temp file: {}", source))]
+    WritingParquetToFile {
+        source: parquet::errors::ParquetError,
+    },
+
+    #[snafu(display("Error joining the temp file writer: {}", source))]
+    JoiningFileWriter { source: tokio::task::JoinError },
+
     #[snafu(display("Error closing Parquet Writer: {}", source))]
     ClosingParquetWriter {
         source: parquet::errors::ParquetError,
@@ -81,17 +100,46 @@ pub enum Error {
 
     #[snafu(display("No data to convert to parquet"))]
     NoData {},
+
+    #[snafu(display("Cannot convert arrow schema to IOx schema: {}", source))]
+    ConvertingSchema { source: schema::Error },
+
+    #[snafu(display("Cannot build tag bloom filters: {}", source))]
+    BuildingTagBloomFilters { source: crate::bloom::Error },
+
+    #[snafu(display("Cannot write parquet footer: {}", source))]
+    WritingFooter { source: thrift::Error },
+
+    #[snafu(display("Cannot decode non-nullable column {}: {}", column, source))]
+    DecodingColumn { column: String, source: ArrowError },
+
+    #[snafu(display("Cannot assemble the decoded columns: {}", source))]
+    AssemblingColumns { source: ArrowError },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, Clone)]
 pub struct Storage {
     iox_object_store: Arc<IoxObjectStore>,
+
+    /// Build bloom filters over the tag values of written files
+    tag_bloom_filters: bool,
 }
 
 impl Storage {
     pub fn new(iox_object_store: Arc<IoxObjectStore>) -> Self {
-        Self { iox_object_store }
+        Self {
+            iox_object_store,
+            tag_bloom_filters: false,
+        }
+    }
+
+    /// Store bloom filters over the tag values in files written by
+    /// [`Self::write_to_object_store`], so that chunks can be pruned for
+    /// `tag = 'value'` predicates.
+    pub fn with_tag_bloom_filters(mut self, tag_bloom_filters: bool) -> Self {
+        self.tag_bloom_filters = tag_bloom_filters;
+        self
     }
 
     /// Write the given stream of data of a specified table of
@@ -112,7 +160,8 @@ impl Storage {
         let path = ParquetFilePath::new(&chunk_addr);
 
         let schema = stream.schema();
-        let data = Self::parquet_stream_to_bytes(stream, schema, metadata).await?;
+        let data =
+            Self::parquet_stream_to_bytes(stream, schema, metadata, self.tag_bloom_filters).await?;
         // no data
         if data.is_empty() {
             return Ok(None);
@@ -128,26 +177,66 @@ impl Storage {
         Ok(Some((path, file_size_bytes, md)))
     }
 
-    fn writer_props(metadata_bytes: &[u8]) -> WriterProperties {
+    fn writer_props(key_value_metadata: Vec<KeyValue>) -> WriterProperties {
         WriterProperties::builder()
-            .set_key_value_metadata(Some(vec![KeyValue {
-                key: METADATA_KEY.to_string(),
-                value: Some(base64::encode(&metadata_bytes)),
-            }]))
+            .set_key_value_metadata(Some(key_value_metadata))
             .set_compression(Compression::ZSTD)
             .build()
     }
 
-    /// Convert the given stream of RecordBatches to bytes. This should be deleted when switching
-    /// over to use `ingester` only.
+    /// Key-value metadata entry storing the base64-encoded `bytes` under `key`
+    fn key_value(key: &str, bytes: &[u8]) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(base64::encode(bytes)),
+        }
+    }
+
+    /// Convert the given stream of RecordBatches to bytes, optionally
+    /// including bloom filters over the tag values. This should be deleted
+    /// when switching over to use `ingester` only.
     async fn parquet_stream_to_bytes(
         stream: SendableRecordBatchStream,
         schema: SchemaRef,
         metadata: IoxMetadataOld,
+        tag_bloom_filters: bool,
     ) -> Result<Vec<u8>> {
         let metadata_bytes = metadata.to_protobuf().context(MetadataEncodeFailureSnafu)?;
+        let key_value_metadata = vec![Self::key_value(METADATA_KEY, &metadata_bytes)];
+
+        if !tag_bloom_filters {
+            return Self::record_batches_to_parquet_bytes(stream, schema, key_value_metadata).await;
+        }
 
-        Self::record_batches_to_parquet_bytes(stream, schema, &metadata_bytes).await
+        // The key-value metadata can't be changed once the parquet writer
+        // is created, so the bloom filters are built as the batches are
+        // written and added to the footer afterwards
+        let iox_schema =
+            schema::Schema::try_from(Arc::clone(&schema)).context(ConvertingSchemaSnafu)?;
+        let mut filters = TagBloomFiltersBuilder::new(&iox_schema);
+        let written = Self::write_parquet_to_memory(stream, schema, key_value_metadata, |batch| {
+            filters.push(batch).context(BuildingTagBloomFiltersSnafu)
+        })
+        .await?;
+        let (mut data, mut file_metadata) = match written {
+            Some(written) => written,
+            None => return Ok(vec![]),
+        };
+
+        let tag_bloom_filters_bytes = filters
+            .build()
+            .to_protobuf()
+            .context(MetadataEncodeFailureSnafu)?;
+        file_metadata
+            .key_value_metadata
+            .get_or_insert_with(Vec::new)
+            .push(Self::key_value(
+                TAG_BLOOM_FILTERS_KEY,
+                &tag_bloom_filters_bytes,
+            ));
+        replace_footer(&mut data, &file_metadata)?;
+
+        Ok(data)
     }
 
     /// Convert the given metadata and RecordBatches to parquet file bytes. Used by `ingester`.
@@ -159,37 +248,116 @@ impl Storage {
         let metadata_bytes = metadata.to_protobuf().context(MetadataEncodeFailureSnafu)?;
 
         let stream = Box::pin(stream::iter(record_batches.into_iter().map(Ok)));
+        let key_value_metadata = vec![Self::key_value(METADATA_KEY, &metadata_bytes)];
 
-        Self::record_batches_to_parquet_bytes(stream, schema, &metadata_bytes).await
+        Self::record_batches_to_parquet_bytes(stream, schema, key_value_metadata).await
+    }
+
+    /// Encode the given metadata and stream of RecordBatches as a parquet file in a temporary
+    /// file, writing each batch as it arrives so neither the stream nor the encoded file is
+    /// held in memory. Return the file, positioned at its start, and its size in bytes, or
+    /// `None` if the stream had no data. Used by `ingester`.
+    ///
+    /// The batches are encoded on a blocking thread, as writing the temporary file blocks.
+    pub async fn parquet_stream_to_file(
+        mut stream: SendableRecordBatchStream,
+        metadata: &IoxMetadata,
+    ) -> Result<Option<(File, u64)>> {
+        let metadata_bytes = metadata.to_protobuf().context(MetadataEncodeFailureSnafu)?;
+        let props = Self::writer_props(vec![Self::key_value(METADATA_KEY, &metadata_bytes)]);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let schema = stream.schema();
+        let writer =
+            tokio::task::spawn_blocking(move || Self::write_parquet_to_file(schema, props, rx));
+
+        while let Some(batch) = stream.next().await {
+            let batch = batch.context(ReadingStreamSnafu)?;
+            if tx.send(batch).await.is_err() {
+                // The writer failed, its error is returned below
+                break;
+            }
+        }
+        drop(tx);
+
+        writer.await.context(JoiningFileWriterSnafu)?
+    }
+
+    /// Encode the record batches received from `rx` as a parquet file in a temporary file.
+    /// Return the file, positioned at its start, and its size in bytes, or `None` if no batch
+    /// was received. Blocks on the file IO, see [`Self::parquet_stream_to_file`].
+    fn write_parquet_to_file(
+        schema: SchemaRef,
+        props: WriterProperties,
+        mut rx: tokio::sync::mpsc::Receiver<RecordBatch>,
+    ) -> Result<Option<(File, u64)>> {
+        let mut file = tempfile::tempfile().context(OpenTempFileSnafu)?;
+        {
+            let mut writer = ArrowWriter::try_new(
+                file.try_clone().context(OpenTempFileSnafu)?,
+                schema,
+                Some(props),
+            )
+            .context(OpeningParquetWriterSnafu)?;
+            let mut no_stream_data = true;
+            while let Some(batch) = rx.blocking_recv() {
+                no_stream_data = false;
+                writer.write(&batch).context(WritingParquetToFileSnafu)?;
+            }
+            if no_stream_data {
+                return Ok(None);
+            }
+            writer.close().context(ClosingParquetWriterSnafu)?;
+        }
+
+        let file_size_bytes = file.seek(SeekFrom::End(0)).context(WriteTempFileSnafu)?;
+        file.seek(SeekFrom::Start(0)).context(WriteTempFileSnafu)?;
+        Ok(Some((file, file_size_bytes)))
     }
 
     /// Share code between `parquet_stream_to_bytes` and `parquet_bytes`. When
     /// `parquet_stream_to_bytes` is deleted, this code can be moved into `parquet_bytes` and
     /// made simpler by using a plain `Iter` rather than a `Stream`.
     async fn record_batches_to_parquet_bytes(
-        mut stream: impl Stream<Item = ArrowResult<RecordBatch>> + Send + Sync + Unpin,
+        stream: impl Stream<Item = ArrowResult<RecordBatch>> + Send + Sync + Unpin,
         schema: SchemaRef,
-        metadata_bytes: &[u8],
+        key_value_metadata: Vec<KeyValue>,
     ) -> Result<Vec<u8>> {
-        let props = Self::writer_props(metadata_bytes);
+        let written =
+            Self::write_parquet_to_memory(stream, schema, key_value_metadata, |_| Ok(())).await?;
+        Ok(written.map(|(data, _)| data).unwrap_or_default())
+    }
+
+    /// Encode the stream of RecordBatches as a parquet file in memory, calling `inspect` with
+    /// each batch as it is written. Return the file and the metadata written in its footer, or
+    /// `None` if the stream had no data.
+    async fn write_parquet_to_memory(
+        mut stream: impl Stream<Item = ArrowResult<RecordBatch>> + Send + Sync + Unpin,
+        schema: SchemaRef,
+        key_value_metadata: Vec<KeyValue>,
+        mut inspect: impl FnMut(&RecordBatch) -> Result<()> + Send,
+    ) -> Result<Option<(Vec<u8>, parquet_format::FileMetaData)>> {
+        let props = Self::writer_props(key_value_metadata);
 
         let mem_writer = MemWriter::default();
-        {
+        let file_metadata = {
             let mut writer = ArrowWriter::try_new(mem_writer.clone(), schema, Some(props))
                 .context(OpeningParquetWriterSnafu)?;
             let mut no_stream_data = true;
             while let Some(batch) = stream.next().await {
                 no_stream_data = false;
                 let batch = batch.context(ReadingStreamSnafu)?;
+                inspect(&batch)?;
                 writer.write(&batch).context(WritingParquetToMemorySnafu)?;
             }
             if no_stream_data {
-                return Ok(vec![]);
+                return Ok(None);
             }
-            writer.close().context(ClosingParquetWriterSnafu)?;
-        } // drop the reference to the MemWriter that the SerializedFileWriter has
+            writer.close().context(ClosingParquetWriterSnafu)?
+        }; // drop the reference to the MemWriter that the SerializedFileWriter has
 
-        mem_writer.into_inner().context(WritingToMemWriterSnafu)
+        let data = mem_writer.into_inner().context(WritingToMemWriterSnafu)?;
+        Ok(Some((data, file_metadata)))
     }
 
     /// Put the given vector of bytes to the specified location
@@ -218,6 +386,10 @@ impl Storage {
                 })
                 .collect(),
             Selection::All => fields.enumerate().map(|(p, _)| p).collect(),
+            Selection::AllExcept(_) => fields
+                .enumerate()
+                .filter_map(|(p, x)| selection.contains(x.name()).then(|| p))
+                .collect(),
         }
     }
 
@@ -227,8 +399,10 @@ impl Storage {
     /// The resulting record batches from Parquet are sent back to `tx`
     fn download_and_scan_parquet(
         projection: Vec<usize>,
+        schema: SchemaRef,
         path: ParquetFilePath,
         store: Arc<IoxObjectStore>,
+        null_corrupt_columns: Option<U64Counter>,
         tx: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
     ) -> Result<()> {
         // Size of each batch
@@ -261,8 +435,28 @@ impl Storage {
             }
         };
 
-        let file_reader = SerializedFileReader::new(file).context(ParquetReaderSnafu)?;
-        let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
+        let file_reader: Arc<dyn FileReader> =
+            Arc::new(SerializedFileReader::new(file).context(ParquetReaderSnafu)?);
+
+        if let Some(corrupt_columns) = null_corrupt_columns.filter(|_| !projection.is_empty()) {
+            let batches = Self::read_columns_nulling_corrupt(
+                file_reader,
+                projection,
+                schema,
+                batch_size,
+                &path,
+                &corrupt_columns,
+            )?;
+            for batch in batches {
+                if tx.blocking_send(Ok(batch)).is_err() {
+                    debug!(?path, "Receiver hung up - exiting");
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
         let record_batch_reader = arrow_reader
             .get_record_reader_by_columns(projection, batch_size)
             .context(ParquetReaderSnafu)?;
@@ -277,15 +471,101 @@ impl Storage {
         Ok(())
     }
 
+    /// Decodes each column of `projection` separately and in full, replacing
+    /// nullable columns that fail to decode or that do not have the row count
+    /// of the file by nulls and counting them in
+    /// `corrupt_columns`. Returns batches of `batch_size` rows with the given
+    /// (projected) `schema`.
+    fn read_columns_nulling_corrupt(
+        file_reader: Arc<dyn FileReader>,
+        projection: Vec<usize>,
+        schema: SchemaRef,
+        batch_size: usize,
+        path: &ParquetFilePath,
+        corrupt_columns: &U64Counter,
+    ) -> Result<Vec<RecordBatch>> {
+        let num_rows = file_reader.metadata().file_metadata().num_rows() as usize;
+
+        let mut columns = Vec::with_capacity(projection.len());
+        for (column, field) in projection.into_iter().zip(schema.fields()) {
+            let array = match Self::read_column(
+                Arc::clone(&file_reader),
+                column,
+                field.data_type(),
+                batch_size,
+            )
+            .and_then(|array| check_num_rows(array, num_rows))
+            {
+                Ok(array) => array,
+                Err(e) if field.is_nullable() => {
+                    warn!(
+                        ?path,
+                        column=%field.name(),
+                        %e,
+                        "Reading corrupt parquet column as nulls"
+                    );
+                    corrupt_columns.inc(1);
+                    new_null_array(field.data_type(), num_rows)
+                }
+                Err(e) => {
+                    return Err(e).context(DecodingColumnSnafu {
+                        column: field.name(),
+                    })
+                }
+            };
+            columns.push(array);
+        }
+
+        let batch = RecordBatch::try_new(schema, columns).context(AssemblingColumnsSnafu)?;
+        Ok((0..num_rows)
+            .step_by(batch_size)
+            .map(|offset| batch.slice(offset, batch_size.min(num_rows - offset)))
+            .collect())
+    }
+
+    /// Decodes a single column of the parquet file in full
+    fn read_column(
+        file_reader: Arc<dyn FileReader>,
+        column: usize,
+        data_type: &DataType,
+        batch_size: usize,
+    ) -> ArrowResult<ArrayRef> {
+        let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
+        let arrays = arrow_reader
+            .get_record_reader_by_columns(vec![column], batch_size)
+            .map_err(|e| ArrowError::ParquetError(e.to_string()))?
+            .map(|batch| batch.map(|b| Arc::clone(b.column(0))))
+            .collect::<ArrowResult<Vec<_>>>()?;
+
+        match arrays.len() {
+            0 => Ok(new_empty_array(data_type)),
+            1 => Ok(Arc::clone(&arrays[0])),
+            _ => concat(&arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>()),
+        }
+    }
+
+    /// Read the selected columns of the parquet file at `path`.
+    ///
+    /// If `null_corrupt_columns` is set, nullable columns that fail to
+    /// decode are returned as nulls and counted in it, rather than failing
+    /// the read. Columns are then decoded one at a time and in full.
     pub fn read_filter(
         _predicate: &Predicate,
         selection: Selection<'_>,
         schema: SchemaRef,
         path: ParquetFilePath,
         store: Arc<IoxObjectStore>,
+        null_corrupt_columns: Option<U64Counter>,
     ) -> Result<SendableRecordBatchStream> {
-        // Indices of columns in the schema needed to read
+        // Indices of columns in the schema needed to read - only these
+        // column chunks are decoded by the parquet reader.
         let projection: Vec<usize> = Self::column_indices(selection, Arc::clone(&schema));
+        debug!(
+            ?path,
+            num_columns = schema.fields().len(),
+            num_projected = projection.len(),
+            "reading parquet file"
+        );
 
         // Compute final (output) schema after selection
         let schema = Arc::new(Schema::new(
@@ -300,9 +580,16 @@ impl Storage {
         // Run async dance here to make sure any error returned
         // `download_and_scan_parquet` is sent back to the reader and
         // not silently ignored
+        let output_schema = Arc::clone(&schema);
         tokio::task::spawn_blocking(move || {
-            let download_result =
-                Self::download_and_scan_parquet(projection, path, store, tx.clone());
+            let download_result = Self::download_and_scan_parquet(
+                projection,
+                output_schema,
+                path,
+                store,
+                null_corrupt_columns,
+                tx.clone(),
+            );
 
             // If there was an error returned from download_and_scan_parquet send it back to the receiver.
             if let Err(e) = download_result {
@@ -319,6 +606,51 @@ impl Storage {
     }
 }
 
+/// Replace the footer of the parquet file `data` with `file_metadata`.
+///
+/// A parquet file ends with its thrift-encoded metadata, the length of the
+/// metadata as a 4-byte little-endian integer and the magic bytes. The row
+/// groups are addressed by their offset from the start of the file, so the
+/// data before the metadata is kept as is.
+fn replace_footer(data: &mut Vec<u8>, file_metadata: &parquet_format::FileMetaData) -> Result<()> {
+    const MAGIC: &[u8] = b"PAR1";
+    let footer_start = data.len() - 8;
+    let metadata_len = u32::from_le_bytes(
+        data[footer_start..footer_start + 4]
+            .try_into()
+            .expect("4-byte slice"),
+    );
+    data.truncate(footer_start - metadata_len as usize);
+    let metadata_start = data.len();
+
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut *data);
+        file_metadata
+            .write_to_out_protocol(&mut protocol)
+            .context(WritingFooterSnafu)?;
+        protocol.flush().context(WritingFooterSnafu)?;
+    }
+
+    let metadata_len = (data.len() - metadata_start) as u32;
+    data.extend_from_slice(&metadata_len.to_le_bytes());
+    data.extend_from_slice(MAGIC);
+    Ok(())
+}
+
+/// Return `array` if it has `num_rows` rows, the row count of the file it
+/// was decoded from, and an error otherwise.
+fn check_num_rows(array: ArrayRef, num_rows: usize) -> ArrowResult<ArrayRef> {
+    if array.len() == num_rows {
+        Ok(array)
+    } else {
+        Err(ArrowError::ParquetError(format!(
+            "column has {} rows but the file has {}",
+            array.len(),
+            num_rows
+        )))
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct MemWriter {
     mem: Arc<Mutex<Cursor<Vec<u8>>>>,
@@ -364,6 +696,7 @@ impl TryClone for MemWriter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunk::{ChunkMetrics, ParquetChunk};
     use crate::test_utils::generator::ChunkGenerator;
     use crate::test_utils::{
         create_partition_and_database_checkpoint, load_parquet_from_store, make_iox_object_store,
@@ -373,6 +706,8 @@ mod tests {
     use arrow_util::assert_batches_eq;
     use data_types::chunk_metadata::{ChunkId, ChunkOrder};
     use datafusion_util::{stream_from_batch, MemoryStream};
+    use metric::{Attributes, Metric};
+    use parquet::file::serialized_reader::SliceableCursor;
     use parquet::schema::types::ColumnPath;
     use time::Time;
 
@@ -403,17 +738,23 @@ mod tests {
             record_batches,
             Arc::clone(schema.inner()),
         ));
-        let bytes =
-            Storage::parquet_stream_to_bytes(stream, Arc::clone(schema.inner()), metadata.clone())
-                .await
-                .unwrap();
+        let bytes = Storage::parquet_stream_to_bytes(
+            stream,
+            Arc::clone(schema.inner()),
+            metadata.clone(),
+            true,
+        )
+        .await
+        .unwrap();
 
         // extract metadata
         let md = IoxParquetMetaData::from_file_bytes(bytes).unwrap().unwrap();
-        let metadata_roundtrip = md.decode().unwrap().read_iox_metadata().unwrap();
+        let decoded = md.decode().unwrap();
+        let metadata_roundtrip = decoded.read_iox_metadata().unwrap();
 
         // compare with input
         assert_eq!(metadata_roundtrip, metadata);
+        assert!(decoded.read_tag_bloom_filters().unwrap().is_some());
     }
 
     #[tokio::test]
@@ -491,6 +832,7 @@ mod tests {
             schema,
             path,
             iox_object_store,
+            None,
         )
         .expect("successfully called read_filter");
 
@@ -501,10 +843,147 @@ mod tests {
         assert_batches_eq!(&expected, &read_batches);
     }
 
+    #[tokio::test]
+    async fn test_read_filter_projection() {
+        let mut generator = ChunkGenerator::new().await;
+        let (chunk, _) = generator.generate().await.unwrap();
+
+        let selection = ["foo_field_i64_normal", "time"];
+        let read_stream = chunk
+            .read_filter(&Predicate::default(), Selection::Some(&selection))
+            .expect("successfully called read_filter");
+        let read_batches = datafusion::physical_plan::common::collect(read_stream)
+            .await
+            .expect("collecting results");
+
+        // Exactly the selected columns are returned.
+        for batch in &read_batches {
+            let names = batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect::<Vec<_>>();
+            assert_eq!(names, selection);
+        }
+
+        let expected = vec![
+            "+----------------------+-----------------------------+",
+            "| foo_field_i64_normal | time                        |",
+            "+----------------------+-----------------------------+",
+            "| -1                   | 1970-01-01T00:00:00.000001Z |",
+            "| 2                    | 1970-01-01T00:00:00.000002Z |",
+            "| 3                    | 1970-01-01T00:00:00.000003Z |",
+            "| 4                    | 1970-01-01T00:00:00.000004Z |",
+            "+----------------------+-----------------------------+",
+        ];
+        assert_batches_eq!(expected, &read_batches);
+
+        // Only the column chunks of the selected columns need to be read.
+        let decoded = chunk.parquet_metadata().decode().unwrap();
+        let projected = decoded.column_chunk_bytes(Selection::Some(&selection));
+        let all = decoded.column_chunk_bytes(Selection::All);
+        assert!(projected > 0);
+        assert!(projected < all, "{} >= {}", projected, all);
+    }
+
+    #[tokio::test]
+    async fn test_read_filter_null_corrupt_columns() {
+        let mut generator = ChunkGenerator::new().await;
+        let (chunk, _) = generator.generate().await.unwrap();
+        let store = Arc::clone(generator.store());
+
+        // Zero the column chunks of one column so that it fails to decode
+        let corrupt = "foo_field_f64_normal";
+        let mut data = load_parquet_from_store(&chunk, Arc::clone(&store))
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(SliceableCursor::new(data.clone())).unwrap();
+        for row_group in reader.metadata().row_groups() {
+            for column in row_group.columns() {
+                if column.column_descr().name() == corrupt {
+                    let start = column
+                        .dictionary_page_offset()
+                        .unwrap_or_else(|| column.data_page_offset())
+                        as usize;
+                    let end = start + column.compressed_size() as usize;
+                    data[start..end].fill(0);
+                }
+            }
+        }
+        Storage::new(Arc::clone(&store))
+            .to_object_store(data, chunk.path())
+            .await
+            .unwrap();
+
+        let registry = metric::Registry::new();
+        let chunk = ParquetChunk::new(
+            chunk.path(),
+            store,
+            chunk.file_size_bytes(),
+            chunk.parquet_metadata(),
+            Arc::from(chunk.table_name()),
+            Arc::from(chunk.partition_key()),
+            ChunkMetrics::new(&registry),
+        )
+        .unwrap();
+        let selection = ["foo_field_i64_normal", corrupt, "time"];
+
+        // By default the corrupt column fails the whole read
+        let read_stream = chunk
+            .read_filter(&Predicate::default(), Selection::Some(&selection))
+            .unwrap();
+        assert!(datafusion::physical_plan::common::collect(read_stream)
+            .await
+            .is_err());
+
+        // Opting in returns the readable columns, with the corrupt one nulled
+        let chunk = chunk.with_null_corrupt_columns(true);
+        let read_stream = chunk
+            .read_filter(&Predicate::default(), Selection::Some(&selection))
+            .unwrap();
+        let read_batches = datafusion::physical_plan::common::collect(read_stream)
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "+----------------------+----------------------+-----------------------------+",
+            "| foo_field_i64_normal | foo_field_f64_normal | time                        |",
+            "+----------------------+----------------------+-----------------------------+",
+            "| -1                   |                      | 1970-01-01T00:00:00.000001Z |",
+            "| 2                    |                      | 1970-01-01T00:00:00.000002Z |",
+            "| 3                    |                      | 1970-01-01T00:00:00.000003Z |",
+            "| 4                    |                      | 1970-01-01T00:00:00.000004Z |",
+            "+----------------------+----------------------+-----------------------------+",
+        ];
+        assert_batches_eq!(expected, &read_batches);
+
+        let corrupt_columns = registry
+            .get_instrument::<Metric<U64Counter>>("parquet_corrupt_columns")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(corrupt_columns, 1);
+    }
+
+    #[test]
+    fn test_check_num_rows() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        assert_eq!(check_num_rows(Arc::clone(&array), 2).unwrap().len(), 2);
+
+        let err = check_num_rows(array, 3).unwrap_err().to_string();
+        assert!(
+            err.contains("column has 2 rows but the file has 3"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_props_have_compression() {
         // should be writing with compression
-        let props = Storage::writer_props(&[]);
+        let props = Storage::writer_props(vec![]);
 
         // arbitrary column name to get default values
         let col_path: ColumnPath = "default".into();
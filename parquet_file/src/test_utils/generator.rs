@@ -79,7 +79,7 @@ impl ChunkGenerator {
         );
 
         let chunk_id = ChunkId::new_test(id as _);
-        let chunk_order = ChunkOrder::new(id).unwrap();
+        let chunk_order = ChunkOrder::new(id.into()).unwrap();
         let chunk_addr = ChunkAddr::new(&self.partition, chunk_id);
 
         let metadata = IoxMetadataOld {
@@ -124,6 +124,11 @@ impl ChunkGenerator {
 
         written_result.as_ref()?;
         let (path, file_size_bytes, parquet_metadata) = written_result.unwrap();
+        let tag_bloom_filters = parquet_metadata
+            .decode()
+            .unwrap()
+            .read_tag_bloom_filters()
+            .unwrap();
 
         let chunk = ParquetChunk::new_from_parts(
             Arc::clone(&self.partition.partition_key),
@@ -133,6 +138,7 @@ impl ChunkGenerator {
             Arc::clone(&self.iox_object_store),
             file_size_bytes,
             Arc::new(parquet_metadata),
+            tag_bloom_filters.map(Arc::new),
             rows,
             ChunkMetrics::new_unregistered(),
         );
@@ -118,6 +118,20 @@ impl Predicate {
         self == &EMPTY_PREDICATE
     }
 
+    /// Restrict the timestamp range of this predicate to exclude rows with
+    /// a timestamp before `cutoff`, e.g. because they have expired.
+    ///
+    /// If the predicate already has a range starting after `cutoff`, the
+    /// tighter bound is kept.
+    pub fn with_retention_cutoff(mut self, cutoff: i64) -> Self {
+        let range = match self.range {
+            Some(range) => range.exclude_before(cutoff),
+            None => TimestampRange::new(cutoff, MAX_NANO_TIME),
+        };
+        self.range = Some(range);
+        self
+    }
+
     /// Return a negated DF logical expression for the given delete predicates
     pub fn negated_expr<S>(delete_predicates: &[S]) -> Option<Expr>
     where
@@ -159,6 +173,11 @@ impl Predicate {
         //    NOT(city != "Boston"  AND temp = 70 AND time_range in [10, 30]),  NOT(state = "NY" AND route != "I90" AND time_range in [20, 50]) which means
         //   [NOT(city = Boston") OR NOT(temp = 70) OR NOT(time_range in [10, 30])], [NOT(state = "NY") OR NOT(route != "I90") OR NOT(time_range in [20, 50])]
         // Note that the "NOT(time_range in [20, 50])]" or "NOT(20 <= time <= 50)"" is replaced with "time < 20 OR time > 50"
+        //
+        // A row is therefore eliminated if it matches *any* of the delete predicates. A row with a
+        // null in the column of a delete expression does not match that expression, so
+        // "NOT(city = Boston)" is written as "NOT(city = Boston) OR (city = Boston) IS NULL", as a
+        // null would otherwise make the row fail the select predicate.
 
         for pred in delete_predicates {
             let pred = pred.as_ref();
@@ -181,9 +200,10 @@ impl Predicate {
 
             // Exprs
             for exp in &pred.exprs {
+                let negated = exp.clone().not().or(exp.clone().is_null());
                 match expr {
-                    None => expr = Some(exp.clone().not()),
-                    Some(e) => expr = Some(e.or(exp.clone().not())),
+                    None => expr = Some(negated),
+                    Some(e) => expr = Some(e.or(negated)),
                 }
             }
 
@@ -317,6 +337,18 @@ impl PredicateBuilder {
         self
     }
 
+    /// Adds a `column IS NULL` expression, filtering out rows where
+    /// `column` has a value
+    pub fn is_null(self, column: &str) -> Self {
+        self.add_expr(col(column).is_null())
+    }
+
+    /// Adds a `column IS NOT NULL` expression, filtering out rows where
+    /// `column` is null
+    pub fn is_not_null(self, column: &str) -> Self {
+        self.add_expr(col(column).is_not_null())
+    }
+
     /// Builds a regex matching expression from the provided column name and
     /// pattern. Values not matching the regex will be filtered out.
     pub fn build_regex_match_expr(self, column: &str, pattern: impl Into<String>) -> Self {
@@ -456,6 +488,7 @@ pub struct BinaryExpr {
 mod tests {
     use super::*;
     use datafusion::logical_plan::{col, lit};
+    use std::sync::Arc;
 
     #[test]
     fn test_default_predicate_is_empty() {
@@ -470,6 +503,37 @@ mod tests {
         assert!(!p.is_empty());
     }
 
+    #[test]
+    fn test_with_retention_cutoff() {
+        // No existing range
+        let predicate = Predicate::default().with_retention_cutoff(100);
+        assert_eq!(
+            predicate.range,
+            Some(TimestampRange::new(100, MAX_NANO_TIME))
+        );
+
+        // The cutoff is tighter than the existing range
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(50, 200)
+            .build()
+            .with_retention_cutoff(100);
+        assert_eq!(predicate.range, Some(TimestampRange::new(100, 200)));
+
+        // The existing range is tighter than the cutoff
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(150, 200)
+            .build()
+            .with_retention_cutoff(100);
+        assert_eq!(predicate.range, Some(TimestampRange::new(150, 200)));
+
+        // The existing range is entirely older than the cutoff
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(10, 50)
+            .build()
+            .with_retention_cutoff(100);
+        assert_eq!(predicate.range, Some(TimestampRange::new(50, 50)));
+    }
+
     #[test]
     fn test_pushdown_predicates() {
         let mut filters = vec![];
@@ -645,4 +709,45 @@ mod tests {
         // rewrite
         assert_eq!(p.clear_timestamp_if_max_range(), expected);
     }
+
+    #[test]
+    fn test_is_null_is_not_null() {
+        let p = PredicateBuilder::new()
+            .is_null("foo")
+            .is_not_null("bar")
+            .build();
+
+        assert_eq!(
+            p.exprs,
+            vec![col("foo").is_null(), col("bar").is_not_null()]
+        );
+        assert_eq!(
+            p.to_string(),
+            "Predicate exprs: [#foo IS NULL, #bar IS NOT NULL]"
+        );
+    }
+
+    #[test]
+    fn test_negated_expr_multiple_deletes() {
+        let delete1 = PredicateBuilder::new()
+            .timestamp_range(0, 10)
+            .add_expr(col("foo").eq(lit(1)))
+            .build();
+        let delete2 = PredicateBuilder::new()
+            .add_expr(col("bar").eq(lit(2)))
+            .add_expr(col("baz").eq(lit(3)))
+            .build();
+
+        let negated = |e: Expr| e.clone().not().or(e.is_null());
+        let expected = col(TIME_COLUMN_NAME)
+            .lt(lit_timestamp_nano(0))
+            .or(col(TIME_COLUMN_NAME).gt(lit_timestamp_nano(10)))
+            .or(negated(col("foo").eq(lit(1))))
+            .and(negated(col("bar").eq(lit(2))).or(negated(col("baz").eq(lit(3)))));
+
+        assert_eq!(
+            Predicate::negated_expr(&[Arc::new(delete1), Arc::new(delete2)]),
+            Some(expected)
+        );
+    }
 }
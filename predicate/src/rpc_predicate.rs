@@ -24,11 +24,12 @@ use std::sync::Arc;
 /// filled in with a literal for the respective name of that field
 pub const MEASUREMENT_COLUMN_NAME: &str = "_measurement";
 
-/// Any equality expressions using this column name are removed and replaced
-/// with projections on the specified column.
+/// Any equality expressions, and inequality expressions that are top-level
+/// conjuncts, using this column name are removed and replaced with projections
+/// on the specified (or all other) columns.
 ///
 /// This is required to support predicates like
-/// `_field` = temperature
+/// `_field` = temperature or `_field` != temperature
 pub const FIELD_COLUMN_NAME: &str = "_field";
 
 /// Any column references to this name are rewritten to be a disjunctive set of
@@ -80,7 +81,9 @@ impl InfluxRpcPredicate {
 
     /// Convert to a list of [`Predicate`] to apply to specific tables
     ///
-    /// Returns a list of [`Predicate`] and their associated table name
+    /// Returns a list of [`Predicate`] and their associated table name. The
+    /// predicates exclude data that has expired, see
+    /// [`QueryDatabaseMeta::retention_cutoff`].
     pub fn table_predicates<D: QueryDatabaseMeta>(
         &self,
         table_info: &D,
@@ -89,11 +92,16 @@ impl InfluxRpcPredicate {
             Some(table_names) => itertools::Either::Left(table_names.iter().cloned()),
             None => itertools::Either::Right(table_info.table_names().into_iter()),
         };
+        let retention_cutoff = table_info.retention_cutoff();
 
         table_names
             .map(|table| {
                 let schema = table_info.table_schema(&table);
                 let predicate = normalize_predicate(&table, schema, &self.inner);
+                let predicate = match retention_cutoff {
+                    Some(cutoff) => predicate.with_retention_cutoff(cutoff),
+                    None => predicate,
+                };
 
                 (table, predicate)
             })
@@ -118,6 +126,13 @@ pub trait QueryDatabaseMeta {
 
     /// Schema for a specific table if the table exists.
     fn table_schema(&self, table_name: &str) -> Option<Arc<Schema>>;
+
+    /// Timestamp in nanoseconds since the epoch before which data has
+    /// expired and must not be returned by queries, if the DB has a
+    /// retention period
+    fn retention_cutoff(&self) -> Option<i64> {
+        None
+    }
 }
 
 /// Predicate that has been "specialized" / normalized for a
@@ -128,7 +143,8 @@ pub trait QueryDatabaseMeta {
 /// * any expression on the [VALUE_COLUMN_NAME] column is rewritten to be
 /// applied across all field columns.
 /// * any expression on the [FIELD_COLUMN_NAME] is rewritten to be
-/// applied for the particular fields.
+/// applied for the particular fields. A top-level `AND _field != "name"`
+/// projects all field columns of the table except "name".
 ///
 /// For example if the original predicate was
 /// ```text
@@ -157,6 +173,7 @@ fn normalize_predicate(
 ) -> Predicate {
     let mut predicate = predicate.clone();
     let mut field_projections = BTreeSet::new();
+    let mut field_exclusions = BTreeSet::new();
     let mut field_value_exprs = vec![];
 
     predicate.exprs = predicate
@@ -168,10 +185,11 @@ fn normalize_predicate(
         // augment field projections with conditions using `CASE` statements.
         .map(|e| rewrite_field_value_references(&mut field_value_exprs, e))
         .map(|e| {
-            // Rewrite any references to `_field = a_field_name` with a literal true
-            // and keep track of referenced field names to add to the field
+            // Rewrite any references to `_field = a_field_name` (or
+            // `_field != a_field_name`) with a literal true and keep track of
+            // referenced field names to add to (or remove from) the field
             // column projection set.
-            rewrite_field_column_references(&mut field_projections, e)
+            rewrite_field_column_references(&mut field_projections, &mut field_exclusions, e)
         })
         .map(|e| {
             // apply IOx specific rewrites (that unlock other simplifications)
@@ -194,7 +212,25 @@ fn normalize_predicate(
     // Store any field value (`_value`) expressions on the `Predicate`.
     predicate.value_expr = field_value_exprs;
 
-    if !field_projections.is_empty() {
+    if !field_exclusions.is_empty() {
+        // Exclusions restrict the explicitly projected fields, or if there are
+        // none, all the fields of the table
+        if field_projections.is_empty() {
+            field_projections = match &predicate.field_columns {
+                Some(field_columns) => field_columns.clone(),
+                None => schema
+                    .iter()
+                    .flat_map(|schema| schema.fields_iter())
+                    .map(|field| field.name().to_string())
+                    .collect(),
+            };
+        }
+        field_projections.retain(|name| !field_exclusions.contains(name));
+
+        // Every field may have been excluded, in which case no field is
+        // projected
+        predicate.field_columns = Some(field_projections);
+    } else if !field_projections.is_empty() {
         match &mut predicate.field_columns {
             Some(field_columns) => field_columns.extend(field_projections.into_iter()),
             None => predicate.field_columns = Some(field_projections),
@@ -316,15 +352,51 @@ impl<'a> ExprRewriter for FieldValueRewriter<'a> {
 ///
 /// For example, the expression `_field = "load4"` is removed from the
 /// normalised expression, and a column "load4" added to the predicate
-/// projection.
+/// projection. The expression `_field != "load4"` is removed as well, and
+/// "load4" added to `field_exclusions`, but only if it is a top-level
+/// conjunct of `expr`: excluding the field from the projection is only
+/// equivalent to the expression if every row must satisfy it, which is not
+/// the case for `_field != "load4" OR city = "x"` or `NOT (_field != "load4")`.
 fn rewrite_field_column_references(
     field_projections: &'_ mut BTreeSet<String>,
+    field_exclusions: &'_ mut BTreeSet<String>,
     expr: Expr,
 ) -> Expr {
+    let expr = rewrite_field_exclusions(field_exclusions, expr);
     let mut rewriter = FieldColumnRewriter { field_projections };
     expr.rewrite(&mut rewriter).expect("rewrite is infallible")
 }
 
+/// Rewrites the `_field != "name"` top-level conjuncts of `expr` as a boolean
+/// true literal, adding "name" to `field_exclusions`.
+fn rewrite_field_exclusions(field_exclusions: &mut BTreeSet<String>, expr: Expr) -> Expr {
+    match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => Expr::BinaryExpr {
+            left: Box::new(rewrite_field_exclusions(field_exclusions, *left)),
+            op: Operator::And,
+            right: Box::new(rewrite_field_exclusions(field_exclusions, *right)),
+        },
+        Expr::BinaryExpr {
+            ref left,
+            op: Operator::NotEq,
+            ref right,
+        } => match (&**left, &**right) {
+            (Expr::Column(inner), Expr::Literal(ScalarValue::Utf8(Some(name))))
+                if inner.name == FIELD_COLUMN_NAME =>
+            {
+                field_exclusions.insert(name.to_owned());
+                lit(true)
+            }
+            _ => expr,
+        },
+        _ => expr,
+    }
+}
+
 struct FieldColumnRewriter<'a> {
     field_projections: &'a mut BTreeSet<String>,
 }
@@ -339,7 +411,6 @@ impl<'a> ExprRewriter for FieldColumnRewriter<'a> {
             } => {
                 if let Expr::Column(inner) = &**left {
                     if inner.name != FIELD_COLUMN_NAME || op != Operator::Eq {
-                        // TODO(edd): add support for !=
                         return Ok(expr);
                     }
 
@@ -417,27 +488,31 @@ mod tests {
 
     #[test]
     fn test_field_column_rewriter() {
-        let mut field_columns = BTreeSet::new();
-        let mut rewriter = FieldColumnRewriter {
-            field_projections: &mut field_columns,
-        };
+        let mut field_projections = BTreeSet::new();
+        let mut field_exclusions = BTreeSet::new();
+
+        let not_foo = || binary_expr(col(FIELD_COLUMN_NAME), Operator::NotEq, lit("foo"));
+        let city = || binary_expr(col("city"), Operator::Eq, lit("x"));
 
         let cases = vec![
             (
                 binary_expr(col("f1"), Operator::Eq, lit(1.82)),
                 binary_expr(col("f1"), Operator::Eq, lit(1.82)),
                 vec![],
+                vec![],
             ),
+            (not_foo(), lit(true), vec![], vec!["foo"]),
             (
-                // TODO - should be rewritten and project onto all field columns
-                binary_expr(col(FIELD_COLUMN_NAME), Operator::NotEq, lit("foo")),
-                binary_expr(col(FIELD_COLUMN_NAME), Operator::NotEq, lit("foo")),
+                binary_expr(col(FIELD_COLUMN_NAME), Operator::Lt, lit("foo")),
+                binary_expr(col(FIELD_COLUMN_NAME), Operator::Lt, lit("foo")),
                 vec![],
+                vec!["foo"],
             ),
             (
                 binary_expr(col(FIELD_COLUMN_NAME), Operator::Eq, lit("f1")),
                 lit(true),
                 vec!["f1"],
+                vec!["foo"],
             ),
             (
                 binary_expr(
@@ -447,18 +522,133 @@ mod tests {
                 ),
                 binary_expr(lit(true), Operator::Or, lit(true)),
                 vec!["f1", "f2"],
+                vec!["foo"],
+            ),
+            // `_field != "bar"` conjuncts are rewritten
+            (
+                binary_expr(
+                    city(),
+                    Operator::And,
+                    binary_expr(col(FIELD_COLUMN_NAME), Operator::NotEq, lit("bar")),
+                ),
+                binary_expr(city(), Operator::And, lit(true)),
+                vec!["f1", "f2"],
+                vec!["bar", "foo"],
+            ),
+            // but `_field != "foo"` under OR or NOT is not
+            (
+                binary_expr(not_foo(), Operator::Or, city()),
+                binary_expr(not_foo(), Operator::Or, city()),
+                vec!["f1", "f2"],
+                vec!["bar", "foo"],
+            ),
+            (
+                Expr::Not(Box::new(binary_expr(
+                    col(FIELD_COLUMN_NAME),
+                    Operator::NotEq,
+                    lit("baz"),
+                ))),
+                Expr::Not(Box::new(binary_expr(
+                    col(FIELD_COLUMN_NAME),
+                    Operator::NotEq,
+                    lit("baz"),
+                ))),
+                vec!["f1", "f2"],
+                vec!["bar", "foo"],
             ),
         ];
 
-        for (input, exp_expr, field_columns) in cases {
-            let rewritten = input.rewrite(&mut rewriter).unwrap();
+        for (input, exp_expr, exp_field_projections, exp_field_exclusions) in cases {
+            let rewritten = rewrite_field_column_references(
+                &mut field_projections,
+                &mut field_exclusions,
+                input,
+            );
 
             assert_eq!(rewritten, exp_expr);
-            let mut exp_field_columns = field_columns
+            let exp_field_projections = exp_field_projections
+                .into_iter()
+                .map(String::from)
+                .collect::<BTreeSet<String>>();
+            assert_eq!(field_projections, exp_field_projections);
+            let exp_field_exclusions = exp_field_exclusions
                 .into_iter()
                 .map(String::from)
                 .collect::<BTreeSet<String>>();
-            assert_eq!(rewriter.field_projections, &mut exp_field_columns);
+            assert_eq!(field_exclusions, exp_field_exclusions);
         }
     }
+
+    #[test]
+    fn test_normalize_field_exclusion() {
+        let schema = Arc::new(
+            schema::builder::SchemaBuilder::new()
+                .tag("t1")
+                .field("f1", arrow::datatypes::DataType::Float64)
+                .field("f2", arrow::datatypes::DataType::Float64)
+                .field("f3", arrow::datatypes::DataType::Float64)
+                .timestamp()
+                .build()
+                .unwrap(),
+        );
+
+        let exclude = |field: &str| {
+            crate::predicate::PredicateBuilder::default()
+                .add_expr(col(FIELD_COLUMN_NAME).not_eq(lit(field)))
+                .build()
+        };
+        let field_columns = |fields: &[&str]| {
+            Some(
+                fields
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<BTreeSet<_>>(),
+            )
+        };
+
+        // projects all other fields of the table
+        let predicate = normalize_predicate("cpu", Some(Arc::clone(&schema)), &exclude("f2"));
+        assert_eq!(predicate.field_columns, field_columns(&["f1", "f3"]));
+
+        // restricts an explicit projection
+        let predicate = crate::predicate::PredicateBuilder::default()
+            .field_columns(vec!["f1", "f2"])
+            .add_expr(col(FIELD_COLUMN_NAME).not_eq(lit("f2")))
+            .build();
+        let predicate = normalize_predicate("cpu", Some(Arc::clone(&schema)), &predicate);
+        assert_eq!(predicate.field_columns, field_columns(&["f1"]));
+
+        // excluding a field the table does not have projects all fields
+        let predicate = normalize_predicate("cpu", Some(Arc::clone(&schema)), &exclude("f4"));
+        assert_eq!(predicate.field_columns, field_columns(&["f1", "f2", "f3"]));
+
+        // unknown table
+        let predicate = normalize_predicate("cpu", None, &exclude("f2"));
+        assert_eq!(predicate.field_columns, field_columns(&[]));
+    }
+
+    #[test]
+    fn test_table_predicates_retention_cutoff() {
+        struct Expiring;
+
+        impl QueryDatabaseMeta for Expiring {
+            fn table_names(&self) -> Vec<String> {
+                vec!["cpu".to_string()]
+            }
+
+            fn table_schema(&self, _table_name: &str) -> Option<Arc<Schema>> {
+                None
+            }
+
+            fn retention_cutoff(&self) -> Option<i64> {
+                Some(100)
+            }
+        }
+
+        let predicate = InfluxRpcPredicate::new(None, Predicate::default());
+        let table_predicates = predicate.table_predicates(&Expiring);
+        assert_eq!(table_predicates.len(), 1);
+        assert_eq!(table_predicates[0].0, "cpu");
+        assert_eq!(table_predicates[0].1.range.unwrap().start(), 100);
+    }
 }
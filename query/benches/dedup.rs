@@ -0,0 +1,144 @@
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, DictionaryArray, Int64Array, TimestampNanosecondArray};
+use arrow::datatypes::{DataType, Int32Type};
+use arrow::record_batch::RecordBatch;
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use datafusion::datasource::TableProvider;
+use query::exec::{Executor, ExecutorType};
+use query::provider::ProviderBuilder;
+use query::test::TestChunk;
+use query::QueryChunkMeta;
+use tokio::runtime::Runtime;
+
+/// The number of rows in each chunk.
+const ROWS_PER_CHUNK: usize = 10_000;
+
+/// The number of distinct `tag1` values.
+const NUM_TAG_VALUES: usize = 10;
+
+/// Build chunk `idx` of `num_chunks` overlapping chunks.
+///
+/// The first `duplicate_ratio` of the rows of every chunk share the same
+/// primary keys (and so are duplicates across all chunks), while the
+/// remaining rows have keys unique to the chunk. The unique keys of all
+/// chunks are interleaved so that the chunk time ranges always overlap,
+/// forcing a dedup merge regardless of the duplicate ratio.
+fn make_chunk(idx: usize, num_chunks: usize, duplicate_ratio: f64) -> Arc<TestChunk> {
+    let num_duplicates = (ROWS_PER_CHUNK as f64 * duplicate_ratio) as usize;
+
+    let keys: Vec<i64> = (0..ROWS_PER_CHUNK)
+        .map(|row| {
+            if row < num_duplicates {
+                row
+            } else {
+                ROWS_PER_CHUNK + row * num_chunks + idx
+            }
+        })
+        .map(|key| key as i64)
+        .collect();
+
+    let min = *keys.iter().min().unwrap();
+    let max = *keys.iter().max().unwrap();
+
+    let chunk = TestChunk::new("t")
+        .with_id(idx as u128)
+        .with_order(idx as u64 + 1)
+        .with_time_column_with_full_stats(
+            Some(min),
+            Some(max),
+            ROWS_PER_CHUNK as u64,
+            NonZeroU64::new(ROWS_PER_CHUNK as u64),
+        )
+        .with_tag_column_with_full_stats(
+            "tag1",
+            Some("tag0"),
+            Some(&format!("tag{}", NUM_TAG_VALUES - 1)),
+            ROWS_PER_CHUNK as u64,
+            NonZeroU64::new(NUM_TAG_VALUES as u64),
+        )
+        .with_i64_field_column("field_int");
+
+    let schema = chunk.schema();
+    let columns = schema
+        .iter()
+        .map(|(_, field)| match field.data_type() {
+            DataType::Int64 => Arc::new(Int64Array::from(keys.clone())) as ArrayRef,
+            DataType::Timestamp(_, _) => {
+                Arc::new(TimestampNanosecondArray::from_vec(keys.clone(), None)) as ArrayRef
+            }
+            DataType::Dictionary(_, _) => Arc::new(
+                keys.iter()
+                    .map(|key| format!("tag{}", *key as usize % NUM_TAG_VALUES))
+                    .collect::<DictionaryArray<Int32Type>>(),
+            ) as ArrayRef,
+            t => panic!("unexpected data type {:?}", t),
+        })
+        .collect();
+    let batch = RecordBatch::try_new(schema.as_arrow(), columns).unwrap();
+
+    Arc::new(chunk.with_record_batch(batch))
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn dedup_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dedup");
+    group.sample_size(10);
+
+    for num_chunks in [2, 8, 32] {
+        for duplicate_ratio in [0.0, 0.5, 0.9] {
+            benchmark_dedup(&mut group, num_chunks, duplicate_ratio);
+        }
+    }
+
+    group.finish();
+}
+
+fn benchmark_dedup(group: &mut BenchmarkGroup<WallTime>, num_chunks: usize, duplicate_ratio: f64) {
+    let chunks: Vec<_> = (0..num_chunks)
+        .map(|idx| make_chunk(idx, num_chunks, duplicate_ratio))
+        .collect();
+    let schema = chunks[0].schema();
+
+    let executor = Executor::new(1);
+
+    group.throughput(Throughput::Elements((num_chunks * ROWS_PER_CHUNK) as u64));
+    group.bench_function(
+        BenchmarkId::new(
+            format!("{} chunks", num_chunks),
+            format!("{}% duplicates", duplicate_ratio * 100.0),
+        ),
+        |b| {
+            b.to_async(runtime()).iter(|| async {
+                let provider = chunks
+                    .iter()
+                    .fold(
+                        ProviderBuilder::new("t", Arc::clone(&schema)).add_no_op_pruner(),
+                        |builder, chunk| builder.add_chunk(Arc::clone(chunk)),
+                    )
+                    .build()
+                    .unwrap();
+
+                let plan = provider.scan(&None, &[], None).await.unwrap();
+                executor
+                    .new_context(ExecutorType::Query)
+                    .collect(plan)
+                    .await
+                    .unwrap()
+            })
+        },
+    );
+}
+
+criterion_group!(benches, dedup_benchmarks);
+criterion_main!(benches);
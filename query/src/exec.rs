@@ -1,11 +1,15 @@
 //! This module handles the manipulation / execution of storage
 //! plans. This is currently implemented using DataFusion, and this
 //! interface abstracts away many of the details
+pub mod cancel;
 pub(crate) mod context;
 pub mod field;
 pub mod fieldlist;
+mod multi_namespace;
 mod non_null_checker;
+pub mod progress;
 mod query_tracing;
+mod rechunk;
 mod schema_pivot;
 pub mod seriesset;
 pub(crate) mod split;
@@ -13,10 +17,11 @@ pub mod stringset;
 mod task;
 pub use context::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
 
-use std::sync::Arc;
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 
 use datafusion::{
     self,
+    catalog::catalog::CatalogProvider,
     execution::{runtime_env::RuntimeEnv, DiskManager, MemoryManager},
     logical_plan::{normalize_col, plan::Extension, Expr, LogicalPlan},
     prelude::ExecutionConfig,
@@ -24,8 +29,12 @@ use datafusion::{
 
 pub use context::{IOxExecutionConfig, IOxExecutionContext};
 use schema_pivot::SchemaPivotNode;
+use tokio_util::sync::CancellationToken;
 
-use self::{non_null_checker::NonNullCheckerNode, split::StreamSplitNode, task::DedicatedExecutor};
+use self::{
+    cancel::QueryCancellationRegistry, multi_namespace::MultiNamespaceCatalog,
+    non_null_checker::NonNullCheckerNode, split::StreamSplitNode, task::DedicatedExecutor,
+};
 
 /// Configuration for an Executor
 #[derive(Debug, Clone)]
@@ -35,6 +44,29 @@ pub struct ExecutorConfig {
 
     /// Target parallelism for query execution
     pub target_query_partitions: usize,
+
+    /// Maximum number of chunks of a table read concurrently by a scan,
+    /// unlimited if `None`
+    pub scan_parallelism: Option<NonZeroUsize>,
+
+    /// Queries taking longer than this to complete are logged as slow
+    /// queries, none are if `None`
+    pub slow_query_threshold: Option<Duration>,
+
+    /// Number of rows of the batches of query output streams, which are
+    /// re-chunked to this size. Batches are emitted as produced by the plan
+    /// if `None`
+    pub target_batch_size: Option<NonZeroUsize>,
+
+    /// Keep all chunks of the queried tables rather than pruning those
+    /// whose metadata shows they cannot match the predicate. The predicate
+    /// is still applied to the data, so this must not change query results:
+    /// only intended for debugging pruning problems.
+    pub disable_pruning: bool,
+
+    /// Record the latency, rows and bytes of the chunk reads of queries,
+    /// per table and chunk type
+    pub chunk_read_metrics: bool,
 }
 
 /// Handles executing DataFusion plans, and marshalling the results into rust
@@ -59,6 +91,9 @@ pub struct Executor {
 
     /// The DataFusion DiskManager used for all queries run in this executor
     disk_manager: Arc<DiskManager>,
+
+    /// Tokens to cancel the queries of a namespace
+    query_cancellation: QueryCancellationRegistry,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -76,6 +111,11 @@ impl Executor {
         Self::new_with_config(ExecutorConfig {
             num_threads,
             target_query_partitions: num_threads,
+            scan_parallelism: None,
+            slow_query_threshold: None,
+            target_batch_size: None,
+            disable_pruning: false,
+            chunk_read_metrics: false,
         })
     }
 
@@ -92,6 +132,7 @@ impl Executor {
             config,
             memory_manager: runtime.memory_manager,
             disk_manager: runtime.disk_manager,
+            query_cancellation: Default::default(),
         }
     }
 
@@ -104,6 +145,7 @@ impl Executor {
             .with_target_partitions(self.config.target_query_partitions)
             .with_memory_manager(Arc::clone(&self.memory_manager))
             .with_disk_manager(Arc::clone(&self.disk_manager))
+            .with_target_batch_size(self.config.target_batch_size)
     }
 
     /// Create a new execution context, suitable for executing a new query or system task
@@ -113,6 +155,86 @@ impl Executor {
         self.new_execution_config(executor_type).build()
     }
 
+    /// Create a new execution context like [`Self::new_context`], but that
+    /// registers and resolves tables in the given `catalog` and `schema`
+    /// rather than [`DEFAULT_CATALOG`] and [`DEFAULT_SCHEMA`].
+    ///
+    /// This allows the same table name to be used by contexts for different
+    /// namespaces without the tables colliding.
+    pub fn new_context_with_schema(
+        &self,
+        executor_type: ExecutorType,
+        catalog: &str,
+        schema: &str,
+    ) -> IOxExecutionContext {
+        self.new_execution_config(executor_type)
+            .with_default_catalog_and_schema(catalog, schema)
+            .build()
+    }
+
+    /// Create a new execution context like [`Self::new_context`], but that
+    /// registers the tables of several namespaces, each as a schema of
+    /// [`DEFAULT_CATALOG`] named after the namespace.
+    ///
+    /// `namespaces` maps namespace names to their catalog, e.g. a `Db`,
+    /// whose [`DEFAULT_SCHEMA`] tables are registered. Table names must be
+    /// qualified with the namespace in SQL, which allows queries across
+    /// namespaces such as `SELECT * FROM ns1.t UNION ALL SELECT * FROM ns2.t`.
+    pub fn new_multi_namespace_context(
+        &self,
+        executor_type: ExecutorType,
+        namespaces: impl IntoIterator<Item = (String, Arc<dyn CatalogProvider>)>,
+    ) -> IOxExecutionContext {
+        let catalog = MultiNamespaceCatalog::new(namespaces);
+        self.new_execution_config(executor_type)
+            .with_default_catalog(Arc::new(catalog))
+            .build()
+    }
+
+    /// The configuration of this executor
+    pub fn config(&self) -> &ExecutorConfig {
+        &self.config
+    }
+
+    /// Maximum number of chunks of a table read concurrently by a scan, see
+    /// [`ExecutorConfig::scan_parallelism`]
+    pub fn scan_parallelism(&self) -> Option<NonZeroUsize> {
+        self.config.scan_parallelism
+    }
+
+    /// Returns a token for a new query against `namespace`, cancelled by
+    /// [`Self::cancel_queries`]
+    ///
+    /// Pass it to [`IOxExecutionConfig::with_cancellation_token`] to make
+    /// the query cancellable.
+    pub fn query_cancellation_token(&self, namespace: &str) -> CancellationToken {
+        self.query_cancellation.token(namespace)
+    }
+
+    /// Cancels all running queries against `namespace`, e.g. because it is
+    /// being dropped. Returns false if no query against it was started.
+    pub fn cancel_queries(&self, namespace: &str) -> bool {
+        self.query_cancellation.cancel(namespace)
+    }
+
+    /// Latency above which completed queries are logged, see
+    /// [`ExecutorConfig::slow_query_threshold`]
+    pub fn slow_query_threshold(&self) -> Option<Duration> {
+        self.config.slow_query_threshold
+    }
+
+    /// Whether queries keep all chunks rather than pruning them, see
+    /// [`ExecutorConfig::disable_pruning`]
+    pub fn disable_pruning(&self) -> bool {
+        self.config.disable_pruning
+    }
+
+    /// Whether queries record chunk read metrics, see
+    /// [`ExecutorConfig::chunk_read_metrics`]
+    pub fn chunk_read_metrics(&self) -> bool {
+        self.config.chunk_read_metrics
+    }
+
     /// Return the execution pool  of the specified type
     fn executor(&self, executor_type: ExecutorType) -> &DedicatedExecutor {
         match executor_type {
@@ -247,8 +369,9 @@ mod tests {
     use arrow::{
         array::{ArrayRef, Int64Array, StringBuilder},
         datatypes::{DataType, Field, Schema, SchemaRef},
+        util::pretty::pretty_format_batches,
     };
-    use datafusion::logical_plan::LogicalPlanBuilder;
+    use datafusion::{datasource::MemTable, logical_plan::LogicalPlanBuilder};
     use stringset::StringSet;
 
     use super::*;
@@ -433,6 +556,117 @@ mod tests {
         Arc::new(builder.finish())
     }
 
+    #[tokio::test]
+    async fn executor_context_with_schema() {
+        let exec = Executor::new(1);
+
+        let ctx1 = exec.new_context_with_schema(ExecutorType::Query, "public", "bananas");
+        let ctx2 = exec.new_context_with_schema(ExecutorType::Query, "public", "platanos");
+
+        // Register a table called "t" in each context, with different data
+        for (ctx, value) in [(&ctx1, "foo"), (&ctx2, "bar")] {
+            let batch = RecordBatch::try_from_iter_with_nullable(vec![(
+                "a",
+                to_string_array(&[value]),
+                true,
+            )])
+            .expect("created new record batch");
+            let table = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+            ctx.inner().register_table("t", Arc::new(table)).unwrap();
+        }
+
+        async fn query(ctx: &IOxExecutionContext, sql: &str) -> String {
+            let plan = ctx.prepare_sql(sql).await.unwrap();
+            let batches = ctx.collect(plan).await.unwrap();
+            pretty_format_batches(&batches).unwrap().to_string()
+        }
+
+        let expected_foo = "+-----+\n| a   |\n+-----+\n| foo |\n+-----+";
+        let expected_bar = "+-----+\n| a   |\n+-----+\n| bar |\n+-----+";
+
+        assert_eq!(query(&ctx1, "SELECT a FROM t").await, expected_foo);
+        assert_eq!(query(&ctx2, "SELECT a FROM t").await, expected_bar);
+
+        // The tables are registered in their respective schemas
+        assert_eq!(
+            query(&ctx1, "SELECT a FROM public.bananas.t").await,
+            expected_foo
+        );
+        assert_eq!(
+            query(&ctx2, "SELECT a FROM public.platanos.t").await,
+            expected_bar
+        );
+    }
+
+    #[tokio::test]
+    async fn executor_multi_namespace_context() {
+        let exec = Executor::new(1);
+
+        // Register a table called "t" in the catalog of each namespace, with
+        // different data
+        let mut namespaces = vec![];
+        for (namespace, value) in [("ns1", "foo"), ("ns2", "bar")] {
+            let ctx = exec.new_context(ExecutorType::Query);
+            let batch = RecordBatch::try_from_iter_with_nullable(vec![(
+                "a",
+                to_string_array(&[value]),
+                true,
+            )])
+            .expect("created new record batch");
+            let table = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+            ctx.inner().register_table("t", Arc::new(table)).unwrap();
+
+            let catalog = ctx.inner().catalog(DEFAULT_CATALOG).unwrap();
+            namespaces.push((namespace.to_string(), catalog));
+        }
+
+        let ctx = exec.new_multi_namespace_context(ExecutorType::Query, namespaces);
+
+        let sql = "SELECT a FROM ns1.t UNION ALL SELECT a FROM ns2.t ORDER BY a";
+        let plan = ctx.prepare_sql(sql).await.unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+        let expected = "+-----+\n| a   |\n+-----+\n| bar |\n| foo |\n+-----+";
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected
+        );
+
+        // Tables must be qualified with their namespace
+        ctx.prepare_sql("SELECT a FROM t").await.unwrap_err();
+        ctx.prepare_sql("SELECT a FROM ns3.t").await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn executor_target_batch_size() {
+        let exec = Executor::new_with_config(ExecutorConfig {
+            num_threads: 1,
+            target_query_partitions: 1,
+            scan_parallelism: None,
+            slow_query_threshold: None,
+            target_batch_size: NonZeroUsize::new(10),
+            disable_pruning: false,
+            chunk_read_metrics: false,
+        });
+
+        // Many small batches of 3 rows each
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let batches = (0..25)
+            .map(|_| {
+                let data = to_string_array(&["foo", "bar", "baz"]);
+                RecordBatch::try_new(Arc::clone(&schema), vec![data]).unwrap()
+            })
+            .collect();
+        let plan = make_plan(schema, batches);
+
+        let ctx = exec.new_context(ExecutorType::Query);
+        let physical_plan = ctx.prepare_plan(&plan).await.unwrap();
+        let output = ctx.collect(physical_plan).await.unwrap();
+
+        // Only the last batch is smaller than the target size
+        let sizes: Vec<_> = output.iter().map(|b| b.num_rows()).collect();
+        assert_eq!(sizes, vec![10, 10, 10, 10, 10, 10, 10, 5]);
+    }
+
     // creates a DataFusion plan that reads the RecordBatches into memory
     fn make_plan(schema: SchemaRef, data: Vec<RecordBatch>) -> LogicalPlan {
         let projection = None;
@@ -0,0 +1,158 @@
+//! Cancellation of running queries, e.g. when the namespace they query is
+//! dropped
+
+use std::{collections::HashMap, pin::Pin};
+
+use arrow::{datatypes::SchemaRef, error::ArrowError, record_batch::RecordBatch};
+use datafusion::{
+    error::DataFusionError,
+    physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::{Future, FutureExt, Stream, StreamExt};
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// The message of the error returned by cancelled queries
+const CANCELLED_MESSAGE: &str = "query cancelled";
+
+/// Returns the error with which cancelled queries fail
+pub fn cancelled_error() -> DataFusionError {
+    DataFusionError::Execution(CANCELLED_MESSAGE.to_string())
+}
+
+/// Hands out a [`CancellationToken`] per query so all queries of a
+/// namespace can be cancelled at once.
+#[derive(Debug, Default)]
+pub struct QueryCancellationRegistry {
+    namespaces: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl QueryCancellationRegistry {
+    /// Returns a token for a new query against `namespace`, which is
+    /// cancelled by [`Self::cancel`] for the same namespace
+    pub fn token(&self, namespace: &str) -> CancellationToken {
+        self.namespaces
+            .lock()
+            .entry(namespace.to_string())
+            .or_insert_with(CancellationToken::new)
+            .child_token()
+    }
+
+    /// Cancels all queries against `namespace` whose token was obtained
+    /// before this call, returning false if there were none.
+    ///
+    /// Queries started afterwards, e.g. against a re-created namespace of
+    /// the same name, are unaffected.
+    pub fn cancel(&self, namespace: &str) -> bool {
+        match self.namespaces.lock().remove(namespace) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Stream wrapper that ends with a "query cancelled" error as soon as
+/// its token is cancelled, even while waiting for the inner stream.
+pub(crate) struct CancellableStream {
+    inner: SendableRecordBatchStream,
+    cancelled: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl CancellableStream {
+    pub(crate) fn new(inner: SendableRecordBatchStream, cancel: CancellationToken) -> Self {
+        Self {
+            inner,
+            cancelled: Some(Box::pin(async move { cancel.cancelled().await })),
+        }
+    }
+}
+
+impl RecordBatchStream for CancellableStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for CancellableStream {
+    type Item = arrow::error::Result<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let cancelled = match self.cancelled.as_mut() {
+            Some(cancelled) => cancelled,
+            // Already cancelled, the error has been returned
+            None => return std::task::Poll::Ready(None),
+        };
+
+        if cancelled.poll_unpin(cx).is_ready() {
+            self.cancelled = None;
+            let err = ArrowError::ExternalError(Box::new(cancelled_error()));
+            return std::task::Poll::Ready(Some(Err(err)));
+        }
+
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Wraps `stream` so it ends early with an error when `cancel` is
+/// cancelled
+pub(crate) fn cancellable(
+    stream: SendableRecordBatchStream,
+    cancel: CancellationToken,
+) -> SendableRecordBatchStream {
+    Box::pin(CancellableStream::new(stream, cancel)) as _
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::datatypes::Schema;
+    use datafusion_util::MemoryStream;
+
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let registry = QueryCancellationRegistry::default();
+        let a1 = registry.token("a");
+        let a2 = registry.token("a");
+        let b = registry.token("b");
+
+        assert!(registry.cancel("a"));
+        assert!(a1.is_cancelled());
+        assert!(a2.is_cancelled());
+        assert!(!b.is_cancelled());
+
+        // Nothing left to cancel
+        assert!(!registry.cancel("a"));
+
+        // New queries are unaffected by the previous cancellation
+        let a3 = registry.token("a");
+        assert!(!a3.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_cancellable_stream() {
+        let schema = Arc::new(Schema::empty());
+        let batch = RecordBatch::new_empty(Arc::clone(&schema));
+        let inner = Box::pin(MemoryStream::new_with_schema(
+            vec![batch.clone(), batch],
+            schema,
+        ));
+
+        let cancel = CancellationToken::new();
+        let mut stream = cancellable(inner, cancel.clone());
+        stream.next().await.unwrap().unwrap();
+
+        cancel.cancel();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("query cancelled"), "{}", err);
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -2,7 +2,14 @@
 //! DataFusion
 
 use async_trait::async_trait;
-use std::{convert::TryInto, fmt, sync::Arc};
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use arrow::record_batch::RecordBatch;
 
@@ -13,6 +20,7 @@ use datafusion::{
     logical_plan::{LogicalPlan, UserDefinedLogicalNode},
     physical_plan::{
         coalesce_partitions::CoalescePartitionsExec,
+        display::DisplayableExecutionPlan,
         displayable,
         planner::{DefaultPhysicalPlanner, ExtensionPlanner},
         ExecutionPlan, PhysicalPlanner, SendableRecordBatchStream,
@@ -21,16 +29,21 @@ use datafusion::{
 };
 use futures::TryStreamExt;
 use observability_deps::tracing::{debug, trace};
+use tokio_util::sync::CancellationToken;
 use trace::{ctx::SpanContext, span::SpanRecorder};
 
 use crate::exec::{
+    cancel::{cancellable, cancelled_error},
     fieldlist::{FieldList, IntoFieldList},
     non_null_checker::NonNullCheckerExec,
+    progress::{plan_with_reporter, QueryProgressReporter},
     query_tracing::TracedStream,
+    rechunk::rechunk,
     schema_pivot::{SchemaPivotExec, SchemaPivotNode},
     seriesset::{
         converter::{GroupGenerator, SeriesSetConverter},
         series::Series,
+        Error as SeriesSetError,
     },
     split::StreamSplitExec,
     stringset::{IntoStringSet, StringSetRef},
@@ -57,7 +70,11 @@ pub const DEFAULT_SCHEMA: &str = "iox";
 
 /// This structure implements the DataFusion notion of "query planner"
 /// and is needed to create plans with the IOx extension nodes.
-struct IOxQueryPlanner {}
+struct IOxQueryPlanner {
+    /// Reporter to which the scans of the planned query report their
+    /// progress, if any
+    progress: Option<QueryProgressReporter>,
+}
 
 #[async_trait]
 impl QueryPlanner for IOxQueryPlanner {
@@ -73,9 +90,11 @@ impl QueryPlanner for IOxQueryPlanner {
         let physical_planner =
             DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(IOxExtensionPlanner {})]);
         // Delegate most work of physical planning to the default physical planner
-        physical_planner
-            .create_physical_plan(logical_plan, ctx_state)
-            .await
+        plan_with_reporter(
+            self.progress.clone(),
+            physical_planner.create_physical_plan(logical_plan, ctx_state),
+        )
+        .await
     }
 }
 
@@ -150,8 +169,20 @@ pub struct IOxExecutionConfig {
     /// Default catalog
     default_catalog: Option<Arc<dyn CatalogProvider>>,
 
+    /// Name under which tables are registered and resolved by default
+    catalog_name: String,
+
     /// Span context from which to create spans for this query
     span_ctx: Option<SpanContext>,
+
+    /// Token to cancel the query
+    cancel: Option<CancellationToken>,
+
+    /// Number of rows of the batches of output streams, if re-chunked
+    target_batch_size: Option<NonZeroUsize>,
+
+    /// Reporter of the progress of queries, if any
+    progress: Option<QueryProgressReporter>,
 }
 
 impl fmt::Debug for IOxExecutionConfig {
@@ -169,13 +200,17 @@ impl IOxExecutionConfig {
             .create_default_catalog_and_schema(true)
             .with_information_schema(true)
             .with_default_catalog_and_schema(DEFAULT_CATALOG, DEFAULT_SCHEMA)
-            .with_query_planner(Arc::new(IOxQueryPlanner {}));
+            .with_query_planner(Arc::new(IOxQueryPlanner { progress: None }));
 
         Self {
             exec,
             execution_config,
             default_catalog: None,
+            catalog_name: DEFAULT_CATALOG.to_string(),
             span_ctx: None,
+            cancel: None,
+            target_batch_size: None,
+            progress: None,
         }
     }
 
@@ -211,17 +246,63 @@ impl IOxExecutionConfig {
         }
     }
 
+    /// Set the default catalog and schema names, used to register tables
+    /// and to resolve unqualified table names in SQL.
+    ///
+    /// Defaults to [`DEFAULT_CATALOG`] and [`DEFAULT_SCHEMA`].
+    pub fn with_default_catalog_and_schema(mut self, catalog: &str, schema: &str) -> Self {
+        self.execution_config = self
+            .execution_config
+            .with_default_catalog_and_schema(catalog, schema);
+        self.catalog_name = catalog.to_string();
+        self
+    }
+
     /// Set the span context from which to create  distributed tracing spans for this query
     pub fn with_span_context(self, span_ctx: Option<SpanContext>) -> Self {
         Self { span_ctx, ..self }
     }
 
+    /// Set the token which cancels the query when cancelled, failing any
+    /// running execution with a "query cancelled" error
+    pub fn with_cancellation_token(self, cancel: CancellationToken) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    /// Set the number of rows of the batches emitted by
+    /// [`IOxExecutionContext::execute_stream`], which re-chunks the output
+    /// of plans to that size. Batches are emitted as produced if `None`
+    pub fn with_target_batch_size(self, target_batch_size: Option<NonZeroUsize>) -> Self {
+        Self {
+            target_batch_size,
+            ..self
+        }
+    }
+
+    /// Set the reporter to which queries report their progress: the rows
+    /// and chunks they scanned. The completion of a query is reported by
+    /// its caller with [`QueryProgressReporter::complete`].
+    ///
+    /// Progress is not tracked unless a reporter is set.
+    pub fn with_progress_reporter(mut self, reporter: QueryProgressReporter) -> Self {
+        self.execution_config =
+            self.execution_config
+                .with_query_planner(Arc::new(IOxQueryPlanner {
+                    progress: Some(reporter.clone()),
+                }));
+        self.progress = Some(reporter);
+        self
+    }
+
     /// Create an ExecutionContext suitable for executing DataFusion plans
     pub fn build(self) -> IOxExecutionContext {
         let inner = ExecutionContext::with_config(self.execution_config);
 
         if let Some(default_catalog) = self.default_catalog {
-            inner.register_catalog(DEFAULT_CATALOG, default_catalog);
+            inner.register_catalog(&self.catalog_name, default_catalog);
         }
 
         let maybe_span = self.span_ctx.map(|ctx| ctx.child("Query Execution"));
@@ -230,6 +311,9 @@ impl IOxExecutionConfig {
             inner,
             exec: self.exec,
             recorder: SpanRecorder::new(maybe_span),
+            cancel: self.cancel.unwrap_or_else(CancellationToken::new),
+            target_batch_size: self.target_batch_size,
+            progress: self.progress,
         }
     }
 }
@@ -259,6 +343,16 @@ pub struct IOxExecutionContext {
 
     /// Span context from which to create spans for this query
     recorder: SpanRecorder,
+
+    /// Token to cancel the query, never cancelled unless set with
+    /// [`IOxExecutionConfig::with_cancellation_token`]
+    cancel: CancellationToken,
+
+    /// Number of rows of the batches of output streams, if re-chunked
+    target_batch_size: Option<NonZeroUsize>,
+
+    /// Reporter of the progress of queries, if any
+    progress: Option<QueryProgressReporter>,
 }
 
 impl fmt::Debug for IOxExecutionContext {
@@ -275,6 +369,12 @@ impl IOxExecutionContext {
         &self.inner
     }
 
+    /// Returns the reporter to which queries run in this context report
+    /// their progress, if any
+    pub fn progress_reporter(&self) -> Option<&QueryProgressReporter> {
+        self.progress.as_ref()
+    }
+
     /// Prepare a SQL statement for execution. This assumes that any
     /// tables referenced in the SQL have been registered with this context
     pub async fn prepare_sql(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
@@ -320,26 +420,71 @@ impl IOxExecutionContext {
         .await
     }
 
+    /// Executes `physical_plan` to completion, discarding its output, and
+    /// returns the plan annotated with the metrics (such as rows produced
+    /// and elapsed compute time) recorded by each operator.
+    ///
+    /// This is the equivalent of SQL's `EXPLAIN ANALYZE`.
+    pub async fn explain_analyze(&self, physical_plan: Arc<dyn ExecutionPlan>) -> Result<String> {
+        let ctx = self.child_ctx("explain_analyze");
+        ctx.collect(Arc::clone(&physical_plan)).await?;
+
+        Ok(
+            DisplayableExecutionPlan::with_metrics(physical_plan.as_ref())
+                .indent()
+                .to_string(),
+        )
+    }
+
+    /// Runs each of the `series_set_plans` as per
+    /// [`Self::explain_analyze`], returning the annotated plans one after
+    /// the other, each headed by the table it reads from.
+    pub async fn explain_analyze_series_set_plans(
+        &self,
+        series_set_plans: SeriesSetPlans,
+    ) -> Result<String> {
+        let mut plans = series_set_plans.plans;
+        plans.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+
+        let mut output = String::new();
+        for plan in plans {
+            let physical_plan = self.prepare_plan(&plan.plan).await?;
+            let analyzed = self.explain_analyze(physical_plan).await?;
+
+            output.push_str(&format!("SeriesSetPlan: table_name={}\n", plan.table_name));
+            output.push_str(&analyzed);
+        }
+
+        Ok(output)
+    }
+
     /// Executes the physical plan and produces a
     /// `SendableRecordBatchStream` to stream over the result that
     /// iterates over the results. The creation of the stream is
     /// performed in a separate thread pool.
+    ///
+    /// The batches are re-chunked to the target batch size, if any.
     pub async fn execute_stream(
         &self,
         physical_plan: Arc<dyn ExecutionPlan>,
     ) -> Result<SendableRecordBatchStream> {
-        match physical_plan.output_partitioning().partition_count() {
+        let stream = match physical_plan.output_partitioning().partition_count() {
             0 => unreachable!(),
-            1 => self.execute_stream_partitioned(physical_plan, 0).await,
+            1 => self.execute_stream_partitioned(physical_plan, 0).await?,
             _ => {
                 // Merge into a single partition
                 self.execute_stream_partitioned(
                     Arc::new(CoalescePartitionsExec::new(physical_plan)),
                     0,
                 )
-                .await
+                .await?
             }
-        }
+        };
+
+        Ok(match self.target_batch_size {
+            Some(target_batch_size) => rechunk(stream, target_batch_size),
+            None => stream,
+        })
     }
 
     /// Executes a single partition of a physical plan and produces a
@@ -357,11 +502,12 @@ impl IOxExecutionContext {
             .map(|span| span.child("execute_stream_partitioned"));
 
         let runtime = self.inner.runtime_env();
+        let cancel = self.cancel.clone();
 
         self.run(async move {
             let stream = physical_plan.execute(partition, runtime).await?;
             let stream = TracedStream::new(stream, span, physical_plan);
-            Ok(Box::pin(stream) as _)
+            Ok(cancellable(Box::pin(stream), cancel))
         })
         .await
     }
@@ -377,6 +523,8 @@ impl IOxExecutionContext {
         let SeriesSetPlans {
             mut plans,
             group_columns,
+            include_empty_series,
+            max_series,
         } = series_set_plans;
 
         if plans.is_empty() {
@@ -386,11 +534,15 @@ impl IOxExecutionContext {
         // sort plans by table (measurement) name
         plans.sort_by(|a, b| a.table_name.cmp(&b.table_name));
 
+        // The number of series produced so far by all plans
+        let num_series = Arc::new(AtomicUsize::new(0));
+
         // Run the plans in parallel
         let handles = plans
             .into_iter()
             .map(|plan| {
                 let ctx = self.child_ctx("to_series_set");
+                let num_series = Arc::clone(&num_series);
                 self.run(async move {
                     let SeriesSetPlan {
                         table_name,
@@ -405,7 +557,7 @@ impl IOxExecutionContext {
 
                     let it = ctx.execute_stream(physical_plan).await?;
 
-                    SeriesSetConverter::default()
+                    let series_sets = SeriesSetConverter::default()
                         .convert(table_name, tag_columns, field_columns, it)
                         .await
                         .map_err(|e| {
@@ -413,36 +565,61 @@ impl IOxExecutionContext {
                                 "Error executing series set conversion: {}",
                                 e
                             ))
-                        })
+                        })?;
+
+                    // convert to series
+                    let mut data: Vec<Series> = vec![];
+                    for series_set in series_sets {
+                        // If all timestamps of returned columns are nulls,
+                        // there must be no data. We need to check this because
+                        // aggregate (e.g. count, min, max) returns one row that are
+                        // all null (even the values of aggregate) for min, max and 0 for count.
+                        // For influx read_group's series and group, we do not want to return 0
+                        // for count either.
+                        if series_set.is_timestamp_all_null() {
+                            continue;
+                        }
+
+                        let series =
+                            series_set
+                                .try_into_series(include_empty_series)
+                                .map_err(|e| {
+                                    Error::Execution(format!("Error converting to series: {}", e))
+                                })?;
+
+                        // Count the series of all plans as they are converted, so
+                        // the query fails without waiting for the plans still running
+                        // once the limit is exceeded
+                        if let Some(max_series) = max_series {
+                            let total = num_series.fetch_add(series.len(), Ordering::Relaxed)
+                                + series.len();
+                            if total > max_series {
+                                return Err(Error::External(Box::new(
+                                    SeriesSetError::TooManySeries {
+                                        max_series,
+                                        num_series: total,
+                                    },
+                                )));
+                            }
+                        }
+
+                        data.extend(series);
+                    }
+
+                    Ok(data)
                 })
             })
             .collect::<Vec<_>>();
 
         // join_all ensures that the results are consumed in the same order they
         // were spawned maintaining the guarantee to return results ordered
-        // by table name and plan sort order.
-        let all_series_sets = futures::future::try_join_all(handles).await?;
-
-        // convert to series sets
-        let mut data: Vec<Series> = vec![];
-        for series_sets in all_series_sets {
-            for series_set in series_sets {
-                // If all timestamps of returned columns are nulls,
-                // there must be no data. We need to check this because
-                // aggregate (e.g. count, min, max) returns one row that are
-                // all null (even the values of aggregate) for min, max and 0 for count.
-                // For influx read_group's series and group, we do not want to return 0
-                // for count either.
-                if series_set.is_timestamp_all_null() {
-                    continue;
-                }
-
-                let series: Vec<Series> = series_set
-                    .try_into()
-                    .map_err(|e| Error::Execution(format!("Error converting to series: {}", e)))?;
-                data.extend(series);
-            }
-        }
+        // by table name and plan sort order. It fails as soon as any plan
+        // fails, such as by exceeding the series limit.
+        let data: Vec<Series> = futures::future::try_join_all(handles)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
 
         // If we have group columns, sort the results, and create the
         // appropriate groups
@@ -542,15 +719,21 @@ impl IOxExecutionContext {
     }
 
     /// Runs the provided future using this execution context
+    ///
+    /// Fails with a "query cancelled" error as soon as the query is
+    /// cancelled, stopping the future.
     pub async fn run<Fut, T>(&self, fut: Fut) -> Result<T>
     where
         Fut: std::future::Future<Output = Result<T>> + Send + 'static,
         T: Send + 'static,
     {
-        self.exec
-            .spawn(fut)
-            .await
-            .unwrap_or_else(|e| Err(Error::Execution(format!("Join Error: {}", e))))
+        tokio::select! {
+            res = self.exec.spawn(fut) => {
+                res.unwrap_or_else(|e| Err(Error::Execution(format!("Join Error: {}", e))))
+            }
+            // dropping the job cancels the spawned task
+            _ = self.cancel.cancelled() => Err(cancelled_error()),
+        }
     }
 
     /// Returns a IOxExecutionContext with a SpanRecorder that is a child of the current
@@ -559,6 +742,9 @@ impl IOxExecutionContext {
             inner: self.inner.clone(),
             exec: self.exec.clone(),
             recorder: self.recorder.child(name),
+            cancel: self.cancel.clone(),
+            target_batch_size: self.target_batch_size,
+            progress: self.progress.clone(),
         }
     }
 
@@ -0,0 +1,42 @@
+//! A DataFusion catalog exposing several namespaces as separate schemas,
+//! for queries across namespaces
+
+use std::{any::Any, collections::BTreeMap, sync::Arc};
+
+use datafusion::catalog::{catalog::CatalogProvider, schema::SchemaProvider};
+
+use super::DEFAULT_SCHEMA;
+
+/// Exposes the tables of each namespace as a schema named after the
+/// namespace, so that `SELECT * FROM ns1.t UNION ALL SELECT * FROM ns2.t`
+/// reads table `t` of both `ns1` and `ns2`.
+pub(crate) struct MultiNamespaceCatalog {
+    namespaces: BTreeMap<String, Arc<dyn CatalogProvider>>,
+}
+
+impl MultiNamespaceCatalog {
+    /// Create a catalog from the catalogs of each namespace, keyed by
+    /// namespace name. The tables of a namespace are those in its
+    /// [`DEFAULT_SCHEMA`].
+    pub(crate) fn new(
+        namespaces: impl IntoIterator<Item = (String, Arc<dyn CatalogProvider>)>,
+    ) -> Self {
+        Self {
+            namespaces: namespaces.into_iter().collect(),
+        }
+    }
+}
+
+impl CatalogProvider for MultiNamespaceCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        self.namespaces.keys().cloned().collect()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        self.namespaces.get(name)?.schema(DEFAULT_SCHEMA)
+    }
+}
@@ -0,0 +1,254 @@
+//! Progress events of running queries, e.g. for UIs showing the progress of
+//! long-running scans
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+tokio::task_local! {
+    /// The reporter of the query whose physical plan is being created
+    static PLANNING_REPORTER: Option<QueryProgressReporter>;
+}
+
+/// Progress of a query, totalled over all the chunks it scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryProgress {
+    /// Chunks are being scanned
+    Scanning {
+        /// Number of rows read from chunks so far
+        rows_scanned: u64,
+        /// Number of chunks read entirely
+        chunks_completed: u64,
+    },
+
+    /// The output of the query has been entirely produced. This is the
+    /// last event of a query.
+    Complete {
+        /// Number of rows read from chunks
+        rows_scanned: u64,
+        /// Number of chunks read entirely
+        chunks_completed: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    chunks_planned: u64,
+    rows_scanned: u64,
+    chunks_completed: u64,
+    complete: bool,
+}
+
+/// Sends the [`QueryProgress`] events of a query.
+///
+/// Set with [`IOxExecutionConfig::with_progress_reporter`]; queries do not
+/// track their progress otherwise.
+///
+/// [`IOxExecutionConfig::with_progress_reporter`]: super::IOxExecutionConfig::with_progress_reporter
+#[derive(Debug, Clone)]
+pub struct QueryProgressReporter {
+    sender: mpsc::UnboundedSender<QueryProgress>,
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl QueryProgressReporter {
+    /// Create a reporter and the receiver of its events.
+    ///
+    /// Events are dropped if the receiver is dropped, without affecting
+    /// the query.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<QueryProgress>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let reporter = Self {
+            sender,
+            state: Default::default(),
+        };
+        (reporter, receiver)
+    }
+
+    /// Returns the number of chunks the physical plans of the query read
+    pub fn chunks_planned(&self) -> u64 {
+        self.state.lock().chunks_planned
+    }
+
+    /// Record that a physical plan of the query reads `chunks` chunks.
+    ///
+    /// No event is sent as no data has been scanned.
+    pub(crate) fn plan_chunks(&self, chunks: usize) {
+        self.state.lock().chunks_planned += chunks as u64;
+    }
+
+    /// Record that `rows` more rows were read from a chunk
+    pub(crate) fn rows_scanned(&self, rows: usize) {
+        self.update(|state| state.rows_scanned += rows as u64);
+    }
+
+    /// Record that a chunk was read entirely
+    pub(crate) fn chunk_completed(&self) {
+        self.update(|state| state.chunks_completed += 1);
+    }
+
+    /// Record that the query completed, sending the final event. Does
+    /// nothing if called again.
+    ///
+    /// A query may execute several plans, e.g. one per table, so this is
+    /// called by whoever runs the query once all of its output has been
+    /// produced rather than at the end of each output stream.
+    pub fn complete(&self) {
+        let mut state = self.state.lock();
+        if !state.complete {
+            state.complete = true;
+            let _ = self.sender.send(QueryProgress::Complete {
+                rows_scanned: state.rows_scanned,
+                chunks_completed: state.chunks_completed,
+            });
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ProgressState)) {
+        // Send while holding the lock so events are received in order
+        let mut state = self.state.lock();
+        if state.complete {
+            return;
+        }
+        f(&mut state);
+        let _ = self.sender.send(QueryProgress::Scanning {
+            rows_scanned: state.rows_scanned,
+            chunks_completed: state.chunks_completed,
+        });
+    }
+}
+
+/// Runs `fut`, which creates a physical plan, so that the scans it plans
+/// report their progress to `reporter`
+pub(crate) async fn plan_with_reporter<F: Future>(
+    reporter: Option<QueryProgressReporter>,
+    fut: F,
+) -> F::Output {
+    PLANNING_REPORTER.scope(reporter, fut).await
+}
+
+/// Returns the reporter of the query whose physical plan is being created,
+/// if it reports its progress
+pub(crate) fn planning_reporter() -> Option<QueryProgressReporter> {
+    PLANNING_REPORTER.try_with(Clone::clone).ok().flatten()
+}
+
+/// Reports the progress of the wrapped stream, which reads a chunk, to a
+/// [`QueryProgressReporter`]: its rows are scanned and its end completes the
+/// chunk
+pub(crate) struct ProgressStream {
+    inner: SendableRecordBatchStream,
+    reporter: QueryProgressReporter,
+    done: bool,
+}
+
+impl ProgressStream {
+    pub(crate) fn new(inner: SendableRecordBatchStream, reporter: QueryProgressReporter) -> Self {
+        Self {
+            inner,
+            reporter,
+            done: false,
+        }
+    }
+}
+
+impl RecordBatchStream for ProgressStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for ProgressStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = futures::ready!(self.inner.poll_next_unpin(cx));
+        match &next {
+            Some(Ok(batch)) => self.reporter.rows_scanned(batch.num_rows()),
+            None if !self.done => {
+                self.done = true;
+                self.reporter.chunk_completed();
+            }
+            _ => {}
+        }
+        Poll::Ready(next)
+    }
+}
+
+/// Wraps `stream` to report its progress to `reporter`, see
+/// [`ProgressStream`]
+pub(crate) fn with_progress(
+    stream: SendableRecordBatchStream,
+    reporter: QueryProgressReporter,
+) -> SendableRecordBatchStream {
+    Box::pin(ProgressStream::new(stream, reporter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reporter() {
+        let (reporter, mut receiver) = QueryProgressReporter::new();
+        let reporter2 = reporter.clone();
+
+        reporter.rows_scanned(10);
+        reporter2.rows_scanned(5);
+        reporter.plan_chunks(2);
+        reporter.chunk_completed();
+        reporter.complete();
+        // Nothing is sent after completion
+        reporter2.rows_scanned(5);
+        reporter2.complete();
+        assert_eq!(reporter2.chunks_planned(), 2);
+        drop(reporter);
+        drop(reporter2);
+
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            vec![
+                QueryProgress::Scanning {
+                    rows_scanned: 10,
+                    chunks_completed: 0
+                },
+                QueryProgress::Scanning {
+                    rows_scanned: 15,
+                    chunks_completed: 0
+                },
+                QueryProgress::Scanning {
+                    rows_scanned: 15,
+                    chunks_completed: 1
+                },
+                QueryProgress::Complete {
+                    rows_scanned: 15,
+                    chunks_completed: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_planning_reporter() {
+        assert!(planning_reporter().is_none());
+
+        let (reporter, _receiver) = QueryProgressReporter::new();
+        let found = plan_with_reporter(Some(reporter), async { planning_reporter() }).await;
+        assert!(found.is_some());
+
+        assert!(planning_reporter().is_none());
+    }
+}
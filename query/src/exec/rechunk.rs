@@ -0,0 +1,171 @@
+//! Re-chunking of query output into batches of a target size
+
+use std::{
+    collections::VecDeque,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+
+/// Stream that coalesces small batches of its input, and splits large
+/// ones, into batches of `target_batch_size` rows. Only the last batch may
+/// have fewer rows.
+pub(crate) struct RechunkStream {
+    inner: SendableRecordBatchStream,
+    target_batch_size: usize,
+
+    /// Batches received from `inner` but not yet emitted
+    buffered: VecDeque<RecordBatch>,
+    buffered_rows: usize,
+
+    /// Set once `inner` is exhausted or failed
+    done: bool,
+}
+
+impl RechunkStream {
+    pub(crate) fn new(inner: SendableRecordBatchStream, target_batch_size: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            target_batch_size: target_batch_size.get(),
+            buffered: VecDeque::new(),
+            buffered_rows: 0,
+            done: false,
+        }
+    }
+
+    /// Removes the first `num_rows` buffered rows, as a single batch
+    fn take_rows(&mut self, num_rows: usize) -> ArrowResult<RecordBatch> {
+        let mut batches = Vec::new();
+        let mut remaining = num_rows;
+
+        while remaining > 0 {
+            let batch = self.buffered.pop_front().expect("enough buffered rows");
+            if batch.num_rows() > remaining {
+                self.buffered
+                    .push_front(batch.slice(remaining, batch.num_rows() - remaining));
+                batches.push(batch.slice(0, remaining));
+                remaining = 0;
+            } else {
+                remaining -= batch.num_rows();
+                batches.push(batch);
+            }
+        }
+        self.buffered_rows -= num_rows;
+
+        match batches.len() {
+            1 => Ok(batches.pop().unwrap()),
+            _ => RecordBatch::concat(&self.inner.schema(), &batches),
+        }
+    }
+}
+
+impl RecordBatchStream for RechunkStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for RechunkStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.buffered_rows >= self.target_batch_size {
+                let target_batch_size = self.target_batch_size;
+                return Poll::Ready(Some(self.take_rows(target_batch_size)));
+            }
+
+            if self.done {
+                let rows = self.buffered_rows;
+                return match rows {
+                    0 => Poll::Ready(None),
+                    _ => Poll::Ready(Some(self.take_rows(rows))),
+                };
+            }
+
+            match futures::ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => {
+                    if batch.num_rows() > 0 {
+                        self.buffered_rows += batch.num_rows();
+                        self.buffered.push_back(batch);
+                    }
+                }
+                Some(Err(e)) => {
+                    self.done = true;
+                    self.buffered.clear();
+                    self.buffered_rows = 0;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+/// Wraps `stream` so it emits batches of `target_batch_size` rows, see
+/// [`RechunkStream`]
+pub(crate) fn rechunk(
+    stream: SendableRecordBatchStream,
+    target_batch_size: NonZeroUsize,
+) -> SendableRecordBatchStream {
+    Box::pin(RechunkStream::new(stream, target_batch_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array};
+    use datafusion_util::MemoryStream;
+
+    use super::*;
+
+    fn batch(values: impl Iterator<Item = i64>) -> RecordBatch {
+        let array: ArrayRef = Arc::new(values.collect::<Int64Array>());
+        RecordBatch::try_from_iter(vec![("a", array)]).unwrap()
+    }
+
+    async fn run(input: Vec<RecordBatch>, target_batch_size: usize) -> Vec<RecordBatch> {
+        let schema = input[0].schema();
+        let inner = Box::pin(MemoryStream::new_with_schema(input, schema));
+        let stream = rechunk(inner, NonZeroUsize::new(target_batch_size).unwrap());
+        stream.map(|b| b.unwrap()).collect().await
+    }
+
+    fn values(batches: &[RecordBatch]) -> Vec<i64> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                let array = b.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                array.values().to_vec()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rechunk_small_batches() {
+        // 100 batches of 3 rows
+        let input: Vec<_> = (0..100).map(|i| batch(i * 3..(i + 1) * 3)).collect();
+
+        let output = run(input, 64).await;
+
+        let sizes: Vec<_> = output.iter().map(|b| b.num_rows()).collect();
+        assert_eq!(sizes, vec![64, 64, 64, 64, 44]);
+        assert_eq!(values(&output), (0..300).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_rechunk_large_batches() {
+        let input = vec![batch(0..10), batch(10..10), batch(10..35)];
+
+        let output = run(input, 8).await;
+
+        let sizes: Vec<_> = output.iter().map(|b| b.num_rows()).collect();
+        assert_eq!(sizes, vec![8, 8, 8, 8, 3]);
+        assert_eq!(values(&output), (0..35).collect::<Vec<_>>());
+    }
+}
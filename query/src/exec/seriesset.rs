@@ -23,11 +23,25 @@ pub mod converter;
 pub mod series;
 
 use arrow::{self, record_batch::RecordBatch};
+use snafu::Snafu;
 
 use std::sync::Arc;
 
 use super::field::FieldIndexes;
 
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Query exceeded the maximum of {} series ({} series produced)",
+        max_series,
+        num_series
+    ))]
+    TooManySeries {
+        max_series: usize,
+        num_series: usize,
+    },
+}
+
 #[derive(Debug)]
 /// Information to map a slice of rows in a [`RecordBatch`] sorted by
 /// tags and timestamps to several timeseries that share the same
@@ -4,14 +4,14 @@
 
 use arrow::{
     self,
-    array::{Array, DictionaryArray, StringArray},
+    array::{Array, DictionaryArray, Float64Array, StringArray},
     datatypes::{DataType, Int32Type},
     record_batch::RecordBatch,
 };
 use datafusion::physical_plan::SendableRecordBatchStream;
 
 use observability_deps::tracing::trace;
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ResultExt, Snafu};
 use std::sync::Arc;
 use tokio::sync::mpsc::error::SendError;
 use tokio_stream::StreamExt;
@@ -41,12 +41,15 @@ pub enum Error {
     ))]
     ReadingRecordBatch { source: arrow::error::ArrowError },
 
+    #[snafu(display(
+        "Error replacing non finite values while converting series set: {}",
+        source
+    ))]
+    ReplacingNonFiniteValues { source: arrow::error::ArrowError },
+
     #[snafu(display("Internal field error while converting series set: {}", source))]
     InternalField { source: field::Error },
 
-    #[snafu(display("Internal error finding grouping colum: {}", column_name))]
-    FindingGroupColumn { column_name: String },
-
     #[snafu(display("Sending series set results during conversion: {:?}", source))]
     SendingDuringConversion {
         source: Box<SendError<Result<SeriesSet>>>,
@@ -103,6 +106,13 @@ impl SeriesSetConverter {
             let field_indexes = FieldIndexes::from_field_columns(&schema, &field_columns)
                 .context(InternalFieldSnafu)?;
 
+            // NaN and infinite values (e.g. the mean of values whose sum
+            // overflows) can not be encoded by clients, so they are
+            // treated as null and not emitted. The batch is shared by all
+            // the series sets, so they are replaced once here
+            let batch = Self::non_finite_to_null(batch, &field_indexes)
+                .context(ReplacingNonFiniteValuesSnafu)?;
+
             // Algorithm: compute, via bitsets, the rows at which each
             // tag column changes and thereby where the tagset
             // changes. Emit a new SeriesSet at each such transition
@@ -159,6 +169,37 @@ impl SeriesSetConverter {
         Ok(results)
     }
 
+    /// Returns `batch` with all NaN and infinite values in its float field
+    /// columns replaced by nulls. The batch is returned unchanged if all of
+    /// these values are finite.
+    fn non_finite_to_null(
+        batch: RecordBatch,
+        field_indexes: &FieldIndexes,
+    ) -> arrow::error::Result<RecordBatch> {
+        let mut columns = batch.columns().to_vec();
+        let mut replaced = false;
+
+        for index in field_indexes.iter() {
+            let column = &mut columns[index.value_index];
+            let floats = match column.as_any().downcast_ref::<Float64Array>() {
+                Some(floats) if !floats.iter().flatten().all(f64::is_finite) => floats,
+                _ => continue,
+            };
+
+            let floats: Float64Array = floats
+                .iter()
+                .map(|value| value.filter(|value| value.is_finite()))
+                .collect();
+            *column = Arc::new(floats);
+            replaced = true;
+        }
+
+        if !replaced {
+            return Ok(batch);
+        }
+        RecordBatch::try_new(batch.schema(), columns)
+    }
+
     /// returns a bitset with all row indexes where the value of the
     /// batch `col_idx` changes.  Does not include row 0, always includes
     /// the last row, `batch.num_rows() - 1`
@@ -328,10 +369,10 @@ impl GroupGenerator {
 
     /// groups the set of `series` into SeriesOrGroups
     pub fn group(&self, series: Vec<Series>) -> Result<Vec<Either>> {
-        let mut series = series
+        let mut series: Vec<_> = series
             .into_iter()
-            .map(|series| SortableSeries::try_new(series, &self.group_columns))
-            .collect::<Result<Vec<_>>>()?;
+            .map(|series| SortableSeries::new(series, &self.group_columns))
+            .collect();
 
         // Potential optimization is to skip this sort if we are
         // grouping by a prefix of the tags for a single measurement
@@ -426,7 +467,7 @@ impl Ord for SortableSeries {
 }
 
 impl SortableSeries {
-    fn try_new(series: Series, group_columns: &[Arc<str>]) -> Result<Self> {
+    fn new(series: Series, group_columns: &[Arc<str>]) -> Self {
         // Compute the order of new tag values
         let tags = &series.tags;
 
@@ -449,21 +490,15 @@ impl SortableSeries {
                         tag_used_set[i] = true;
                         Arc::clone(&tag.value)
                     })
-                    .or_else(|| {
-                        // treat these specially and use value "" to mirror what TSM does
-                        // see https://github.com/influxdata/influxdb_iox/issues/2693#issuecomment-947695442
-                        // for more details
-                        if col.as_ref() == "_start" || col.as_ref() == "_stop" {
-                            Some(Arc::from(""))
-                        } else {
-                            None
-                        }
-                    })
-                    .context(FindingGroupColumnSnafu {
-                        column_name: col.as_ref(),
-                    })
+                    // Series without a value for the group column (such as the
+                    // special "_start" and "_stop" columns or a tag that is
+                    // null for the series) are grouped together using the value
+                    // "" to mirror what TSM does, see
+                    // https://github.com/influxdata/influxdb_iox/issues/2693#issuecomment-947695442
+                    // for more details
+                    .unwrap_or_else(|| Arc::from(""))
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect();
 
         // Fill in all remaining tags
         tag_vals.extend(tags.iter().enumerate().filter_map(|(i, tag)| {
@@ -471,11 +506,11 @@ impl SortableSeries {
             use_tag.then(|| Arc::clone(&tag.value))
         }));
 
-        Ok(Self {
+        Self {
             series,
             tag_vals,
             num_partition_keys: group_columns.len(),
-        })
+        }
     }
 }
 
@@ -788,6 +823,74 @@ mod tests {
         assert_eq!(series_set2.num_rows, 1);
     }
 
+    #[tokio::test]
+    async fn test_convert_non_finite_floats() {
+        let state = StringArray::from(vec!["CA", "CA", "MA", "MA"]);
+        let float_field = Float64Array::from(vec![
+            Some(f64::NAN),
+            Some(1.0),
+            Some(f64::INFINITY),
+            Some(f64::NEG_INFINITY),
+        ]);
+        let nan_field = Float64Array::from(vec![Some(f64::NAN), None, Some(f64::NAN), None]);
+        let int_field = Int64Array::from(vec![1, 2, 3, 4]);
+        let time = Int64Array::from(vec![1000, 2000, 3000, 4000]);
+
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("state", Arc::new(state) as ArrayRef, true),
+            ("float_field", Arc::new(float_field), true),
+            ("nan_field", Arc::new(nan_field), true),
+            ("int_field", Arc::new(int_field), true),
+            ("time", Arc::new(time), false),
+        ])
+        .unwrap();
+        let input = batch_to_iterator(batch);
+
+        let table_name = "foo";
+        let tag_columns = ["state"];
+        let field_columns = ["float_field", "nan_field", "int_field"];
+        let results = convert(table_name, &tag_columns, &field_columns, input).await;
+
+        assert_eq!(results.len(), 2);
+
+        // Non finite values are replaced by nulls in the batch shared by
+        // both series sets
+        let expected_data = vec![
+            "+-------+-------------+-----------+-----------+------+",
+            "| state | float_field | nan_field | int_field | time |",
+            "+-------+-------------+-----------+-----------+------+",
+            "| CA    |             |           | 1         | 1000 |",
+            "| CA    | 1           |           | 2         | 2000 |",
+            "| MA    |             |           | 3         | 3000 |",
+            "| MA    |             |           | 4         | 4000 |",
+            "+-------+-------------+-----------+-----------+------+",
+        ];
+        for series_set in &results {
+            assert_batches_eq!(expected_data, &[series_set.batch.clone()]);
+        }
+
+        // so nan_field, which has no finite values, does not produce a
+        // series
+        let series = results
+            .into_iter()
+            .map(|series_set| Vec::<Series>::try_from(series_set).unwrap())
+            .flatten()
+            .map(|series| series.to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "Series tags={_measurement=foo, state=CA, _field=float_field}\n  FloatPoints timestamps: [2000], values: [1.0]",
+            "Series tags={_measurement=foo, state=CA, _field=int_field}\n  IntegerPoints timestamps: [1000, 2000], values: [1, 2]",
+            "Series tags={_measurement=foo, state=MA, _field=int_field}\n  IntegerPoints timestamps: [3000, 4000], values: [3, 4]",
+        ];
+
+        assert_eq!(
+            series, expected,
+            "Expected:\n{:#?}\nActual:\n{:#?}",
+            expected, series
+        );
+    }
+
     /// Test helper: run conversion and return a Vec
     pub async fn convert<'a>(
         table_name: &'a str,
@@ -64,7 +64,10 @@ impl fmt::Display for Series {
             write!(f, "{}", tag)
         })?;
         writeln!(f, "}}")?;
-        write!(f, "  {}", self.data)
+        match f.precision() {
+            Some(precision) => write!(f, "  {:.*}", precision, self.data),
+            None => write!(f, "  {}", self.data),
+        }
     }
 }
 
@@ -97,14 +100,20 @@ pub enum Data {
     },
 }
 
+/// Float values are rendered using their shortest representation by
+/// default. A precision (e.g. `{:.17}`) renders every float value with that
+/// many decimals instead, which can reveal differences the shortest
+/// representation hides.
 impl fmt::Display for Data {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::FloatPoints { timestamps, values } => write!(
-                f,
-                "FloatPoints timestamps: {:?}, values: {:?}",
-                timestamps, values
-            ),
+            Self::FloatPoints { timestamps, values } => {
+                write!(f, "FloatPoints timestamps: {:?}, values: ", timestamps)?;
+                match f.precision() {
+                    Some(precision) => fmt_floats(f, values, precision),
+                    None => write!(f, "{:?}", values),
+                }
+            }
             Self::IntegerPoints { timestamps, values } => write!(
                 f,
                 "IntegerPoints timestamps: {:?}, values: {:?}",
@@ -135,15 +144,26 @@ impl TryFrom<SeriesSet> for Vec<Series> {
     /// Converts a particular SeriesSet into a Vec of Series. Note the
     /// order is important
     fn try_from(value: SeriesSet) -> Result<Self, Self::Error> {
-        value
-            .field_indexes
-            .iter()
-            .filter_map(|index| value.field_to_series(index).transpose())
-            .collect()
+        value.try_into_series(false)
     }
 }
 
 impl SeriesSet {
+    /// Converts this SeriesSet into a Vec of Series, one per field.
+    ///
+    /// Fields with no non-null values are omitted unless
+    /// `include_empty_series` is true, in which case they produce a series
+    /// with no points.
+    pub fn try_into_series(self, include_empty_series: bool) -> Result<Vec<Series>> {
+        self.field_indexes
+            .iter()
+            .filter_map(|index| {
+                self.field_to_series(index, include_empty_series)
+                    .transpose()
+            })
+            .collect()
+    }
+
     /// Returns true if the array is entirely null between start_row and
     /// start_row+num_rows
     fn is_all_null(arr: &ArrayRef, start_row: usize, num_rows: usize) -> bool {
@@ -163,7 +183,11 @@ impl SeriesSet {
 
     // Convert and append the values from a single field to a Series
     // appended to `frames`
-    fn field_to_series(&self, index: &FieldIndex) -> Result<Option<Series>> {
+    fn field_to_series(
+        &self,
+        index: &FieldIndex,
+        include_empty_series: bool,
+    ) -> Result<Option<Series>> {
         let batch = &self.batch;
         let schema = batch.schema();
 
@@ -174,8 +198,11 @@ impl SeriesSet {
         let num_rows = self.num_rows;
 
         // No values for this field are in the array so it does not
-        // contribute to a series.
-        if field.is_nullable() && Self::is_all_null(array, start_row, num_rows) {
+        // contribute to a series, unless empty series were requested.
+        if !include_empty_series
+            && field.is_nullable()
+            && Self::is_all_null(array, start_row, num_rows)
+        {
             return Ok(None);
         }
 
@@ -339,6 +366,20 @@ impl fmt::Display for Either {
     }
 }
 
+fn fmt_floats(f: &mut fmt::Formatter<'_>, values: &[f64], precision: usize) -> fmt::Result {
+    write!(f, "[")?;
+    let mut first = true;
+    values.iter().try_for_each(|value| {
+        if !first {
+            write!(f, ", ")?;
+        } else {
+            first = false;
+        }
+        write!(f, "{:.*}", precision, value)
+    })?;
+    write!(f, "]")
+}
+
 fn fmt_strings(f: &mut fmt::Formatter<'_>, strings: &[Arc<str>]) -> fmt::Result {
     let mut first = true;
     strings.iter().try_for_each(|item| {
@@ -620,6 +661,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_series_display_float_precision() {
+        let series = Series {
+            tags: vec![Tag {
+                key: Arc::from("_field"),
+                value: Arc::from("float_field"),
+            }],
+            data: Data::FloatPoints {
+                timestamps: vec![1000, 2000],
+                values: vec![1.0 / 3.0, 70.5],
+            },
+        };
+
+        // default uses the shortest representation
+        assert_eq!(
+            series.to_string(),
+            "Series tags={_field=float_field}\n  FloatPoints timestamps: [1000, 2000], values: [0.3333333333333333, 70.5]"
+        );
+
+        // a precision renders every value with that many decimals
+        assert_eq!(
+            format!("{:.20}", series),
+            "Series tags={_field=float_field}\n  FloatPoints timestamps: [1000, 2000], values: [0.33333333333333331483, 70.50000000000000000000]"
+        );
+
+        // the precision is forwarded when displaying an `Either`
+        assert_eq!(
+            format!("{:.2}", Either::from(series)),
+            "Series tags={_field=float_field}\n  FloatPoints timestamps: [1000, 2000], values: [0.33, 70.50]"
+        );
+    }
+
     fn make_record_batch() -> RecordBatch {
         let string_array: ArrayRef = Arc::new(StringArray::from(vec!["foo", "bar", "baz", "foo"]));
         let int_array: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3, 4]));
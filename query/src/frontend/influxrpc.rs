@@ -4,18 +4,20 @@ use std::{
     sync::Arc,
 };
 
-use arrow::datatypes::DataType;
-use data_types::chunk_metadata::ChunkId;
+use arrow::{datatypes::DataType, error::ArrowError, record_batch::RecordBatch};
+use data_types::{chunk_metadata::ChunkId, partition_metadata::InfluxDbType};
 use datafusion::{
     error::{DataFusionError, Result as DatafusionResult},
     logical_plan::{
         binary_expr, col, lit, when, DFSchema, DFSchemaRef, Expr, ExprRewriter, LogicalPlan,
         LogicalPlanBuilder,
     },
+    physical_plan::SendableRecordBatchStream,
     scalar::ScalarValue,
 };
 use datafusion_util::AsExpr;
 
+use futures::{Stream, StreamExt, TryStreamExt};
 use hashbrown::HashSet;
 use observability_deps::tracing::{debug, trace};
 use predicate::predicate::{BinaryExpr, Predicate, PredicateMatch};
@@ -25,7 +27,15 @@ use schema::{InfluxColumnType, Schema, TIME_COLUMN_NAME};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
 use crate::{
-    exec::{field::FieldColumns, make_non_null_checker, make_schema_pivot},
+    exec::{
+        field::{FieldColumns, FieldIndexes},
+        make_non_null_checker, make_schema_pivot,
+        seriesset::{
+            series::{Data, Error as SeriesError},
+            SeriesSet,
+        },
+        stringset::StringSet,
+    },
     func::{
         selectors::{selector_first, selector_last, selector_max, selector_min, SelectorOutput},
         window::make_window_bound_expr,
@@ -36,7 +46,8 @@ use crate::{
         seriesset::{SeriesSetPlan, SeriesSetPlans},
         stringset::{Error as StringSetError, StringSetPlan, StringSetPlanBuilder},
     },
-    provider::ProviderBuilder,
+    provider::{Deduplicater, ProviderBuilder},
+    util::predicate_to_display,
     QueryChunk, QueryChunkMeta, QueryDatabase,
 };
 
@@ -96,6 +107,26 @@ pub enum Error {
         source: crate::provider::Error,
     },
 
+    #[snafu(display(
+        "gRPC planner got error estimating the cost of scanning table {}: {}",
+        table_name,
+        source
+    ))]
+    EstimatingCost {
+        table_name: String,
+        source: crate::provider::Error,
+    },
+
+    #[snafu(display(
+        "query would scan {} chunks, more than the maximum of {}",
+        num_chunks,
+        max_chunks
+    ))]
+    TooManyChunks {
+        num_chunks: usize,
+        max_chunks: usize,
+    },
+
     #[snafu(display("gRPC planner got error building plan: {}", source))]
     BuildingPlan {
         source: datafusion::error::DataFusionError,
@@ -155,6 +186,9 @@ pub enum Error {
     #[snafu(display("Internal error: unexpected aggregate request for None aggregate",))]
     InternalUnexpectedNoneAggregate {},
 
+    #[snafu(display("At least one aggregate must be requested"))]
+    NoAggregates {},
+
     #[snafu(display("Internal error: aggregate {:?} is not a selector", agg))]
     InternalAggregateNotSelector { agg: Aggregate },
 
@@ -170,10 +204,36 @@ pub enum Error {
         table_name: String,
         source: datafusion::error::DataFusionError,
     },
+
+    #[snafu(display("Error reading record batch for field points: {}", source))]
+    ReadingFieldPoints { source: ArrowError },
+
+    #[snafu(display("Error finding field columns for field points: {}", source))]
+    FindingFieldPointsColumns { source: crate::exec::field::Error },
+
+    #[snafu(display("Error converting record batch to field points: {}", source))]
+    ConvertingFieldPoints { source: SeriesError },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// A cheap estimate of the cost of a query, computed from chunk metadata only,
+/// see [`InfluxRpcPlanner::estimate_cost`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryCostEstimate {
+    /// Number of chunks that would be scanned, after pruning
+    pub chunks: usize,
+
+    /// Upper bound of the number of rows in the scanned chunks. Chunks
+    /// without statistics are not counted.
+    pub estimated_rows: u64,
+
+    /// Whether the data of some chunks has to be sorted and deduplicated,
+    /// because the chunks overlap, may contain duplicates themselves or
+    /// have no statistics
+    pub requires_sort: bool,
+}
+
 /// Plans queries that originate from the InfluxDB Storage gRPC
 /// interface, which are in terms of the InfluxDB Data model (e.g.
 /// `ParsedLine`). The query methods on this trait such as
@@ -195,12 +255,232 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// categories with the same data type, columns of different
 /// categories are treated differently in the different query types.
 #[derive(Default, Debug)]
-pub struct InfluxRpcPlanner {}
+pub struct InfluxRpcPlanner {
+    /// See [`SeriesSetPlans::include_empty_series`]
+    include_empty_series: bool,
+
+    /// The server-wide maximum number of series a query may produce
+    max_series: Option<usize>,
+
+    /// The maximum number of series requested for this query, capped by
+    /// `max_series`
+    request_max_series: Option<usize>,
+
+    /// See [`Self::with_disable_pruning`]
+    disable_pruning: bool,
+
+    /// The server-wide maximum number of chunks a query may scan
+    max_chunks: Option<usize>,
+
+    /// The maximum number of chunks this query may scan, capped by
+    /// `max_chunks`
+    request_max_chunks: Option<usize>,
+}
 
 impl InfluxRpcPlanner {
     /// Create a new instance of the RPC planner
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Emit series for fields whose values are all null (after applying
+    /// the predicate) with an empty set of points, rather than omitting
+    /// them from the read_filter, read_group and read_window_aggregate
+    /// output.
+    pub fn with_include_empty_series(mut self, include_empty_series: bool) -> Self {
+        self.include_empty_series = include_empty_series;
+        self
+    }
+
+    /// Fail read_filter, read_group and read_window_aggregate queries with
+    /// a [`TooManySeries`](crate::exec::seriesset::Error::TooManySeries)
+    /// error once they produce more than `max_series` series.
+    ///
+    /// This is the server maximum, which a per-request limit set with
+    /// [`Self::with_request_max_series`] can lower but not raise.
+    pub fn with_max_series(mut self, max_series: usize) -> Self {
+        self.max_series = Some(max_series);
+        self
+    }
+
+    /// Limit the number of series produced by this request to
+    /// `max_series`, or the server maximum if that is lower.
+    pub fn with_request_max_series(mut self, max_series: usize) -> Self {
+        self.request_max_series = Some(max_series);
+        self
+    }
+
+    /// Fail field_columns, read_filter, read_group and
+    /// read_window_aggregate queries with a [`Error::TooManyChunks`] error
+    /// if they would scan more than `max_chunks` chunks after pruning.
+    ///
+    /// This protects against queries of partitions with many small chunks
+    /// that have not been compacted yet. It is the server maximum, which a
+    /// per-request limit set with [`Self::with_request_max_chunks`] can
+    /// lower but not raise.
+    pub fn with_max_chunks(mut self, max_chunks: usize) -> Self {
+        self.max_chunks = Some(max_chunks);
+        self
+    }
+
+    /// Limit the number of chunks scanned by this request to `max_chunks`,
+    /// or the server maximum if that is lower.
+    pub fn with_request_max_chunks(mut self, max_chunks: usize) -> Self {
+        self.request_max_chunks = Some(max_chunks);
+        self
+    }
+
+    /// Keep all chunks rather than pruning those that the metadata shows
+    /// cannot match the predicate, both when requesting the chunks from the
+    /// database and when planning. The predicate is still applied to the
+    /// data of the chunks, so this must not change the results of a query.
+    ///
+    /// Databases may prune the chunks of the plans further, such as `Db`
+    /// unless [`ExecutorConfig::disable_pruning`] is set.
+    ///
+    /// [`ExecutorConfig::disable_pruning`]: crate::exec::ExecutorConfig::disable_pruning
+    ///
+    /// Only intended for debugging pruning problems: the results with and
+    /// without pruning should be identical.
+    pub fn with_disable_pruning(mut self, disable_pruning: bool) -> Self {
+        self.disable_pruning = disable_pruning;
+        self
+    }
+
+    /// Applies `predicate` to the metadata of `chunk`, or returns
+    /// [`PredicateMatch::Unknown`] if pruning is disabled.
+    ///
+    /// An empty predicate matches every row, so it is answered from the
+    /// summary of the chunk if possible, see [`empty_predicate_match`].
+    fn apply_predicate_to_metadata<C>(
+        &self,
+        chunk: &C,
+        predicate: &Predicate,
+    ) -> Result<PredicateMatch>
+    where
+        C: QueryChunk,
+    {
+        if self.disable_pruning {
+            return Ok(PredicateMatch::Unknown);
+        }
+
+        if predicate.is_empty() {
+            if let Some(pred_result) = empty_predicate_match(chunk) {
+                trace!(chunk_id=%chunk.id(), ?pred_result, "empty predicate, matched from summary");
+                return Ok(pred_result);
+            }
+        }
+
+        chunk
+            .apply_predicate_to_metadata(predicate)
+            .map_err(|e| Box::new(e) as _)
+            .context(CheckingChunkPredicateSnafu {
+                chunk_id: chunk.id(),
+            })
+    }
+
+    /// Returns the chunks of `table_name` in `database` that may match
+    /// `predicate`, or all of them if pruning is disabled
+    fn chunks<D>(&self, database: &D, table_name: &str, predicate: &Predicate) -> Vec<Arc<D::Chunk>>
+    where
+        D: QueryDatabase,
+    {
+        if self.disable_pruning {
+            // Unlike the rest of the predicate, the partition key is not
+            // applied to the data of the chunks
+            let predicate = Predicate {
+                partition_key: predicate.partition_key.clone(),
+                ..Default::default()
+            };
+            return database.chunks(table_name, &predicate);
+        }
+        database.chunks(table_name, predicate)
+    }
+
+    /// Prunes `chunks` using their metadata, unless pruning is disabled
+    fn prune_chunks<C>(&self, chunks: Vec<Arc<C>>, predicate: &Predicate) -> Result<Vec<Arc<C>>>
+    where
+        C: QueryChunk + 'static,
+    {
+        if self.disable_pruning {
+            return Ok(chunks);
+        }
+        prune_chunks_metadata(chunks, predicate)
+    }
+
+    /// The series limit applied to produced plans
+    fn effective_max_series(&self) -> Option<usize> {
+        match (self.max_series, self.request_max_series) {
+            (Some(max), Some(requested)) => Some(max.min(requested)),
+            (max, requested) => max.or(requested),
+        }
+    }
+
+    /// Returns an error if scanning `num_chunks` chunks exceeds the chunk
+    /// limit of this planner
+    fn check_max_chunks(&self, num_chunks: usize) -> Result<()> {
+        let max_chunks = match (self.max_chunks, self.request_max_chunks) {
+            (Some(max), Some(requested)) => Some(max.min(requested)),
+            (max, requested) => max.or(requested),
+        };
+
+        match max_chunks {
+            Some(max_chunks) if num_chunks > max_chunks => TooManyChunksSnafu {
+                num_chunks,
+                max_chunks,
+            }
+            .fail(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the options of this planner to `plans`
+    fn series_set_plans(&self, plans: Vec<SeriesSetPlan>) -> SeriesSetPlans {
+        SeriesSetPlans::new(plans)
+            .with_include_empty_series(self.include_empty_series)
+            .with_max_series(self.effective_max_series())
+    }
+
+    /// Estimates the cost of running a query with `rpc_predicate` against
+    /// `database`, without planning or executing it.
+    ///
+    /// Only the metadata of the chunks is used: chunks are pruned the same
+    /// way as for the other queries of this planner, and their statistics
+    /// provide the number of rows and whether they overlap.
+    pub fn estimate_cost<D>(
+        &self,
+        database: &D,
+        rpc_predicate: InfluxRpcPredicate,
+    ) -> Result<QueryCostEstimate>
+    where
+        D: QueryDatabase + 'static,
+    {
+        let mut estimate = QueryCostEstimate::default();
+
+        let table_predicates = rpc_predicate.table_predicates(database);
+        for (table_name, predicate) in &table_predicates {
+            let chunks = self.chunks(database, table_name, predicate);
+            let chunks = self.prune_chunks(chunks, predicate)?;
+            if chunks.is_empty() {
+                continue;
+            }
+
+            estimate.chunks += chunks.len();
+            estimate.estimated_rows += chunks
+                .iter()
+                .filter_map(|chunk| chunk.summary())
+                .map(|summary| summary.total_count())
+                .sum::<u64>();
+
+            let mut deduplicater = Deduplicater::new();
+            deduplicater
+                .split_overlapped_chunks(chunks)
+                .context(EstimatingCostSnafu { table_name })?;
+            estimate.requires_sort |= !deduplicater.no_duplicates();
+        }
+
+        debug!(?estimate, "estimated query cost");
+        Ok(estimate)
     }
 
     /// Returns a builder that includes
@@ -228,7 +508,7 @@ impl InfluxRpcPlanner {
         for (table_name, predicate) in &table_predicates {
             // Identify which chunks can answer from its metadata and then record its table,
             // and which chunks needs full plan and group them into their table
-            for chunk in database.chunks(table_name, predicate) {
+            for chunk in self.chunks(database, table_name, predicate) {
                 trace!(chunk_id=%chunk.id(), %table_name, "Considering table");
 
                 // Table is already in the returned table list, no longer needs to discover it from other chunks
@@ -246,12 +526,8 @@ impl InfluxRpcPlanner {
                         .push(Arc::clone(&chunk));
                 } else {
                     // Try and apply the predicate using only metadata
-                    let pred_result = chunk
-                        .apply_predicate_to_metadata(predicate)
-                        .map_err(|e| Box::new(e) as _)
-                        .context(CheckingChunkPredicateSnafu {
-                            chunk_id: chunk.id(),
-                        })?;
+                    let pred_result =
+                        self.apply_predicate_to_metadata(chunk.as_ref(), predicate)?;
 
                     match pred_result {
                         PredicateMatch::AtLeastOneNonNullField => {
@@ -333,18 +609,13 @@ impl InfluxRpcPlanner {
 
         let table_predicates = rpc_predicate.table_predicates(database);
         for (table_name, predicate) in &table_predicates {
-            for chunk in database.chunks(table_name, predicate) {
+            for chunk in self.chunks(database, table_name, predicate) {
                 // If there are delete predicates, we need to scan (or do full plan) the data to eliminate
                 // deleted data before getting tag keys
                 let mut do_full_plan = chunk.has_delete_predicates();
 
                 // Try and apply the predicate using only metadata
-                let pred_result = chunk
-                    .apply_predicate_to_metadata(predicate)
-                    .map_err(|e| Box::new(e) as _)
-                    .context(CheckingChunkPredicateSnafu {
-                        chunk_id: chunk.id(),
-                    })?;
+                let pred_result = self.apply_predicate_to_metadata(chunk.as_ref(), predicate)?;
 
                 if matches!(pred_result, PredicateMatch::Zero) {
                     continue;
@@ -447,6 +718,70 @@ impl InfluxRpcPlanner {
     {
         debug!(?rpc_predicate, tag_name, "planning tag_values");
 
+        let mut builder = StringSetPlanBuilder::new();
+        let mut known_values = BTreeSet::new();
+
+        for (_, mut table_values) in self.table_tag_values(database, tag_name, rpc_predicate)? {
+            known_values.append(&mut table_values.known);
+            if let Some(plan) = table_values.plan {
+                builder = builder.append_other(plan.into());
+            }
+        }
+
+        // add the known values we could find from metadata only
+        builder
+            .append_other(known_values.into())
+            .build()
+            .context(CreatingStringSetSnafu)
+    }
+
+    /// Returns a plan per table (measurement) which finds the distinct,
+    /// non-null tag values in the specified `tag_name` column of that table
+    /// which pass the conditions specified by `predicate`.
+    ///
+    /// This is equivalent to calling [`Self::tag_values`] once per table,
+    /// restricting the predicate to that table. Tables that are known to
+    /// have no matching values are omitted.
+    pub fn tag_values_by_table<D>(
+        &self,
+        database: &D,
+        tag_name: &str,
+        rpc_predicate: InfluxRpcPredicate,
+    ) -> Result<BTreeMap<String, StringSetPlan>>
+    where
+        D: QueryDatabase + 'static,
+    {
+        debug!(?rpc_predicate, tag_name, "planning tag_values_by_table");
+
+        self.table_tag_values(database, tag_name, rpc_predicate)?
+            .into_iter()
+            .map(|(table_name, table_values)| {
+                let mut builder = StringSetPlanBuilder::new();
+                if let Some(plan) = table_values.plan {
+                    builder = builder.append_other(plan.into());
+                }
+
+                let plan = builder
+                    .append_other(table_values.known.into())
+                    .build()
+                    .context(CreatingStringSetSnafu)?;
+
+                Ok((table_name, plan))
+            })
+            .collect()
+    }
+
+    /// Finds the values of `tag_name` in each table, either from metadata
+    /// alone or as a plan that must be run.
+    fn table_tag_values<D>(
+        &self,
+        database: &D,
+        tag_name: &str,
+        rpc_predicate: InfluxRpcPredicate,
+    ) -> Result<BTreeMap<String, TableTagValues>>
+    where
+        D: QueryDatabase + 'static,
+    {
         // The basic algorithm is:
         //
         // 1. Find all the potential tables in the chunks
@@ -459,22 +794,17 @@ impl InfluxRpcPlanner {
         // for that table but that we couldn't evaluate the predicate
         // entirely using the metadata
         let mut need_full_plans = BTreeMap::new();
-        let mut known_values = BTreeSet::new();
+        let mut table_values: BTreeMap<String, TableTagValues> = BTreeMap::new();
 
         let table_predicates = rpc_predicate.table_predicates(database);
         for (table_name, predicate) in &table_predicates {
-            for chunk in database.chunks(table_name, predicate) {
+            for chunk in self.chunks(database, table_name, predicate) {
                 // If there are delete predicates, we need to scan (or do full plan) the data to eliminate
                 // deleted data before getting tag values
                 let mut do_full_plan = chunk.has_delete_predicates();
 
                 // Try and apply the predicate using only metadata
-                let pred_result = chunk
-                    .apply_predicate_to_metadata(predicate)
-                    .map_err(|e| Box::new(e) as _)
-                    .context(CheckingChunkPredicateSnafu {
-                        chunk_id: chunk.id(),
-                    })?;
+                let pred_result = self.apply_predicate_to_metadata(chunk.as_ref(), predicate)?;
 
                 if matches!(pred_result, PredicateMatch::Zero) {
                     continue;
@@ -524,7 +854,13 @@ impl InfluxRpcPlanner {
                                 chunk_id=%chunk.id().get(),
                                 "tag values found from metadata",
                             );
-                            known_values.append(&mut names);
+                            if !names.is_empty() {
+                                table_values
+                                    .entry(table_name.clone())
+                                    .or_default()
+                                    .known
+                                    .append(&mut names);
+                            }
                         }
                         None => {
                             do_full_plan = true;
@@ -549,8 +885,6 @@ impl InfluxRpcPlanner {
             }
         }
 
-        let mut builder = StringSetPlanBuilder::new();
-
         let select_exprs = vec![col(tag_name)];
 
         // At this point, we have a set of tag_values we know at plan
@@ -591,16 +925,12 @@ impl InfluxRpcPlanner {
                         .build()
                         .context(BuildingPlanSnafu)?;
 
-                    builder = builder.append_other(plan.into());
+                    table_values.entry(table_name.clone()).or_default().plan = Some(plan);
                 }
             }
         }
 
-        // add the known values we could find from metadata only
-        builder
-            .append_other(known_values.into())
-            .build()
-            .context(CreatingStringSetSnafu)
+        Ok(table_values)
     }
 
     /// Returns a plan that produces a list of columns and their
@@ -626,9 +956,12 @@ impl InfluxRpcPlanner {
         let table_predicates = rpc_predicate.table_predicates(database);
         let mut field_list_plan = FieldListPlan::with_capacity(table_predicates.len());
 
+        let mut num_chunks = 0;
         for (table_name, predicate) in &table_predicates {
-            let chunks = database.chunks(table_name, predicate);
-            let chunks = prune_chunks_metadata(chunks, predicate)?;
+            let chunks = self.chunks(database, table_name, predicate);
+            let chunks = self.prune_chunks(chunks, predicate)?;
+            num_chunks += chunks.len();
+            self.check_max_chunks(num_chunks)?;
 
             if chunks.is_empty() {
                 continue;
@@ -676,9 +1009,17 @@ impl InfluxRpcPlanner {
 
         let table_predicates = rpc_predicate.table_predicates(database);
         let mut ss_plans = Vec::with_capacity(table_predicates.len());
+        let mut num_chunks = 0;
         for (table_name, predicate) in &table_predicates {
-            let chunks = database.chunks(table_name, predicate);
-            let chunks = prune_chunks_metadata(chunks, predicate)?;
+            debug!(
+                %table_name,
+                predicate=%predicate_to_display(predicate),
+                "planning read_filter for table"
+            );
+            let chunks = self.chunks(database, table_name, predicate);
+            let chunks = self.prune_chunks(chunks, predicate)?;
+            num_chunks += chunks.len();
+            self.check_max_chunks(num_chunks)?;
 
             if chunks.is_empty() {
                 continue;
@@ -695,7 +1036,7 @@ impl InfluxRpcPlanner {
             }
         }
 
-        Ok(SeriesSetPlans::new(ss_plans))
+        Ok(self.series_set_plans(ss_plans))
     }
 
     /// Creates one or more GroupedSeriesSet plans that produces an
@@ -730,12 +1071,79 @@ impl InfluxRpcPlanner {
     {
         debug!(?rpc_predicate, ?agg, "planning read_group");
 
+        self.read_group_impl(
+            database,
+            rpc_predicate,
+            group_columns,
+            |table_name, schema, predicate, chunks| match agg {
+                Aggregate::None => self.read_filter_plan(table_name, schema, predicate, chunks),
+                _ => self.read_group_plan(table_name, schema, predicate, agg, chunks),
+            },
+        )
+    }
+
+    /// Creates one or more GroupedSeriesSet plans like
+    /// [`Self::read_group`], but computing each of `aggs` in a single pass
+    /// over the data.
+    ///
+    /// Each aggregate produces its own series for every field, with the
+    /// field named `<field>_<aggregate>` (e.g. `temp_mean` and `temp_max`)
+    /// so that the aggregates can be told apart.
+    pub fn read_group_aggregates<D>(
+        &self,
+        database: &D,
+        rpc_predicate: InfluxRpcPredicate,
+        aggs: &[Aggregate],
+        group_columns: &[impl AsRef<str>],
+    ) -> Result<SeriesSetPlans>
+    where
+        D: QueryDatabase + 'static,
+    {
+        debug!(?rpc_predicate, ?aggs, "planning read_group_aggregates");
+
+        ensure!(!aggs.is_empty(), NoAggregatesSnafu);
+        ensure!(
+            !aggs.contains(&Aggregate::None),
+            InternalUnexpectedNoneAggregateSnafu
+        );
+
+        self.read_group_impl(
+            database,
+            rpc_predicate,
+            group_columns,
+            |table_name, schema, predicate, chunks| {
+                self.read_group_aggregates_plan(table_name, schema, predicate, aggs, chunks)
+            },
+        )
+    }
+
+    /// Creates the plans for a read_group request, using `make_plan` to
+    /// create the plan for each table.
+    fn read_group_impl<D, F>(
+        &self,
+        database: &D,
+        rpc_predicate: InfluxRpcPredicate,
+        group_columns: &[impl AsRef<str>],
+        make_plan: F,
+    ) -> Result<SeriesSetPlans>
+    where
+        D: QueryDatabase + 'static,
+        F: Fn(&str, Arc<Schema>, &Predicate, Vec<Arc<D::Chunk>>) -> Result<Option<SeriesSetPlan>>,
+    {
         let table_predicates = rpc_predicate.table_predicates(database);
         let mut ss_plans = Vec::with_capacity(table_predicates.len());
 
+        let mut num_chunks = 0;
         for (table_name, predicate) in &table_predicates {
-            let chunks = database.chunks(table_name, predicate);
-            let chunks = prune_chunks_metadata(chunks, predicate)?;
+            debug!(
+                %table_name,
+                predicate=%predicate_to_display(predicate),
+                "planning read_group for table"
+            );
+            let chunks = self.chunks(database, table_name, predicate);
+            let chunks = self.prune_chunks(chunks, predicate)?;
+            num_chunks += chunks.len();
+            self.check_max_chunks(num_chunks)?;
 
             if chunks.is_empty() {
                 continue;
@@ -745,12 +1153,7 @@ impl InfluxRpcPlanner {
                 .table_schema(table_name)
                 .context(TableRemovedSnafu { table_name })?;
 
-            let ss_plan = match agg {
-                Aggregate::None => {
-                    self.read_filter_plan(table_name, Arc::clone(&schema), predicate, chunks)?
-                }
-                _ => self.read_group_plan(table_name, schema, predicate, agg, chunks)?,
-            };
+            let ss_plan = make_plan(table_name, schema, predicate, chunks)?;
 
             // If we have to do real work, add it to the list of plans
             if let Some(ss_plan) = ss_plan {
@@ -758,7 +1161,7 @@ impl InfluxRpcPlanner {
             }
         }
 
-        let plan = SeriesSetPlans::new(ss_plans);
+        let plan = self.series_set_plans(ss_plans);
 
         // Note always group (which will resort the frames)
         // by tag, even if there are 0 columns
@@ -794,9 +1197,12 @@ impl InfluxRpcPlanner {
         // group tables by chunk, pruning if possible
         let table_predicates = rpc_predicate.table_predicates(database);
         let mut ss_plans = Vec::with_capacity(table_predicates.len());
+        let mut num_chunks = 0;
         for (table_name, predicate) in &table_predicates {
-            let chunks = database.chunks(table_name, predicate);
-            let chunks = prune_chunks_metadata(chunks, predicate)?;
+            let chunks = self.chunks(database, table_name, predicate);
+            let chunks = self.prune_chunks(chunks, predicate)?;
+            num_chunks += chunks.len();
+            self.check_max_chunks(num_chunks)?;
 
             if chunks.is_empty() {
                 continue;
@@ -816,7 +1222,7 @@ impl InfluxRpcPlanner {
             }
         }
 
-        Ok(SeriesSetPlans::new(ss_plans))
+        Ok(self.series_set_plans(ss_plans))
     }
 
     /// Creates a DataFusion LogicalPlan that returns column *names* as a
@@ -1121,6 +1527,50 @@ impl InfluxRpcPlanner {
     ) -> Result<Option<SeriesSetPlan>>
     where
         C: QueryChunk + 'static,
+    {
+        self.grouped_aggregate_plan(table_name, schema, predicate, chunks, |schema| {
+            let agg_exprs = AggExprs::try_new_for_read_group(agg, schema, predicate)?;
+            Ok(vec![(agg, agg_exprs)])
+        })
+    }
+
+    /// Creates a SeriesSetPlan like [`Self::read_group_plan`] that computes
+    /// all of `aggs`, naming the output field columns
+    /// `<field>_<aggregate>`.
+    fn read_group_aggregates_plan<C>(
+        &self,
+        table_name: &str,
+        schema: Arc<Schema>,
+        predicate: &Predicate,
+        aggs: &[Aggregate],
+        chunks: Vec<Arc<C>>,
+    ) -> Result<Option<SeriesSetPlan>>
+    where
+        C: QueryChunk + 'static,
+    {
+        self.grouped_aggregate_plan(table_name, schema, predicate, chunks, |schema| {
+            aggs.iter()
+                .map(|&agg| {
+                    let agg_exprs = AggExprs::try_new_for_read_group_named(agg, schema, predicate)?;
+                    Ok((agg, agg_exprs))
+                })
+                .collect()
+        })
+    }
+
+    /// Creates a SeriesSetPlan that groups by all tag columns and computes
+    /// the aggregates returned by `make_aggs` for the scanned schema.
+    fn grouped_aggregate_plan<C, F>(
+        &self,
+        table_name: &str,
+        schema: Arc<Schema>,
+        predicate: &Predicate,
+        chunks: Vec<Arc<C>>,
+        make_aggs: F,
+    ) -> Result<Option<SeriesSetPlan>>
+    where
+        C: QueryChunk + 'static,
+        F: FnOnce(&Schema) -> Result<Vec<(Aggregate, AggExprs)>>,
     {
         let scan_and_filter = self.scan_and_filter(table_name, schema, predicate, chunks)?;
 
@@ -1143,10 +1593,11 @@ impl InfluxRpcPlanner {
             .map(|tag_name| tag_name.as_expr())
             .collect::<Vec<_>>();
 
-        let AggExprs {
-            agg_exprs,
-            field_columns,
-        } = AggExprs::try_new_for_read_group(agg, &schema, predicate)?;
+        let aggs = make_aggs(&schema)?;
+        let agg_exprs = aggs
+            .iter()
+            .flat_map(|(_, agg_exprs)| agg_exprs.agg_exprs.iter().cloned())
+            .collect::<Vec<_>>();
 
         let plan_builder = plan_builder
             .aggregate(group_exprs, agg_exprs)
@@ -1178,10 +1629,30 @@ impl InfluxRpcPlanner {
             plan_builder
         };
 
-        let plan_builder = cast_aggregates(plan_builder, agg, &field_columns)?;
+        let mut plan_builder = plan_builder;
+        for (agg, agg_exprs) in &aggs {
+            plan_builder = cast_aggregates(plan_builder, *agg, &agg_exprs.field_columns)?;
+        }
 
         let plan = plan_builder.build().context(BuildingPlanSnafu)?;
 
+        let field_columns = match aggs.len() {
+            1 => aggs.into_iter().next().unwrap().1.field_columns,
+            // The field columns of each aggregate have distinct names, so can
+            // be combined into one list
+            _ => aggs
+                .into_iter()
+                .flat_map(|(_, agg_exprs)| match agg_exprs.field_columns {
+                    FieldColumns::SharedTimestamp(names) => names
+                        .into_iter()
+                        .map(|name| (name, Arc::from(TIME_COLUMN_NAME)))
+                        .collect::<Vec<_>>(),
+                    FieldColumns::DifferentTimestamp(names) => names,
+                })
+                .collect::<Vec<_>>()
+                .into(),
+        };
+
         let tag_columns = tag_columns.iter().map(|s| Arc::from(*s)).collect();
         let ss_plan = SeriesSetPlan::new(
             Arc::from(table_name.to_string()),
@@ -1374,13 +1845,40 @@ impl InfluxRpcPlanner {
     }
 }
 
+/// Returns the match of an empty predicate for `chunk` from its summary alone:
+/// [`PredicateMatch::AtLeastOneNonNullField`] if some field has a non-null
+/// value. Returns `None` if the chunk has no summary or if the summary can't
+/// tell, e.g. because all its field values are null.
+fn empty_predicate_match<C>(chunk: &C) -> Option<PredicateMatch>
+where
+    C: QueryChunk,
+{
+    chunk
+        .summary()?
+        .columns
+        .iter()
+        .any(|c| c.influxdb_type == Some(InfluxDbType::Field) && c.null_count() < c.total_count())
+        .then(|| PredicateMatch::AtLeastOneNonNullField)
+}
+
 /// Prunes the provided list of chunks using [`QueryChunk::apply_predicate_to_metadata`]
 ///
+/// An empty predicate matches every row, so all chunks are kept without
+/// evaluating it against their metadata.
+///
 /// TODO: Should this logic live with the rest of the chunk pruning logic?
 fn prune_chunks_metadata<C>(chunks: Vec<Arc<C>>, predicate: &Predicate) -> Result<Vec<Arc<C>>>
 where
     C: QueryChunk + 'static,
 {
+    if predicate.is_empty() {
+        trace!(
+            num_chunks = chunks.len(),
+            "empty predicate, keeping all chunks"
+        );
+        return Ok(chunks);
+    }
+
     let mut filtered = Vec::with_capacity(chunks.len());
     for chunk in chunks {
         // Try and apply the predicate using only metadata
@@ -1467,6 +1965,16 @@ fn cast_aggregates(
     plan_builder.project(cast_exprs).context(BuildingPlanSnafu)
 }
 
+/// The values of a tag in a single table, as found by
+/// [`InfluxRpcPlanner::tag_values`].
+#[derive(Debug, Default)]
+struct TableTagValues {
+    /// Values known from metadata alone
+    known: StringSet,
+    /// A plan to find the values that could not be determined from metadata
+    plan: Option<LogicalPlan>,
+}
+
 struct TableScanAndFilter {
     /// Represents plan that scans a table and applies optional filtering
     plan_builder: LogicalPlanBuilder,
@@ -1553,6 +2061,87 @@ impl AggExprs {
         }
     }
 
+    /// Create aggregate expressions for a `read_group` plan like
+    /// [`Self::try_new_for_read_group`], but with each output column
+    /// suffixed by the name of `agg` so that several aggregates can be
+    /// computed in the same plan.
+    ///
+    /// Equivalent SQL would look like:
+    ///
+    ///   agg_function(_val1) as _value1_agg
+    ///   agg_function(time) as time_agg
+    ///   ..
+    ///
+    /// for aggregates, and for selectors:
+    ///
+    ///   agg_function(_val1) as _value1_agg
+    ///   agg_function(time) as time__value1_agg
+    ///   ..
+    pub fn try_new_for_read_group_named(
+        agg: Aggregate,
+        schema: &Schema,
+        predicate: &Predicate,
+    ) -> Result<Self> {
+        let mut agg_exprs = Vec::new();
+        let mut field_list = Vec::new();
+
+        match agg {
+            Aggregate::Sum | Aggregate::Count | Aggregate::Mean => {
+                let time_column_name = format!("{}_{}", TIME_COLUMN_NAME, agg);
+
+                for field in filtered_fields_iter(schema, predicate) {
+                    let value_column_name = format!("{}_{}", field.name, agg);
+                    agg_exprs.push(make_named_agg_expr(agg, field, &value_column_name)?);
+                    field_list.push((
+                        Arc::from(value_column_name.as_str()),
+                        Arc::from(time_column_name.as_str()),
+                    ));
+                }
+
+                for field in schema.time_iter() {
+                    agg_exprs.push(make_named_agg_expr(
+                        agg,
+                        FieldExpr {
+                            expr: col(field.name()),
+                            datatype: field.data_type(),
+                            name: field.name(),
+                        },
+                        &time_column_name,
+                    )?);
+                }
+            }
+            Aggregate::First | Aggregate::Last | Aggregate::Min | Aggregate::Max => {
+                for field in filtered_fields_iter(schema, predicate) {
+                    let value_column_name = format!("{}_{}", field.name, agg);
+                    let time_column_name = format!("{}_{}", TIME_COLUMN_NAME, value_column_name);
+
+                    agg_exprs.push(make_selector_expr(
+                        agg,
+                        SelectorOutput::Value,
+                        field.clone(),
+                        &value_column_name,
+                    )?);
+                    agg_exprs.push(make_selector_expr(
+                        agg,
+                        SelectorOutput::Time,
+                        field,
+                        &time_column_name,
+                    )?);
+                    field_list.push((
+                        Arc::from(value_column_name.as_str()),
+                        Arc::from(time_column_name.as_str()),
+                    ));
+                }
+            }
+            Aggregate::None => return InternalUnexpectedNoneAggregateSnafu.fail(),
+        }
+
+        Ok(Self {
+            agg_exprs,
+            field_columns: field_list.into(),
+        })
+    }
+
     /// Create the appropriate aggregate expressions, based on the type of the
     /// field for a `read_window_aggregate` plan.
     pub fn try_new_for_read_window_aggregate(
@@ -1688,6 +2277,15 @@ impl AggExprs {
 ///
 /// equivalent to `CAST agg(field) as field`
 fn make_agg_expr(agg: Aggregate, field_expr: FieldExpr<'_>) -> Result<Expr> {
+    let field_name = field_expr.name;
+    make_named_agg_expr(agg, field_expr, field_name)
+}
+
+/// Creates a DataFusion expression suitable for calculating an aggregate,
+/// like [`make_agg_expr`] but with the output named `col_name`:
+///
+/// equivalent to `CAST agg(field) as col_name`
+fn make_named_agg_expr(agg: Aggregate, field_expr: FieldExpr<'_>, col_name: &str) -> Result<Expr> {
     // For timestamps, use `MAX` which corresponds to the last
     // timestamp in the group, unless `MIN` was specifically requested
     // to be consistent with the Go implementation which takes the
@@ -1698,10 +2296,9 @@ fn make_agg_expr(agg: Aggregate, field_expr: FieldExpr<'_>) -> Result<Expr> {
         agg
     };
 
-    let field_name = field_expr.name;
     agg.to_datafusion_expr(field_expr.expr)
         .context(CreatingAggregatesSnafu)
-        .map(|agg| agg.alias(field_name))
+        .map(|agg| agg.alias(col_name))
 }
 
 /// Creates a DataFusion expression suitable for calculating the time part of a
@@ -1738,6 +2335,133 @@ fn make_selector_expr<'a>(
         .alias(col_name))
 }
 
+/// The non-null points of a single field column, typed by the data type
+/// of the field.
+///
+/// This is the data of a [`crate::exec::seriesset::series::Series`] without
+/// its tags, as produced by [`field_points_stream`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldPoints {
+    Float {
+        field_name: Arc<str>,
+        timestamps: Vec<i64>,
+        values: Vec<f64>,
+    },
+    Integer {
+        field_name: Arc<str>,
+        timestamps: Vec<i64>,
+        values: Vec<i64>,
+    },
+    Unsigned {
+        field_name: Arc<str>,
+        timestamps: Vec<i64>,
+        values: Vec<u64>,
+    },
+    Boolean {
+        field_name: Arc<str>,
+        timestamps: Vec<i64>,
+        values: Vec<bool>,
+    },
+    String {
+        field_name: Arc<str>,
+        timestamps: Vec<i64>,
+        values: Vec<String>,
+    },
+}
+
+impl FieldPoints {
+    fn new(field_name: Arc<str>, data: Data) -> Self {
+        match data {
+            Data::FloatPoints { timestamps, values } => Self::Float {
+                field_name,
+                timestamps,
+                values,
+            },
+            Data::IntegerPoints { timestamps, values } => Self::Integer {
+                field_name,
+                timestamps,
+                values,
+            },
+            Data::UnsignedPoints { timestamps, values } => Self::Unsigned {
+                field_name,
+                timestamps,
+                values,
+            },
+            Data::BooleanPoints { timestamps, values } => Self::Boolean {
+                field_name,
+                timestamps,
+                values,
+            },
+            Data::StringPoints { timestamps, values } => Self::String {
+                field_name,
+                timestamps,
+                values,
+            },
+        }
+    }
+
+    /// The name of the field these points belong to
+    pub fn field_name(&self) -> &str {
+        match self {
+            Self::Float { field_name, .. }
+            | Self::Integer { field_name, .. }
+            | Self::Unsigned { field_name, .. }
+            | Self::Boolean { field_name, .. }
+            | Self::String { field_name, .. } => field_name,
+        }
+    }
+}
+
+/// Converts `stream` into a stream of [`FieldPoints`], yielding the points
+/// of each field in `field_columns` for every record batch in which that
+/// field has at least one non-null value.
+///
+/// Fields are yielded in the order of `field_columns`, and rows where a
+/// field is null, NaN or infinite are skipped, matching the series set
+/// output format.
+pub fn field_points_stream(
+    stream: SendableRecordBatchStream,
+    field_columns: FieldColumns,
+) -> impl Stream<Item = Result<FieldPoints>> {
+    stream
+        .map(move |batch| {
+            let batch = batch.context(ReadingFieldPointsSnafu)?;
+            record_batch_to_field_points(batch, &field_columns)
+        })
+        .map_ok(|points| futures::stream::iter(points.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+fn record_batch_to_field_points(
+    batch: RecordBatch,
+    field_columns: &FieldColumns,
+) -> Result<Vec<FieldPoints>> {
+    let field_indexes = FieldIndexes::from_field_columns(&batch.schema(), field_columns)
+        .context(FindingFieldPointsColumnsSnafu)?;
+
+    let series_set = SeriesSet {
+        table_name: Arc::from(""),
+        tags: vec![],
+        field_indexes,
+        start_row: 0,
+        num_rows: batch.num_rows(),
+        batch,
+    };
+
+    let series = series_set
+        .try_into_series(false)
+        .context(ConvertingFieldPointsSnafu)?;
+
+    Ok(series
+        .into_iter()
+        .map(|mut series| {
+            // the `_field` tag is always last
+            let field = series.tags.pop().expect("series has a field tag");
+            FieldPoints::new(field.value, series.data)
+        })
+        .collect())
+}
+
 /// Rewrites the provided expr such that references to any column that
 /// are not present in `schema` become null.
 ///
@@ -1833,12 +2557,20 @@ impl<'a> ExprRewriter for MissingColumnsToNull<'a> {
 
 #[cfg(test)]
 mod tests {
+    use arrow::{
+        array::{
+            ArrayRef, BooleanArray, DictionaryArray, Float64Array, Int64Array, StringArray,
+            TimestampNanosecondArray, UInt64Array,
+        },
+        datatypes::Int32Type,
+    };
     use datafusion::logical_plan::lit;
+    use datafusion_util::MemoryStream;
     use predicate::predicate::PredicateBuilder;
     use schema::builder::SchemaBuilder;
 
     use crate::{
-        exec::Executor,
+        exec::{Executor, ExecutorType},
         test::{TestChunk, TestDatabase},
     };
 
@@ -2075,4 +2807,340 @@ mod tests {
             actual_predicate, expected_predicate
         );
     }
+
+    #[test]
+    fn test_prune_chunks_metadata_empty_predicate() {
+        // A chunk whose metadata never matches any predicate
+        let chunk = || {
+            Arc::new(
+                TestChunk::new("h2o")
+                    .with_id(0)
+                    .with_predicate_match(PredicateMatch::Zero),
+            )
+        };
+
+        // An empty predicate keeps the chunk without consulting its metadata
+        let empty_chunk = chunk();
+        let predicate = Predicate::default();
+        assert!(predicate.is_empty());
+        let chunks = prune_chunks_metadata(vec![Arc::clone(&empty_chunk)], &predicate).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(empty_chunk.predicates().is_empty());
+
+        // A non-empty predicate is evaluated against the metadata
+        let filtered_chunk = chunk();
+        let predicate = PredicateBuilder::default().timestamp_range(0, 100).build();
+        assert!(!predicate.is_empty());
+        let chunks = prune_chunks_metadata(vec![Arc::clone(&filtered_chunk)], &predicate).unwrap();
+        assert!(chunks.is_empty());
+        assert_eq!(filtered_chunk.predicates(), vec![predicate]);
+    }
+
+    #[test]
+    fn test_empty_predicate_matched_from_summary() {
+        // Chunks whose metadata never matches any predicate, but whose
+        // summary shows non-null field values for the first only
+        let with_values = Arc::new(
+            TestChunk::new("h2o")
+                .with_id(0)
+                .with_time_column()
+                .with_tag_column("state")
+                .with_i64_field_column_with_counts("temp", 5, 0)
+                .with_predicate_match(PredicateMatch::Zero),
+        );
+        let all_null = Arc::new(
+            TestChunk::new("o2")
+                .with_id(1)
+                .with_time_column()
+                .with_tag_column("state")
+                .with_i64_field_column_with_counts("temp", 5, 5)
+                .with_predicate_match(PredicateMatch::Zero),
+        );
+
+        let executor = Arc::new(Executor::new(1));
+        let test_db = TestDatabase::new(Arc::clone(&executor));
+        test_db.add_chunk("my_partition_key", Arc::clone(&with_values));
+        test_db.add_chunk("my_partition_key", Arc::clone(&all_null));
+
+        // An empty predicate is answered from the summary if it can be, and
+        // from the chunk metadata otherwise
+        let planner = InfluxRpcPlanner::new();
+        let plan = planner
+            .table_names(&test_db, InfluxRpcPredicate::default())
+            .unwrap();
+        match plan {
+            StringSetPlan::Known(names) => {
+                assert_eq!(names.iter().collect::<Vec<_>>(), vec!["h2o"])
+            }
+            _ => panic!("expected known table names, got {:?}", plan),
+        }
+        assert!(with_values.predicates().is_empty());
+        assert_eq!(all_null.predicates().len(), 1);
+
+        for plan in [
+            planner.tag_keys(&test_db, InfluxRpcPredicate::default()),
+            planner.tag_values(&test_db, "state", InfluxRpcPredicate::default()),
+        ] {
+            plan.unwrap();
+        }
+        assert!(with_values.predicates().is_empty());
+        assert_eq!(all_null.predicates().len(), 3);
+    }
+
+    #[test]
+    fn test_max_chunks() {
+        let executor = Arc::new(Executor::new(1));
+        let test_db = TestDatabase::new(Arc::clone(&executor));
+        for id in 0..4 {
+            let mut chunk = TestChunk::new("h2o")
+                .with_id(id)
+                .with_time_column()
+                .with_tag_column("state")
+                .with_i64_field_column("temp");
+            // One of the chunks is pruned
+            if id == 0 {
+                chunk = chunk.with_predicate_match(PredicateMatch::Zero);
+            }
+            test_db.add_chunk("my_partition_key", Arc::new(chunk));
+        }
+
+        let predicate = PredicateBuilder::default().timestamp_range(0, 100).build();
+        let rpc_predicate = InfluxRpcPredicate::new(None, predicate);
+
+        // Pruning leaves more chunks than the limit
+        let err = InfluxRpcPlanner::new()
+            .with_max_chunks(2)
+            .read_filter(&test_db, rpc_predicate.clone())
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::TooManyChunks {
+                    num_chunks: 3,
+                    max_chunks: 2
+                }
+            ),
+            "{}",
+            err
+        );
+
+        // The limit of a request can not exceed the server limit
+        let err = InfluxRpcPlanner::new()
+            .with_max_chunks(2)
+            .with_request_max_chunks(10)
+            .read_filter(&test_db, rpc_predicate.clone())
+            .unwrap_err();
+        assert!(matches!(err, Error::TooManyChunks { max_chunks: 2, .. }));
+
+        // But it can lower it
+        let err = InfluxRpcPlanner::new()
+            .with_max_chunks(10)
+            .with_request_max_chunks(1)
+            .read_filter(&test_db, rpc_predicate.clone())
+            .unwrap_err();
+        assert!(matches!(err, Error::TooManyChunks { max_chunks: 1, .. }));
+
+        // The pruned chunk does not count against the limit
+        InfluxRpcPlanner::new()
+            .with_max_chunks(3)
+            .read_filter(&test_db, rpc_predicate)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let chunk = |id, min, max| {
+            TestChunk::new("h2o")
+                .with_id(id)
+                .with_time_column_with_full_stats(Some(min), Some(max), 10, None)
+        };
+
+        let executor = Arc::new(Executor::new(1));
+        let test_db = TestDatabase::new(Arc::clone(&executor));
+        test_db.add_chunk("my_partition_key", Arc::new(chunk(0, 1, 100)));
+        // A chunk whose metadata never matches a predicate
+        test_db.add_chunk(
+            "my_partition_key",
+            Arc::new(chunk(1, 200, 300).with_predicate_match(PredicateMatch::Zero)),
+        );
+
+        let planner = InfluxRpcPlanner::new();
+
+        // Without a predicate all chunks are scanned
+        let estimate = planner
+            .estimate_cost(&test_db, InfluxRpcPredicate::default())
+            .unwrap();
+        assert_eq!(
+            estimate,
+            QueryCostEstimate {
+                chunks: 2,
+                estimated_rows: 20,
+                requires_sort: false,
+            }
+        );
+
+        // The second chunk is pruned
+        let predicate = PredicateBuilder::default().timestamp_range(0, 150).build();
+        let rpc_predicate = InfluxRpcPredicate::new(None, predicate);
+        let estimate = planner
+            .estimate_cost(&test_db, rpc_predicate.clone())
+            .unwrap();
+        assert_eq!(
+            estimate,
+            QueryCostEstimate {
+                chunks: 1,
+                estimated_rows: 10,
+                requires_sort: false,
+            }
+        );
+
+        // Unless pruning is disabled
+        let estimate = InfluxRpcPlanner::new()
+            .with_disable_pruning(true)
+            .estimate_cost(&test_db, rpc_predicate.clone())
+            .unwrap();
+        assert_eq!(estimate.chunks, 2);
+
+        // Overlapping chunks need to be sorted to be deduplicated
+        test_db.add_chunk("my_partition_key", Arc::new(chunk(2, 50, 150)));
+        let estimate = planner.estimate_cost(&test_db, rpc_predicate).unwrap();
+        assert_eq!(
+            estimate,
+            QueryCostEstimate {
+                chunks: 2,
+                estimated_rows: 20,
+                requires_sort: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_field_points_stream() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "float",
+                Arc::new(Float64Array::from(vec![Some(1.5), None])) as ArrayRef,
+            ),
+            (
+                "int",
+                Arc::new(Int64Array::from(vec![Some(1), Some(2)])) as ArrayRef,
+            ),
+            (
+                "uint",
+                Arc::new(UInt64Array::from(vec![None, Some(5)])) as ArrayRef,
+            ),
+            (
+                "bool",
+                Arc::new(BooleanArray::from(vec![None, Some(true)])) as ArrayRef,
+            ),
+            (
+                "str",
+                Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_vec(vec![100, 200], None)) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let stream = Box::pin(MemoryStream::new(vec![batch]));
+        let field_columns = FieldColumns::from(vec!["float", "int", "uint", "bool", "str"]);
+
+        let points: Vec<_> = field_points_stream(stream, field_columns)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            points,
+            vec![
+                FieldPoints::Float {
+                    field_name: Arc::from("float"),
+                    timestamps: vec![100],
+                    values: vec![1.5],
+                },
+                FieldPoints::Integer {
+                    field_name: Arc::from("int"),
+                    timestamps: vec![100, 200],
+                    values: vec![1, 2],
+                },
+                FieldPoints::Unsigned {
+                    field_name: Arc::from("uint"),
+                    timestamps: vec![200],
+                    values: vec![5],
+                },
+                FieldPoints::Boolean {
+                    field_name: Arc::from("bool"),
+                    timestamps: vec![200],
+                    values: vec![true],
+                },
+                FieldPoints::String {
+                    field_name: Arc::from("str"),
+                    timestamps: vec![100, 200],
+                    values: vec!["a".to_string(), "b".to_string()],
+                },
+            ]
+        );
+        assert_eq!(points[3].field_name(), "bool");
+    }
+
+    #[tokio::test]
+    async fn test_read_window_aggregate_non_finite_mean() {
+        let chunk = TestChunk::new("h2o")
+            .with_id(0)
+            .with_tag_column("state")
+            .with_f64_field_column("temp")
+            .with_time_column();
+
+        // The sum of the values in the second window overflows, so their
+        // mean is infinite
+        let schema = chunk.schema();
+        let columns = schema
+            .iter()
+            .map(|(_, field)| match field.name().as_str() {
+                "state" => Arc::new(
+                    vec!["MA", "MA", "MA"]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ) as ArrayRef,
+                "temp" => Arc::new(Float64Array::from(vec![10.0, f64::MAX, f64::MAX])) as ArrayRef,
+                "time" => Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![100, 1100, 1200],
+                    None,
+                )) as ArrayRef,
+                name => panic!("unexpected column {}", name),
+            })
+            .collect();
+        let batch = RecordBatch::try_new(schema.as_arrow(), columns).unwrap();
+
+        let executor = Arc::new(Executor::new(1));
+        let test_db = TestDatabase::new(Arc::clone(&executor));
+        test_db.add_chunk("my_partition_key", Arc::new(chunk.with_record_batch(batch)));
+
+        let plans = InfluxRpcPlanner::new()
+            .read_window_aggregate(
+                &test_db,
+                InfluxRpcPredicate::new_table("h2o", Predicate::default()),
+                Aggregate::Mean,
+                WindowDuration::from_nanoseconds(1000),
+                WindowDuration::empty(),
+            )
+            .expect("creating plan");
+
+        let results = executor
+            .new_context(ExecutorType::Query)
+            .to_series_and_groups(plans)
+            .await
+            .expect("running plan")
+            .into_iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>();
+
+        // the infinite mean is emitted as null, i.e. it has no point
+        let expected = vec![
+            "Series tags={_measurement=h2o, state=MA, _field=temp}\n  FloatPoints timestamps: [1000], values: [10.0]",
+        ];
+        assert_eq!(results, expected);
+    }
 }
@@ -51,8 +51,9 @@ use schema::TIME_DATA_TYPE;
 ///
 /// value is the value of the value_column at the position of the
 /// minimum of the timestamp column. If there are multiple rows with
-/// the minimum timestamp value, the value of the value_column is
-/// arbitrarily picked
+/// the minimum timestamp value, the greatest non-null value of the
+/// value_column among them is picked (NaN being greater than any other
+/// float), regardless of the order of the input
 pub fn selector_first(data_type: &DataType, output: SelectorOutput) -> AggregateUDF {
     let name = match output {
         SelectorOutput::Value => "selector_first_value",
@@ -81,8 +82,9 @@ pub fn selector_first(data_type: &DataType, output: SelectorOutput) -> Aggregate
 ///
 /// value is the value of the data_column at the position of the
 /// maximum of the timestamp column. If there are multiple rows with
-/// the maximum timestamp value, the value of the data_column is
-/// arbitrarily picked
+/// the maximum timestamp value, the greatest non-null value of the
+/// data_column among them is picked (NaN being greater than any other
+/// float), regardless of the order of the input
 pub fn selector_last(data_type: &DataType, output: SelectorOutput) -> AggregateUDF {
     let name = match output {
         SelectorOutput::Value => "selector_last_value",
@@ -439,6 +441,121 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_selector_first_last_colliding_timestamps() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("f64_value", DataType::Float64, false),
+            Field::new("time", TIME_DATA_TYPE(), true),
+        ]));
+
+        // Both batches have a value for the min and the max timestamp
+        let batch1 = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Float64Array::from(vec![Some(2.0), Some(9.0)])),
+                Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![1000, 5000],
+                    TIME_DATA_TIMEZONE(),
+                )),
+            ],
+        )
+        .unwrap();
+        let batch2 = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Float64Array::from(vec![Some(3.0), Some(1.0), Some(1.0)])),
+                Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![1000, 5000, 1000],
+                    TIME_DATA_TIMEZONE(),
+                )),
+            ],
+        )
+        .unwrap();
+
+        let args = vec![col("f64_value"), col("time")];
+        let aggs = vec![
+            selector_first(&DataType::Float64, SelectorOutput::Value).call(args.clone()),
+            selector_first(&DataType::Float64, SelectorOutput::Time).call(args.clone()),
+            selector_last(&DataType::Float64, SelectorOutput::Value).call(args.clone()),
+            selector_last(&DataType::Float64, SelectorOutput::Time).call(args),
+        ];
+
+        // The largest value wins, regardless of the input order
+        let expected = vec![
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+            "| selector_first_value(t.f64_value,t.time) | selector_first_time(t.f64_value,t.time) | selector_last_value(t.f64_value,t.time) | selector_last_time(t.f64_value,t.time) |",
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+            "| 3                                        | 1970-01-01 00:00:00.000001              | 9                                       | 1970-01-01 00:00:00.000005             |",
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+        ];
+
+        for input in [vec![batch1.clone(), batch2.clone()], vec![batch2, batch1]] {
+            let actual = run_with_inputs(Arc::clone(&schema), aggs.clone(), input).await;
+            assert_eq!(
+                expected, actual,
+                "\n\nEXPECTED:\n{:#?}\nACTUAL:\n{:#?}\n",
+                expected, actual
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_selector_first_last_colliding_timestamps_nan_and_null() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("f64_value", DataType::Float64, true),
+            Field::new("time", TIME_DATA_TYPE(), true),
+        ]));
+
+        let batch1 = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Float64Array::from(vec![Some(1.0), None, Some(4.0)])),
+                Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![1000, 1000, 5000],
+                    TIME_DATA_TIMEZONE(),
+                )),
+            ],
+        )
+        .unwrap();
+        let batch2 = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Float64Array::from(vec![Some(f64::NAN), None])),
+                Arc::new(TimestampNanosecondArray::from_vec(
+                    vec![1000, 5000],
+                    TIME_DATA_TIMEZONE(),
+                )),
+            ],
+        )
+        .unwrap();
+
+        let args = vec![col("f64_value"), col("time")];
+        let aggs = vec![
+            selector_first(&DataType::Float64, SelectorOutput::Value).call(args.clone()),
+            selector_first(&DataType::Float64, SelectorOutput::Time).call(args.clone()),
+            selector_last(&DataType::Float64, SelectorOutput::Value).call(args.clone()),
+            selector_last(&DataType::Float64, SelectorOutput::Time).call(args),
+        ];
+
+        // NaN is greater than any other value, and nulls are never selected
+        let expected = vec![
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+            "| selector_first_value(t.f64_value,t.time) | selector_first_time(t.f64_value,t.time) | selector_last_value(t.f64_value,t.time) | selector_last_time(t.f64_value,t.time) |",
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+            "| NaN                                      | 1970-01-01 00:00:00.000001              | 4                                       | 1970-01-01 00:00:00.000005             |",
+            "+------------------------------------------+-----------------------------------------+-----------------------------------------+----------------------------------------+",
+        ];
+
+        for input in [vec![batch1.clone(), batch2.clone()], vec![batch2, batch1]] {
+            let actual = run_with_inputs(Arc::clone(&schema), aggs.clone(), input).await;
+            assert_eq!(
+                expected, actual,
+                "\n\nEXPECTED:\n{:#?}\nACTUAL:\n{:#?}\n",
+                expected, actual
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_selector_min() {
         let cases = vec![
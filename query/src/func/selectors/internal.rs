@@ -64,6 +64,54 @@ impl LtVal<&str> for String {
     }
 }
 
+/// Total order of the values of rows with the same time, used by the
+/// first and last selectors to pick the same row regardless of the order
+/// of their input. Unlike [`LtVal`], NaN is ordered (above positive
+/// infinity, as in IEEE 754 `totalOrder`) and -0.0 is below 0.0.
+trait TieBreak<T> {
+    /// return true if v is greater than self in the total order
+    fn tie_break_lt(&self, v: &T) -> bool;
+}
+
+impl TieBreak<Self> for f64 {
+    fn tie_break_lt(&self, v: &Self) -> bool {
+        total_order_key(*self) < total_order_key(*v)
+    }
+}
+
+impl TieBreak<Self> for i64 {
+    fn tie_break_lt(&self, v: &Self) -> bool {
+        self < v
+    }
+}
+
+impl TieBreak<Self> for bool {
+    fn tie_break_lt(&self, v: &Self) -> bool {
+        self < v
+    }
+}
+
+impl TieBreak<Self> for &str {
+    fn tie_break_lt(&self, v: &Self) -> bool {
+        self < v
+    }
+}
+
+impl TieBreak<&str> for String {
+    fn tie_break_lt(&self, v: &&str) -> bool {
+        self.as_str() < *v
+    }
+}
+
+/// Maps `v` to an integer whose order is the IEEE 754 `totalOrder` of `v`
+/// (`f64::total_cmp` is not yet stable)
+fn total_order_key(v: f64) -> i64 {
+    let bits = v.to_bits() as i64;
+    // Flip all but the sign bit of negative values, so that they order
+    // in reverse of their magnitude
+    bits ^ (((bits >> 63) as u64) >> 1) as i64
+}
+
 /// Trait for comparing converting the result of aggregate kernels to their
 /// native representation Note the only one that is different is &str --> String
 trait ToState<T> {
@@ -168,24 +216,40 @@ macro_rules! make_first_selector {
 
                 let cur_min_time = $MINFUNC(&time_arr);
 
-                let need_update = match (&self.time, &cur_min_time) {
-                    (Some(time), Some(cur_min_time)) => cur_min_time < time,
+                // arrow doesn't tell us what index had the minimum, so
+                // need to find it ourselves see also
+                // https://github.com/apache/arrow-datafusion/issues/600
+                //
+                // If several rows have the minimum time, the value that
+                // is greatest in the total order of `TieBreak` wins, so
+                // the result does not depend on the order of the input.
+                // Rows with a null value are never selected
+                let index = time_arr
+                    .iter()
+                    .zip(value_arr.iter())
+                    .enumerate()
+                    .filter(|(_, (time, _))| cur_min_time.is_some() && cur_min_time == *time)
+                    .filter_map(|(idx, (_, value))| value.map(|value| (idx, value)))
+                    .reduce(|a, b| if a.1.tie_break_lt(&b.1) { b } else { a })
+                    .map(|(idx, _)| idx);
+
+                let need_update = match (&self.time, &cur_min_time, index) {
+                    (Some(time), Some(cur_min_time), Some(index)) => {
+                        cur_min_time < time
+                            || (cur_min_time == time
+                                && match &self.value {
+                                    Some(value) => value.tie_break_lt(&value_arr.value(index)),
+                                    None => true,
+                                })
+                    }
                     // No existing minimum, so update needed
-                    (None, Some(_)) => true,
+                    (None, Some(_), _) => true,
                     // No actual minimum time found, so no update needed
-                    (_, None) => false,
+                    _ => false,
                 };
 
                 if need_update {
-                    let index = time_arr
-                        .iter()
-                        // arrow doesn't tell us what index had the
-                        // minimum, so need to find it ourselves see also
-                        // https://github.com/apache/arrow-datafusion/issues/600
-                        .enumerate()
-                        .find(|(_, time)| cur_min_time == *time)
-                        .map(|(idx, _)| idx)
-                        .unwrap(); // value always exists
+                    let index = index.unwrap(); // value always exists
 
                     self.time = cur_min_time;
                     self.value = if value_arr.is_null(index) {
@@ -275,23 +339,39 @@ macro_rules! make_last_selector {
 
                 let cur_max_time = $MAXFUNC(&time_arr);
 
-                let need_update = match (&self.time, &cur_max_time) {
-                    (Some(time), Some(cur_max_time)) => time < cur_max_time,
+                // arrow doesn't tell us what index had the maximum, so
+                // need to find it ourselves
+                //
+                // If several rows have the maximum time, the value that
+                // is greatest in the total order of `TieBreak` wins, so
+                // the result does not depend on the order of the input.
+                // Rows with a null value are never selected
+                let index = time_arr
+                    .iter()
+                    .zip(value_arr.iter())
+                    .enumerate()
+                    .filter(|(_, (time, _))| cur_max_time.is_some() && cur_max_time == *time)
+                    .filter_map(|(idx, (_, value))| value.map(|value| (idx, value)))
+                    .reduce(|a, b| if a.1.tie_break_lt(&b.1) { b } else { a })
+                    .map(|(idx, _)| idx);
+
+                let need_update = match (&self.time, &cur_max_time, index) {
+                    (Some(time), Some(cur_max_time), Some(index)) => {
+                        time < cur_max_time
+                            || (time == cur_max_time
+                                && match &self.value {
+                                    Some(value) => value.tie_break_lt(&value_arr.value(index)),
+                                    None => true,
+                                })
+                    }
                     // No existing maximum, so update needed
-                    (None, Some(_)) => true,
+                    (None, Some(_), _) => true,
                     // No actual maximum value found, so no update needed
-                    (_, None) => false,
+                    _ => false,
                 };
 
                 if need_update {
-                    let index = time_arr
-                        .iter()
-                        // arrow doesn't tell us what index had the
-                        // maximum, so need to find it ourselves
-                        .enumerate()
-                        .find(|(_, time)| cur_max_time == *time)
-                        .map(|(idx, _)| idx)
-                        .unwrap(); // value always exists
+                    let index = index.unwrap(); // value always exists
 
                     self.time = cur_max_time;
                     self.value = if value_arr.is_null(index) {
@@ -29,6 +29,11 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// column of data into a single row by *selecting* a single row.  In
 /// other words, they can return the timestamp value from the
 /// associated row in addition to its value.
+///
+/// Aggregates over float fields may produce NaN or infinite values (for
+/// example the mean of values whose sum overflows). Such values are
+/// treated as null when results are converted to series, and are
+/// therefore never emitted.
 pub enum Aggregate {
     /// Aggregate: the sum of all values in the column
     Sum,
@@ -47,15 +52,25 @@ pub enum Aggregate {
     Max,
 
     /// Selector: Selects the value of a column with the minimum
-    /// timestamp and the associated timestamp. In the case of
-    /// multiple rows with the min timestamp, one is abritrarily
-    /// chosen
+    /// timestamp and the associated timestamp.
+    ///
+    /// Rows of the same series with the same timestamp are
+    /// deduplicated before the selector is applied, so the value
+    /// from the chunk with the highest `ChunkOrder` (the last write)
+    /// is used, even if another chunk has a greater value. Only rows
+    /// of different series can remain with the min timestamp, in which
+    /// case the greatest non-null value is used
     First,
 
-    /// Selector: Selects the value of a column with the minimum
-    /// timestamp and the associated timestamp. In the case of
-    /// multiple rows with the min timestamp, one is abritrarily
-    /// chosen
+    /// Selector: Selects the value of a column with the maximum
+    /// timestamp and the associated timestamp.
+    ///
+    /// Rows of the same series with the same timestamp are
+    /// deduplicated before the selector is applied, so the value
+    /// from the chunk with the highest `ChunkOrder` (the last write)
+    /// is used, even if another chunk has a greater value. Only rows
+    /// of different series can remain with the max timestamp, in which
+    /// case the greatest non-null value is used
     Last,
 
     /// Aggregate: Average (geometric mean) column's value
@@ -65,6 +80,22 @@ pub enum Aggregate {
     None,
 }
 
+impl std::fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Sum => "sum",
+            Self::Count => "count",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::First => "first",
+            Self::Last => "last",
+            Self::Mean => "mean",
+            Self::None => "none",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 /// Represents some duration in time
 #[derive(Debug, Clone, PartialEq)]
 pub enum WindowDuration {
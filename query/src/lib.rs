@@ -11,10 +11,11 @@
 use data_types::{
     chunk_metadata::{ChunkAddr, ChunkId, ChunkOrder, ChunkSummary},
     delete_predicate::DeletePredicate,
-    partition_metadata::{InfluxDbType, PartitionAddr, TableSummary},
+    partition_metadata::{InfluxDbType, PartitionAddr, PartitionMeta, TableSummary},
 };
 use datafusion::physical_plan::SendableRecordBatchStream;
-use exec::stringset::StringSet;
+use exec::{stringset::StringSet, IOxExecutionContext};
+use metric::{Attributes, U64Counter};
 use observability_deps::tracing::{debug, trace};
 use predicate::{
     predicate::{Predicate, PredicateMatch},
@@ -24,7 +25,7 @@ use schema::selection::Selection;
 use schema::{sort::SortKey, Schema, TIME_COLUMN_NAME};
 
 use hashbrown::HashMap;
-use std::{collections::BTreeSet, fmt::Debug, iter::FromIterator, sync::Arc};
+use std::{collections::BTreeSet, fmt::Debug, iter::FromIterator, ops::RangeInclusive, sync::Arc};
 
 pub mod exec;
 pub mod frontend;
@@ -38,6 +39,57 @@ pub mod util;
 
 pub use exec::context::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
 
+/// An inclusive range of the write buffer sequence numbers assigned by a
+/// single sequencer.
+///
+/// Sequence numbers are only unique within a sequencer, so ranges of
+/// different sequencers never overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceNumberRange {
+    sequencer_id: u32,
+    range: RangeInclusive<i64>,
+}
+
+impl SequenceNumberRange {
+    /// Create the range of the sequence numbers `range` assigned by the
+    /// sequencer `sequencer_id`.
+    pub fn new(sequencer_id: u32, range: RangeInclusive<i64>) -> Self {
+        Self {
+            sequencer_id,
+            range,
+        }
+    }
+
+    /// The ID of the sequencer that assigned the sequence numbers.
+    pub fn sequencer_id(&self) -> u32 {
+        self.sequencer_id
+    }
+
+    /// The first sequence number of the range.
+    pub fn start(&self) -> i64 {
+        *self.range.start()
+    }
+
+    /// The last sequence number of the range.
+    pub fn end(&self) -> i64 {
+        *self.range.end()
+    }
+
+    /// Returns true if the sequence number `sequence_number` of the sequencer
+    /// `sequencer_id` is within this range.
+    pub fn contains(&self, sequencer_id: u32, sequence_number: i64) -> bool {
+        self.sequencer_id == sequencer_id && self.range.contains(&sequence_number)
+    }
+
+    /// Returns true if `other` is a range of the same sequencer sharing at
+    /// least one sequence number with this range.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.sequencer_id == other.sequencer_id
+            && self.start() <= other.end()
+            && other.start() <= self.end()
+    }
+}
+
 /// Trait for an object (designed to be a Chunk) which can provide
 /// metadata
 pub trait QueryChunkMeta: Sized {
@@ -50,6 +102,10 @@ pub trait QueryChunkMeta: Sized {
     /// return a reference to delete predicates of the chunk
     fn delete_predicates(&self) -> &[Arc<DeletePredicate>];
 
+    /// Return the approximate in-memory size of this chunk, in bytes,
+    /// including its data, dictionaries and metadata
+    fn size_bytes(&self) -> usize;
+
     /// return true if the chunk has delete predicates
     fn has_delete_predicates(&self) -> bool {
         !self.delete_predicates().is_empty()
@@ -120,6 +176,9 @@ pub trait QueryDatabase: QueryDatabaseMeta + Debug + Send + Sync {
     /// Return the partition keys for data in this DB
     fn partition_addrs(&self) -> Vec<PartitionAddr>;
 
+    /// Return the persistence state of the partitions of `table_name`
+    fn partition_metadata(&self, table_name: &str) -> Vec<PartitionMeta>;
+
     /// Returns a set of chunks within the partition with data that may match
     /// the provided predicate. If possible, chunks which have no rows that can
     /// possibly match the predicate may be omitted.
@@ -128,14 +187,57 @@ pub trait QueryDatabase: QueryDatabaseMeta + Debug + Send + Sync {
     /// Return a summary of all chunks in this database, in all partitions
     fn chunk_summaries(&self) -> Vec<ChunkSummary>;
 
-    /// Record that particular type of query was run / planned
+    /// Record that particular type of query was run / planned in `ctx`
     fn record_query(
         &self,
+        ctx: &IOxExecutionContext,
         query_type: impl Into<String>,
         query_text: impl Into<String>,
     ) -> QueryCompletedToken<'_>;
 }
 
+/// Error with which [`QueryChunk::read_filter`] fails, as the error or the
+/// source of the chunk's error, when the chunk can not evaluate a clause of
+/// the predicate.
+///
+/// Callers can then, for example, read the chunk with a different
+/// predicate and filter the data themselves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedPredicate {
+    /// The clause of the predicate that can not be evaluated
+    pub clause: String,
+
+    /// Why the chunk can not evaluate `clause`
+    pub reason: String,
+}
+
+impl UnsupportedPredicate {
+    /// Returns the [`UnsupportedPredicate`] that is `err` or one of its
+    /// sources, if any
+    pub fn find<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a Self> {
+        let mut err = Some(err);
+        while let Some(e) = err {
+            if let Some(unsupported) = e.downcast_ref::<Self>() {
+                return Some(unsupported);
+            }
+            err = e.source();
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for UnsupportedPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unsupported predicate clause '{}': {}",
+            self.clause, self.reason
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedPredicate {}
+
 /// Collection of data that shares the same partition key
 pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync {
     type Error: std::error::Error + Send + Sync + 'static;
@@ -189,8 +291,9 @@ pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync {
     /// Provides access to raw `QueryChunk` data as an
     /// asynchronous stream of `RecordBatch`es filtered by a *required*
     /// predicate. Note that not all chunks can evaluate all types of
-    /// predicates and this function will return an error
-    /// if requested to evaluate with a predicate that is not supported
+    /// predicates and this function will return an error caused by an
+    /// [`UnsupportedPredicate`] if requested to evaluate with a predicate
+    /// that is not supported
     ///
     /// This is the analog of the `TableProvider` in DataFusion
     ///
@@ -216,6 +319,31 @@ pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync {
 
     /// Order of this chunk relative to other overlapping chunks.
     fn order(&self) -> ChunkOrder;
+
+    /// Returns the key of the partition of this chunk, if known
+    fn partition_key(&self) -> Option<Arc<str>> {
+        Some(self.addr().partition_key)
+    }
+
+    /// Returns the range of sequence numbers of the writes whose data is
+    /// contained in this chunk, if known
+    fn sequence_numbers(&self) -> Option<SequenceNumberRange> {
+        None
+    }
+
+    /// Returns a chunk with the data of this chunk, except for the data of
+    /// the writes with a sequence number within `exclude`, or `None` if no
+    /// data remains.
+    ///
+    /// Chunks only exclude data at the granularity at which they track
+    /// sequence numbers: writes that are stored together with writes outside
+    /// of `exclude` are kept. By default the chunk is returned unchanged.
+    fn exclude_sequence_numbers(
+        self: &Arc<Self>,
+        _exclude: &SequenceNumberRange,
+    ) -> Option<Arc<Self>> {
+        Some(Arc::clone(self))
+    }
 }
 
 /// Implement ChunkMeta for something wrapped in an Arc (like Chunks often are)
@@ -236,6 +364,10 @@ where
         debug!(?pred, "Delete predicate in QueryChunkMeta");
         pred
     }
+
+    fn size_bytes(&self) -> usize {
+        self.as_ref().size_bytes()
+    }
 }
 
 /// return true if all the chunks inlcude statistics
@@ -249,6 +381,57 @@ where
     chunks.iter().all(|c| c.summary().is_some())
 }
 
+/// Counts how often [`compute_sort_key_for_chunks_with_metrics`] computed a
+/// sort key from the statistics of the chunks, and how often it fell back to
+/// the primary key because some chunk had no statistics
+#[derive(Debug, Clone)]
+pub struct SortKeyMetrics {
+    /// Number of sort keys computed from the chunk statistics
+    with_stats: U64Counter,
+    /// Number of sort keys that fell back to the primary key
+    without_stats: U64Counter,
+}
+
+impl SortKeyMetrics {
+    /// Register the sort key metrics with the given registry, labelled with
+    /// `attributes`
+    pub fn new(registry: &metric::Registry, attributes: impl Into<Attributes>) -> Self {
+        let metric = registry.register_metric::<U64Counter>(
+            "query_sort_key_computed",
+            "Number of sort keys computed for a set of chunks, by whether all chunks had statistics",
+        );
+
+        let mut attributes = attributes.into();
+        attributes.insert("stats", "present");
+        let with_stats = metric.recorder(attributes.clone());
+        attributes.insert("stats", "missing");
+        let without_stats = metric.recorder(attributes);
+
+        Self {
+            with_stats,
+            without_stats,
+        }
+    }
+}
+
+/// Compute the sort key for `chunks`, see [`compute_sort_key_for_chunks`],
+/// recording in `metrics` whether it was computed from the chunk statistics
+pub fn compute_sort_key_for_chunks_with_metrics<'a, C>(
+    schema: &'a Schema,
+    chunks: &'a [C],
+    metrics: &SortKeyMetrics,
+) -> SortKey<'a>
+where
+    C: QueryChunkMeta,
+{
+    if chunks_have_stats(chunks) {
+        metrics.with_stats.inc(1);
+    } else {
+        metrics.without_stats.inc(1);
+    }
+    compute_sort_key_for_chunks(schema, chunks)
+}
+
 pub fn compute_sort_key_for_chunks<'a, C>(schema: &'a Schema, chunks: &'a [C]) -> SortKey<'a>
 where
     C: QueryChunkMeta,
@@ -275,6 +458,19 @@ where
 /// In the absence of more precise information, this should yield a
 /// good ordering for RLE compression
 pub fn compute_sort_key<'a>(summaries: impl Iterator<Item = &'a TableSummary>) -> SortKey<'a> {
+    compute_sort_key_with_pinned(summaries, &[])
+}
+
+/// Compute a sort key like [`compute_sort_key`], but place the `pinned` tag
+/// columns first in the given order, regardless of their cardinality
+///
+/// This is useful for tables that are almost always queried with a predicate
+/// on a specific tag. Pinned columns that are not tags of the summaries are
+/// ignored.
+pub fn compute_sort_key_with_pinned<'a>(
+    summaries: impl Iterator<Item = &'a TableSummary>,
+    pinned: &[&str],
+) -> SortKey<'a> {
     let mut cardinalities: HashMap<&str, u64> = Default::default();
     for summary in summaries {
         for column in &summary.columns {
@@ -292,11 +488,17 @@ pub fn compute_sort_key<'a>(summaries: impl Iterator<Item = &'a TableSummary>) -
 
     trace!(cardinalities=?cardinalities, "cardinalities of of columns to compute sort key");
 
+    let mut key = SortKey::with_capacity(cardinalities.len() + 1);
+    for col in pinned {
+        if let Some((col, _)) = cardinalities.remove_entry(*col) {
+            key.push(col, Default::default())
+        }
+    }
+
     let mut cardinalities: Vec<_> = cardinalities.into_iter().collect();
     // Sort by (cardinality, column_name) to have deterministic order if same cardinality
     cardinalities.sort_by_key(|x| (x.1, x.0));
 
-    let mut key = SortKey::with_capacity(cardinalities.len() + 1);
     for (col, _) in cardinalities {
         key.push(col, Default::default())
     }
@@ -319,3 +521,61 @@ pub fn compute_sort_key<'a>(summaries: impl Iterator<Item = &'a TableSummary>) -
 //
 //#[cfg(test)]
 pub mod test;
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use data_types::partition_metadata::{ColumnSummary, StatValues, Statistics};
+
+    use super::*;
+
+    fn tag(name: &str, distinct: u64) -> ColumnSummary {
+        ColumnSummary {
+            name: name.to_string(),
+            influxdb_type: Some(InfluxDbType::Tag),
+            stats: Statistics::String(StatValues::new_with_distinct(
+                Some("a".to_string()),
+                Some("z".to_string()),
+                distinct,
+                0,
+                NonZeroU64::new(distinct),
+            )),
+        }
+    }
+
+    fn sort_key_columns<'a>(key: &SortKey<'a>) -> Vec<&'a str> {
+        key.iter().map(|(col, _)| *col).collect()
+    }
+
+    #[test]
+    fn test_compute_sort_key_with_pinned() {
+        let summary = TableSummary {
+            name: "t".to_string(),
+            columns: vec![tag("host", 10), tag("region", 100), tag("env", 2)],
+        };
+
+        let key = compute_sort_key(std::iter::once(&summary));
+        assert_eq!(
+            sort_key_columns(&key),
+            vec!["env", "host", "region", TIME_COLUMN_NAME]
+        );
+
+        // region has the highest cardinality, but is pinned first
+        let key = compute_sort_key_with_pinned(std::iter::once(&summary), &["region"]);
+        assert_eq!(
+            sort_key_columns(&key),
+            vec!["region", "env", "host", TIME_COLUMN_NAME]
+        );
+
+        // pinned columns keep their order, unknown columns are ignored
+        let key = compute_sort_key_with_pinned(
+            std::iter::once(&summary),
+            &["region", "unknown", "host", "region"],
+        );
+        assert_eq!(
+            sort_key_columns(&key),
+            vec!["region", "host", "env", TIME_COLUMN_NAME]
+        );
+    }
+}
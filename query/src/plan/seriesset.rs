@@ -81,6 +81,17 @@ pub struct SeriesSetPlans {
     /// 2. _measurement (means group by the table name)
     /// 3. _time (means group by the time column)
     pub group_columns: Option<Vec<Arc<str>>>,
+
+    /// If true, a series is produced for each field even if all of its
+    /// values are null (e.g. after applying a predicate), rather than
+    /// omitting the series entirely.
+    pub include_empty_series: bool,
+
+    /// The maximum number of series executing these plans may produce, if
+    /// any. Exceeding it fails execution with a
+    /// [`TooManySeries`](crate::exec::seriesset::Error::TooManySeries)
+    /// error.
+    pub max_series: Option<usize>,
 }
 
 impl SeriesSetPlans {
@@ -95,6 +106,8 @@ impl SeriesSetPlans {
         Self {
             plans,
             group_columns: None,
+            include_empty_series: false,
+            max_series: None,
         }
     }
 
@@ -105,4 +118,17 @@ impl SeriesSetPlans {
             ..self
         }
     }
+
+    /// Produce series for fields with no non-null values
+    pub fn with_include_empty_series(self, include_empty_series: bool) -> Self {
+        Self {
+            include_empty_series,
+            ..self
+        }
+    }
+
+    /// Limit the number of series produced when executing the plans
+    pub fn with_max_series(self, max_series: Option<usize>) -> Self {
+        Self { max_series, ..self }
+    }
 }
@@ -1,9 +1,10 @@
 //! Implementation of a DataFusion `TableProvider` in terms of `QueryChunk`s
 
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 
 use arrow::{datatypes::SchemaRef as ArrowSchemaRef, error::ArrowError};
+use data_types::delete_predicate::DeletePredicate;
 use datafusion::{
     datasource::{datasource::TableProviderFilterPushDown, TableProvider},
     error::{DataFusionError, Result as DataFusionResult},
@@ -17,24 +18,29 @@ use datafusion::{
         ExecutionPlan,
     },
 };
+use datafusion_util::make_range_expr;
 use observability_deps::tracing::{debug, trace};
 use predicate::predicate::{Predicate, PredicateBuilder};
-use schema::{merge::SchemaMerger, sort::SortKey, Schema};
+use schema::{merge::SchemaMerger, sort::SortKey, Schema, TIME_COLUMN_NAME};
 
 use crate::{
-    chunks_have_stats, compute_sort_key_for_chunks,
+    chunks_have_stats, compute_sort_key_for_chunks, compute_sort_key_for_chunks_with_metrics,
     util::{arrow_sort_key_exprs, df_physical_expr},
-    QueryChunk,
+    QueryChunk, SortKeyMetrics,
 };
 
 use snafu::{ResultExt, Snafu};
 
 mod adapter;
+mod chain;
 mod deduplicate;
+mod measured;
 mod overlap;
 mod physical;
+use self::chain::ChainExec;
 use self::overlap::group_potential_duplicates;
 pub(crate) use deduplicate::DeduplicateExec;
+pub use measured::{ChunkReadMetrics, MeasuredChunk, MeasuredPruner};
 pub(crate) use physical::IOxReadFilterNode;
 
 #[derive(Debug, Snafu)]
@@ -112,6 +118,12 @@ pub struct ProviderBuilder<C: QueryChunk + 'static> {
     chunks: Vec<Arc<C>>,
     /// ensure the output is sorted on the pk columns (in an optimal order computed based on their cardinality)
     ensure_pk_sort: bool,
+    /// exclude rows with a timestamp before this cutoff (in nanoseconds)
+    retention_cutoff: Option<i64>,
+    /// the maximum number of chunks read concurrently by a scan
+    scan_parallelism: Option<NonZeroUsize>,
+    /// records how the sort keys of the scans were computed
+    sort_key_metrics: Option<SortKeyMetrics>,
 }
 
 impl<C: QueryChunk> ProviderBuilder<C> {
@@ -122,6 +134,9 @@ impl<C: QueryChunk> ProviderBuilder<C> {
             chunk_pruner: None,
             chunks: Vec::new(),
             ensure_pk_sort: false, // never sort the output unless explicitly specified
+            retention_cutoff: None,
+            scan_parallelism: None,
+            sort_key_metrics: None,
         }
     }
 
@@ -158,6 +173,32 @@ impl<C: QueryChunk> ProviderBuilder<C> {
         self.add_pruner(chunk_pruner)
     }
 
+    /// Exclude data older than `retention` relative to `now` (in
+    /// nanoseconds since the epoch) from all scans of the provider, even if
+    /// it has not yet been removed from the chunks.
+    pub fn add_retention(mut self, retention: Duration, now: i64) -> Self {
+        self.retention_cutoff = Some(retention_cutoff(retention, now));
+        self
+    }
+
+    /// Read at most `scan_parallelism` chunks concurrently in every scan of
+    /// the provider, instead of all chunks at once.
+    ///
+    /// This bounds the memory used by scans of tables with many chunks. It
+    /// does not apply to chunks that need to be sorted or deduplicated, as
+    /// those are each read by a separate plan.
+    pub fn with_scan_parallelism(mut self, scan_parallelism: NonZeroUsize) -> Self {
+        self.scan_parallelism = Some(scan_parallelism);
+        self
+    }
+
+    /// Record in `sort_key_metrics` whether the sort keys computed by the
+    /// scans of the provider were derived from the chunk statistics
+    pub fn with_sort_key_metrics(mut self, sort_key_metrics: SortKeyMetrics) -> Self {
+        self.sort_key_metrics = Some(sort_key_metrics);
+        self
+    }
+
     /// Create the Provider
     pub fn build(self) -> Result<ChunkTableProvider<C>> {
         let chunk_pruner = match self.chunk_pruner {
@@ -176,6 +217,9 @@ impl<C: QueryChunk> ProviderBuilder<C> {
             table_name: self.table_name,
             chunks: self.chunks,
             ensure_pk_sort: self.ensure_pk_sort,
+            retention_cutoff: self.retention_cutoff,
+            scan_parallelism: self.scan_parallelism,
+            sort_key_metrics: self.sort_key_metrics,
         })
     }
 }
@@ -195,6 +239,12 @@ pub struct ChunkTableProvider<C: QueryChunk + 'static> {
     chunks: Vec<Arc<C>>,
     /// ensure the output is sorted on the pk columns (in an optimal order computed based on their cardinality)
     ensure_pk_sort: bool,
+    /// exclude rows with a timestamp before this cutoff (in nanoseconds)
+    retention_cutoff: Option<i64>,
+    /// the maximum number of chunks read concurrently by a scan
+    scan_parallelism: Option<NonZeroUsize>,
+    /// records how the sort keys of the scans were computed
+    sort_key_metrics: Option<SortKeyMetrics>,
 }
 
 impl<C: QueryChunk + 'static> ChunkTableProvider<C> {
@@ -217,6 +267,100 @@ impl<C: QueryChunk + 'static> ChunkTableProvider<C> {
     pub fn ensure_pk_sort(&mut self) {
         self.ensure_pk_sort = true;
     }
+
+    /// Return `schema` with the time column of this table added, if it is
+    /// not already present.
+    fn with_time_column(&self, schema: &Arc<Schema>) -> Arc<Schema> {
+        match self.iox_schema.find_index_of(TIME_COLUMN_NAME) {
+            Some(idx) if schema.find_index_of(TIME_COLUMN_NAME).is_none() => {
+                let time_schema = self.iox_schema.select_by_indices(&[idx]);
+                Deduplicater::<C>::compute_input_schema(schema, &time_schema)
+            }
+            _ => Arc::clone(schema),
+        }
+    }
+}
+
+/// Sort `chunks` into the order in which they are deduplicated, from the
+/// oldest to the newest. When the same primary key appears in more than one
+/// chunk, the row from the chunk that sorts last wins.
+///
+/// Chunks are ordered by [`QueryChunk::order`], then by [`QueryChunk::id`]
+/// to break ties between chunks with the same order (e.g. chunks created at
+/// the same time), and finally by the sequencer and smallest of their
+/// [`QueryChunk::sequence_numbers`], chunks without sequence numbers sorting
+/// first. This is a total order for the chunks of a table, so the
+/// deduplicated output is reproducible.
+pub fn sort_chunks_for_dedup<C>(mut chunks: Vec<Arc<C>>) -> Vec<Arc<C>>
+where
+    C: QueryChunk + 'static,
+{
+    chunks.sort_by_cached_key(|c| {
+        let min_sequence_number = c
+            .sequence_numbers()
+            .map(|range| (range.sequencer_id(), range.start()));
+        (c.order(), c.id(), min_sequence_number)
+    });
+    chunks
+}
+
+/// Exclude from every chunk the data of the writes that are also contained in
+/// a chunk preceding it in deduplication order (see
+/// [`sort_chunks_for_dedup`]), so that combining chunks that overlap in
+/// sequence numbers, such as buffered and persisted data, reads the data of
+/// every write exactly once.
+///
+/// Only the sequence numbers of the same sequencer overlap, and a write is
+/// split between the partitions it writes to, so only chunks of the same
+/// partition exclude data from each other. Chunks left without data are
+/// removed, all others keep their position. Chunks without known sequence
+/// numbers or partition are returned unchanged.
+pub fn exclude_overlapping_sequence_numbers<C>(chunks: Vec<Arc<C>>) -> Vec<Arc<C>>
+where
+    C: QueryChunk + 'static,
+{
+    let covered: Vec<_> = chunks
+        .iter()
+        .filter_map(|c| {
+            Some((
+                c.partition_key()?,
+                (c.order(), c.id()),
+                c.sequence_numbers()?,
+            ))
+        })
+        .collect();
+    if covered.len() < 2 {
+        return chunks;
+    }
+
+    chunks
+        .into_iter()
+        .filter_map(|chunk| {
+            let (partition_key, range) = match (chunk.partition_key(), chunk.sequence_numbers()) {
+                (Some(partition_key), Some(range)) => (partition_key, range),
+                _ => return Some(chunk),
+            };
+            let key = (chunk.order(), chunk.id());
+
+            covered
+                .iter()
+                .filter(|(other_partition_key, other, exclude)| {
+                    *other_partition_key == partition_key
+                        && *other < key
+                        && exclude.overlaps(&range)
+                })
+                .try_fold(chunk, |chunk, (_, _, exclude)| {
+                    chunk.exclude_sequence_numbers(exclude)
+                })
+        })
+        .collect()
+}
+
+/// Returns the timestamp (in nanoseconds since the epoch) before which data
+/// is older than `retention` relative to `now`
+pub fn retention_cutoff(retention: Duration, now: i64) -> i64 {
+    let retention = i64::try_from(retention.as_nanos()).unwrap_or(i64::MAX);
+    now.saturating_sub(retention)
 }
 
 #[async_trait]
@@ -245,6 +389,13 @@ impl<C: QueryChunk + 'static> TableProvider for ChunkTableProvider<C> {
             .add_pushdown_exprs(filters)
             .build();
 
+        // Unlike the pushed down filters, the retention bound is not applied
+        // by DataFusion above the scan, so it must be applied here.
+        let predicate = match self.retention_cutoff {
+            Some(cutoff) => predicate.with_retention_cutoff(cutoff),
+            None => predicate,
+        };
+
         // Now we have a second attempt to prune out chunks based on
         // metadata using the pushed down predicate (e.g. in SQL).
         let chunks: Vec<Arc<C>> = self.chunks.to_vec();
@@ -257,6 +408,10 @@ impl<C: QueryChunk + 'static> TableProvider for ChunkTableProvider<C> {
         );
         debug!(%predicate, num_initial_chunks, num_final_chunks=chunks.len(), "pruned with pushed down predicates");
 
+        // Data of writes contained in more than one chunk must only be read
+        // once
+        let chunks = exclude_overlapping_sequence_numbers(chunks);
+
         // Figure out the schema of the requested output
         let scan_schema = match projection {
             Some(indicies) => Arc::new(self.iox_schema.select_by_indices(indicies)),
@@ -272,15 +427,36 @@ impl<C: QueryChunk + 'static> TableProvider for ChunkTableProvider<C> {
         //     trace!("Schema of chunk {}: {:#?}", chunk.id(), chunk.schema());
         // }
 
+        let retention_range = self.retention_cutoff.and(predicate.range);
+
+        // The retention filter needs the time column, even if it is not
+        // part of the requested output
+        let input_schema = match retention_range {
+            Some(_) => self.with_time_column(&scan_schema),
+            None => Arc::clone(&scan_schema),
+        };
+
         let mut deduplicate = Deduplicater::new();
+        deduplicate.scan_parallelism = self.scan_parallelism;
+        deduplicate.sort_key_metrics = self.sort_key_metrics.clone();
         let plan = deduplicate.build_scan_plan(
             Arc::clone(&self.table_name),
-            scan_schema,
+            input_schema,
             chunks,
             predicate,
             self.ensure_pk_sort,
         )?;
 
+        let plan = match retention_range {
+            Some(range) => {
+                let expr = make_range_expr(range.start(), range.end(), TIME_COLUMN_NAME);
+                let expr = df_physical_expr(&*plan, expr).context(InternalFilterSnafu)?;
+                let plan = Arc::new(FilterExec::try_new(expr, plan).context(InternalFilterSnafu)?);
+                Deduplicater::<C>::add_projection_node_if_needed(scan_schema, plan)?
+            }
+            None => plan,
+        };
+
         Ok(plan)
     }
 
@@ -304,6 +480,12 @@ pub(crate) struct Deduplicater<C: QueryChunk + 'static> {
 
     // a vector of non-overlapped and non-duplicates chunks
     pub no_duplicates_chunks: Vec<Arc<C>>,
+
+    // the maximum number of non-overlapped and non-duplicates chunks read concurrently
+    pub scan_parallelism: Option<NonZeroUsize>,
+
+    // records how the sort keys of the plans are computed
+    pub sort_key_metrics: Option<SortKeyMetrics>,
 }
 
 impl<C: QueryChunk + 'static> Deduplicater<C> {
@@ -312,6 +494,8 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
             overlapped_chunks_set: vec![],
             in_chunk_duplicates_chunks: vec![],
             no_duplicates_chunks: vec![],
+            scan_parallelism: None,
+            sort_key_metrics: None,
         }
     }
 
@@ -394,7 +578,11 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         let mut output_sort_key = SortKey::with_capacity(0);
         if sort_output {
             // Compute the output sort key which is the super key of chunks' keys base on their data cardinality
-            output_sort_key = compute_sort_key_for_chunks(&output_schema, chunks.as_ref());
+            output_sort_key = Self::compute_sort_key(
+                &output_schema,
+                chunks.as_ref(),
+                self.sort_key_metrics.as_ref(),
+            );
         }
 
         // find overlapped chunks and put them into the right group
@@ -410,6 +598,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
                 chunks.to_owned(),
                 predicate,
                 &output_sort_key,
+                self.scan_parallelism,
             )?;
             plans.append(&mut non_duplicate_plans);
         } else {
@@ -426,6 +615,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
                     overlapped_chunks.to_owned(),
                     predicate.clone(),
                     &output_sort_key,
+                    self.sort_key_metrics.as_ref(),
                 )?);
             }
 
@@ -437,6 +627,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
                     chunk_with_duplicates.to_owned(),
                     predicate.clone(),
                     &output_sort_key,
+                    self.sort_key_metrics.as_ref(),
                 )?);
             }
 
@@ -447,6 +638,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
                 self.no_duplicates_chunks.to_vec(),
                 predicate,
                 &output_sort_key,
+                self.scan_parallelism,
             )?;
             plans.append(&mut non_duplicate_plans);
         }
@@ -481,8 +673,13 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
     ///  1. vector of vector of overlapped chunks
     ///  2. vector of non-overlapped chunks, each have duplicates in itself
     ///  3. vectors of non-overlapped chunks without duplicates
-    fn split_overlapped_chunks(&mut self, chunks: Vec<Arc<C>>) -> Result<()> {
-        if !chunks_have_stats(&chunks) {
+    pub(crate) fn split_overlapped_chunks(&mut self, chunks: Vec<Arc<C>>) -> Result<()> {
+        if chunks.len() == 1 && !chunks[0].may_contain_pk_duplicates() {
+            // Fast path: a single chunk without duplicates in itself has
+            // nothing to be deduplicated against, with or without statistics
+            trace!("single chunk without duplicates, skipping deduplication");
+            self.no_duplicates_chunks = chunks;
+        } else if !chunks_have_stats(&chunks) {
             // no statistics, consider all chunks overlap
             self.overlapped_chunks_set.push(chunks);
         } else {
@@ -505,7 +702,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
     }
 
     /// Return true if all chunks neither overlap nor have duplicates in itself
-    fn no_duplicates(&self) -> bool {
+    pub(crate) fn no_duplicates(&self) -> bool {
         self.overlapped_chunks_set.is_empty() && self.in_chunk_duplicates_chunks.is_empty()
     }
 
@@ -555,17 +752,14 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         chunks: Vec<Arc<C>>, // These chunks are identified overlapped
         predicate: Predicate,
         output_sort_key: &SortKey<'_>,
+        sort_key_metrics: Option<&SortKeyMetrics>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         // Note that we may need to sort/deduplicate based on tag
         // columns which do not appear in the output
 
-        // We need to sort chunks before creating the execution plan. For that, the chunk order is used. Since the order
-        // only sorts overlapping chunks, we also use the chunk ID for deterministic outputs.
-        let chunks = {
-            let mut chunks = chunks;
-            chunks.sort_unstable_by_key(|c| (c.order(), c.id()));
-            chunks
-        };
+        // We need to sort chunks before creating the execution plan, as
+        // later chunks win when deduplicating.
+        let chunks = sort_chunks_for_dedup(chunks);
 
         let pk_schema = Self::compute_pk_schema(&chunks);
         let input_schema = Self::compute_input_schema(&output_schema, &pk_schema);
@@ -574,7 +768,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         let sort_key = if !output_sort_key.is_empty() {
             output_sort_key.to_owned()
         } else {
-            compute_sort_key_for_chunks(&output_schema, chunks.as_ref())
+            Self::compute_sort_key(&output_schema, chunks.as_ref(), sort_key_metrics)
         };
         trace!(sort_key=?sort_key, "sort key for the input chunks");
 
@@ -650,6 +844,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         chunk: Arc<C>, // This chunk is identified having duplicates
         predicate: Predicate,
         output_sort_key: &SortKey<'_>,
+        sort_key_metrics: Option<&SortKeyMetrics>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         let pk_schema = Self::compute_pk_schema(&[Arc::clone(&chunk)]);
         let input_schema = Self::compute_input_schema(&output_schema, &pk_schema);
@@ -659,7 +854,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         let mut sort_key = if !output_sort_key.is_empty() {
             output_sort_key.to_owned()
         } else {
-            compute_sort_key_for_chunks(&output_schema, &chunks)
+            Self::compute_sort_key(&output_schema, &chunks, sort_key_metrics)
         };
         trace!(sort_key=?sort_key,chunk_id=?chunks[0].id(), "Computed the sort key for the input chunk");
 
@@ -807,16 +1002,38 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
             input_schema = Self::compute_input_schema(&input_schema, &pred_schema);
         }
 
+        let mut input =
+            Self::build_read_filter_plan(table_name, input_schema, Arc::clone(&chunk), predicate)?;
+
+        // Add the sort operator, SortExec, if needed
+        if !output_sort_key.is_empty() {
+            input = Self::build_sort_plan(chunk, input, output_sort_key)?
+        }
+
+        // Add a projection operator to return only schema of the operator above this in the plan
+        // This is needed for matching column index of that operator
+        Self::add_projection_node_if_needed(output_schema, input)
+    }
+
+    /// Return the IOxReadFilterNode reading `chunk` with `input_schema`, with
+    /// a FilterExec on top of it if the chunk has delete predicates
+    fn build_read_filter_plan(
+        table_name: Arc<str>,
+        input_schema: Arc<Schema>,
+        chunk: Arc<C>,
+        predicate: Predicate, // This is the select predicate of the query
+    ) -> Result<Arc<dyn ExecutionPlan>> {
         // Create the bottom node IOxReadFilterNode for this chunk
         let mut input: Arc<dyn ExecutionPlan> = Arc::new(IOxReadFilterNode::new(
-            Arc::clone(&table_name),
+            table_name,
             input_schema,
             vec![Arc::clone(&chunk)],
             predicate,
         ));
 
-        // Add Filter operator, FilterExec, if the chunk has delete predicates
-        let del_preds = chunk.delete_predicates();
+        // Add Filter operator, FilterExec, if the chunk has delete predicates,
+        // merging overlapping predicates to reduce the cost of evaluating them
+        let del_preds = DeletePredicate::coalesce(chunk.delete_predicates());
         let del_preds: Vec<Arc<Predicate>> = del_preds
             .iter()
             .map(|pred| Arc::new(pred.as_ref().clone().into()))
@@ -836,14 +1053,7 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
             );
         }
 
-        // Add the sort operator, SortExec, if needed
-        if !output_sort_key.is_empty() {
-            input = Self::build_sort_plan(chunk, input, output_sort_key)?
-        }
-
-        // Add a projection operator to return only schema of the operator above this in the plan
-        // This is needed for matching column index of that operator
-        Self::add_projection_node_if_needed(output_schema, input)
+        Ok(input)
     }
 
     /// Add SortExec operator on top of the input plan of the given chunk
@@ -961,12 +1171,16 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
     ///   │    (Chunk 1)    │                   │    (Chunk n)    │
     ///   └─────────────────┘                   └─────────────────┘
     ///```
+    /// Or, if `scan_parallelism` is lower than the number of chunks, one plan
+    /// for each group of chunks read together, see
+    /// [`build_plans_for_chunk_groups`](Self::build_plans_for_chunk_groups).
     fn build_plans_for_non_duplicates_chunks(
         table_name: Arc<str>,
         output_schema: Arc<Schema>,
         chunks: Vec<Arc<C>>, // These chunks is identified having no duplicates
         predicate: Predicate,
         output_sort_key: &SortKey<'_>,
+        scan_parallelism: Option<NonZeroUsize>,
     ) -> Result<Vec<Arc<dyn ExecutionPlan>>> {
         let mut plans: Vec<Arc<dyn ExecutionPlan>> = vec![];
 
@@ -974,14 +1188,26 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         // if there is no chunk, we still need to return a plan
         if (output_sort_key.is_empty() && Self::no_delete_predicates(&chunks)) || chunks.is_empty()
         {
-            plans.push(Arc::new(IOxReadFilterNode::new(
-                Arc::clone(&table_name),
+            let node =
+                IOxReadFilterNode::new(Arc::clone(&table_name), output_schema, chunks, predicate);
+            let node = match scan_parallelism {
+                Some(parallelism) => node.with_parallelism(parallelism),
+                None => node,
+            };
+            plans.push(Arc::new(node));
+
+            return Ok(plans);
+        }
+
+        if let Some(parallelism) = scan_parallelism.filter(|p| chunks.len() > p.get()) {
+            return Self::build_plans_for_chunk_groups(
+                table_name,
                 output_schema,
                 chunks,
                 predicate,
-            )));
-
-            return Ok(plans);
+                output_sort_key,
+                parallelism,
+            );
         }
 
         // Build sorted plans, one for each chunk
@@ -1001,6 +1227,100 @@ impl<C: QueryChunk + 'static> Deduplicater<C> {
         sorted_chunk_plans
     }
 
+    /// Return `parallelism` plans reading `chunks`, which have no duplicates,
+    /// so that at most `parallelism` chunks are read concurrently:
+    /// ```text
+    ///   ┌─────────────────┐                   ┌─────────────────┐
+    ///   │    SortExec     │                   │    SortExec     │
+    ///   │   (optional)    │                   │   (optional)    │
+    ///   └─────────────────┘                   └─────────────────┘
+    ///            ▲                                     ▲
+    ///            │                                     │
+    ///   ┌─────────────────┐                   ┌─────────────────┐
+    ///   │    ChainExec    │                   │    ChainExec    │
+    ///   │    (Group 1)    │       .....       │  (Group n = p)  │
+    ///   └─────────────────┘                   └─────────────────┘
+    ///            ▲                                     ▲
+    ///            │                                     │
+    /// ┌─────────────────────────┐          ┌─────────────────────────┐
+    /// │ FilterExec (optional)   │          │ FilterExec (optional)   │
+    /// | To apply delete preds   │   .....  | To apply delete preds   │
+    /// │  (Each chunk of group)  │          │  (Each chunk of group)  │
+    /// └─────────────────────────┘          └─────────────────────────┘
+    ///            ▲                                     ▲
+    ///            │                                     │
+    ///   ┌─────────────────┐                   ┌─────────────────┐
+    ///   │IOxReadFilterNode│                   │IOxReadFilterNode│
+    ///   │(Each chunk of g)│                   │(Each chunk of g)│
+    ///   └─────────────────┘                   └─────────────────┘
+    ///```
+    /// The chunks are distributed over `parallelism` groups, the chunks of
+    /// a group being read one after the other. Each group is sorted as a
+    /// whole if `output_sort_key` is not empty.
+    fn build_plans_for_chunk_groups(
+        table_name: Arc<str>,
+        output_schema: Arc<Schema>,
+        chunks: Vec<Arc<C>>,
+        predicate: Predicate,
+        output_sort_key: &SortKey<'_>,
+        parallelism: NonZeroUsize,
+    ) -> Result<Vec<Arc<dyn ExecutionPlan>>> {
+        // The chunks of a group are read with the same schema: the output
+        // columns plus the columns of the sort key and of the delete
+        // predicates of all chunks
+        let mut input_schema = Arc::clone(&output_schema);
+        if !output_sort_key.is_empty() {
+            let pk_schema = Self::compute_pk_schema(&chunks);
+            input_schema = Self::compute_input_schema(&input_schema, &pk_schema);
+        }
+        if !Self::no_delete_predicates(&chunks) {
+            let pred_schema = Self::compute_delete_predicate_schema(&chunks);
+            input_schema = Self::compute_input_schema(&input_schema, &pred_schema);
+        }
+
+        (0..parallelism.get())
+            .map(|group| {
+                let inputs = chunks
+                    .iter()
+                    .skip(group)
+                    .step_by(parallelism.get())
+                    .map(|chunk| {
+                        Self::build_read_filter_plan(
+                            Arc::clone(&table_name),
+                            Arc::clone(&input_schema),
+                            Arc::clone(chunk),
+                            predicate.clone(),
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                let mut plan: Arc<dyn ExecutionPlan> = Arc::new(ChainExec::new(inputs));
+                if !output_sort_key.is_empty() {
+                    let sort_exprs = arrow_sort_key_exprs(output_sort_key, &plan.schema());
+                    plan =
+                        Arc::new(SortExec::try_new(sort_exprs, plan).context(InternalSortSnafu)?);
+                }
+
+                Self::add_projection_node_if_needed(Arc::clone(&output_schema), plan)
+            })
+            .collect()
+    }
+
+    /// Compute the sort key of `chunks`, recording in `sort_key_metrics` if provided
+    /// whether it was computed from their statistics
+    fn compute_sort_key<'a>(
+        output_schema: &'a Schema,
+        chunks: &'a [Arc<C>],
+        sort_key_metrics: Option<&SortKeyMetrics>,
+    ) -> SortKey<'a> {
+        match sort_key_metrics {
+            Some(metrics) => {
+                compute_sort_key_for_chunks_with_metrics(output_schema, chunks, metrics)
+            }
+            None => compute_sort_key_for_chunks(output_schema, chunks),
+        }
+    }
+
     fn no_delete_predicates(chunks: &[Arc<C>]) -> bool {
         chunks
             .iter()
@@ -1070,14 +1390,23 @@ impl<C: QueryChunk> ChunkPruner<C> for NoOpPruner {
 mod test {
     use std::num::NonZeroU64;
 
-    use arrow::datatypes::DataType;
+    use arrow::{datatypes::DataType, util::pretty::pretty_format_batches};
     use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
+    use data_types::timestamp::TimestampRange;
+    use datafusion::{
+        logical_plan::{col, lit},
+        physical_plan::displayable,
+    };
     use datafusion_util::test_collect;
-    use schema::{builder::SchemaBuilder, TIME_COLUMN_NAME};
+    use schema::{builder::SchemaBuilder, selection::Selection, TIME_COLUMN_NAME};
 
     use crate::{
+        exec::{
+            progress::{QueryProgress, QueryProgressReporter},
+            Executor, ExecutorType,
+        },
         test::{raw_data, TestChunk},
-        QueryChunkMeta,
+        QueryChunkMeta, SequenceNumberRange, UnsupportedPredicate,
     };
 
     use super::*;
@@ -1423,6 +1752,7 @@ mod test {
             chunks,
             Predicate::default(),
             &output_sort_key,
+            None,
         )
         .unwrap();
         let batch = test_collect(sort_plan).await;
@@ -1441,6 +1771,69 @@ mod test {
         assert_batches_eq!(&expected, &batch);
     }
 
+    #[tokio::test]
+    async fn deduplicate_plan_for_overlapped_chunks_with_same_order() {
+        test_helpers::maybe_start_logging();
+
+        // Chunks with a single row for the same series and time, differing
+        // only in their field value
+        let make_chunk = |id, order, sequence_numbers, value| {
+            Arc::new(
+                TestChunk::new("t")
+                    .with_id(id)
+                    .with_order(order)
+                    .with_sequence_numbers(SequenceNumberRange::new(1, sequence_numbers))
+                    .with_time_column()
+                    .with_tag_column("tag1")
+                    .with_i64_field_column("field_int")
+                    .with_one_row_of_data_with_int_value(value),
+            )
+        };
+
+        async fn dedup(chunks: Vec<Arc<TestChunk>>) -> Vec<arrow::record_batch::RecordBatch> {
+            let schema = chunks[0].schema();
+            let plan = Deduplicater::build_deduplicate_plan_for_overlapped_chunks(
+                Arc::from("t"),
+                schema,
+                chunks,
+                Predicate::default(),
+                &SortKey::with_capacity(0),
+                None,
+            )
+            .unwrap();
+            test_collect(plan).await
+        }
+
+        // With equal orders, the chunk with the highest ID wins regardless of
+        // the order in which the chunks are provided
+        let chunk1 = make_chunk(1, 1, 1..=1, 10);
+        let chunk2 = make_chunk(2, 1, 1..=1, 20);
+        let expected = vec![
+            "+-----------+------+-----------------------------+",
+            "| field_int | tag1 | time                        |",
+            "+-----------+------+-----------------------------+",
+            "| 20        | MA   | 1970-01-01T00:00:00.000001Z |",
+            "+-----------+------+-----------------------------+",
+        ];
+        assert_batches_eq!(
+            &expected,
+            &dedup(vec![Arc::clone(&chunk1), Arc::clone(&chunk2)]).await
+        );
+        assert_batches_eq!(&expected, &dedup(vec![chunk2, chunk1]).await);
+
+        // The chunk order takes precedence over the ID
+        let chunk1 = make_chunk(1, 2, 1..=1, 10);
+        let chunk2 = make_chunk(2, 1, 1..=1, 20);
+        let expected = vec![
+            "+-----------+------+-----------------------------+",
+            "| field_int | tag1 | time                        |",
+            "+-----------+------+-----------------------------+",
+            "| 10        | MA   | 1970-01-01T00:00:00.000001Z |",
+            "+-----------+------+-----------------------------+",
+        ];
+        assert_batches_eq!(&expected, &dedup(vec![chunk1, chunk2]).await);
+    }
+
     #[tokio::test]
     async fn deduplicate_plan_for_overlapped_chunks_subset() {
         test_helpers::maybe_start_logging();
@@ -1537,6 +1930,7 @@ mod test {
             chunks,
             Predicate::default(),
             &output_sort_key,
+            None,
         )
         .unwrap();
         let batch = test_collect(sort_plan).await;
@@ -1670,6 +2064,7 @@ mod test {
             chunks,
             Predicate::default(),
             &output_sort_key,
+            None,
         )
         .unwrap();
         let batch = test_collect(sort_plan).await;
@@ -1818,6 +2213,7 @@ mod test {
             chunks,
             Predicate::default(),
             &output_sort_key,
+            None,
         )
         .unwrap();
         let batch = test_collect(sort_plan).await;
@@ -1897,6 +2293,94 @@ mod test {
         assert_batches_eq!(&expected, &batch);
     }
 
+    #[tokio::test]
+    async fn scan_plan_with_one_chunk_no_duplicates_no_stats() {
+        test_helpers::maybe_start_logging();
+
+        // A chunk without statistics can not be checked for overlaps, but as
+        // the only chunk it has nothing to overlap with
+        let chunk = Arc::new(
+            TestChunk::new("t")
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data()
+                .with_no_summary(),
+        );
+        assert!(chunk.summary().is_none());
+
+        let schema = chunk.schema();
+        let chunks = vec![chunk];
+
+        let mut deduplicator = Deduplicater::new();
+        let plan = deduplicator
+            .build_scan_plan(Arc::from("t"), schema, chunks, Predicate::default(), false)
+            .unwrap();
+
+        // The chunk is streamed as-is, without any deduplication or sorting
+        let plan_text = displayable(plan.as_ref()).indent().to_string();
+        assert_eq!(
+            plan_text.trim(),
+            "IOxReadFilterNode: table_name=t, chunks=1 predicate=Predicate"
+        );
+    }
+
+    #[tokio::test]
+    async fn scan_records_sort_key_metrics() {
+        test_helpers::maybe_start_logging();
+
+        let registry = metric::Registry::new();
+        let metrics = SortKeyMetrics::new(&registry, &[("table", "t")]);
+        let sort_keys_computed = |stats| {
+            registry
+                .get_instrument::<metric::Metric<metric::U64Counter>>("query_sort_key_computed")
+                .unwrap()
+                .get_observer(&metric::Attributes::from(&[
+                    ("table", "t"),
+                    ("stats", stats),
+                ]))
+                .unwrap()
+                .fetch()
+        };
+
+        let chunk = || {
+            TestChunk::new("t")
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data()
+        };
+        let scan = |chunk: TestChunk| {
+            let mut provider = ProviderBuilder::new("t", chunk.schema())
+                .add_no_op_pruner()
+                .add_chunk(Arc::new(chunk))
+                .with_sort_key_metrics(metrics.clone())
+                .build()
+                .unwrap();
+            provider.ensure_pk_sort();
+            async move { provider.scan(&None, &[], None).await.unwrap() }
+        };
+
+        // A chunk without statistics falls back to the primary key
+        scan(chunk().with_no_summary()).await;
+        assert_eq!(sort_keys_computed("missing"), 1);
+        assert_eq!(sort_keys_computed("present"), 0);
+
+        scan(chunk()).await;
+        assert_eq!(sort_keys_computed("missing"), 1);
+        assert_eq!(sort_keys_computed("present"), 1);
+
+        // No sort key is computed if the output need not be sorted
+        let provider = ProviderBuilder::new("t", chunk().schema())
+            .add_no_op_pruner()
+            .add_chunk(Arc::new(chunk().with_no_summary()))
+            .with_sort_key_metrics(metrics)
+            .build()
+            .unwrap();
+        provider.scan(&None, &[], None).await.unwrap();
+        assert_eq!(sort_keys_computed("missing"), 1);
+    }
+
     #[tokio::test]
     async fn scan_plan_with_one_chunk_with_duplicates() {
         test_helpers::maybe_start_logging();
@@ -2512,6 +2996,479 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn scan_with_retention() {
+        test_helpers::maybe_start_logging();
+
+        let chunk = Arc::new(
+            TestChunk::new("t")
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data(),
+        );
+
+        // Data older than 6us before "now" (7050ns) has expired
+        let provider = ProviderBuilder::new("t", chunk.schema())
+            .add_chunk(chunk)
+            .add_no_op_pruner()
+            .add_retention(Duration::from_micros(6), 7050)
+            .build()
+            .unwrap();
+
+        // No time predicate is provided
+        let plan = provider.scan(&None, &[], None).await.unwrap();
+        let expected = vec![
+            "+-----------+------+-----------------------------+",
+            "| field_int | tag1 | time                        |",
+            "+-----------+------+-----------------------------+",
+            "| 10        | MT   | 1970-01-01T00:00:00.000007Z |",
+            "| 5         | MT   | 1970-01-01T00:00:00.000005Z |",
+            "+-----------+------+-----------------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &test_collect(plan).await);
+
+        // The retention filter still applies when the time column is not
+        // selected
+        let field_idx = provider.iox_schema().find_index_of("field_int").unwrap();
+        let plan = provider
+            .scan(&Some(vec![field_idx]), &[], None)
+            .await
+            .unwrap();
+        let expected = vec![
+            "+-----------+",
+            "| field_int |",
+            "+-----------+",
+            "| 10        |",
+            "| 5         |",
+            "+-----------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &test_collect(plan).await);
+    }
+
+    #[tokio::test]
+    async fn scan_with_unsupported_predicate() {
+        test_helpers::maybe_start_logging();
+
+        let chunk = Arc::new(
+            TestChunk::new("t")
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_unsupported_predicate_column("tag1")
+                .with_five_rows_of_data(),
+        );
+
+        // The chunk reports which clause it can not evaluate
+        let predicate = PredicateBuilder::new()
+            .add_expr(col("tag1").eq(lit("MT")))
+            .build();
+        let err = chunk.read_filter(&predicate, Selection::All).unwrap_err();
+        assert_eq!(
+            UnsupportedPredicate::find(&err),
+            Some(&UnsupportedPredicate {
+                clause: r#"#tag1 = Utf8("MT")"#.to_string(),
+                reason: "column tag1 can not be filtered".to_string(),
+            })
+        );
+
+        // The scan reads the chunk without the predicate instead, the filter
+        // is applied by DataFusion
+        let provider = ProviderBuilder::new("t", chunk.schema())
+            .add_chunk(chunk)
+            .add_no_op_pruner()
+            .build()
+            .unwrap();
+        let plan = provider
+            .scan(&None, &[col("tag1").eq(lit("MT"))], None)
+            .await
+            .unwrap();
+        assert_eq!(
+            test_collect(plan)
+                .await
+                .iter()
+                .map(|b| b.num_rows())
+                .sum::<usize>(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn scan_with_progress() {
+        test_helpers::maybe_start_logging();
+
+        // Three chunks that do not overlap
+        let chunks: Vec<_> = (1..=3)
+            .map(|id| {
+                let start = id as i64 * 10_000;
+                Arc::new(
+                    TestChunk::new("t")
+                        .with_id(id)
+                        .with_order(id as u64)
+                        .with_time_column_with_stats(Some(start), Some(start + 9_999))
+                        .with_tag_column("tag1")
+                        .with_i64_field_column("field_int")
+                        .with_five_rows_of_data(),
+                )
+            })
+            .collect();
+
+        let mut builder = ProviderBuilder::new("t", chunks[0].schema()).add_no_op_pruner();
+        for chunk in chunks {
+            builder = builder.add_chunk(chunk);
+        }
+        let provider = builder.build().unwrap();
+
+        let exec = Executor::new(1);
+        let (reporter, mut receiver) = QueryProgressReporter::new();
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_progress_reporter(reporter.clone())
+            .build();
+        ctx.inner().register_table("t", Arc::new(provider)).unwrap();
+
+        let plan = ctx.prepare_sql("SELECT * FROM t").await.unwrap();
+        assert_eq!(reporter.chunks_planned(), 3);
+        let batches = ctx.collect(plan).await.unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 15);
+        // The caller completes the query once it has all of its output
+        reporter.complete();
+
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+
+        // The progress only increases, and completes once every chunk has
+        // been read
+        let counts: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                QueryProgress::Scanning {
+                    rows_scanned,
+                    chunks_completed,
+                }
+                | QueryProgress::Complete {
+                    rows_scanned,
+                    chunks_completed,
+                } => (*rows_scanned, *chunks_completed),
+            })
+            .collect();
+        assert!(counts.len() > 2, "{:?}", events);
+        for pair in counts.windows(2) {
+            assert!(
+                pair[0].0 <= pair[1].0 && pair[0].1 <= pair[1].1,
+                "{:?}",
+                events
+            );
+        }
+
+        let (last, scanning) = events.split_last().unwrap();
+        assert_eq!(
+            last,
+            &QueryProgress::Complete {
+                rows_scanned: 15,
+                chunks_completed: 3
+            }
+        );
+        assert!(scanning
+            .iter()
+            .all(|event| matches!(event, QueryProgress::Scanning { .. })));
+    }
+
+    #[tokio::test]
+    async fn scan_several_tables_with_progress() {
+        test_helpers::maybe_start_logging();
+
+        let exec = Executor::new(1);
+        let (reporter, mut receiver) = QueryProgressReporter::new();
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_progress_reporter(reporter.clone())
+            .build();
+
+        // Two tables of two chunks each, queried one after the other like an
+        // influxrpc query runs one plan per table
+        for table_name in ["t", "u"] {
+            let chunks: Vec<_> = (1..=2)
+                .map(|id| {
+                    let start = id as i64 * 10_000;
+                    Arc::new(
+                        TestChunk::new(table_name)
+                            .with_id(id)
+                            .with_order(id as u64)
+                            .with_time_column_with_stats(Some(start), Some(start + 9_999))
+                            .with_tag_column("tag1")
+                            .with_i64_field_column("field_int")
+                            .with_five_rows_of_data(),
+                    )
+                })
+                .collect();
+            let mut builder =
+                ProviderBuilder::new(table_name, chunks[0].schema()).add_no_op_pruner();
+            for chunk in chunks {
+                builder = builder.add_chunk(chunk);
+            }
+            ctx.inner()
+                .register_table(table_name, Arc::new(builder.build().unwrap()))
+                .unwrap();
+        }
+        for table_name in ["t", "u"] {
+            let plan = ctx
+                .prepare_sql(&format!("SELECT * FROM {}", table_name))
+                .await
+                .unwrap();
+            let batches = ctx.collect(plan).await.unwrap();
+            assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+        }
+
+        // The end of the output of the first table does not complete the query
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, QueryProgress::Scanning { .. })));
+        assert_eq!(
+            events.last().unwrap(),
+            &QueryProgress::Scanning {
+                rows_scanned: 20,
+                chunks_completed: 4
+            }
+        );
+
+        // The query completes once, when the caller completes it
+        reporter.complete();
+        reporter.complete();
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            vec![QueryProgress::Complete {
+                rows_scanned: 20,
+                chunks_completed: 4
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn scan_with_overlapping_sequence_numbers() {
+        test_helpers::maybe_start_logging();
+
+        let chunk = |id: u128, sequencer_id, sequence_numbers| {
+            TestChunk::new("t")
+                .with_id(id)
+                .with_order(id as u64)
+                .with_sequence_numbers(SequenceNumberRange::new(sequencer_id, sequence_numbers))
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+        };
+
+        // Persisted data of the writes 1 to 5
+        let persisted = Arc::new(chunk(1, 1, 1..=5).with_five_rows_of_data());
+        // Buffered data of the writes 3 to 5 of another sequencer, which
+        // assigns its own sequence numbers and is not persisted
+        let other_sequencer = Arc::new(chunk(2, 2, 3..=5).with_one_row_of_data_with_int_value(42));
+        // Buffered data of the writes 3 to 5 that is also persisted. Its row
+        // would win over the row of the other sequencer when deduplicating if
+        // it was read again
+        let buffered_persisted =
+            Arc::new(chunk(3, 1, 3..=5).with_one_row_of_data_with_int_value(7));
+        // Buffered data of the writes 5 to 6, which is only partially
+        // persisted and cannot be split by this chunk
+        let buffered_partial = Arc::new(chunk(4, 1, 5..=6).with_three_rows_of_data());
+        // Buffered data of the writes 7 to 8 that is not yet persisted
+        let buffered = Arc::new(chunk(5, 1, 7..=8).with_three_rows_of_data());
+
+        let chunks = vec![
+            Arc::clone(&buffered),
+            Arc::clone(&buffered_persisted),
+            Arc::clone(&buffered_partial),
+            Arc::clone(&other_sequencer),
+            Arc::clone(&persisted),
+        ];
+        let chunks = exclude_overlapping_sequence_numbers(chunks);
+        assert_eq!(chunk_ids(&chunks), "5, 4, 2, 1");
+
+        let provider = ProviderBuilder::new("t", persisted.schema())
+            .add_chunk(persisted)
+            .add_chunk(other_sequencer)
+            .add_chunk(buffered_persisted)
+            .add_chunk(buffered)
+            .add_no_op_pruner()
+            .build()
+            .unwrap();
+
+        // Every row is returned exactly once, and the data of the other
+        // sequencer is kept
+        let plan = provider.scan(&None, &[], None).await.unwrap();
+        let expected = vec![
+            "+-----------+------+--------------------------------+",
+            "| field_int | tag1 | time                           |",
+            "+-----------+------+--------------------------------+",
+            "| 100       | AL   | 1970-01-01T00:00:00.000000050Z |",
+            "| 70        | CT   | 1970-01-01T00:00:00.000000100Z |",
+            "| 42        | MA   | 1970-01-01T00:00:00.000001Z    |",
+            "| 1000      | MT   | 1970-01-01T00:00:00.000001Z    |",
+            "| 5         | MT   | 1970-01-01T00:00:00.000005Z    |",
+            "| 10        | MT   | 1970-01-01T00:00:00.000007Z    |",
+            "| 70        | UT   | 1970-01-01T00:00:00.000020Z    |",
+            "| 10        | VT   | 1970-01-01T00:00:00.000010Z    |",
+            "| 1000      | WA   | 1970-01-01T00:00:00.000008Z    |",
+            "+-----------+------+--------------------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &test_collect(plan).await);
+    }
+
+    #[test]
+    fn exclude_overlapping_sequence_numbers_per_partition() {
+        let chunk = |id: u128, partition_key: &str| {
+            Arc::new(
+                TestChunk::new("t")
+                    .with_id(id)
+                    .with_order(id as u64)
+                    .with_partition_key(partition_key)
+                    .with_sequence_numbers(SequenceNumberRange::new(1, 1..=5)),
+            )
+        };
+
+        // A write to two partitions is contained in a chunk of each of them,
+        // which must both be kept
+        let chunks = vec![chunk(1, "a"), chunk(2, "b"), chunk(3, "a")];
+        let chunks = exclude_overlapping_sequence_numbers(chunks);
+        assert_eq!(chunk_ids(&chunks), "1, 2");
+    }
+
+    #[tokio::test]
+    async fn scan_with_parallelism() {
+        test_helpers::maybe_start_logging();
+
+        // Ten chunks that do not overlap and two that do
+        let new_chunk = |id: u128| {
+            let start = (id.min(11) as i64) * 10_000;
+            TestChunk::new("t")
+                .with_id(id)
+                .with_order(id as u64)
+                .with_time_column_with_stats(Some(start), Some(start + 9_999))
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data()
+        };
+        let chunks: Vec<_> = (1..=12).map(|id| Arc::new(new_chunk(id))).collect();
+
+        let scan = |parallelism: usize, sorted: bool| {
+            let chunks = chunks.clone();
+            async move {
+                let mut builder = ProviderBuilder::new("t", chunks[0].schema())
+                    .add_no_op_pruner()
+                    .with_scan_parallelism(NonZeroUsize::new(parallelism).unwrap());
+                for chunk in chunks {
+                    builder = builder.add_chunk(chunk);
+                }
+                if sorted {
+                    builder.ensure_pk_sort();
+                }
+                let provider = builder.build().unwrap();
+
+                let plan = provider.scan(&None, &[], None).await.unwrap();
+                let batches = test_collect(plan).await;
+                let formatted = pretty_format_batches(&batches).unwrap().to_string();
+                let mut lines: Vec<_> = formatted.lines().map(String::from).collect();
+                if !sorted {
+                    lines.sort();
+                }
+                lines
+            }
+        };
+
+        for sorted in [false, true] {
+            let expected = scan(1, sorted).await;
+            assert_eq!(expected.len(), 4 + 11 * 5);
+            assert_eq!(scan(8, sorted).await, expected);
+        }
+
+        // The chunks without duplicates are read by as many partitions as
+        // requested
+        let chunks: Vec<_> = chunks[..10].to_vec();
+        for (parallelism, partitions) in [(1, 1), (8, 8), (20, 10)] {
+            let mut deduplicator = Deduplicater::new();
+            deduplicator.scan_parallelism = NonZeroUsize::new(parallelism);
+            let plan = deduplicator
+                .build_scan_plan(
+                    Arc::from("t"),
+                    chunks[0].schema(),
+                    chunks.clone(),
+                    Predicate::default(),
+                    false,
+                )
+                .unwrap();
+            assert_eq!(plan.output_partitioning().partition_count(), partitions);
+        }
+
+        // So are they if they are sorted or have delete predicates, with a
+        // plan per group of chunks
+        let deleted_chunks: Vec<_> = (1..=10)
+            .map(|id| {
+                Arc::new(new_chunk(id).with_delete_predicate(DeletePredicate {
+                    range: TimestampRange::new(0, 1),
+                    exprs: vec![],
+                }))
+            })
+            .collect();
+        for (chunks, sort_output) in [(&chunks, true), (&deleted_chunks, false)] {
+            for (parallelism, plans) in [(1, 1), (8, 8), (20, 10)] {
+                let mut deduplicator = Deduplicater::new();
+                deduplicator.scan_parallelism = NonZeroUsize::new(parallelism);
+                let plan = deduplicator
+                    .build_scan_plan(
+                        Arc::from("t"),
+                        chunks[0].schema(),
+                        chunks.clone(),
+                        Predicate::default(),
+                        sort_output,
+                    )
+                    .unwrap();
+                let plan = if sort_output {
+                    Arc::clone(&plan.children()[0])
+                } else {
+                    plan
+                };
+                let num_plans = match plan.as_any().downcast_ref::<UnionExec>() {
+                    Some(union) => union.children().len(),
+                    None => 1,
+                };
+                assert_eq!(num_plans, plans);
+            }
+        }
+
+        let scan_deleted = |parallelism: usize| {
+            let chunks = deleted_chunks.clone();
+            async move {
+                let mut builder = ProviderBuilder::new("t", chunks[0].schema())
+                    .add_no_op_pruner()
+                    .with_scan_parallelism(NonZeroUsize::new(parallelism).unwrap());
+                for chunk in chunks {
+                    builder = builder.add_chunk(chunk);
+                }
+                let provider = builder.build().unwrap();
+
+                let plan = provider.scan(&None, &[], None).await.unwrap();
+                let batches = test_collect(plan).await;
+                let formatted = pretty_format_batches(&batches).unwrap().to_string();
+                let mut lines: Vec<_> = formatted.lines().map(String::from).collect();
+                lines.sort();
+                lines
+            }
+        };
+        let expected = scan_deleted(20).await;
+        assert_eq!(expected.len(), 4 + 10 * 5);
+        assert_eq!(scan_deleted(3).await, expected);
+    }
+
     fn chunk_ids(group: &[Arc<TestChunk>]) -> String {
         let ids = group
             .iter()
@@ -6,6 +6,7 @@ use std::task::{Context, Poll};
 
 use arrow::{
     array::new_null_array,
+    compute::cast,
     datatypes::{DataType, SchemaRef},
     error::Result as ArrowResult,
     record_batch::RecordBatch,
@@ -54,6 +55,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// stream would append a column of B / nulls to each record batch
 /// that flowed through it
 ///
+/// Int64 input columns of Float64 output columns, such as integer fields
+/// since promoted to float fields, are cast to Float64.
+///
 /// ```text
 ///
 ///                       ┌────────────────┐                         ┌─────────────────────────┐
@@ -119,7 +123,14 @@ impl SchemaAdapterStream {
                     .map(|(idx, _)| idx);
 
                 if let Some(input_field_index) = input_field_index {
-                    ColumnMapping::FromInput(input_field_index)
+                    let input_type = input_schema.field(input_field_index).data_type();
+                    if input_type == &DataType::Int64
+                        && output_field.data_type() == &DataType::Float64
+                    {
+                        ColumnMapping::CastFromInput(input_field_index)
+                    } else {
+                        ColumnMapping::FromInput(input_field_index)
+                    }
                 } else {
                     ColumnMapping::MakeNull(output_field.data_type().clone())
                 }
@@ -160,6 +171,7 @@ impl SchemaAdapterStream {
                         .fail();
                     }
                 }
+                ColumnMapping::CastFromInput(_) => {}
                 ColumnMapping::MakeNull(data_type) => {
                     let output_field = output_schema.field(output_index);
                     if data_type != output_field.data_type() {
@@ -189,10 +201,15 @@ impl SchemaAdapterStream {
             .mappings
             .iter()
             .map(|mapping| match mapping {
-                ColumnMapping::FromInput(input_index) => Arc::clone(batch.column(*input_index)),
-                ColumnMapping::MakeNull(data_type) => new_null_array(data_type, batch.num_rows()),
+                ColumnMapping::FromInput(input_index) => Ok(Arc::clone(batch.column(*input_index))),
+                ColumnMapping::CastFromInput(input_index) => {
+                    cast(batch.column(*input_index), &DataType::Float64)
+                }
+                ColumnMapping::MakeNull(data_type) => {
+                    Ok(new_null_array(data_type, batch.num_rows()))
+                }
             })
-            .collect::<Vec<_>>();
+            .collect::<ArrowResult<Vec<_>>>()?;
 
         RecordBatch::try_new(Arc::clone(&self.output_schema), output_columns)
     }
@@ -227,6 +244,8 @@ impl Stream for SchemaAdapterStream {
 enum ColumnMapping {
     /// Output column is found at <index> column of the input schema
     FromInput(usize),
+    /// Output column is the Int64 <index> column of the input schema cast to Float64
+    CastFromInput(usize),
     /// Output colum should be synthesized with nulls of the specified type
     MakeNull(DataType),
 }
@@ -237,7 +256,7 @@ mod tests {
 
     use super::*;
     use arrow::{
-        array::{ArrayRef, Int32Array, StringArray},
+        array::{ArrayRef, Int32Array, Int64Array, StringArray},
         datatypes::{Field, Schema},
         record_batch::RecordBatch,
     };
@@ -363,6 +382,38 @@ mod tests {
         assert_contains!(res.unwrap_err().to_string(), "input field 'c' had type 'Utf8' which is different than output field 'c' which had type 'Float32'");
     }
 
+    #[tokio::test]
+    async fn input_has_promoted_integer_type() {
+        let col_a = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let col_d = Arc::new(Int64Array::from(vec![Some(7), None, Some(9)]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("a", col_a as ArrayRef), ("d", col_d)]).unwrap();
+
+        // column d has int64 type in input, output asks float64
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("d", DataType::Float64, true),
+        ]));
+        let input_stream = stream_from_batch(batch);
+        let adapter_stream =
+            SchemaAdapterStream::try_new(input_stream, output_schema, baseline_metrics()).unwrap();
+
+        let output = collect(Box::pin(adapter_stream))
+            .await
+            .expect("Running plan");
+        assert_eq!(output[0].column(1).data_type(), &DataType::Float64);
+        let expected = vec![
+            "+---+---+",
+            "| a | d |",
+            "+---+---+",
+            "| 1 | 7 |",
+            "| 2 |   |",
+            "| 3 | 9 |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(&expected, &output);
+    }
+
     // input has different column types than desired output
 
     fn make_batch() -> RecordBatch {
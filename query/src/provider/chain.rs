@@ -0,0 +1,168 @@
+//! Implementation of a DataFusion PhysicalPlan node reading its inputs one
+//! after the other
+
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{
+    datatypes::SchemaRef,
+    error::{ArrowError, Result as ArrowResult},
+    record_batch::RecordBatch,
+};
+use async_trait::async_trait;
+use datafusion::{
+    error::Result,
+    execution::runtime_env::RuntimeEnv,
+    physical_plan::{
+        metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+        DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
+
+/// Concatenates the output of its inputs, which must all have the same
+/// schema, into a single output partition.
+///
+/// Unlike a `UnionExec` whose partitions are read concurrently, the
+/// partitions of the inputs are read one after the other, only starting to
+/// read a partition once the previous one is exhausted.
+#[derive(Debug)]
+pub(crate) struct ChainExec {
+    schema: SchemaRef,
+    inputs: Vec<Arc<dyn ExecutionPlan>>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl ChainExec {
+    /// Create a node reading the (non empty) `inputs` one after the other
+    pub fn new(inputs: Vec<Arc<dyn ExecutionPlan>>) -> Self {
+        assert!(!inputs.is_empty(), "ChainExec needs at least one input");
+        Self {
+            schema: inputs[0].schema(),
+            inputs,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for ChainExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        self.inputs.clone()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::new(children)))
+    }
+
+    async fn execute(
+        &self,
+        partition: usize,
+        runtime: Arc<RuntimeEnv>,
+    ) -> Result<SendableRecordBatchStream> {
+        assert_eq!(partition, 0, "ChainExec has a single output partition");
+
+        let input_partitions: Vec<_> = self
+            .inputs
+            .iter()
+            .flat_map(|input| {
+                let partitions = input.output_partitioning().partition_count();
+                (0..partitions).map(move |partition| (Arc::clone(input), partition))
+            })
+            .collect();
+
+        let inner = futures::stream::iter(input_partitions)
+            .then(move |(input, partition)| {
+                let runtime = Arc::clone(&runtime);
+                async move {
+                    input
+                        .execute(partition, runtime)
+                        .await
+                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+                }
+            })
+            .try_flatten()
+            .boxed();
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        Ok(Box::pin(ChainedStream::new(
+            self.schema(),
+            inner,
+            baseline_metrics,
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(f, "ChainExec: inputs={}", self.inputs.len()),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// The concatenated output of several streams
+pub(super) struct ChainedStream {
+    schema: SchemaRef,
+    inner: BoxStream<'static, ArrowResult<RecordBatch>>,
+    /// Metrics of the output partition reading the streams
+    baseline_metrics: BaselineMetrics,
+}
+
+impl ChainedStream {
+    /// Create a stream of the output of `inner`, with the given `schema`,
+    /// recording its polls in `baseline_metrics`
+    pub(super) fn new(
+        schema: SchemaRef,
+        inner: BoxStream<'static, ArrowResult<RecordBatch>>,
+        baseline_metrics: BaselineMetrics,
+    ) -> Self {
+        Self {
+            schema,
+            inner,
+            baseline_metrics,
+        }
+    }
+}
+
+impl Stream for ChainedStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.inner.poll_next_unpin(cx);
+        self.baseline_metrics.record_poll(poll)
+    }
+}
+
+impl RecordBatchStream for ChainedStream {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
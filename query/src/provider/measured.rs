@@ -0,0 +1,427 @@
+//! A [`QueryChunk`] wrapper recording how long reading the chunk takes and
+//! how much data it produces
+
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use data_types::{
+    chunk_metadata::{ChunkAddr, ChunkId, ChunkOrder},
+    delete_predicate::DeletePredicate,
+    partition_metadata::TableSummary,
+};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use metric::{Attributes, DurationHistogram, Metric, U64Counter};
+use predicate::predicate::{Predicate, PredicateMatch};
+use schema::{selection::Selection, sort::SortKey, Schema};
+
+use super::ChunkPruner;
+use crate::{exec::stringset::StringSet, QueryChunk, QueryChunkMeta, SequenceNumberRange};
+
+/// The metrics recorded by [`MeasuredChunk`]s
+#[derive(Debug, Clone)]
+pub struct ChunkReadMetrics {
+    attributes: Attributes,
+    duration: Metric<DurationHistogram>,
+    rows: Metric<U64Counter>,
+    memory_bytes: Metric<U64Counter>,
+}
+
+impl ChunkReadMetrics {
+    /// Register the chunk read metrics with the given registry. The metrics
+    /// of each chunk are labelled with `attributes` and the table and type
+    /// of the chunk, so the number of series does not grow with the number
+    /// of chunks.
+    pub fn new(registry: &metric::Registry, attributes: impl Into<Attributes>) -> Self {
+        let duration = registry.register_metric::<DurationHistogram>(
+            "query_chunk_read_filter_duration",
+            "Time from requesting the data of a chunk until its stream ended",
+        );
+        let rows = registry.register_metric::<U64Counter>(
+            "query_chunk_read_filter_rows",
+            "Number of rows produced by reading chunks",
+        );
+        let memory_bytes = registry.register_metric::<U64Counter>(
+            "query_chunk_read_filter_memory_bytes",
+            "Size in memory of the buffers of the record batches produced by reading chunks, \
+             including the parts of the buffers outside of sliced batches",
+        );
+
+        Self {
+            attributes: attributes.into(),
+            duration,
+            rows,
+            memory_bytes,
+        }
+    }
+
+    fn recorders(&self, chunk: &impl QueryChunk) -> ChunkReadRecorders {
+        let mut attributes = self.attributes.clone();
+        attributes.insert("table", chunk.table_name().to_string());
+        attributes.insert("chunk_type", chunk.chunk_type().to_string());
+
+        ChunkReadRecorders {
+            duration: self.duration.recorder(attributes.clone()),
+            rows: self.rows.recorder(attributes.clone()),
+            memory_bytes: self.memory_bytes.recorder(attributes),
+        }
+    }
+}
+
+/// The metrics of a single chunk
+#[derive(Debug, Clone)]
+struct ChunkReadRecorders {
+    duration: DurationHistogram,
+    rows: U64Counter,
+    memory_bytes: U64Counter,
+}
+
+/// Wraps a [`QueryChunk`], recording the latency of each `read_filter`, and
+/// the rows and memory size of the batches it produces, in
+/// [`ChunkReadMetrics`]. All other methods are forwarded to the wrapped
+/// chunk.
+#[derive(Debug)]
+pub struct MeasuredChunk<C: QueryChunk> {
+    inner: Arc<C>,
+    recorders: ChunkReadRecorders,
+}
+
+impl<C: QueryChunk> MeasuredChunk<C> {
+    /// Wrap `inner`, recording its reads in `metrics`
+    pub fn new(inner: Arc<C>, metrics: &ChunkReadMetrics) -> Self {
+        let recorders = metrics.recorders(inner.as_ref());
+        Self { inner, recorders }
+    }
+
+    /// Returns the wrapped chunk
+    pub fn inner(&self) -> &Arc<C> {
+        &self.inner
+    }
+}
+
+impl<C: QueryChunk> QueryChunkMeta for MeasuredChunk<C> {
+    fn summary(&self) -> Option<&TableSummary> {
+        self.inner.summary()
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        self.inner.schema()
+    }
+
+    fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
+        self.inner.delete_predicates()
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.inner.size_bytes()
+    }
+}
+
+impl<C: QueryChunk> QueryChunk for MeasuredChunk<C> {
+    type Error = C::Error;
+
+    fn id(&self) -> ChunkId {
+        self.inner.id()
+    }
+
+    fn addr(&self) -> ChunkAddr {
+        self.inner.addr()
+    }
+
+    fn table_name(&self) -> &str {
+        self.inner.table_name()
+    }
+
+    fn may_contain_pk_duplicates(&self) -> bool {
+        self.inner.may_contain_pk_duplicates()
+    }
+
+    fn apply_predicate_to_metadata(
+        &self,
+        predicate: &Predicate,
+    ) -> Result<PredicateMatch, Self::Error> {
+        self.inner.apply_predicate_to_metadata(predicate)
+    }
+
+    fn column_names(
+        &self,
+        predicate: &Predicate,
+        columns: Selection<'_>,
+    ) -> Result<Option<StringSet>, Self::Error> {
+        self.inner.column_names(predicate, columns)
+    }
+
+    fn column_values(
+        &self,
+        column_name: &str,
+        predicate: &Predicate,
+    ) -> Result<Option<StringSet>, Self::Error> {
+        self.inner.column_values(column_name, predicate)
+    }
+
+    fn read_filter(
+        &self,
+        predicate: &Predicate,
+        selection: Selection<'_>,
+    ) -> Result<SendableRecordBatchStream, Self::Error> {
+        let start = Instant::now();
+        let stream = self.inner.read_filter(predicate, selection)?;
+        Ok(Box::pin(MeasuredStream {
+            inner: stream,
+            recorders: self.recorders.clone(),
+            start,
+        }))
+    }
+
+    fn is_sorted_on_pk(&self) -> bool {
+        self.inner.is_sorted_on_pk()
+    }
+
+    fn sort_key(&self) -> Option<SortKey<'_>> {
+        self.inner.sort_key()
+    }
+
+    fn chunk_type(&self) -> &str {
+        self.inner.chunk_type()
+    }
+
+    fn order(&self) -> ChunkOrder {
+        self.inner.order()
+    }
+
+    fn partition_key(&self) -> Option<Arc<str>> {
+        self.inner.partition_key()
+    }
+
+    fn sequence_numbers(&self) -> Option<SequenceNumberRange> {
+        self.inner.sequence_numbers()
+    }
+
+    fn exclude_sequence_numbers(
+        self: &Arc<Self>,
+        exclude: &SequenceNumberRange,
+    ) -> Option<Arc<Self>> {
+        let inner = self.inner.exclude_sequence_numbers(exclude)?;
+        if Arc::ptr_eq(&inner, &self.inner) {
+            return Some(Arc::clone(self));
+        }
+        Some(Arc::new(Self {
+            inner,
+            recorders: self.recorders.clone(),
+        }))
+    }
+}
+
+/// A [`ChunkPruner`] for [`MeasuredChunk`]s, pruning them with the pruner
+/// of the wrapped chunks
+#[derive(Debug)]
+pub struct MeasuredPruner<C: QueryChunk> {
+    inner: Arc<dyn ChunkPruner<C>>,
+}
+
+impl<C: QueryChunk> MeasuredPruner<C> {
+    /// Prune the wrapped chunks with `inner`
+    pub fn new(inner: Arc<dyn ChunkPruner<C>>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<C: QueryChunk> ChunkPruner<MeasuredChunk<C>> for MeasuredPruner<C> {
+    fn prune_chunks(
+        &self,
+        table_name: &str,
+        table_schema: Arc<Schema>,
+        chunks: Vec<Arc<MeasuredChunk<C>>>,
+        predicate: &Predicate,
+    ) -> Vec<Arc<MeasuredChunk<C>>> {
+        let inner = chunks
+            .iter()
+            .map(|chunk| Arc::clone(chunk.inner()))
+            .collect();
+        let kept: HashSet<_> = self
+            .inner
+            .prune_chunks(table_name, table_schema, inner, predicate)
+            .iter()
+            .map(Arc::as_ptr)
+            .collect();
+
+        chunks
+            .into_iter()
+            .filter(|chunk| kept.contains(&Arc::as_ptr(chunk.inner())))
+            .collect()
+    }
+}
+
+/// Counts the rows and memory size of the wrapped stream, and records the time
+/// since the read started when dropped
+struct MeasuredStream {
+    inner: SendableRecordBatchStream,
+    recorders: ChunkReadRecorders,
+    start: Instant,
+}
+
+impl RecordBatchStream for MeasuredStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for MeasuredStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = futures::ready!(self.inner.poll_next_unpin(cx));
+        if let Some(Ok(batch)) = &next {
+            // The memory size of sliced arrays includes their whole buffers,
+            // which may be shared with other batches
+            let memory_bytes: usize = batch
+                .columns()
+                .iter()
+                .map(|array| array.get_array_memory_size())
+                .sum();
+            self.recorders.rows.inc(batch.num_rows() as u64);
+            self.recorders.memory_bytes.inc(memory_bytes as u64);
+        }
+        Poll::Ready(next)
+    }
+}
+
+impl Drop for MeasuredStream {
+    fn drop(&mut self) {
+        self.recorders.duration.record(self.start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::physical_plan::common::collect;
+
+    use crate::test::TestChunk;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn read_filter_records_metrics() {
+        let registry = metric::Registry::new();
+        let metrics = ChunkReadMetrics::new(&registry, &[("db_name", "db")]);
+
+        let inner = Arc::new(
+            TestChunk::new("t")
+                .with_id(1)
+                .with_time_column()
+                .with_tag_column("tag")
+                .with_four_rows_of_data(),
+        );
+        let chunk = MeasuredChunk::new(Arc::clone(&inner), &metrics);
+        assert_eq!(chunk.id(), inner.id());
+        assert_eq!(chunk.table_name(), "t");
+
+        let stream = chunk
+            .read_filter(&Predicate::default(), Selection::All)
+            .unwrap();
+        let batches = collect(stream).await.unwrap();
+        let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(num_rows, 4);
+
+        let attributes = Attributes::from([
+            ("db_name", "db".into()),
+            ("table", "t".into()),
+            ("chunk_type", chunk.chunk_type().to_string().into()),
+        ]);
+
+        let duration = registry
+            .get_instrument::<Metric<DurationHistogram>>("query_chunk_read_filter_duration")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(duration.sample_count(), 1);
+
+        let rows = registry
+            .get_instrument::<Metric<U64Counter>>("query_chunk_read_filter_rows")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(rows, 4);
+
+        let memory_bytes = registry
+            .get_instrument::<Metric<U64Counter>>("query_chunk_read_filter_memory_bytes")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert!(memory_bytes > 0);
+
+        // Other chunks of the table record into the same series
+        let other = MeasuredChunk::new(
+            Arc::new(
+                TestChunk::new("t")
+                    .with_id(2)
+                    .with_time_column()
+                    .with_tag_column("tag")
+                    .with_four_rows_of_data(),
+            ),
+            &metrics,
+        );
+        collect(
+            other
+                .read_filter(&Predicate::default(), Selection::All)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let rows = registry
+            .get_instrument::<Metric<U64Counter>>("query_chunk_read_filter_rows")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(rows, 8);
+    }
+
+    #[test]
+    fn measured_pruner_prunes_wrapped_chunks() {
+        #[derive(Debug)]
+        struct KeepId(ChunkId);
+
+        impl ChunkPruner<TestChunk> for KeepId {
+            fn prune_chunks(
+                &self,
+                _table_name: &str,
+                _table_schema: Arc<Schema>,
+                chunks: Vec<Arc<TestChunk>>,
+                _predicate: &Predicate,
+            ) -> Vec<Arc<TestChunk>> {
+                chunks
+                    .into_iter()
+                    .filter(|chunk| chunk.id() == self.0)
+                    .collect()
+            }
+        }
+
+        let registry = metric::Registry::new();
+        let metrics = ChunkReadMetrics::new(&registry, &[("db_name", "db")]);
+        let chunks: Vec<_> = (1..=3)
+            .map(|id| {
+                Arc::new(MeasuredChunk::new(
+                    Arc::new(TestChunk::new("t").with_id(id).with_time_column()),
+                    &metrics,
+                ))
+            })
+            .collect();
+        let schema = chunks[0].schema();
+
+        let pruner = MeasuredPruner::new(Arc::new(KeepId(ChunkId::new_test(2))));
+        let kept = pruner.prune_chunks("t", schema, chunks, &Predicate::default());
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].id(), ChunkId::new_test(2));
+    }
+}
@@ -1,8 +1,8 @@
 //! Implementation of a DataFusion PhysicalPlan node across partition chunks
 
-use std::{fmt, sync::Arc};
+use std::{fmt, num::NonZeroUsize, sync::Arc};
 
-use arrow::datatypes::SchemaRef;
+use arrow::{datatypes::SchemaRef, error::ArrowError};
 use data_types::partition_metadata::TableSummary;
 use datafusion::{
     error::DataFusionError,
@@ -12,15 +12,20 @@ use datafusion::{
         DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
     },
 };
+use futures::{StreamExt, TryStreamExt};
+use observability_deps::tracing::debug;
 use schema::selection::Selection;
 use schema::Schema;
 
-use crate::QueryChunk;
+use crate::{
+    exec::progress::{planning_reporter, with_progress, QueryProgressReporter},
+    QueryChunk, UnsupportedPredicate,
+};
 use predicate::predicate::Predicate;
 
 use async_trait::async_trait;
 
-use super::adapter::SchemaAdapterStream;
+use super::{adapter::SchemaAdapterStream, chain::ChainedStream};
 
 /// Implements the DataFusion physical plan interface
 #[derive(Debug)]
@@ -31,8 +36,13 @@ pub(crate) struct IOxReadFilterNode<C: QueryChunk + 'static> {
     iox_schema: Arc<Schema>,
     chunks: Vec<Arc<C>>,
     predicate: Predicate,
+    /// The maximum number of chunks read concurrently, unlimited if `None`
+    parallelism: Option<NonZeroUsize>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
+    /// Reporter of the rows and chunks read, if the query reports its
+    /// progress
+    progress: Option<QueryProgressReporter>,
 }
 
 impl<C: QueryChunk + 'static> IOxReadFilterNode<C> {
@@ -45,12 +55,38 @@ impl<C: QueryChunk + 'static> IOxReadFilterNode<C> {
         chunks: Vec<Arc<C>>,
         predicate: Predicate,
     ) -> Self {
+        let progress = planning_reporter();
+        if let Some(reporter) = &progress {
+            reporter.plan_chunks(chunks.len());
+        }
+
         Self {
             table_name,
             iox_schema,
             chunks,
             predicate,
+            parallelism: None,
             metrics: ExecutionPlanMetricsSet::new(),
+            progress,
+        }
+    }
+
+    /// Read at most `parallelism` chunks concurrently.
+    ///
+    /// The chunks are distributed over `parallelism` output partitions,
+    /// each of which reads its chunks one after the other.
+    pub fn with_parallelism(self, parallelism: NonZeroUsize) -> Self {
+        Self {
+            parallelism: Some(parallelism),
+            ..self
+        }
+    }
+
+    /// Returns the number of output partitions
+    fn num_partitions(&self) -> usize {
+        match self.parallelism {
+            Some(parallelism) => self.chunks.len().min(parallelism.get()),
+            None => self.chunks.len(),
         }
     }
 }
@@ -66,7 +102,7 @@ impl<C: QueryChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.chunks.len())
+        Partitioning::UnknownPartitioning(self.num_partitions())
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -89,7 +125,9 @@ impl<C: QueryChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
             iox_schema: Arc::clone(&self.iox_schema),
             chunks,
             predicate: self.predicate.clone(),
+            parallelism: self.parallelism,
             metrics: ExecutionPlanMetricsSet::new(),
+            progress: self.progress.clone(),
         };
 
         Ok(Arc::new(new_self))
@@ -100,42 +138,57 @@ impl<C: QueryChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
         partition: usize,
         _runtime: Arc<RuntimeEnv>,
     ) -> datafusion::error::Result<SendableRecordBatchStream> {
+        let schema = self.schema();
+        let mut chunks: Vec<_> = self
+            .chunks
+            .iter()
+            .skip(partition)
+            .step_by(self.num_partitions())
+            .map(Arc::clone)
+            .collect();
+
+        if chunks.len() == 1 {
+            let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+            return read_chunk(
+                &self.table_name,
+                schema,
+                chunks.remove(0).as_ref(),
+                &self.predicate,
+                baseline_metrics,
+                self.progress.clone(),
+            );
+        }
+
+        // Read the chunks of this partition one after the other, only
+        // starting to read a chunk once the previous one is exhausted. The
+        // partition's metrics are recorded once for the concatenated output,
+        // the metrics of each chunk read are not reported.
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
-        let timer = baseline_metrics.elapsed_compute().timer();
+        let table_name = Arc::clone(&self.table_name);
+        let predicate = self.predicate.clone();
+        let progress = self.progress.clone();
+        let chunk_schema = Arc::clone(&schema);
+        let inner = futures::stream::iter(chunks)
+            .map(move |chunk| {
+                let chunk_metrics = BaselineMetrics::new(&ExecutionPlanMetricsSet::new(), 0);
+                read_chunk(
+                    &table_name,
+                    Arc::clone(&chunk_schema),
+                    chunk.as_ref(),
+                    &predicate,
+                    chunk_metrics,
+                    progress.clone(),
+                )
+                .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+            })
+            .try_flatten()
+            .boxed();
 
-        let schema = self.schema();
-        let fields = schema.fields();
-        let selection_cols = fields.iter().map(|f| f.name() as &str).collect::<Vec<_>>();
-
-        let chunk = Arc::clone(&self.chunks[partition]);
-
-        let chunk_table_schema = chunk.schema();
-
-        // The output selection is all the columns in the schema.
-        //
-        // However, this chunk may not have all those columns. Thus we
-        // restrict the requested selection to the actual columns
-        // available, and use SchemaAdapterStream to pad the rest of
-        // the columns with NULLs if necessary
-        let selection_cols = restrict_selection(selection_cols, &chunk_table_schema);
-        let selection = Selection::Some(&selection_cols);
-
-        let stream = chunk.read_filter(&self.predicate, selection).map_err(|e| {
-            DataFusionError::Execution(format!(
-                "Error creating scan for table {} chunk {}: {}",
-                self.table_name,
-                chunk.id(),
-                e
-            ))
-        })?;
-
-        // all CPU time is now done, pass in baseline metrics to adapter
-        timer.done();
-
-        let adapter = SchemaAdapterStream::try_new(stream, schema, baseline_metrics)
-            .map_err(|e| DataFusionError::Internal(e.to_string()))?;
-
-        Ok(Box::pin(adapter))
+        Ok(Box::pin(ChainedStream::new(
+            schema,
+            inner,
+            baseline_metrics,
+        )))
     }
 
     fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -183,6 +236,63 @@ impl<C: QueryChunk + 'static> ExecutionPlan for IOxReadFilterNode<C> {
     }
 }
 
+/// Returns a stream of the data of `chunk` matching `predicate`, with the
+/// columns of `schema`, reporting the rows read to `progress` if any
+fn read_chunk<C: QueryChunk>(
+    table_name: &str,
+    schema: SchemaRef,
+    chunk: &C,
+    predicate: &Predicate,
+    baseline_metrics: BaselineMetrics,
+    progress: Option<QueryProgressReporter>,
+) -> datafusion::error::Result<SendableRecordBatchStream> {
+    let timer = baseline_metrics.elapsed_compute().timer();
+
+    let fields = schema.fields();
+    let selection_cols = fields.iter().map(|f| f.name() as &str).collect::<Vec<_>>();
+
+    let chunk_table_schema = chunk.schema();
+
+    // The output selection is all the columns in the schema.
+    //
+    // However, this chunk may not have all those columns. Thus we
+    // restrict the requested selection to the actual columns
+    // available, and use SchemaAdapterStream to pad the rest of
+    // the columns with NULLs if necessary
+    let selection_cols = restrict_selection(selection_cols, &chunk_table_schema);
+    let selection = Selection::Some(&selection_cols);
+
+    // The predicate is only pushed down to chunks for performance, the
+    // filters are applied again to the scan output. So if the chunk can not
+    // evaluate it, read the chunk unfiltered.
+    let stream = match chunk.read_filter(predicate, selection) {
+        Err(e) if UnsupportedPredicate::find(&e).is_some() => {
+            debug!(%table_name, chunk_id=%chunk.id(), %e, "reading chunk without predicate");
+            chunk.read_filter(&Predicate::default(), selection)
+        }
+        res => res,
+    }
+    .map_err(|e| {
+        DataFusionError::Execution(format!(
+            "Error creating scan for table {} chunk {}: {}",
+            table_name,
+            chunk.id(),
+            e
+        ))
+    })?;
+
+    // all CPU time is now done, pass in baseline metrics to adapter
+    timer.done();
+
+    let adapter = SchemaAdapterStream::try_new(stream, Arc::clone(&schema), baseline_metrics)
+        .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+
+    Ok(match progress {
+        Some(reporter) => with_progress(Box::pin(adapter), reporter),
+        None => Box::pin(adapter),
+    })
+}
+
 /// Removes any columns that are not present in schema, returning a possibly
 /// restricted set of columns
 fn restrict_selection<'a>(
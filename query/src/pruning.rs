@@ -9,12 +9,12 @@ use arrow::datatypes::{DataType, Int32Type, TimeUnit};
 
 use data_types::partition_metadata::{StatValues, Statistics};
 use datafusion::{
-    logical_plan::Column,
+    logical_plan::{Column, Expr},
     physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
 };
 use observability_deps::tracing::{debug, trace};
-use predicate::predicate::Predicate;
-use schema::Schema;
+use predicate::predicate::{Predicate, PredicateBuilder};
+use schema::{Schema, TIME_COLUMN_NAME};
 
 use crate::{group_by::Aggregate, QueryChunkMeta};
 
@@ -33,6 +33,14 @@ pub trait PruningObserver {
 /// filtering those where the predicate can be proven to evaluate to
 /// `false` for every single row.
 ///
+/// Chunks whose rows are all deleted by one of their delete predicates are
+/// removed regardless of `predicate`.
+///
+/// Expressions that reference more than one column, such as comparing
+/// two columns (`col1 > col2`), can not be evaluated against per-column
+/// statistics and are treated as possibly `true` for every chunk. Other
+/// conjuncts of the predicate are still used for pruning.
+///
 /// TODO(raphael): Perhaps this should return `Result<Vec<bool>>` instead of
 /// the [`PruningObserver`] plumbing
 pub fn prune_chunks<C, O>(
@@ -48,6 +56,9 @@ where
     let num_chunks = chunks.len();
     trace!(num_chunks, %predicate, "Pruning chunks");
 
+    let chunks = prune_fully_deleted(observer, chunks);
+    let chunks = prune_by_null_counts(observer, chunks, predicate);
+
     let filter_expr = match predicate.filter_expr() {
         Some(expr) => expr,
         None => {
@@ -103,6 +114,117 @@ where
     pruned_chunks
 }
 
+/// Removes the chunks whose time range is covered by one of their delete
+/// predicates without expressions, which deletes all of their rows.
+///
+/// Unlike query ranges, the time range of a delete predicate includes its
+/// end.
+fn prune_fully_deleted<C, O>(observer: &O, chunks: Vec<Arc<C>>) -> Vec<Arc<C>>
+where
+    C: QueryChunkMeta,
+    O: PruningObserver<Observed = C>,
+{
+    chunks
+        .into_iter()
+        .filter(|chunk| {
+            let time_range = chunk
+                .summary()
+                .and_then(|summary| summary.column(TIME_COLUMN_NAME))
+                .and_then(|column| column.stats.timestamp_min_max());
+            let time_range = match time_range {
+                Some(time_range) => time_range,
+                None => return true,
+            };
+
+            let fully_deleted = chunk.delete_predicates().iter().any(|delete| {
+                delete.exprs.is_empty()
+                    && delete.range.start() <= time_range.min
+                    && time_range.max <= delete.range.end()
+            });
+
+            if fully_deleted {
+                trace!(%time_range.min, %time_range.max, "Pruning fully deleted chunk");
+                observer.was_pruned(chunk.as_ref());
+            }
+            !fully_deleted
+        })
+        .collect()
+}
+
+/// Removes the chunks for which a `col IS NULL` / `col IS NOT NULL`
+/// conjunct of `predicate` is `false` for every row, according to the
+/// null count of `col` in the chunk's summary.
+///
+/// A column missing from a chunk is null for all of its rows.
+fn prune_by_null_counts<C, O>(
+    observer: &O,
+    chunks: Vec<Arc<C>>,
+    predicate: &Predicate,
+) -> Vec<Arc<C>>
+where
+    C: QueryChunkMeta,
+    O: PruningObserver<Observed = C>,
+{
+    // (column name, whether the column must be null)
+    let mut null_checks = vec![];
+    for expr in &predicate.exprs {
+        let mut conjuncts = vec![];
+        PredicateBuilder::split_members(expr, &mut conjuncts);
+        null_checks.extend(conjuncts.iter().filter_map(null_check));
+    }
+
+    if null_checks.is_empty() {
+        return chunks;
+    }
+
+    chunks
+        .into_iter()
+        .filter(|chunk| {
+            let summary = match chunk.summary() {
+                Some(summary) => summary,
+                None => return true,
+            };
+            let keep = null_checks.iter().all(|(column, is_null)| {
+                let (null_count, total_count) = match summary.column(column) {
+                    Some(c) => (c.null_count(), c.total_count()),
+                    None => (summary.total_count(), summary.total_count()),
+                };
+
+                match is_null {
+                    true => null_count > 0,
+                    false => null_count < total_count,
+                }
+            });
+
+            if !keep {
+                observer.was_pruned(chunk.as_ref());
+            }
+            keep
+        })
+        .collect()
+}
+
+/// Returns the column name and whether it must be null if `expr` is a
+/// null check of a single column
+fn null_check(expr: &Expr) -> Option<(&str, bool)> {
+    match expr {
+        Expr::IsNull(inner) => Some((column_name(inner)?, true)),
+        Expr::IsNotNull(inner) => Some((column_name(inner)?, false)),
+        Expr::Not(inner) => {
+            let (column, is_null) = null_check(inner)?;
+            Some((column, !is_null))
+        }
+        _ => None,
+    }
+}
+
+fn column_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Column(column) => Some(column.name.as_str()),
+        _ => None,
+    }
+}
+
 /// Wraps a collection of [`QueryChunkMeta`] and implements the [`PruningStatistics`]
 /// interface required by [`PruningPredicate`]
 struct ChunkPruningStatistics<'a, C> {
@@ -227,8 +349,11 @@ fn get_aggregate<T>(stats: &StatValues<T>, aggregate: Aggregate) -> &Option<T> {
 mod test {
     use std::{cell::RefCell, sync::Arc};
 
+    use data_types::{
+        delete_predicate::{DeleteExpr, DeletePredicate, Op, Scalar},
+        timestamp::TimestampRange,
+    };
     use datafusion::logical_plan::{col, lit};
-    use predicate::predicate::PredicateBuilder;
     use schema::merge::SchemaMerger;
 
     use crate::{test::TestChunk, QueryChunk};
@@ -688,6 +813,80 @@ mod test {
         assert_eq!(names(&pruned), vec!["chunk1"]);
     }
 
+    #[test]
+    fn test_pruned_null_counts() {
+        test_helpers::maybe_start_logging();
+        // column1 IS NOT NULL / column1 IS NULL where
+        //   c1: column1 has no nulls
+        //   c2: column1 has some nulls
+        //   c3: column1 is entirely null
+        //   c4: column1 does not exist
+        let c1 = Arc::new(
+            TestChunk::new("chunk1").with_tag_column_with_nulls_and_full_stats(
+                "column1",
+                Some("a"),
+                Some("z"),
+                100,
+                None,
+                0,
+            ),
+        );
+
+        let c2 = Arc::new(
+            TestChunk::new("chunk2").with_tag_column_with_nulls_and_full_stats(
+                "column1",
+                Some("a"),
+                Some("z"),
+                100,
+                None,
+                10,
+            ),
+        );
+
+        let c3 = Arc::new(
+            TestChunk::new("chunk3")
+                .with_tag_column_with_nulls_and_full_stats("column1", None, None, 100, None, 100),
+        );
+
+        let c4 = Arc::new(
+            TestChunk::new("chunk4").with_tag_column_with_nulls_and_full_stats(
+                "column2",
+                Some("a"),
+                Some("z"),
+                100,
+                None,
+                0,
+            ),
+        );
+
+        let chunks = vec![c1, c2, c3, c4];
+        let schema = merge_schema(&chunks);
+
+        let observer = TestObserver::new();
+        let predicate = PredicateBuilder::new().is_not_null("column1").build();
+        let pruned = prune_chunks(&observer, Arc::clone(&schema), chunks.clone(), &predicate);
+
+        assert_eq!(observer.events(), vec!["chunk3: Pruned", "chunk4: Pruned"]);
+        assert_eq!(names(&pruned), vec!["chunk1", "chunk2"]);
+
+        let observer = TestObserver::new();
+        let predicate = PredicateBuilder::new().is_null("column1").build();
+        let pruned = prune_chunks(&observer, Arc::clone(&schema), chunks.clone(), &predicate);
+
+        assert_eq!(observer.events(), vec!["chunk1: Pruned"]);
+        assert_eq!(names(&pruned), vec!["chunk2", "chunk3", "chunk4"]);
+
+        // NOT (column1 IS NOT NULL) is the same as column1 IS NULL
+        let observer = TestObserver::new();
+        let predicate = PredicateBuilder::new()
+            .add_expr(col("column1").is_not_null().not())
+            .build();
+        let pruned = prune_chunks(&observer, schema, chunks, &predicate);
+
+        assert_eq!(observer.events(), vec!["chunk1: Pruned"]);
+        assert_eq!(names(&pruned), vec!["chunk2", "chunk3", "chunk4"]);
+    }
+
     #[test]
     fn test_pruned_multi_column() {
         test_helpers::maybe_start_logging();
@@ -752,6 +951,134 @@ mod test {
         assert_eq!(names(&pruned), vec!["chunk1", "chunk4", "chunk6"]);
     }
 
+    #[test]
+    fn test_not_pruned_column_comparison() {
+        test_helpers::maybe_start_logging();
+        // column1 > column2 where
+        //   c1: column1 [0, 10], column2 [100, 1000] --> not pruned (can not compare columns)
+        //   c2: column1 [100, 1000], column2 [0, 10] --> not pruned
+        let observer = TestObserver::new();
+        let c1 = Arc::new(
+            TestChunk::new("chunk1")
+                .with_i64_field_column_with_stats("column1", Some(0), Some(10))
+                .with_i64_field_column_with_stats("column2", Some(100), Some(1000)),
+        );
+
+        let c2 = Arc::new(
+            TestChunk::new("chunk2")
+                .with_i64_field_column_with_stats("column1", Some(100), Some(1000))
+                .with_i64_field_column_with_stats("column2", Some(0), Some(10)),
+        );
+
+        let predicate = PredicateBuilder::new()
+            .add_expr(col("column1").gt(col("column2")))
+            .build();
+
+        let chunks = vec![c1, c2];
+        let schema = merge_schema(&chunks);
+
+        let pruned = prune_chunks(&observer, schema, chunks, &predicate);
+
+        assert!(observer.events().is_empty());
+        assert_eq!(names(&pruned), vec!["chunk1", "chunk2"]);
+    }
+
+    #[test]
+    fn test_pruned_column_comparison_and_literal() {
+        test_helpers::maybe_start_logging();
+        // column1 > column2 AND column1 > 100 where
+        //   c1: column1 [0, 10], column2 [0, 10] --> pruned (column1 out of range)
+        //   c2: column1 [100, 1000], column2 [0, 10] --> not pruned
+        let observer = TestObserver::new();
+        let c1 = Arc::new(
+            TestChunk::new("chunk1")
+                .with_i64_field_column_with_stats("column1", Some(0), Some(10))
+                .with_i64_field_column_with_stats("column2", Some(0), Some(10)),
+        );
+
+        let c2 = Arc::new(
+            TestChunk::new("chunk2")
+                .with_i64_field_column_with_stats("column1", Some(100), Some(1000))
+                .with_i64_field_column_with_stats("column2", Some(0), Some(10)),
+        );
+
+        let predicate = PredicateBuilder::new()
+            .add_expr(col("column1").gt(col("column2")))
+            .add_expr(col("column1").gt(lit(100)))
+            .build();
+
+        let chunks = vec![c1, c2];
+        let schema = merge_schema(&chunks);
+
+        let pruned = prune_chunks(&observer, schema, chunks, &predicate);
+
+        assert_eq!(observer.events(), vec!["chunk1: Pruned"]);
+        assert_eq!(names(&pruned), vec!["chunk2"]);
+    }
+
+    #[test]
+    fn test_pruned_fully_deleted() {
+        test_helpers::maybe_start_logging();
+        // No predicate, where
+        //   c1: time [1, 2], delete all in [1, 2] --> pruned
+        //   c2: time [1, 3], delete all in [1, 2] --> not pruned
+        //   c3: time [1, 2], delete foo = 1 in [0, 10] --> not pruned
+        //   c4: time [5, 6], deletes all in [0, 1] and [4, 10] --> pruned
+        //   c5: time [1, 2] without delete --> not pruned
+        let observer = TestObserver::new();
+        let delete_all = |start, end| DeletePredicate {
+            range: TimestampRange::new(start, end),
+            exprs: vec![],
+        };
+
+        let c1 = Arc::new(
+            TestChunk::new("chunk1")
+                .with_time_column_with_stats(Some(1), Some(2))
+                .with_delete_predicate(delete_all(1, 2)),
+        );
+
+        let c2 = Arc::new(
+            TestChunk::new("chunk2")
+                .with_time_column_with_stats(Some(1), Some(3))
+                .with_delete_predicate(delete_all(1, 2)),
+        );
+
+        let c3 = Arc::new(
+            TestChunk::new("chunk3")
+                .with_time_column_with_stats(Some(1), Some(2))
+                .with_delete_predicate(DeletePredicate {
+                    range: TimestampRange::new(0, 10),
+                    exprs: vec![DeleteExpr::new("foo".to_string(), Op::Eq, Scalar::I64(1))],
+                }),
+        );
+
+        let c4 = Arc::new(
+            TestChunk::new("chunk4")
+                .with_time_column_with_stats(Some(5), Some(6))
+                .with_delete_predicate(delete_all(0, 1))
+                .with_delete_predicate(delete_all(4, 10)),
+        );
+
+        let c5 = Arc::new(TestChunk::new("chunk5").with_time_column_with_stats(Some(1), Some(2)));
+
+        let predicate = PredicateBuilder::new().build();
+
+        let chunks = vec![c1, c2, c3, c4, c5];
+        let schema = merge_schema(&chunks);
+
+        let pruned = prune_chunks(&observer, schema, chunks, &predicate);
+
+        assert_eq!(
+            observer.events(),
+            vec![
+                "chunk1: Pruned",
+                "chunk4: Pruned",
+                "Could not prune: No expression on predicate"
+            ]
+        );
+        assert_eq!(names(&pruned), vec!["chunk2", "chunk3", "chunk5"]);
+    }
+
     fn names(pruned: &[Arc<TestChunk>]) -> Vec<&str> {
         pruned.iter().map(|p| p.table_name()).collect()
     }
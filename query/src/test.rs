@@ -7,7 +7,8 @@ use crate::exec::{ExecutionContextProvider, Executor, ExecutorType, IOxExecution
 use crate::QueryCompletedToken;
 use crate::{
     exec::stringset::{StringSet, StringSetRef},
-    Predicate, PredicateMatch, QueryChunk, QueryChunkMeta, QueryDatabase,
+    Predicate, PredicateMatch, QueryChunk, QueryChunkMeta, QueryDatabase, SequenceNumberRange,
+    UnsupportedPredicate,
 };
 use arrow::array::UInt64Array;
 use arrow::{
@@ -20,9 +21,11 @@ use data_types::partition_metadata::PartitionAddr;
 use data_types::{
     chunk_metadata::ChunkSummary,
     delete_predicate::DeletePredicate,
-    partition_metadata::{ColumnSummary, InfluxDbType, StatValues, Statistics, TableSummary},
+    partition_metadata::{
+        ColumnSummary, InfluxDbType, PartitionMeta, StatValues, Statistics, TableSummary,
+    },
 };
-use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::{optimizer::utils::expr_to_columns, physical_plan::SendableRecordBatchStream};
 use datafusion_util::stream_from_batches;
 use futures::StreamExt;
 use hashbrown::HashSet;
@@ -62,6 +65,9 @@ pub enum TestError {
     DatabaseWrite {
         source: Box<dyn std::error::Error + Send + Sync + 'static>,
     },
+
+    #[snafu(display("Test chunk can not evaluate predicate: {}", source))]
+    Unsupported { source: UnsupportedPredicate },
 }
 
 pub type Result<T, E = TestError> = std::result::Result<T, E>;
@@ -135,6 +141,26 @@ impl QueryDatabase for TestDatabase {
             .collect()
     }
 
+    /// Return the partitions with chunks of `table_name`, test chunks are
+    /// never persisted
+    fn partition_metadata(&self, table_name: &str) -> Vec<PartitionMeta> {
+        let partitions = self.partitions.lock();
+        partitions
+            .iter()
+            .filter_map(|(partition_key, chunks)| {
+                let chunk_count = chunks
+                    .values()
+                    .filter(|chunk| chunk.table_name() == table_name)
+                    .count();
+                (chunk_count > 0).then(|| PartitionMeta {
+                    partition_key: Arc::from(partition_key.as_str()),
+                    persisted_sequence_numbers: Default::default(),
+                    chunk_count,
+                })
+            })
+            .collect()
+    }
+
     fn chunks(&self, table_name: &str, predicate: &Predicate) -> Vec<Arc<Self::Chunk>> {
         // save last predicate
         *self.chunks_predicate.lock() = predicate.clone();
@@ -154,6 +180,7 @@ impl QueryDatabase for TestDatabase {
 
     fn record_query(
         &self,
+        _ctx: &IOxExecutionContext,
         _query_type: impl Into<String>,
         _query_text: impl Into<String>,
     ) -> QueryCompletedToken<'_> {
@@ -227,6 +254,9 @@ pub struct TestChunk {
     /// A saved error that is returned instead of actual results
     saved_error: Option<String>,
 
+    /// Column which read_filter can not evaluate predicates on, if any
+    unsupported_predicate_column: Option<String>,
+
     /// Return value for apply_predicate, if desired
     predicate_match: Option<PredicateMatch>,
 
@@ -235,6 +265,15 @@ pub struct TestChunk {
 
     /// Order of this chunk relative to other overlapping chunks.
     order: ChunkOrder,
+
+    /// Set if the chunk does not provide a table summary
+    no_summary: bool,
+
+    /// Return value for sequence_numbers()
+    sequence_numbers: Option<SequenceNumberRange>,
+
+    /// Partition key returned in addr()
+    partition_key: Arc<str>,
 }
 
 /// Implements a method for adding a column with default stats
@@ -307,9 +346,13 @@ impl TestChunk {
             predicates: Default::default(),
             table_data: Default::default(),
             saved_error: Default::default(),
+            unsupported_predicate_column: None,
             predicate_match: Default::default(),
             delete_predicates: Default::default(),
             order: ChunkOrder::MIN,
+            no_summary: false,
+            sequence_numbers: None,
+            partition_key: Arc::from("TestChunkPartitionKey"),
         }
     }
 
@@ -318,6 +361,36 @@ impl TestChunk {
         self
     }
 
+    /// Set the [`ChunkOrder`] of this chunk
+    pub fn with_order(mut self, order: u64) -> Self {
+        self.order = ChunkOrder::new(order).expect("non-zero chunk order");
+        self
+    }
+
+    /// Model a chunk that cannot provide a table summary
+    pub fn with_no_summary(mut self) -> Self {
+        self.no_summary = true;
+        self
+    }
+
+    /// Set the range of sequence numbers of the writes in this chunk
+    pub fn with_sequence_numbers(mut self, sequence_numbers: SequenceNumberRange) -> Self {
+        self.sequence_numbers = Some(sequence_numbers);
+        self
+    }
+
+    /// Set the partition key of this chunk
+    pub fn with_partition_key(mut self, partition_key: impl Into<Arc<str>>) -> Self {
+        self.partition_key = partition_key.into();
+        self
+    }
+
+    /// Add a delete predicate to this chunk
+    pub fn with_delete_predicate(mut self, predicate: DeletePredicate) -> Self {
+        self.delete_predicates.push(Arc::new(predicate));
+        self
+    }
+
     /// specify that any call should result in an error with the message
     /// specified
     pub fn with_error(mut self, error_message: impl Into<String>) -> Self {
@@ -325,6 +398,13 @@ impl TestChunk {
         self
     }
 
+    /// specify that read_filter fails with an [`UnsupportedPredicate`] if
+    /// any expression of the predicate references `column`
+    pub fn with_unsupported_predicate_column(mut self, column: impl Into<String>) -> Self {
+        self.unsupported_predicate_column = Some(column.into());
+        self
+    }
+
     /// specify that any call to apply_predicate should return this value
     pub fn with_predicate_match(mut self, predicate_match: PredicateMatch) -> Self {
         self.predicate_match = Some(predicate_match);
@@ -340,6 +420,29 @@ impl TestChunk {
         }
     }
 
+    /// Fails if `predicate` references the column set with
+    /// [`Self::with_unsupported_predicate_column`]
+    fn check_predicate_supported(&self, predicate: &Predicate) -> Result<()> {
+        let column = match &self.unsupported_predicate_column {
+            Some(column) => column,
+            None => return Ok(()),
+        };
+
+        for expr in &predicate.exprs {
+            let mut columns = std::collections::HashSet::new();
+            expr_to_columns(expr, &mut columns).expect("collecting columns");
+            if columns.iter().any(|c| &c.name == column) {
+                return Err(TestError::Unsupported {
+                    source: UnsupportedPredicate {
+                        clause: expr.to_string(),
+                        reason: format!("column {} can not be filtered", column),
+                    },
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Set the `may_contain_pk_duplicates` flag
     pub fn with_may_contain_pk_duplicates(mut self, v: bool) -> Self {
         self.may_contain_pk_duplicates = v;
@@ -458,6 +561,30 @@ impl TestChunk {
     impl_with_column_no_stats!(with_i64_field_column_no_stats, Int64);
     impl_with_column_with_stats!(with_i64_field_column_with_stats, Int64, i64, I64);
 
+    /// Register an i64 field column with the test chunk, with stats of
+    /// `count` values of which `null_count` are null
+    pub fn with_i64_field_column_with_counts(
+        self,
+        column_name: impl Into<String>,
+        count: u64,
+        null_count: u64,
+    ) -> Self {
+        let column_name = column_name.into();
+
+        let new_column_schema = SchemaBuilder::new()
+            .field(&column_name, DataType::Int64)
+            .build()
+            .unwrap();
+
+        let stats = Statistics::I64(StatValues {
+            total_count: count,
+            null_count,
+            ..Default::default()
+        });
+
+        self.add_schema_to_table(new_column_schema, true, Some(stats))
+    }
+
     impl_with_column!(with_u64_column, UInt64);
     impl_with_column_no_stats!(with_u64_field_column_no_stats, UInt64);
     impl_with_column_with_stats!(with_u64_field_column_with_stats, UInt64, u64, U64);
@@ -556,13 +683,20 @@ impl TestChunk {
 
     /// Prepares this chunk to return a specific record batch with one
     /// row of non null data.
-    pub fn with_one_row_of_data(mut self) -> Self {
+    pub fn with_one_row_of_data(self) -> Self {
+        self.with_one_row_of_data_with_int_value(1000)
+    }
+
+    /// Prepares this chunk to return a record batch with the same single row
+    /// as [`Self::with_one_row_of_data`], except with `value` in the Int64
+    /// columns.
+    pub fn with_one_row_of_data_with_int_value(mut self, value: i64) -> Self {
         // create arrays
         let columns = self
             .schema
             .iter()
             .map(|(_influxdb_column_type, field)| match field.data_type() {
-                DataType::Int64 => Arc::new(Int64Array::from(vec![1000])) as ArrayRef,
+                DataType::Int64 => Arc::new(Int64Array::from(vec![value])) as ArrayRef,
                 DataType::Utf8 => Arc::new(StringArray::from(vec!["MA"])) as ArrayRef,
                 DataType::Timestamp(TimeUnit::Nanosecond, _) => {
                     Arc::new(TimestampNanosecondArray::from_vec(vec![1000], None)) as ArrayRef
@@ -857,6 +991,19 @@ impl TestChunk {
         self
     }
 
+    /// Prepares this chunk to return `batch`, which must have the same
+    /// schema as this chunk.
+    pub fn with_record_batch(mut self, batch: RecordBatch) -> Self {
+        assert_eq!(
+            batch.schema(),
+            self.schema.as_arrow(),
+            "record batch schema does not match the chunk schema"
+        );
+
+        self.table_data.push(Arc::new(batch));
+        self
+    }
+
     /// Set the sort key for this chunk
     pub fn with_sort_key(mut self, sort_key: &SortKey<'_>) -> Self {
         let mut merger = SchemaMerger::new();
@@ -902,7 +1049,7 @@ impl QueryChunk for TestChunk {
         ChunkAddr {
             db_name: Arc::from("TestChunkDb"),
             table_name: Arc::from(self.table_name.as_str()),
-            partition_key: Arc::from("TestChunkPartitionKey"),
+            partition_key: Arc::clone(&self.partition_key),
             chunk_id: self.id,
         }
     }
@@ -921,6 +1068,7 @@ impl QueryChunk for TestChunk {
         _selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, Self::Error> {
         self.check_error()?;
+        self.check_predicate_supported(predicate)?;
 
         // save the predicate
         self.predicates.lock().push(predicate.clone());
@@ -980,6 +1128,11 @@ impl QueryChunk for TestChunk {
         let column_names = match selection {
             Selection::All => self.all_column_names(),
             Selection::Some(cols) => self.specific_column_names_selection(cols),
+            Selection::AllExcept(_) => self
+                .all_column_names()
+                .into_iter()
+                .filter(|col| selection.contains(col))
+                .collect(),
         };
 
         Ok(Some(column_names))
@@ -988,11 +1141,32 @@ impl QueryChunk for TestChunk {
     fn order(&self) -> ChunkOrder {
         self.order
     }
+
+    fn sequence_numbers(&self) -> Option<SequenceNumberRange> {
+        self.sequence_numbers.clone()
+    }
+
+    /// Models a chunk that only tracks the sequence numbers of all of its
+    /// data as a whole, so data is only excluded if the whole chunk is
+    fn exclude_sequence_numbers(
+        self: &Arc<Self>,
+        exclude: &SequenceNumberRange,
+    ) -> Option<Arc<Self>> {
+        match &self.sequence_numbers {
+            Some(range)
+                if exclude.contains(range.sequencer_id(), range.start())
+                    && exclude.contains(range.sequencer_id(), range.end()) =>
+            {
+                None
+            }
+            _ => Some(Arc::clone(self)),
+        }
+    }
 }
 
 impl QueryChunkMeta for TestChunk {
     fn summary(&self) -> Option<&TableSummary> {
-        Some(&self.table_summary)
+        (!self.no_summary).then(|| &self.table_summary)
     }
 
     fn schema(&self) -> Arc<Schema> {
@@ -1006,6 +1180,17 @@ impl QueryChunkMeta for TestChunk {
 
         pred
     }
+
+    fn size_bytes(&self) -> usize {
+        let data: usize = self
+            .table_data
+            .iter()
+            .flat_map(|batch| batch.columns())
+            .map(|array| array.get_array_memory_size())
+            .sum();
+
+        std::mem::size_of::<Self>() + data + self.table_summary.size()
+    }
 }
 
 /// Return the raw data from the list of chunks
@@ -7,15 +7,17 @@ use arrow::{compute::SortOptions, datatypes::Schema as ArrowSchema, record_batch
 use datafusion::{
     error::DataFusionError,
     execution::context::ExecutionProps,
-    logical_plan::{DFSchema, Expr, LogicalPlan, LogicalPlanBuilder},
+    logical_plan::{DFSchema, Expr, LogicalPlan, LogicalPlanBuilder, Operator},
     physical_plan::{
         expressions::{col as physical_col, PhysicalSortExpr},
         planner::create_physical_expr,
         ExecutionPlan, PhysicalExpr,
     },
+    scalar::ScalarValue,
 };
 use observability_deps::tracing::trace;
-use schema::sort::SortKey;
+use predicate::predicate::Predicate;
+use schema::{sort::SortKey, TIME_COLUMN_NAME};
 
 /// Create a logical plan that produces the record batch
 pub fn make_scan_plan(batch: RecordBatch) -> std::result::Result<LogicalPlan, DataFusionError> {
@@ -85,3 +87,147 @@ pub fn df_physical_expr(
         &execution_props,
     )
 }
+
+/// Render `predicate` as a compact, SQL-like string suitable for logging,
+/// such as `time IN [100, 210) AND city = 'LA'`.
+///
+/// All restrictions in the predicate are AND'ed together. The time range is
+/// rendered as a half-open interval, as the end of the range is exclusive.
+/// A predicate without any restrictions is rendered as `true`.
+pub fn predicate_to_display(predicate: &Predicate) -> String {
+    let mut parts = vec![];
+
+    if let Some(range) = &predicate.range {
+        parts.push(format!(
+            "{} IN [{}, {})",
+            TIME_COLUMN_NAME,
+            range.start(),
+            range.end()
+        ));
+    }
+
+    if let Some(partition_key) = &predicate.partition_key {
+        parts.push(format!("partition_key = {}", quote(partition_key)));
+    }
+
+    if let Some(field_columns) = &predicate.field_columns {
+        let fields = field_columns
+            .iter()
+            .map(|f| quote(f))
+            .collect::<Vec<_>>()
+            .join(", ");
+        parts.push(format!("_field IN ({})", fields));
+    }
+
+    // Each expression is a term of the top level conjunction
+    parts.extend(
+        predicate
+            .exprs
+            .iter()
+            .map(|e| operand_to_display(Some(Operator::And), e)),
+    );
+
+    parts.extend(predicate.value_expr.iter().map(|v| {
+        format!(
+            "{} {} {}",
+            v.left.name,
+            v.op,
+            operand_to_display(Some(v.op), &v.right)
+        )
+    }));
+
+    if parts.is_empty() {
+        return "true".to_string();
+    }
+
+    parts.join(" AND ")
+}
+
+fn expr_to_display(expr: &Expr) -> String {
+    match expr {
+        Expr::Column(c) => c.name.clone(),
+        Expr::Literal(ScalarValue::Utf8(Some(v))) => quote(v),
+        Expr::Literal(v) => v.to_string(),
+        Expr::BinaryExpr { left, op, right } => format!(
+            "{} {} {}",
+            operand_to_display(Some(*op), left),
+            op,
+            operand_to_display(Some(*op), right)
+        ),
+        Expr::Not(e) => format!("NOT {}", operand_to_display(None, e)),
+        Expr::IsNull(e) => format!("{} IS NULL", operand_to_display(None, e)),
+        Expr::IsNotNull(e) => format!("{} IS NOT NULL", operand_to_display(None, e)),
+        // Fall back to the DataFusion rendering for anything else
+        e => e.to_string(),
+    }
+}
+
+/// Render `expr` as an operand of the `parent` operator (or of a unary
+/// expression if `None`), parenthesising it where the result would
+/// otherwise be ambiguous.
+fn operand_to_display(parent: Option<Operator>, expr: &Expr) -> String {
+    let is_logical = |op: &Operator| matches!(op, Operator::And | Operator::Or);
+
+    let needs_parens = match expr {
+        Expr::BinaryExpr { op, .. } => match parent {
+            // Comparisons bind tighter than AND / OR, and chains of the same
+            // logical operator are associative.
+            Some(parent) if is_logical(&parent) => is_logical(op) && *op != parent,
+            _ => true,
+        },
+        _ => false,
+    };
+
+    if needs_parens {
+        format!("({})", expr_to_display(expr))
+    } else {
+        expr_to_display(expr)
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::logical_plan::{col, lit};
+    use predicate::predicate::PredicateBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_predicate_to_display() {
+        // The predicate from the read_group_data_pred influxrpc test
+        let predicate = PredicateBuilder::default()
+            .add_expr(col("city").eq(lit("LA")))
+            .timestamp_range(190, 210)
+            .build();
+        assert_eq!(
+            predicate_to_display(&predicate),
+            "time IN [190, 210) AND city = 'LA'"
+        );
+    }
+
+    #[test]
+    fn test_predicate_to_display_nested() {
+        let predicate = PredicateBuilder::default()
+            .field_columns(vec!["temp", "other"])
+            .add_expr(
+                col("state")
+                    .eq(lit("MA"))
+                    .or(col("city").not_eq(lit("O'Hare"))),
+            )
+            .add_expr(col("host").is_null())
+            .build();
+        assert_eq!(
+            predicate_to_display(&predicate),
+            "_field IN ('other', 'temp') AND (state = 'MA' OR city != 'O''Hare') AND host IS NULL"
+        );
+    }
+
+    #[test]
+    fn test_empty_predicate_to_display() {
+        assert_eq!(predicate_to_display(&Predicate::default()), "true");
+    }
+}
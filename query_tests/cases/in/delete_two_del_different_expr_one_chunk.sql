@@ -0,0 +1,9 @@
+-- Demonstrate rows matching any of several deletes with different expressions are not returned
+-- IOX_SETUP: TwoDeletesDifferentExprsOneChunk
+
+-- select *
+SELECT * from cpu order by time;
+
+SELECT count(*) from cpu;
+
+SELECT * from cpu where foo is null;
@@ -102,6 +102,20 @@ async fn test_cases_delete_three_delete_three_chunks_sql() {
         .expect("flush worked");
 }
 
+#[tokio::test]
+// Tests from "delete_two_del_different_expr_one_chunk.sql",
+async fn test_cases_delete_two_del_different_expr_one_chunk_sql() {
+    let input_path = Path::new("cases").join("in").join("delete_two_del_different_expr_one_chunk.sql");
+    let mut runner = Runner::new();
+    runner
+        .run(input_path)
+        .await
+        .expect("test failed");
+    runner
+        .flush()
+        .expect("flush worked");
+}
+
 #[tokio::test]
 // Tests from "delete_two_del_multi_expr_one_chunk.sql",
 async fn test_cases_delete_two_del_multi_expr_one_chunk_sql() {
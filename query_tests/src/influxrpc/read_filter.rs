@@ -2,7 +2,7 @@
 #[cfg(test)]
 use crate::scenarios::{
     DbScenario, DbSetup, NoData, TwoMeasurements, TwoMeasurementsManyFields,
-    TwoMeasurementsWithDelete, TwoMeasurementsWithDeleteAll,
+    TwoMeasurementsUnsignedType, TwoMeasurementsWithDelete, TwoMeasurementsWithDeleteAll,
 };
 use crate::{
     influxrpc::util::run_series_set_plan,
@@ -12,10 +12,23 @@ use crate::{
         TwoMeasurementsMultiSeriesWithDelete, TwoMeasurementsMultiSeriesWithDeleteAll,
     },
 };
-use datafusion::logical_plan::{col, lit};
+use data_types::database_rules::LifecycleRules;
+use datafusion::{
+    error::DataFusionError,
+    logical_plan::{col, lit},
+};
+use db::{test_helpers::write_lp, utils::TestDb};
 use predicate::predicate::PredicateBuilder;
 use predicate::rpc_predicate::InfluxRpcPredicate;
-use query::frontend::influxrpc::InfluxRpcPlanner;
+use query::{
+    exec::{progress::QueryProgressReporter, seriesset::Error as SeriesSetError, ExecutorType},
+    frontend::influxrpc::InfluxRpcPlanner,
+};
+use std::{
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+};
+use time::{MockProvider, Time};
 
 /// runs read_filter(predicate) and compares it to the expected
 /// output
@@ -640,6 +653,46 @@ async fn test_read_filter_on_field() {
     run_read_filter_test_case(TwoMeasurementsManyFields {}, predicate, expected_results).await;
 }
 
+#[tokio::test]
+async fn test_read_filter_on_field_not_eq() {
+    test_helpers::maybe_start_logging();
+
+    // Predicate should pick all fields except 'temp'
+    // (_field != 'temp')
+    let p1 = col("_field").not_eq(lit("temp"));
+    let predicate = PredicateBuilder::default().add_expr(p1).build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let expected_results = vec![
+        "Series tags={_measurement=h2o, city=Boston, state=CA, _field=other_temp}\n  FloatPoints timestamps: [350], values: [72.4]",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=moisture}\n  FloatPoints timestamps: [100000], values: [43.0]",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=other_temp}\n  FloatPoints timestamps: [250], values: [70.4]",
+        "Series tags={_measurement=o2, city=Boston, state=MA, _field=reading}\n  FloatPoints timestamps: [50], values: [51.0]",
+    ];
+
+    run_read_filter_test_case(TwoMeasurementsManyFields {}, predicate, expected_results).await;
+}
+
+#[tokio::test]
+async fn test_read_filter_on_field_and_tag() {
+    test_helpers::maybe_start_logging();
+
+    // Predicate should pick 'temp' field where state is MA
+    // (_field = 'temp' AND state = 'MA')
+    let p1 = col("_field")
+        .eq(lit("temp"))
+        .and(col("state").eq(lit("MA")));
+    let predicate = PredicateBuilder::default().add_expr(p1).build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let expected_results = vec![
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp}\n  FloatPoints timestamps: [50, 100000], values: [70.4, 70.4]",
+        "Series tags={_measurement=o2, city=Boston, state=MA, _field=temp}\n  FloatPoints timestamps: [50], values: [53.4]",
+    ];
+
+    run_read_filter_test_case(TwoMeasurementsManyFields {}, predicate, expected_results).await;
+}
+
 #[tokio::test]
 async fn test_read_filter_on_field_single_measurement() {
     test_helpers::maybe_start_logging();
@@ -688,3 +741,183 @@ async fn test_read_filter_on_field_multi_measurement() {
 
     run_read_filter_test_case(TwoMeasurementsManyFields {}, predicate, expected_results).await;
 }
+
+#[tokio::test]
+async fn test_read_filter_unsigned() {
+    let expected_results = vec![
+        "Series tags={_measurement=restaurant, town=andover, _field=count}\n  UnsignedPoints timestamps: [100], values: [40000]",
+        "Series tags={_measurement=restaurant, town=reading, _field=count}\n  UnsignedPoints timestamps: [120], values: [632]",
+        "Series tags={_measurement=school, town=andover, _field=count}\n  UnsignedPoints timestamps: [160], values: [25]",
+        "Series tags={_measurement=school, town=reading, _field=count}\n  UnsignedPoints timestamps: [150], values: [17]",
+    ];
+
+    run_read_filter_test_case(
+        TwoMeasurementsUnsignedType {},
+        InfluxRpcPredicate::default(),
+        expected_results,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_read_filter_too_many_series() {
+    test_helpers::maybe_start_logging();
+
+    // TwoMeasurementsMultiSeries produces 4 series, so both a server maximum
+    // and a lower per-request limit below that must fail, while a request
+    // cannot raise the limit above the server maximum.
+    let planners = [
+        (InfluxRpcPlanner::new().with_max_series(3), 3),
+        (
+            InfluxRpcPlanner::new()
+                .with_max_series(10)
+                .with_request_max_series(2),
+            2,
+        ),
+        (
+            InfluxRpcPlanner::new()
+                .with_max_series(3)
+                .with_request_max_series(10),
+            3,
+        ),
+    ];
+
+    let db_setup = TwoMeasurementsMultiSeries {};
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+
+        for (planner, expected_max_series) in &planners {
+            let plan = planner
+                .read_filter(db.as_ref(), InfluxRpcPredicate::default())
+                .expect("built plan successfully");
+
+            let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+            let err = ctx
+                .to_series_and_groups(plan)
+                .await
+                .expect_err("query should exceed the series limit");
+
+            let err = match &err {
+                DataFusionError::External(e) => e.downcast_ref::<SeriesSetError>(),
+                _ => None,
+            };
+            match err {
+                Some(SeriesSetError::TooManySeries {
+                    max_series,
+                    num_series,
+                }) => {
+                    assert_eq!(max_series, expected_max_series);
+                    assert!(num_series > max_series);
+                }
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        // Within the limit the query succeeds
+        let planner = InfluxRpcPlanner::new().with_max_series(4);
+        let plan = planner
+            .read_filter(db.as_ref(), InfluxRpcPredicate::default())
+            .expect("built plan successfully");
+        let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+        assert_eq!(run_series_set_plan(&ctx, plan).await.len(), 4);
+    }
+}
+
+#[tokio::test]
+async fn test_read_filter_disable_pruning() {
+    test_helpers::maybe_start_logging();
+
+    let predicate = PredicateBuilder::new()
+        .timestamp_range(900, 1200)
+        .add_expr(col("state").eq(lit("MA")))
+        .build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let expected_results = vec![
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp}\n  FloatPoints timestamps: [1000], values: [72.4]",
+    ];
+
+    // The chunk of the first write does not overlap the time range of the
+    // predicate and is only read if pruning is disabled
+    for (disable_pruning, expected_chunks) in [(false, 1), (true, 2)] {
+        let db = TestDb::builder()
+            .disable_pruning(disable_pruning)
+            .build()
+            .await
+            .db;
+        write_lp(
+            &db,
+            "h2o,state=MA,city=Boston temp=70.4 100\nh2o,state=CA,city=LA temp=90.0 200",
+        );
+        db.rollover_partition("h2o", "1970-01-01T00").await.unwrap();
+        write_lp(
+            &db,
+            "h2o,state=MA,city=Boston temp=72.4 1000\nh2o,state=CA,city=LA temp=91.0 1100",
+        );
+
+        let planner = InfluxRpcPlanner::new().with_disable_pruning(disable_pruning);
+        let plan = planner
+            .read_filter(db.as_ref(), predicate.clone())
+            .expect("built plan successfully");
+
+        let (reporter, _receiver) = QueryProgressReporter::new();
+        let ctx = db
+            .executor()
+            .new_execution_config(ExecutorType::Query)
+            .with_progress_reporter(reporter.clone())
+            .build();
+        let string_results = run_series_set_plan(&ctx, plan).await;
+
+        assert_eq!(
+            expected_results, string_results,
+            "Error with disable_pruning={}",
+            disable_pruning
+        );
+        assert_eq!(
+            reporter.chunks_planned(),
+            expected_chunks,
+            "Error with disable_pruning={}",
+            disable_pruning
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_read_filter_retention() {
+    test_helpers::maybe_start_logging();
+
+    // Data older than one second before "now" has expired
+    let now = Time::from_timestamp_nanos(1_500_000_000);
+    let db = TestDb::builder()
+        .lifecycle_rules(LifecycleRules {
+            late_arrive_window_seconds: NonZeroU32::new(1).unwrap(),
+            retention_period_seconds: NonZeroU64::new(1),
+            ..Default::default()
+        })
+        .time_provider(Arc::new(MockProvider::new(now)))
+        .build()
+        .await
+        .db;
+    write_lp(
+        &db,
+        "h2o,state=MA,city=Boston temp=70.4 100\nh2o,state=MA,city=Boston temp=72.4 1000000000",
+    );
+
+    // No time predicate is provided
+    let plan = InfluxRpcPlanner::new()
+        .read_filter(db.as_ref(), InfluxRpcPredicate::default())
+        .expect("built plan successfully");
+    let ctx = db
+        .executor()
+        .new_execution_config(ExecutorType::Query)
+        .build();
+    let string_results = run_series_set_plan(&ctx, plan).await;
+
+    let expected_results = vec![
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp}\n  FloatPoints timestamps: [1000000000], values: [72.4]",
+    ];
+    assert_eq!(expected_results, string_results);
+}
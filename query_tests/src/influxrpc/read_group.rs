@@ -3,7 +3,8 @@ use crate::{
     influxrpc::util::run_series_set_plan,
     scenarios::{
         util::{all_scenarios_for_one_chunk, make_two_chunk_scenarios},
-        DbScenario, DbSetup, NoData, TwoMeasurementsManyFields, TwoMeasurementsManyFieldsOneChunk,
+        DbScenario, DbSetup, NoData, TwoMeasurementForAggs, TwoMeasurementsManyFields,
+        TwoMeasurementsManyFieldsOneChunk,
     },
 };
 
@@ -231,6 +232,38 @@ async fn test_read_group_data_no_tag_columns_count_with_delete_all() {
     .await;
 }
 
+#[tokio::test]
+async fn test_read_group_data_no_tag_columns_with_delete_all_prunes_chunk() {
+    test_helpers::maybe_start_logging();
+
+    let group_columns: Vec<&str> = vec![];
+    let scenarios = OneMeasurementNoTagsWithDeleteAllWithAndWithoutChunk {}
+        .make()
+        .await;
+    for scenario in scenarios {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+
+        // The chunk is pruned as all of its data is deleted, so there is
+        // nothing left to plan
+        let plans = InfluxRpcPlanner::new()
+            .read_group(
+                db.as_ref(),
+                InfluxRpcPredicate::default(),
+                Aggregate::Count,
+                &group_columns,
+            )
+            .expect("built plan successfully");
+        assert!(
+            plans.plans.is_empty(),
+            "fully deleted chunk was not pruned in scenario '{}'",
+            scenario_name
+        );
+    }
+}
+
 #[tokio::test]
 async fn test_read_group_data_no_tag_columns_min_with_delete_all() {
     let agg = Aggregate::Min;
@@ -377,6 +410,191 @@ async fn test_grouped_series_set_plan_sum() {
     .await;
 }
 
+#[tokio::test]
+async fn test_grouped_series_set_plan_sum_is_not_null() {
+    // humidity IS NOT NULL (filters out all MA rows)
+    let predicate = PredicateBuilder::default().is_not_null("humidity").build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let agg = Aggregate::Sum;
+    let group_columns = vec!["state"];
+
+    let expected_results = vec![
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: CA",
+        "Series tags={_measurement=h2o, city=LA, state=CA, _field=humidity}\n  FloatPoints timestamps: [600], values: [21.0]",
+        "Series tags={_measurement=h2o, city=LA, state=CA, _field=temp}\n  FloatPoints timestamps: [600], values: [181.0]",
+    ];
+
+    run_read_group_test_case(
+        AnotherMeasurementForAggs {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_sum_include_empty_series() {
+    test_helpers::maybe_start_logging();
+
+    let predicate = PredicateBuilder::default()
+        // city=Boston OR city=Cambridge (filters out LA rows)
+        .add_expr(
+            col("city")
+                .eq(lit("Boston"))
+                .or(col("city").eq(lit("Cambridge"))),
+        )
+        // fiter out first Cambridge row
+        .timestamp_range(100, 1000)
+        .build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    // Same as test_grouped_series_set_plan_sum, but the humidity field (null
+    // for all rows after predicates) is sent as series without points
+    let expected_results = vec![
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: MA",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=humidity}\n  FloatPoints timestamps: [], values: []",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp}\n  FloatPoints timestamps: [400], values: [141.0]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=humidity}\n  FloatPoints timestamps: [], values: []",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=temp}\n  FloatPoints timestamps: [200], values: [163.0]",
+    ];
+
+    let db_setup = AnotherMeasurementForAggs {};
+    let group_columns = vec!["state"];
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+        let planner = InfluxRpcPlanner::new().with_include_empty_series(true);
+        let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+
+        let plans = planner
+            .read_group(
+                db.as_ref(),
+                predicate.clone(),
+                Aggregate::Sum,
+                &group_columns,
+            )
+            .expect("built plan successfully");
+
+        let string_results = run_series_set_plan(&ctx, plans).await;
+
+        assert_eq!(
+            expected_results, string_results,
+            "Error in  scenario '{}'\n\nexpected:\n\n{:#?}\nactual:\n\n{:#?}",
+            scenario_name, expected_results, string_results
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_multiple_aggregates() {
+    test_helpers::maybe_start_logging();
+
+    let predicate = PredicateBuilder::default()
+        // city=Boston OR city=Cambridge (filters out LA rows)
+        .add_expr(
+            col("city")
+                .eq(lit("Boston"))
+                .or(col("city").eq(lit("Cambridge"))),
+        )
+        // fiter out first Cambridge row
+        .timestamp_range(100, 1000)
+        .build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    // Each aggregate is reported as its own field
+    let expected_results = vec![
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: MA",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp_max}\n  FloatPoints timestamps: [400], values: [71.0]",
+        "Series tags={_measurement=h2o, city=Boston, state=MA, _field=temp_mean}\n  FloatPoints timestamps: [400], values: [70.5]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=temp_max}\n  FloatPoints timestamps: [200], values: [82.0]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=temp_mean}\n  FloatPoints timestamps: [200], values: [81.5]",
+    ];
+
+    let db_setup = AnotherMeasurementForAggs {};
+    let group_columns = vec!["state"];
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+        let planner = InfluxRpcPlanner::new();
+        let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+
+        let plans = planner
+            .read_group_aggregates(
+                db.as_ref(),
+                predicate.clone(),
+                &[Aggregate::Mean, Aggregate::Max],
+                &group_columns,
+            )
+            .expect("built plan successfully");
+
+        let string_results = run_series_set_plan(&ctx, plans).await;
+
+        assert_eq!(
+            expected_results, string_results,
+            "Error in  scenario '{}'\n\nexpected:\n\n{:#?}\nactual:\n\n{:#?}",
+            scenario_name, expected_results, string_results
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_explain_analyze() {
+    test_helpers::maybe_start_logging();
+
+    let db_setup = AnotherMeasurementForAggs {};
+    let group_columns = vec!["state"];
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+        let planner = InfluxRpcPlanner::new();
+        let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+
+        let plans = planner
+            .read_group(
+                db.as_ref(),
+                InfluxRpcPredicate::default(),
+                Aggregate::Sum,
+                &group_columns,
+            )
+            .expect("built plan successfully");
+
+        let output = ctx
+            .explain_analyze_series_set_plans(plans)
+            .await
+            .expect("ran plans successfully");
+
+        assert!(output.starts_with("SeriesSetPlan: table_name=h2o\n"));
+
+        // Each stage of the plan reports the rows it produced
+        for operator in ["IOxReadFilterNode", "AggregateExec", "SortExec"] {
+            let output_rows = output
+                .lines()
+                .filter(|l| l.trim_start().starts_with(operator))
+                .map(|l| {
+                    let (_, rows) = l
+                        .split_once("output_rows=")
+                        .unwrap_or_else(|| panic!("no output_rows metric in: {}", l));
+                    let rows = rows.split(|c: char| !c.is_ascii_digit()).next().unwrap();
+                    rows.parse::<usize>().unwrap()
+                })
+                .max()
+                .unwrap_or_else(|| panic!("no {} in scenario '{}'", operator, scenario_name));
+
+            assert!(output_rows > 0, "{} produced no rows", operator);
+        }
+        assert!(output.contains("elapsed_compute="));
+    }
+}
+
 #[tokio::test]
 async fn test_grouped_series_set_plan_count() {
     let predicate = PredicateBuilder::default()
@@ -445,25 +663,6 @@ async fn test_grouped_series_set_plan_mean() {
     .await;
 }
 
-struct TwoMeasurementForAggs {}
-#[async_trait]
-impl DbSetup for TwoMeasurementForAggs {
-    async fn make(&self) -> Vec<DbScenario> {
-        let partition_key = "1970-01-01T00";
-
-        let lp_lines1 = vec![
-            "h2o,state=MA,city=Boston temp=70.4 100",
-            "h2o,state=MA,city=Boston temp=72.4 250",
-        ];
-        let lp_lines2 = vec![
-            "o2,state=CA,city=LA temp=90.0 200",
-            "o2,state=CA,city=LA temp=90.0 350",
-        ];
-
-        make_two_chunk_scenarios(partition_key, &lp_lines1.join("\n"), &lp_lines2.join("\n")).await
-    }
-}
-
 // NGA todo: add delete DbSetup after all scenarios are done for 2 chunks
 
 #[tokio::test]
@@ -641,6 +840,84 @@ async fn test_grouped_series_set_plan_last_with_nulls() {
     .await;
 }
 
+struct MeasurementForSelectorsCollidingTimestamps {}
+#[async_trait]
+impl DbSetup for MeasurementForSelectorsCollidingTimestamps {
+    async fn make(&self) -> Vec<DbScenario> {
+        let partition_key = "1970-01-01T00";
+
+        // The second chunk overwrites both the first and the last point of
+        // the series with smaller values, so that the tie-break of the
+        // selectors on the value would pick the first chunk
+        let lp_lines1 = vec![
+            "h2o,state=MA,city=Cambridge f=8.0,i=8i,b=true,s=\"d\" 1000",
+            "h2o,state=MA,city=Cambridge f=6.0,i=6i,b=true,s=\"b\" 3000",
+        ];
+        let lp_lines2 = vec![
+            "h2o,state=MA,city=Cambridge f=7.0,i=7i,b=false,s=\"c\" 1000",
+            "h2o,state=MA,city=Cambridge f=5.0,i=5i,b=false,s=\"a\" 3000",
+        ];
+
+        make_two_chunk_scenarios(partition_key, &lp_lines1.join("\n"), &lp_lines2.join("\n")).await
+    }
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_first_colliding_timestamps() {
+    let predicate = PredicateBuilder::default().build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let agg = Aggregate::First;
+    let group_columns = vec!["state"];
+
+    // the last write (second chunk) wins, as rows of a series are
+    // deduplicated by ChunkOrder before the selector is applied
+    let expected_results = vec![
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: MA",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=b}\n  BooleanPoints timestamps: [1000], values: [false]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=f}\n  FloatPoints timestamps: [1000], values: [7.0]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=i}\n  IntegerPoints timestamps: [1000], values: [7]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=s}\n  StringPoints timestamps: [1000], values: [\"c\"]",
+    ];
+
+    run_read_group_test_case(
+        MeasurementForSelectorsCollidingTimestamps {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_last_colliding_timestamps() {
+    let predicate = PredicateBuilder::default().build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+
+    let agg = Aggregate::Last;
+    let group_columns = vec!["state"];
+
+    // the last write (second chunk) wins, as rows of a series are
+    // deduplicated by ChunkOrder before the selector is applied
+    let expected_results = vec![
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: MA",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=b}\n  BooleanPoints timestamps: [3000], values: [false]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=f}\n  FloatPoints timestamps: [3000], values: [5.0]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=i}\n  IntegerPoints timestamps: [3000], values: [5]",
+        "Series tags={_measurement=h2o, city=Cambridge, state=MA, _field=s}\n  StringPoints timestamps: [3000], values: [\"a\"]",
+    ];
+
+    run_read_group_test_case(
+        MeasurementForSelectorsCollidingTimestamps {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results,
+    )
+    .await;
+}
+
 struct MeasurementForMin {}
 #[async_trait]
 impl DbSetup for MeasurementForMin {
@@ -892,6 +1169,34 @@ async fn test_grouped_series_set_plan_group_by_field_none() {
     .await;
 }
 
+#[tokio::test]
+async fn test_grouped_series_set_plan_group_by_field_compare_fields() {
+    // Compare two field columns, which can not be pruned using statistics
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("load1").gt(col("load2")))
+        .build();
+    let predicate = InfluxRpcPredicate::new(None, predicate);
+    let agg = Aggregate::None;
+    let group_columns = vec!["_field"];
+
+    // Only the row at time 100 for host=remote has load1 > load2
+    let expected_results = vec![
+        "Group tag_keys: _measurement, host, region, _field partition_key_vals: load1",
+        "Series tags={_measurement=system, host=remote, region=B, _field=load1}\n  FloatPoints timestamps: [100], values: [10.1]",
+        "Group tag_keys: _measurement, host, region, _field partition_key_vals: load2",
+        "Series tags={_measurement=system, host=remote, region=B, _field=load2}\n  FloatPoints timestamps: [100], values: [2.1]",
+    ];
+
+    run_read_group_test_case(
+        MeasurementForGroupByField {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results,
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_grouped_series_set_plan_group_by_field_and_tag_none() {
     let agg = Aggregate::None;
@@ -1031,6 +1336,34 @@ async fn test_grouped_series_set_plan_group_field_start_stop() {
     .await;
 }
 
+#[tokio::test]
+async fn test_grouped_series_set_plan_group_by_missing_tag() {
+    let predicate = InfluxRpcPredicate::new_table("o2", Default::default());
+
+    let agg = Aggregate::Count;
+    let group_columns = vec!["city"];
+
+    // The series without a city are grouped together with a blank
+    // partition value, which sorts before all other values
+    let expected_results = vec![
+        "Group tag_keys: _measurement, state, _field partition_key_vals: ",
+        "Series tags={_measurement=o2, state=CA, _field=reading}\n  IntegerPoints timestamps: [300], values: [0]",
+        "Series tags={_measurement=o2, state=CA, _field=temp}\n  IntegerPoints timestamps: [300], values: [1]",
+        "Group tag_keys: _measurement, city, state, _field partition_key_vals: Boston",
+        "Series tags={_measurement=o2, city=Boston, state=MA, _field=reading}\n  IntegerPoints timestamps: [50], values: [1]",
+        "Series tags={_measurement=o2, city=Boston, state=MA, _field=temp}\n  IntegerPoints timestamps: [50], values: [1]",
+    ];
+
+    run_read_group_test_case(
+        TwoMeasurementsManyFieldsOneChunk {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results,
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_grouped_series_set_plan_group_field_pred_and_null_fields() {
     let predicate = InfluxRpcPredicate::new_table("o2", Default::default());
@@ -332,6 +332,41 @@ async fn list_tag_values_field_col() {
     }
 }
 
+#[tokio::test]
+async fn list_tag_values_by_table() {
+    test_helpers::maybe_start_logging();
+
+    // The state tag is MA for all h2o rows, and CA for all o2 rows
+    let db_setup = TwoMeasurementForAggs {};
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+        let planner = InfluxRpcPlanner::new();
+        let ctx = db.executor().new_context(query::exec::ExecutorType::Query);
+
+        let plans = planner
+            .tag_values_by_table(db.as_ref(), "state", InfluxRpcPredicate::default())
+            .expect("built plan successfully");
+
+        let mut values = vec![];
+        for (table_name, plan) in plans {
+            let names = ctx
+                .to_string_set(plan)
+                .await
+                .expect("converted plan to strings successfully");
+            values.push((table_name, names));
+        }
+
+        let expected = vec![
+            ("h2o".to_string(), to_stringset(&["MA"])),
+            ("o2".to_string(), to_stringset(&["CA"])),
+        ];
+        assert_eq!(values, expected, "Error in scenario '{}'", scenario_name);
+    }
+}
+
 fn to_stringset(v: &[&str]) -> StringSetRef {
     v.into_stringset().unwrap()
 }
@@ -280,6 +280,11 @@ impl<W: Write> Runner<W> {
             let executor = Arc::new(Executor::new_with_config(ExecutorConfig {
                 num_threads: 1,
                 target_query_partitions: 4,
+                scan_parallelism: None,
+                slow_query_threshold: None,
+                target_batch_size: None,
+                disable_pruning: false,
+                chunk_read_metrics: false,
             }));
             let ctx = executor
                 .new_execution_config(ExecutorType::Query)
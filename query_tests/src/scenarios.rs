@@ -8,7 +8,7 @@ use async_trait::async_trait;
 use db::Db;
 use delete::{
     OneDeleteMultiExprsOneChunk, OneDeleteSimpleExprOneChunk, OneDeleteSimpleExprOneChunkDeleteAll,
-    ThreeDeleteThreeChunks, TwoDeletesMultiExprsOneChunk,
+    ThreeDeleteThreeChunks, TwoDeletesDifferentExprsOneChunk, TwoDeletesMultiExprsOneChunk,
 };
 use once_cell::sync::OnceCell;
 use std::{collections::HashMap, sync::Arc};
@@ -61,6 +61,7 @@ pub fn get_all_setups() -> &'static HashMap<String, Arc<dyn DbSetup>> {
             register_setup!(OneDeleteSimpleExprOneChunk),
             register_setup!(OneDeleteMultiExprsOneChunk),
             register_setup!(TwoDeletesMultiExprsOneChunk),
+            register_setup!(TwoDeletesDifferentExprsOneChunk),
             register_setup!(OneMeasurementRealisticTimes),
         ]
         .into_iter()
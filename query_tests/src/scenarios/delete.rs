@@ -186,6 +186,57 @@ impl DbSetup for TwoDeletesMultiExprsOneChunk {
     }
 }
 
+#[derive(Debug)]
+/// Setup for two deletes with different expressions on one table and one chunk moved from MUB to
+/// RUB to OS, some rows having nulls in the delete columns
+pub struct TwoDeletesDifferentExprsOneChunk {}
+#[async_trait]
+impl DbSetup for TwoDeletesDifferentExprsOneChunk {
+    async fn make(&self) -> Vec<DbScenario> {
+        let partition_key = "1970-01-01T00";
+        let table_name = "cpu";
+        // chunk data
+        let lp_lines = vec![
+            "cpu,foo=me bar=1 10",  // deleted by pred1
+            "cpu,foo=you bar=2 20", // deleted by pred2
+            "cpu,foo=you bar=3 30",
+            "cpu bar=2 35", // deleted by pred2
+            "cpu bar=3 40", // foo is null, not deleted by pred1
+            "cpu,foo=me bar=1 50",
+        ];
+        // delete predicate
+        // pred1: delete from cpu where 0 <= time < 45 and foo = 'me'
+        let pred1 = DeletePredicate {
+            range: TimestampRange::new(0, 45),
+            exprs: vec![DeleteExpr::new(
+                "foo".to_string(),
+                data_types::delete_predicate::Op::Eq,
+                data_types::delete_predicate::Scalar::String("me".to_string()),
+            )],
+        };
+
+        // pred2: delete from cpu where 0 <= time < 100 and bar = 2
+        let pred2 = DeletePredicate {
+            range: TimestampRange::new(0, 100),
+            exprs: vec![DeleteExpr::new(
+                "bar".to_string(),
+                data_types::delete_predicate::Op::Eq,
+                data_types::delete_predicate::Scalar::F64((2.0).into()),
+            )],
+        };
+
+        // build all possible scenarios
+        all_scenarios_for_one_chunk(
+            vec![&pred1],
+            vec![&pred2],
+            lp_lines,
+            table_name,
+            partition_key,
+        )
+        .await
+    }
+}
+
 // Three different delete on three different chunks
 #[derive(Debug)]
 /// Setup for three different delete on three different chunks
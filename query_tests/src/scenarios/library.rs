@@ -1218,3 +1218,24 @@ impl DbSetup for MeasurementsForDefect2845 {
         all_scenarios_for_one_chunk(vec![], vec![], lp_lines, "system", partition_key).await
     }
 }
+
+/// Two measurements (h2o and o2), each with a single series in its own chunk
+#[derive(Debug)]
+pub struct TwoMeasurementForAggs {}
+#[async_trait]
+impl DbSetup for TwoMeasurementForAggs {
+    async fn make(&self) -> Vec<DbScenario> {
+        let partition_key = "1970-01-01T00";
+
+        let lp_lines1 = vec![
+            "h2o,state=MA,city=Boston temp=70.4 100",
+            "h2o,state=MA,city=Boston temp=72.4 250",
+        ];
+        let lp_lines2 = vec![
+            "o2,state=CA,city=LA temp=90.0 200",
+            "o2,state=CA,city=LA temp=90.0 350",
+        ];
+
+        make_two_chunk_scenarios(partition_key, &lp_lines1.join("\n"), &lp_lines2.join("\n")).await
+    }
+}
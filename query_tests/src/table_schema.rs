@@ -394,3 +394,57 @@ async fn test_set_sort_key_invalid_not_exist() {
         expected_schema, schema
     );
 }
+
+#[tokio::test]
+async fn read_filter_all_except_field() {
+    test_helpers::maybe_start_logging();
+
+    // excluding a column that does not exist is a no-op
+    let selection = Selection::AllExcept(&["other_temp", "does_not_exist"]);
+
+    let db_setup = TwoMeasurementsManyFields {};
+    for scenario in db_setup.make().await {
+        let DbScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+
+        let chunks = db.chunks("h2o", &Default::default());
+        assert!(!chunks.is_empty(), "Expected at least one chunk");
+
+        for chunk in chunks {
+            let expected_columns: Vec<_> = chunk
+                .schema()
+                .iter()
+                .map(|(_, field)| field.name().to_string())
+                .filter(|name| name != "other_temp")
+                .collect();
+
+            let stream = chunk.read_filter(&Default::default(), selection).unwrap();
+            let batches = datafusion::physical_plan::common::collect(stream)
+                .await
+                .unwrap();
+
+            for batch in batches {
+                let schema = batch.schema();
+                let mut actual_columns: Vec<_> = schema
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().to_string())
+                    .collect();
+                let mut expected_columns = expected_columns.clone();
+                actual_columns.sort_unstable();
+                expected_columns.sort_unstable();
+
+                assert_eq!(
+                    expected_columns,
+                    actual_columns,
+                    "Mismatch in chunk {} of scenario '{}'",
+                    chunk.id(),
+                    scenario_name
+                );
+                assert!(schema.field_with_name("other_temp").is_err());
+            }
+        }
+    }
+}
@@ -276,6 +276,9 @@ impl Chunk {
             select_columns: match columns {
                 Selection::All => table_meta.schema_for_all_columns(),
                 Selection::Some(column_names) => table_meta.schema_for_column_names(column_names),
+                Selection::AllExcept(column_names) => {
+                    table_meta.schema_for_all_columns_except(column_names)
+                }
             },
             ..ResultSchema::default()
         })
@@ -336,6 +339,12 @@ impl Chunk {
                 .fail();
             }
             Selection::Some(columns) => columns,
+            Selection::AllExcept(_) => {
+                return UnsupportedOperationSnafu {
+                    msg: "column_values does not support AllExcept columns".to_owned(),
+                }
+                .fail();
+            }
         };
 
         self.table
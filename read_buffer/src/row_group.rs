@@ -1118,6 +1118,13 @@ impl RowGroup {
                             None
                         }
                     }
+                    Selection::AllExcept(names) => {
+                        if names.iter().any(|selection| name == selection) {
+                            None
+                        } else {
+                            Some((name, &self.columns[id]))
+                        }
+                    }
                 },
             })
             .collect::<Vec<_>>();
@@ -313,6 +313,9 @@ impl Table {
             select_columns: match columns {
                 Selection::All => meta.schema_for_all_columns(),
                 Selection::Some(column_names) => meta.schema_for_column_names(column_names),
+                Selection::AllExcept(column_names) => {
+                    meta.schema_for_all_columns_except(column_names)
+                }
             },
             ..ResultSchema::default()
         };
@@ -359,6 +362,9 @@ impl Table {
             group_columns: match group_columns {
                 Selection::All => meta.schema_for_all_columns(),
                 Selection::Some(column_names) => meta.schema_for_column_names(column_names),
+                Selection::AllExcept(column_names) => {
+                    meta.schema_for_all_columns_except(column_names)
+                }
             },
             aggregate_columns: meta.schema_for_aggregate_column_names(aggregates),
             ..ResultSchema::default()
@@ -710,6 +716,22 @@ impl MetaData {
         column_schema
     }
 
+    /// As `schema_for_all_columns` but skipping the named columns. Names of
+    /// columns that do not exist in the table are ignored.
+    pub fn schema_for_all_columns_except(
+        &self,
+        names: &[ColumnName<'_>],
+    ) -> Vec<(ColumnType, LogicalDataType)> {
+        self.column_names
+            .iter()
+            .filter(|column_name| !names.contains(&column_name.as_str()))
+            .map(|column_name| {
+                let schema = self.columns.get(column_name).unwrap();
+                (schema.typ.clone(), schema.logical_data_type)
+            })
+            .collect()
+    }
+
     // As `schema_for_column_names` but also embeds the provided aggregate type.
     fn schema_for_aggregate_column_names(
         &self,
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BatchSize, BenchmarkGroup, Criterion, Throughput,
+};
+use data_types::DatabaseName;
+use hashbrown::HashMap;
+use iox_catalog::{create_or_get_default_records, interface::Catalog, mem::MemCatalog};
+use mutable_batch::MutableBatch;
+use router2::dml_handlers::{nop::NopDmlHandler, DmlHandler, SchemaValidator};
+use router2::namespace_cache::MemoryNamespaceCache;
+use tokio::runtime::Runtime;
+
+const NAMESPACE: &str = "bananas";
+
+/// The write validated in each benchmark.
+const LP: &str = "platanos,tag1=A,tag2=B val=42i,other=4.2 123456";
+
+/// [`LP`] with an additional field column, requiring the cached schema to be
+/// widened.
+const LP_NEW_COLUMN: &str = "platanos,tag1=A,tag2=B val=42i,other=4.2,new=true 123456";
+
+fn lp_to_batches(lp: &str) -> HashMap<String, MutableBatch> {
+    mutable_batch_lp::lines_to_batches(lp, 0).expect("invalid line protocol")
+}
+
+fn namespace() -> DatabaseName<'static> {
+    DatabaseName::new(NAMESPACE).unwrap()
+}
+
+/// Initialise a [`SchemaValidator`] backed by an in-memory catalog containing
+/// the namespace and the schema of [`LP`].
+///
+/// If `cached` is false, the namespace schema is present in the catalog but
+/// not in the validator's namespace cache.
+async fn init_validator(cached: bool) -> SchemaValidator<NopDmlHandler> {
+    let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new());
+    let (kafka_topic, query_pool, _) = create_or_get_default_records(1, &*catalog)
+        .await
+        .expect("failed to create default catalog records");
+    catalog
+        .namespaces()
+        .create(NAMESPACE, Some("inf"), kafka_topic.id, query_pool.id)
+        .await
+        .expect("failed to create namespace");
+
+    let validator = SchemaValidator::new(
+        NopDmlHandler,
+        Arc::clone(&catalog),
+        Arc::new(MemoryNamespaceCache::default()),
+    );
+    validator
+        .write(namespace(), lp_to_batches(LP), None)
+        .await
+        .expect("failed to write initial schema");
+
+    if cached {
+        return validator;
+    }
+
+    SchemaValidator::new(
+        NopDmlHandler,
+        catalog,
+        Arc::new(MemoryNamespaceCache::default()),
+    )
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .build()
+        .unwrap()
+}
+
+fn schema_validator_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("schema_validator");
+    group.throughput(Throughput::Elements(1));
+
+    bench_cache_hit(&mut group);
+    bench_new_column(&mut group);
+    bench_cache_miss(&mut group);
+
+    group.finish();
+}
+
+/// A write matching the cached schema.
+fn bench_cache_hit(group: &mut BenchmarkGroup<WallTime>) {
+    let validator = runtime().block_on(init_validator(true));
+    let validator = &validator;
+    let batches = lp_to_batches(LP);
+
+    group.bench_function("cache hit", |b| {
+        b.to_async(runtime()).iter_batched(
+            || batches.clone(),
+            |batches| async move {
+                validator
+                    .write(namespace(), batches, None)
+                    .await
+                    .expect("write should succeed")
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// A write adding a column to the cached schema.
+///
+/// Each iteration uses a fresh catalog and cache so that the new column is
+/// always created in the catalog.
+fn bench_new_column(group: &mut BenchmarkGroup<WallTime>) {
+    group.bench_function("cache hit with new column", |b| {
+        b.to_async(runtime()).iter_custom(|iters| async move {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let validator = init_validator(true).await;
+                let batches = lp_to_batches(LP_NEW_COLUMN);
+
+                let start = Instant::now();
+                validator
+                    .write(namespace(), batches, None)
+                    .await
+                    .expect("write should succeed");
+                elapsed += start.elapsed();
+            }
+            elapsed
+        })
+    });
+}
+
+/// A write matching the catalog schema, which is not in the namespace cache.
+fn bench_cache_miss(group: &mut BenchmarkGroup<WallTime>) {
+    group.bench_function("cache miss", |b| {
+        b.to_async(runtime()).iter_custom(|iters| async move {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let validator = init_validator(false).await;
+                let batches = lp_to_batches(LP);
+
+                let start = Instant::now();
+                validator
+                    .write(namespace(), batches, None)
+                    .await
+                    .expect("write should succeed");
+                elapsed += start.elapsed();
+            }
+            elapsed
+        })
+    });
+}
+
+criterion_group!(benches, schema_validator_benchmarks);
+criterion_main!(benches);
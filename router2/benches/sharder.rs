@@ -1,11 +1,23 @@
+use std::fmt::Debug;
+use std::hash::Hasher;
+
 use criterion::measurement::WallTime;
 use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion, Throughput};
-use data_types::DatabaseName;
-use router2::sharder::{Sharder, TableNamespaceSharder};
+use data_types::{delete_predicate::DeletePredicate, timestamp::TimestampRange, DatabaseName};
+use router2::sharder::{Fnv1aHasher, Sharder, TableNamespaceSharder};
+use siphasher::sip::{SipHasher13, SipHasher24};
+use twox_hash::XxHash64;
 
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
+fn delete() -> DeletePredicate {
+    DeletePredicate {
+        range: TimestampRange::new(1, 2),
+        exprs: vec![],
+    }
+}
+
 fn get_random_string(length: usize) -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
@@ -75,6 +87,35 @@ fn sharder_benchmarks(c: &mut Criterion) {
     );
 
     group.finish();
+
+    // benchmark the hash functions the sharder can be configured with
+    let mut group = c.benchmark_group("sharder_hasher");
+    let key = [42; 16];
+    benchmark_hasher(&mut group, "siphash 1-3", SipHasher13::new_with_key(&key));
+    benchmark_hasher(&mut group, "siphash 2-4", SipHasher24::new_with_key(&key));
+    benchmark_hasher(&mut group, "fnv 1a", Fnv1aHasher::default());
+    benchmark_hasher(&mut group, "xxhash64", XxHash64::with_seed(42));
+    group.finish();
+}
+
+/// Benchmark the sharder throughput with `hasher`.
+///
+/// The distribution of each hasher is asserted by the sharder tests.
+fn benchmark_hasher<H>(group: &mut BenchmarkGroup<WallTime>, bench_name: &str, hasher: H)
+where
+    H: Hasher + Clone + Debug + Send + Sync,
+{
+    let sharder = TableNamespaceSharder::new(0..10_000).with_hasher(hasher);
+    let table = get_random_string(16);
+    let namespace = DatabaseName::try_from(get_random_string(16)).unwrap();
+    let predicate = delete();
+
+    group.throughput(Throughput::Elements(1));
+    group.bench_function(bench_name, |b| {
+        b.iter(|| {
+            sharder.shard(&table, &namespace, &predicate);
+        });
+    });
 }
 
 fn benchmark_sharder(
@@ -85,11 +126,12 @@ fn benchmark_sharder(
     namespace: &DatabaseName<'_>,
 ) {
     let hasher = TableNamespaceSharder::new(0..num_buckets);
+    let predicate = delete();
 
     group.throughput(Throughput::Elements(1));
     group.bench_function(bench_name, |b| {
         b.iter(|| {
-            hasher.shard(table, namespace, &0);
+            hasher.shard(table, namespace, &predicate);
         });
     });
 }
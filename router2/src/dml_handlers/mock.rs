@@ -8,7 +8,7 @@ use mutable_batch::MutableBatch;
 use parking_lot::Mutex;
 use trace::ctx::SpanContext;
 
-use super::{DmlError, DmlHandler};
+use super::{DmlError, DmlHandler, WriteSummary};
 
 #[derive(Debug, Clone)]
 pub enum MockDmlHandlerCall {
@@ -77,7 +77,7 @@ impl DmlHandler for Arc<MockDmlHandler> {
         namespace: DatabaseName<'static>,
         batches: HashMap<String, MutableBatch>,
         _span_ctx: Option<SpanContext>,
-    ) -> Result<(), Self::WriteError> {
+    ) -> Result<WriteSummary, Self::WriteError> {
         record_and_return!(
             self,
             MockDmlHandlerCall::Write {
@@ -86,6 +86,7 @@ impl DmlHandler for Arc<MockDmlHandler> {
             },
             write_return
         )
+        .map(|_| WriteSummary::default())
     }
 
     async fn delete<'a>(
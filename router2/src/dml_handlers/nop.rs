@@ -8,7 +8,7 @@ use mutable_batch::MutableBatch;
 use observability_deps::tracing::*;
 use trace::ctx::SpanContext;
 
-use super::{DmlError, DmlHandler};
+use super::{DmlError, DmlHandler, WriteSummary};
 
 /// A [`DmlHandler`] implementation that does nothing.
 #[derive(Debug, Default)]
@@ -24,9 +24,9 @@ impl DmlHandler for NopDmlHandler {
         namespace: DatabaseName<'static>,
         batches: HashMap<String, MutableBatch>,
         _span_ctx: Option<SpanContext>,
-    ) -> Result<(), Self::WriteError> {
+    ) -> Result<WriteSummary, Self::WriteError> {
         info!(%namespace, ?batches, "dropping write operation");
-        Ok(())
+        Ok(WriteSummary::default())
     }
 
     async fn delete<'a>(
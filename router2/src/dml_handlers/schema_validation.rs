@@ -1,20 +1,25 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use data_types::{delete_predicate::DeletePredicate, DatabaseName};
 use hashbrown::HashMap;
 use iox_catalog::{
-    interface::{get_schema_by_name, Catalog},
+    interface::{get_schema_by_name, Catalog, ColumnType, NamespaceSchema},
     validate_or_insert_schema,
 };
-use mutable_batch::MutableBatch;
+use mutable_batch::{column::ColumnData, MutableBatch};
 use observability_deps::tracing::*;
+use schema::{InfluxColumnType, InfluxFieldType, TIME_COLUMN_NAME};
 use thiserror::Error;
+use time::{SystemProvider, TimeProvider};
 use trace::ctx::SpanContext;
 
 use crate::namespace_cache::{MemoryNamespaceCache, NamespaceCache};
 
-use super::{DmlError, DmlHandler};
+use super::{DmlError, DmlHandler, WriteSummary};
 
 /// Errors emitted during schema validation.
 #[derive(Debug, Error)]
@@ -31,6 +36,10 @@ pub enum SchemaError {
     #[error(transparent)]
     Validate(iox_catalog::interface::Error),
 
+    /// Integer values of the request could not be coerced to floats.
+    #[error("failed to coerce integer values to floats: {0}")]
+    Coerce(mutable_batch::Error),
+
     /// The inner DML handler returned an error.
     #[error(transparent)]
     Inner(Box<DmlError>),
@@ -81,10 +90,34 @@ pub enum SchemaError {
 /// produce incorrect schemas ([#3573]).
 ///
 /// [#3573]: https://github.com/influxdata/influxdb_iox/issues/3573
+///
+/// # Retention
+///
+/// Once the namespace schema is resolved, any points with a timestamp outside
+/// of the retention period of the namespace are dropped from the write before
+/// the schema is validated. The remaining points are passed through to the
+/// inner handler, and the number of dropped points is reported in the
+/// [`WriteSummary`] of the write.
+///
+/// # Integer to Float Coercion
+///
+/// When enabled with [`SchemaValidator::with_int_to_float_coercion()`], a write
+/// of float values to an existing integer column promotes the column to a
+/// float column in the catalog (and the cached schema) instead of being
+/// rejected, once the rest of the write passed validation, and integer values written to an existing float column are
+/// converted to floats before being passed to the inner handler.
+///
+/// Coercion is decided against the cached schema - a router with a stale
+/// cached integer column passes integer writes through unchanged until it
+/// observes the promotion. Integer data buffered or persisted for a promoted
+/// column is cast to floats when it is merged with float data by the
+/// ingester and in queries.
 #[derive(Debug)]
 pub struct SchemaValidator<D, C = Arc<MemoryNamespaceCache>> {
     inner: D,
     catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    coerce_int_to_float: bool,
 
     cache: C,
 }
@@ -98,9 +131,26 @@ impl<D, C> SchemaValidator<D, C> {
         Self {
             inner,
             catalog,
+            time_provider: Arc::new(SystemProvider::default()),
+            coerce_int_to_float: false,
             cache: ns_cache,
         }
     }
+
+    /// Use `time_provider` to determine the current time when applying the
+    /// retention period of a namespace.
+    pub fn with_time_provider(mut self, time_provider: Arc<dyn TimeProvider>) -> Self {
+        self.time_provider = time_provider;
+        self
+    }
+
+    /// Coerce between integer and float field values instead of rejecting
+    /// writes that conflict with the existing column type (disabled by
+    /// default).
+    pub fn with_int_to_float_coercion(mut self, enabled: bool) -> Self {
+        self.coerce_int_to_float = enabled;
+        self
+    }
 }
 
 #[async_trait]
@@ -127,14 +177,18 @@ where
     /// A request that fails validation on one or more tables fails the request
     /// as a whole - calling this method has "all or nothing" semantics.
     ///
+    /// Points outside of the retention period of `namespace` are dropped
+    /// rather than failing the request. If all points are dropped, the inner
+    /// handler is not called.
+    ///
     /// If the inner handler returns an error (wrapped in a
     /// [`SchemaError::Inner`]), the semantics of the inner handler write apply.
     async fn write(
         &self,
         namespace: DatabaseName<'static>,
-        batches: HashMap<String, MutableBatch>,
+        mut batches: HashMap<String, MutableBatch>,
         span_ctx: Option<SpanContext>,
-    ) -> Result<(), Self::WriteError> {
+    ) -> Result<WriteSummary, Self::WriteError> {
         // Load the namespace schema from the cache, falling back to pulling it
         // from the global catalog (if it exists).
         let schema = self.cache.get_schema(&namespace);
@@ -159,6 +213,39 @@ where
             }
         };
 
+        let dropped_out_of_retention = match schema
+            .retention
+            .and_then(|retention| self.time_provider.now().checked_sub(retention))
+        {
+            Some(cutoff) => drop_out_of_retention(&mut batches, cutoff.timestamp_nanos()),
+            None => 0,
+        };
+        if dropped_out_of_retention > 0 {
+            debug!(%namespace, dropped_out_of_retention, "dropped points outside retention period");
+        }
+        if batches.is_empty() {
+            return Ok(WriteSummary {
+                dropped_out_of_retention,
+                ..Default::default()
+            });
+        }
+
+        // Coerce integer values written to float columns, and validate float
+        // values written to integer columns as if those columns were float
+        // columns already. They are only promoted in the catalog once the
+        // whole write is valid.
+        let to_promote = if self.coerce_int_to_float {
+            coerce_int_to_float(&mut batches, &schema).map_err(|e| {
+                warn!(error=%e, %namespace, "integer to float coercion failed");
+                e
+            })?
+        } else {
+            vec![]
+        };
+        let promoted_schema =
+            (!to_promote.is_empty()).then(|| Arc::new(with_float_columns(&schema, &to_promote)));
+        let schema = promoted_schema.clone().unwrap_or(schema);
+
         let maybe_new_schema = validate_or_insert_schema(
             batches.iter().map(|(k, v)| (k.as_str(), v)),
             &schema,
@@ -171,13 +258,23 @@ where
         })?
         .map(Arc::new);
 
+        if !to_promote.is_empty() {
+            promote_to_float(&to_promote, &schema, &*self.catalog)
+                .await
+                .map_err(|e| {
+                    warn!(error=%e, %namespace, "promoting integer columns to float failed");
+                    e
+                })?;
+            debug!(%namespace, "promoted integer columns to float");
+        }
+
         trace!(%namespace, "schema validation complete");
 
         // If the schema has been updated, immediately add it to the cache
         // (before passing through the write) in order to allow subsequent,
         // parallel requests to use it while waiting on the inner DML handler to
         // perform the write.
-        match maybe_new_schema {
+        match maybe_new_schema.or(promoted_schema) {
             Some(v) => {
                 // This call MAY overwrite a more-up-to-date cache entry if
                 // racing with another request for the same namespace, but the
@@ -190,10 +287,16 @@ where
             }
         }
 
-        self.inner
+        let summary = self
+            .inner
             .write(namespace, batches, span_ctx)
             .await
-            .map_err(|e| SchemaError::Inner(Box::new(e.into())))
+            .map_err(|e| SchemaError::Inner(Box::new(e.into())))?;
+
+        Ok(WriteSummary {
+            dropped_out_of_retention: summary.dropped_out_of_retention + dropped_out_of_retention,
+            ..summary
+        })
     }
 
     /// This call is passed through to `D` - no schema validation is performed
@@ -211,6 +314,213 @@ where
     }
 }
 
+/// The changes a write would make to a namespace schema, as computed by
+/// [`schema_diff()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Tables of the write that do not exist in the namespace.
+    pub new_tables: BTreeSet<String>,
+
+    /// Columns of the write that do not exist in the namespace, keyed by
+    /// table and column name, with the type they would be created with.
+    ///
+    /// This includes all columns of new tables.
+    pub new_columns: BTreeMap<(String, String), ColumnType>,
+
+    /// Columns of the write whose type conflicts with the existing column,
+    /// keyed by table and column name, with the existing and the written type.
+    ///
+    /// A write with conflicting columns is rejected by the [`SchemaValidator`].
+    pub conflicting_columns: BTreeMap<(String, String), (ColumnType, ColumnType)>,
+
+    /// Integer columns written as floats, keyed by table and column name,
+    /// which are promoted to float columns when integer to float coercion is
+    /// enabled.
+    pub promoted_columns: BTreeSet<(String, String)>,
+}
+
+impl SchemaDiff {
+    /// Returns true if the write would not change the namespace schema.
+    pub fn is_empty(&self) -> bool {
+        self.new_tables.is_empty()
+            && self.new_columns.is_empty()
+            && self.promoted_columns.is_empty()
+    }
+}
+
+/// Compute the tables and columns that writing `tables` would add to the
+/// namespace `schema`, without modifying the schema or the catalog.
+///
+/// Columns are typed the same way as when the write is validated by the
+/// [`SchemaValidator`]. If `coerce_int_to_float` is set, as for a validator
+/// configured with [`SchemaValidator::with_int_to_float_coercion()`],
+/// integers written to float columns do not conflict, and floats written to
+/// integer columns are reported as promoted columns instead of conflicts.
+pub fn schema_diff<'a>(
+    tables: impl IntoIterator<Item = (&'a str, &'a MutableBatch)>,
+    schema: &NamespaceSchema,
+    coerce_int_to_float: bool,
+) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+
+    for (table_name, batch) in tables {
+        let table = schema.tables.get(table_name);
+        if table.is_none() {
+            diff.new_tables.insert(table_name.to_string());
+        }
+
+        for (column_name, column) in batch.columns() {
+            let column_type = ColumnType::from(column.influx_type());
+            let key = (table_name.to_string(), column_name.to_string());
+            match table.and_then(|t| t.columns.get(column_name.as_str())) {
+                Some(existing) if existing.matches_type(column) => {}
+                Some(existing) if coerce_int_to_float => {
+                    match (existing.column_type, column_type) {
+                        (ColumnType::F64, ColumnType::I64) => {}
+                        (ColumnType::I64, ColumnType::F64) => {
+                            diff.promoted_columns.insert(key);
+                        }
+                        (existing, written) => {
+                            diff.conflicting_columns.insert(key, (existing, written));
+                        }
+                    }
+                }
+                Some(existing) => {
+                    diff.conflicting_columns
+                        .insert(key, (existing.column_type, column_type));
+                }
+                None => {
+                    diff.new_columns.insert(key, column_type);
+                }
+            }
+        }
+    }
+
+    diff
+}
+
+/// Convert the integer fields in `batches` that are float columns in `schema`
+/// to floats, and return the table and column names of the integer columns of
+/// `schema` that are written as floats, which need to be promoted to float
+/// columns.
+fn coerce_int_to_float(
+    batches: &mut HashMap<String, MutableBatch>,
+    schema: &NamespaceSchema,
+) -> Result<Vec<(String, String)>, SchemaError> {
+    let mut to_promote = vec![];
+    for (table_name, batch) in batches.iter_mut() {
+        let table = match schema.tables.get(table_name) {
+            Some(t) => t,
+            None => continue,
+        };
+
+        let mut to_coerce = vec![];
+        for (name, column) in batch.columns() {
+            let existing = table.columns.get(name.as_str()).map(|c| c.column_type);
+            match (existing, column.influx_type()) {
+                (Some(ColumnType::F64), InfluxColumnType::Field(InfluxFieldType::Integer)) => {
+                    to_coerce.push(name.clone())
+                }
+                (Some(ColumnType::I64), InfluxColumnType::Field(InfluxFieldType::Float)) => {
+                    to_promote.push((table_name.clone(), name.clone()))
+                }
+                _ => {}
+            }
+        }
+
+        for name in to_coerce {
+            batch
+                .coerce_integer_to_float(&name)
+                .map_err(SchemaError::Coerce)?;
+        }
+    }
+
+    Ok(to_promote)
+}
+
+/// Return `schema` with the `columns` (table and column names) changed to
+/// float columns.
+fn with_float_columns(schema: &NamespaceSchema, columns: &[(String, String)]) -> NamespaceSchema {
+    let mut schema = schema.clone();
+    for (table_name, name) in columns {
+        if let Some(column) = schema
+            .tables
+            .get_mut(table_name)
+            .and_then(|t| t.columns.get_mut(name))
+        {
+            column.column_type = ColumnType::F64;
+        }
+    }
+    schema
+}
+
+/// Promote the integer `columns` (table and column names) of the tables of
+/// `schema` to float columns in `catalog`.
+async fn promote_to_float(
+    columns: &[(String, String)],
+    schema: &NamespaceSchema,
+    catalog: &dyn Catalog,
+) -> Result<(), SchemaError> {
+    for (table_name, name) in columns {
+        let table_id = schema.tables[table_name].id;
+        catalog
+            .columns()
+            .promote_to_float(name, table_id)
+            .await
+            .map_err(SchemaError::Validate)?;
+    }
+    Ok(())
+}
+
+/// Remove all points with a timestamp before `cutoff` nanoseconds since the
+/// epoch from `batches`, removing any batch left empty.
+///
+/// Returns the number of points removed.
+fn drop_out_of_retention(batches: &mut HashMap<String, MutableBatch>, cutoff: i64) -> usize {
+    let mut dropped = 0;
+    batches.retain(|_, batch| {
+        let times = match batch.column(TIME_COLUMN_NAME).map(|c| c.data()) {
+            Ok(ColumnData::I64(times, _)) => times,
+            _ => return true,
+        };
+
+        // Build the ranges of consecutive rows within the retention period.
+        let mut ranges = vec![];
+        let mut start = None;
+        for (idx, retained) in times.iter().map(|t| *t >= cutoff).enumerate() {
+            match (start, retained) {
+                (None, true) => start = Some(idx),
+                (Some(s), false) => {
+                    ranges.push(s..idx);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            ranges.push(s..times.len());
+        }
+
+        let retained = ranges.iter().map(|r| r.len()).sum::<usize>();
+        dropped += batch.rows() - retained;
+
+        if retained == batch.rows() {
+            return true;
+        }
+        if retained == 0 {
+            return false;
+        }
+
+        let mut filtered = MutableBatch::new();
+        filtered
+            .extend_from_ranges(batch, &ranges)
+            .expect("ranges are valid rows of the batch");
+        *batch = filtered;
+        true
+    });
+    dropped
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -221,7 +531,6 @@ mod tests {
         interface::{ColumnType, KafkaTopicId, QueryPoolId},
         mem::MemCatalog,
     };
-    use schema::{InfluxColumnType, InfluxFieldType};
 
     use crate::dml_handlers::mock::{MockDmlHandler, MockDmlHandlerCall};
 
@@ -250,7 +559,7 @@ mod tests {
             .namespaces()
             .create(
                 NAMESPACE,
-                "inf",
+                Some("inf"),
                 KafkaTopicId::new(42),
                 QueryPoolId::new(24),
             )
@@ -371,6 +680,131 @@ mod tests {
         assert_cache(&handler, "bananas", "time", ColumnType::Time);
     }
 
+    #[tokio::test]
+    async fn test_write_int_to_float_coercion() {
+        let catalog = create_catalog().await;
+        let mock =
+            Arc::new(MockDmlHandler::default().with_write_return(vec![Ok(()), Ok(()), Ok(())]));
+        let handler = SchemaValidator::new(
+            Arc::clone(&mock),
+            Arc::clone(&catalog),
+            Arc::new(MemoryNamespaceCache::default()),
+        )
+        .with_int_to_float_coercion(true);
+
+        // First write sets the schema
+        let writes = lp_to_writes("bananas,tag1=A val=42i 123456"); // val=i64
+        handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_cache(&handler, "bananas", "val", ColumnType::I64);
+
+        // A float write promotes the column
+        let writes = lp_to_writes("bananas,tag1=A val=42.5 123457"); // val=float
+        handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_cache(&handler, "bananas", "val", ColumnType::F64);
+
+        // The promotion is recorded in the catalog
+        let schema = get_schema_by_name(NAMESPACE, &*catalog).await.unwrap();
+        assert_eq!(
+            schema.tables["bananas"].columns["val"].column_type,
+            ColumnType::F64
+        );
+
+        // Subsequent integer writes are coerced to floats
+        let writes = lp_to_writes("bananas,tag1=A val=24i 123458"); // val=i64
+        handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect("request should succeed");
+
+        assert_matches!(mock.calls().as_slice(), [_, _, MockDmlHandlerCall::Write{batches, ..}] => {
+            let batch = batches.get("bananas").expect("table not found in write");
+            let col = batch.column("val").expect("column not found in write");
+            assert_matches!(col.influx_type(), InfluxColumnType::Field(InfluxFieldType::Float));
+            assert_matches!(col.data(), ColumnData::F64(values, _) if values == &[24.0]);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_int_to_float_coercion_invalid_write() {
+        let catalog = create_catalog().await;
+        let mock = Arc::new(MockDmlHandler::default().with_write_return(vec![Ok(())]));
+        let handler = SchemaValidator::new(
+            Arc::clone(&mock),
+            Arc::clone(&catalog),
+            Arc::new(MemoryNamespaceCache::default()),
+        )
+        .with_int_to_float_coercion(true);
+
+        let writes = lp_to_writes("bananas,tag1=A val=42i,other=1i 123456"); // val=i64
+        handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect("request should succeed");
+
+        // A float write to val is rejected with the conflicting write to other
+        let writes = lp_to_writes("bananas,tag1=A val=42.5,other=\"x\" 123457");
+        let err = handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect_err("request should fail");
+        assert_matches!(
+            err,
+            SchemaError::Validate(iox_catalog::interface::Error::ColumnTypeMismatch { .. })
+        );
+
+        // The column is not promoted
+        assert_cache(&handler, "bananas", "val", ColumnType::I64);
+        let schema = get_schema_by_name(NAMESPACE, &*catalog).await.unwrap();
+        assert_eq!(
+            schema.tables["bananas"].columns["val"].column_type,
+            ColumnType::I64
+        );
+        assert_eq!(mock.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_int_to_float_coercion_disabled() {
+        let catalog = create_catalog().await;
+        let mock = Arc::new(MockDmlHandler::default().with_write_return(vec![Ok(())]));
+        let handler = SchemaValidator::new(
+            Arc::clone(&mock),
+            Arc::clone(&catalog),
+            Arc::new(MemoryNamespaceCache::default()),
+        )
+        .with_int_to_float_coercion(false);
+
+        let writes = lp_to_writes("bananas,tag1=A val=42i 123456"); // val=i64
+        handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect("request should succeed");
+
+        let writes = lp_to_writes("bananas,tag1=A val=42.5 123457"); // val=float
+        let err = handler
+            .write(NAMESPACE.try_into().unwrap(), writes, None)
+            .await
+            .expect_err("request should fail");
+        assert_matches!(
+            err,
+            SchemaError::Validate(iox_catalog::interface::Error::ColumnTypeMismatch { .. })
+        );
+
+        // Neither the cache nor the catalog is changed
+        assert_cache(&handler, "bananas", "val", ColumnType::I64);
+        let schema = get_schema_by_name(NAMESPACE, &*catalog).await.unwrap();
+        assert_eq!(
+            schema.tables["bananas"].columns["val"].column_type,
+            ColumnType::I64
+        );
+        assert_eq!(mock.calls().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_write_inner_handler_error() {
         let catalog = create_catalog().await;
@@ -472,4 +906,178 @@ mod tests {
         // Deletes have no effect on the cache.
         assert!(handler.cache.get_schema(&ns).is_none());
     }
+
+    #[tokio::test]
+    async fn test_write_drops_points_outside_retention() {
+        const HOUR: i64 = 60 * 60 * 1_000_000_000;
+
+        let catalog = create_catalog().await;
+        catalog
+            .namespaces()
+            .create(
+                "retained",
+                Some("1h"),
+                KafkaTopicId::new(42),
+                QueryPoolId::new(24),
+            )
+            .await
+            .expect("failed to create test namespace");
+
+        let now = 10 * HOUR;
+        let mock = Arc::new(MockDmlHandler::default().with_write_return(vec![Ok(())]));
+        let handler = SchemaValidator::new(
+            Arc::clone(&mock),
+            catalog,
+            Arc::new(MemoryNamespaceCache::default()),
+        )
+        .with_time_provider(Arc::new(time::MockProvider::new(
+            time::Time::from_timestamp_nanos(now),
+        )));
+        let ns = DatabaseName::try_from("retained").unwrap();
+
+        // A write straddling the retention boundary only passes through the
+        // points within the retention period.
+        let writes = lp_to_writes(&format!(
+            "bananas,tag1=A val=1i {}\n\
+            bananas,tag1=B val=2i {}\n\
+            bananas,tag1=C val=3i {}\n\
+            platanos val=4i {}",
+            now - 2 * HOUR,
+            now - HOUR / 2,
+            now - 3 * HOUR,
+            now - 2 * HOUR,
+        ));
+        let summary = handler
+            .write(ns.clone(), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_eq!(summary.dropped_out_of_retention, 3);
+
+        assert_matches!(mock.calls().as_slice(), [MockDmlHandlerCall::Write{batches, ..}] => {
+            assert_eq!(batches.len(), 1);
+            let batch = batches.get("bananas").expect("table not found in write");
+            assert_eq!(batch.rows(), 1);
+            assert_eq!(batch.timestamp_summary().unwrap().stats.min, Some(now - HOUR / 2));
+        });
+
+        // The table containing only dropped points is not created.
+        let schema = handler
+            .cache
+            .get_schema(&ns)
+            .expect("cache should be populated");
+        assert!(schema.tables.contains_key("bananas"));
+        assert!(!schema.tables.contains_key("platanos"));
+
+        // A write containing only points outside the retention period is not
+        // passed through at all.
+        let writes = lp_to_writes(&format!("bananas,tag1=A val=1i {}", now - 2 * HOUR));
+        let summary = handler
+            .write(ns, writes, None)
+            .await
+            .expect("request should succeed");
+        assert_eq!(summary.dropped_out_of_retention, 1);
+        assert_eq!(mock.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schema_diff() {
+        let catalog = create_catalog().await;
+        let writes = lp_to_writes("bananas,tag1=A,tag2=B val=42i 123456");
+        let schema = get_schema_by_name(NAMESPACE, &*catalog).await.unwrap();
+        let schema = validate_or_insert_schema(
+            writes.iter().map(|(k, v)| (k.as_str(), v)),
+            &schema,
+            &*catalog,
+        )
+        .await
+        .unwrap()
+        .expect("schema should be updated");
+
+        // A write adding a single new field reports exactly that column
+        let writes = lp_to_writes("bananas,tag1=A,tag2=B val=42i,ratio=0.5 123457");
+        let diff = schema_diff(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema, false);
+        assert_eq!(
+            diff,
+            SchemaDiff {
+                new_columns: [(
+                    ("bananas".to_string(), "ratio".to_string()),
+                    ColumnType::F64
+                )]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            }
+        );
+
+        // New tables report all their columns, conflicting types are reported
+        let writes = lp_to_writes("platanos,tag1=A val=1u 123457\nbananas val=\"str\" 123457");
+        let diff = schema_diff(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema, false);
+        assert_eq!(
+            diff.new_tables,
+            ["platanos".to_string()].into_iter().collect()
+        );
+        let new_columns: Vec<_> = diff
+            .new_columns
+            .iter()
+            .map(|((table, column), column_type)| (table.as_str(), column.as_str(), *column_type))
+            .collect();
+        assert_eq!(
+            new_columns,
+            vec![
+                ("platanos", "tag1", ColumnType::Tag),
+                ("platanos", "time", ColumnType::Time),
+                ("platanos", "val", ColumnType::U64),
+            ]
+        );
+        assert_eq!(
+            diff.conflicting_columns,
+            [(
+                ("bananas".to_string(), "val".to_string()),
+                (ColumnType::I64, ColumnType::String)
+            )]
+            .into_iter()
+            .collect()
+        );
+
+        // With integer to float coercion, integers written to float columns do
+        // not conflict and floats written to integer columns promote them
+        let writes = lp_to_writes("bananas,tag1=A val=4.2,ratio=1i 123458");
+        let diff = schema_diff(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema, false);
+        assert!(diff.promoted_columns.is_empty());
+        assert_eq!(
+            diff.conflicting_columns,
+            [(
+                ("bananas".to_string(), "val".to_string()),
+                (ColumnType::I64, ColumnType::F64)
+            )]
+            .into_iter()
+            .collect()
+        );
+
+        let writes = lp_to_writes("bananas,tag1=A val=1i,ratio=0.5 123457");
+        let schema = validate_or_insert_schema(
+            writes.iter().map(|(k, v)| (k.as_str(), v)),
+            &schema,
+            &*catalog,
+        )
+        .await
+        .unwrap()
+        .expect("schema should be updated");
+        let writes = lp_to_writes("bananas,tag1=A val=4.2,ratio=1i 123458");
+        let diff = schema_diff(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema, true);
+        assert_eq!(
+            diff,
+            SchemaDiff {
+                promoted_columns: [("bananas".to_string(), "val".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            }
+        );
+        assert!(!diff.is_empty());
+
+        // Nothing was written to the catalog
+        let catalog_schema = get_schema_by_name(NAMESPACE, &*catalog).await.unwrap();
+        assert_eq!(catalog_schema, schema);
+    }
 }
@@ -4,6 +4,7 @@ use std::{
     fmt::{Debug, Display},
     future,
     sync::Arc,
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -17,7 +18,11 @@ use thiserror::Error;
 use trace::ctx::SpanContext;
 use write_buffer::core::WriteBufferError;
 
-use crate::{dml_handlers::DmlHandler, sequencer::Sequencer, sharder::Sharder};
+use crate::{
+    dml_handlers::{DmlHandler, WriteSummary},
+    sequencer::Sequencer,
+    sharder::Sharder,
+};
 
 /// Errors occurring while writing to one or more write buffer shards.
 #[derive(Debug, Error)]
@@ -33,6 +38,42 @@ pub enum ShardError {
         /// The errors returned by the failed shard writes.
         errs: Vec<WriteBufferError>,
     },
+
+    /// One or more shards did not accept the operation within the configured
+    /// enqueue timeout, indicating the write buffer is applying backpressure.
+    ///
+    /// The operation may still be applied to the shards that timed out, and
+    /// to any other shard if `successes > 0` - callers should retry no sooner
+    /// than `retry_after`.
+    #[error("write buffer full: {timeouts} shards did not accept the operation within {timeout:?} ({successes} shards successful)")]
+    WriteBufferFull {
+        /// The number of successful shard writes.
+        successes: usize,
+        /// The number of shard writes that timed out.
+        timeouts: usize,
+        /// The enqueue timeout that elapsed.
+        timeout: Duration,
+    },
+}
+
+impl ShardError {
+    /// The duration after which a client may retry the operation, if the error
+    /// is transient.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::WriteBufferErrors { .. } => None,
+            Self::WriteBufferFull { timeout, .. } => Some(*timeout),
+        }
+    }
+}
+
+/// The outcome of a single, failed shard enqueue.
+#[derive(Debug)]
+enum EnqueueError {
+    /// The shard did not accept the operation within the enqueue timeout.
+    Timeout,
+    /// The write buffer returned an error.
+    WriteBuffer(WriteBufferError),
 }
 
 /// Helper function to turn the set of `T` into strings and join them with `;`.
@@ -53,30 +94,47 @@ where
 /// instances and dispatching them to the write buffer.
 ///
 /// Writes are batched per-shard, producing one op per shard, per write. For a
-/// single write, all shards are wrote to in parallel.
+/// single write, all shards are wrote to in parallel, as are all the shards a
+/// delete is routed to.
 ///
 /// The buffering / async return behaviour of the methods on this type are
 /// defined by the behaviour of the underlying [write buffer] implementation.
 ///
+/// If an enqueue timeout is configured with
+/// [`ShardedWriteBuffer::with_enqueue_timeout()`], a shard that does not accept
+/// an operation within the timeout causes [`ShardError::WriteBufferFull`] to be
+/// returned, instead of blocking the caller until the write buffer catches up.
+///
 /// [write buffer]: write_buffer::core::WriteBufferWriting
 #[derive(Debug)]
 pub struct ShardedWriteBuffer<S> {
     sharder: S,
+    enqueue_timeout: Option<Duration>,
 }
 
 impl<S> ShardedWriteBuffer<S> {
     /// Construct a [`ShardedWriteBuffer`] using the specified [`Sharder`]
     /// implementation.
     pub fn new(sharder: S) -> Self {
-        Self { sharder }
+        Self {
+            sharder,
+            enqueue_timeout: None,
+        }
+    }
+
+    /// Return [`ShardError::WriteBufferFull`] when a shard does not accept an
+    /// operation within `timeout`.
+    pub fn with_enqueue_timeout(mut self, timeout: Duration) -> Self {
+        self.enqueue_timeout = Some(timeout);
+        self
     }
 }
 
 #[async_trait]
 impl<S> DmlHandler for ShardedWriteBuffer<S>
 where
-    S: Sharder<MutableBatch, Item = Arc<Sequencer>>
-        + Sharder<DeletePredicate, Item = Arc<Sequencer>>,
+    S: Sharder<MutableBatch, Item = Vec<(Arc<Sequencer>, MutableBatch)>>
+        + Sharder<DeletePredicate, Item = Vec<Arc<Sequencer>>>,
 {
     type WriteError = ShardError;
     type DeleteError = ShardError;
@@ -87,21 +145,21 @@ where
         namespace: DatabaseName<'static>,
         writes: HashMap<String, MutableBatch>,
         span_ctx: Option<SpanContext>,
-    ) -> Result<(), ShardError> {
+    ) -> Result<WriteSummary, ShardError> {
         let mut collated: HashMap<_, HashMap<String, MutableBatch>> = HashMap::new();
 
         // Shard each entry in `writes` and collate them into one DML operation
         // per shard to maximise the size of each write, and therefore increase
         // the effectiveness of compression of ops in the write buffer.
         for (table, batch) in writes.into_iter() {
-            let sequencer = Arc::clone(self.sharder.shard(&table, &namespace, &batch));
+            for (sequencer, batch) in self.sharder.shard(&table, &namespace, &batch) {
+                let existing = collated
+                    .entry(sequencer)
+                    .or_default()
+                    .insert(table.clone(), batch);
 
-            let existing = collated
-                .entry(sequencer)
-                .or_default()
-                .insert(table, batch.clone());
-
-            assert!(existing.is_none());
+                assert!(existing.is_none());
+            }
         }
 
         let iter = collated.into_iter().map(|(sequencer, batch)| {
@@ -118,10 +176,12 @@ where
             (sequencer, DmlOperation::from(dml))
         });
 
-        parallel_enqueue(iter).await
+        parallel_enqueue(iter, self.enqueue_timeout).await?;
+
+        Ok(WriteSummary::default())
     }
 
-    /// Shard `predicate` and dispatch it to the appropriate shard.
+    /// Shard `predicate` and dispatch it to the appropriate shards.
     async fn delete<'a>(
         &self,
         namespace: DatabaseName<'static>,
@@ -130,42 +190,51 @@ where
         span_ctx: Option<SpanContext>,
     ) -> Result<(), ShardError> {
         let table_name = table_name.into();
-        let sequencer = self.sharder.shard(&table_name, &namespace, &predicate);
+        let sequencers = self.sharder.shard(&table_name, &namespace, &predicate);
 
-        trace!(sequencer_id=%sequencer.id(), %table_name, %namespace, "routing delete to shard");
+        let iter = sequencers.into_iter().map(|sequencer| {
+            trace!(sequencer_id=%sequencer.id(), %table_name, %namespace, "routing delete to shard");
 
-        let dml = DmlDelete::new(
-            &namespace,
-            predicate,
-            NonEmptyString::new(table_name),
-            DmlMeta::unsequenced(span_ctx),
-        );
+            let dml = DmlDelete::new(
+                &namespace,
+                predicate.clone(),
+                NonEmptyString::new(table_name.clone()),
+                DmlMeta::unsequenced(span_ctx.clone()),
+            );
 
-        sequencer
-            .enqueue(DmlOperation::from(dml))
-            .await
-            .map_err(|e| ShardError::WriteBufferErrors {
-                successes: 0,
-                errs: vec![e],
-            })?;
+            (sequencer, DmlOperation::from(dml))
+        });
 
-        Ok(())
+        parallel_enqueue(iter, self.enqueue_timeout).await
     }
 }
 
 /// Enumerates all items in the iterator, maps each to a future that dispatches
 /// the [`DmlOperation`] to its paired [`Sequencer`], executes all the futures
 /// in parallel and gathers any errors.
-async fn parallel_enqueue<T>(v: T) -> Result<(), ShardError>
+///
+/// If `timeout` is specified, each enqueue that does not complete within it is
+/// abandoned and reported as [`ShardError::WriteBufferFull`], taking precedence
+/// over any write buffer errors.
+async fn parallel_enqueue<T>(v: T, timeout: Option<Duration>) -> Result<(), ShardError>
 where
     T: Iterator<Item = (Arc<Sequencer>, DmlOperation)> + Send,
 {
     let mut successes = 0;
     let errs = v
         .map(|(sequencer, op)| async move {
-            tokio::spawn(async move { sequencer.enqueue(op).await })
-                .await
-                .expect("shard enqueue panic")
+            tokio::spawn(async move {
+                let enqueue = sequencer.enqueue(op);
+                match timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, enqueue).await {
+                        Ok(res) => res.map_err(EnqueueError::WriteBuffer),
+                        Err(_) => Err(EnqueueError::Timeout),
+                    },
+                    None => enqueue.await.map_err(EnqueueError::WriteBuffer),
+                }
+            })
+            .await
+            .expect("shard enqueue panic")
         })
         .collect::<FuturesUnordered<_>>()
         .filter_map(|v| {
@@ -174,9 +243,29 @@ where
             }
             future::ready(v.err())
         })
-        .collect::<Vec<WriteBufferError>>()
+        .collect::<Vec<EnqueueError>>()
         .await;
 
+    let mut timeouts = 0;
+    let errs = errs
+        .into_iter()
+        .filter_map(|e| match e {
+            EnqueueError::Timeout => {
+                timeouts += 1;
+                None
+            }
+            EnqueueError::WriteBuffer(e) => Some(e),
+        })
+        .collect::<Vec<_>>();
+
+    if timeouts > 0 {
+        return Err(ShardError::WriteBufferFull {
+            successes,
+            timeouts,
+            timeout: timeout.expect("enqueue timed out without a timeout"),
+        });
+    }
+
     match errs.len() {
         0 => Ok(()),
         _n => Err(ShardError::WriteBufferErrors { successes, errs }),
@@ -189,11 +278,16 @@ mod tests {
     use data_types::timestamp::TimestampRange;
     use std::sync::Arc;
 
-    use write_buffer::mock::{MockBufferForWriting, MockBufferSharedState};
+    use write_buffer::mock::{
+        MockBufferForWriting, MockBufferForWritingThatBlocks, MockBufferSharedState,
+    };
 
     use crate::{
         dml_handlers::DmlHandler,
-        sharder::mock::{MockSharder, MockSharderCall},
+        sharder::{
+            mock::{MockSharder, MockSharderCall},
+            TableNamespaceSharder,
+        },
     };
 
     use super::*;
@@ -431,4 +525,147 @@ mod tests {
             assert_eq!(*d.predicate(), predicate);
         });
     }
+
+    #[tokio::test]
+    async fn test_tag_key_sharding_splits_writes_and_fans_out_deletes() {
+        let write_buffer1 = init_write_buffer(1);
+        let write_buffer1_state = write_buffer1.state();
+        let shard1 = Arc::new(Sequencer::new(0, Arc::new(write_buffer1)));
+
+        let write_buffer2 = init_write_buffer(2);
+        let write_buffer2_state = write_buffer2.state();
+        let shard2 = Arc::new(Sequencer::new(1, Arc::new(write_buffer2)));
+
+        let sharder = TableNamespaceSharder::new_with_tag_keys(
+            [Arc::clone(&shard1), Arc::clone(&shard2)],
+            ["host"],
+        );
+        let w = ShardedWriteBuffer::new(sharder);
+
+        // A write containing many hosts is split across both shards
+        let lp = (0..10)
+            .map(|i| format!("bananas,host=host{} val=42i {}", i, i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ns = DatabaseName::new("bananas").unwrap();
+        w.write(ns.clone(), lp_to_writes(&lp), None)
+            .await
+            .expect("write failed");
+
+        let rows = |state: &MockBufferSharedState, sequencer: &Sequencer| {
+            let mut got = state.get_messages(sequencer.id() as _);
+            assert_eq!(got.len(), 1);
+            let got = got
+                .pop()
+                .unwrap()
+                .expect("write should have been successful");
+            assert_matches!(got, DmlOperation::Write(w) => {
+                w.table("bananas").expect("no write for table").rows()
+            })
+        };
+        let rows1 = rows(&write_buffer1_state, &shard1);
+        let rows2 = rows(&write_buffer2_state, &shard2);
+        assert!(rows1 > 0);
+        assert!(rows2 > 0);
+        assert_eq!(rows1 + rows2, 10);
+
+        // While a delete is routed to both shards
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+        w.delete(ns, "bananas", predicate.clone(), None)
+            .await
+            .expect("delete failed");
+
+        for (state, sequencer) in [
+            (&write_buffer1_state, &shard1),
+            (&write_buffer2_state, &shard2),
+        ] {
+            let got = state.get_messages(sequencer.id() as _);
+            assert_eq!(got.len(), 2);
+            assert_matches!(got.last().unwrap(), Ok(DmlOperation::Delete(d)) => {
+                assert_eq!(d.table_name(), Some("bananas"));
+                assert_eq!(*d.predicate(), predicate);
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_buffer_full() {
+        let writes = lp_to_writes(
+            "\
+                bananas,tag1=A,tag2=B val=42i 123456\n\
+                platanos,tag1=A,tag2=B value=42i 123456\n\
+            ",
+        );
+
+        // Configure the first shard to write to a working write buffer
+        let write_buffer1 = init_write_buffer(1);
+        let write_buffer1_state = write_buffer1.state();
+        let shard1 = Arc::new(Sequencer::new(0, Arc::new(write_buffer1)));
+
+        // And the second to a write buffer that never accepts the write
+        let shard2 = Arc::new(Sequencer::new(
+            0,
+            Arc::new(MockBufferForWritingThatBlocks::default()),
+        ));
+
+        let sharder = Arc::new(
+            MockSharder::default().with_return([Arc::clone(&shard1), Arc::clone(&shard2)]),
+        );
+
+        let timeout = Duration::from_millis(10);
+        let w = ShardedWriteBuffer::new(Arc::clone(&sharder)).with_enqueue_timeout(timeout);
+
+        // The write must return an error rather than block forever.
+        let ns = DatabaseName::new("bananas").unwrap();
+        let err = tokio::time::timeout(Duration::from_secs(5), w.write(ns, writes, None))
+            .await
+            .expect("write should not block")
+            .expect_err("write should return a failure");
+        assert_matches!(err, ShardError::WriteBufferFull{successes, timeouts, timeout: t} => {
+            assert_eq!(successes, 1);
+            assert_eq!(timeouts, 1);
+            assert_eq!(t, timeout);
+        });
+        assert_eq!(err.retry_after(), Some(timeout));
+
+        let got = write_buffer1_state.get_messages(shard1.id() as _);
+        assert_eq!(got.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_write_buffer_full() {
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+
+        let shard = Arc::new(Sequencer::new(
+            0,
+            Arc::new(MockBufferForWritingThatBlocks::default()),
+        ));
+        let sharder = Arc::new(MockSharder::default().with_return([Arc::clone(&shard)]));
+
+        let timeout = Duration::from_millis(10);
+        let w = ShardedWriteBuffer::new(Arc::clone(&sharder)).with_enqueue_timeout(timeout);
+
+        let ns = DatabaseName::new("namespace").unwrap();
+        let err = tokio::time::timeout(
+            Duration::from_secs(5),
+            w.delete(ns, "bananas", predicate, None),
+        )
+        .await
+        .expect("delete should not block")
+        .expect_err("delete should return a failure");
+        assert_matches!(
+            err,
+            ShardError::WriteBufferFull {
+                successes: 0,
+                timeouts: 1,
+                ..
+            }
+        );
+    }
 }
@@ -31,6 +31,18 @@ pub enum DmlError {
     Internal(Box<dyn Error + Send + Sync>),
 }
 
+/// A summary of a write accepted by a [`DmlHandler`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteSummary {
+    /// The number of points dropped from the write because their timestamp
+    /// falls outside the retention period of the namespace.
+    pub dropped_out_of_retention: usize,
+
+    /// The number of points whose timestamp was clamped because it lay
+    /// further in the future than the configured maximum skew.
+    pub clamped_future_timestamps: usize,
+}
+
 /// A composable, abstract handler of DML requests.
 #[async_trait]
 pub trait DmlHandler: Debug + Send + Sync {
@@ -42,13 +54,14 @@ pub trait DmlHandler: Debug + Send + Sync {
     /// The error type of the delete handler.
     type DeleteError: Error + Into<DmlError> + Send;
 
-    /// Write `batches` to `namespace`.
+    /// Write `batches` to `namespace`, returning a [`WriteSummary`] of the
+    /// accepted write.
     async fn write(
         &self,
         namespace: DatabaseName<'static>,
         batches: HashMap<String, MutableBatch>,
         span_ctx: Option<SpanContext>,
-    ) -> Result<(), Self::WriteError>;
+    ) -> Result<WriteSummary, Self::WriteError>;
 
     /// Delete the data specified in `delete`.
     async fn delete<'a>(
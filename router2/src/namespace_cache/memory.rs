@@ -1,8 +1,8 @@
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
 use data_types::DatabaseName;
 use hashbrown::HashMap;
-use iox_catalog::interface::NamespaceSchema;
+use iox_catalog::interface::{Catalog, NamespaceSchema};
 use parking_lot::RwLock;
 
 use super::NamespaceCache;
@@ -14,6 +14,47 @@ pub struct MemoryNamespaceCache {
     cache: RwLock<HashMap<DatabaseName<'static>, Arc<NamespaceSchema>>>,
 }
 
+impl MemoryNamespaceCache {
+    /// Reload the retention of the cached namespaces from `catalog`, so that
+    /// writes apply changes to it without a restart.
+    pub async fn refresh_retention(
+        &self,
+        catalog: &dyn Catalog,
+    ) -> Result<(), iox_catalog::interface::Error> {
+        let kafka_topics: BTreeSet<_> = self
+            .cache
+            .read()
+            .values()
+            .map(|schema| schema.kafka_topic_id)
+            .collect();
+
+        let mut retentions = HashMap::new();
+        for kafka_topic_id in kafka_topics {
+            for namespace in catalog
+                .namespaces()
+                .list_by_kafka_topic(kafka_topic_id)
+                .await?
+            {
+                let retention = namespace.retention_or_infinite();
+                retentions.insert(namespace.name, retention);
+            }
+        }
+
+        for (name, schema) in self.cache.write().iter_mut() {
+            match retentions.get(name.as_str()) {
+                Some(&retention) if retention != schema.retention => {
+                    *schema = Arc::new(NamespaceSchema {
+                        retention,
+                        ..NamespaceSchema::clone(schema)
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
 impl NamespaceCache for Arc<MemoryNamespaceCache> {
     fn get_schema(&self, namespace: &DatabaseName<'_>) -> Option<Arc<NamespaceSchema>> {
         self.cache.read().get(namespace).map(Arc::clone)
@@ -30,7 +71,12 @@ impl NamespaceCache for Arc<MemoryNamespaceCache> {
 
 #[cfg(test)]
 mod tests {
-    use iox_catalog::interface::{KafkaTopicId, NamespaceId, QueryPoolId};
+    use std::time::Duration;
+
+    use iox_catalog::{
+        interface::{KafkaTopicId, NamespaceId, QueryPoolId},
+        mem::MemCatalog,
+    };
 
     use super::*;
 
@@ -45,6 +91,7 @@ mod tests {
             id: NamespaceId::new(42),
             kafka_topic_id: KafkaTopicId::new(24),
             query_pool_id: QueryPoolId::new(1234),
+            retention: None,
             tables: Default::default(),
         };
         assert!(cache.put_schema(ns.clone(), schema1.clone()).is_none());
@@ -54,6 +101,7 @@ mod tests {
             id: NamespaceId::new(2),
             kafka_topic_id: KafkaTopicId::new(2),
             query_pool_id: QueryPoolId::new(2),
+            retention: None,
             tables: Default::default(),
         };
 
@@ -65,4 +113,29 @@ mod tests {
         );
         assert_eq!(*cache.get_schema(&ns).expect("lookup failure"), schema2);
     }
+
+    #[tokio::test]
+    async fn test_refresh_retention() {
+        let catalog = MemCatalog::new();
+        let kafka_topic = catalog.kafka_topics().create_or_get("t").await.unwrap();
+        let query_pool = catalog.query_pools().create_or_get("p").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create("test", Some("1h"), kafka_topic.id, query_pool.id)
+            .await
+            .unwrap();
+
+        let ns = DatabaseName::new("test").expect("database name is valid");
+        let cache = Arc::new(MemoryNamespaceCache::default());
+        cache.put_schema(
+            ns.clone(),
+            NamespaceSchema::new(namespace.id, kafka_topic.id, query_pool.id),
+        );
+
+        cache.refresh_retention(&catalog).await.unwrap();
+        assert_eq!(
+            cache.get_schema(&ns).expect("lookup failure").retention,
+            Some(Duration::from_secs(60 * 60))
+        );
+    }
 }
@@ -1,12 +1,13 @@
 //! Router server entrypoint.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::dml_handlers::DmlHandler;
 use trace::TraceCollector;
 
 use self::{grpc::GrpcDelegate, http::HttpDelegate};
 
+pub mod drain;
 pub mod grpc;
 pub mod http;
 
@@ -62,4 +63,21 @@ where
     pub fn grpc(&self) -> &GrpcDelegate {
         &self.grpc
     }
+
+    /// Reject new DML requests and wait up to `timeout` for in-flight
+    /// requests to complete.
+    ///
+    /// Returns true if all in-flight requests completed before the deadline.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.http.drain(timeout).await
+    }
+
+    /// Apply the config `fields` to all HTTP requests from now on, without
+    /// restarting the server, see [`HttpDelegate::reload_config()`].
+    pub fn reload_config(
+        &self,
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value, http::Error> {
+        self.http.reload_config(fields)
+    }
 }
@@ -0,0 +1,124 @@
+//! Tracking of in-flight requests to support a graceful shutdown.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+/// Tracks the number of in-flight requests, and once draining has begun,
+/// rejects new requests while waiting for the in-flight requests to complete.
+#[derive(Debug, Default)]
+pub struct DrainTracker {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl DrainTracker {
+    /// Register the start of a request, returning a guard that marks it as
+    /// complete when dropped.
+    ///
+    /// Returns [`None`] if the tracker is draining and no new requests should
+    /// be accepted.
+    pub fn enter(&self) -> Option<InFlightGuard<'_>> {
+        // Increment before checking the draining flag to ensure a concurrent
+        // drain() either observes this request as in-flight, or this request
+        // observes the draining flag.
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard(self);
+
+        if self.draining.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        Some(guard)
+    }
+
+    /// Stop accepting new requests, and wait up to `timeout` for all
+    /// in-flight requests to complete.
+    ///
+    /// Returns true if all in-flight requests completed within `timeout`.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::SeqCst);
+
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Register for the wakeup before checking the count to avoid
+                // missing a notification between the two.
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    /// Returns true if [`DrainTracker::drain()`] has been called.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of requests currently in-flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+}
+
+/// A guard marking a request as in-flight for the lifetime of the guard.
+#[derive(Debug)]
+pub struct InFlightGuard<'a>(&'a DrainTracker);
+
+impl<'a> Drop for InFlightGuard<'a> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::poll;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight() {
+        let tracker = DrainTracker::default();
+
+        let guard = tracker.enter().expect("should accept requests");
+        assert_eq!(tracker.in_flight(), 1);
+
+        let drain = tracker.drain(Duration::from_secs(5));
+        tokio::pin!(drain);
+        assert!(poll!(&mut drain).is_pending());
+        assert!(tracker.is_draining());
+
+        // New requests are rejected once draining.
+        assert!(tracker.enter().is_none());
+        assert_eq!(tracker.in_flight(), 1);
+
+        drop(guard);
+        assert!(drain.await);
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout() {
+        let tracker = DrainTracker::default();
+
+        let _guard = tracker.enter().expect("should accept requests");
+        assert!(!tracker.drain(Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn test_drain_idle() {
+        let tracker = DrainTracker::default();
+        assert!(tracker.drain(Duration::from_millis(10)).await);
+    }
+}
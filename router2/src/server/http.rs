@@ -1,20 +1,72 @@
 //! HTTP service implementations for `router2`.
 
-use std::str::Utf8Error;
+use std::{str::Utf8Error, sync::Arc, time::Duration};
 
+use arrow::{error::ArrowError, ipc::reader::StreamReader};
 use bytes::{Bytes, BytesMut};
-use data_types::names::{org_and_bucket_to_database, OrgBucketMappingError};
+use data_types::{
+    names::{org_and_bucket_to_database, OrgBucketMappingError},
+    DatabaseName,
+};
 
 use futures::StreamExt;
-use hyper::{header::CONTENT_ENCODING, Body, Method, Request, Response, StatusCode};
+use hashbrown::HashMap;
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER},
+    Body, Method, Request, Response, StatusCode,
+};
+use iox_catalog::interface::Catalog;
+use metric::U64Counter;
+use mutable_batch::{column::ColumnData, record_batch::write_record_batch, MutableBatch};
+use mutable_batch_lp::LinesConverter;
 use observability_deps::tracing::*;
+use parking_lot::RwLock;
 use predicate::delete_predicate::{parse_delete_predicate, parse_http_delete_request};
+use schema::TIME_COLUMN_NAME;
 use serde::Deserialize;
 use thiserror::Error;
 use time::{SystemProvider, TimeProvider};
 use trace::ctx::SpanContext;
+use trogging::LogFilterHandle;
+use uuid::Uuid;
 
-use crate::dml_handlers::{DmlError, DmlHandler};
+use super::drain::DrainTracker;
+use crate::dml_handlers::{DmlError, DmlHandler, ShardError, WriteSummary};
+
+/// The HTTP header carrying the ID used to correlate the logs of a single
+/// request.
+///
+/// If the client does not provide a request ID, one is generated.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The HTTP header naming the table an Arrow IPC stream write is applied to.
+pub const ARROW_TABLE_HEADER: &str = "x-iox-table";
+
+/// The `Content-Type` of a write request body containing an Arrow IPC stream
+/// rather than line protocol.
+pub const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+/// The default maximum length, in bytes, of a tag key or value in a write.
+///
+/// This is large enough to accept any reasonable write, while preventing a
+/// single pathological write from bloating the tag dictionaries.
+pub const DEFAULT_MAX_TAG_BYTES: usize = 64 * 1024;
+
+/// The maximum size of the body of a config reload request, independent of the
+/// (reloadable) maximum size of other requests.
+const MAX_CONFIG_RELOAD_BYTES: usize = 64 * 1024;
+
+/// How a write containing points timestamped further in the future than the
+/// configured maximum skew is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FutureTimestampMode {
+    /// Reject the whole write with [`Error::FutureTimestamp`].
+    Reject,
+
+    /// Replace the timestamp of each offending point with the latest
+    /// acceptable timestamp, and accept the write.
+    Clamp,
+}
 
 /// Errors returned by the `router2` HTTP request handler.
 #[derive(Debug, Error)]
@@ -27,6 +79,10 @@ pub enum Error {
     #[error(transparent)]
     InvalidOrgBucket(#[from] OrgBucketError),
 
+    /// An error with the db/rp in a v1 write request.
+    #[error(transparent)]
+    InvalidDbRp(#[from] DbRpError),
+
     /// The request body content is not valid utf8.
     #[error("body content is not valid utf8: {0}")]
     NonUtf8Body(Utf8Error),
@@ -47,6 +103,11 @@ pub enum Error {
     #[error("max request size ({0} bytes) exceeded")]
     RequestSizeExceeded(usize),
 
+    /// The client did not send the full request body within the configured
+    /// request timeout.
+    #[error("request body not received within {0:?}")]
+    RequestTimeout(Duration),
+
     /// Decoding a gzip-compressed stream of data failed.
     #[error("error decoding gzip stream: {0}")]
     InvalidGzip(std::io::Error),
@@ -55,6 +116,18 @@ pub enum Error {
     #[error("failed to parse line protocol: {0}")]
     ParseLineProtocol(mutable_batch_lp::Error),
 
+    /// An Arrow IPC stream write did not specify the table to write to.
+    #[error("no table provided in the {} header", ARROW_TABLE_HEADER)]
+    ArrowTableNotSpecified,
+
+    /// Failure to decode the provided Arrow IPC stream.
+    #[error("failed to decode arrow ipc stream: {0}")]
+    DecodeArrow(ArrowError),
+
+    /// Failure to convert the decoded record batches to a write.
+    #[error("failed to convert arrow record batch: {0}")]
+    ConvertArrow(mutable_batch::record_batch::Error),
+
     /// Failure to parse the request delete predicate.
     #[error("failed to parse delete predicate: {0}")]
     ParseDelete(#[from] predicate::delete_predicate::Error),
@@ -62,6 +135,63 @@ pub enum Error {
     /// An error returned from the [`DmlHandler`].
     #[error("dml handler error: {0}")]
     DmlHandler(#[from] DmlError),
+
+    /// The server is shutting down and no longer accepts requests.
+    #[error("server is shutting down")]
+    ShuttingDown,
+
+    /// The query string of a namespace summary request is invalid.
+    #[error("invalid namespace summary request: {0}")]
+    InvalidNamespaceSummaryRequest(serde_urlencoded::de::Error),
+
+    /// The namespace of a namespace summary request does not exist.
+    #[error("namespace {0} not found")]
+    NamespaceNotFound(String),
+
+    /// A catalog request failed.
+    #[error("catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    /// A tag key or value in the write exceeds the configured maximum
+    /// length.
+    #[error(
+        "tag {key:?} in table {table:?} exceeds the maximum tag key/value length of {max_bytes} bytes"
+    )]
+    TagTooLong {
+        /// The table containing the tag.
+        table: String,
+        /// The offending tag key.
+        key: String,
+        /// The configured maximum length.
+        max_bytes: usize,
+    },
+
+    /// The write contains more points than the configured maximum.
+    #[error("write contains {points} points, more than the maximum of {max_points}")]
+    TooManyPoints {
+        /// The number of points in the write.
+        points: usize,
+        /// The configured maximum number of points.
+        max_points: usize,
+    },
+
+    /// The write contains points timestamped further in the future than the
+    /// configured maximum skew.
+    #[error("{points} point(s) have a timestamp more than {max_skew:?} in the future")]
+    FutureTimestamp {
+        /// The number of offending points.
+        points: usize,
+        /// The configured maximum skew.
+        max_skew: Duration,
+    },
+
+    /// The body of a config reload request is invalid.
+    #[error("invalid config reload request: {0}")]
+    InvalidConfigReload(String),
+
+    /// The request does not carry the token required by the endpoint.
+    #[error("missing or invalid authorization token")]
+    Unauthorized,
 }
 
 impl Error {
@@ -73,25 +203,58 @@ impl Error {
                 StatusCode::NOT_FOUND
             }
             Error::InvalidOrgBucket(_) => StatusCode::BAD_REQUEST,
+            Error::InvalidDbRp(_) => StatusCode::BAD_REQUEST,
             Error::ClientHangup(_) => StatusCode::BAD_REQUEST,
             Error::InvalidGzip(_) => StatusCode::BAD_REQUEST,
             Error::NonUtf8ContentHeader(_) => StatusCode::BAD_REQUEST,
             Error::NonUtf8Body(_) => StatusCode::BAD_REQUEST,
             Error::ParseLineProtocol(_) => StatusCode::BAD_REQUEST,
+            Error::ArrowTableNotSpecified => StatusCode::BAD_REQUEST,
+            Error::DecodeArrow(_) => StatusCode::BAD_REQUEST,
+            Error::ConvertArrow(_) => StatusCode::BAD_REQUEST,
             Error::ParseDelete(_) => StatusCode::BAD_REQUEST,
+            Error::TagTooLong { .. } => StatusCode::BAD_REQUEST,
+            Error::FutureTimestamp { .. } => StatusCode::BAD_REQUEST,
             Error::RequestSizeExceeded(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::TooManyPoints { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
             Error::DmlHandler(DmlError::Schema(_)) => StatusCode::BAD_REQUEST,
             Error::InvalidContentEncoding(_) => {
                 // https://www.rfc-editor.org/rfc/rfc7231#section-6.5.13
                 StatusCode::UNSUPPORTED_MEDIA_TYPE
             }
+            Error::DmlHandler(DmlError::WriteBuffer(ShardError::WriteBufferFull { .. })) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             Error::DmlHandler(DmlError::Internal(_) | DmlError::WriteBuffer(_)) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
+            Error::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            Error::InvalidNamespaceSummaryRequest(_) => StatusCode::BAD_REQUEST,
+            Error::NamespaceNotFound(_) => StatusCode::NOT_FOUND,
+            Error::Catalog(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::InvalidConfigReload(_) => StatusCode::BAD_REQUEST,
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// The duration after which the client may retry the request, returned
+    /// to the client in a `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::DmlHandler(DmlError::WriteBuffer(e)) => e.retry_after(),
+            _ => None,
         }
     }
 }
 
+/// Format `d` as the value of a `Retry-After` header, in whole seconds rounded
+/// up so that a client never retries too early.
+pub fn retry_after_header_value(d: Duration) -> String {
+    let secs = d.as_secs() + u64::from(d.subsec_nanos() > 0);
+    secs.max(1).to_string()
+}
+
 /// Errors returned when decoding the organisation / bucket information from a
 /// HTTP request and deriving the database name from it.
 #[derive(Debug, Error)]
@@ -114,6 +277,11 @@ pub enum OrgBucketError {
 pub struct OrgBucketInfo {
     org: String,
     bucket: String,
+
+    /// The precision of any timestamps in a write request - ignored for
+    /// deletes.
+    #[serde(default)]
+    precision: Precision,
 }
 
 impl<T> TryFrom<&Request<T>> for OrgBucketInfo {
@@ -132,6 +300,114 @@ impl<T> TryFrom<&Request<T>> for OrgBucketInfo {
     }
 }
 
+/// Errors returned when decoding the database / retention policy information
+/// from a v1 write request and deriving the database name from it.
+#[derive(Debug, Error)]
+pub enum DbRpError {
+    /// The request contains no db destination information.
+    #[error("no database destination provided")]
+    NotSpecified,
+
+    /// The request contains invalid parameters.
+    #[error("failed to deserialise db/rp in request: {0}")]
+    DecodeFail(#[from] serde::de::value::Error),
+
+    /// The provided db/rp could not be converted into a database name.
+    #[error(transparent)]
+    MappingFail(#[from] OrgBucketMappingError),
+}
+
+/// The precision of the timestamps in a line protocol write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Precision {
+    #[serde(rename = "s")]
+    Seconds,
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "us", alias = "u")]
+    Microseconds,
+    #[serde(rename = "ns", alias = "n")]
+    Nanoseconds,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self::Nanoseconds
+    }
+}
+
+impl Precision {
+    /// Returns the multiplier to convert to nanosecond timestamps
+    fn timestamp_base(&self) -> i64 {
+        match self {
+            Precision::Seconds => 1_000_000_000,
+            Precision::Milliseconds => 1_000_000,
+            Precision::Microseconds => 1_000,
+            Precision::Nanoseconds => 1,
+        }
+    }
+}
+
+/// The retention policy assumed for v1 writes that do not specify one.
+pub const DEFAULT_V1_RETENTION_POLICY: &str = "autogen";
+
+#[derive(Debug, Deserialize)]
+/// Database & retention policy identifiers for a v1 write.
+///
+/// The db/rp pair is mapped to a namespace in the same way as a v2 org/bucket
+/// pair, with a missing rp defaulting to [`DEFAULT_V1_RETENTION_POLICY`].
+pub struct DbRpInfo {
+    db: String,
+    #[serde(default)]
+    rp: Option<String>,
+    #[serde(default)]
+    precision: Precision,
+}
+
+impl DbRpInfo {
+    /// The retention policy of this write.
+    fn rp(&self) -> &str {
+        match self.rp.as_deref() {
+            None | Some("") => DEFAULT_V1_RETENTION_POLICY,
+            Some(rp) => rp,
+        }
+    }
+}
+
+impl<T> TryFrom<&Request<T>> for DbRpInfo {
+    type Error = DbRpError;
+
+    fn try_from(req: &Request<T>) -> Result<Self, Self::Error> {
+        let query = req.uri().query().ok_or(DbRpError::NotSpecified)?;
+        let got: DbRpInfo = serde_urlencoded::from_str(query)?;
+
+        // An empty db is not acceptable.
+        if got.db.is_empty() {
+            return Err(DbRpError::NotSpecified);
+        }
+
+        Ok(got)
+    }
+}
+
+/// Query string of a namespace summary request.
+#[derive(Debug, Deserialize)]
+struct NamespaceSummaryRequest {
+    namespace: String,
+}
+
+/// The limits of a [`HttpDelegate`] that can be changed while it serves
+/// requests, see [`HttpDelegate::reload_config()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReloadableLimits {
+    /// Maximum size of a request body, in bytes.
+    pub max_request_bytes: usize,
+    /// Maximum length of a tag key or value in a write, in bytes.
+    pub max_tag_bytes: usize,
+    /// Maximum number of points in a write, if limited.
+    pub max_points: Option<usize>,
+}
+
 /// This type is responsible for servicing requests to the `router2` HTTP
 /// endpoint.
 ///
@@ -140,7 +416,14 @@ impl<T> TryFrom<&Request<T>> for OrgBucketInfo {
 /// metrics, pprof, etc.
 #[derive(Debug, Default)]
 pub struct HttpDelegate<D, T = SystemProvider> {
-    max_request_bytes: usize,
+    limits: RwLock<ReloadableLimits>,
+    request_timeout: Option<Duration>,
+    tag_too_long: U64Counter,
+    max_future_skew: Option<(Duration, FutureTimestampMode)>,
+    drain: DrainTracker,
+    catalog: Option<(Arc<dyn Catalog>, String)>,
+    config_reload_token: Option<String>,
+    log_filter: Option<LogFilterHandle>,
     time_provider: T,
     dml_handler: D,
 }
@@ -153,13 +436,213 @@ impl<D> HttpDelegate<D, SystemProvider> {
     /// returning an error if exceeded.
     pub fn new(max_request_bytes: usize, dml_handler: D) -> Self {
         Self {
-            max_request_bytes,
+            limits: RwLock::new(ReloadableLimits {
+                max_request_bytes,
+                max_tag_bytes: DEFAULT_MAX_TAG_BYTES,
+                max_points: None,
+            }),
+            request_timeout: None,
+            tag_too_long: Default::default(),
+            max_future_skew: None,
+            drain: Default::default(),
+            catalog: None,
+            config_reload_token: None,
+            log_filter: None,
             time_provider: SystemProvider::default(),
             dml_handler,
         }
     }
 }
 
+impl<D, T> HttpDelegate<D, T> {
+    /// Limit the time allowed to receive a request body to `timeout`,
+    /// returning [`Error::RequestTimeout`] if exceeded.
+    ///
+    /// By default no timeout is applied.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Reject writes containing a tag key or value longer than `max_bytes`
+    /// with [`Error::TagTooLong`], counting rejected writes in the
+    /// `http_write_tag_too_long` metric registered in `metrics`.
+    ///
+    /// Defaults to [`DEFAULT_MAX_TAG_BYTES`].
+    pub fn with_max_tag_bytes(mut self, max_bytes: usize, metrics: &metric::Registry) -> Self {
+        self.limits.get_mut().max_tag_bytes = max_bytes;
+        self.tag_too_long = metrics
+            .register_metric::<U64Counter>(
+                "http_write_tag_too_long",
+                "number of writes rejected for containing an over-length tag key or value",
+            )
+            .recorder(&[]);
+        self
+    }
+
+    /// Reject writes containing more than `max_points` points (lines of line
+    /// protocol, or rows of an Arrow IPC stream) with
+    /// [`Error::TooManyPoints`].
+    ///
+    /// The limit is enforced while the request body is decoded, bounding the
+    /// size of a write independently of the (possibly compressed) request
+    /// size. By default the number of points is not limited.
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.limits.get_mut().max_points = Some(max_points);
+        self
+    }
+
+    /// Apply `mode` to any point in a write with a timestamp more than
+    /// `max_skew` after the current time.
+    ///
+    /// By default no bound is applied to future timestamps.
+    pub fn with_max_future_skew(mut self, max_skew: Duration, mode: FutureTimestampMode) -> Self {
+        self.max_future_skew = Some((max_skew, mode));
+        self
+    }
+
+    /// Serve the namespace summaries of `catalog` on the
+    /// `GET /api/v2/admin/namespace_summary?namespace=<name>` admin endpoint.
+    ///
+    /// Requests must carry `token` in an `Authorization: Token <token>`
+    /// header, and are rejected with [`Error::Unauthorized`] otherwise.
+    ///
+    /// The endpoint is not served by default.
+    pub fn with_catalog(mut self, catalog: Arc<dyn Catalog>, token: impl Into<String>) -> Self {
+        self.catalog = Some((catalog, token.into()));
+        self
+    }
+
+    /// Serve the `POST /api/v2/admin/reload_config` admin endpoint, applying
+    /// the JSON object of the request body with [`Self::reload_config()`].
+    ///
+    /// Requests must carry `token` in an `Authorization: Token <token>`
+    /// header, and are rejected with [`Error::Unauthorized`] otherwise.
+    ///
+    /// The endpoint is not served by default.
+    pub fn with_config_reload(mut self, token: impl Into<String>) -> Self {
+        self.config_reload_token = Some(token.into());
+        self
+    }
+
+    /// Change the log filter through `handle` when a config reload sets the
+    /// `log_filter` field.
+    ///
+    /// By default the field is ignored.
+    pub fn with_log_filter_reload(mut self, handle: LogFilterHandle) -> Self {
+        self.log_filter = Some(handle);
+        self
+    }
+
+    /// Use `time_provider` to determine the current time.
+    pub fn with_time_provider<U>(self, time_provider: U) -> HttpDelegate<D, U> {
+        HttpDelegate {
+            limits: self.limits,
+            request_timeout: self.request_timeout,
+            tag_too_long: self.tag_too_long,
+            max_future_skew: self.max_future_skew,
+            drain: self.drain,
+            catalog: self.catalog,
+            config_reload_token: self.config_reload_token,
+            log_filter: self.log_filter,
+            time_provider,
+            dml_handler: self.dml_handler,
+        }
+    }
+
+    /// Reject all new requests with [`Error::ShuttingDown`] and wait up to
+    /// `timeout` for in-flight requests to complete.
+    ///
+    /// Returns true if all in-flight requests completed before the deadline.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.drain.drain(timeout).await
+    }
+
+    /// Return the limits currently applied to requests.
+    pub fn limits(&self) -> ReloadableLimits {
+        *self.limits.read()
+    }
+
+    /// Apply the config `fields`, named as in the server config, to all
+    /// requests from now on, and return the resulting config and the ignored
+    /// fields as a JSON object.
+    ///
+    /// The [`ReloadableLimits`] are set by the `max_http_request_size`,
+    /// `max_tag_bytes` and `max_points_per_request` fields, and the log filter
+    /// by the `log_filter` field if [`Self::with_log_filter_reload()`] was
+    /// set. Absent fields are left unchanged. Any other field cannot be
+    /// changed without a restart and is ignored.
+    ///
+    /// If any field is invalid, [`Error::InvalidConfigReload`] is returned
+    /// and nothing is changed. Requests already being handled may observe
+    /// either the old or the new limits, but never a mix of both for a single
+    /// limit check.
+    pub fn reload_config(
+        &self,
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        // Validate all the fields before applying any of them, holding the
+        // lock so that concurrent reloads do not overwrite each other
+        let mut current = self.limits.write();
+        let mut limits = *current;
+        let mut log_filter = None;
+        let mut ignored = vec![];
+        for (key, value) in fields {
+            match (key.as_str(), &self.log_filter) {
+                ("max_http_request_size", _) => {
+                    limits.max_request_bytes = parse_limit(&key, &value)?
+                }
+                ("max_tag_bytes", _) => limits.max_tag_bytes = parse_limit(&key, &value)?,
+                ("max_points_per_request", _) if value.is_null() => limits.max_points = None,
+                ("max_points_per_request", _) => {
+                    limits.max_points = Some(parse_limit(&key, &value)?)
+                }
+                ("log_filter", Some(_)) => match value {
+                    serde_json::Value::String(filter) => log_filter = Some(filter),
+                    _ => {
+                        return Err(Error::InvalidConfigReload(format!(
+                            "log_filter must be a string, got {}",
+                            value
+                        )))
+                    }
+                },
+                _ => {
+                    warn!(field=%key, "ignoring config field that cannot be reloaded");
+                    ignored.push(key);
+                }
+            }
+        }
+
+        // Changing the log filter is the only step that can fail, so it is
+        // done before the limits change
+        if let (Some(filter), Some(handle)) = (&log_filter, &self.log_filter) {
+            handle
+                .reload(filter)
+                .map_err(|e| Error::InvalidConfigReload(e.to_string()))?;
+            info!(%filter, "reloaded log filter");
+        }
+        info!(old=?*current, new=?limits, "reloading http limits");
+        *current = limits;
+        drop(current);
+
+        let mut config = self.reloadable_config();
+        config["ignored"] = serde_json::json!(ignored);
+        Ok(config)
+    }
+
+    /// Return the current values of the config fields that
+    /// [`Self::reload_config()`] can change, as a JSON object.
+    pub fn reloadable_config(&self) -> serde_json::Value {
+        let limits = self.limits();
+        serde_json::json!({
+            "max_http_request_size": limits.max_request_bytes,
+            "max_tag_bytes": limits.max_tag_bytes,
+            "max_points_per_request": limits.max_points,
+            "log_filter": self.log_filter.as_ref().and_then(|h| h.current()),
+        })
+    }
+}
+
 impl<D, T> HttpDelegate<D, T>
 where
     D: DmlHandler,
@@ -167,23 +650,158 @@ where
 {
     /// Routes `req` to the appropriate handler, if any, returning the handler
     /// response.
+    ///
+    /// All logs emitted while handling `req` are recorded within a span
+    /// containing the request ID read from the [`REQUEST_ID_HEADER`], or a
+    /// newly generated ID if the header is not set.
+    ///
+    /// Errors from the v1 `/write` endpoint are returned as a response with a
+    /// v1-style JSON error body, rather than as an [`Error`].
+    ///
+    /// A successful write responds with `204 No Content`, unless points were
+    /// dropped for falling outside the retention period of the namespace or
+    /// had a future timestamp clamped, in which case it responds with `200 OK`
+    /// and a JSON body containing the number of affected points.
     pub async fn route(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let request_id = request_id(&req);
+        let span = info_span!("router2_request", %request_id);
+
         match (req.method(), req.uri().path()) {
-            (&Method::POST, "/api/v2/write") => self.write_handler(req).await,
-            (&Method::POST, "/api/v2/delete") => self.delete_handler(req).await,
-            _ => return Err(Error::NoHandler),
+            (&Method::POST, "/write") => {
+                return Ok(match self.write_v1_handler(req).instrument(span).await {
+                    Ok(summary) => response_write(summary, &request_id),
+                    Err(e) => response_v1_error(&e, &request_id),
+                })
+            }
+            (&Method::POST, "/api/v2/write") => self
+                .write_handler(req)
+                .instrument(span)
+                .await
+                .map(|summary| response_write(summary, &request_id)),
+            (&Method::POST, "/api/v2/delete") => self
+                .delete_handler(req)
+                .instrument(span)
+                .await
+                .map(|_| response_no_content(&request_id)),
+            (&Method::GET, "/api/v2/admin/namespace_summary") => self
+                .namespace_summary_handler(req)
+                .instrument(span)
+                .await
+                .map(|body| response_json(body, &request_id)),
+            (&Method::POST, "/api/v2/admin/reload_config") => self
+                .reload_config_handler(req)
+                .instrument(span)
+                .await
+                .map(|body| response_json(body, &request_id)),
+            _ => Err(Error::NoHandler),
         }
-        .map(|_| response_no_content())
     }
 
-    async fn write_handler(&self, req: Request<Body>) -> Result<(), Error> {
-        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+    /// Return the table, column and parquet file counts of the namespace
+    /// named in the query string of `req` as a JSON object.
+    async fn namespace_summary_handler(
+        &self,
+        req: Request<Body>,
+    ) -> Result<serde_json::Value, Error> {
+        let (catalog, token) = self.catalog.as_ref().ok_or(Error::NoHandler)?;
+        if !authorized(&req, token) {
+            return Err(Error::Unauthorized);
+        }
+
+        let query = req.uri().query().unwrap_or_default();
+        let request: NamespaceSummaryRequest =
+            serde_urlencoded::from_str(query).map_err(Error::InvalidNamespaceSummaryRequest)?;
+
+        let namespace = catalog
+            .namespaces()
+            .get_by_name(&request.namespace)
+            .await?
+            .ok_or_else(|| Error::NamespaceNotFound(request.namespace.clone()))?;
+        let summary = catalog.namespaces().summary(namespace.id).await?;
+
+        Ok(serde_json::json!({
+            "namespace": namespace.name,
+            "table_count": summary.table_count,
+            "column_count": summary.column_count,
+            "parquet_file_count": summary.parquet_file_count,
+            "total_bytes": summary.total_bytes,
+        }))
+    }
+
+    /// Apply the config in the JSON object body of `req`, see
+    /// [`Self::with_config_reload()`], and return the resulting config and
+    /// ignored fields as a JSON object.
+    async fn reload_config_handler(&self, req: Request<Body>) -> Result<serde_json::Value, Error> {
+        let token = self.config_reload_token.as_ref().ok_or(Error::NoHandler)?;
+        if !authorized(&req, token) {
+            return Err(Error::Unauthorized);
+        }
+
+        // The request size cap being reloaded does not apply, so that a cap
+        // set too low can still be raised
+        let body = self
+            .read_body_with_limit(req, MAX_CONFIG_RELOAD_BYTES)
+            .await?;
+        let fields: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&body).map_err(|e| Error::InvalidConfigReload(e.to_string()))?;
+
+        self.reload_config(fields)
+    }
+
+    async fn write_handler(&self, req: Request<Body>) -> Result<WriteSummary, Error> {
+        let _in_flight = self.drain.enter().ok_or(Error::ShuttingDown)?;
 
         let account = OrgBucketInfo::try_from(&req)?;
         let namespace = org_and_bucket_to_database(&account.org, &account.bucket)
             .map_err(OrgBucketError::MappingFail)?;
 
-        trace!(org=%account.org, bucket=%account.bucket, %namespace, "processing write request");
+        trace!(
+            org=%account.org,
+            bucket=%account.bucket,
+            precision=?account.precision,
+            %namespace,
+            "processing write request"
+        );
+
+        let is_arrow = req
+            .headers()
+            .get(&CONTENT_TYPE)
+            .map(|v| v == ARROW_STREAM_CONTENT_TYPE)
+            .unwrap_or(false);
+        if is_arrow {
+            return self.write_arrow(namespace, req).await;
+        }
+
+        self.write_lp(namespace, account.precision, req).await
+    }
+
+    async fn write_v1_handler(&self, req: Request<Body>) -> Result<WriteSummary, Error> {
+        let _in_flight = self.drain.enter().ok_or(Error::ShuttingDown)?;
+
+        let info = DbRpInfo::try_from(&req)?;
+        let namespace =
+            org_and_bucket_to_database(&info.db, info.rp()).map_err(DbRpError::MappingFail)?;
+
+        trace!(
+            db=%info.db,
+            rp=%info.rp(),
+            precision=?info.precision,
+            %namespace,
+            "processing v1 write request"
+        );
+
+        self.write_lp(namespace, info.precision, req).await
+    }
+
+    /// Parse the line protocol body of `req`, scaling timestamps from
+    /// `precision` to nanoseconds, and pass the result to the DML handler.
+    async fn write_lp(
+        &self,
+        namespace: DatabaseName<'static>,
+        precision: Precision,
+        req: Request<Body>,
+    ) -> Result<WriteSummary, Error> {
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
 
         // Read the HTTP body and convert it to a str.
         let body = self.read_body(req).await?;
@@ -193,34 +811,136 @@ where
         // contain a timestamp
         let default_time = self.time_provider.now().timestamp_nanos();
 
-        let (batches, stats) = match mutable_batch_lp::lines_to_batches_stats(body, default_time) {
+        let mut converter = LinesConverter::new(default_time);
+        converter.set_timestamp_base(precision.timestamp_base());
+        if let Some(max_points) = self.limits().max_points {
+            converter.set_max_lines(max_points);
+        }
+        let (mut batches, stats) = match converter.write_lp(body).and_then(|_| converter.finish()) {
             Ok(v) => v,
             Err(mutable_batch_lp::Error::EmptyPayload) => {
                 debug!("nothing to write");
-                return Ok(());
+                return Ok(WriteSummary::default());
+            }
+            Err(mutable_batch_lp::Error::TooManyLines { lines, max }) => {
+                debug!(
+                    %namespace,
+                    points=lines,
+                    max_points=max,
+                    "rejecting write with too many points"
+                );
+                return Err(Error::TooManyPoints {
+                    points: lines,
+                    max_points: max,
+                });
             }
             Err(e) => return Err(Error::ParseLineProtocol(e)),
         };
 
+        if let Err(e) = check_tag_lengths(&batches, self.limits().max_tag_bytes) {
+            debug!(error=%e, %namespace, "rejecting write with over-length tag");
+            self.tag_too_long.inc(1);
+            return Err(e);
+        }
+        let clamped_future_timestamps = self.apply_max_future_skew(&mut batches, &namespace)?;
+
         debug!(
             num_lines=stats.num_lines,
             num_fields=stats.num_fields,
             body_size=body.len(),
             %namespace,
-            org=%account.org,
-            bucket=%account.bucket,
             "routing write",
         );
 
-        self.dml_handler
+        let summary = self
+            .dml_handler
             .write(namespace, batches, span_ctx)
             .await
             .map_err(Into::into)?;
 
-        Ok(())
+        Ok(WriteSummary {
+            clamped_future_timestamps,
+            ..summary
+        })
+    }
+
+    /// Decode the Arrow IPC stream body of `req` into a write to the table
+    /// named by the [`ARROW_TABLE_HEADER`], and pass it to the DML handler.
+    ///
+    /// Timestamps must be nanoseconds since the epoch, the `precision` of the
+    /// request is not applied.
+    async fn write_arrow(
+        &self,
+        namespace: DatabaseName<'static>,
+        req: Request<Body>,
+    ) -> Result<WriteSummary, Error> {
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+
+        let table = req
+            .headers()
+            .get(ARROW_TABLE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .ok_or(Error::ArrowTableNotSpecified)?
+            .to_string();
+
+        let body = self.read_body(req).await?;
+        let mut reader = StreamReader::try_new(&body[..]).map_err(Error::DecodeArrow)?;
+
+        let max_points = self.limits().max_points;
+        let mut batch = MutableBatch::new();
+        let mut num_batches = 0;
+        while let Some(record_batch) = reader.next() {
+            let record_batch = record_batch.map_err(Error::DecodeArrow)?;
+            if let Some(max_points) = max_points {
+                if batch.rows() + record_batch.num_rows() > max_points {
+                    // Count the points of the remaining batches without converting them
+                    let mut points = batch.rows() + record_batch.num_rows();
+                    for record_batch in reader {
+                        points += record_batch.map_err(Error::DecodeArrow)?.num_rows();
+                    }
+                    debug!(%namespace, points, max_points, "rejecting write with too many points");
+                    return Err(Error::TooManyPoints { points, max_points });
+                }
+            }
+            write_record_batch(&mut batch, &record_batch).map_err(Error::ConvertArrow)?;
+            num_batches += 1;
+        }
+
+        if batch.rows() == 0 {
+            debug!("nothing to write");
+            return Ok(WriteSummary::default());
+        }
+
+        let mut batches: HashMap<_, _> = std::iter::once((table, batch)).collect();
+        if let Err(e) = check_tag_lengths(&batches, self.limits().max_tag_bytes) {
+            debug!(error=%e, %namespace, "rejecting write with over-length tag");
+            self.tag_too_long.inc(1);
+            return Err(e);
+        }
+        let clamped_future_timestamps = self.apply_max_future_skew(&mut batches, &namespace)?;
+
+        debug!(
+            num_batches,
+            body_size=body.len(),
+            %namespace,
+            "routing arrow write",
+        );
+
+        let summary = self
+            .dml_handler
+            .write(namespace, batches, span_ctx)
+            .await
+            .map_err(Into::into)?;
+
+        Ok(WriteSummary {
+            clamped_future_timestamps,
+            ..summary
+        })
     }
 
     async fn delete_handler(&self, req: Request<Body>) -> Result<(), Error> {
+        let _in_flight = self.drain.enter().ok_or(Error::ShuttingDown)?;
         let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
 
         let account = OrgBucketInfo::try_from(&req)?;
@@ -261,9 +981,63 @@ where
         Ok(())
     }
 
+    /// Apply the configured maximum future skew, if any, to `batches`.
+    ///
+    /// Returns the number of points with a clamped timestamp, or
+    /// [`Error::FutureTimestamp`] if the write is rejected.
+    fn apply_max_future_skew(
+        &self,
+        batches: &mut HashMap<String, MutableBatch>,
+        namespace: &DatabaseName<'static>,
+    ) -> Result<usize, Error> {
+        let (max_skew, mode) = match self.max_future_skew {
+            Some(v) => v,
+            None => return Ok(0),
+        };
+
+        let skew_nanos = i64::try_from(max_skew.as_nanos()).unwrap_or(i64::MAX);
+        let max_time = self
+            .time_provider
+            .now()
+            .timestamp_nanos()
+            .saturating_add(skew_nanos);
+
+        match mode {
+            FutureTimestampMode::Reject => {
+                let points = count_future_timestamps(batches, max_time);
+                if points > 0 {
+                    debug!(%namespace, points, "rejecting write with future timestamps");
+                    return Err(Error::FutureTimestamp { points, max_skew });
+                }
+                Ok(0)
+            }
+            FutureTimestampMode::Clamp => {
+                let clamped = batches
+                    .values_mut()
+                    .map(|batch| batch.clamp_timestamps(max_time))
+                    .sum::<usize>();
+                if clamped > 0 {
+                    debug!(%namespace, clamped, "clamped future timestamps");
+                }
+                Ok(clamped)
+            }
+        }
+    }
+
     /// Parse the request's body into raw bytes, applying the configured size
     /// limits and decoding any content encoding.
     async fn read_body(&self, req: hyper::Request<Body>) -> Result<Bytes, Error> {
+        self.read_body_with_limit(req, self.limits().max_request_bytes)
+            .await
+    }
+
+    /// Parse the request's body into raw bytes, limiting its size to
+    /// `max_request_bytes` and decoding any content encoding.
+    async fn read_body_with_limit(
+        &self,
+        req: hyper::Request<Body>,
+        max_request_bytes: usize,
+    ) -> Result<Bytes, Error> {
         let encoding = req
             .headers()
             .get(&CONTENT_ENCODING)
@@ -277,16 +1051,28 @@ where
 
         let mut payload = req.into_body();
 
-        let mut body = BytesMut::new();
-        while let Some(chunk) = payload.next().await {
-            let chunk = chunk.map_err(Error::ClientHangup)?;
-            // limit max size of in-memory payload
-            if (body.len() + chunk.len()) > self.max_request_bytes {
-                return Err(Error::RequestSizeExceeded(self.max_request_bytes));
+        let read = async {
+            let mut body = BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk.map_err(Error::ClientHangup)?;
+                // limit max size of in-memory payload
+                if (body.len() + chunk.len()) > max_request_bytes {
+                    return Err(Error::RequestSizeExceeded(max_request_bytes));
+                }
+                body.extend_from_slice(&chunk);
             }
-            body.extend_from_slice(&chunk);
-        }
-        let body = body.freeze();
+            Ok::<_, Error>(body.freeze())
+        };
+
+        // Bound the time a (potentially slow) client may take to send the
+        // body - an oversized body is still rejected as soon as the limit is
+        // crossed, before the deadline.
+        let body = match self.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, read)
+                .await
+                .map_err(|_| Error::RequestTimeout(timeout))??,
+            None => read.await?,
+        };
 
         // If the body is not compressed, return early.
         if !ungzip {
@@ -303,7 +1089,7 @@ where
         // In order to detect if the entire stream ahs been read, or truncated,
         // read an extra byte beyond the limit and check the resulting data
         // length - see the max_request_size_truncation test.
-        let mut decoder = decoder.take(self.max_request_bytes as u64 + 1);
+        let mut decoder = decoder.take(max_request_bytes as u64 + 1);
         let mut decoded_data = Vec::new();
         decoder
             .read_to_end(&mut decoded_data)
@@ -311,21 +1097,150 @@ where
 
         // If the length is max_size+1, the body is at least max_size+1 bytes in
         // length, and possibly longer, but truncated.
-        if decoded_data.len() > self.max_request_bytes {
-            return Err(Error::RequestSizeExceeded(self.max_request_bytes));
+        if decoded_data.len() > max_request_bytes {
+            return Err(Error::RequestSizeExceeded(max_request_bytes));
         }
 
         Ok(decoded_data.into())
     }
 }
 
-fn response_no_content() -> Response<Body> {
+/// Compares `a` and `b` in a time independent of the position of the first
+/// difference, so that a token cannot be guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Parse the value of the limit `key` of a config reload request.
+fn parse_limit(key: &str, value: &serde_json::Value) -> Result<usize, Error> {
+    value
+        .as_u64()
+        .and_then(|v| usize::try_from(v).ok())
+        .ok_or_else(|| {
+            Error::InvalidConfigReload(format!(
+                "{} must be a non-negative integer, got {}",
+                key, value
+            ))
+        })
+}
+
+/// Returns [`Error::TagTooLong`] if any tag key or value in `batches` is
+/// longer than `max_bytes`.
+fn check_tag_lengths(
+    batches: &HashMap<String, MutableBatch>,
+    max_bytes: usize,
+) -> Result<(), Error> {
+    for (table, batch) in batches {
+        for (key, column) in batch.columns() {
+            let dictionary = match column.data() {
+                ColumnData::Tag(_, dictionary, _) => dictionary,
+                _ => continue,
+            };
+
+            if key.len() > max_bytes || dictionary.values().iter().any(|v| v.len() > max_bytes) {
+                return Err(Error::TagTooLong {
+                    table: table.clone(),
+                    key: key.clone(),
+                    max_bytes,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of points in `batches` with a timestamp after
+/// `max_time` nanoseconds since the epoch.
+fn count_future_timestamps(batches: &HashMap<String, MutableBatch>, max_time: i64) -> usize {
+    let mut points = 0;
+    for batch in batches.values() {
+        if let Ok(ColumnData::I64(times, _)) = batch.column(TIME_COLUMN_NAME).map(|c| c.data()) {
+            points += times.iter().filter(|t| **t > max_time).count();
+        }
+    }
+    points
+}
+
+/// Read the request ID from the [`REQUEST_ID_HEADER`] of `req`, or generate a
+/// new ID if it is not set (or is not valid utf8).
+fn request_id<T>(req: &Request<T>) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(ToString::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Build the response to a successful write.
+///
+/// If any points were dropped from the write for being outside the retention
+/// period of the namespace, or had their future timestamp clamped, the number
+/// of affected points is returned in a JSON body, otherwise the response has
+/// no content.
+/// Return true if `req` carries `token` in an `Authorization: Token <token>`
+/// header.
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Token "))
+        .map(|v| constant_time_eq(v.as_bytes(), token.as_bytes()))
+        .unwrap_or(false)
+}
+
+fn response_write(summary: WriteSummary, request_id: &str) -> Response<Body> {
+    if summary.dropped_out_of_retention == 0 && summary.clamped_future_timestamps == 0 {
+        return response_no_content(request_id);
+    }
+
+    let body = serde_json::json!({
+        "dropped_out_of_retention": summary.dropped_out_of_retention,
+        "clamped_future_timestamps": summary.clamped_future_timestamps,
+    })
+    .to_string();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(REQUEST_ID_HEADER, request_id)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn response_json(body: serde_json::Value, request_id: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(REQUEST_ID_HEADER, request_id)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn response_no_content(request_id: &str) -> Response<Body> {
     Response::builder()
         .status(StatusCode::NO_CONTENT)
+        .header(REQUEST_ID_HEADER, request_id)
         .body(Body::empty())
         .unwrap()
 }
 
+/// Build a response for `error` with the `{"error": "..."}` JSON body returned
+/// by the InfluxDB v1 API.
+fn response_v1_error(error: &Error, request_id: &str) -> Response<Body> {
+    debug!(%error, %request_id, "v1 write request failed");
+
+    let body = serde_json::json!({ "error": error.to_string() }).to_string();
+    let mut response = Response::builder()
+        .status(error.as_status_code())
+        .header(CONTENT_TYPE, "application/json")
+        .header(REQUEST_ID_HEADER, request_id);
+    if let Some(d) = error.retry_after() {
+        response = response.header(RETRY_AFTER, retry_after_header_value(d));
+    }
+    response.body(Body::from(body)).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use std::{io::Write, iter, sync::Arc};
@@ -334,8 +1249,21 @@ mod tests {
 
     use flate2::{write::GzEncoder, Compression};
     use hyper::header::HeaderValue;
+    use metric::{Attributes, Metric};
+    use time::{MockProvider, Time};
+
+    use mutable_batch::column::ColumnData;
 
-    use crate::dml_handlers::mock::{MockDmlHandler, MockDmlHandlerCall};
+    use write_buffer::mock::MockBufferForWritingThatBlocks;
+
+    use crate::{
+        dml_handlers::{
+            mock::{MockDmlHandler, MockDmlHandlerCall},
+            ShardedWriteBuffer,
+        },
+        sequencer::Sequencer,
+        sharder::TableNamespaceSharder,
+    };
 
     use super::*;
 
@@ -548,6 +1476,24 @@ mod tests {
         want_dml_calls = [] // None
     );
 
+    test_write_handler!(
+        duplicate_field,
+        query_string = "?org=bananas&bucket=test",
+        body = "platanos val=42i,val=24i 123456".as_bytes(),
+        dml_handler = [Ok(())],
+        want_result = Err(Error::ParseLineProtocol(_)),
+        want_dml_calls = [] // None
+    );
+
+    test_write_handler!(
+        timestamp_overflow,
+        query_string = "?org=bananas&bucket=test&precision=s",
+        body = "platanos val=42i 9223372036854775807".as_bytes(),
+        dml_handler = [Ok(())],
+        want_result = Err(Error::ParseLineProtocol(_)),
+        want_dml_calls = [] // None
+    );
+
     test_write_handler!(
         non_utf8_body,
         query_string = "?org=bananas&bucket=test",
@@ -585,6 +1531,70 @@ mod tests {
         want_dml_calls = [] // None
     );
 
+    test_write_handler!(
+        precision_ms,
+        query_string = "?org=bananas&bucket=test&precision=ms",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [Ok(())],
+        want_result = Ok(_),
+        want_dml_calls = [MockDmlHandlerCall::Write{namespace, batches}] => {
+            assert_eq!(namespace, "bananas_test");
+            assert_matches!(batches["platanos"].column("time").unwrap().data(), ColumnData::I64(v, _) => {
+                assert_eq!(v, &[123_456_000_000]);
+            });
+        }
+    );
+
+    test_write_handler!(
+        precision_s,
+        query_string = "?org=bananas&bucket=test&precision=s",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [Ok(())],
+        want_result = Ok(_),
+        want_dml_calls = [MockDmlHandlerCall::Write{namespace, batches}] => {
+            assert_eq!(namespace, "bananas_test");
+            assert_matches!(batches["platanos"].column("time").unwrap().data(), ColumnData::I64(v, _) => {
+                assert_eq!(v, &[123_456_000_000_000]);
+            });
+        }
+    );
+
+    test_write_handler!(
+        invalid_precision,
+        query_string = "?org=bananas&bucket=test&precision=decades",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [Ok(())],
+        want_result = Err(Error::InvalidOrgBucket(OrgBucketError::DecodeFail(_))),
+        want_dml_calls = [] // None
+    );
+
+    #[tokio::test]
+    async fn test_write_precision_default_time() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        // One line with an explicit timestamp, and one relying on the server
+        // time, which must not be scaled by the precision.
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test&precision=s")
+            .method("POST")
+            .body(Body::from(
+                "platanos,tag1=A val=42i 123456\nplatanos,tag1=B val=24i",
+            ))
+            .unwrap();
+
+        let before = SystemProvider::default().now().timestamp_nanos();
+        delegate.route(request).await.expect("write should succeed");
+        let after = SystemProvider::default().now().timestamp_nanos();
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{batches, ..}] => {
+            assert_matches!(batches["platanos"].column("time").unwrap().data(), ColumnData::I64(v, _) => {
+                assert_eq!(v[0], 123_456_000_000_000);
+                assert!((before..=after).contains(&v[1]));
+            });
+        });
+    }
+
     test_write_handler!(
         db_not_found,
         query_string = "?org=bananas&bucket=test",
@@ -700,6 +1710,445 @@ mod tests {
         }
     );
 
+    #[tokio::test]
+    async fn test_request_id() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(()), Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        // A caller-provided request ID is echoed in the response.
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .header(REQUEST_ID_HEADER, "bananas-42")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = delegate.route(request).await.expect("write should succeed");
+        assert_eq!(got.headers().get(REQUEST_ID_HEADER).unwrap(), "bananas-42");
+
+        // Otherwise a request ID is generated.
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = delegate.route(request).await.expect("write should succeed");
+        let id = got
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        Uuid::parse_str(id).expect("generated request ID should be a UUID");
+    }
+
+    #[tokio::test]
+    async fn test_write_request_timeout() {
+        const TIMEOUT: Duration = Duration::from_millis(10);
+
+        // A body that sends a partial line and then stalls without closing
+        // the stream.
+        let (mut tx, body) = Body::channel();
+        tx.send_data("platanos,tag1=A".into()).await.unwrap();
+
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .body(body)
+            .unwrap();
+
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate =
+            HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler)).with_request_timeout(TIMEOUT);
+
+        let got = delegate.route(request).await;
+        assert_matches!(got, Err(Error::RequestTimeout(t)) => {
+            assert_eq!(t, TIMEOUT);
+        });
+        assert_eq!(
+            got.unwrap_err().as_status_code(),
+            StatusCode::REQUEST_TIMEOUT
+        );
+        assert!(dml_handler.calls().is_empty());
+
+        // Keep the sender alive until the request has timed out.
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        const URI: &str = "https://bananas.example/api/v2/write?org=bananas&bucket=test";
+
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        // Start a write that stalls part way through sending the body.
+        let (mut tx, body) = Body::channel();
+        tx.send_data("platanos,tag1=A,tag2=B".into()).await.unwrap();
+        let request = Request::builder()
+            .uri(URI)
+            .method("POST")
+            .body(body)
+            .unwrap();
+
+        let in_flight = delegate.route(request);
+        tokio::pin!(in_flight);
+        assert!(futures::poll!(&mut in_flight).is_pending());
+
+        // Begin draining, which must wait for the in-flight write.
+        let drain = delegate.drain(Duration::from_secs(5));
+        tokio::pin!(drain);
+        assert!(futures::poll!(&mut drain).is_pending());
+
+        // A new write is rejected.
+        let request = Request::builder()
+            .uri(URI)
+            .method("POST")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+        let got = delegate.route(request).await;
+        assert_matches!(got, Err(Error::ShuttingDown));
+        assert_eq!(
+            got.unwrap_err().as_status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        // The in-flight write is allowed to complete.
+        tx.send_data(" val=42i 123456".into()).await.unwrap();
+        drop(tx);
+        let got = in_flight.await.expect("in-flight write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+        assert!(drain.await, "drain should complete before the deadline");
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{namespace, ..}] => {
+            assert_eq!(namespace, "bananas_test");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_max_tag_bytes() {
+        const MAX_TAG_BYTES: usize = 4;
+
+        let metrics = metric::Registry::default();
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler))
+            .with_max_tag_bytes(MAX_TAG_BYTES, &metrics);
+
+        let write = |body: &'static str| {
+            let request = Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST")
+                .body(Body::from(body))
+                .unwrap();
+            delegate.route(request)
+        };
+
+        // A tag value at the limit is accepted
+        let got = write("platanos,tag1=AAAA val=42i 123456")
+            .await
+            .expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+        assert_matches!(
+            dml_handler.calls().as_slice(),
+            [MockDmlHandlerCall::Write { .. }]
+        );
+
+        // A tag value over the limit is rejected, naming the tag key
+        let got = write("platanos,tag1=A,tag2=BBBBB val=42i 123456").await;
+        assert_matches!(&got, Err(Error::TagTooLong { table, key, max_bytes }) => {
+            assert_eq!(table, "platanos");
+            assert_eq!(key, "tag2");
+            assert_eq!(*max_bytes, MAX_TAG_BYTES);
+        });
+        let err = got.unwrap_err();
+        assert_eq!(err.as_status_code(), StatusCode::BAD_REQUEST);
+        assert!(err.to_string().contains("\"tag2\""));
+
+        // As is a tag key over the limit
+        let got = write("platanos,longtag=A val=42i 123456").await;
+        assert_matches!(got, Err(Error::TagTooLong { key, .. }) => {
+            assert_eq!(key, "longtag");
+        });
+
+        // Neither rejected write reaches the DML handler
+        assert_eq!(dml_handler.calls().len(), 1);
+
+        let rejected = metrics
+            .get_instrument::<Metric<U64Counter>>("http_write_tag_too_long")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(rejected, 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_max_points() {
+        const MAX_POINTS: usize = 3;
+
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate =
+            HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler)).with_max_points(MAX_POINTS);
+
+        let write = |body: &'static str| {
+            let request = Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST")
+                .body(Body::from(body))
+                .unwrap();
+            delegate.route(request)
+        };
+
+        // A write at the limit is accepted
+        let got = write("platanos val=1i 1\nplatanos val=2i 2\nbananas val=3i 3")
+            .await
+            .expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+
+        // A write over the limit is rejected, naming its size and the limit
+        let got =
+            write("platanos val=1i 1\nplatanos val=2i 2\nbananas val=3i 3\nbananas val=4i 4").await;
+        assert_matches!(&got, Err(Error::TooManyPoints { points, max_points }) => {
+            assert_eq!(*points, 4);
+            assert_eq!(*max_points, MAX_POINTS);
+        });
+        let err = got.unwrap_err();
+        assert_eq!(err.as_status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            err.to_string(),
+            "write contains 4 points, more than the maximum of 3"
+        );
+
+        // The rejected write does not reach the DML handler
+        assert_eq!(dml_handler.calls().len(), 1);
+    }
+
+    const NANOS_PER_SECOND: i64 = 1_000_000_000;
+    const NOW: i64 = 1_600_000_000 * NANOS_PER_SECOND;
+    const YEAR: i64 = 365 * 24 * 60 * 60 * NANOS_PER_SECOND;
+    const MAX_FUTURE_SKEW: Duration = Duration::from_secs(60 * 60);
+
+    fn future_skew_delegate(
+        mode: FutureTimestampMode,
+        dml_handler: &Arc<MockDmlHandler>,
+    ) -> HttpDelegate<Arc<MockDmlHandler>, MockProvider> {
+        HttpDelegate::new(MAX_BYTES, Arc::clone(dml_handler))
+            .with_max_future_skew(MAX_FUTURE_SKEW, mode)
+            .with_time_provider(MockProvider::new(Time::from_timestamp_nanos(NOW)))
+    }
+
+    fn future_skew_request() -> Request<Body> {
+        Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .body(Body::from(format!(
+                "platanos val=1i {}\nplatanos val=2i {}",
+                NOW,
+                NOW + YEAR
+            )))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_max_future_skew_reject() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = future_skew_delegate(FutureTimestampMode::Reject, &dml_handler);
+
+        let got = delegate.route(future_skew_request()).await;
+        assert_matches!(&got, Err(Error::FutureTimestamp { points, max_skew }) => {
+            assert_eq!(*points, 1);
+            assert_eq!(*max_skew, MAX_FUTURE_SKEW);
+        });
+        assert_eq!(got.unwrap_err().as_status_code(), StatusCode::BAD_REQUEST);
+        assert!(dml_handler.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_max_future_skew_clamp() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = future_skew_delegate(FutureTimestampMode::Clamp, &dml_handler);
+
+        let got = delegate
+            .route(future_skew_request())
+            .await
+            .expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(got.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["clamped_future_timestamps"], 1);
+        assert_eq!(body["dropped_out_of_retention"], 0);
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{batches, ..}] => {
+            let time = batches["platanos"].column("time").unwrap().data();
+            assert_matches!(time, ColumnData::I64(v, _) => {
+                let max_time = NOW + MAX_FUTURE_SKEW.as_nanos() as i64;
+                assert_eq!(v, &[NOW, max_time]);
+            });
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_max_future_skew_disabled() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler))
+            .with_time_provider(MockProvider::new(Time::from_timestamp_nanos(NOW)));
+
+        let got = delegate
+            .route(future_skew_request())
+            .await
+            .expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{batches, ..}] => {
+            let time = batches["platanos"].column("time").unwrap().data();
+            assert_matches!(time, ColumnData::I64(v, _) => {
+                assert_eq!(v, &[NOW, NOW + YEAR]);
+            });
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_v1() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = Request::builder()
+            .uri("https://bananas.example/write?db=bananas&rp=test&precision=s")
+            .method("POST")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = delegate.route(request).await.expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{namespace, batches}] => {
+            assert_eq!(namespace, "bananas_test");
+            let time = batches["platanos"].column("time").unwrap().data();
+            assert_matches!(time, ColumnData::I64(v, _) => {
+                assert_eq!(v, &[123456 * 1_000_000_000]);
+            });
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_v1_default_rp() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = Request::builder()
+            .uri("https://bananas.example/write?db=bananas")
+            .method("POST")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = delegate.route(request).await.expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+
+        assert_matches!(dml_handler.calls().as_slice(), [MockDmlHandlerCall::Write{namespace, ..}] => {
+            assert_eq!(namespace, "bananas_autogen");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_v1_error_body() {
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = Request::builder()
+            .uri("https://bananas.example/write?db=&rp=test")
+            .method("POST")
+            .header(REQUEST_ID_HEADER, "bananas-42")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = delegate
+            .route(request)
+            .await
+            .expect("v1 errors should be returned as a response");
+        assert_eq!(got.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(got.headers().get(REQUEST_ID_HEADER).unwrap(), "bananas-42");
+
+        let body = hyper::body::to_bytes(got.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "no database destination provided" })
+        );
+
+        assert!(dml_handler.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_v1_write_buffer_full() {
+        // A write buffer that never accepts a write, behind a sharded write
+        // buffer that gives up after a short timeout
+        let shard = Arc::new(Sequencer::new(
+            0,
+            Arc::new(MockBufferForWritingThatBlocks::default()),
+        ));
+        let dml_handler = ShardedWriteBuffer::new(TableNamespaceSharder::new([shard]))
+            .with_enqueue_timeout(Duration::from_millis(10));
+        let delegate = HttpDelegate::new(MAX_BYTES, dml_handler);
+
+        let request = Request::builder()
+            .uri("https://bananas.example/write?db=bananas&rp=test")
+            .method("POST")
+            .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+            .unwrap();
+
+        let got = tokio::time::timeout(Duration::from_secs(5), delegate.route(request))
+            .await
+            .expect("write should not block")
+            .expect("v1 errors should be returned as a response");
+        assert_eq!(got.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(got.headers().get(RETRY_AFTER).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_retry_after_header_value() {
+        assert_eq!(retry_after_header_value(Duration::from_secs(0)), "1");
+        assert_eq!(retry_after_header_value(Duration::from_millis(10)), "1");
+        assert_eq!(retry_after_header_value(Duration::from_secs(3)), "3");
+        assert_eq!(retry_after_header_value(Duration::from_millis(3001)), "4");
+    }
+
+    #[tokio::test]
+    async fn test_write_arrow_errors() {
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = |table: Option<&str>, body: &'static [u8]| {
+            let mut request = Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST")
+                .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE);
+            if let Some(table) = table {
+                request = request.header(ARROW_TABLE_HEADER, table);
+            }
+            request.body(Body::from(body)).unwrap()
+        };
+
+        let got = delegate.route(request(None, b"")).await;
+        assert_matches!(got, Err(Error::ArrowTableNotSpecified));
+
+        let got = delegate.route(request(Some(""), b"")).await;
+        assert_matches!(got, Err(Error::ArrowTableNotSpecified));
+
+        // A continuation marker and length prefix, followed by a message
+        // that is not a valid flatbuffer
+        let got = delegate
+            .route(request(
+                Some("platanos"),
+                b"\xff\xff\xff\xff\x08\x00\x00\x00garbage!",
+            ))
+            .await;
+        assert_matches!(got, Err(Error::DecodeArrow(_)));
+
+        assert!(dml_handler.calls().is_empty());
+    }
+
     test_http_handler!(
         not_found,
         uri = "https://bananas.example/wat",
@@ -709,4 +2158,248 @@ mod tests {
         want_result = Err(Error::NoHandler),
         want_dml_calls = []
     );
+
+    #[tokio::test]
+    async fn test_namespace_summary() {
+        let catalog: Arc<dyn Catalog> = Arc::new(iox_catalog::mem::MemCatalog::new());
+        let kafka = catalog.kafka_topics().create_or_get("foo").await.unwrap();
+        let pool = catalog.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = catalog
+            .namespaces()
+            .create("bananas_test", Some("inf"), kafka.id, pool.id)
+            .await
+            .unwrap();
+        let table = catalog
+            .tables()
+            .create_or_get("platanos", namespace.id)
+            .await
+            .unwrap();
+        catalog
+            .columns()
+            .create_or_get("tag1", table.id, iox_catalog::interface::ColumnType::Tag)
+            .await
+            .unwrap();
+        let sequencer = catalog
+            .sequencers()
+            .create_or_get(&kafka, iox_catalog::interface::KafkaPartition::new(1))
+            .await
+            .unwrap();
+        let partition = catalog
+            .partitions()
+            .create_or_get("platanos_partition", sequencer.id, table.id)
+            .await
+            .unwrap();
+        catalog
+            .parquet_files()
+            .create(
+                sequencer.id,
+                table.id,
+                partition.id,
+                uuid::Uuid::new_v4(),
+                iox_catalog::interface::SequenceNumber::new(1),
+                iox_catalog::interface::SequenceNumber::new(2),
+                iox_catalog::interface::Timestamp::new(1),
+                iox_catalog::interface::Timestamp::new(2),
+                42,
+            )
+            .await
+            .unwrap();
+
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate =
+            HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler)).with_catalog(catalog, "s3cret");
+
+        // Requests without the admin token are rejected
+        for authorization in [None, Some("Token wrong")] {
+            let mut request = Request::builder()
+                .uri(
+                    "https://bananas.example/api/v2/admin/namespace_summary?namespace=bananas_test",
+                )
+                .method("GET");
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            let got = delegate.route(request.body(Body::empty()).unwrap()).await;
+            assert_matches!(got, Err(Error::Unauthorized));
+        }
+
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/admin/namespace_summary?namespace=bananas_test")
+            .method("GET")
+            .header(AUTHORIZATION, "Token s3cret")
+            .body(Body::empty())
+            .unwrap();
+        let response = delegate
+            .route(request)
+            .await
+            .expect("request should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "namespace": "bananas_test",
+                "table_count": 1,
+                "column_count": 1,
+                "parquet_file_count": 1,
+                "total_bytes": 42,
+            })
+        );
+
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/admin/namespace_summary?namespace=missing")
+            .method("GET")
+            .header(AUTHORIZATION, "Token s3cret")
+            .body(Body::empty())
+            .unwrap();
+        let got = delegate.route(request).await;
+        assert_matches!(got, Err(Error::NamespaceNotFound(name)) if name == "missing");
+    }
+
+    #[tokio::test]
+    async fn test_namespace_summary_without_catalog() {
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/admin/namespace_summary?namespace=bananas_test")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+        let got = delegate.route(request).await;
+        assert_matches!(got, Err(Error::NoHandler));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config() {
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(())]));
+        let delegate =
+            HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler)).with_config_reload("s3cret");
+
+        let write = || {
+            let request = Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST")
+                .body(Body::from("platanos val=42i 123456"))
+                .unwrap();
+            delegate.route(request)
+        };
+        let reload = |body: &'static str| {
+            let request = Request::builder()
+                .uri("https://bananas.example/api/v2/admin/reload_config")
+                .method("POST")
+                .header(AUTHORIZATION, "Token s3cret")
+                .body(Body::from(body))
+                .unwrap();
+            delegate.route(request)
+        };
+
+        let got = write().await.expect("write should succeed");
+        assert_eq!(got.status(), StatusCode::NO_CONTENT);
+
+        // Requests without the token cannot change the limits
+        for authorization in [None, Some("Token bananas"), Some("s3cret")] {
+            let mut request = Request::builder()
+                .uri("https://bananas.example/api/v2/admin/reload_config")
+                .method("POST");
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            let request = request
+                .body(Body::from(r#"{"max_http_request_size": 0}"#))
+                .unwrap();
+            let got = delegate.route(request).await;
+            assert_matches!(&got, Err(Error::Unauthorized));
+            assert_eq!(got.unwrap_err().as_status_code(), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(delegate.limits().max_request_bytes, MAX_BYTES);
+
+        // Lower the request size cap below the size of the write, along with
+        // a field that cannot be reloaded
+        let response =
+            reload(r#"{"max_http_request_size": 10, "catalog_dsn": "postgres://localhost/iox"}"#)
+                .await
+                .expect("reload should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "max_http_request_size": 10,
+                "max_tag_bytes": DEFAULT_MAX_TAG_BYTES,
+                "max_points_per_request": null,
+                "log_filter": null,
+                "ignored": ["catalog_dsn"],
+            })
+        );
+
+        // The next write is rejected with the new cap
+        let got = write().await;
+        assert_matches!(got, Err(Error::RequestSizeExceeded(10)));
+        assert_eq!(dml_handler.calls().len(), 1);
+
+        // An invalid value is rejected without changing any limit
+        let got = reload(r#"{"max_tag_bytes": 1, "max_http_request_size": -1}"#).await;
+        assert_matches!(&got, Err(Error::InvalidConfigReload(_)));
+        assert_eq!(got.unwrap_err().as_status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            delegate.limits(),
+            ReloadableLimits {
+                max_request_bytes: 10,
+                max_tag_bytes: DEFAULT_MAX_TAG_BYTES,
+                max_points: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_reload_config_log_filter() {
+        let (_subscriber, handle) = trogging::Builder::new()
+            .with_log_filter(&Some("info".to_string()))
+            .build_with_log_filter_handle()
+            .unwrap();
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::new(MockDmlHandler::default()))
+            .with_log_filter_reload(handle.clone());
+        let fields = |json: serde_json::Value| match json {
+            serde_json::Value::Object(fields) => fields,
+            _ => unreachable!(),
+        };
+
+        let got = delegate
+            .reload_config(fields(serde_json::json!({
+                "log_filter": "debug",
+                "max_tag_bytes": 12,
+            })))
+            .unwrap();
+        assert_eq!(got["log_filter"], "debug");
+        assert_eq!(handle.current().unwrap(), "debug");
+        assert_eq!(delegate.limits().max_tag_bytes, 12);
+
+        // An invalid filter is rejected without changing the limits
+        let got = delegate.reload_config(fields(serde_json::json!({
+            "log_filter": "foo=bananas",
+            "max_tag_bytes": 42,
+        })));
+        assert_matches!(got, Err(Error::InvalidConfigReload(_)));
+        assert_eq!(handle.current().unwrap(), "debug");
+        assert_eq!(delegate.limits().max_tag_bytes, 12);
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_disabled() {
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let delegate = HttpDelegate::new(MAX_BYTES, Arc::clone(&dml_handler));
+
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/admin/reload_config")
+            .method("POST")
+            .body(Body::from(r#"{"max_http_request_size": 10}"#))
+            .unwrap();
+        let got = delegate.route(request).await;
+        assert_matches!(got, Err(Error::NoHandler));
+        assert_eq!(delegate.limits().max_request_bytes, MAX_BYTES);
+    }
 }
@@ -56,9 +56,7 @@ impl<T> MockSharder<T> {
     /// Return the values specified in `ret` in sequence for calls to `shard`,
     /// starting from the front.
     ///
-    /// # Memory Leak
-    ///
-    /// Each call to `shard` leaks the memory of the `T` it returns.
+    /// A write is mapped, unsplit, to the single shard returned by each call.
     pub fn with_return(self, ret: impl Into<VecDeque<T>>) -> Self {
         self.0.lock().shard_return = ret.into();
         self
@@ -73,26 +71,25 @@ impl<T> Sharder<MutableBatch> for Arc<MockSharder<T>>
 where
     T: Debug + Send + Sync,
 {
-    type Item = T;
+    type Item = Vec<(T, MutableBatch)>;
 
     fn shard(
         &self,
         table: &str,
         namespace: &data_types::DatabaseName<'_>,
         payload: &MutableBatch,
-    ) -> &Self::Item {
+    ) -> Self::Item {
         let mut guard = self.0.lock();
         guard.record_call(MockSharderCall {
             table_name: table.to_string(),
             namespace: namespace.to_string(),
             payload: MockSharderPayload::MutableBatch(payload.clone()),
         });
-        Box::leak(Box::new(
-            guard
-                .shard_return
-                .pop_front()
-                .expect("no shard mock value to return"),
-        ))
+        let shard = guard
+            .shard_return
+            .pop_front()
+            .expect("no shard mock value to return");
+        vec![(shard, payload.clone())]
     }
 }
 
@@ -100,25 +97,24 @@ impl<T> Sharder<DeletePredicate> for Arc<MockSharder<T>>
 where
     T: Debug + Send + Sync,
 {
-    type Item = T;
+    type Item = Vec<T>;
 
     fn shard(
         &self,
         table: &str,
         namespace: &data_types::DatabaseName<'_>,
         payload: &DeletePredicate,
-    ) -> &Self::Item {
+    ) -> Self::Item {
         let mut guard = self.0.lock();
         guard.record_call(MockSharderCall {
             table_name: table.to_string(),
             namespace: namespace.to_string(),
             payload: MockSharderPayload::DeletePredicate(payload.clone()),
         });
-        Box::leak(Box::new(
-            guard
-                .shard_return
-                .pop_front()
-                .expect("no shard mock value to return"),
-        ))
+        let shard = guard
+            .shard_return
+            .pop_front()
+            .expect("no shard mock value to return");
+        vec![shard]
     }
 }
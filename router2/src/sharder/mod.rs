@@ -6,5 +6,8 @@ pub use r#trait::*;
 mod table_namespace_sharder;
 pub use table_namespace_sharder::*;
 
+mod fnv;
+pub use fnv::*;
+
 #[cfg(test)]
 pub mod mock;
@@ -1,9 +1,16 @@
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     hash::{Hash, Hasher},
+    ops::Range,
 };
 
-use data_types::DatabaseName;
+use data_types::{delete_predicate::DeletePredicate, DatabaseName};
+use hashbrown::HashMap;
+use mutable_batch::{
+    column::{Column, ColumnData},
+    MutableBatch,
+};
 use siphasher::sip::SipHasher13;
 
 use super::Sharder;
@@ -20,11 +27,23 @@ use super::Sharder;
 /// Google's [jump hash] internally. Adding 1 additional shard causes
 /// approximately `1/N` keys to be remapped.
 ///
+/// Optionally, writes can be split across shards by the values of a set of tag
+/// keys - see [`TableNamespaceSharder::new_with_tag_keys()`].
+///
+/// The table, namespace and tag values are hashed to a `u64` sharding key by
+/// `H`, a keyed [`SipHasher13`] by default. A different hash function can be
+/// used with [`TableNamespaceSharder::with_hasher()`].
+///
 /// [jump hash]: https://arxiv.org/ftp/arxiv/papers/1406/1406.2294.pdf
 #[derive(Debug)]
-pub struct TableNamespaceSharder<T> {
-    hasher: SipHasher13,
+pub struct TableNamespaceSharder<T, H = SipHasher13> {
+    /// The initial hash state, cloned to hash each key.
+    hasher: H,
     shards: Vec<T>,
+
+    /// Sorted, deduplicated tag keys whose values are included in the hash of
+    /// a write.
+    tag_keys: Vec<String>,
 }
 
 impl<T> TableNamespaceSharder<T> {
@@ -58,6 +77,44 @@ impl<T> TableNamespaceSharder<T> {
         Self {
             hasher: SipHasher13::new_with_key(&key),
             shards,
+            tag_keys: vec![],
+        }
+    }
+
+    /// Initialise a [`TableNamespaceSharder`] that includes the values of the
+    /// specified `tag_keys` in the hash of a [`MutableBatch`] write, in
+    /// addition to the table and namespace.
+    ///
+    /// Each row of a write is mapped by its values of the `tag_keys`, and the
+    /// write is split into one write per shard, co-locating the data for a
+    /// given tag value (such as a `host`). Rows with none of the `tag_keys`
+    /// are sharded by table and namespace alone, exactly as [`Self::new()`]
+    /// would.
+    ///
+    /// The order of `tag_keys` does not affect the mapping.
+    ///
+    /// # Correctness
+    ///
+    /// Deletes carry no tag values, so they are routed to every shard as any
+    /// of them may contain writes for the table.
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the number of elements in `shards` is 0.
+    pub fn new_with_tag_keys<K>(
+        shards: impl IntoIterator<Item = T>,
+        tag_keys: impl IntoIterator<Item = K>,
+    ) -> Self
+    where
+        K: Into<String>,
+    {
+        let mut tag_keys = tag_keys.into_iter().map(Into::into).collect::<Vec<_>>();
+        tag_keys.sort_unstable();
+        tag_keys.dedup();
+
+        Self {
+            tag_keys,
+            ..Self::new(shards)
         }
     }
 }
@@ -71,13 +128,48 @@ impl<T> TableNamespaceSharder<T> {
         let hasher = SipHasher13::new_with_key(key);
         Self { hasher, ..self }
     }
+}
+
+impl<T, H> TableNamespaceSharder<T, H>
+where
+    H: Hasher + Clone,
+{
+    /// Reinitialise [`Self`] to hash keys with a clone of `hasher`.
+    ///
+    /// Changing the hash function changes the mapping of inputs to output
+    /// instances of `T`.
+    ///
+    /// # Correctness
+    ///
+    /// All instances must be given a `hasher` with the same initial state to
+    /// map the same input to the same shard - a randomly seeded hasher (such
+    /// as those built by [`std::collections::hash_map::RandomState`]) breaks
+    /// the consistency of the mapping.
+    pub fn with_hasher<U>(self, hasher: U) -> TableNamespaceSharder<T, U>
+    where
+        U: Hasher + Clone,
+    {
+        TableNamespaceSharder {
+            hasher,
+            shards: self.shards,
+            tag_keys: self.tag_keys,
+        }
+    }
 
     /// Consistently hash `key` to a `T`.
-    fn hash<H>(&self, key: H) -> &T
+    fn hash<K>(&self, key: K) -> &T
     where
-        H: Hash,
+        K: Hash,
     {
-        let mut state = self.hasher;
+        &self.shards[self.bucket(key)]
+    }
+
+    /// Consistently hash `key` to the index of a shard.
+    fn bucket<K>(&self, key: K) -> usize
+    where
+        K: Hash,
+    {
+        let mut state = self.hasher.clone();
         key.hash(&mut state);
         let mut key = state.finish();
 
@@ -90,10 +182,8 @@ impl<T> TableNamespaceSharder<T> {
                 as i64
         }
 
-        assert!(b >= 0);
-        self.shards
-            .get(b as usize)
-            .expect("sharder mapped input to non-existant bucket")
+        assert!(b >= 0 && (b as usize) < self.shards.len());
+        b as usize
     }
 }
 
@@ -109,31 +199,187 @@ struct HashKey<'a> {
     namespace: &'a str,
 }
 
-/// A [`TableNamespaceSharder`] is generic over `P`, the payload type, enabling
-/// it to map any type of payload to a shard as it only considers the table name
-/// and namespace when making a sharding decision.
-impl<T, P> Sharder<P> for TableNamespaceSharder<T>
+#[derive(Hash)]
+struct TagHashKey<'a> {
+    table: &'a str,
+    namespace: &'a str,
+    tags: Vec<(&'a str, &'a str)>,
+}
+
+/// Returns the value of the tag `column` in `row`, if any.
+fn tag_value(column: &Column, row: usize) -> Option<&str> {
+    match column.data() {
+        ColumnData::Tag(keys, dictionary, _) if column.valid_mask().get(row) => {
+            dictionary.lookup_id(keys[row])
+        }
+        _ => None,
+    }
+}
+
+impl<T, H> TableNamespaceSharder<T, H>
 where
-    T: Debug + Send + Sync,
+    H: Hasher + Clone,
 {
-    type Item = T;
+    /// Returns the tag columns of `batch` for the configured tag keys, in tag
+    /// key order.
+    fn tag_columns<'a>(&'a self, batch: &'a MutableBatch) -> Vec<(&'a str, &'a Column)> {
+        self.tag_keys
+            .iter()
+            .filter_map(|key| {
+                let column = batch.column(key).ok()?;
+                matches!(column.data(), ColumnData::Tag(..)).then(|| (key.as_str(), column))
+            })
+            .collect()
+    }
+
+    /// Maps each row of `batch` to a bucket by its values of the configured
+    /// tag keys, returning the row ranges of each bucket.
+    ///
+    /// Rows that have none of the tag keys are mapped by `table` and
+    /// `namespace` alone.
+    fn split_rows(
+        &self,
+        table: &str,
+        namespace: &str,
+        batch: &MutableBatch,
+    ) -> BTreeMap<usize, Vec<Range<usize>>> {
+        let columns = self.tag_columns(batch);
+
+        // Hash each distinct combination of tag values only once.
+        let mut buckets: HashMap<Vec<Option<&str>>, usize> = HashMap::new();
+        let mut ranges: BTreeMap<usize, Vec<Range<usize>>> = BTreeMap::new();
 
-    fn shard(&self, table: &str, namespace: &DatabaseName<'_>, _payload: &P) -> &Self::Item {
-        // The derived hash impl for HashKey is hardened against prefix
-        // collisions when combining the two fields.
-        self.hash(&HashKey {
-            table,
-            namespace: namespace.as_ref(),
-        })
+        for row in 0..batch.rows() {
+            let values = columns
+                .iter()
+                .map(|(_, column)| tag_value(column, row))
+                .collect::<Vec<_>>();
+
+            let bucket = match buckets.get(&values) {
+                Some(bucket) => *bucket,
+                None => {
+                    let tags = columns
+                        .iter()
+                        .zip(&values)
+                        .filter_map(|((key, _), value)| Some((*key, (*value)?)))
+                        .collect::<Vec<_>>();
+
+                    let bucket = if tags.is_empty() {
+                        self.bucket(&HashKey { table, namespace })
+                    } else {
+                        self.bucket(&TagHashKey {
+                            table,
+                            namespace,
+                            tags,
+                        })
+                    };
+                    buckets.insert(values, bucket);
+                    bucket
+                }
+            };
+
+            let rows = ranges.entry(bucket).or_default();
+            match rows.last_mut() {
+                Some(range) if range.end == row => range.end += 1,
+                _ => rows.push(row..row + 1),
+            }
+        }
+
+        ranges
+    }
+}
+
+/// Maps a write to the shard of its table and namespace or, if tag keys are
+/// configured, splits it into one write per shard by the tag values of each
+/// row.
+impl<T, H> Sharder<MutableBatch> for TableNamespaceSharder<T, H>
+where
+    T: Clone + Debug + Send + Sync,
+    H: Hasher + Clone + Debug + Send + Sync,
+{
+    type Item = Vec<(T, MutableBatch)>;
+
+    fn shard(
+        &self,
+        table: &str,
+        namespace: &DatabaseName<'_>,
+        payload: &MutableBatch,
+    ) -> Self::Item {
+        let namespace = namespace.as_ref();
+
+        let ranges = match self.tag_keys.is_empty() {
+            true => BTreeMap::new(),
+            false => self.split_rows(table, namespace, payload),
+        };
+
+        if ranges.len() <= 1 {
+            // The derived hash impl for HashKey is hardened against prefix
+            // collisions when combining the two fields.
+            let bucket = match ranges.keys().next() {
+                Some(bucket) => *bucket,
+                None => self.bucket(&HashKey { table, namespace }),
+            };
+            return vec![(self.shards[bucket].clone(), payload.clone())];
+        }
+
+        ranges
+            .into_iter()
+            .map(|(bucket, ranges)| {
+                let mut batch = MutableBatch::new();
+                batch
+                    .extend_from_ranges(payload, &ranges)
+                    .expect("failed to split write into an empty batch");
+                (self.shards[bucket].clone(), batch)
+            })
+            .collect()
+    }
+}
+
+/// Maps a delete to the shard of its table and namespace or, if tag keys are
+/// configured, to every shard as the rows it matches may be in any of them.
+impl<T, H> Sharder<DeletePredicate> for TableNamespaceSharder<T, H>
+where
+    T: Clone + Debug + Send + Sync,
+    H: Hasher + Clone + Debug + Send + Sync,
+{
+    type Item = Vec<T>;
+
+    fn shard(
+        &self,
+        table: &str,
+        namespace: &DatabaseName<'_>,
+        _payload: &DeletePredicate,
+    ) -> Self::Item {
+        if !self.tag_keys.is_empty() {
+            return self.shards.clone();
+        }
+
+        vec![self
+            .hash(&HashKey {
+                table,
+                namespace: namespace.as_ref(),
+            })
+            .clone()]
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use hashbrown::HashMap;
+    use data_types::timestamp::TimestampRange;
+    use siphasher::sip::SipHasher24;
+    use twox_hash::XxHash64;
+
+    use crate::sharder::Fnv1aHasher;
 
     use super::*;
 
+    fn delete() -> DeletePredicate {
+        DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        }
+    }
+
     #[test]
     fn test_consistent_hashing() {
         const NUM_TESTS: usize = 10_000;
@@ -174,29 +420,118 @@ mod tests {
             .all(|(&key, &value)| hasher.hash(key) == value));
     }
 
+    #[test]
+    fn test_pluggable_hasher() {
+        const NUM_TESTS: usize = 10_000;
+        const NUM_SHARDS: usize = 10;
+
+        fn mapping<H: Hasher + Clone>(sharder: &TableNamespaceSharder<usize, H>) -> Vec<usize> {
+            (0..NUM_TESTS).map(|v| *sharder.hash(v)).collect()
+        }
+
+        let sip24 = || {
+            TableNamespaceSharder::new(0..NUM_SHARDS)
+                .with_hasher(SipHasher24::new_with_key(&[42; 16]))
+        };
+        let fnv = || TableNamespaceSharder::new(0..NUM_SHARDS).with_hasher(Fnv1aHasher::default());
+
+        // Each hasher maps the same keys to the same shards across instances
+        let sip24_mapping = mapping(&sip24());
+        assert_eq!(sip24_mapping, mapping(&sip24()));
+        let fnv_mapping = mapping(&fnv());
+        assert_eq!(fnv_mapping, mapping(&fnv()));
+
+        // The mappings of the two hashers differ, although they both use all
+        // the shards
+        assert_ne!(sip24_mapping, fnv_mapping);
+        for m in [&sip24_mapping, &fnv_mapping] {
+            assert!((0..NUM_SHARDS).all(|shard| m.contains(&shard)));
+        }
+
+        // And both are usable as a Sharder
+        let namespace = DatabaseName::try_from("bananas").unwrap();
+        let write = lp_to_batch("cpu,host=a v=1 1");
+        assert_eq!(
+            vec![sip24().shard("cpu", &namespace, &write)[0].0],
+            sip24().shard("cpu", &namespace, &delete())
+        );
+        assert_eq!(
+            vec![fnv().shard("cpu", &namespace, &write)[0].0],
+            fnv().shard("cpu", &namespace, &delete())
+        );
+    }
+
+    #[test]
+    fn test_hasher_distribution() {
+        const NUM_TABLES: usize = 100_000;
+        const NUM_SHARDS: usize = 100;
+
+        /// Assert the tables of a common prefix, as written by many clients,
+        /// are spread evenly over the shards.
+        fn assert_distribution<H>(sharder: TableNamespaceSharder<usize, H>)
+        where
+            H: Hasher + Clone + Debug + Send + Sync,
+        {
+            let namespace = DatabaseName::try_from("namespace").unwrap();
+            let mut counts = vec![0_usize; NUM_SHARDS];
+            for i in 0..NUM_TABLES {
+                counts[sharder.shard(&format!("table_{}", i), &namespace, &delete())[0]] += 1;
+            }
+
+            // Allow each shard 20% more or fewer tables than the mean
+            let mean = NUM_TABLES / NUM_SHARDS;
+            let (min, max) = (mean * 8 / 10, mean * 12 / 10);
+            assert!(
+                counts.iter().all(|&c| (min..=max).contains(&c)),
+                "{:?} has an uneven distribution: {:?}",
+                sharder.hasher,
+                counts
+            );
+        }
+
+        assert_distribution(TableNamespaceSharder::new(0..NUM_SHARDS));
+        assert_distribution(
+            TableNamespaceSharder::new(0..NUM_SHARDS)
+                .with_hasher(SipHasher24::new_with_key(&[42; 16])),
+        );
+        assert_distribution(
+            TableNamespaceSharder::new(0..NUM_SHARDS).with_hasher(Fnv1aHasher::default()),
+        );
+        assert_distribution(
+            TableNamespaceSharder::new(0..NUM_SHARDS).with_hasher(XxHash64::with_seed(42)),
+        );
+    }
+
     #[test]
     fn test_sharder_impl() {
         let hasher = TableNamespaceSharder::new(0..10_000);
 
-        let a = hasher.shard("table", &DatabaseName::try_from("namespace").unwrap(), &0);
-        let b = hasher.shard("table", &DatabaseName::try_from("namespace2").unwrap(), &0);
+        let namespace = DatabaseName::try_from("namespace").unwrap();
+        let a = hasher.shard("table", &namespace, &delete());
+        let b = hasher.shard(
+            "table",
+            &DatabaseName::try_from("namespace2").unwrap(),
+            &delete(),
+        );
         assert_ne!(a, b);
 
-        let a = hasher.shard("table", &DatabaseName::try_from("namespace").unwrap(), &0);
-        let b = hasher.shard("table2", &DatabaseName::try_from("namespace").unwrap(), &0);
+        let a = hasher.shard("table", &namespace, &delete());
+        let b = hasher.shard("table2", &namespace, &delete());
         assert_ne!(a, b);
 
-        // Assert payloads are ignored for this sharder
-        let a = hasher.shard("table", &DatabaseName::try_from("namespace").unwrap(), &0);
-        let b = hasher.shard("table", &DatabaseName::try_from("namespace").unwrap(), &42);
-        assert_eq!(a, b);
+        // Assert a write and a delete for the same table map to the same
+        // shard, irrespective of the payload
+        let a = hasher.shard("table", &namespace, &lp_to_batch("cpu,host=a v=1 1"));
+        let b = hasher.shard("table", &namespace, &lp_to_batch("cpu,host=b v=1 1"));
+        assert_eq!(shard_rows(a), shard_rows(b.clone()));
+        assert_eq!(vec![b[0].0], hasher.shard("table", &namespace, &delete()));
     }
 
     #[test]
     fn test_sharder_prefix_collision() {
         let hasher = TableNamespaceSharder::new(0..10_000);
-        let a = hasher.shard("a", &DatabaseName::try_from("bc").unwrap(), &0);
-        let b = hasher.shard("ab", &DatabaseName::try_from("c").unwrap(), &0);
+        let a = hasher.shard("a", &DatabaseName::try_from("bc").unwrap(), &delete());
+        let b = hasher.shard("ab", &DatabaseName::try_from("c").unwrap(), &delete());
         assert_ne!(a, b);
     }
 
@@ -217,8 +552,103 @@ mod tests {
         let hasher = TableNamespaceSharder::new(0..1000);
         let namespace = DatabaseName::try_from("bananas").unwrap();
 
-        assert_eq!(*hasher.shard("42", &namespace, &0), 904);
-        assert_eq!(*hasher.shard("4242", &namespace, &1), 230);
-        assert_eq!(*hasher.shard("bananas", &namespace, &2), 183);
+        assert_eq!(hasher.shard("42", &namespace, &delete()), [904]);
+        assert_eq!(hasher.shard("4242", &namespace, &delete()), [230]);
+        assert_eq!(hasher.shard("bananas", &namespace, &delete()), [183]);
+    }
+
+    fn lp_to_batch(lp: &str) -> MutableBatch {
+        mutable_batch_lp::lines_to_batches(lp, 0)
+            .unwrap()
+            .remove("cpu")
+            .unwrap()
+    }
+
+    /// Returns the shard and row count of each split write.
+    fn shard_rows(writes: Vec<(usize, MutableBatch)>) -> Vec<(usize, usize)> {
+        writes
+            .into_iter()
+            .map(|(shard, batch)| (shard, batch.rows()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tag_key_sharding() {
+        let hasher = TableNamespaceSharder::new_with_tag_keys(0..10_000, ["host"]);
+        let namespace = DatabaseName::try_from("bananas").unwrap();
+
+        // Two writes with the same host map to the same shard
+        let a = shard_rows(hasher.shard("cpu", &namespace, &lp_to_batch("cpu,host=a v=1 1")));
+        let b = shard_rows(hasher.shard(
+            "cpu",
+            &namespace,
+            &lp_to_batch("cpu,host=a,region=eu v=2 2\ncpu,host=a v=3 3"),
+        ));
+        assert_eq!(a.len(), 1);
+        assert_eq!(b, [(a[0].0, 2)]);
+
+        // While a different host is (with this seed) mapped to another shard
+        let c = shard_rows(hasher.shard("cpu", &namespace, &lp_to_batch("cpu,host=b v=1 1")));
+        assert_eq!(c.len(), 1);
+        assert_ne!(a[0].0, c[0].0);
+
+        // The mapping is deterministic across instances, and independent of
+        // the order of the tag keys
+        let other = TableNamespaceSharder::new_with_tag_keys(0..10_000, ["region", "host"]);
+        let d = shard_rows(other.shard("cpu", &namespace, &lp_to_batch("cpu,host=a v=1 1")));
+        assert_eq!(a, d);
+    }
+
+    #[test]
+    fn test_tag_key_sharding_split() {
+        let hasher = TableNamespaceSharder::new_with_tag_keys(0..10_000, ["host"]);
+        let plain = TableNamespaceSharder::new(0..10_000);
+        let namespace = DatabaseName::try_from("bananas").unwrap();
+
+        let shard_of = |lp| {
+            let got = shard_rows(hasher.shard("cpu", &namespace, &lp_to_batch(lp)));
+            assert_eq!(got.len(), 1);
+            got[0].0
+        };
+        let host_a = shard_of("cpu,host=a v=1 1");
+        let host_b = shard_of("cpu,host=b v=1 1");
+        let no_host = plain.shard("cpu", &namespace, &delete())[0];
+
+        // Rows without a host fall back to the table and namespace
+        assert_eq!(shard_of("cpu,region=eu v=1 1"), no_host);
+
+        // A write with many hosts, and rows without a host, is split by host
+        let got = hasher.shard(
+            "cpu",
+            &namespace,
+            &lp_to_batch("cpu,host=a v=1 1\ncpu,host=b v=2 2\ncpu v=3 3\ncpu,host=a v=4 4"),
+        );
+        let mut want = vec![(host_a, 2), (host_b, 1), (no_host, 1)];
+        want.sort_unstable();
+        assert_eq!(shard_rows(got.clone()), want);
+
+        // Each split write contains only the rows of its shard
+        let (_, batch) = got.iter().find(|(shard, _)| *shard == host_a).unwrap();
+        let summary = batch.timestamp_summary().unwrap();
+        assert_eq!(summary.stats.min, Some(1));
+        assert_eq!(summary.stats.max, Some(4));
+    }
+
+    #[test]
+    fn test_tag_key_sharding_deletes() {
+        let namespace = DatabaseName::try_from("bananas").unwrap();
+
+        // Deletes are routed to every shard when sharding by tag keys
+        let hasher = TableNamespaceSharder::new_with_tag_keys(0..3, ["host"]);
+        assert_eq!(hasher.shard("cpu", &namespace, &delete()), [0, 1, 2]);
+
+        // And to the shard of the table otherwise
+        let hasher = TableNamespaceSharder::new(0..3);
+        let got = hasher.shard("cpu", &namespace, &delete());
+        assert_eq!(got.len(), 1);
+        assert_eq!(
+            shard_rows(hasher.shard("cpu", &namespace, &lp_to_batch("cpu,host=a v=1 1"))),
+            [(got[0], 1)]
+        );
     }
 }
@@ -0,0 +1,325 @@
+use std::{collections::BTreeSet, num::NonZeroU32, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, DictionaryArray, Float64Array, TimestampNanosecondArray},
+    datatypes::Int32Type,
+    ipc::writer::StreamWriter,
+    record_batch::RecordBatch,
+};
+use assert_matches::assert_matches;
+use dml::DmlOperation;
+use hyper::{header::CONTENT_TYPE, Body, Request, StatusCode};
+use iox_catalog::{create_or_get_default_records, interface::Catalog, mem::MemCatalog};
+use mutable_batch::column::ColumnData;
+use router2::{
+    dml_handlers::{DmlError, SchemaValidator, ShardedWriteBuffer},
+    namespace_cache::MemoryNamespaceCache,
+    sequencer::Sequencer,
+    server::http::{
+        Error, HttpDelegate, ARROW_STREAM_CONTENT_TYPE, ARROW_TABLE_HEADER, REQUEST_ID_HEADER,
+    },
+    sharder::TableNamespaceSharder,
+};
+use test_helpers::tracing::TracingCapture;
+use time::TimeProvider;
+use write_buffer::{
+    core::WriteBufferWriting,
+    mock::{MockBufferForWriting, MockBufferSharedState},
+};
+
+/// The namespace derived from the "bananas" org and "test" bucket.
+const NAMESPACE: &str = "bananas_test";
+
+type HandlerStack = SchemaValidator<ShardedWriteBuffer<TableNamespaceSharder<Arc<Sequencer>>>>;
+
+/// A [`HttpDelegate`] driving the full router2 DML handler stack, backed by
+/// an in-memory catalog and a mock write buffer.
+struct TestContext {
+    delegate: HttpDelegate<HandlerStack>,
+    write_buffer_state: MockBufferSharedState,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        Self::new_with_retention("inf").await
+    }
+
+    /// Initialise a [`TestContext`] whose namespace has the given retention
+    /// period.
+    async fn new_with_retention(retention: &str) -> Self {
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new());
+        let (kafka_topic, query_pool, _) = create_or_get_default_records(1, &*catalog)
+            .await
+            .expect("failed to create default catalog records");
+        catalog
+            .namespaces()
+            .create(NAMESPACE, Some(retention), kafka_topic.id, query_pool.id)
+            .await
+            .expect("failed to create test namespace");
+
+        let write_buffer_state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::new(1).unwrap());
+        let write_buffer: Arc<dyn WriteBufferWriting> = Arc::new(
+            MockBufferForWriting::new(
+                write_buffer_state.clone(),
+                None,
+                Arc::new(time::SystemProvider::new()),
+            )
+            .expect("failed to init mock write buffer"),
+        );
+
+        let shards: BTreeSet<_> = write_buffer.sequencer_ids();
+        let sharded_write_buffer = ShardedWriteBuffer::new(
+            shards
+                .into_iter()
+                .map(|id| Sequencer::new(id as _, Arc::clone(&write_buffer)))
+                .map(Arc::new)
+                .collect::<TableNamespaceSharder<_>>(),
+        );
+
+        let handler_stack = SchemaValidator::new(
+            sharded_write_buffer,
+            catalog,
+            Arc::new(MemoryNamespaceCache::default()),
+        );
+
+        Self {
+            delegate: HttpDelegate::new(1024, handler_stack),
+            write_buffer_state,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_request_id_logged() {
+    let ctx = TestContext::new().await;
+    let capture = TracingCapture::new();
+
+    let request = Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .header(REQUEST_ID_HEADER, "request-42")
+        .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+        .unwrap();
+
+    let response = ctx
+        .delegate
+        .route(request)
+        .await
+        .expect("write should succeed");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers().get(REQUEST_ID_HEADER).unwrap(),
+        "request-42"
+    );
+    assert_eq!(ctx.write_buffer_state.get_messages(0).len(), 1);
+
+    // The same request ID must appear in both the schema validation log and
+    // the write buffer routing log.
+    let logs = capture.to_string();
+    for msg in ["schema validation complete", "routing writes to shard"] {
+        let line = logs
+            .lines()
+            .find(|l| l.contains(msg))
+            .unwrap_or_else(|| panic!("no log containing {:?} in:\n{}", msg, logs));
+        assert!(
+            line.contains("request_id = request-42"),
+            "request ID missing from log line: {}",
+            line
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_write_v1_precision() {
+    let ctx = TestContext::new().await;
+
+    // The "bananas" db and "test" rp map to the same namespace as the
+    // "bananas" org and "test" bucket.
+    let request = Request::builder()
+        .uri("https://bananas.example/write?db=bananas&rp=test&precision=s")
+        .method("POST")
+        .body(Body::from("platanos,tag1=A,tag2=B val=42i 123456"))
+        .unwrap();
+
+    let response = ctx
+        .delegate
+        .route(request)
+        .await
+        .expect("write should succeed");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let writes = ctx.write_buffer_state.get_messages(0);
+    assert_matches!(writes.as_slice(), [Ok(DmlOperation::Write(w))] => {
+        assert_eq!(w.namespace(), NAMESPACE);
+        let time = w.table("platanos").unwrap().column("time").unwrap().data();
+        assert_matches!(time, ColumnData::I64(v, _) => {
+            assert_eq!(v, &[123_456_000_000_000]);
+        });
+    });
+}
+
+#[tokio::test]
+async fn test_write_unsigned() {
+    let ctx = TestContext::new().await;
+
+    let request = Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .body(Body::from("m v=5u 42"))
+        .unwrap();
+
+    let response = ctx
+        .delegate
+        .route(request)
+        .await
+        .expect("write should succeed");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let writes = ctx.write_buffer_state.get_messages(0);
+    assert_matches!(writes.as_slice(), [Ok(DmlOperation::Write(w))] => {
+        let v = w.table("m").unwrap().column("v").unwrap().data();
+        assert_matches!(v, ColumnData::U64(v, _) => {
+            assert_eq!(v, &[5]);
+        });
+    });
+}
+
+/// Encode `batch` as an Arrow IPC stream.
+fn arrow_stream(batch: &RecordBatch) -> Vec<u8> {
+    let mut buf = vec![];
+    {
+        let mut writer = StreamWriter::try_new(&mut buf, &batch.schema()).unwrap();
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+    }
+    buf
+}
+
+fn arrow_request(table: &str, batch: &RecordBatch) -> Request<Body> {
+    Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE)
+        .header(ARROW_TABLE_HEADER, table)
+        .body(Body::from(arrow_stream(batch)))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_write_arrow() {
+    let ctx = TestContext::new().await;
+
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "tag1",
+            Arc::new(
+                vec!["A", "B"]
+                    .into_iter()
+                    .collect::<DictionaryArray<Int32Type>>(),
+            ) as ArrayRef,
+        ),
+        ("val", Arc::new(Float64Array::from(vec![Some(1.5), None]))),
+        (
+            "time",
+            Arc::new(TimestampNanosecondArray::from_vec(vec![1, 2], None)),
+        ),
+    ])
+    .unwrap();
+
+    let response = ctx
+        .delegate
+        .route(arrow_request("platanos", &batch))
+        .await
+        .expect("write should succeed");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let writes = ctx.write_buffer_state.get_messages(0);
+    assert_matches!(writes.as_slice(), [Ok(DmlOperation::Write(w))] => {
+        assert_eq!(w.namespace(), NAMESPACE);
+        let table = w.table("platanos").unwrap();
+        assert_eq!(table.rows(), 2);
+        assert_matches!(table.column("tag1").unwrap().data(), ColumnData::Tag(..));
+        assert_matches!(table.column("val").unwrap().data(), ColumnData::F64(..));
+        assert_matches!(table.column("time").unwrap().data(), ColumnData::I64(v, _) => {
+            assert_eq!(v, &[1, 2]);
+        });
+    });
+}
+
+#[tokio::test]
+async fn test_write_arrow_schema_conflict() {
+    let ctx = TestContext::new().await;
+
+    // Establish "val" as an integer column.
+    let request = Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .body(Body::from("platanos val=42i 123456"))
+        .unwrap();
+    ctx.delegate
+        .route(request)
+        .await
+        .expect("write should succeed");
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("val", Arc::new(Float64Array::from(vec![1.5])) as ArrayRef),
+        (
+            "time",
+            Arc::new(TimestampNanosecondArray::from_vec(vec![1], None)),
+        ),
+    ])
+    .unwrap();
+
+    // The conflict is reported exactly as it is for a line protocol write.
+    let err = ctx
+        .delegate
+        .route(arrow_request("platanos", &batch))
+        .await
+        .expect_err("write should fail");
+    assert_matches!(err, Error::DmlHandler(DmlError::Schema(_)));
+    assert_eq!(err.as_status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(ctx.write_buffer_state.get_messages(0).len(), 1);
+}
+
+#[tokio::test]
+async fn test_write_drops_points_outside_retention() {
+    const HOUR: i64 = 60 * 60 * 1_000_000_000;
+
+    let ctx = TestContext::new_with_retention("1h").await;
+    let now = time::SystemProvider::new().now().timestamp_nanos();
+
+    let body = format!(
+        "platanos,tag1=A val=1i {}\nplatanos,tag1=B val=2i {}\nplatanos,tag1=C val=3i {}",
+        now - 2 * HOUR,
+        now - HOUR / 2,
+        now,
+    );
+    let request = Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = ctx
+        .delegate
+        .route(request)
+        .await
+        .expect("write should succeed");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["dropped_out_of_retention"], 1);
+
+    // Only the points within the retention period are forwarded.
+    let msgs = ctx.write_buffer_state.get_messages(0);
+    assert_matches!(msgs.as_slice(), [Ok(DmlOperation::Write(w))] => {
+        let batch = w.table("platanos").expect("table not found in write");
+        assert_eq!(batch.rows(), 2);
+        assert_eq!(batch.timestamp_summary().unwrap().stats.min, Some(now - HOUR / 2));
+    });
+}
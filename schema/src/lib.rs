@@ -378,7 +378,7 @@ impl Schema {
 
     /// Returns a Schema that represents selecting some of the columns
     /// in this schema. An error is returned if the selection refers to
-    /// columns that do not exist.
+    /// columns that do not exist, unless they are excluded columns.
     pub fn select(&self, selection: Selection<'_>) -> Result<Self> {
         Ok(match selection {
             Selection::All => self.clone(),
@@ -386,6 +386,15 @@ impl Schema {
                 let columns = self.compute_select_indicies(columns)?;
                 self.select_by_indices(&columns)
             }
+            Selection::AllExcept(_) => {
+                let columns: Vec<_> = self
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, field))| selection.contains(field.name()))
+                    .map(|(idx, _)| idx)
+                    .collect();
+                self.select_by_indices(&columns)
+            }
         })
     }
 
@@ -1158,6 +1167,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_select_all_except() {
+        let schema = SchemaBuilder::new()
+            .influx_field("the_field", String)
+            .tag("the_tag")
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let selected = schema
+            .select(Selection::AllExcept(&["the_field", "does_not_exist"]))
+            .unwrap();
+
+        let expected = SchemaBuilder::new()
+            .tag("the_tag")
+            .timestamp()
+            .build()
+            .unwrap();
+        assert_eq!(selected, expected);
+    }
+
     #[test]
     fn test_sort() {
         let mut sort_key = SortKey::with_capacity(3);
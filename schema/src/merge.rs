@@ -10,7 +10,7 @@ use snafu::Snafu;
 
 use crate::sort::SortKey;
 
-use super::{InfluxColumnType, Schema};
+use super::{InfluxColumnType, InfluxFieldType, Schema};
 
 /// Database schema creation / validation errors.
 #[derive(Debug, Snafu)]
@@ -77,8 +77,11 @@ fn nullable_to_str(nullability: bool) -> &'static str {
 /// This is infallable because the schemas of chunks within a
 /// partition are assumed to be compatible because that schema was
 /// enforced as part of writing into the partition
+///
+/// Integer fields are promoted to float fields of the same name, as the
+/// buffered batches may predate the promotion of the column by a router.
 pub fn merge_record_batch_schemas(batches: &[Arc<RecordBatch>]) -> Arc<Schema> {
-    let mut merger = SchemaMerger::new();
+    let mut merger = SchemaMerger::new().with_integer_to_float_promotion(true);
     for batch in batches {
         let schema = Schema::try_from(batch.schema()).expect("Schema conversion error");
         merger = merger.merge(&schema).expect("Schemas compatible");
@@ -98,12 +101,19 @@ pub fn merge_record_batch_schemas(batches: &[Arc<RecordBatch>]) -> Arc<Schema> {
 ///
 /// 2. The measurement names must be consistent: one or both can be
 ///    `None`, or they can both be `Some(name`)
+///
+/// 3. If enabled with [`SchemaMerger::with_integer_to_float_promotion`],
+///    an integer field and a float field of the same name merge to the
+///    float field, as integer columns can be promoted to float columns
+///    after data was written with the integer type
 #[derive(Debug, Default, Clone)]
 pub struct SchemaMerger {
     /// Maps column names to their definition
     fields: HashMap<String, (Field, Option<InfluxColumnType>)>,
     /// The measurement name if any
     measurement: Option<String>,
+    /// Whether integer fields merge with float fields of the same name
+    promote_integer_to_float: bool,
 }
 
 impl SchemaMerger {
@@ -111,6 +121,13 @@ impl SchemaMerger {
         Self::default()
     }
 
+    /// Allow integer fields to merge with float fields of the same name,
+    /// resulting in the float field. Disabled by default.
+    pub fn with_integer_to_float_promotion(mut self, enabled: bool) -> Self {
+        self.promote_integer_to_float = enabled;
+        self
+    }
+
     /// Appends the schema to the merged schema being built,
     /// validating that no columns are added.
     pub fn merge(mut self, other: &Schema) -> Result<Self> {
@@ -143,6 +160,7 @@ impl SchemaMerger {
         column_type: Option<InfluxColumnType>,
     ) -> Result<&mut Self> {
         let field_name = field.name();
+        let promote_integer_to_float = self.promote_integer_to_float;
         match self.fields.raw_entry_mut().from_key(field_name) {
             RawEntryMut::Vacant(vacant) => {
                 // Purposefully don't propagate metadata to avoid blindly propagating
@@ -151,13 +169,31 @@ impl SchemaMerger {
                 let field = Field::new(field_name, field.data_type().clone(), field.is_nullable());
                 vacant.insert(field_name.clone(), (field, column_type));
             }
-            RawEntryMut::Occupied(occupied) => {
-                let (existing_field, existing_column_type) = occupied.get();
+            RawEntryMut::Occupied(mut occupied) => {
+                let (existing_field, existing_column_type) = occupied.get_mut();
+
+                let integer = Some(InfluxColumnType::Field(InfluxFieldType::Integer));
+                let float = Some(InfluxColumnType::Field(InfluxFieldType::Float));
+                if promote_integer_to_float
+                    && *existing_column_type == integer
+                    && column_type == float
+                {
+                    *existing_field = Field::new(
+                        field_name,
+                        field.data_type().clone(),
+                        existing_field.is_nullable(),
+                    );
+                    *existing_column_type = float;
+                }
+                let promoted = promote_integer_to_float
+                    && *existing_column_type == float
+                    && column_type == integer;
 
                 // for now, insist the types are exactly the same
-                // (e.g. None and Some(..) don't match). We could
-                // consider relaxing this constraint
-                if existing_column_type != &column_type {
+                // (e.g. None and Some(..) don't match), other than
+                // for promoted integer fields. We could consider
+                // relaxing this constraint
+                if !promoted && existing_column_type != &column_type {
                     return Err(Error::TryMergeBadColumnType {
                         field_name: field_name.to_string(),
                         existing_column_type: *existing_column_type,
@@ -165,7 +201,7 @@ impl SchemaMerger {
                     });
                 }
 
-                if field.data_type() != existing_field.data_type() {
+                if !promoted && field.data_type() != existing_field.data_type() {
                     return Err(Error::TryMergeBadArrowType {
                         field_name: field_name.to_string(),
                         existing_data_type: existing_field.data_type().clone(),
@@ -460,4 +496,44 @@ mod tests {
 
         assert_eq!(merged_schema_error.to_string(), "Schema Merge Error: Incompatible nullability for 'int_field'. Existing field can not be null, new field can be null");
     }
+
+    #[test]
+    fn test_merge_promoted_integer_field() {
+        let int_schema = SchemaBuilder::new()
+            .influx_field("the_field", Integer)
+            .build()
+            .unwrap();
+        let float_schema = SchemaBuilder::new()
+            .influx_field("the_field", InfluxFieldType::Float)
+            .build()
+            .unwrap();
+
+        // promotion is opt-in
+        let merged_schema_error = SchemaMerger::new()
+            .merge(&int_schema)
+            .unwrap()
+            .merge(&float_schema)
+            .unwrap_err();
+
+        assert_eq!(merged_schema_error.to_string(), "Schema Merge Error: Incompatible column type for 'the_field'. Existing type Some(Field(Integer)), new type Some(Field(Float))");
+
+        // the float field is kept whichever schema is merged first
+        let merged_schema = SchemaMerger::new()
+            .with_integer_to_float_promotion(true)
+            .merge(&int_schema)
+            .unwrap()
+            .merge(&float_schema)
+            .unwrap()
+            .build();
+        assert_eq!(merged_schema, float_schema);
+
+        let merged_schema = SchemaMerger::new()
+            .with_integer_to_float_promotion(true)
+            .merge(&float_schema)
+            .unwrap()
+            .merge(&int_schema)
+            .unwrap()
+            .build();
+        assert_eq!(merged_schema, float_schema);
+    }
 }
@@ -10,4 +10,20 @@ pub enum Selection<'a> {
 
     /// Return only the named columns
     Some(&'a [&'a str]),
+
+    /// Return all columns except the named ones. Naming a column that does
+    /// not exist is not an error.
+    /// The columns are returned in an arbitrary order
+    AllExcept(&'a [&'a str]),
+}
+
+impl<'a> Selection<'a> {
+    /// Returns true if the column named `column_name` is part of this selection
+    pub fn contains(&self, column_name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Some(cols) => cols.contains(&column_name),
+            Self::AllExcept(cols) => !cols.contains(&column_name),
+        }
+    }
 }
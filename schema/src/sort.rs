@@ -5,7 +5,7 @@ use indexmap::{map::Iter, IndexMap};
 use itertools::Itertools;
 use snafu::Snafu;
 
-use super::TIME_COLUMN_NAME;
+use super::{Schema, TIME_COLUMN_NAME};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -24,6 +24,13 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Error returned when a [`SortKey`] does not apply to a schema
+#[derive(Debug, Snafu)]
+pub enum SortKeyError {
+    #[snafu(display("sort key column {} is not in the schema", column))]
+    ColumnNotFound { column: String },
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct ColumnSort {
     /// Position of this column in the sort key
@@ -130,6 +137,51 @@ impl<'a> SortKey<'a> {
         self.columns.is_empty()
     }
 
+    /// Returns an error if this sort key references a column that is not
+    /// part of `schema`, such as a sort key computed before a schema change
+    pub fn validate_against(&self, schema: &Schema) -> Result<(), SortKeyError> {
+        match self
+            .columns
+            .keys()
+            .find(|col| schema.find_index_of(col).is_none())
+        {
+            Some(column) => ColumnNotFoundSnafu { column: *column }.fail(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns this sort key with the primary key columns of `schema` it does
+    /// not include, such as tags added since the key was computed, inserted
+    /// before the time column in primary key order. The time column is always
+    /// last.
+    pub fn with_missing_primary_key(&self, schema: &'a Schema) -> SortKey<'a> {
+        let missing: Vec<_> = schema
+            .primary_key()
+            .into_iter()
+            .filter(|col| *col != TIME_COLUMN_NAME && !self.columns.contains_key(col))
+            .collect();
+        if missing.is_empty() && self.columns.get_index_of(TIME_COLUMN_NAME).is_some() {
+            return self.clone();
+        }
+
+        let mut key = SortKey::with_capacity(self.len() + missing.len() + 1);
+        for (col, options) in &self.columns {
+            if *col != TIME_COLUMN_NAME {
+                key.push(col, *options);
+            }
+        }
+        for col in missing {
+            key.with_col(col);
+        }
+        let time_options = self
+            .columns
+            .get(TIME_COLUMN_NAME)
+            .copied()
+            .unwrap_or_default();
+        key.push(TIME_COLUMN_NAME, time_options);
+        key
+    }
+
     /// Returns a subset of the sort key that includes only the given columns
     pub fn selected_sort_key(&self, select_keys: Vec<&str>) -> SortKey<'a> {
         let keys: IndexMap<&'a str, SortOptions> = self
@@ -541,4 +593,61 @@ mod tests {
         expected_key.with_col(TIME_COLUMN_NAME);
         assert_eq!(selected_key, expected_key);
     }
+
+    #[test]
+    fn test_validate_against() {
+        let schema = crate::builder::SchemaBuilder::new()
+            .tag("a")
+            .tag("b")
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let mut sort_key = SortKey::with_capacity(3);
+        sort_key.with_col("b");
+        sort_key.with_col("a");
+        sort_key.with_col(TIME_COLUMN_NAME);
+        sort_key.validate_against(&schema).unwrap();
+
+        // A column dropped from the schema
+        let mut sort_key = SortKey::with_capacity(3);
+        sort_key.with_col("a");
+        sort_key.with_col("c");
+        sort_key.with_col(TIME_COLUMN_NAME);
+        let err = sort_key.validate_against(&schema).unwrap_err();
+        assert!(matches!(err, SortKeyError::ColumnNotFound { column } if column == "c"));
+    }
+
+    #[test]
+    fn test_with_missing_primary_key() {
+        let schema = crate::builder::SchemaBuilder::new()
+            .tag("a")
+            .tag("b")
+            .tag("c")
+            .field("f", arrow::datatypes::DataType::Float64)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        // Tags added since the key was computed go before the time column
+        let mut sort_key = SortKey::with_capacity(2);
+        sort_key.with_col_opts("b", true, false);
+        sort_key.with_col_opts(TIME_COLUMN_NAME, true, true);
+        let mut expected = SortKey::with_capacity(4);
+        expected.with_col_opts("b", true, false);
+        expected.with_col("a");
+        expected.with_col("c");
+        expected.with_col_opts(TIME_COLUMN_NAME, true, true);
+        assert_eq!(
+            sort_key.with_missing_primary_key(&schema).to_string(),
+            expected.to_string()
+        );
+        assert_eq!(expected.to_string(), "b DESC NULLS LAST, a, c, time DESC,");
+
+        // A complete key is left as is
+        assert_eq!(
+            expected.with_missing_primary_key(&schema).to_string(),
+            expected.to_string()
+        );
+    }
 }
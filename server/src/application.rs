@@ -1,12 +1,37 @@
 use job_registry::JobRegistry;
 use object_store::ObjectStore;
 use observability_deps::tracing::info;
-use query::exec::Executor;
-use std::sync::Arc;
+use query::exec::{Executor, ExecutorConfig};
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 use time::TimeProvider;
 use trace::TraceCollector;
 use write_buffer::config::WriteBufferConfigFactory;
 
+/// Settings of the queries run by an [`ApplicationState`], see
+/// [`ExecutorConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct QuerySettings {
+    /// Maximum number of chunks of a table read concurrently by a scan,
+    /// unlimited if `None`
+    pub scan_parallelism: Option<NonZeroUsize>,
+
+    /// Queries taking longer than this to complete are logged as slow
+    /// queries, none are if `None`
+    pub slow_query_threshold: Option<Duration>,
+
+    /// Number of rows of the batches of query output streams, emitted as
+    /// produced by the plan if `None`
+    pub target_batch_size: Option<NonZeroUsize>,
+
+    /// Keep all chunks of the queried tables rather than pruning them, see
+    /// [`ExecutorConfig::disable_pruning`]
+    pub disable_pruning: bool,
+
+    /// Record the latency, rows and memory size of the chunk reads of
+    /// queries
+    pub chunk_read_metrics: bool,
+}
+
 /// A container for application-global resources
 /// shared between server and all DatabaseInstances
 #[derive(Debug, Clone)]
@@ -23,12 +48,22 @@ pub struct ApplicationState {
 impl ApplicationState {
     /// Creates a new `ApplicationState`
     ///
-    /// Uses number of CPUs in the system if num_worker_threads is not set
+    /// Uses number of CPUs in the system if num_worker_threads is not set,
+    /// and runs queries with `query_settings`
     pub fn new(
         object_store: Arc<ObjectStore>,
         num_worker_threads: Option<usize>,
+        query_settings: QuerySettings,
         trace_collector: Option<Arc<dyn TraceCollector>>,
     ) -> Self {
+        let QuerySettings {
+            scan_parallelism,
+            slow_query_threshold,
+            target_batch_size,
+            disable_pruning,
+            chunk_read_metrics,
+        } = query_settings;
+
         let num_threads = num_worker_threads.unwrap_or_else(num_cpus::get);
         info!(%num_threads, "using specified number of threads per thread pool");
 
@@ -47,7 +82,15 @@ impl ApplicationState {
         Self {
             object_store,
             write_buffer_factory,
-            executor: Arc::new(Executor::new(num_threads)),
+            executor: Arc::new(Executor::new_with_config(ExecutorConfig {
+                num_threads,
+                target_query_partitions: num_threads,
+                scan_parallelism,
+                slow_query_threshold,
+                target_batch_size,
+                disable_pruning,
+                chunk_read_metrics,
+            })),
             job_registry,
             metric_registry,
             time_provider,
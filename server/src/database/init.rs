@@ -447,6 +447,10 @@ impl DatabaseStateRulesLoaded {
             Arc::clone(shared.application.time_provider()),
             wipe_catalog_on_error,
             skip_replay,
+            self.provided_rules
+                .rules()
+                .lifecycle_rules
+                .null_corrupt_parquet_columns,
         )
         .await
         .context(CatalogLoadSnafu)?;
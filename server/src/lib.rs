@@ -98,7 +98,7 @@ use tokio_util::sync::CancellationToken;
 use tracker::TaskTracker;
 use uuid::Uuid;
 
-pub use application::ApplicationState;
+pub use application::{ApplicationState, QuerySettings};
 mod application;
 pub mod database;
 pub mod rules;
@@ -684,6 +684,13 @@ impl Server {
             .release()
             .await
             .context(CannotReleaseDatabaseSnafu)?;
+
+        // Don't let queries continue against the released database
+        self.shared
+            .application
+            .executor()
+            .cancel_queries(db_name.as_str());
+
         database.shutdown();
         let _ = database
             .join()
@@ -1165,6 +1172,7 @@ pub mod test_utils {
         Arc::new(ApplicationState::new(
             Arc::new(ObjectStore::new_in_memory()),
             None,
+            Default::default(),
             None,
         ))
     }
@@ -1201,6 +1209,7 @@ mod tests {
         write_buffer::WriteBufferConnection,
     };
     use dml::DmlWrite;
+    use futures::StreamExt;
     use iox_object_store::IoxObjectStore;
     use mutable_batch_lp::lines_to_batches;
     use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
@@ -1208,7 +1217,7 @@ mod tests {
         core::{PreservedCatalog, PreservedCatalogConfig},
         test_helpers::{load_ok, new_empty},
     };
-    use query::QueryDatabase;
+    use query::{exec::ExecutionContextProvider, QueryDatabase};
     use std::{
         convert::TryFrom,
         sync::Arc,
@@ -1827,7 +1836,7 @@ mod tests {
     async fn init_error_generic() {
         // use an object store that will hopefully fail to read
         let store = Arc::new(ObjectStore::new_failing_store().unwrap());
-        let application = Arc::new(ApplicationState::new(store, None, None));
+        let application = Arc::new(ApplicationState::new(store, None, Default::default(), None));
         let server = make_server(application);
 
         server.set_id(ServerId::try_from(1).unwrap()).unwrap();
@@ -2044,6 +2053,47 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn release_database_cancels_queries() {
+        let application = make_application();
+        let server_id = ServerId::try_from(1).unwrap();
+
+        let foo_db_name = DatabaseName::new("foo").unwrap();
+
+        // start server
+        let server = make_server(Arc::clone(&application));
+        server.set_id(server_id).unwrap();
+        server.wait_for_init().await.unwrap();
+
+        // create database with data
+        create_simple_database(&server, &foo_db_name).await.unwrap();
+        let tables = lines_to_batches("cpu bar=1 10", 0).unwrap();
+        let write = DmlWrite::new(foo_db_name.as_str(), tables, Default::default());
+        let db = server.db(&foo_db_name).unwrap();
+        db.store_write(&write).unwrap();
+
+        // start a query
+        let ctx = db.new_query_context(None);
+        let physical_plan = ctx.prepare_sql("select * from cpu").await.unwrap();
+        let mut stream = ctx.execute_stream(physical_plan).await.unwrap();
+
+        server.release_database(&foo_db_name, None).await.unwrap();
+
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_contains!(err.to_string(), "query cancelled");
+        assert!(stream.next().await.is_none());
+
+        // queries against a re-created database are unaffected
+        create_simple_database(&server, &foo_db_name).await.unwrap();
+        let db = server.db(&foo_db_name).unwrap();
+        db.store_write(&write).unwrap();
+
+        let ctx = db.new_query_context(None);
+        let physical_plan = ctx.prepare_sql("select * from cpu").await.unwrap();
+        let batches = ctx.collect(physical_plan).await.unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+    }
+
     #[tokio::test]
     async fn cant_release_nonexistent_database() {
         let application = make_application();
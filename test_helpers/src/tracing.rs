@@ -13,6 +13,9 @@ use parking_lot::Mutex;
 /// This struct captures tracing `Event`s as strings, and can be used
 /// to verify that messages are making it to logs correctly
 ///
+/// The fields of any spans entered when an event is emitted are appended to
+/// the captured event string.
+///
 /// Upon creation it registers itself as the global default span
 /// subscriber, and upon drop it sets a NoOp in its place.
 #[derive(Debug)]
@@ -32,6 +35,8 @@ impl TracingCapture {
         // Register a subscriber to actually capture the log messages
         let my_subscriber = TracingCaptureSubscriber {
             logs: Arc::clone(&logs),
+            spans: Default::default(),
+            stack: Default::default(),
         };
 
         // install the subscriber (is uninstalled when the guard is dropped)
@@ -52,11 +57,22 @@ impl fmt::Display for TracingCapture {
 /// Captures span events to verify
 struct TracingCaptureSubscriber {
     logs: Arc<Mutex<Vec<String>>>,
+    /// The recorded fields of each span, indexed by the span ID - 1.
+    spans: Mutex<Vec<String>>,
+    /// The stack of currently entered spans.
+    stack: Mutex<Vec<Id>>,
 }
 
 impl Subscriber for TracingCaptureSubscriber {
-    fn new_span(&self, _span: &Attributes<'_>) -> Id {
-        Id::from_u64(1)
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut v = StringVisitor {
+            string: String::new(),
+        };
+        span.record(&mut v);
+
+        let mut spans = self.spans.lock();
+        spans.push(v.string);
+        Id::from_u64(spans.len() as u64)
     }
 
     fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
@@ -72,12 +88,26 @@ impl Subscriber for TracingCaptureSubscriber {
             string: String::new(),
         };
         event.record(&mut v);
+
+        let spans = self.spans.lock();
+        for id in self.stack.lock().iter() {
+            v.string.push_str(&spans[id.into_u64() as usize - 1]);
+        }
+
         let mut logs = self.logs.lock();
         logs.push(v.string);
     }
 
-    fn enter(&self, _span: &Id) {}
-    fn exit(&self, _span: &Id) {}
+    fn enter(&self, span: &Id) {
+        self.stack.lock().push(span.clone());
+    }
+
+    fn exit(&self, span: &Id) {
+        let mut stack = self.stack.lock();
+        if let Some(idx) = stack.iter().rposition(|id| id == span) {
+            stack.remove(idx);
+        }
+    }
 }
 
 struct StringVisitor {
@@ -20,14 +20,16 @@ pub use config::*;
 pub use tracing_subscriber;
 
 use observability_deps::tracing::{self, Subscriber};
+use once_cell::sync::OnceCell;
 use std::cmp::min;
 use std::io;
 use std::io::Write;
 use thiserror::Error;
 use tracing_subscriber::{
+    filter::ParseError,
     fmt::{self, writer::BoxMakeWriter, MakeWriter},
     layer::SubscriberExt,
-    EnvFilter, Layer,
+    reload, EnvFilter, Layer, Registry,
 };
 
 /// Maximum length of a log line.
@@ -47,6 +49,12 @@ pub enum Error {
 
     #[error("Cannot set global log subscriber")]
     SetLoggerError(#[from] tracing_log::log_tracer::SetLoggerError),
+
+    #[error("Invalid log filter: {0}")]
+    InvalidLogFilter(#[from] ParseError),
+
+    #[error("Cannot reload log filter: {0}")]
+    ReloadLogFilter(#[from] reload::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -190,6 +198,13 @@ where
     }
 
     pub fn build(self) -> Result<impl Subscriber> {
+        self.build_with_log_filter_handle()
+            .map(|(subscriber, _handle)| subscriber)
+    }
+
+    /// Build a tracing subscriber, along with a handle changing its log filter while it is in
+    /// use.
+    pub fn build_with_log_filter_handle(self) -> Result<(impl Subscriber, LogFilterHandle)> {
         let log_writer = self.make_writer;
         let log_format = self.log_format;
         let with_target = self.with_target;
@@ -241,8 +256,9 @@ where
             };
 
         let log_filter = self.log_filter.unwrap_or(self.default_log_filter);
+        let (log_filter, handle) = reload::Layer::new(log_filter);
 
-        let subscriber = tracing_subscriber::Registry::default().with(
+        let subscriber = Registry::default().with(
             log_filter
                 .and_then(log_format_full)
                 .and_then(log_format_pretty)
@@ -250,20 +266,53 @@ where
                 .and_then(log_format_logfmt),
         );
 
-        Ok(subscriber)
+        Ok((subscriber, LogFilterHandle(handle)))
     }
 
     /// Build a tracing subscriber and install it as a global default subscriber for all threads.
     ///
+    /// Its log filter can then be changed through [`log_filter_handle()`].
+    ///
     /// It returns a RAII guard that will ensure all events are flushed on drop
     pub fn install_global(self) -> Result<TroggingGuard> {
-        let subscriber = self.build()?;
+        let (subscriber, handle) = self.build_with_log_filter_handle()?;
         tracing::subscriber::set_global_default(subscriber)?;
         tracing_log::LogTracer::init()?;
+        // Only one global subscriber can be installed, so this is set at most once
+        let _ = GLOBAL_LOG_FILTER.set(handle);
         Ok(TroggingGuard)
     }
 }
 
+/// The log filter handle of the subscriber installed by [`Builder::install_global()`].
+static GLOBAL_LOG_FILTER: OnceCell<LogFilterHandle> = OnceCell::new();
+
+/// Returns the handle changing the log filter of the global subscriber, if one was installed by
+/// [`Builder::install_global()`].
+pub fn log_filter_handle() -> Option<LogFilterHandle> {
+    GLOBAL_LOG_FILTER.get().cloned()
+}
+
+/// Changes the log filter of a subscriber built by [`Builder`] while it is in use.
+#[derive(Debug, Clone)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilterHandle {
+    /// Replace the log filter with `filter`, in the same syntax as `--log-filter`.
+    ///
+    /// An invalid filter is rejected, leaving the current one in place.
+    pub fn reload(&self, filter: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(filter)?;
+        self.0.reload(filter)?;
+        Ok(())
+    }
+
+    /// Returns the current log filter, or [`None`] if the subscriber was dropped.
+    pub fn current(&self) -> Option<String> {
+        self.0.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
 /// A RAII guard. On Drop, ensures all events are flushed
 ///
 /// Note: This is currently unnecessary but has been kept in case we choose to
@@ -431,7 +480,7 @@ mod tests {
     use super::*;
 
     use crate::test_util::*;
-    use observability_deps::tracing::{debug, error};
+    use observability_deps::tracing::{debug, error, info};
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
 
@@ -634,6 +683,41 @@ ERROR foo
         assert_eq!(captured.to_string().len(), MAX_LINE_LENGTH);
     }
 
+    #[test]
+    fn reload_log_filter() {
+        let (writer, captured) = TestWriter::new();
+        let (subscriber, handle) = Builder::new()
+            .with_writer(make_writer(writer))
+            .with_target(false)
+            .with_ansi(false)
+            .build_with_log_filter_handle()
+            .expect("subscriber");
+        assert_eq!(handle.current().unwrap(), "warn");
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("foo");
+            handle.reload("info").unwrap();
+            info!("bar");
+
+            // An invalid filter leaves the current one in place
+            assert!(matches!(
+                handle.reload("foo=bananas"),
+                Err(Error::InvalidLogFilter(_))
+            ));
+            info!("baz");
+        });
+
+        assert_eq!(handle.current().unwrap(), "info");
+        assert_eq!(
+            captured.without_timestamps(),
+            r#"
+INFO bar
+INFO baz
+"#
+            .trim_start(),
+        );
+    }
+
     #[test]
     fn limited_writer() {
         const TEST_MAX_LINE_LENGTH: usize = 3;
@@ -34,6 +34,7 @@ pub const HEADER_TRACE_CONTEXT: &str = "uber-trace-id";
 /// Message header for namespace.
 pub const HEADER_NAMESPACE: &str = "iox-namespace";
 
+/// Content type of a write buffer message payload.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ContentType {
     Protobuf,
@@ -6,6 +6,7 @@ use std::{
 use async_trait::async_trait;
 use dml::{DmlMeta, DmlOperation, DmlWrite};
 use futures::{future::BoxFuture, stream::BoxStream};
+use time::Time;
 
 /// Generic boxed error type that is used in this crate.
 ///
@@ -105,6 +106,33 @@ pub trait WriteBufferReading: Sync + Send + Debug + 'static {
         sequence_number: u64,
     ) -> Result<(), WriteBufferError>;
 
+    /// Look up the sequence number of the first entry of the given sequencer that was produced at or after the given
+    /// wall-clock time, without moving the sequencer. If there is no such entry, the sequence number following the end
+    /// of the known content is returned.
+    async fn sequence_number_at_timestamp(
+        &self,
+        sequencer_id: u32,
+        timestamp: Time,
+    ) -> Result<u64, WriteBufferError>;
+
+    /// Seek given sequencer to the first entry that was produced at or after the given wall-clock time and return the
+    /// sequence number that the sequencer was moved to. If there is no such entry, the sequencer is moved to the end of
+    /// the known content.
+    ///
+    /// Note that due to the mutable borrow, it is not possible to seek while streams exists.
+    async fn seek_to_timestamp(
+        &mut self,
+        sequencer_id: u32,
+        timestamp: Time,
+    ) -> Result<u64, WriteBufferError> {
+        let sequence_number = self
+            .sequence_number_at_timestamp(sequencer_id, timestamp)
+            .await?;
+        self.seek(sequencer_id, sequence_number).await?;
+
+        Ok(sequence_number)
+    }
+
     /// Return type (like `"mock"` or `"kafka"`) of this reader.
     fn type_name(&self) -> &'static str;
 }
@@ -189,6 +217,7 @@ pub mod test_utils {
         test_multi_sequencer_io(&adapter).await;
         test_multi_writer_multi_reader(&adapter).await;
         test_seek(&adapter).await;
+        test_seek_to_timestamp(&adapter).await;
         test_watermark(&adapter).await;
         test_timestamp(&adapter).await;
         test_sequencer_auto_creation(&adapter).await;
@@ -497,6 +526,78 @@ pub mod test_utils {
         reader_1.seek(0, 42).await.unwrap();
     }
 
+    /// Test seeking to a timestamp.
+    ///
+    /// This tests that:
+    /// - seeking moves to the first entry produced at or after the timestamp
+    /// - seeking past the last entry moves to the end of the sequencer
+    /// - looking up a sequence number does not move the sequencer
+    /// - seeking an unknown sequencer is an error
+    async fn test_seek_to_timestamp<T>(adapter: &T)
+    where
+        T: TestAdapter,
+    {
+        // Note: Roundtrips are only guaranteed for millisecond-precision
+        let t0 = Time::from_timestamp_millis(1_000);
+        let time = Arc::new(time::MockProvider::new(t0));
+        let context = adapter
+            .new_context_with_time(
+                NonZeroU32::try_from(1).unwrap(),
+                Arc::<time::MockProvider>::clone(&time),
+            )
+            .await;
+
+        let writer = context.writing(true).await.unwrap();
+        let w_1 = write("namespace", &writer, "upc user=1 100", 0, None).await;
+        time.inc(Duration::from_secs(10));
+        let w_2 = write("namespace", &writer, "upc user=2 200", 0, None).await;
+        time.inc(Duration::from_secs(10));
+        let w_3 = write("namespace", &writer, "upc user=3 300", 0, None).await;
+        let t2 = w_2.meta().producer_ts().unwrap();
+        let t3 = w_3.meta().producer_ts().unwrap();
+
+        let mut reader = context.reading(true).await.unwrap();
+
+        // exact match
+        let sequence_number = reader.seek_to_timestamp(0, t2).await.unwrap();
+        assert_eq!(sequence_number, w_2.meta().sequence().unwrap().number);
+        assert_reader_content(&mut reader, &[(0, &[&w_2, &w_3])]).await;
+
+        // in between entries
+        let sequence_number = reader
+            .seek_to_timestamp(0, t0 + Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(sequence_number, w_2.meta().sequence().unwrap().number);
+
+        // before all entries
+        reader
+            .seek_to_timestamp(0, Time::from_timestamp_millis(0))
+            .await
+            .unwrap();
+        assert_reader_content(&mut reader, &[(0, &[&w_1, &w_2, &w_3])]).await;
+
+        // after all entries
+        let sequence_number = reader
+            .seek_to_timestamp(0, t3 + Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(sequence_number, w_3.meta().sequence().unwrap().number + 1);
+        assert_reader_content(&mut reader, &[(0, &[])]).await;
+
+        // looking up a sequence number does not move the sequencer
+        let sequence_number = reader.sequence_number_at_timestamp(0, t2).await.unwrap();
+        assert_eq!(sequence_number, w_2.meta().sequence().unwrap().number);
+        assert_reader_content(&mut reader, &[(0, &[])]).await;
+
+        // unknown sequencer
+        reader.seek_to_timestamp(42, t0).await.unwrap_err();
+        reader
+            .sequence_number_at_timestamp(42, t0)
+            .await
+            .unwrap_err();
+    }
+
     /// Test watermark fetching.
     ///
     /// This tests that:
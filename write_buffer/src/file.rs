@@ -343,6 +343,43 @@ impl WriteBufferReading for FileBufferConsumer {
         Ok(())
     }
 
+    async fn sequence_number_at_timestamp(
+        &self,
+        sequencer_id: u32,
+        timestamp: Time,
+    ) -> Result<u64, WriteBufferError> {
+        let (sequencer_path, _) = self
+            .dirs
+            .get(&sequencer_id)
+            .ok_or_else::<WriteBufferError, _>(|| {
+                format!("Unknown sequencer: {}", sequencer_id).into()
+            })?;
+        let committed = sequencer_path.join("committed");
+        let files: Vec<_> = scan_dir::<u64>(&committed, FileType::File)
+            .await?
+            .into_iter()
+            .collect();
+
+        // Binary search for the first entry produced at or after the given time, assuming that
+        // entries are produced in sequence number order. Without such entry, the end of the
+        // committed entries is returned.
+        let mut low = 0;
+        let mut high = files.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let data = tokio::fs::read(&files[mid].1).await?;
+            if ConsumerStream::decode_timestamp(&data)? < timestamp {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(match files.get(low) {
+            Some((sequence_number, _)) => *sequence_number,
+            None => files.last().map(|(n, _)| n + 1).unwrap_or(0),
+        })
+    }
+
     fn type_name(&self) -> &'static str {
         "file"
     }
@@ -473,22 +510,7 @@ impl ConsumerStream {
                     trace_collector.as_ref(),
                 )?;
 
-                // parse timestamp
-                let mut timestamp = None;
-                for header in headers {
-                    if header.name.eq_ignore_ascii_case(HEADER_TIME) {
-                        if let Ok(value) = String::from_utf8(header.value.to_vec()) {
-                            if let Ok(time) = Time::from_rfc3339(&value) {
-                                timestamp = Some(time);
-                            }
-                        }
-                    }
-                }
-                let timestamp = if let Some(timestamp) = timestamp {
-                    timestamp
-                } else {
-                    return Err("Timestamp missing".to_string().into());
-                };
+                let timestamp = Self::header_timestamp(headers)?;
 
                 // parse entry
                 let full_data_length = data.len();
@@ -505,6 +527,29 @@ impl ConsumerStream {
             httparse::Status::Partial => Err("Too many headers".to_string().into()),
         }
     }
+
+    /// Decode only the time at which the message in `data` was produced
+    fn decode_timestamp(data: &[u8]) -> Result<Time, WriteBufferError> {
+        let mut headers = [httparse::EMPTY_HEADER; 16];
+        match httparse::parse_headers(data, &mut headers)? {
+            httparse::Status::Complete((_offset, headers)) => Self::header_timestamp(headers),
+            httparse::Status::Partial => Err("Too many headers".to_string().into()),
+        }
+    }
+
+    fn header_timestamp(headers: &[httparse::Header<'_>]) -> Result<Time, WriteBufferError> {
+        let mut timestamp = None;
+        for header in headers {
+            if header.name.eq_ignore_ascii_case(HEADER_TIME) {
+                if let Ok(value) = String::from_utf8(header.value.to_vec()) {
+                    if let Ok(time) = Time::from_rfc3339(&value) {
+                        timestamp = Some(time);
+                    }
+                }
+            }
+        }
+        timestamp.ok_or_else(|| "Timestamp missing".to_string().into())
+    }
 }
 
 impl Stream for ConsumerStream {
@@ -531,6 +576,21 @@ impl Stream for ConsumerStream {
     }
 }
 
+/// Return the IDs of the existing sequencers of the write buffer for `database_name` under `root`,
+/// without creating any. The set is empty if the write buffer was not initialized yet.
+pub async fn existing_sequencer_ids(
+    root: &Path,
+    database_name: &str,
+) -> Result<BTreeSet<u32>, WriteBufferError> {
+    let active = root.join(database_name).join("active");
+    if tokio::fs::metadata(&active).await.is_err() {
+        return Ok(BTreeSet::new());
+    }
+
+    let directories = scan_dir::<u32>(&active, FileType::Dir).await?;
+    Ok(directories.into_keys().collect())
+}
+
 async fn maybe_auto_create_directories(
     root: &Path,
     creation_config: Option<&WriteBufferCreationConfig>,
@@ -760,6 +820,24 @@ mod tests {
         perform_generic_tests(FileTestAdapter::new()).await;
     }
 
+    #[tokio::test]
+    async fn test_existing_sequencer_ids() {
+        let adapter = FileTestAdapter::new();
+        let ctx = adapter.new_context(NonZeroU32::new(2).unwrap()).await;
+        assert!(existing_sequencer_ids(&ctx.path, &ctx.database_name)
+            .await
+            .unwrap()
+            .is_empty());
+
+        ctx.writing(true).await.unwrap();
+        assert_eq!(
+            existing_sequencer_ids(&ctx.path, &ctx.database_name)
+                .await
+                .unwrap(),
+            BTreeSet::from([0, 1])
+        );
+    }
+
     #[tokio::test]
     async fn test_ignores_missing_files_multi() {
         let adapter = FileTestAdapter::new();
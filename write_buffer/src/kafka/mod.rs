@@ -10,12 +10,15 @@ use async_trait::async_trait;
 use data_types::{sequence::Sequence, write_buffer::WriteBufferCreationConfig};
 use dml::{DmlMeta, DmlOperation};
 use futures::{FutureExt, StreamExt};
-use rskafka::client::{
-    consumer::StreamConsumerBuilder,
-    error::{Error as RSKafkaError, ProtocolError},
-    partition::PartitionClient,
-    producer::{BatchProducer, BatchProducerBuilder},
-    ClientBuilder,
+use rskafka::{
+    client::{
+        consumer::StreamConsumerBuilder,
+        error::{Error as RSKafkaError, ProtocolError},
+        partition::PartitionClient,
+        producer::{BatchProducer, BatchProducerBuilder},
+        ClientBuilder,
+    },
+    record::Record,
 };
 use time::{Time, TimeProvider};
 use trace::TraceCollector;
@@ -211,16 +214,7 @@ impl WriteBufferReading for RSKafkaConsumer {
                     number: record.offset.try_into()?,
                 };
 
-                let timestamp_millis =
-                    i64::try_from(record.record.timestamp.unix_timestamp_nanos() / 1_000_000)?;
-                let timestamp = Time::from_timestamp_millis_opt(timestamp_millis)
-                    .ok_or_else::<WriteBufferError, _>(|| {
-                    format!(
-                        "Cannot parse timestamp for milliseconds: {}",
-                        timestamp_millis
-                    )
-                    .into()
-                })?;
+                let timestamp = record_timestamp(&record.record)?;
 
                 let value = record
                     .record
@@ -272,11 +266,79 @@ impl WriteBufferReading for RSKafkaConsumer {
         Ok(())
     }
 
+    async fn sequence_number_at_timestamp(
+        &self,
+        sequencer_id: u32,
+        timestamp: Time,
+    ) -> Result<u64, WriteBufferError> {
+        let partition_client = self
+            .partitions
+            .get(&sequencer_id)
+            .map(|partition| Arc::clone(&partition.partition_client))
+            .ok_or_else::<WriteBufferError, _>(|| {
+                format!("Unknown partition: {}", sequencer_id).into()
+            })?;
+
+        // Binary search for the first offset of a record produced at or after the given time,
+        // assuming that records are produced in offset order. Without such record, the
+        // high watermark is returned.
+        let mut low = 0;
+        let mut high = partition_client.get_high_watermark().await?;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match first_record_timestamp(&partition_client, mid).await? {
+                Some((offset, record_timestamp)) if record_timestamp < timestamp => {
+                    low = (offset + 1).min(high)
+                }
+                Some(_) => high = mid,
+                // the records before `mid` are gone
+                None => low = mid + 1,
+            }
+        }
+
+        Ok(u64::try_from(low)?)
+    }
+
     fn type_name(&self) -> &'static str {
         "kafka"
     }
 }
 
+/// Return the offset and the production time of the first record at or after `offset`, or
+/// `None` if there is no such record because `offset` is before the first retained record.
+async fn first_record_timestamp(
+    partition_client: &PartitionClient,
+    offset: i64,
+) -> Result<Option<(i64, Time)>, WriteBufferError> {
+    let records = match partition_client
+        .fetch_records(offset, 1..1_000_000, 1_000)
+        .await
+    {
+        Ok((records, _watermark)) => records,
+        Err(RSKafkaError::ServerError(ProtocolError::OffsetOutOfRange, _)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // a fetch may return the records of a batch preceding `offset` as well
+    records
+        .into_iter()
+        .find(|record| record.offset >= offset)
+        .map(|record| Ok((record.offset, record_timestamp(&record.record)?)))
+        .transpose()
+}
+
+/// Return the production time of the given record, with millisecond precision
+fn record_timestamp(record: &Record) -> Result<Time, WriteBufferError> {
+    let timestamp_millis = i64::try_from(record.timestamp.unix_timestamp_nanos() / 1_000_000)?;
+    Time::from_timestamp_millis_opt(timestamp_millis).ok_or_else::<WriteBufferError, _>(|| {
+        format!(
+            "Cannot parse timestamp for milliseconds: {}",
+            timestamp_millis
+        )
+        .into()
+    })
+}
+
 async fn setup_topic(
     conn: String,
     database_name: String,
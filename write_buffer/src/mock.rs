@@ -12,7 +12,7 @@ use parking_lot::Mutex;
 use data_types::sequence::Sequence;
 use data_types::write_buffer::WriteBufferCreationConfig;
 use dml::{DmlDelete, DmlMeta, DmlOperation, DmlWrite};
-use time::TimeProvider;
+use time::{Time, TimeProvider};
 
 use crate::core::{
     FetchHighWatermark, FetchHighWatermarkFut, WriteBufferError, WriteBufferReading,
@@ -344,6 +344,34 @@ impl WriteBufferWriting for MockBufferForWritingThatAlwaysErrors {
     }
 }
 
+/// A [`WriteBufferWriting`] that never completes storing an operation, as a
+/// write buffer that cannot keep up with the write load.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MockBufferForWritingThatBlocks;
+
+#[async_trait]
+impl WriteBufferWriting for MockBufferForWritingThatBlocks {
+    fn sequencer_ids(&self) -> BTreeSet<u32> {
+        IntoIterator::into_iter([0]).collect()
+    }
+
+    async fn store_operation(
+        &self,
+        _sequencer_id: u32,
+        _operation: &DmlOperation,
+    ) -> Result<DmlMeta, WriteBufferError> {
+        futures::future::pending().await
+    }
+
+    async fn flush(&self) {
+        // no buffer
+    }
+
+    fn type_name(&self) -> &'static str {
+        "mock_blocking"
+    }
+}
+
 /// Sequencer-specific playback state
 struct PlaybackState {
     /// Index within the entry vector.
@@ -499,6 +527,35 @@ impl WriteBufferReading for MockBufferForReading {
         Ok(())
     }
 
+    async fn sequence_number_at_timestamp(
+        &self,
+        sequencer_id: u32,
+        timestamp: Time,
+    ) -> Result<u64, WriteBufferError> {
+        let guard = self.shared_state.writes.lock();
+        let writes = guard.as_ref().unwrap();
+        let writes_vec = writes
+            .get(&sequencer_id)
+            .ok_or_else::<WriteBufferError, _>(|| {
+                format!("Unknown sequencer: {}", sequencer_id).into()
+            })?;
+
+        // first entry produced at or after the given time, otherwise the end of the recorded entries
+        Ok(writes_vec
+            .writes
+            .iter()
+            .filter_map(|write_result| write_result.as_ref().ok())
+            .find(|write| {
+                write
+                    .meta()
+                    .producer_ts()
+                    .map(|ts| ts >= timestamp)
+                    .unwrap_or(false)
+            })
+            .map(|write| write.meta().sequence().unwrap().number)
+            .unwrap_or_else(|| writes_vec.max_seqno.map(|n| n + 1).unwrap_or(0)))
+    }
+
     fn type_name(&self) -> &'static str {
         "mock"
     }
@@ -542,6 +599,14 @@ impl WriteBufferReading for MockBufferForReadingThatAlwaysErrors {
         Err(String::from("Something bad happened while seeking the stream").into())
     }
 
+    async fn sequence_number_at_timestamp(
+        &self,
+        _sequencer_id: u32,
+        _timestamp: Time,
+    ) -> Result<u64, WriteBufferError> {
+        Err(String::from("Something bad happened while looking up the sequence number").into())
+    }
+
     fn type_name(&self) -> &'static str {
         "mock_failing"
     }
@@ -745,6 +810,14 @@ mod tests {
             reader.seek(0, 0).await.unwrap_err().to_string(),
             "Something bad happened while seeking the stream"
         );
+        assert_eq!(
+            reader
+                .seek_to_timestamp(0, Time::from_timestamp_nanos(0))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Something bad happened while looking up the sequence number"
+        );
 
         let mut streams = reader.streams();
         let (_id, mut stream) = map_pop_first(&mut streams).unwrap();
@@ -761,6 +834,79 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_seek_to_timestamp() {
+        let state =
+            MockBufferSharedState::empty_with_n_sequencers(NonZeroU32::try_from(1).unwrap());
+        for (sequence_number, ts) in [(0, 10), (3, 20), (4, 30)] {
+            let tables = lines_to_batches("upc user=1 100", 0).unwrap();
+            let meta = DmlMeta::sequenced(
+                Sequence::new(0, sequence_number),
+                Time::from_timestamp_nanos(ts),
+                None,
+                0,
+            );
+            state.push_write(DmlWrite::new("test_db", tables, meta));
+        }
+
+        let mut reader = MockBufferForReading::new(state.clone(), None).unwrap();
+
+        // exact match
+        assert_eq!(
+            reader
+                .seek_to_timestamp(0, Time::from_timestamp_nanos(20))
+                .await
+                .unwrap(),
+            3
+        );
+        let mut stream = map_pop_first(&mut reader.streams()).unwrap().1.stream;
+        let op = stream.next().await.unwrap().unwrap();
+        assert_eq!(op.meta().sequence().unwrap().number, 3);
+        drop(stream);
+
+        // in between entries => next entry
+        assert_eq!(
+            reader
+                .seek_to_timestamp(0, Time::from_timestamp_nanos(11))
+                .await
+                .unwrap(),
+            3
+        );
+
+        // before all entries => start
+        assert_eq!(
+            reader
+                .seek_to_timestamp(0, Time::from_timestamp_nanos(0))
+                .await
+                .unwrap(),
+            0
+        );
+
+        // after all entries => end
+        assert_eq!(
+            reader
+                .seek_to_timestamp(0, Time::from_timestamp_nanos(31))
+                .await
+                .unwrap(),
+            5
+        );
+        state.push_lp(Sequence::new(0, 5), "upc user=2 200");
+        let mut stream = map_pop_first(&mut reader.streams()).unwrap().1.stream;
+        let op = stream.next().await.unwrap().unwrap();
+        assert_eq!(op.meta().sequence().unwrap().number, 5);
+        drop(stream);
+
+        // unknown sequencer
+        assert_eq!(
+            reader
+                .seek_to_timestamp(1, Time::from_timestamp_nanos(0))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Unknown sequencer: 1"
+        );
+    }
+
     #[tokio::test]
     async fn test_always_error_write() {
         let writer = MockBufferForWritingThatAlwaysErrors {};